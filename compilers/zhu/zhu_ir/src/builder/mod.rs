@@ -1,5 +1,5 @@
 use crate::entities::block::Block;
-use crate::entities::constant::{Constant, ConstantData};
+use crate::entities::constant::ConstantData;
 use crate::entities::function::{Function, FunctionRef};
 use crate::entities::global_value::GlobalValue;
 use crate::entities::immediate::{Immediate, Offset};
@@ -116,8 +116,7 @@ impl<'a> FunctionBuilder<'a> {
     /// wrap `build_inst_and_result`, only provide opcode and binary operand as paramemter.
     pub(crate) fn build_const_inst(&mut self, opcode: OpCode, bytes: Vec<u8>, ty: ValueType) -> Value {
         let constant_data = ConstantData { bytes };
-        let constant = Constant(self.function.constants.keys().len() as u32);
-        self.function.constants.insert(constant, constant_data);
+        let constant = self.function.create_constant(constant_data);
         let const_inst_data = InstructionData::UnaryConst { opcode, constant };
         self.build_inst_and_result(const_inst_data, ty)
     }
@@ -162,7 +161,6 @@ impl<'a> FunctionBuilder<'a> {
                 .function
                 .entities
                 .create_value(ValueData::Inst { inst, ty: ty.clone() });
-            println!("??");
             self.function.entities.mark_inst_result(result, inst);
             Some(result)
         } else {
@@ -308,6 +306,19 @@ impl<'a> FunctionBuilder<'a> {
         self.build_inst_without_result(inst_data);
     }
 }
+/// Build comment instruction
+impl<'a> FunctionBuilder<'a> {
+    /// Build comment instruction
+    ///
+    /// Input:
+    ///   - comment: text of comment without comment marker.
+    ///
+    /// Output:
+    ///   - a instruction reference of comment.
+    pub fn comment_inst(&mut self, comment: String) -> Instruction {
+        self.build_inst_without_result(InstructionData::Comment(comment))
+    }
+}
 /// Build Memory relative instruction
 impl<'a> FunctionBuilder<'a> {
     /// Build stackalloc instruction
@@ -424,6 +435,21 @@ impl<'a> FunctionBuilder<'a> {
     pub fn iconst_inst(&mut self, bytes: Vec<u8>, ty: ValueType) -> Value {
         self.build_const_inst(OpCode::Iconst, bytes, ty)
     }
+    /// Build instructuon generate unsign int constant.
+    ///
+    /// Input :
+    ///  - bytes: constant data in bytes
+    ///  - ty: Value type of constant data
+    ///
+    /// Output:
+    ///  - a Value with `ty` value type.
+    ///
+    /// Invariant:
+    ///   - caller need to make sure `ty` is one of unsign int
+    ///   - caller need to make sure `bytes` format.
+    pub fn uconst_inst(&mut self, bytes: Vec<u8>, ty: ValueType) -> Value {
+        self.build_const_inst(OpCode::Uconst, bytes, ty)
+    }
     /// Build instruction generate f32 constant.
     ///
     /// Input :
@@ -465,7 +491,7 @@ impl<'a> FunctionBuilder<'a> {
     /// Output:
     ///  - a Value which type is U8, only have two possible value, 0 and 1.
     pub fn fcmp_inst(&mut self, flag: CmpFlag, args: [Value; 2]) -> Value {
-        let inst_data = InstructionData::Icmp {
+        let inst_data = InstructionData::Fcmp {
             opcode: OpCode::Fcmp,
             flag,
             args,
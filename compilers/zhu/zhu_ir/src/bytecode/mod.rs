@@ -0,0 +1,194 @@
+use std::fmt;
+
+use crate::bytecode::reader::BytecodeReader;
+use crate::bytecode::writer::BytecodeWriter;
+use crate::entities::block::{Block, BlockData};
+use crate::entities::function::Function;
+use crate::entities::instruction::opcode::{CmpFlag, OpCode};
+use crate::entities::instruction::InstructionData;
+use crate::entities::module::Module;
+
+pub mod reader;
+pub mod writer;
+
+/// Magic bytes at the start of bytecode file.
+pub const BYTECODE_MAGIC: [u8; 4] = *b"ZHUB";
+/// Version of bytecode format, bump it when encoding is changed.
+pub const BYTECODE_VERSION: u16 = 1;
+/// Header is magic, version (u16), length (u32) and checksum (u32) of payload, numbers
+/// are little endian.
+pub const BYTECODE_HEADER_SIZE: usize = 14;
+
+/// Encode module to bytecode.
+pub fn encode_module(module: &Module) -> Vec<u8> {
+    let mut writer = BytecodeWriter::new();
+    writer.write_module(module);
+    writer.finish()
+}
+
+/// Decode module from bytecode produced by `encode_module`, header and checksum are
+/// validated before decoding payload.
+pub fn decode_module(bytes: &[u8]) -> Result<Module, BytecodeError> {
+    BytecodeReader::new(bytes)?.read_module()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BytecodeError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    UnexpectedEnd {
+        offset: usize,
+    },
+    ChecksumMismatch {
+        expected: u32,
+        found: u32,
+    },
+    LengthMismatch {
+        expected: usize,
+        found: usize,
+    },
+    VarintOverflow {
+        offset: usize,
+    },
+    InvalidTag {
+        kind: &'static str,
+        tag: u64,
+        offset: usize,
+    },
+    InvalidUtf8 {
+        offset: usize,
+    },
+    DuplicateEntity {
+        kind: &'static str,
+        index: u32,
+    },
+    UnknownEntity {
+        kind: &'static str,
+        index: u32,
+    },
+}
+
+impl fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BytecodeError::BadMagic => write!(f, "[Error]: not a zhu bytecode, magic is mismatched"),
+            BytecodeError::UnsupportedVersion(version) => write!(
+                f,
+                "[Error]: bytecode version {} is not supported, expect version {}",
+                version, BYTECODE_VERSION
+            ),
+            BytecodeError::UnexpectedEnd { offset } => write!(f, "[Error]: unexpected end of bytecode at {}", offset),
+            BytecodeError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "[Error]: bytecode is corrupted, checksum is {:#010x} but expect {:#010x}",
+                found, expected
+            ),
+            BytecodeError::LengthMismatch { expected, found } => write!(
+                f,
+                "[Error]: bytecode is corrupted, payload has {} bytes but expect {}",
+                found, expected
+            ),
+            BytecodeError::VarintOverflow { offset } => write!(f, "[Error]: integer at {} is overflow", offset),
+            BytecodeError::InvalidTag { kind, tag, offset } => {
+                write!(f, "[Error]: invalid tag {} of {} at {}", tag, kind, offset)
+            }
+            BytecodeError::InvalidUtf8 { offset } => write!(f, "[Error]: string at {} is not valid utf-8", offset),
+            BytecodeError::DuplicateEntity { kind, index } => write!(f, "[Error]: {} {} is defined twice", kind, index),
+            BytecodeError::UnknownEntity { kind, index } => write!(f, "[Error]: {} {} is not defined", kind, index),
+        }
+    }
+}
+
+/// FNV-1a hash of payload, detect accidental corruption of bytecode.
+pub(crate) fn checksum(bytes: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in bytes {
+        hash ^= *byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+/// Block data which can be derived from layout, phi instructions are in `phis`
+/// and other instructions are in `insts`.
+pub(crate) fn derive_block_data(function: &Function, block: Block) -> BlockData {
+    let mut block_data = BlockData::new();
+    if function.layout.blocks.contains_key(&block) {
+        for inst in function.get_insts_of_block(block) {
+            match function.entities.insts.get(&inst) {
+                Some(InstructionData::Phi { .. }) => block_data.phis.insert(inst),
+                _ => block_data.insts.insert(inst),
+            };
+        }
+    }
+    block_data
+}
+
+/// Tag of instruction data which is followed by opcode, only used when instruction
+/// data is not the usual variant of opcode.
+pub(crate) const INST_TAG_ESCAPE: u8 = 64;
+/// Tag of comment, comment has no opcode so it always be escaped.
+pub(crate) const INST_TAG_COMMENT: u8 = 18;
+
+/// Opcode is encoded as index in this table with tag of its usual instruction data,
+/// new opcode must be appended to keep index of existing opcodes.
+pub(crate) const OPCODES: [(OpCode, u8); 48] = [
+    (OpCode::Uconst, 0),
+    (OpCode::Iconst, 0),
+    (OpCode::Fconst, 0),
+    (OpCode::Add, 2),
+    (OpCode::Addi, 3),
+    (OpCode::Sub, 2),
+    (OpCode::Subi, 3),
+    (OpCode::Mul, 2),
+    (OpCode::Muli, 3),
+    (OpCode::Divide, 2),
+    (OpCode::Dividei, 3),
+    (OpCode::Reminder, 2),
+    (OpCode::Reminderi, 3),
+    (OpCode::FAdd, 2),
+    (OpCode::FSub, 2),
+    (OpCode::FMul, 2),
+    (OpCode::FDivide, 2),
+    (OpCode::FReminder, 2),
+    (OpCode::BitwiseNot, 1),
+    (OpCode::BitwiseOR, 2),
+    (OpCode::BitwiseAnd, 2),
+    (OpCode::ShiftLeft, 2),
+    (OpCode::ShiftRight, 2),
+    (OpCode::Mov, 1),
+    (OpCode::Neg, 1),
+    (OpCode::Icmp, 5),
+    (OpCode::Fcmp, 6),
+    (OpCode::Call, 7),
+    (OpCode::Ret, 8),
+    (OpCode::ToU8, 9),
+    (OpCode::ToU16, 9),
+    (OpCode::ToU32, 9),
+    (OpCode::ToU64, 9),
+    (OpCode::ToI16, 9),
+    (OpCode::ToI32, 9),
+    (OpCode::ToI64, 9),
+    (OpCode::ToF32, 9),
+    (OpCode::ToF64, 9),
+    (OpCode::ToAddress, 9),
+    (OpCode::StackAlloc, 10),
+    (OpCode::StackAddr, 10),
+    (OpCode::LoadRegister, 11),
+    (OpCode::StoreRegister, 12),
+    (OpCode::GlobalLoad, 13),
+    (OpCode::GlobalStore, 14),
+    (OpCode::BrIf, 15),
+    (OpCode::Jump, 16),
+    (OpCode::Phi, 17),
+];
+
+/// Compare flag is encoded as index in this table.
+pub(crate) const CMP_FLAGS: [CmpFlag; 6] = [
+    CmpFlag::Eq,
+    CmpFlag::NotEq,
+    CmpFlag::Gt,
+    CmpFlag::Gteq,
+    CmpFlag::Lt,
+    CmpFlag::LtEq,
+];
@@ -0,0 +1,823 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::bytecode::{
+    checksum, derive_block_data, BytecodeError, BYTECODE_HEADER_SIZE, BYTECODE_MAGIC, BYTECODE_VERSION, CMP_FLAGS,
+    INST_TAG_COMMENT, INST_TAG_ESCAPE, OPCODES,
+};
+use crate::entities::block::{Block, BlockData};
+use crate::entities::constant::{Constant, ConstantData};
+use crate::entities::external_name::{ExternalName, UserDefNamespace};
+use crate::entities::function::{ExternalFunctionData, Function, FunctionRef, FunctionSignature};
+use crate::entities::global_value::{GlobalValue, GlobalValueData};
+use crate::entities::immediate::{Immediate, Offset};
+use crate::entities::instruction::opcode::{CmpFlag, OpCode};
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::module::{DataDescription, DataId, DataInit, DataRelocation, FuncId, Module, ModuleLevelId};
+use crate::entities::r#type::{ArrayTypeData, MemType, MemTypeData, StructTypeData, StructTypeDataField, ValueType};
+use crate::entities::value::{Value, ValueData};
+
+type ReadResult<T> = Result<T, BytecodeError>;
+
+/// Insert entity into table, report error when index is already defined.
+fn insert_entity<K: Eq + Hash, V>(
+    map: &mut HashMap<K, V>,
+    key: K,
+    value: V,
+    kind: &'static str,
+    index: u32,
+) -> ReadResult<()> {
+    if map.insert(key, value).is_some() {
+        return Err(BytecodeError::DuplicateEntity { kind, index });
+    }
+    Ok(())
+}
+
+/// ## Bytecode Reader
+/// Decode module from bytecode, header is validated when reader is created. Every
+/// read is bounds checked, so truncated or corrupted bytecode is reported as error
+/// instead of panic.
+pub struct BytecodeReader<'a> {
+    payload: &'a [u8],
+    pos: usize,
+}
+
+/// Header and primitive decoding.
+impl<'a> BytecodeReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> ReadResult<Self> {
+        if bytes.len() < BYTECODE_MAGIC.len() || bytes[..BYTECODE_MAGIC.len()] != BYTECODE_MAGIC {
+            return Err(BytecodeError::BadMagic);
+        }
+        if bytes.len() < BYTECODE_HEADER_SIZE {
+            return Err(BytecodeError::UnexpectedEnd { offset: bytes.len() });
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != BYTECODE_VERSION {
+            return Err(BytecodeError::UnsupportedVersion(version));
+        }
+        let length = u32::from_le_bytes([bytes[6], bytes[7], bytes[8], bytes[9]]) as usize;
+        let expected_checksum = u32::from_le_bytes([bytes[10], bytes[11], bytes[12], bytes[13]]);
+        let payload = &bytes[BYTECODE_HEADER_SIZE..];
+        if payload.len() != length {
+            return Err(BytecodeError::LengthMismatch {
+                expected: length,
+                found: payload.len(),
+            });
+        }
+        let found_checksum = checksum(payload);
+        if found_checksum != expected_checksum {
+            return Err(BytecodeError::ChecksumMismatch {
+                expected: expected_checksum,
+                found: found_checksum,
+            });
+        }
+        Ok(Self { payload, pos: 0 })
+    }
+    /// Offset of current position in whole bytecode (include header).
+    fn offset(&self) -> usize {
+        self.pos + BYTECODE_HEADER_SIZE
+    }
+    fn read_raw(&mut self, len: usize) -> ReadResult<&'a [u8]> {
+        if self.payload.len() - self.pos < len {
+            return Err(BytecodeError::UnexpectedEnd {
+                offset: self.payload.len() + BYTECODE_HEADER_SIZE,
+            });
+        }
+        let bytes = &self.payload[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+    fn read_u8(&mut self) -> ReadResult<u8> {
+        Ok(self.read_raw(1)?[0])
+    }
+    fn read_uvarint(&mut self) -> ReadResult<u64> {
+        let start = self.offset();
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_u8()?;
+            let bits = (byte & 0x7f) as u64;
+            if shift == 63 && bits > 1 || shift > 63 {
+                return Err(BytecodeError::VarintOverflow { offset: start });
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+    fn read_svarint(&mut self) -> ReadResult<i64> {
+        let value = self.read_uvarint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+    fn read_u32(&mut self) -> ReadResult<u32> {
+        let start = self.offset();
+        u32::try_from(self.read_uvarint()?).map_err(|_| BytecodeError::VarintOverflow { offset: start })
+    }
+    fn read_i32(&mut self) -> ReadResult<i32> {
+        let start = self.offset();
+        i32::try_from(self.read_svarint()?).map_err(|_| BytecodeError::VarintOverflow { offset: start })
+    }
+    /// Read length of list, every element take at least one byte, so length larger
+    /// than remain bytes must come from corrupted bytecode.
+    fn read_len(&mut self) -> ReadResult<usize> {
+        let len = self.read_uvarint()?;
+        if len > (self.payload.len() - self.pos) as u64 {
+            return Err(BytecodeError::UnexpectedEnd {
+                offset: self.payload.len() + BYTECODE_HEADER_SIZE,
+            });
+        }
+        Ok(len as usize)
+    }
+    fn read_bytes(&mut self) -> ReadResult<Vec<u8>> {
+        let len = self.read_len()?;
+        Ok(self.read_raw(len)?.to_vec())
+    }
+    fn read_string(&mut self) -> ReadResult<String> {
+        let start = self.offset();
+        String::from_utf8(self.read_bytes()?).map_err(|_| BytecodeError::InvalidUtf8 { offset: start })
+    }
+    fn read_bool(&mut self) -> ReadResult<bool> {
+        let (tag, offset) = self.read_tag()?;
+        match tag {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid_tag("bool", tag, offset)),
+        }
+    }
+    /// Read tag of enum variant, return offset of tag for error report.
+    fn read_tag(&mut self) -> ReadResult<(u8, usize)> {
+        let offset = self.offset();
+        Ok((self.read_u8()?, offset))
+    }
+    /// Read index written as difference to the index next to last index.
+    fn read_delta(&mut self, last: &mut Option<u32>) -> ReadResult<u32> {
+        let start = self.offset();
+        let expected = last.map_or(0, |last| last as i64 + 1);
+        let index = expected
+            .checked_add(self.read_svarint()?)
+            .and_then(|index| u32::try_from(index).ok())
+            .ok_or(BytecodeError::VarintOverflow { offset: start })?;
+        *last = Some(index);
+        Ok(index)
+    }
+    fn read_index_set(&mut self) -> ReadResult<HashSet<Instruction>> {
+        let len = self.read_len()?;
+        let mut set = HashSet::with_capacity(len);
+        for _ in 0..len {
+            let index = self.read_u32()?;
+            if !set.insert(Instruction(index)) {
+                return Err(BytecodeError::DuplicateEntity {
+                    kind: "instruction",
+                    index,
+                });
+            }
+        }
+        Ok(set)
+    }
+}
+
+fn invalid_tag(kind: &'static str, tag: u8, offset: usize) -> BytecodeError {
+    BytecodeError::InvalidTag {
+        kind,
+        tag: tag as u64,
+        offset,
+    }
+}
+
+/// Module level decoding.
+impl BytecodeReader<'_> {
+    pub fn read_module(mut self) -> ReadResult<Module> {
+        let mut module = Module::new();
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let name = self.read_string()?;
+            let (tag, offset) = self.read_tag()?;
+            let index = self.read_u32()?;
+            let id = match tag {
+                0 => ModuleLevelId::Func(FuncId(index)),
+                1 => ModuleLevelId::Data(DataId(index)),
+                _ => return Err(invalid_tag("symbol", tag, offset)),
+            };
+            insert_entity(&mut module.symbol_table, name, id, "symbol", index)?;
+        }
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let index = self.read_u32()?;
+            let data = self.read_data()?;
+            insert_entity(&mut module.data_objects, DataId(index), data, "data", index)?;
+        }
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let index = self.read_u32()?;
+            let function = self.read_function()?;
+            insert_entity(&mut module.functions, FuncId(index), function, "function", index)?;
+        }
+        if self.pos != self.payload.len() {
+            return Err(BytecodeError::LengthMismatch {
+                expected: self.pos,
+                found: self.payload.len(),
+            });
+        }
+        check_module(&module)?;
+        Ok(module)
+    }
+    fn read_data(&mut self) -> ReadResult<DataDescription> {
+        let size = self.read_uvarint()?;
+        let align = self.read_uvarint()?;
+        let mutable = self.read_bool()?;
+        let (tag, offset) = self.read_tag()?;
+        let init = match tag {
+            0 => DataInit::Zeros,
+            1 => DataInit::Bytes(self.read_bytes()?),
+            _ => return Err(invalid_tag("data init", tag, offset)),
+        };
+        let len = self.read_len()?;
+        let mut relocations = Vec::with_capacity(len);
+        for _ in 0..len {
+            relocations.push(DataRelocation {
+                offset: self.read_uvarint()?,
+                name: self.read_external_name()?,
+                addend: self.read_svarint()?,
+            });
+        }
+        Ok(DataDescription {
+            size,
+            align,
+            mutable,
+            init,
+            relocations,
+        })
+    }
+    fn read_external_name(&mut self) -> ReadResult<ExternalName> {
+        let (tag, offset) = self.read_tag()?;
+        let namespace = match tag {
+            0 => UserDefNamespace::Data,
+            1 => UserDefNamespace::Function,
+            2 => UserDefNamespace::Other(self.read_u32()?),
+            _ => return Err(invalid_tag("external name", tag, offset)),
+        };
+        Ok(ExternalName::UserDefName {
+            namespace,
+            value: self.read_u32()?,
+        })
+    }
+}
+
+/// Function level decoding.
+impl BytecodeReader<'_> {
+    fn read_function(&mut self) -> ReadResult<Function> {
+        let mut function = Function::new();
+        function.signature = self.read_signature()?;
+        // types and other function info.
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let index = self.read_u32()?;
+            let mem_type_data = self.read_mem_type_data()?;
+            insert_entity(&mut function.mem_type, MemType(index), mem_type_data, "mem type", index)?;
+        }
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let index = self.read_u32()?;
+            let constant_data = ConstantData {
+                bytes: self.read_bytes()?,
+            };
+            insert_entity(
+                &mut function.constants,
+                Constant(index),
+                constant_data,
+                "constant",
+                index,
+            )?;
+        }
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let index = self.read_u32()?;
+            let external_func = ExternalFunctionData {
+                sig: self.read_signature()?,
+                name: self.read_external_name()?,
+            };
+            insert_entity(
+                &mut function.external_funcs,
+                FunctionRef(index),
+                external_func,
+                "function ref",
+                index,
+            )?;
+        }
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let index = self.read_u32()?;
+            let global_data = self.read_global_value_data()?;
+            insert_entity(
+                &mut function.global_values,
+                GlobalValue(index),
+                global_data,
+                "global value",
+                index,
+            )?;
+        }
+        // entities.
+        let entities = &mut function.entities;
+        entities.set_block_next_index(self.read_u32()?);
+        entities.set_inst_next_index(self.read_u32()?);
+        entities.set_value_next_index(self.read_u32()?);
+        let len = self.read_len()?;
+        for _ in 0..len {
+            entities.params.push(Value(self.read_u32()?));
+        }
+        // layout, only block and instruction which are not defined yet can be placed.
+        let (mut last_block, mut last_inst, mut last_value) = (None, None, None);
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let index = self.read_delta(&mut last_block)?;
+            let block = Block(index);
+            if function.layout.blocks.contains_key(&block) {
+                return Err(BytecodeError::DuplicateEntity { kind: "block", index });
+            }
+            function.layout.append_block(block);
+            let insts_len = self.read_len()?;
+            for _ in 0..insts_len {
+                let index = self.read_delta(&mut last_inst)?;
+                let inst = Instruction(index);
+                let inst_data = self.read_inst_data()?;
+                insert_entity(&mut function.entities.insts, inst, inst_data, "instruction", index)?;
+                function.layout.append_inst(inst, block);
+                if let Some(ty) = self.read_optional_value_type()? {
+                    let index = self.read_delta(&mut last_value)?;
+                    let value_data = ValueData::Inst { inst, ty };
+                    insert_entity(&mut function.entities.values, Value(index), value_data, "value", index)?;
+                    function.entities.insts_result.insert(inst, Value(index));
+                }
+            }
+            let block_data = derive_block_data(&function, block);
+            function.entities.blocks.insert(block, block_data);
+        }
+        // entities which can not be derived from layout.
+        let entities = &mut function.entities;
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let index = self.read_u32()?;
+            let inst_data = self.read_inst_data()?;
+            insert_entity(&mut entities.insts, Instruction(index), inst_data, "instruction", index)?;
+        }
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let index = self.read_u32()?;
+            let (tag, offset) = self.read_tag()?;
+            let value_data = match tag {
+                0 => ValueData::Inst {
+                    inst: Instruction(self.read_u32()?),
+                    ty: self.read_value_type()?,
+                },
+                1 => ValueData::Param {
+                    ty: self.read_value_type()?,
+                    index: self.read_u32()? as usize,
+                },
+                _ => return Err(invalid_tag("value", tag, offset)),
+            };
+            insert_entity(&mut entities.values, Value(index), value_data, "value", index)?;
+        }
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let index = self.read_u32()?;
+            let value = Value(self.read_u32()?);
+            insert_entity(
+                &mut entities.insts_result,
+                Instruction(index),
+                value,
+                "instruction result",
+                index,
+            )?;
+        }
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let index = self.read_u32()?;
+            insert_entity(&mut entities.blocks, Block(index), BlockData::new(), "block", index)?;
+        }
+        let len = self.read_len()?;
+        for _ in 0..len {
+            let index = self.read_u32()?;
+            let phis = self.read_index_set()?;
+            let insts = self.read_index_set()?;
+            match entities.blocks.get_mut(&Block(index)) {
+                Some(block_data) => {
+                    block_data.phis = phis;
+                    block_data.insts = insts;
+                }
+                None => return Err(BytecodeError::UnknownEntity { kind: "block", index }),
+            }
+        }
+        // param which value is not written is derived from signature.
+        for (index, param) in entities.params.iter().enumerate() {
+            if let (false, Some(ty)) = (
+                entities.values.contains_key(param),
+                function.signature.params.get(index),
+            ) {
+                let value_data = ValueData::Param { ty: ty.clone(), index };
+                entities.values.insert(*param, value_data);
+            }
+        }
+        Ok(function)
+    }
+    fn read_signature(&mut self) -> ReadResult<FunctionSignature> {
+        let len = self.read_len()?;
+        let mut params = Vec::with_capacity(len);
+        for _ in 0..len {
+            params.push(self.read_value_type()?);
+        }
+        let return_type = self.read_optional_value_type()?;
+        Ok(FunctionSignature { params, return_type })
+    }
+    fn read_value_type(&mut self) -> ReadResult<ValueType> {
+        let (tag, offset) = self.read_tag()?;
+        self.read_value_type_of_tag(tag, offset)
+    }
+    /// Type tag is shifted by one, zero means there is no type.
+    fn read_optional_value_type(&mut self) -> ReadResult<Option<ValueType>> {
+        let (tag, offset) = self.read_tag()?;
+        if tag == 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.read_value_type_of_tag(tag - 1, offset)?))
+    }
+    fn read_value_type_of_tag(&mut self, tag: u8, offset: usize) -> ReadResult<ValueType> {
+        Ok(match tag {
+            0 => ValueType::U8,
+            1 => ValueType::U16,
+            2 => ValueType::U32,
+            3 => ValueType::U64,
+            4 => ValueType::I16,
+            5 => ValueType::I32,
+            6 => ValueType::I64,
+            7 => ValueType::F32,
+            8 => ValueType::F64,
+            9 => ValueType::Mem(MemType(self.read_u32()?)),
+            _ => return Err(invalid_tag("value type", tag, offset)),
+        })
+    }
+    fn read_mem_type_data(&mut self) -> ReadResult<MemTypeData> {
+        let (tag, offset) = self.read_tag()?;
+        Ok(match tag {
+            0 => {
+                let size = self.read_u32()?;
+                let len = self.read_len()?;
+                let mut fields = Vec::with_capacity(len);
+                for _ in 0..len {
+                    fields.push(StructTypeDataField {
+                        offset: self.read_u32()?,
+                        ty: self.read_value_type()?,
+                    });
+                }
+                MemTypeData::Struct(StructTypeData { size, fields })
+            }
+            1 => MemTypeData::Array(ArrayTypeData {
+                size: self.read_u32()?,
+                ty: self.read_value_type()?,
+            }),
+            2 => MemTypeData::Unknow,
+            _ => return Err(invalid_tag("mem type", tag, offset)),
+        })
+    }
+    fn read_global_value_data(&mut self) -> ReadResult<GlobalValueData> {
+        let (tag, offset) = self.read_tag()?;
+        Ok(match tag {
+            0 => GlobalValueData::Symbol {
+                name: self.read_external_name()?,
+            },
+            1 => GlobalValueData::Load {
+                base: GlobalValue(self.read_u32()?),
+                offset: Offset(self.read_i32()?),
+                ty: self.read_value_type()?,
+            },
+            2 => GlobalValueData::AddI {
+                base: GlobalValue(self.read_u32()?),
+                offset: Offset(self.read_i32()?),
+                ty: self.read_value_type()?,
+            },
+            _ => return Err(invalid_tag("global value", tag, offset)),
+        })
+    }
+}
+
+fn require(defined: bool, kind: &'static str, index: u32) -> ReadResult<()> {
+    if !defined {
+        return Err(BytecodeError::UnknownEntity { kind, index });
+    }
+    Ok(())
+}
+
+/// Entities refer to each other by index, which is only checked after whole module is
+/// decoded. Formatter and passes assume every reference is defined, so bytecode which
+/// passes checksum but refers to undefined entity is reported here instead of panic.
+fn check_module(module: &Module) -> ReadResult<()> {
+    for id in module.symbol_table.values() {
+        match *id {
+            ModuleLevelId::Func(FuncId(index)) => {
+                require(module.functions.contains_key(&FuncId(index)), "function", index)?
+            }
+            ModuleLevelId::Data(DataId(index)) => {
+                require(module.data_objects.contains_key(&DataId(index)), "data", index)?
+            }
+        }
+    }
+    let named = module.symbol_table.values().copied().collect::<HashSet<_>>();
+    for (data_id, data) in &module.data_objects {
+        require(
+            named.contains(&ModuleLevelId::Data(*data_id)),
+            "symbol of data",
+            data_id.0,
+        )?;
+        for relocation in &data.relocations {
+            check_external_name(&relocation.name, &named)?;
+        }
+    }
+    for (func_id, function) in &module.functions {
+        require(
+            named.contains(&ModuleLevelId::Func(*func_id)),
+            "symbol of function",
+            func_id.0,
+        )?;
+        check_function(function, &named)?;
+    }
+    Ok(())
+}
+fn check_external_name(name: &ExternalName, named: &HashSet<ModuleLevelId>) -> ReadResult<()> {
+    match name {
+        ExternalName::UserDefName {
+            namespace: UserDefNamespace::Data,
+            value,
+        } => require(named.contains(&ModuleLevelId::Data(DataId(*value))), "data", *value),
+        ExternalName::UserDefName {
+            namespace: UserDefNamespace::Function,
+            value,
+        } => require(named.contains(&ModuleLevelId::Func(FuncId(*value))), "function", *value),
+        ExternalName::UserDefName { .. } => Ok(()),
+    }
+}
+fn check_function(function: &Function, named: &HashSet<ModuleLevelId>) -> ReadResult<()> {
+    let check_type = |ty: &ValueType| match ty {
+        ValueType::Mem(mem_type) => require(function.mem_type.contains_key(mem_type), "mem type", mem_type.0),
+        _ => Ok(()),
+    };
+    let check_signature = |sig: &FunctionSignature| sig.params.iter().chain(&sig.return_type).try_for_each(check_type);
+    // types and other function info.
+    check_signature(&function.signature)?;
+    for mem_type_data in function.mem_type.values() {
+        match mem_type_data {
+            MemTypeData::Struct(struct_data) => {
+                struct_data.fields.iter().try_for_each(|field| check_type(&field.ty))?
+            }
+            MemTypeData::Array(array_data) => check_type(&array_data.ty)?,
+            MemTypeData::Unknow => {}
+        }
+    }
+    for external_func in function.external_funcs.values() {
+        check_signature(&external_func.sig)?;
+        check_external_name(&external_func.name, named)?;
+    }
+    for global_data in function.global_values.values() {
+        match global_data {
+            GlobalValueData::Symbol { name } => check_external_name(name, named)?,
+            GlobalValueData::Load { base, ty, .. } | GlobalValueData::AddI { base, ty, .. } => {
+                require(function.global_values.contains_key(base), "global value", base.0)?;
+                check_type(ty)?;
+            }
+        }
+    }
+    // values, every param in signature must have a value.
+    let entities = &function.entities;
+    let params_len = function.signature.params.len();
+    require(
+        entities.params.len() >= params_len,
+        "param",
+        entities.params.len() as u32,
+    )?;
+    for param in &entities.params {
+        require(entities.values.contains_key(param), "value", param.0)?;
+    }
+    for value_data in entities.values.values() {
+        match value_data {
+            ValueData::Inst { inst, ty } => {
+                require(entities.insts.contains_key(inst), "instruction", inst.0)?;
+                check_type(ty)?;
+            }
+            ValueData::Param { ty, index } => {
+                require(*index < params_len, "param", *index as u32)?;
+                check_type(ty)?;
+            }
+        }
+    }
+    // result must be defined by instruction, not param.
+    for (inst, value) in &entities.insts_result {
+        require(entities.insts.contains_key(inst), "instruction", inst.0)?;
+        let is_inst_value = matches!(entities.values.get(value), Some(ValueData::Inst { .. }));
+        require(is_inst_value, "value", value.0)?;
+    }
+    for block_data in entities.blocks.values() {
+        for inst in block_data.phis.iter().chain(&block_data.insts) {
+            require(entities.insts.contains_key(inst), "instruction", inst.0)?;
+        }
+    }
+    // instructions, operand is not checked since builder allows use of value which
+    // is never defined, verifier reports it as undefined value. Removed
+    // instruction may have no result, only instruction in layout must have one.
+    let check_block = |block: &Block| require(entities.blocks.contains_key(block), "block", block.0);
+    for (inst, inst_data) in &entities.insts {
+        let should_have_result = function.layout.insts.contains_key(inst)
+            && !matches!(
+                inst_data,
+                InstructionData::Call { .. }
+                    | InstructionData::Ret { .. }
+                    | InstructionData::StoreRegister { .. }
+                    | InstructionData::GlobalStore { .. }
+                    | InstructionData::BrIf { .. }
+                    | InstructionData::Jump { .. }
+                    | InstructionData::Comment(_)
+            );
+        if should_have_result {
+            require(entities.insts_result.contains_key(inst), "instruction result", inst.0)?;
+        }
+        match inst_data {
+            InstructionData::UnaryConst { constant, .. } => {
+                require(function.constants.contains_key(constant), "constant", constant.0)?
+            }
+            InstructionData::GlobalLoad { base, .. } | InstructionData::GlobalStore { base, .. } => {
+                require(function.global_values.contains_key(base), "global value", base.0)?
+            }
+            InstructionData::Call { name, .. } => {
+                require(function.external_funcs.contains_key(name), "function ref", name.0)?
+            }
+            InstructionData::BrIf { conseq, alter, .. } => {
+                check_block(conseq)?;
+                check_block(alter)?;
+            }
+            InstructionData::Jump { dst, .. } => check_block(dst)?,
+            InstructionData::Phi { from, .. } => from.iter().try_for_each(|(block, _)| check_block(block))?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Instruction decoding.
+impl BytecodeReader<'_> {
+    fn read_opcode(&mut self) -> ReadResult<(OpCode, u8)> {
+        let (tag, offset) = self.read_tag()?;
+        OPCODES
+            .get(tag as usize)
+            .cloned()
+            .ok_or(invalid_tag("opcode", tag, offset))
+    }
+    fn read_cmp_flag(&mut self) -> ReadResult<CmpFlag> {
+        let (tag, offset) = self.read_tag()?;
+        CMP_FLAGS
+            .get(tag as usize)
+            .cloned()
+            .ok_or(invalid_tag("compare flag", tag, offset))
+    }
+    fn read_value(&mut self) -> ReadResult<Value> {
+        Ok(Value(self.read_u32()?))
+    }
+    fn read_offset(&mut self) -> ReadResult<Offset> {
+        Ok(Offset(self.read_i32()?))
+    }
+    fn read_immediate(&mut self) -> ReadResult<Immediate> {
+        let (tag, offset) = self.read_tag()?;
+        let start = self.offset();
+        let overflow = |_| BytecodeError::VarintOverflow { offset: start };
+        Ok(match tag {
+            0 => Immediate::U8(u8::try_from(self.read_uvarint()?).map_err(overflow)?),
+            1 => Immediate::U16(u16::try_from(self.read_uvarint()?).map_err(overflow)?),
+            2 => Immediate::U32(u32::try_from(self.read_uvarint()?).map_err(overflow)?),
+            3 => Immediate::U64(self.read_uvarint()?),
+            4 => Immediate::I16(i16::try_from(self.read_svarint()?).map_err(overflow)?),
+            5 => Immediate::I32(i32::try_from(self.read_svarint()?).map_err(overflow)?),
+            6 => Immediate::I64(self.read_svarint()?),
+            7 => {
+                let bytes = self.read_raw(4)?;
+                Immediate::F32(f32::from_bits(u32::from_le_bytes(bytes.try_into().unwrap())))
+            }
+            8 => {
+                let bytes = self.read_raw(8)?;
+                Immediate::F64(f64::from_bits(u64::from_le_bytes(bytes.try_into().unwrap())))
+            }
+            _ => return Err(invalid_tag("immediate", tag, offset)),
+        })
+    }
+    fn read_inst_data(&mut self) -> ReadResult<InstructionData> {
+        let (code, offset) = self.read_tag()?;
+        let (opcode, tag) = match code {
+            code if code == INST_TAG_ESCAPE + INST_TAG_COMMENT => {
+                return Ok(InstructionData::Comment(self.read_string()?))
+            }
+            code if code >= INST_TAG_ESCAPE => (self.read_opcode()?.0, code - INST_TAG_ESCAPE),
+            code => OPCODES
+                .get(code as usize)
+                .cloned()
+                .ok_or(invalid_tag("opcode", code, offset))?,
+        };
+        Ok(match tag {
+            0 => InstructionData::UnaryConst {
+                opcode,
+                constant: Constant(self.read_u32()?),
+            },
+            1 => InstructionData::Unary {
+                opcode,
+                value: self.read_value()?,
+            },
+            2 => InstructionData::Binary {
+                opcode,
+                args: [self.read_value()?, self.read_value()?],
+            },
+            3 => InstructionData::BinaryI {
+                opcode,
+                value: self.read_value()?,
+                imm: self.read_immediate()?,
+            },
+            4 => InstructionData::Move {
+                opcode,
+                src: self.read_value()?,
+            },
+            5 => InstructionData::Icmp {
+                opcode,
+                flag: self.read_cmp_flag()?,
+                args: [self.read_value()?, self.read_value()?],
+            },
+            6 => InstructionData::Fcmp {
+                opcode,
+                flag: self.read_cmp_flag()?,
+                args: [self.read_value()?, self.read_value()?],
+            },
+            7 => {
+                let name = FunctionRef(self.read_u32()?);
+                let len = self.read_len()?;
+                let mut params = Vec::with_capacity(len);
+                for _ in 0..len {
+                    params.push(self.read_value()?);
+                }
+                InstructionData::Call { opcode, name, params }
+            }
+            8 => {
+                let (value_tag, value_offset) = self.read_tag()?;
+                let value = match value_tag {
+                    0 => None,
+                    1 => Some(self.read_value()?),
+                    _ => return Err(invalid_tag("return value", value_tag, value_offset)),
+                };
+                InstructionData::Ret { opcode, value }
+            }
+            9 => InstructionData::Convert {
+                opcode,
+                src: self.read_value()?,
+            },
+            10 => InstructionData::StackAlloc {
+                opcode,
+                size: self.read_immediate()?,
+                align: self.read_immediate()?,
+            },
+            11 => InstructionData::LoadRegister {
+                opcode,
+                base: self.read_value()?,
+                offset: self.read_offset()?,
+            },
+            12 => InstructionData::StoreRegister {
+                opcode,
+                base: self.read_value()?,
+                offset: self.read_offset()?,
+                src: self.read_value()?,
+            },
+            13 => InstructionData::GlobalLoad {
+                opcode,
+                base: GlobalValue(self.read_u32()?),
+                offset: self.read_offset()?,
+            },
+            14 => InstructionData::GlobalStore {
+                opcode,
+                base: GlobalValue(self.read_u32()?),
+                offset: self.read_offset()?,
+                src: self.read_value()?,
+            },
+            15 => InstructionData::BrIf {
+                opcode,
+                test: self.read_value()?,
+                conseq: Block(self.read_u32()?),
+                alter: Block(self.read_u32()?),
+            },
+            16 => InstructionData::Jump {
+                opcode,
+                dst: Block(self.read_u32()?),
+            },
+            17 => {
+                let len = self.read_len()?;
+                let mut from = Vec::with_capacity(len);
+                for _ in 0..len {
+                    from.push((Block(self.read_u32()?), self.read_value()?));
+                }
+                InstructionData::Phi { opcode, from }
+            }
+            _ => return Err(invalid_tag("instruction", tag, offset)),
+        })
+    }
+}
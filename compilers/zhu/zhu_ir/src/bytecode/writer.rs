@@ -0,0 +1,573 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::bytecode::{
+    checksum, derive_block_data, BYTECODE_MAGIC, BYTECODE_VERSION, CMP_FLAGS, INST_TAG_COMMENT, INST_TAG_ESCAPE,
+    OPCODES,
+};
+use crate::entities::external_name::{ExternalName, UserDefNamespace};
+use crate::entities::function::{Function, FunctionSignature};
+use crate::entities::global_value::GlobalValueData;
+use crate::entities::immediate::Immediate;
+use crate::entities::instruction::opcode::{CmpFlag, OpCode};
+use crate::entities::instruction::InstructionData;
+use crate::entities::module::{DataDescription, DataInit, Module, ModuleLevelId};
+use crate::entities::r#type::{MemTypeData, ValueType};
+use crate::entities::value::ValueData;
+
+fn sorted_keys<K: Copy + Ord, V>(map: &HashMap<K, V>) -> Vec<K> {
+    let mut keys = map.keys().copied().collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+/// ## Bytecode Writer
+/// Encode module into payload, integers are LEB128 varint (signed integers are
+/// zigzag encoded first). Instructions are written in layout order with their
+/// result, only entities which can not be derived from layout are written as
+/// tables, tables are sorted by index so same module always produce same bytes.
+/// `finish` prepend header to payload.
+pub struct BytecodeWriter {
+    payload: Vec<u8>,
+}
+
+impl Default for BytecodeWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Primitive encoding.
+impl BytecodeWriter {
+    pub fn new() -> Self {
+        Self { payload: Vec::new() }
+    }
+    /// Get bytes of header and payload.
+    pub fn finish(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.payload.len() + super::BYTECODE_HEADER_SIZE);
+        bytes.extend_from_slice(&BYTECODE_MAGIC);
+        bytes.extend_from_slice(&BYTECODE_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.payload.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&checksum(&self.payload).to_le_bytes());
+        bytes.extend_from_slice(&self.payload);
+        bytes
+    }
+    fn write_u8(&mut self, value: u8) {
+        self.payload.push(value);
+    }
+    fn write_uvarint(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.payload.push(byte);
+                return;
+            }
+            self.payload.push(byte | 0x80);
+        }
+    }
+    fn write_svarint(&mut self, value: i64) {
+        self.write_uvarint(((value << 1) ^ (value >> 63)) as u64);
+    }
+    fn write_len(&mut self, len: usize) {
+        self.write_uvarint(len as u64);
+    }
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_len(bytes.len());
+        self.payload.extend_from_slice(bytes);
+    }
+    fn write_str(&mut self, text: &str) {
+        self.write_bytes(text.as_bytes());
+    }
+    fn write_bool(&mut self, value: bool) {
+        self.write_u8(value as u8);
+    }
+    /// Write index as difference to the index next to last written index, index
+    /// of entities are usually continuous so most of them take one byte.
+    fn write_delta(&mut self, index: u32, last: &mut Option<u32>) {
+        let expected = last.map_or(0, |last| last as i64 + 1);
+        self.write_svarint(index as i64 - expected);
+        *last = Some(index);
+    }
+    fn write_index_set<T: Copy + Ord>(&mut self, set: &HashSet<T>, index: impl Fn(T) -> u32) {
+        let mut elements = set.iter().copied().collect::<Vec<_>>();
+        elements.sort();
+        self.write_len(elements.len());
+        for element in elements {
+            self.write_uvarint(index(element) as u64);
+        }
+    }
+}
+
+/// Module level encoding.
+impl BytecodeWriter {
+    pub fn write_module(&mut self, module: &Module) {
+        let mut symbols = module.symbol_table.iter().collect::<Vec<_>>();
+        symbols.sort_by_key(|(name, _)| *name);
+        self.write_len(symbols.len());
+        for (name, id) in symbols {
+            self.write_str(name);
+            match id {
+                ModuleLevelId::Func(func_id) => {
+                    self.write_u8(0);
+                    self.write_uvarint(func_id.0 as u64);
+                }
+                ModuleLevelId::Data(data_id) => {
+                    self.write_u8(1);
+                    self.write_uvarint(data_id.0 as u64);
+                }
+            }
+        }
+        let data_ids = sorted_keys(&module.data_objects);
+        self.write_len(data_ids.len());
+        for data_id in data_ids {
+            self.write_uvarint(data_id.0 as u64);
+            self.write_data(&module.data_objects[&data_id]);
+        }
+        let func_ids = sorted_keys(&module.functions);
+        self.write_len(func_ids.len());
+        for func_id in func_ids {
+            self.write_uvarint(func_id.0 as u64);
+            self.write_function(&module.functions[&func_id]);
+        }
+    }
+    fn write_data(&mut self, data: &DataDescription) {
+        self.write_uvarint(data.size);
+        self.write_uvarint(data.align);
+        self.write_bool(data.mutable);
+        match &data.init {
+            DataInit::Zeros => self.write_u8(0),
+            DataInit::Bytes(bytes) => {
+                self.write_u8(1);
+                self.write_bytes(bytes);
+            }
+        }
+        self.write_len(data.relocations.len());
+        for relocation in &data.relocations {
+            self.write_uvarint(relocation.offset);
+            self.write_external_name(&relocation.name);
+            self.write_svarint(relocation.addend);
+        }
+    }
+    fn write_external_name(&mut self, name: &ExternalName) {
+        let ExternalName::UserDefName { namespace, value } = name;
+        match namespace {
+            UserDefNamespace::Data => self.write_u8(0),
+            UserDefNamespace::Function => self.write_u8(1),
+            UserDefNamespace::Other(other) => {
+                self.write_u8(2);
+                self.write_uvarint(*other as u64);
+            }
+        }
+        self.write_uvarint(*value as u64);
+    }
+}
+
+/// Function level encoding.
+impl BytecodeWriter {
+    fn write_function(&mut self, function: &Function) {
+        self.write_signature(&function.signature);
+        // types and other function info.
+        let mem_types = sorted_keys(&function.mem_type);
+        self.write_len(mem_types.len());
+        for mem_type in mem_types {
+            self.write_uvarint(mem_type.0 as u64);
+            self.write_mem_type_data(&function.mem_type[&mem_type]);
+        }
+        let constants = sorted_keys(&function.constants);
+        self.write_len(constants.len());
+        for constant in constants {
+            self.write_uvarint(constant.0 as u64);
+            self.write_bytes(&function.constants[&constant].bytes);
+        }
+        let func_refs = sorted_keys(&function.external_funcs);
+        self.write_len(func_refs.len());
+        for func_ref in func_refs {
+            let external_func = &function.external_funcs[&func_ref];
+            self.write_uvarint(func_ref.0 as u64);
+            self.write_signature(&external_func.sig);
+            self.write_external_name(&external_func.name);
+        }
+        let global_values = sorted_keys(&function.global_values);
+        self.write_len(global_values.len());
+        for global_value in global_values {
+            self.write_uvarint(global_value.0 as u64);
+            self.write_global_value_data(&function.global_values[&global_value]);
+        }
+        // entities.
+        let entities = &function.entities;
+        let (next_block, next_inst, next_value) = entities.get_next_indexes();
+        self.write_uvarint(next_block as u64);
+        self.write_uvarint(next_inst as u64);
+        self.write_uvarint(next_value as u64);
+        self.write_len(entities.params.len());
+        for param in &entities.params {
+            self.write_uvarint(param.0 as u64);
+        }
+        // layout, instructions are written in order with their result. sets of block
+        // data and result of instruction are derived from layout by reader.
+        let mut layout_blocks = Vec::new();
+        let mut cur_block = function.layout.first_block;
+        while let Some(block) = cur_block {
+            layout_blocks.push(block);
+            cur_block = function.layout.blocks[&block].next;
+        }
+        let mut written_insts = HashSet::new();
+        let mut written_values = HashSet::new();
+        let mut written_results = HashSet::new();
+        let (mut last_block, mut last_inst, mut last_value) = (None, None, None);
+        self.write_len(layout_blocks.len());
+        for block in &layout_blocks {
+            self.write_delta(block.0, &mut last_block);
+            let insts = function.get_insts_of_block(*block);
+            self.write_len(insts.len());
+            for inst in insts {
+                self.write_delta(inst.0, &mut last_inst);
+                self.write_inst_data(&entities.insts[&inst]);
+                let result = entities
+                    .insts_result
+                    .get(&inst)
+                    .and_then(|value| match entities.values.get(value) {
+                        Some(ValueData::Inst { inst: def_inst, ty }) if *def_inst == inst => Some((*value, ty)),
+                        _ => None,
+                    });
+                match result {
+                    Some((value, ty)) => {
+                        self.write_optional_value_type(Some(ty));
+                        self.write_delta(value.0, &mut last_value);
+                        written_values.insert(value);
+                        written_results.insert(inst);
+                    }
+                    None => self.write_optional_value_type(None),
+                }
+                written_insts.insert(inst);
+            }
+        }
+        // entities which can not be derived from layout, value of param is derived
+        // from signature.
+        let derived_params = entities
+            .params
+            .iter()
+            .enumerate()
+            .filter(|(index, param)| {
+                matches!(
+                    (entities.values.get(param), function.signature.params.get(*index)),
+                    (Some(ValueData::Param { ty, index: param_index }), Some(param_ty))
+                        if ty == param_ty && param_index == index
+                )
+            })
+            .map(|(_, param)| *param)
+            .collect::<HashSet<_>>();
+        let insts = sorted_keys(&entities.insts)
+            .into_iter()
+            .filter(|inst| !written_insts.contains(inst))
+            .collect::<Vec<_>>();
+        self.write_len(insts.len());
+        for inst in insts {
+            self.write_uvarint(inst.0 as u64);
+            self.write_inst_data(&entities.insts[&inst]);
+        }
+        let values = sorted_keys(&entities.values)
+            .into_iter()
+            .filter(|value| !written_values.contains(value) && !derived_params.contains(value))
+            .collect::<Vec<_>>();
+        self.write_len(values.len());
+        for value in values {
+            self.write_uvarint(value.0 as u64);
+            match &entities.values[&value] {
+                ValueData::Inst { inst, ty } => {
+                    self.write_u8(0);
+                    self.write_uvarint(inst.0 as u64);
+                    self.write_value_type(ty);
+                }
+                ValueData::Param { ty, index } => {
+                    self.write_u8(1);
+                    self.write_value_type(ty);
+                    self.write_uvarint(*index as u64);
+                }
+            }
+        }
+        let results = sorted_keys(&entities.insts_result)
+            .into_iter()
+            .filter(|inst| !written_results.contains(inst))
+            .collect::<Vec<_>>();
+        self.write_len(results.len());
+        for inst in results {
+            self.write_uvarint(inst.0 as u64);
+            self.write_uvarint(entities.insts_result[&inst].0 as u64);
+        }
+        let blocks = sorted_keys(&entities.blocks)
+            .into_iter()
+            .filter(|block| !function.layout.blocks.contains_key(block))
+            .collect::<Vec<_>>();
+        self.write_len(blocks.len());
+        for block in blocks {
+            self.write_uvarint(block.0 as u64);
+        }
+        let blocks = sorted_keys(&entities.blocks)
+            .into_iter()
+            .filter(|block| entities.blocks[block] != derive_block_data(function, *block))
+            .collect::<Vec<_>>();
+        self.write_len(blocks.len());
+        for block in blocks {
+            let block_data = &entities.blocks[&block];
+            self.write_uvarint(block.0 as u64);
+            self.write_index_set(&block_data.phis, |inst| inst.0);
+            self.write_index_set(&block_data.insts, |inst| inst.0);
+        }
+    }
+    fn write_signature(&mut self, signature: &FunctionSignature) {
+        self.write_len(signature.params.len());
+        for param in &signature.params {
+            self.write_value_type(param);
+        }
+        self.write_optional_value_type(signature.return_type.as_ref());
+    }
+    /// Type tag is shifted by one, zero means there is no type.
+    fn write_optional_value_type(&mut self, ty: Option<&ValueType>) {
+        match ty {
+            Some(ty) => {
+                let start = self.payload.len();
+                self.write_value_type(ty);
+                self.payload[start] += 1;
+            }
+            None => self.write_u8(0),
+        }
+    }
+    fn write_value_type(&mut self, ty: &ValueType) {
+        match ty {
+            ValueType::U8 => self.write_u8(0),
+            ValueType::U16 => self.write_u8(1),
+            ValueType::U32 => self.write_u8(2),
+            ValueType::U64 => self.write_u8(3),
+            ValueType::I16 => self.write_u8(4),
+            ValueType::I32 => self.write_u8(5),
+            ValueType::I64 => self.write_u8(6),
+            ValueType::F32 => self.write_u8(7),
+            ValueType::F64 => self.write_u8(8),
+            ValueType::Mem(mem_type) => {
+                self.write_u8(9);
+                self.write_uvarint(mem_type.0 as u64);
+            }
+        }
+    }
+    fn write_mem_type_data(&mut self, mem_type_data: &MemTypeData) {
+        match mem_type_data {
+            MemTypeData::Struct(struct_data) => {
+                self.write_u8(0);
+                self.write_uvarint(struct_data.size as u64);
+                self.write_len(struct_data.fields.len());
+                for field in &struct_data.fields {
+                    self.write_uvarint(field.offset as u64);
+                    self.write_value_type(&field.ty);
+                }
+            }
+            MemTypeData::Array(array_data) => {
+                self.write_u8(1);
+                self.write_uvarint(array_data.size as u64);
+                self.write_value_type(&array_data.ty);
+            }
+            MemTypeData::Unknow => self.write_u8(2),
+        }
+    }
+    fn write_global_value_data(&mut self, global_data: &GlobalValueData) {
+        match global_data {
+            GlobalValueData::Symbol { name } => {
+                self.write_u8(0);
+                self.write_external_name(name);
+            }
+            GlobalValueData::Load { base, offset, ty } | GlobalValueData::AddI { base, offset, ty } => {
+                self.write_u8(if matches!(global_data, GlobalValueData::Load { .. }) {
+                    1
+                } else {
+                    2
+                });
+                self.write_uvarint(base.0 as u64);
+                self.write_svarint(offset.0 as i64);
+                self.write_value_type(ty);
+            }
+        }
+    }
+}
+
+/// Instruction encoding, every instruction start with opcode, tag of variant is
+/// only written when it is not the usual variant of opcode.
+impl BytecodeWriter {
+    fn write_inst_head(&mut self, tag: u8, opcode: &OpCode) {
+        let index = OPCODES
+            .iter()
+            .position(|(candidate, _)| candidate == opcode)
+            .expect("opcode is not in bytecode opcode table.");
+        if OPCODES[index].1 != tag {
+            self.write_u8(INST_TAG_ESCAPE + tag);
+        }
+        self.write_u8(index as u8);
+    }
+    fn write_cmp_flag(&mut self, flag: &CmpFlag) {
+        let index = CMP_FLAGS.iter().position(|candidate| candidate == flag).unwrap();
+        self.write_u8(index as u8);
+    }
+    fn write_immediate(&mut self, imm: &Immediate) {
+        match imm {
+            Immediate::U8(value) => {
+                self.write_u8(0);
+                self.write_uvarint(*value as u64);
+            }
+            Immediate::U16(value) => {
+                self.write_u8(1);
+                self.write_uvarint(*value as u64);
+            }
+            Immediate::U32(value) => {
+                self.write_u8(2);
+                self.write_uvarint(*value as u64);
+            }
+            Immediate::U64(value) => {
+                self.write_u8(3);
+                self.write_uvarint(*value);
+            }
+            Immediate::I16(value) => {
+                self.write_u8(4);
+                self.write_svarint(*value as i64);
+            }
+            Immediate::I32(value) => {
+                self.write_u8(5);
+                self.write_svarint(*value as i64);
+            }
+            Immediate::I64(value) => {
+                self.write_u8(6);
+                self.write_svarint(*value);
+            }
+            // float is written as raw bits so nan and sign of zero are kept.
+            Immediate::F32(value) => {
+                self.write_u8(7);
+                self.payload.extend_from_slice(&value.to_bits().to_le_bytes());
+            }
+            Immediate::F64(value) => {
+                self.write_u8(8);
+                self.payload.extend_from_slice(&value.to_bits().to_le_bytes());
+            }
+        }
+    }
+    fn write_inst_data(&mut self, inst_data: &InstructionData) {
+        match inst_data {
+            InstructionData::UnaryConst { opcode, constant } => {
+                self.write_inst_head(0, opcode);
+                self.write_uvarint(constant.0 as u64);
+            }
+            InstructionData::Unary { opcode, value } => {
+                self.write_inst_head(1, opcode);
+                self.write_uvarint(value.0 as u64);
+            }
+            InstructionData::Binary { opcode, args } => {
+                self.write_inst_head(2, opcode);
+                self.write_uvarint(args[0].0 as u64);
+                self.write_uvarint(args[1].0 as u64);
+            }
+            InstructionData::BinaryI { opcode, value, imm } => {
+                self.write_inst_head(3, opcode);
+                self.write_uvarint(value.0 as u64);
+                self.write_immediate(imm);
+            }
+            InstructionData::Move { opcode, src } => {
+                self.write_inst_head(4, opcode);
+                self.write_uvarint(src.0 as u64);
+            }
+            InstructionData::Icmp { opcode, flag, args } | InstructionData::Fcmp { opcode, flag, args } => {
+                let tag = if matches!(inst_data, InstructionData::Icmp { .. }) {
+                    5
+                } else {
+                    6
+                };
+                self.write_inst_head(tag, opcode);
+                self.write_cmp_flag(flag);
+                self.write_uvarint(args[0].0 as u64);
+                self.write_uvarint(args[1].0 as u64);
+            }
+            InstructionData::Call { opcode, name, params } => {
+                self.write_inst_head(7, opcode);
+                self.write_uvarint(name.0 as u64);
+                self.write_len(params.len());
+                for param in params {
+                    self.write_uvarint(param.0 as u64);
+                }
+            }
+            InstructionData::Ret { opcode, value } => {
+                self.write_inst_head(8, opcode);
+                match value {
+                    Some(value) => {
+                        self.write_u8(1);
+                        self.write_uvarint(value.0 as u64);
+                    }
+                    None => self.write_u8(0),
+                }
+            }
+            InstructionData::Convert { opcode, src } => {
+                self.write_inst_head(9, opcode);
+                self.write_uvarint(src.0 as u64);
+            }
+            InstructionData::StackAlloc { opcode, size, align } => {
+                self.write_inst_head(10, opcode);
+                self.write_immediate(size);
+                self.write_immediate(align);
+            }
+            InstructionData::LoadRegister { opcode, base, offset } => {
+                self.write_inst_head(11, opcode);
+                self.write_uvarint(base.0 as u64);
+                self.write_svarint(offset.0 as i64);
+            }
+            InstructionData::StoreRegister {
+                opcode,
+                base,
+                offset,
+                src,
+            } => {
+                self.write_inst_head(12, opcode);
+                self.write_uvarint(base.0 as u64);
+                self.write_svarint(offset.0 as i64);
+                self.write_uvarint(src.0 as u64);
+            }
+            InstructionData::GlobalLoad { opcode, base, offset } => {
+                self.write_inst_head(13, opcode);
+                self.write_uvarint(base.0 as u64);
+                self.write_svarint(offset.0 as i64);
+            }
+            InstructionData::GlobalStore {
+                opcode,
+                base,
+                offset,
+                src,
+            } => {
+                self.write_inst_head(14, opcode);
+                self.write_uvarint(base.0 as u64);
+                self.write_svarint(offset.0 as i64);
+                self.write_uvarint(src.0 as u64);
+            }
+            InstructionData::BrIf {
+                opcode,
+                test,
+                conseq,
+                alter,
+            } => {
+                self.write_inst_head(15, opcode);
+                self.write_uvarint(test.0 as u64);
+                self.write_uvarint(conseq.0 as u64);
+                self.write_uvarint(alter.0 as u64);
+            }
+            InstructionData::Jump { opcode, dst } => {
+                self.write_inst_head(16, opcode);
+                self.write_uvarint(dst.0 as u64);
+            }
+            InstructionData::Phi { opcode, from } => {
+                self.write_inst_head(17, opcode);
+                self.write_len(from.len());
+                for (block, value) in from {
+                    self.write_uvarint(block.0 as u64);
+                    self.write_uvarint(value.0 as u64);
+                }
+            }
+            InstructionData::Comment(comment) => {
+                self.write_u8(INST_TAG_ESCAPE + INST_TAG_COMMENT);
+                self.write_str(comment);
+            }
+        }
+    }
+}
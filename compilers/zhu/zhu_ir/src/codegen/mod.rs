@@ -0,0 +1,248 @@
+use std::fmt;
+use std::fmt::Write;
+
+use crate::entities::block::Block;
+use crate::entities::external_name::ExternalName;
+use crate::entities::function::Function;
+use crate::entities::global_value::GlobalValue;
+use crate::entities::instruction::opcode::OpCode;
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::module::{DataDescription, DataId, DataInit, FuncId, Module, ModuleLevelId, DATA_POINTER_SIZE};
+use crate::entities::r#type::ValueType;
+use crate::entities::value::Value;
+
+pub mod regalloc;
+pub mod riscv64;
+pub mod x86_64;
+
+/// Error of code generation, point to the function and instruction which can
+/// not be lowered to target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodegenError {
+    pub func: String,
+    pub inst: Option<Instruction>,
+    pub kind: CodegenErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodegenErrorKind {
+    /// Opcode can not be applied to value type.
+    UnsupportedOperation(OpCode, ValueType),
+    /// Alignment of stack slot is larger than alignment of stack frame.
+    UnsupportedAlignment(u64),
+    /// Stack frame can not be addressed by 32-bit offset.
+    FrameTooLarge,
+    UnresolvedSymbol(ExternalName),
+    UnknownGlobalValue(GlobalValue),
+    UnknownConstant,
+    MissingResult,
+    MissingPhiArgument {
+        block: Block,
+        from: Block,
+    },
+    /// Immediate can not be encoded in instruction.
+    ImmediateOutOfRange(i64),
+    UnknownLabel(String),
+}
+
+impl fmt::Display for CodegenErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodegenErrorKind::UnsupportedOperation(opcode, ty) => {
+                write!(f, "`{}` on value type {:?} is not supported", opcode, ty)
+            }
+            CodegenErrorKind::UnsupportedAlignment(align) => write!(f, "stack alignment {} is not supported", align),
+            CodegenErrorKind::FrameTooLarge => write!(f, "stack frame is too large"),
+            CodegenErrorKind::UnresolvedSymbol(name) => write!(f, "can not resolve external name {:?}", name),
+            CodegenErrorKind::UnknownGlobalValue(global) => write!(f, "global value {} is not in function", global.0),
+            CodegenErrorKind::UnknownConstant => write!(f, "constant is not in function"),
+            CodegenErrorKind::MissingResult => write!(f, "instruction has no result value"),
+            CodegenErrorKind::MissingPhiArgument { block, from } => {
+                write!(f, "phi in block{} has no argument for block{}", block.0, from.0)
+            }
+            CodegenErrorKind::ImmediateOutOfRange(imm) => write!(f, "immediate {} is out of range of instruction", imm),
+            CodegenErrorKind::UnknownLabel(label) => write!(f, "label `{}` is not defined", label),
+        }
+    }
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.inst {
+            Some(inst) => write!(f, "[Error]: func {}, inst{}: {}", self.func, inst.0, self.kind),
+            None => write!(f, "[Error]: func {}: {}", self.func, self.kind),
+        }
+    }
+}
+
+/// Get functions of module which have body, sorted by id so output is stable.
+pub fn get_defined_functions(module: &Module) -> Vec<(FuncId, &str, &Function)> {
+    let mut func_ids = module.functions.keys().copied().collect::<Vec<_>>();
+    func_ids.sort();
+    func_ids
+        .into_iter()
+        .filter_map(|func_id| {
+            let function = &module.functions[&func_id];
+            let name = module.get_symbol_by_module_id(ModuleLevelId::Func(func_id))?;
+            function.first_block().map(|_| (func_id, name, function))
+        })
+        .collect()
+}
+
+/// Get symbol name of data or function which external name refer to.
+pub fn get_symbol_of_external_name<'a>(module: &'a Module, name: &ExternalName) -> Option<&'a str> {
+    module.get_symbol_by_module_id(name.to_module_level_id()?)
+}
+
+/// Is value type a signed integer, signed integer is sign extended in register.
+pub fn is_signed(ty: &ValueType) -> bool {
+    matches!(ty, ValueType::I16 | ValueType::I32 | ValueType::I64)
+}
+
+/// Is value type a float, float is kept as raw bits in integer register.
+pub fn is_float(ty: &ValueType) -> bool {
+    matches!(ty, ValueType::F32 | ValueType::F64)
+}
+
+/// Normalize 64-bit register bits of value type: integer narrower than 64-bit is
+/// zero or sign extended by signedness, `f32` keep bits in low half.
+pub fn normalize_bits(bits: u64, ty: &ValueType) -> u64 {
+    match ty {
+        ValueType::U8 => bits as u8 as u64,
+        ValueType::U16 => bits as u16 as u64,
+        ValueType::U32 | ValueType::F32 => bits as u32 as u64,
+        ValueType::I16 => bits as i16 as i64 as u64,
+        ValueType::I32 => bits as i32 as i64 as u64,
+        ValueType::U64 | ValueType::I64 | ValueType::F64 | ValueType::Mem(_) => bits,
+    }
+}
+
+/// Get bits of constant data with given value type, bytes are little endian.
+pub fn constant_bits(bytes: &[u8], ty: &ValueType) -> u64 {
+    let mut buffer = [0_u8; 8];
+    for (index, byte) in bytes.iter().take(8).enumerate() {
+        buffer[index] = *byte;
+    }
+    normalize_bits(u64::from_le_bytes(buffer), ty)
+}
+
+/// Range of integer type, float out of range is saturated to bound when convert.
+pub fn int_range(ty: &ValueType) -> (i64, u64) {
+    match ty {
+        ValueType::U8 => (0, u8::MAX as u64),
+        ValueType::U16 => (0, u16::MAX as u64),
+        ValueType::U32 => (0, u32::MAX as u64),
+        ValueType::I16 => (i16::MIN as i64, i16::MAX as u64),
+        ValueType::I32 => (i32::MIN as i64, i32::MAX as u64),
+        ValueType::I64 => (i64::MIN, i64::MAX as u64),
+        _ => (0, u64::MAX),
+    }
+}
+
+/// Get instructions of function in layout order.
+fn get_layout_insts(function: &Function) -> Vec<Instruction> {
+    let mut insts = Vec::new();
+    let mut cur_block = function.layout.first_block;
+    while let Some(block) = cur_block {
+        insts.extend(function.get_insts_of_block(block));
+        cur_block = function.layout.blocks[&block].next;
+    }
+    insts
+}
+
+/// Get values which need a home in stack frame, which are params and results of
+/// instructions in layout order.
+pub fn get_frame_values(function: &Function) -> Vec<Value> {
+    let mut values = function.entities.params.clone();
+    for inst in get_layout_insts(function) {
+        if let Some(result) = function.get_inst_result(inst) {
+            values.push(result);
+        }
+    }
+    values
+}
+
+/// Get `stackalloc` instructions in layout order with their size and alignment, zero
+/// alignment is treated as one.
+pub fn get_stack_allocs(function: &Function) -> Vec<(Instruction, u64, u64)> {
+    let mut stack_allocs = Vec::new();
+    for inst in get_layout_insts(function) {
+        if let InstructionData::StackAlloc { size, align, .. } = function.get_inst_data(inst) {
+            let size = u64::from_le_bytes(size.get_bytes());
+            let align = u64::from_le_bytes(align.get_bytes()).max(1);
+            stack_allocs.push((inst, size, align));
+        }
+    }
+    stack_allocs
+}
+
+/// Emit data objects of module in GNU assembler directives, sorted by id. Immutable data
+/// with relocation is put in `.data.rel.ro` so that dynamic linker can still write address
+/// into it.
+pub fn emit_data_objects(module: &Module, output: &mut String) -> Result<(), CodegenError> {
+    let mut data_ids = module.data_objects.keys().copied().collect::<Vec<_>>();
+    data_ids.sort();
+    for data_id in data_ids {
+        let Some(name) = module.get_symbol_by_module_id(ModuleLevelId::Data(data_id)) else {
+            continue;
+        };
+        emit_data(module, name, data_id, output)?;
+    }
+    Ok(())
+}
+
+fn emit_data(module: &Module, name: &str, data_id: DataId, output: &mut String) -> Result<(), CodegenError> {
+    let data = &module.data_objects[&data_id];
+    let section = match (data.mutable, data.relocations.is_empty()) {
+        (true, _) => "    .data",
+        (false, false) => "    .section .data.rel.ro,\"aw\"",
+        (false, true) => "    .section .rodata",
+    };
+    writeln!(output, "{}", section).unwrap();
+    writeln!(output, "    .globl {}", name).unwrap();
+    writeln!(output, "    .type {}, @object", name).unwrap();
+    writeln!(output, "    .balign {}", data.align.max(1)).unwrap();
+    writeln!(output, "{}:", name).unwrap();
+    let mut relocations = data.relocations.iter().collect::<Vec<_>>();
+    relocations.sort_by_key(|relocation| relocation.offset);
+    let mut cursor = 0;
+    for relocation in relocations {
+        emit_data_bytes(data, cursor, relocation.offset, output);
+        let symbol = get_symbol_of_external_name(module, &relocation.name).ok_or_else(|| CodegenError {
+            func: name.to_owned(),
+            inst: None,
+            kind: CodegenErrorKind::UnresolvedSymbol(relocation.name.clone()),
+        })?;
+        writeln!(output, "    .quad {}{:+}", symbol, relocation.addend).unwrap();
+        cursor = relocation.offset + DATA_POINTER_SIZE;
+    }
+    emit_data_bytes(data, cursor, data.size, output);
+    writeln!(output, "    .size {}, {}", name, data.size).unwrap();
+    Ok(())
+}
+
+/// Emit initial content of data in range `[start, end)`, zero bytes are merged into
+/// `.zero` directive.
+fn emit_data_bytes(data: &DataDescription, start: u64, end: u64, output: &mut String) {
+    let bytes = match &data.init {
+        DataInit::Zeros => &[][..],
+        DataInit::Bytes(bytes) => &bytes[..],
+    };
+    let mut offset = start;
+    while offset < end {
+        let init_end = (bytes.len() as u64).clamp(offset, end);
+        if offset < init_end {
+            let chunk_end = init_end.min(offset + 16);
+            let text = bytes[offset as usize..chunk_end as usize]
+                .iter()
+                .map(|byte| byte.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(output, "    .byte {}", text).unwrap();
+            offset = chunk_end;
+        } else {
+            writeln!(output, "    .zero {}", end - offset).unwrap();
+            offset = end;
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::entities::function::Function;
+use crate::entities::module::Module;
+use crate::entities::r#type::ValueType;
+use crate::entities::value::Value;
+use crate::pass::analysis::cfg::cfg_anylysis;
+use crate::pass::analysis::liveness::{fmt_ranges, liveness_analysis, Liveness};
+use crate::pass::analysis::rpo::revrese_post_order_analysis;
+use crate::pass::{get_table_header, FormatTable};
+
+/// Class of registers, value can only be assigned to register of its class.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum RegisterClass {
+    Int,
+    Float,
+}
+
+/// ## Register Target
+/// Registers of a target which can be given to values by register allocator.
+/// Allocator does not model clobber of call, so registers should be preserved
+/// across call (callee saved) if function has call.
+pub trait RegisterTarget {
+    /// Class of register which can hold value of type.
+    fn register_class(&self, ty: &ValueType) -> RegisterClass;
+    /// Allocatable registers of class, in order of preference.
+    fn allocatable_registers(&self, class: RegisterClass) -> Vec<&'static str>;
+}
+
+/// Where a value is kept for its whole interval.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Assignment {
+    Register(&'static str),
+    /// Index of spill slot, every slot is 8 bytes.
+    Spill(usize),
+}
+
+impl fmt::Display for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Assignment::Register(name) => write!(f, "{}", name),
+            Assignment::Spill(slot) => write!(f, "spill{}", slot),
+        }
+    }
+}
+
+/// Result of register allocation of a function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisterAllocation {
+    assignments: HashMap<Value, Assignment>,
+    intervals: HashMap<Value, String>,
+    spill_slot_count: usize,
+}
+
+impl RegisterAllocation {
+    /// Get assignment of value, value which has no live interval is not assigned.
+    pub fn get_assignment(&self, value: Value) -> Option<Assignment> {
+        self.assignments.get(&value).copied()
+    }
+    pub fn get_assignments(&self) -> &HashMap<Value, Assignment> {
+        &self.assignments
+    }
+    /// Number of spill slots used by function, slots are shared by values whose
+    /// intervals do not overlap.
+    pub fn get_spill_slot_count(&self) -> usize {
+        self.spill_slot_count
+    }
+}
+
+impl FormatTable for RegisterAllocation {
+    fn format_table(&self, _func: &Function, _module: &Module) -> String {
+        let mut format_string = get_table_header("Register Allocation");
+        let mut values = self.assignments.keys().copied().collect::<Vec<_>>();
+        values.sort_by_key(|value| value.0);
+        for value in values {
+            format_string.push_str(&format!(
+                "reg{}: {} {}\n",
+                value.0, self.assignments[&value], self.intervals[&value]
+            ));
+        }
+        format_string.push_str(&format!("Spill slots: {}\n", self.spill_slot_count));
+        format_string
+    }
+}
+
+/// Value which occupy a register or spill slot until `end`.
+struct Active {
+    value: Value,
+    end: usize,
+    assignment: Assignment,
+}
+
+/// ## Linear Scan Register Allocation
+/// Allocate registers by scanning live intervals sorted by start position, interval
+/// is treated as a single range from its start to its end. When no register of class
+/// is free, the interval which ends last among current interval and active intervals
+/// of the class is spilled. Spill slots are reused after interval in it ends.
+pub fn linear_scan(function: &Function, liveness: &Liveness, target: &dyn RegisterTarget) -> RegisterAllocation {
+    let mut values = liveness.get_intervals().keys().copied().collect::<Vec<_>>();
+    values.sort_by_key(|value| (liveness.get_interval(*value).unwrap().start(), value.0));
+    let mut free_registers = HashMap::new();
+    for class in [RegisterClass::Int, RegisterClass::Float] {
+        let mut registers = target.allocatable_registers(class);
+        // pop from the end, so most preferred register is given first.
+        registers.reverse();
+        free_registers.insert(class, registers);
+    }
+    let mut active: HashMap<RegisterClass, Vec<Active>> = HashMap::new();
+    let mut active_spills: Vec<Active> = Vec::new();
+    let mut free_slots: Vec<usize> = Vec::new();
+    let mut spill_slot_count = 0;
+    let mut assignments = HashMap::new();
+    let mut new_slot = |free_slots: &mut Vec<usize>| {
+        free_slots.pop().unwrap_or_else(|| {
+            spill_slot_count += 1;
+            spill_slot_count - 1
+        })
+    };
+    for value in values {
+        let interval = liveness.get_interval(value).unwrap();
+        let (start, end) = (interval.start(), interval.end());
+        // expire intervals which end before current interval starts.
+        for (class, class_active) in active.iter_mut() {
+            class_active.retain(|item| {
+                if item.end > start {
+                    return true;
+                }
+                if let Assignment::Register(name) = item.assignment {
+                    free_registers.get_mut(class).unwrap().push(name);
+                }
+                false
+            });
+        }
+        active_spills.retain(|item| {
+            if item.end > start {
+                return true;
+            }
+            if let Assignment::Spill(slot) = item.assignment {
+                free_slots.push(slot);
+            }
+            false
+        });
+        let class = target.register_class(function.value_type(value));
+        let class_active = active.entry(class).or_default();
+        let assignment = if let Some(name) = free_registers.get_mut(&class).unwrap().pop() {
+            class_active.push(Active {
+                value,
+                end,
+                assignment: Assignment::Register(name),
+            });
+            Assignment::Register(name)
+        } else {
+            let victim = class_active
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, item)| (item.end, item.value.0))
+                .map(|(index, item)| (index, item.end));
+            match victim {
+                Some((index, victim_end)) if victim_end > end => {
+                    let victim = class_active.swap_remove(index);
+                    let slot = new_slot(&mut free_slots);
+                    assignments.insert(victim.value, Assignment::Spill(slot));
+                    active_spills.push(Active {
+                        value: victim.value,
+                        end: victim.end,
+                        assignment: Assignment::Spill(slot),
+                    });
+                    class_active.push(Active {
+                        value,
+                        end,
+                        assignment: victim.assignment,
+                    });
+                    victim.assignment
+                }
+                _ => {
+                    let slot = new_slot(&mut free_slots);
+                    active_spills.push(Active {
+                        value,
+                        end,
+                        assignment: Assignment::Spill(slot),
+                    });
+                    Assignment::Spill(slot)
+                }
+            }
+        };
+        assignments.insert(value, assignment);
+    }
+    let intervals = assignments
+        .keys()
+        .map(|value| (*value, fmt_ranges(liveness.get_interval(*value).unwrap())))
+        .collect();
+    RegisterAllocation {
+        assignments,
+        intervals,
+        spill_slot_count,
+    }
+}
+
+/// Compute liveness of function and allocate registers of target by linear scan, used
+/// by backends to find home of values.
+pub fn allocate_registers(function: &Function, target: &dyn RegisterTarget) -> RegisterAllocation {
+    let cfg = cfg_anylysis(function);
+    let rpo = revrese_post_order_analysis(&cfg);
+    let liveness = liveness_analysis(function, &cfg, &rpo);
+    linear_scan(function, &liveness, target)
+}
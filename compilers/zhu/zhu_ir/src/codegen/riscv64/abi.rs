@@ -0,0 +1,37 @@
+use crate::codegen::is_float;
+use crate::codegen::riscv64::register::{FReg, XReg, FLOAT_ARG_REGS, INT_ARG_REGS};
+use crate::entities::r#type::ValueType;
+
+/// Alignment of stack pointer at call.
+pub const STACK_ALIGN: u64 = 16;
+
+/// Where a argument is passed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ArgLocation {
+    Int(XReg),
+    Float(FReg),
+    /// Offset to stack pointer at call.
+    Stack(u64),
+}
+
+/// Classify arguments by LP64D calling convention: float is passed by `fa0` - `fa7`,
+/// integer and address by `a0` - `a7`, float uses integer register when float registers
+/// are exhausted, the rest take 8 bytes slots on stack in order. Return locations and
+/// size of stack area (aligned to 16 bytes).
+pub fn classify_args(types: &[ValueType]) -> (Vec<ArgLocation>, u64) {
+    let (mut int_index, mut float_index, mut stack_size) = (0, 0, 0);
+    let mut locations = Vec::with_capacity(types.len());
+    for ty in types {
+        if is_float(ty) && float_index < FLOAT_ARG_REGS.len() {
+            locations.push(ArgLocation::Float(FLOAT_ARG_REGS[float_index]));
+            float_index += 1;
+        } else if int_index < INT_ARG_REGS.len() {
+            locations.push(ArgLocation::Int(INT_ARG_REGS[int_index]));
+            int_index += 1;
+        } else {
+            locations.push(ArgLocation::Stack(stack_size));
+            stack_size += 8;
+        }
+    }
+    (locations, stack_size.div_ceil(STACK_ALIGN) * STACK_ALIGN)
+}
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+
+use crate::codegen::riscv64::inst::{
+    AluImmOp, AluOp, BranchOp, FArithOp, FCmpOp, FloatFmt, IntFmt, LoadOp, RvInst, StoreOp,
+};
+use crate::codegen::riscv64::register::XReg;
+use crate::codegen::{get_symbol_of_external_name, CodegenError, CodegenErrorKind};
+use crate::entities::function::FunctionSignature;
+use crate::entities::module::{DataInit, Module, ModuleLevelId, DATA_POINTER_SIZE};
+
+/// Address of first instruction, memory below it is never mapped so jump to zero
+/// can be used as return address of simulation.
+pub const TEXT_BASE: u64 = 0x1_0000;
+
+/// Rounding mode field: round toward zero.
+const RM_RTZ: u32 = 0b001;
+/// Rounding mode field: use dynamic rounding mode of `frm`.
+const RM_DYN: u32 = 0b111;
+
+/// ## Program
+/// Module assembled to machine words, text and data are placed at fixed address and
+/// all relocations are resolved. Symbol which is not defined in module (such as `fmod`
+/// of libm) is given a address of external stub, which is left to runtime.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub text_base: u64,
+    pub text: Vec<u32>,
+    pub data_base: u64,
+    pub data: Vec<u8>,
+    /// Address of functions and data objects in module.
+    pub symbols: HashMap<String, u64>,
+    /// Signature of functions which are defined in module.
+    pub signatures: HashMap<String, FunctionSignature>,
+    /// Address of external stubs to symbol.
+    pub externals: HashMap<u64, String>,
+}
+
+impl Program {
+    /// First address after text, data and external stubs.
+    pub fn end_address(&self) -> u64 {
+        let data_end = self.data_base + self.data.len() as u64;
+        self.externals
+            .keys()
+            .map(|address| address + 4)
+            .max()
+            .unwrap_or(0)
+            .max(data_end)
+    }
+}
+
+fn align_to(size: u64, align: u64) -> u64 {
+    size.div_ceil(align) * align
+}
+
+/// Function lowered to machine instructions, ready to assemble.
+pub struct LoweredFunction<'a> {
+    pub name: &'a str,
+    pub signature: FunctionSignature,
+    pub insts: Vec<RvInst>,
+}
+
+/// Assemble lowered functions and data objects of module to program. Function are
+/// placed from `TEXT_BASE` in order, data objects follow text in order of id, and then
+/// stubs of external symbols.
+pub fn assemble(module: &Module, functions: &[LoweredFunction]) -> Result<Program, CodegenError> {
+    let mut symbols = HashMap::new();
+    let mut labels = Vec::with_capacity(functions.len());
+    let mut address = TEXT_BASE;
+    for function in functions {
+        symbols.insert(function.name.to_owned(), address);
+        let mut function_labels = HashMap::new();
+        for inst in &function.insts {
+            if let RvInst::Label(label) = inst {
+                function_labels.insert(label.as_str(), address);
+            }
+            address += inst.size();
+        }
+        labels.push(function_labels);
+    }
+    let text_end = address;
+    let data_base = align_to(text_end, 16);
+    let mut data_ids = module.data_objects.keys().copied().collect::<Vec<_>>();
+    data_ids.sort();
+    let mut data_layout = Vec::new();
+    let mut address = data_base;
+    for data_id in data_ids {
+        let Some(name) = module.get_symbol_by_module_id(ModuleLevelId::Data(data_id)) else {
+            continue;
+        };
+        let data = &module.data_objects[&data_id];
+        address = align_to(address, data.align.max(1));
+        symbols.insert(name.to_owned(), address);
+        data_layout.push((name, data, address));
+        address += data.size;
+    }
+    // symbol which is used by instruction or relocation but not defined get a stub.
+    let mut externals = HashMap::new();
+    let mut address = align_to(address, 4);
+    let referenced = functions
+        .iter()
+        .flat_map(|function| function.insts.iter())
+        .filter_map(|inst| match inst {
+            RvInst::La { symbol, .. } | RvInst::Call { symbol } => Some(symbol.as_str()),
+            _ => None,
+        })
+        .chain(data_layout.iter().flat_map(|(_, data, _)| {
+            data.relocations
+                .iter()
+                .filter_map(|relocation| get_symbol_of_external_name(module, &relocation.name))
+        }))
+        .collect::<Vec<_>>();
+    for symbol in referenced {
+        if !symbols.contains_key(symbol) {
+            symbols.insert(symbol.to_owned(), address);
+            externals.insert(address, symbol.to_owned());
+            address += 4;
+        }
+    }
+    // data image with relocations.
+    let data_end = data_layout
+        .last()
+        .map_or(data_base, |(_, data, address)| address + data.size);
+    let mut data_image = vec![0_u8; (data_end - data_base) as usize];
+    for (name, data, address) in &data_layout {
+        let start = (address - data_base) as usize;
+        if let DataInit::Bytes(bytes) = &data.init {
+            let len = bytes.len().min(data.size as usize);
+            data_image[start..start + len].copy_from_slice(&bytes[..len]);
+        }
+        for relocation in &data.relocations {
+            let target = get_symbol_of_external_name(module, &relocation.name)
+                .map(|symbol| symbols[symbol])
+                .ok_or_else(|| CodegenError {
+                    func: name.to_string(),
+                    inst: None,
+                    kind: CodegenErrorKind::UnresolvedSymbol(relocation.name.clone()),
+                })?;
+            let offset = start + relocation.offset as usize;
+            let pointer = target.wrapping_add(relocation.addend as u64).to_le_bytes();
+            data_image[offset..offset + DATA_POINTER_SIZE as usize].copy_from_slice(&pointer);
+        }
+    }
+    // encode instructions.
+    let mut text = Vec::with_capacity(((text_end - TEXT_BASE) / 4) as usize);
+    let mut pc = TEXT_BASE;
+    for (function, function_labels) in functions.iter().zip(&labels) {
+        let encoder = Encoder {
+            labels: function_labels,
+            symbols: &symbols,
+        };
+        for inst in &function.insts {
+            let words = encoder.encode(inst, pc).map_err(|kind| CodegenError {
+                func: function.name.to_owned(),
+                inst: None,
+                kind,
+            })?;
+            pc += 4 * words.len() as u64;
+            text.extend(words);
+        }
+    }
+    Ok(Program {
+        text_base: TEXT_BASE,
+        text,
+        data_base,
+        data: data_image,
+        symbols,
+        signatures: functions
+            .iter()
+            .map(|function| (function.name.to_owned(), function.signature.clone()))
+            .collect(),
+        externals,
+    })
+}
+
+type EncodeResult<T> = Result<T, CodegenErrorKind>;
+
+fn check_range(imm: i64, bits: u32) -> EncodeResult<()> {
+    let bound = 1_i64 << (bits - 1);
+    if imm < -bound || imm >= bound {
+        return Err(CodegenErrorKind::ImmediateOutOfRange(imm));
+    }
+    Ok(())
+}
+
+fn r_type(funct7: u32, rs2: u32, rs1: u32, funct3: u32, rd: u32, opcode: u32) -> u32 {
+    funct7 << 25 | rs2 << 20 | rs1 << 15 | funct3 << 12 | rd << 7 | opcode
+}
+
+fn i_type(imm: i64, rs1: u32, funct3: u32, rd: u32, opcode: u32) -> EncodeResult<u32> {
+    check_range(imm, 12)?;
+    Ok(((imm as u32) & 0xfff) << 20 | rs1 << 15 | funct3 << 12 | rd << 7 | opcode)
+}
+
+fn s_type(imm: i64, rs2: u32, rs1: u32, funct3: u32, opcode: u32) -> EncodeResult<u32> {
+    check_range(imm, 12)?;
+    let imm = imm as u32;
+    Ok((imm >> 5 & 0x7f) << 25 | rs2 << 20 | rs1 << 15 | funct3 << 12 | (imm & 0x1f) << 7 | opcode)
+}
+
+fn b_type(offset: i64, rs2: u32, rs1: u32, funct3: u32) -> EncodeResult<u32> {
+    check_range(offset, 13)?;
+    let imm = offset as u32;
+    Ok((imm >> 12 & 1) << 31
+        | (imm >> 5 & 0x3f) << 25
+        | rs2 << 20
+        | rs1 << 15
+        | funct3 << 12
+        | (imm >> 1 & 0xf) << 8
+        | (imm >> 11 & 1) << 7
+        | 0x63)
+}
+
+fn j_type(offset: i64, rd: u32) -> EncodeResult<u32> {
+    check_range(offset, 21)?;
+    let imm = offset as u32;
+    Ok((imm >> 20 & 1) << 31
+        | (imm >> 1 & 0x3ff) << 21
+        | (imm >> 11 & 1) << 20
+        | (imm >> 12 & 0xff) << 12
+        | rd << 7
+        | 0x6f)
+}
+
+fn u_type(imm: u32, rd: u32, opcode: u32) -> u32 {
+    (imm & 0xfffff) << 12 | rd << 7 | opcode
+}
+
+/// Split pc relative offset to `auipc` high part and 12-bit low part.
+fn split_pc_relative(offset: i64) -> EncodeResult<(u32, i64)> {
+    let hi = (offset + 0x800) >> 12;
+    check_range(hi, 20)?;
+    Ok((hi as u32 & 0xfffff, offset - (hi << 12)))
+}
+
+fn fmt_bits(fmt: FloatFmt) -> u32 {
+    match fmt {
+        FloatFmt::S => 0,
+        FloatFmt::D => 1,
+    }
+}
+
+fn int_fmt_bits(int: IntFmt) -> u32 {
+    match int {
+        IntFmt::W => 0,
+        IntFmt::Wu => 1,
+        IntFmt::L => 2,
+        IntFmt::Lu => 3,
+    }
+}
+
+/// Float instruction in `OP-FP` major opcode.
+fn fp_type(funct5: u32, fmt: FloatFmt, rs2: u32, rs1: u32, rm: u32, rd: u32) -> u32 {
+    r_type(funct5 << 2 | fmt_bits(fmt), rs2, rs1, rm, rd, 0x53)
+}
+
+struct Encoder<'a> {
+    labels: &'a HashMap<&'a str, u64>,
+    symbols: &'a HashMap<String, u64>,
+}
+
+impl Encoder<'_> {
+    fn label_offset(&self, label: &str, pc: u64) -> EncodeResult<i64> {
+        let address = self
+            .labels
+            .get(label)
+            .ok_or_else(|| CodegenErrorKind::UnknownLabel(label.to_owned()))?;
+        Ok(address.wrapping_sub(pc) as i64)
+    }
+    fn symbol_offset(&self, symbol: &str, pc: u64) -> EncodeResult<i64> {
+        let address = self
+            .symbols
+            .get(symbol)
+            .ok_or_else(|| CodegenErrorKind::UnknownLabel(symbol.to_owned()))?;
+        Ok(address.wrapping_sub(pc) as i64)
+    }
+    /// Encode instruction at address `pc` to machine words.
+    fn encode(&self, inst: &RvInst, pc: u64) -> EncodeResult<Vec<u32>> {
+        let word = match inst {
+            RvInst::Alu { op, rd, rs1, rs2 } => {
+                let (funct7, funct3) = match op {
+                    AluOp::Add => (0x00, 0),
+                    AluOp::Sub => (0x20, 0),
+                    AluOp::Sll => (0x00, 1),
+                    AluOp::Slt => (0x00, 2),
+                    AluOp::Sltu => (0x00, 3),
+                    AluOp::Xor => (0x00, 4),
+                    AluOp::Srl => (0x00, 5),
+                    AluOp::Sra => (0x20, 5),
+                    AluOp::Or => (0x00, 6),
+                    AluOp::And => (0x00, 7),
+                    AluOp::Mul => (0x01, 0),
+                    AluOp::Div => (0x01, 4),
+                    AluOp::Divu => (0x01, 5),
+                    AluOp::Rem => (0x01, 6),
+                    AluOp::Remu => (0x01, 7),
+                };
+                r_type(funct7, reg(*rs2), reg(*rs1), funct3, reg(*rd), 0x33)
+            }
+            RvInst::AluImm { op, rd, rs1, imm } => {
+                let imm = *imm as i64;
+                let (rd, rs1) = (reg(*rd), reg(*rs1));
+                match op {
+                    AluImmOp::Slli | AluImmOp::Srli | AluImmOp::Srai => {
+                        if !(0..64).contains(&imm) {
+                            return Err(CodegenErrorKind::ImmediateOutOfRange(imm));
+                        }
+                        let (high, funct3) = match op {
+                            AluImmOp::Slli => (0x00, 1),
+                            AluImmOp::Srli => (0x00, 5),
+                            _ => (0x10, 5),
+                        };
+                        (high << 26 | (imm as u32) << 20) | rs1 << 15 | funct3 << 12 | rd << 7 | 0x13
+                    }
+                    AluImmOp::Addiw => i_type(imm, rs1, 0, rd, 0x1b)?,
+                    _ => {
+                        let funct3 = match op {
+                            AluImmOp::Addi => 0,
+                            AluImmOp::Slti => 2,
+                            AluImmOp::Sltiu => 3,
+                            AluImmOp::Xori => 4,
+                            AluImmOp::Ori => 6,
+                            _ => 7,
+                        };
+                        i_type(imm, rs1, funct3, rd, 0x13)?
+                    }
+                }
+            }
+            RvInst::Lui { rd, imm } => u_type(*imm, reg(*rd), 0x37),
+            RvInst::Load { op, rd, base, offset } => {
+                let funct3 = match op {
+                    LoadOp::Lb => 0,
+                    LoadOp::Lh => 1,
+                    LoadOp::Lw => 2,
+                    LoadOp::Ld => 3,
+                    LoadOp::Lbu => 4,
+                    LoadOp::Lhu => 5,
+                    LoadOp::Lwu => 6,
+                };
+                i_type(*offset as i64, reg(*base), funct3, reg(*rd), 0x03)?
+            }
+            RvInst::Store { op, src, base, offset } => {
+                let funct3 = match op {
+                    StoreOp::Sb => 0,
+                    StoreOp::Sh => 1,
+                    StoreOp::Sw => 2,
+                    StoreOp::Sd => 3,
+                };
+                s_type(*offset as i64, reg(*src), reg(*base), funct3, 0x23)?
+            }
+            RvInst::Branch { op, rs1, rs2, target } => {
+                let funct3 = match op {
+                    BranchOp::Beq => 0,
+                    BranchOp::Bne => 1,
+                    BranchOp::Blt => 4,
+                    BranchOp::Bge => 5,
+                    BranchOp::Bltu => 6,
+                    BranchOp::Bgeu => 7,
+                };
+                b_type(self.label_offset(target, pc)?, reg(*rs2), reg(*rs1), funct3)?
+            }
+            RvInst::Jal { rd, target } => j_type(self.label_offset(target, pc)?, reg(*rd))?,
+            RvInst::Jalr { rd, rs1, offset } => i_type(*offset as i64, reg(*rs1), 0, reg(*rd), 0x67)?,
+            RvInst::FArith { op, fmt, rd, rs1, rs2 } => {
+                let funct5 = match op {
+                    FArithOp::Add => 0x00,
+                    FArithOp::Sub => 0x01,
+                    FArithOp::Mul => 0x02,
+                    FArithOp::Div => 0x03,
+                };
+                fp_type(funct5, *fmt, rs2.0 as u32, rs1.0 as u32, RM_DYN, rd.0 as u32)
+            }
+            RvInst::FNeg { fmt, rd, rs } => fp_type(0x04, *fmt, rs.0 as u32, rs.0 as u32, 1, rd.0 as u32),
+            RvInst::FCmp { op, fmt, rd, rs1, rs2 } => {
+                let rm = match op {
+                    FCmpOp::Le => 0,
+                    FCmpOp::Lt => 1,
+                    FCmpOp::Eq => 2,
+                };
+                fp_type(0x14, *fmt, rs2.0 as u32, rs1.0 as u32, rm, reg(*rd))
+            }
+            RvInst::FMvToInt { fmt, rd, rs } => fp_type(0x1c, *fmt, 0, rs.0 as u32, 0, reg(*rd)),
+            RvInst::FMvFromInt { fmt, rd, rs } => fp_type(0x1e, *fmt, 0, reg(*rs), 0, rd.0 as u32),
+            RvInst::FCvtToInt { fmt, int, rd, rs } => {
+                fp_type(0x18, *fmt, int_fmt_bits(*int), rs.0 as u32, RM_RTZ, reg(*rd))
+            }
+            RvInst::FCvtFromInt { fmt, int, rd, rs } => {
+                fp_type(0x1a, *fmt, int_fmt_bits(*int), reg(*rs), RM_DYN, rd.0 as u32)
+            }
+            RvInst::FCvtFloat { fmt, rd, rs } => {
+                // `rs2` is format of source.
+                let src_fmt = if *fmt == FloatFmt::S { FloatFmt::D } else { FloatFmt::S };
+                fp_type(0x08, *fmt, fmt_bits(src_fmt), rs.0 as u32, RM_DYN, rd.0 as u32)
+            }
+            RvInst::La { rd, symbol } => {
+                let (hi, lo) = split_pc_relative(self.symbol_offset(symbol, pc)?)?;
+                let rd = reg(*rd);
+                return Ok(vec![u_type(hi, rd, 0x17), i_type(lo, rd, 0, rd, 0x13)?]);
+            }
+            RvInst::Call { symbol } => {
+                let (hi, lo) = split_pc_relative(self.symbol_offset(symbol, pc)?)?;
+                let ra = reg(XReg::RA);
+                return Ok(vec![u_type(hi, ra, 0x17), i_type(lo, ra, 0, ra, 0x67)?]);
+            }
+            RvInst::Label(_) | RvInst::Comment(_) => return Ok(Vec::new()),
+        };
+        Ok(vec![word])
+    }
+}
+
+fn reg(reg: XReg) -> u32 {
+    reg.0 as u32
+}
@@ -0,0 +1,132 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::codegen::regalloc::{allocate_registers, Assignment};
+use crate::codegen::riscv64::abi::STACK_ALIGN;
+use crate::codegen::riscv64::register::{FReg, Riscv64Registers, XReg, ALLOCATABLE_FREGS, ALLOCATABLE_REGS};
+use crate::codegen::{get_frame_values, get_stack_allocs, CodegenErrorKind};
+use crate::entities::function::Function;
+use crate::entities::instruction::Instruction;
+use crate::entities::value::Value;
+
+/// Bytes of `ra` and `s0` saved on top of frame.
+const LINKAGE_SIZE: u64 = 16;
+
+/// Home of a value, value is kept as 64-bit bits in its home for whole function.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Location {
+    Reg(XReg),
+    /// Float value is kept as raw bits in float register.
+    FReg(FReg),
+    /// Offset to frame pointer `s0`.
+    Stack(i32),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Reg(reg) => write!(f, "{}", reg),
+            Location::FReg(reg) => write!(f, "{}", reg),
+            Location::Stack(offset) => write!(f, "{}(s0)", offset),
+        }
+    }
+}
+
+/// ## Frame
+/// Stack frame of function, `s0` point to stack pointer before call, from `s0` to lower
+/// address:
+/// - `ra` and old `s0`.
+/// - callee saved registers and float registers which are used as home of values.
+/// - spill slots of values which do not get a register.
+/// - slots of `stackalloc` instructions.
+#[derive(Debug)]
+pub struct Frame {
+    pub locations: HashMap<Value, Location>,
+    pub stack_slots: HashMap<Instruction, i32>,
+    /// Callee saved registers with their offset to `s0`.
+    pub saved_regs: Vec<(XReg, i32)>,
+    /// Callee saved float registers with their offset to `s0`.
+    pub saved_fregs: Vec<(FReg, i32)>,
+    /// Bytes subtract from `sp` in prologue, 16 bytes aligned.
+    pub frame_size: u64,
+}
+
+fn align_to(size: u64, align: u64) -> u64 {
+    size.div_ceil(align) * align
+}
+
+/// Get offset to `s0` of slot which end at `cursor` bytes below `s0`.
+fn to_offset(cursor: u64) -> Result<i32, CodegenErrorKind> {
+    i32::try_from(cursor)
+        .map(|cursor| -cursor)
+        .map_err(|_| CodegenErrorKind::FrameTooLarge)
+}
+
+/// Allocate home of values and slot of `stackalloc`. Home of values is given by linear
+/// scan, so a callee saved register or a spill slot is shared by values whose live
+/// intervals do not overlap.
+pub fn allocate_frame(function: &Function) -> Result<Frame, CodegenErrorKind> {
+    let allocation = allocate_registers(function, &Riscv64Registers);
+    let mut locations = HashMap::new();
+    let (mut used_regs, mut used_fregs) = (HashSet::new(), HashSet::new());
+    for (value, assignment) in allocation.get_assignments() {
+        if let Assignment::Register(name) = *assignment {
+            if let Some(reg) = ALLOCATABLE_REGS.into_iter().find(|reg| reg.name() == name) {
+                locations.insert(*value, Location::Reg(reg));
+                used_regs.insert(reg);
+            } else {
+                let reg = ALLOCATABLE_FREGS.into_iter().find(|reg| reg.name() == name).unwrap();
+                locations.insert(*value, Location::FReg(reg));
+                used_fregs.insert(reg);
+            }
+        }
+    }
+    let mut cursor = LINKAGE_SIZE;
+    let mut saved_regs = Vec::new();
+    for reg in ALLOCATABLE_REGS.into_iter().filter(|reg| used_regs.contains(reg)) {
+        cursor += 8;
+        saved_regs.push((reg, to_offset(cursor)?));
+    }
+    let mut saved_fregs = Vec::new();
+    for reg in ALLOCATABLE_FREGS.into_iter().filter(|reg| used_fregs.contains(reg)) {
+        cursor += 8;
+        saved_fregs.push((reg, to_offset(cursor)?));
+    }
+    let spill_base = cursor;
+    for (value, assignment) in allocation.get_assignments() {
+        if let Assignment::Spill(slot) = assignment {
+            locations.insert(*value, Location::Stack(to_offset(spill_base + 8 * (*slot as u64 + 1))?));
+        }
+    }
+    cursor += 8 * allocation.get_spill_slot_count() as u64;
+    // value in unreachable block has no live interval, it still needs a home.
+    for value in get_frame_values(function) {
+        if let Entry::Vacant(entry) = locations.entry(value) {
+            cursor += 8;
+            entry.insert(Location::Stack(to_offset(cursor)?));
+        }
+    }
+    let mut stack_slots = HashMap::new();
+    for (inst, size, align) in get_stack_allocs(function) {
+        if align > STACK_ALIGN {
+            return Err(CodegenErrorKind::UnsupportedAlignment(align));
+        }
+        cursor = cursor
+            .checked_add(size)
+            .and_then(|end| end.checked_next_multiple_of(align))
+            .ok_or(CodegenErrorKind::FrameTooLarge)?;
+        stack_slots.insert(inst, to_offset(cursor)?);
+    }
+    let frame_size = align_to(cursor, STACK_ALIGN);
+    if i32::try_from(frame_size).is_err() {
+        return Err(CodegenErrorKind::FrameTooLarge);
+    }
+    Ok(Frame {
+        locations,
+        stack_slots,
+        saved_regs,
+        saved_fregs,
+        frame_size,
+    })
+}
@@ -0,0 +1,386 @@
+use std::fmt;
+
+use crate::codegen::riscv64::register::{FReg, XReg};
+
+/// Register-register integer operation of I and M extension.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AluOp {
+    Add,
+    Sub,
+    Sll,
+    Slt,
+    Sltu,
+    Xor,
+    Srl,
+    Sra,
+    Or,
+    And,
+    Mul,
+    Div,
+    Divu,
+    Rem,
+    Remu,
+}
+
+/// Register-immediate integer operation, immediate is 12-bit signed (6-bit for shift).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AluImmOp {
+    Addi,
+    Slti,
+    Sltiu,
+    Xori,
+    Ori,
+    Andi,
+    Slli,
+    Srli,
+    Srai,
+    Addiw,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LoadOp {
+    Lb,
+    Lh,
+    Lw,
+    Ld,
+    Lbu,
+    Lhu,
+    Lwu,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StoreOp {
+    Sb,
+    Sh,
+    Sw,
+    Sd,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BranchOp {
+    Beq,
+    Bne,
+    Blt,
+    Bge,
+    Bltu,
+    Bgeu,
+}
+
+/// Format of float operand, `S` for single and `D` for double precision.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FloatFmt {
+    S,
+    D,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FCmpOp {
+    Eq,
+    Lt,
+    Le,
+}
+
+/// Format of integer operand in float conversion.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IntFmt {
+    W,
+    Wu,
+    L,
+    Lu,
+}
+
+/// ## RV64 Instruction
+/// Machine instruction selected from IR, printed as GNU assembly and encoded to machine
+/// words by the same data, so text and encoding never disagree. Branch and jump refer to
+/// label in same function, `La` and `Call` refer to symbol in module and take two words.
+#[derive(Debug, PartialEq, Clone)]
+pub enum RvInst {
+    Alu {
+        op: AluOp,
+        rd: XReg,
+        rs1: XReg,
+        rs2: XReg,
+    },
+    AluImm {
+        op: AluImmOp,
+        rd: XReg,
+        rs1: XReg,
+        imm: i32,
+    },
+    /// Load 20-bit immediate to upper bits, sign extended to 64-bit.
+    Lui {
+        rd: XReg,
+        imm: u32,
+    },
+    Load {
+        op: LoadOp,
+        rd: XReg,
+        base: XReg,
+        offset: i32,
+    },
+    Store {
+        op: StoreOp,
+        src: XReg,
+        base: XReg,
+        offset: i32,
+    },
+    Branch {
+        op: BranchOp,
+        rs1: XReg,
+        rs2: XReg,
+        target: String,
+    },
+    Jal {
+        rd: XReg,
+        target: String,
+    },
+    Jalr {
+        rd: XReg,
+        rs1: XReg,
+        offset: i32,
+    },
+    FArith {
+        op: FArithOp,
+        fmt: FloatFmt,
+        rd: FReg,
+        rs1: FReg,
+        rs2: FReg,
+    },
+    /// `fsgnjn rd, rs, rs`, flip sign bit.
+    FNeg {
+        fmt: FloatFmt,
+        rd: FReg,
+        rs: FReg,
+    },
+    FCmp {
+        op: FCmpOp,
+        fmt: FloatFmt,
+        rd: XReg,
+        rs1: FReg,
+        rs2: FReg,
+    },
+    /// Move raw bits of float register to integer register, `S` is sign extended.
+    FMvToInt {
+        fmt: FloatFmt,
+        rd: XReg,
+        rs: FReg,
+    },
+    /// Move raw bits of integer register to float register.
+    FMvFromInt {
+        fmt: FloatFmt,
+        rd: FReg,
+        rs: XReg,
+    },
+    /// Convert float to integer, round toward zero.
+    FCvtToInt {
+        fmt: FloatFmt,
+        int: IntFmt,
+        rd: XReg,
+        rs: FReg,
+    },
+    /// Convert integer to float, round by dynamic rounding mode.
+    FCvtFromInt {
+        fmt: FloatFmt,
+        int: IntFmt,
+        rd: FReg,
+        rs: XReg,
+    },
+    /// Convert float to other float format `fmt`.
+    FCvtFloat {
+        fmt: FloatFmt,
+        rd: FReg,
+        rs: FReg,
+    },
+    /// Load address of symbol, `auipc` + `addi`.
+    La {
+        rd: XReg,
+        symbol: String,
+    },
+    /// Call symbol and link to `ra`, `auipc` + `jalr`.
+    Call {
+        symbol: String,
+    },
+    Label(String),
+    Comment(String),
+}
+
+impl RvInst {
+    /// Size in bytes of instruction after encoding.
+    pub fn size(&self) -> u64 {
+        match self {
+            RvInst::La { .. } | RvInst::Call { .. } => 8,
+            RvInst::Label(_) | RvInst::Comment(_) => 0,
+            _ => 4,
+        }
+    }
+}
+
+impl AluOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AluOp::Add => "add",
+            AluOp::Sub => "sub",
+            AluOp::Sll => "sll",
+            AluOp::Slt => "slt",
+            AluOp::Sltu => "sltu",
+            AluOp::Xor => "xor",
+            AluOp::Srl => "srl",
+            AluOp::Sra => "sra",
+            AluOp::Or => "or",
+            AluOp::And => "and",
+            AluOp::Mul => "mul",
+            AluOp::Div => "div",
+            AluOp::Divu => "divu",
+            AluOp::Rem => "rem",
+            AluOp::Remu => "remu",
+        }
+    }
+}
+
+impl AluImmOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AluImmOp::Addi => "addi",
+            AluImmOp::Slti => "slti",
+            AluImmOp::Sltiu => "sltiu",
+            AluImmOp::Xori => "xori",
+            AluImmOp::Ori => "ori",
+            AluImmOp::Andi => "andi",
+            AluImmOp::Slli => "slli",
+            AluImmOp::Srli => "srli",
+            AluImmOp::Srai => "srai",
+            AluImmOp::Addiw => "addiw",
+        }
+    }
+}
+
+impl LoadOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LoadOp::Lb => "lb",
+            LoadOp::Lh => "lh",
+            LoadOp::Lw => "lw",
+            LoadOp::Ld => "ld",
+            LoadOp::Lbu => "lbu",
+            LoadOp::Lhu => "lhu",
+            LoadOp::Lwu => "lwu",
+        }
+    }
+}
+
+impl StoreOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StoreOp::Sb => "sb",
+            StoreOp::Sh => "sh",
+            StoreOp::Sw => "sw",
+            StoreOp::Sd => "sd",
+        }
+    }
+}
+
+impl BranchOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BranchOp::Beq => "beq",
+            BranchOp::Bne => "bne",
+            BranchOp::Blt => "blt",
+            BranchOp::Bge => "bge",
+            BranchOp::Bltu => "bltu",
+            BranchOp::Bgeu => "bgeu",
+        }
+    }
+}
+
+impl FloatFmt {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FloatFmt::S => "s",
+            FloatFmt::D => "d",
+        }
+    }
+}
+
+impl FArithOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FArithOp::Add => "fadd",
+            FArithOp::Sub => "fsub",
+            FArithOp::Mul => "fmul",
+            FArithOp::Div => "fdiv",
+        }
+    }
+}
+
+impl FCmpOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FCmpOp::Eq => "feq",
+            FCmpOp::Lt => "flt",
+            FCmpOp::Le => "fle",
+        }
+    }
+}
+
+impl IntFmt {
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntFmt::W => "w",
+            IntFmt::Wu => "wu",
+            IntFmt::L => "l",
+            IntFmt::Lu => "lu",
+        }
+    }
+}
+
+impl fmt::Display for RvInst {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RvInst::Alu { op, rd, rs1, rs2 } => write!(f, "    {} {}, {}, {}", op.name(), rd, rs1, rs2),
+            RvInst::AluImm { op, rd, rs1, imm } => write!(f, "    {} {}, {}, {}", op.name(), rd, rs1, imm),
+            RvInst::Lui { rd, imm } => write!(f, "    lui {}, 0x{:x}", rd, imm),
+            RvInst::Load { op, rd, base, offset } => write!(f, "    {} {}, {}({})", op.name(), rd, offset, base),
+            RvInst::Store { op, src, base, offset } => write!(f, "    {} {}, {}({})", op.name(), src, offset, base),
+            RvInst::Branch { op, rs1, rs2, target } => write!(f, "    {} {}, {}, {}", op.name(), rs1, rs2, target),
+            RvInst::Jal { rd, target } => write!(f, "    jal {}, {}", rd, target),
+            RvInst::Jalr { rd, rs1, offset } => write!(f, "    jalr {}, {}({})", rd, offset, rs1),
+            RvInst::FArith { op, fmt, rd, rs1, rs2 } => {
+                write!(f, "    {}.{} {}, {}, {}", op.name(), fmt.name(), rd, rs1, rs2)
+            }
+            RvInst::FNeg { fmt, rd, rs } => write!(f, "    fsgnjn.{} {}, {}, {}", fmt.name(), rd, rs, rs),
+            RvInst::FCmp { op, fmt, rd, rs1, rs2 } => {
+                write!(f, "    {}.{} {}, {}, {}", op.name(), fmt.name(), rd, rs1, rs2)
+            }
+            RvInst::FMvToInt { fmt, rd, rs } => {
+                let fmt = if *fmt == FloatFmt::S { "w" } else { "d" };
+                write!(f, "    fmv.x.{} {}, {}", fmt, rd, rs)
+            }
+            RvInst::FMvFromInt { fmt, rd, rs } => {
+                let fmt = if *fmt == FloatFmt::S { "w" } else { "d" };
+                write!(f, "    fmv.{}.x {}, {}", fmt, rd, rs)
+            }
+            RvInst::FCvtToInt { fmt, int, rd, rs } => {
+                write!(f, "    fcvt.{}.{} {}, {}, rtz", int.name(), fmt.name(), rd, rs)
+            }
+            RvInst::FCvtFromInt { fmt, int, rd, rs } => {
+                write!(f, "    fcvt.{}.{} {}, {}", fmt.name(), int.name(), rd, rs)
+            }
+            RvInst::FCvtFloat { fmt, rd, rs } => {
+                let src_fmt = if *fmt == FloatFmt::S { "d" } else { "s" };
+                write!(f, "    fcvt.{}.{} {}, {}", fmt.name(), src_fmt, rd, rs)
+            }
+            RvInst::La { rd, symbol } => write!(f, "    lla {}, {}", rd, symbol),
+            RvInst::Call { symbol } => write!(f, "    call {}", symbol),
+            RvInst::Label(label) => write!(f, "{}:", label),
+            RvInst::Comment(comment) => write!(f, "    #{}", comment),
+        }
+    }
+}
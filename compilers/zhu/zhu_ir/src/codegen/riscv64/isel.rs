@@ -0,0 +1,812 @@
+use crate::codegen::riscv64::abi::{classify_args, ArgLocation, STACK_ALIGN};
+use crate::codegen::riscv64::frame::{allocate_frame, Frame, Location};
+use crate::codegen::riscv64::inst::{
+    AluImmOp, AluOp, BranchOp, FArithOp, FCmpOp, FloatFmt, IntFmt, LoadOp, RvInst, StoreOp,
+};
+use crate::codegen::riscv64::register::{FReg, XReg};
+use crate::codegen::{
+    constant_bits, get_symbol_of_external_name, int_range, is_float, is_signed, normalize_bits, CodegenError,
+    CodegenErrorKind,
+};
+use crate::entities::block::Block;
+use crate::entities::function::{Function, FunctionRef};
+use crate::entities::global_value::{GlobalValue, GlobalValueData};
+use crate::entities::instruction::opcode::{CmpFlag, OpCode};
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::module::Module;
+use crate::entities::r#type::ValueType;
+use crate::entities::value::Value;
+
+type LowerResult = Result<(), CodegenErrorKind>;
+
+/// Is immediate fit in 12-bit signed field of I-type and S-type instruction.
+pub fn fit_in_imm12(imm: i64) -> bool {
+    (-2048..=2047).contains(&imm)
+}
+
+fn float_fmt(ty: &ValueType) -> FloatFmt {
+    if *ty == ValueType::F32 {
+        FloatFmt::S
+    } else {
+        FloatFmt::D
+    }
+}
+
+fn load_op(ty: &ValueType) -> LoadOp {
+    match ty {
+        ValueType::U8 => LoadOp::Lbu,
+        ValueType::U16 => LoadOp::Lhu,
+        ValueType::U32 | ValueType::F32 => LoadOp::Lwu,
+        ValueType::I16 => LoadOp::Lh,
+        ValueType::I32 => LoadOp::Lw,
+        ValueType::U64 | ValueType::I64 | ValueType::F64 | ValueType::Mem(_) => LoadOp::Ld,
+    }
+}
+
+fn store_op(ty: &ValueType) -> StoreOp {
+    match ty.get_size() {
+        1 => StoreOp::Sb,
+        2 => StoreOp::Sh,
+        4 => StoreOp::Sw,
+        _ => StoreOp::Sd,
+    }
+}
+
+/// ## Function Lowering
+/// Select RV64IMFD instructions for each IR instruction of function. Like x86-64 backend,
+/// every value is kept as 64-bit bits in its home (see `Frame`), narrow integers are zero
+/// or sign extended and floats are raw bits, instructions are computed in scratch registers
+/// `t0` - `t2` and `ft0` - `ft1`, `t3` is reserved to materialize large offset.
+pub struct FunctionLowering<'a> {
+    module: &'a Module,
+    function: &'a Function,
+    name: &'a str,
+    frame: Frame,
+    insts: Vec<RvInst>,
+    label_count: usize,
+}
+
+impl<'a> FunctionLowering<'a> {
+    pub fn new(module: &'a Module, name: &'a str, function: &'a Function) -> Result<Self, CodegenError> {
+        let frame = allocate_frame(function).map_err(|kind| CodegenError {
+            func: name.to_owned(),
+            inst: None,
+            kind,
+        })?;
+        Ok(Self {
+            module,
+            function,
+            name,
+            frame,
+            insts: Vec::new(),
+            label_count: 0,
+        })
+    }
+    /// Lower function to machine instructions, from prologue to epilogue.
+    pub fn lower(mut self) -> Result<Vec<RvInst>, CodegenError> {
+        let error = |inst, kind| CodegenError {
+            func: self.name.to_owned(),
+            inst,
+            kind,
+        };
+        self.lower_prologue();
+        let mut cur_block = self.function.layout.first_block;
+        while let Some(block) = cur_block {
+            let label = self.block_label(block);
+            self.push(RvInst::Label(label));
+            for inst in self.function.get_insts_of_block(block) {
+                self.lower_inst(block, inst).map_err(|kind| error(Some(inst), kind))?;
+            }
+            cur_block = self.function.layout.blocks[&block].next;
+        }
+        self.lower_epilogue();
+        Ok(self.insts)
+    }
+}
+
+/// Helpers to build instructions.
+impl FunctionLowering<'_> {
+    fn push(&mut self, inst: RvInst) {
+        self.insts.push(inst);
+    }
+    fn block_label(&self, block: Block) -> String {
+        format!(".L{}_block{}", self.name, block.0)
+    }
+    fn ret_label(&self) -> String {
+        format!(".L{}_ret", self.name)
+    }
+    fn new_label(&mut self) -> String {
+        self.label_count += 1;
+        format!(".L{}_{}", self.name, self.label_count)
+    }
+    fn value_type(&self, value: Value) -> ValueType {
+        self.function.value_type(value).clone()
+    }
+    fn result_of(&self, inst: Instruction) -> Result<Value, CodegenErrorKind> {
+        self.function
+            .get_inst_result(inst)
+            .ok_or(CodegenErrorKind::MissingResult)
+    }
+    fn alu(&mut self, op: AluOp, rd: XReg, rs1: XReg, rs2: XReg) {
+        self.push(RvInst::Alu { op, rd, rs1, rs2 });
+    }
+    fn alu_imm(&mut self, op: AluImmOp, rd: XReg, rs1: XReg, imm: i32) {
+        self.push(RvInst::AluImm { op, rd, rs1, imm });
+    }
+    fn mv(&mut self, rd: XReg, rs: XReg) {
+        if rd != rs {
+            self.alu_imm(AluImmOp::Addi, rd, rs, 0);
+        }
+    }
+    /// Materialize 64-bit immediate, immediate out of 32-bit is built from its high
+    /// bits by shift and add.
+    fn li(&mut self, rd: XReg, imm: i64) {
+        if let Ok(imm32) = i32::try_from(imm) {
+            let lo = (imm32 << 20) >> 20;
+            let hi = ((imm32 as i64 - lo as i64) >> 12) as u32 & 0xfffff;
+            if hi == 0 {
+                self.alu_imm(AluImmOp::Addi, rd, XReg::ZERO, lo);
+            } else {
+                self.push(RvInst::Lui { rd, imm: hi });
+                if lo != 0 {
+                    // `addiw` wrap result in 32-bit, so `lui` overflow to sign bit is fine.
+                    self.alu_imm(AluImmOp::Addiw, rd, rd, lo);
+                }
+            }
+            return;
+        }
+        let lo = (imm << 52) >> 52;
+        let hi = imm.wrapping_sub(lo) >> 12;
+        let zeros = hi.trailing_zeros();
+        self.li(rd, hi >> zeros);
+        self.alu_imm(AluImmOp::Slli, rd, rd, 12 + zeros as i32);
+        if lo != 0 {
+            self.alu_imm(AluImmOp::Addi, rd, rd, lo as i32);
+        }
+    }
+    /// `rd = rs + imm`, large immediate is materialized in `t3`.
+    fn add_imm(&mut self, rd: XReg, rs: XReg, imm: i64) {
+        if fit_in_imm12(imm) {
+            if imm != 0 || rd != rs {
+                self.alu_imm(AluImmOp::Addi, rd, rs, imm as i32);
+            }
+        } else {
+            self.li(XReg::T3, imm);
+            self.alu(AluOp::Add, rd, rs, XReg::T3);
+        }
+    }
+    /// Make base and offset of memory access fit in instruction.
+    fn address(&mut self, base: XReg, offset: i64) -> (XReg, i32) {
+        if fit_in_imm12(offset) {
+            (base, offset as i32)
+        } else {
+            self.add_imm(XReg::T3, base, offset);
+            (XReg::T3, 0)
+        }
+    }
+    fn load_from(&mut self, op: LoadOp, rd: XReg, base: XReg, offset: i64) {
+        let (base, offset) = self.address(base, offset);
+        self.push(RvInst::Load { op, rd, base, offset });
+    }
+    fn store_to(&mut self, op: StoreOp, src: XReg, base: XReg, offset: i64) {
+        let (base, offset) = self.address(base, offset);
+        self.push(RvInst::Store { op, src, base, offset });
+    }
+    /// Move value from its home to register.
+    fn load(&mut self, value: Value, reg: XReg) {
+        match self.frame.locations[&value] {
+            Location::Reg(home) => self.mv(reg, home),
+            Location::FReg(home) => self.push(RvInst::FMvToInt {
+                fmt: FloatFmt::D,
+                rd: reg,
+                rs: home,
+            }),
+            Location::Stack(offset) => self.load_from(LoadOp::Ld, reg, XReg::S0, offset as i64),
+        }
+    }
+    /// Move register to the home of value.
+    fn store(&mut self, reg: XReg, value: Value) {
+        match self.frame.locations[&value] {
+            Location::Reg(home) => self.mv(home, reg),
+            Location::FReg(home) => self.push(RvInst::FMvFromInt {
+                fmt: FloatFmt::D,
+                rd: home,
+                rs: reg,
+            }),
+            Location::Stack(offset) => self.store_to(StoreOp::Sd, reg, XReg::S0, offset as i64),
+        }
+    }
+    /// Zero or sign extend register to 64-bit by value type.
+    fn normalize(&mut self, reg: XReg, ty: &ValueType) {
+        match ty {
+            ValueType::U8 => self.alu_imm(AluImmOp::Andi, reg, reg, 0xff),
+            ValueType::U16 | ValueType::U32 | ValueType::F32 => {
+                let shift = 64 - ty.get_size() as i32 * 8;
+                self.alu_imm(AluImmOp::Slli, reg, reg, shift);
+                self.alu_imm(AluImmOp::Srli, reg, reg, shift);
+            }
+            ValueType::I16 => {
+                self.alu_imm(AluImmOp::Slli, reg, reg, 48);
+                self.alu_imm(AluImmOp::Srai, reg, reg, 48);
+            }
+            ValueType::I32 => self.alu_imm(AluImmOp::Addiw, reg, reg, 0),
+            ValueType::U64 | ValueType::I64 | ValueType::F64 | ValueType::Mem(_) => {}
+        }
+    }
+    fn move_to_freg(&mut self, rs: XReg, rd: FReg, ty: &ValueType) {
+        self.push(RvInst::FMvFromInt {
+            fmt: float_fmt(ty),
+            rd,
+            rs,
+        });
+    }
+    fn move_from_freg(&mut self, rs: FReg, rd: XReg, ty: &ValueType) {
+        self.push(RvInst::FMvToInt {
+            fmt: float_fmt(ty),
+            rd,
+            rs,
+        });
+        self.normalize(rd, ty);
+    }
+}
+
+/// Prologue and epilogue.
+impl FunctionLowering<'_> {
+    fn lower_prologue(&mut self) {
+        self.alu_imm(AluImmOp::Addi, XReg::SP, XReg::SP, -16);
+        self.push(RvInst::Store {
+            op: StoreOp::Sd,
+            src: XReg::RA,
+            base: XReg::SP,
+            offset: 8,
+        });
+        self.push(RvInst::Store {
+            op: StoreOp::Sd,
+            src: XReg::S0,
+            base: XReg::SP,
+            offset: 0,
+        });
+        self.alu_imm(AluImmOp::Addi, XReg::S0, XReg::SP, 16);
+        self.add_imm(XReg::SP, XReg::SP, 16 - self.frame.frame_size as i64);
+        for (reg, offset) in self.frame.saved_regs.clone() {
+            self.store_to(StoreOp::Sd, reg, XReg::S0, offset as i64);
+        }
+        // float register is saved through `t0`, raw bits are kept by `fmv.x.d`.
+        for (reg, offset) in self.frame.saved_fregs.clone() {
+            self.push(RvInst::FMvToInt {
+                fmt: FloatFmt::D,
+                rd: XReg::T0,
+                rs: reg,
+            });
+            self.store_to(StoreOp::Sd, XReg::T0, XReg::S0, offset as i64);
+        }
+        // move arguments to home of params, stack arguments are above `s0`.
+        let params = self.function.entities.params.clone();
+        let types = params.iter().map(|param| self.value_type(*param)).collect::<Vec<_>>();
+        let (locations, _) = classify_args(&types);
+        for ((param, ty), location) in params.into_iter().zip(types).zip(locations) {
+            match location {
+                ArgLocation::Int(reg) => self.mv(XReg::T0, reg),
+                ArgLocation::Float(reg) => self.push(RvInst::FMvToInt {
+                    fmt: float_fmt(&ty),
+                    rd: XReg::T0,
+                    rs: reg,
+                }),
+                ArgLocation::Stack(offset) => self.load_from(LoadOp::Ld, XReg::T0, XReg::S0, offset as i64),
+            }
+            self.normalize(XReg::T0, &ty);
+            self.store(XReg::T0, param);
+        }
+    }
+    fn lower_epilogue(&mut self) {
+        let ret_label = self.ret_label();
+        self.push(RvInst::Label(ret_label));
+        for (reg, offset) in self.frame.saved_regs.clone() {
+            self.load_from(LoadOp::Ld, reg, XReg::S0, offset as i64);
+        }
+        for (reg, offset) in self.frame.saved_fregs.clone() {
+            self.load_from(LoadOp::Ld, XReg::T0, XReg::S0, offset as i64);
+            self.push(RvInst::FMvFromInt {
+                fmt: FloatFmt::D,
+                rd: reg,
+                rs: XReg::T0,
+            });
+        }
+        self.load_from(LoadOp::Ld, XReg::RA, XReg::S0, -8);
+        self.mv(XReg::SP, XReg::S0);
+        self.load_from(LoadOp::Ld, XReg::S0, XReg::S0, -16);
+        self.push(RvInst::Jalr {
+            rd: XReg::ZERO,
+            rs1: XReg::RA,
+            offset: 0,
+        });
+    }
+}
+
+/// Instruction selection.
+impl FunctionLowering<'_> {
+    fn lower_inst(&mut self, block: Block, inst: Instruction) -> LowerResult {
+        let inst_data = self.function.get_inst_data(inst);
+        match inst_data {
+            InstructionData::UnaryConst { constant, .. } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(result);
+                let constant_data = self
+                    .function
+                    .constants
+                    .get(constant)
+                    .ok_or(CodegenErrorKind::UnknownConstant)?;
+                self.li(XReg::T0, constant_bits(&constant_data.bytes, &ty) as i64);
+                self.store(XReg::T0, result);
+            }
+            InstructionData::Unary { opcode, value } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(result);
+                self.load(*value, XReg::T0);
+                match (opcode, is_float(&ty)) {
+                    (OpCode::Mov, _) => {}
+                    (OpCode::Neg, true) => {
+                        self.move_to_freg(XReg::T0, FReg::FT0, &ty);
+                        self.push(RvInst::FNeg {
+                            fmt: float_fmt(&ty),
+                            rd: FReg::FT0,
+                            rs: FReg::FT0,
+                        });
+                        self.move_from_freg(FReg::FT0, XReg::T0, &ty);
+                    }
+                    (OpCode::Neg, false) => self.alu(AluOp::Sub, XReg::T0, XReg::ZERO, XReg::T0),
+                    (OpCode::BitwiseNot, false) => self.alu_imm(AluImmOp::Xori, XReg::T0, XReg::T0, -1),
+                    _ => return Err(CodegenErrorKind::UnsupportedOperation(*opcode, ty)),
+                }
+                self.normalize(XReg::T0, &ty);
+                self.store(XReg::T0, result);
+            }
+            InstructionData::Move { src, .. } => {
+                let result = self.result_of(inst)?;
+                self.load(*src, XReg::T0);
+                self.store(XReg::T0, result);
+            }
+            InstructionData::Binary { opcode, args } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(result);
+                self.load(args[0], XReg::T0);
+                self.load(args[1], XReg::T1);
+                self.lower_binary(*opcode, &ty)?;
+                self.store(XReg::T0, result);
+            }
+            InstructionData::BinaryI { opcode, value, imm } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(result);
+                if is_float(&ty) {
+                    return Err(CodegenErrorKind::UnsupportedOperation(*opcode, ty));
+                }
+                let imm = normalize_bits(u64::from_le_bytes(imm.get_bytes()), &ty) as i64;
+                self.load(*value, XReg::T0);
+                match opcode {
+                    OpCode::Addi if fit_in_imm12(imm) => {
+                        self.alu_imm(AluImmOp::Addi, XReg::T0, XReg::T0, imm as i32);
+                        self.normalize(XReg::T0, &ty);
+                    }
+                    OpCode::Subi if fit_in_imm12(imm.wrapping_neg()) => {
+                        self.alu_imm(AluImmOp::Addi, XReg::T0, XReg::T0, -imm as i32);
+                        self.normalize(XReg::T0, &ty);
+                    }
+                    _ => {
+                        self.li(XReg::T1, imm);
+                        self.lower_binary(*opcode, &ty)?;
+                    }
+                }
+                self.store(XReg::T0, result);
+            }
+            InstructionData::Icmp { flag, args, .. } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(args[0]);
+                self.load(args[0], XReg::T0);
+                self.load(args[1], XReg::T1);
+                self.lower_icmp(*flag, is_signed(&ty));
+                self.store(XReg::T0, result);
+            }
+            InstructionData::Fcmp { opcode, flag, args } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(args[0]);
+                if !is_float(&ty) {
+                    return Err(CodegenErrorKind::UnsupportedOperation(*opcode, ty));
+                }
+                self.load(args[0], XReg::T0);
+                self.load(args[1], XReg::T1);
+                self.lower_fcmp(*flag, &ty);
+                self.store(XReg::T0, result);
+            }
+            InstructionData::Call { name, params, .. } => {
+                let result = self.function.get_inst_result(inst);
+                self.lower_call(*name, params, result)?;
+            }
+            InstructionData::Ret { value, .. } => {
+                if let Some(value) = value {
+                    let ty = self.value_type(*value);
+                    if is_float(&ty) {
+                        self.load(*value, XReg::T0);
+                        self.move_to_freg(XReg::T0, FReg::FA0, &ty);
+                    } else {
+                        self.load(*value, XReg::A0);
+                    }
+                }
+                let target = self.ret_label();
+                self.push(RvInst::Jal { rd: XReg::ZERO, target });
+            }
+            InstructionData::Convert { opcode, src } => {
+                let result = self.result_of(inst)?;
+                let src_ty = self.value_type(*src);
+                let dst_ty = self.value_type(result);
+                self.load(*src, XReg::T0);
+                self.lower_convert(*opcode, &src_ty, &dst_ty)?;
+                self.store(XReg::T0, result);
+            }
+            InstructionData::StackAlloc { .. } => {
+                let result = self.result_of(inst)?;
+                let offset = self.frame.stack_slots[&inst];
+                self.add_imm(XReg::T0, XReg::S0, offset as i64);
+                self.store(XReg::T0, result);
+            }
+            InstructionData::LoadRegister { base, offset, .. } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(result);
+                self.load(*base, XReg::T0);
+                self.load_from(load_op(&ty), XReg::T0, XReg::T0, offset.0 as i64);
+                self.store(XReg::T0, result);
+            }
+            InstructionData::StoreRegister { base, offset, src, .. } => {
+                let ty = self.value_type(*src);
+                self.load(*base, XReg::T0);
+                self.load(*src, XReg::T1);
+                self.store_to(store_op(&ty), XReg::T1, XReg::T0, offset.0 as i64);
+            }
+            InstructionData::GlobalLoad { base, offset, .. } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(result);
+                self.lower_global_address(*base)?;
+                self.load_from(load_op(&ty), XReg::T0, XReg::T0, offset.0 as i64);
+                self.store(XReg::T0, result);
+            }
+            InstructionData::GlobalStore { base, offset, src, .. } => {
+                let ty = self.value_type(*src);
+                self.lower_global_address(*base)?;
+                self.load(*src, XReg::T1);
+                self.store_to(store_op(&ty), XReg::T1, XReg::T0, offset.0 as i64);
+            }
+            InstructionData::BrIf {
+                test, conseq, alter, ..
+            } => {
+                // conditional branch only reach 4KiB, so it only skip over the jump to
+                // consequent block, which is `jal` with 1MiB range.
+                let alter_label = self.new_label();
+                self.load(*test, XReg::T0);
+                self.push(RvInst::Branch {
+                    op: BranchOp::Beq,
+                    rs1: XReg::T0,
+                    rs2: XReg::ZERO,
+                    target: alter_label.clone(),
+                });
+                self.lower_jump(block, *conseq)?;
+                self.push(RvInst::Label(alter_label));
+                self.lower_jump(block, *alter)?;
+            }
+            InstructionData::Jump { dst, .. } => self.lower_jump(block, *dst)?,
+            // phi is resolved by copies at the end of predecessors.
+            InstructionData::Phi { .. } => {}
+            InstructionData::Comment(comment) => {
+                let comment = comment.clone();
+                self.push(RvInst::Comment(comment));
+            }
+        }
+        Ok(())
+    }
+    /// Apply binary opcode to `t0` and `t1`, result is in `t0`.
+    fn lower_binary(&mut self, opcode: OpCode, ty: &ValueType) -> LowerResult {
+        if is_float(ty) {
+            let fmt = float_fmt(ty);
+            let op = match opcode {
+                OpCode::FAdd => FArithOp::Add,
+                OpCode::FSub => FArithOp::Sub,
+                OpCode::FMul => FArithOp::Mul,
+                OpCode::FDivide => FArithOp::Div,
+                // there is no reminder instruction in F and D extension, call `fmod` of libm.
+                OpCode::FReminder => {
+                    self.move_to_freg(XReg::T0, FReg::FA0, ty);
+                    self.move_to_freg(XReg::T1, FReg::FA1, ty);
+                    let symbol = if fmt == FloatFmt::S { "fmodf" } else { "fmod" };
+                    self.push(RvInst::Call {
+                        symbol: symbol.to_owned(),
+                    });
+                    self.move_from_freg(FReg::FA0, XReg::T0, ty);
+                    return Ok(());
+                }
+                _ => return Err(CodegenErrorKind::UnsupportedOperation(opcode, ty.clone())),
+            };
+            self.move_to_freg(XReg::T0, FReg::FT0, ty);
+            self.move_to_freg(XReg::T1, FReg::FT1, ty);
+            self.push(RvInst::FArith {
+                op,
+                fmt,
+                rd: FReg::FT0,
+                rs1: FReg::FT0,
+                rs2: FReg::FT1,
+            });
+            self.move_from_freg(FReg::FT0, XReg::T0, ty);
+            return Ok(());
+        }
+        let signed = is_signed(ty);
+        let op = match opcode {
+            OpCode::Add | OpCode::Addi => AluOp::Add,
+            OpCode::Sub | OpCode::Subi => AluOp::Sub,
+            OpCode::Mul | OpCode::Muli => AluOp::Mul,
+            OpCode::BitwiseOR => AluOp::Or,
+            OpCode::BitwiseAnd => AluOp::And,
+            OpCode::ShiftLeft | OpCode::ShiftRight => {
+                // shift amount is masked by width of type.
+                let mask = ty.get_size() as i32 * 8 - 1;
+                self.alu_imm(AluImmOp::Andi, XReg::T1, XReg::T1, mask);
+                match (opcode, signed) {
+                    (OpCode::ShiftLeft, _) => AluOp::Sll,
+                    (_, true) => AluOp::Sra,
+                    (_, false) => AluOp::Srl,
+                }
+            }
+            // `MIN / -1` does not trap and result is wrapping as rust, operands are
+            // extended to 64-bit so narrow division is exact.
+            OpCode::Divide | OpCode::Dividei if signed => AluOp::Div,
+            OpCode::Divide | OpCode::Dividei => AluOp::Divu,
+            OpCode::Reminder | OpCode::Reminderi if signed => AluOp::Rem,
+            OpCode::Reminder | OpCode::Reminderi => AluOp::Remu,
+            _ => return Err(CodegenErrorKind::UnsupportedOperation(opcode, ty.clone())),
+        };
+        self.alu(op, XReg::T0, XReg::T0, XReg::T1);
+        self.normalize(XReg::T0, ty);
+        Ok(())
+    }
+    /// Compare `t0` and `t1`, result 0 or 1 is in `t0`.
+    fn lower_icmp(&mut self, flag: CmpFlag, signed: bool) {
+        let slt = if signed { AluOp::Slt } else { AluOp::Sltu };
+        let (t0, t1) = (XReg::T0, XReg::T1);
+        match flag {
+            CmpFlag::Eq => {
+                self.alu(AluOp::Xor, t0, t0, t1);
+                self.alu_imm(AluImmOp::Sltiu, t0, t0, 1);
+            }
+            CmpFlag::NotEq => {
+                self.alu(AluOp::Xor, t0, t0, t1);
+                self.alu(AluOp::Sltu, t0, XReg::ZERO, t0);
+            }
+            CmpFlag::Lt => self.alu(slt, t0, t0, t1),
+            CmpFlag::Gt => self.alu(slt, t0, t1, t0),
+            CmpFlag::Gteq => {
+                self.alu(slt, t0, t0, t1);
+                self.alu_imm(AluImmOp::Xori, t0, t0, 1);
+            }
+            CmpFlag::LtEq => {
+                self.alu(slt, t0, t1, t0);
+                self.alu_imm(AluImmOp::Xori, t0, t0, 1);
+            }
+        }
+    }
+    /// Compare float in `t0` and `t1`, result is in `t0`. comparison with NaN is false
+    /// except `noteq`, which is negation of `feq`.
+    fn lower_fcmp(&mut self, flag: CmpFlag, ty: &ValueType) {
+        let fmt = float_fmt(ty);
+        self.move_to_freg(XReg::T0, FReg::FT0, ty);
+        self.move_to_freg(XReg::T1, FReg::FT1, ty);
+        let (op, rs1, rs2) = match flag {
+            CmpFlag::Eq | CmpFlag::NotEq => (FCmpOp::Eq, FReg::FT0, FReg::FT1),
+            CmpFlag::Lt => (FCmpOp::Lt, FReg::FT0, FReg::FT1),
+            CmpFlag::LtEq => (FCmpOp::Le, FReg::FT0, FReg::FT1),
+            CmpFlag::Gt => (FCmpOp::Lt, FReg::FT1, FReg::FT0),
+            CmpFlag::Gteq => (FCmpOp::Le, FReg::FT1, FReg::FT0),
+        };
+        self.push(RvInst::FCmp {
+            op,
+            fmt,
+            rd: XReg::T0,
+            rs1,
+            rs2,
+        });
+        if flag == CmpFlag::NotEq {
+            self.alu_imm(AluImmOp::Xori, XReg::T0, XReg::T0, 1);
+        }
+    }
+    /// Convert `t0` from source type to destination type with `as` semantic.
+    fn lower_convert(&mut self, opcode: OpCode, src_ty: &ValueType, dst_ty: &ValueType) -> LowerResult {
+        if opcode == OpCode::ToAddress && is_float(dst_ty) {
+            return Err(CodegenErrorKind::UnsupportedOperation(opcode, dst_ty.clone()));
+        }
+        match (is_float(src_ty), is_float(dst_ty)) {
+            (false, false) => {}
+            (false, true) => {
+                let int = if is_signed(src_ty) { IntFmt::L } else { IntFmt::Lu };
+                self.push(RvInst::FCvtFromInt {
+                    fmt: float_fmt(dst_ty),
+                    int,
+                    rd: FReg::FT0,
+                    rs: XReg::T0,
+                });
+                self.move_from_freg(FReg::FT0, XReg::T0, dst_ty);
+            }
+            (true, true) => {
+                if src_ty != dst_ty {
+                    self.move_to_freg(XReg::T0, FReg::FT0, src_ty);
+                    self.push(RvInst::FCvtFloat {
+                        fmt: float_fmt(dst_ty),
+                        rd: FReg::FT0,
+                        rs: FReg::FT0,
+                    });
+                    self.move_from_freg(FReg::FT0, XReg::T0, dst_ty);
+                }
+            }
+            (true, false) => self.lower_float_to_int(src_ty, dst_ty),
+        }
+        self.normalize(XReg::T0, dst_ty);
+        Ok(())
+    }
+    /// Float to integer conversion of RISC-V saturate to range of 32-bit or 64-bit integer,
+    /// narrower integer is clamped after conversion, and NaN (converted to maximum) is
+    /// masked to zero.
+    fn lower_float_to_int(&mut self, src_ty: &ValueType, dst_ty: &ValueType) {
+        let fmt = float_fmt(src_ty);
+        let int = match dst_ty {
+            ValueType::I64 => IntFmt::L,
+            ValueType::I16 | ValueType::I32 => IntFmt::W,
+            ValueType::U8 | ValueType::U16 | ValueType::U32 => IntFmt::Wu,
+            _ => IntFmt::Lu,
+        };
+        self.move_to_freg(XReg::T0, FReg::FT0, src_ty);
+        self.push(RvInst::FCmp {
+            op: FCmpOp::Eq,
+            fmt,
+            rd: XReg::T1,
+            rs1: FReg::FT0,
+            rs2: FReg::FT0,
+        });
+        self.push(RvInst::FCvtToInt {
+            fmt,
+            int,
+            rd: XReg::T0,
+            rs: FReg::FT0,
+        });
+        let (min, max) = int_range(dst_ty);
+        if matches!(dst_ty, ValueType::U8 | ValueType::U16 | ValueType::I16) {
+            let op = if is_signed(dst_ty) {
+                BranchOp::Blt
+            } else {
+                BranchOp::Bltu
+            };
+            self.clamp(op, max as i64, true);
+        }
+        if *dst_ty == ValueType::I16 {
+            self.clamp(BranchOp::Blt, min, false);
+        }
+        self.alu(AluOp::Sub, XReg::T1, XReg::ZERO, XReg::T1);
+        self.alu(AluOp::And, XReg::T0, XReg::T0, XReg::T1);
+    }
+    /// Clamp `t0` to upper bound or lower bound.
+    fn clamp(&mut self, op: BranchOp, bound: i64, is_upper: bool) {
+        let done_label = self.new_label();
+        self.li(XReg::T2, bound);
+        let (rs1, rs2) = if is_upper {
+            (XReg::T0, XReg::T2)
+        } else {
+            (XReg::T2, XReg::T0)
+        };
+        self.push(RvInst::Branch {
+            op,
+            rs1,
+            rs2,
+            target: done_label.clone(),
+        });
+        self.mv(XReg::T0, XReg::T2);
+        self.push(RvInst::Label(done_label));
+    }
+    /// Call function with LP64D calling convention, see `classify_args`.
+    fn lower_call(&mut self, func_ref: FunctionRef, params: &[Value], result: Option<Value>) -> LowerResult {
+        let external_func = &self.function.external_funcs[&func_ref];
+        let symbol = get_symbol_of_external_name(self.module, &external_func.name)
+            .ok_or(CodegenErrorKind::UnresolvedSymbol(external_func.name.clone()))?
+            .to_owned();
+        let types = params.iter().map(|param| self.value_type(*param)).collect::<Vec<_>>();
+        let (locations, stack_size) = classify_args(&types);
+        self.add_imm(XReg::SP, XReg::SP, -(stack_size as i64));
+        for ((param, ty), location) in params.iter().zip(&types).zip(locations) {
+            match location {
+                ArgLocation::Int(reg) => self.load(*param, reg),
+                ArgLocation::Float(reg) => {
+                    self.load(*param, XReg::T0);
+                    self.move_to_freg(XReg::T0, reg, ty);
+                }
+                ArgLocation::Stack(offset) => {
+                    self.load(*param, XReg::T0);
+                    self.store_to(StoreOp::Sd, XReg::T0, XReg::SP, offset as i64);
+                }
+            }
+        }
+        self.push(RvInst::Call { symbol });
+        self.add_imm(XReg::SP, XReg::SP, stack_size as i64);
+        if let Some(result) = result {
+            let ty = self.value_type(result);
+            if is_float(&ty) {
+                self.move_from_freg(FReg::FA0, XReg::T0, &ty);
+            } else {
+                // callee may leave garbage in high bits of narrow integer.
+                self.mv(XReg::T0, XReg::A0);
+                self.normalize(XReg::T0, &ty);
+            }
+            self.store(XReg::T0, result);
+        }
+        Ok(())
+    }
+    /// Compute address of global value into `t0`.
+    fn lower_global_address(&mut self, global: GlobalValue) -> LowerResult {
+        let Some(global_data) = self.function.global_values.get(&global) else {
+            return Err(CodegenErrorKind::UnknownGlobalValue(global));
+        };
+        match global_data.clone() {
+            GlobalValueData::Symbol { name } => {
+                let symbol = get_symbol_of_external_name(self.module, &name)
+                    .ok_or(CodegenErrorKind::UnresolvedSymbol(name.clone()))?
+                    .to_owned();
+                self.push(RvInst::La { rd: XReg::T0, symbol });
+            }
+            GlobalValueData::AddI { base, offset, .. } => {
+                self.lower_global_address(base)?;
+                self.add_imm(XReg::T0, XReg::T0, offset.0 as i64);
+            }
+            GlobalValueData::Load { base, offset, ty } => {
+                self.lower_global_address(base)?;
+                self.load_from(load_op(&ty), XReg::T0, XReg::T0, offset.0 as i64);
+            }
+        }
+        Ok(())
+    }
+    /// Jump to block, copy arguments of phis in block before jump.
+    fn lower_jump(&mut self, from: Block, to: Block) -> LowerResult {
+        let copies = self.get_phi_copies(from, to)?;
+        self.lower_parallel_copies(copies);
+        let target = self.block_label(to);
+        self.push(RvInst::Jal { rd: XReg::ZERO, target });
+        Ok(())
+    }
+    /// Get (source, destination) of phis in block for edge from predecessor.
+    fn get_phi_copies(&self, from: Block, to: Block) -> Result<Vec<(Value, Value)>, CodegenErrorKind> {
+        let mut copies = Vec::new();
+        for inst in self.function.get_insts_of_block(to) {
+            let InstructionData::Phi { from: sources, .. } = self.function.get_inst_data(inst) else {
+                continue;
+            };
+            let Some((_, source)) = sources.iter().find(|(pred, _)| *pred == from) else {
+                return Err(CodegenErrorKind::MissingPhiArgument { block: to, from });
+            };
+            copies.push((*source, self.result_of(inst)?));
+        }
+        Ok(copies)
+    }
+    /// Phis of a block are assigned at the same time, so sources are saved to stack
+    /// first and then loaded to destinations.
+    fn lower_parallel_copies(&mut self, copies: Vec<(Value, Value)>) {
+        if let [(source, destination)] = copies[..] {
+            self.load(source, XReg::T0);
+            self.store(XReg::T0, destination);
+            return;
+        }
+        if copies.is_empty() {
+            return;
+        }
+        let size = (8 * copies.len() as u64).div_ceil(STACK_ALIGN) * STACK_ALIGN;
+        self.add_imm(XReg::SP, XReg::SP, -(size as i64));
+        for (index, (source, _)) in copies.iter().enumerate() {
+            self.load(*source, XReg::T0);
+            self.store_to(StoreOp::Sd, XReg::T0, XReg::SP, 8 * index as i64);
+        }
+        for (index, (_, destination)) in copies.into_iter().enumerate() {
+            self.load_from(LoadOp::Ld, XReg::T0, XReg::SP, 8 * index as i64);
+            self.store(XReg::T0, destination);
+        }
+        self.add_imm(XReg::SP, XReg::SP, size as i64);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::codegen::riscv64::encoder::{assemble, LoweredFunction, Program};
+use crate::codegen::riscv64::isel::FunctionLowering;
+use crate::codegen::{emit_data_objects, get_defined_functions, CodegenError};
+use crate::entities::module::Module;
+
+pub mod abi;
+pub mod encoder;
+pub mod frame;
+pub mod inst;
+pub mod isel;
+pub mod register;
+pub mod simulator;
+
+/// ## RV64 Backend
+/// Emit GNU assembly text of module for RV64IMFD with LP64D ABI. Every function
+/// with body and every data object is exported as global symbol.
+pub fn emit_module(module: &Module) -> Result<String, CodegenError> {
+    let mut output = String::from("    .text\n");
+    for (_, name, function) in get_defined_functions(module) {
+        let insts = FunctionLowering::new(module, name, function)?.lower()?;
+        output.push_str(&format!(
+            "    .globl {name}\n    .type {name}, @function\n    .p2align 2\n{name}:\n"
+        ));
+        for inst in insts {
+            output.push_str(&format!("{}\n", inst));
+        }
+        output.push_str(&format!("    .size {name}, .-{name}\n"));
+    }
+    emit_data_objects(module, &mut output)?;
+    output.push_str("    .section .note.GNU-stack,\"\",@progbits\n");
+    Ok(output)
+}
+
+/// Lower and encode module to machine code, the program can be run by
+/// [`simulator::Simulator`].
+pub fn compile_module(module: &Module) -> Result<Program, CodegenError> {
+    let mut functions = Vec::new();
+    for (_, name, function) in get_defined_functions(module) {
+        functions.push(LoweredFunction {
+            name,
+            signature: function.signature.clone(),
+            insts: FunctionLowering::new(module, name, function)?.lower()?,
+        });
+    }
+    assemble(module, &functions)
+}
@@ -0,0 +1,137 @@
+use std::fmt;
+
+use crate::codegen::is_float;
+use crate::codegen::regalloc::{RegisterClass, RegisterTarget};
+use crate::entities::r#type::ValueType;
+
+/// ## Integer Register
+/// Integer register `x0` - `x31` of RV64, printed by ABI name.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub struct XReg(pub u8);
+
+/// ## Float Register
+/// Float register `f0` - `f31` of RV64 with D extension, printed by ABI name.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub struct FReg(pub u8);
+
+const XREG_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7", "s2",
+    "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4", "t5", "t6",
+];
+const FREG_NAMES: [&str; 32] = [
+    "ft0", "ft1", "ft2", "ft3", "ft4", "ft5", "ft6", "ft7", "fs0", "fs1", "fa0", "fa1", "fa2", "fa3", "fa4", "fa5",
+    "fa6", "fa7", "fs2", "fs3", "fs4", "fs5", "fs6", "fs7", "fs8", "fs9", "fs10", "fs11", "ft8", "ft9", "ft10", "ft11",
+];
+
+impl XReg {
+    pub const ZERO: XReg = XReg(0);
+    pub const RA: XReg = XReg(1);
+    pub const SP: XReg = XReg(2);
+    pub const T0: XReg = XReg(5);
+    pub const T1: XReg = XReg(6);
+    pub const T2: XReg = XReg(7);
+    /// Frame pointer.
+    pub const S0: XReg = XReg(8);
+    pub const A0: XReg = XReg(10);
+    pub const T3: XReg = XReg(28);
+    /// ABI name of register.
+    pub fn name(&self) -> &'static str {
+        XREG_NAMES[self.0 as usize]
+    }
+}
+
+impl FReg {
+    pub const FT0: FReg = FReg(0);
+    pub const FT1: FReg = FReg(1);
+    pub const FA0: FReg = FReg(10);
+    pub const FA1: FReg = FReg(11);
+    /// ABI name of register.
+    pub fn name(&self) -> &'static str {
+        FREG_NAMES[self.0 as usize]
+    }
+}
+
+impl fmt::Display for XReg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl fmt::Display for FReg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Registers of integer arguments `a0` - `a7` in LP64D calling convention, in order.
+pub const INT_ARG_REGS: [XReg; 8] = [
+    XReg(10),
+    XReg(11),
+    XReg(12),
+    XReg(13),
+    XReg(14),
+    XReg(15),
+    XReg(16),
+    XReg(17),
+];
+/// Registers of float arguments `fa0` - `fa7` in LP64D calling convention, in order.
+pub const FLOAT_ARG_REGS: [FReg; 8] = [
+    FReg(10),
+    FReg(11),
+    FReg(12),
+    FReg(13),
+    FReg(14),
+    FReg(15),
+    FReg(16),
+    FReg(17),
+];
+/// Callee saved registers `s1` - `s11` which can hold value across call, used as home
+/// of values. `s0` is kept as frame pointer.
+pub const ALLOCATABLE_REGS: [XReg; 11] = [
+    XReg(9),
+    XReg(18),
+    XReg(19),
+    XReg(20),
+    XReg(21),
+    XReg(22),
+    XReg(23),
+    XReg(24),
+    XReg(25),
+    XReg(26),
+    XReg(27),
+];
+/// Callee saved float registers `fs0` - `fs11`.
+pub const ALLOCATABLE_FREGS: [FReg; 12] = [
+    FReg(8),
+    FReg(9),
+    FReg(18),
+    FReg(19),
+    FReg(20),
+    FReg(21),
+    FReg(22),
+    FReg(23),
+    FReg(24),
+    FReg(25),
+    FReg(26),
+    FReg(27),
+];
+
+/// Register target of RV64 for register allocator, float value is kept in float
+/// register.
+pub struct Riscv64Registers;
+
+impl RegisterTarget for Riscv64Registers {
+    fn register_class(&self, ty: &ValueType) -> RegisterClass {
+        if is_float(ty) {
+            RegisterClass::Float
+        } else {
+            RegisterClass::Int
+        }
+    }
+    fn allocatable_registers(&self, class: RegisterClass) -> Vec<&'static str> {
+        match class {
+            RegisterClass::Int => ALLOCATABLE_REGS.iter().map(|reg| reg.name()).collect(),
+            RegisterClass::Float => ALLOCATABLE_FREGS.iter().map(|reg| reg.name()).collect(),
+        }
+    }
+}
@@ -0,0 +1,639 @@
+use std::fmt;
+
+use crate::codegen::riscv64::abi::{classify_args, ArgLocation, STACK_ALIGN};
+use crate::codegen::riscv64::encoder::Program;
+use crate::codegen::{constant_bits, is_float, normalize_bits};
+use crate::entities::r#type::ValueType;
+use crate::entities::runtime_value::RuntimeValue;
+
+const DEFAULT_STEP_LIMIT: u64 = 10_000_000;
+const DEFAULT_STACK_SIZE: u64 = 1 << 20;
+/// Return address of simulated call, address zero is never mapped to text.
+const RETURN_ADDRESS: u64 = 0;
+const CANONICAL_NAN_F32: u32 = 0x7fc0_0000;
+const CANONICAL_NAN_F64: u64 = 0x7ff8_0000_0000_0000;
+/// Upper bits of single float in 64-bit float register (NaN boxing).
+const NAN_BOX: u64 = 0xffff_ffff_0000_0000;
+const RA: usize = 1;
+const SP: usize = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatorError {
+    pub pc: u64,
+    pub kind: SimulatorErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulatorErrorKind {
+    UnknownFunction(String),
+    ArgumentCountMismatch {
+        expect: usize,
+        actual: usize,
+    },
+    ArgumentTypeMismatch(usize),
+    /// Execute address which is not in text or external stub.
+    InvalidPc(u64),
+    IllegalInstruction(u32),
+    UnknownExternal(String),
+    OutOfBounds {
+        address: u64,
+        size: u64,
+    },
+    StepLimitExceeded(u64),
+}
+
+impl fmt::Display for SimulatorErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulatorErrorKind::UnknownFunction(name) => write!(f, "function `{}` is not in program", name),
+            SimulatorErrorKind::ArgumentCountMismatch { expect, actual } => {
+                write!(f, "expect {} arguments but got {}", expect, actual)
+            }
+            SimulatorErrorKind::ArgumentTypeMismatch(index) => write!(f, "type of argument {} mismatch", index),
+            SimulatorErrorKind::InvalidPc(pc) => write!(f, "can not execute address 0x{:X}", pc),
+            SimulatorErrorKind::IllegalInstruction(word) => write!(f, "illegal instruction 0x{:08X}", word),
+            SimulatorErrorKind::UnknownExternal(name) => write!(f, "external symbol `{}` is not supported", name),
+            SimulatorErrorKind::OutOfBounds { address, size } => {
+                write!(f, "access {} bytes at address 0x{:X} out of bounds", size, address)
+            }
+            SimulatorErrorKind::StepLimitExceeded(limit) => write!(f, "exceed step limit {}", limit),
+        }
+    }
+}
+
+impl fmt::Display for SimulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[Error]: pc 0x{:X}: {}", self.pc, self.kind)
+    }
+}
+
+type StepResult = Result<(), SimulatorErrorKind>;
+
+fn sign_extend(value: u64, bits: u32) -> u64 {
+    (((value << (64 - bits)) as i64) >> (64 - bits)) as u64
+}
+
+fn sign_extend_word(value: u64) -> u64 {
+    value as i32 as i64 as u64
+}
+
+/// Round float by rounding mode field, `dyn` use default round to nearest even.
+fn round_by_mode(num: f64, rm: u32) -> Option<f64> {
+    match rm {
+        0 | 7 => Some(num.round_ties_even()),
+        1 => Some(num.trunc()),
+        2 => Some(num.floor()),
+        3 => Some(num.ceil()),
+        4 => Some(num.round()),
+        _ => None,
+    }
+}
+
+/// ## RV64 Simulator
+/// Load a assembled program into flat memory and execute it instruction by instruction,
+/// used to test RV64 backend without hardware. Support RV64IMFD except atomic, CSR and
+/// fused multiply-add instructions:
+/// - result NaN of float arithmetic is canonical NaN, exception flags are not recorded.
+/// - integer to float conversion always round to nearest even.
+/// - external stubs of `fmod` and `fmodf` are run by host, other externals are error.
+/// - memory keeps value between runs, so data objects are shared as interpreter.
+pub struct Simulator<'a> {
+    program: &'a Program,
+    memory: Vec<u8>,
+    xregs: [u64; 32],
+    fregs: [u64; 32],
+    pc: u64,
+    step_limit: u64,
+    steps: u64,
+}
+
+impl<'a> Simulator<'a> {
+    pub fn new(program: &'a Program) -> Self {
+        let stack_base = program.end_address().div_ceil(STACK_ALIGN) * STACK_ALIGN;
+        let mut memory = vec![0_u8; (stack_base + DEFAULT_STACK_SIZE) as usize];
+        for (index, word) in program.text.iter().enumerate() {
+            let address = program.text_base as usize + 4 * index;
+            memory[address..address + 4].copy_from_slice(&word.to_le_bytes());
+        }
+        let data_base = program.data_base as usize;
+        memory[data_base..data_base + program.data.len()].copy_from_slice(&program.data);
+        Self {
+            program,
+            memory,
+            xregs: [0; 32],
+            fregs: [0; 32],
+            pc: RETURN_ADDRESS,
+            step_limit: DEFAULT_STEP_LIMIT,
+            steps: 0,
+        }
+    }
+    /// Set max number of instructions can be executed in a run.
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.step_limit = limit;
+    }
+    /// Number of instructions executed in last run.
+    pub fn get_steps(&self) -> u64 {
+        self.steps
+    }
+    /// Read bytes of memory.
+    pub fn read_memory(&self, address: u64, size: u64) -> Result<&[u8], SimulatorErrorKind> {
+        let range = self.check_range(address, size)?;
+        Ok(&self.memory[range])
+    }
+    /// Run function with given name and arguments by LP64D calling convention, return
+    /// value is read by return type of function.
+    pub fn run(&mut self, func_name: &str, args: &[RuntimeValue]) -> Result<Option<RuntimeValue>, SimulatorError> {
+        self.steps = 0;
+        let error = |pc, kind| SimulatorError { pc, kind };
+        let (Some(address), Some(signature)) = (
+            self.program.symbols.get(func_name),
+            self.program.signatures.get(func_name),
+        ) else {
+            return Err(error(0, SimulatorErrorKind::UnknownFunction(func_name.to_owned())));
+        };
+        if signature.params.len() != args.len() {
+            return Err(error(
+                0,
+                SimulatorErrorKind::ArgumentCountMismatch {
+                    expect: signature.params.len(),
+                    actual: args.len(),
+                },
+            ));
+        }
+        if let Some(index) = args
+            .iter()
+            .zip(&signature.params)
+            .position(|(arg, ty)| !arg.is_type_of(ty))
+        {
+            return Err(error(0, SimulatorErrorKind::ArgumentTypeMismatch(index)));
+        }
+        self.xregs = [0; 32];
+        self.fregs = [0; 32];
+        let (locations, stack_size) = classify_args(&signature.params);
+        let sp = self.memory.len() as u64 - stack_size;
+        for ((arg, ty), location) in args.iter().zip(&signature.params).zip(locations) {
+            let bits = constant_bits(&arg.to_bytes(), ty);
+            match location {
+                ArgLocation::Int(reg) => self.xregs[reg.0 as usize] = bits,
+                ArgLocation::Float(reg) if *ty == ValueType::F32 => self.fregs[reg.0 as usize] = NAN_BOX | bits,
+                ArgLocation::Float(reg) => self.fregs[reg.0 as usize] = bits,
+                ArgLocation::Stack(offset) => self
+                    .store(sp + offset, &bits.to_le_bytes())
+                    .map_err(|kind| error(0, kind))?,
+            }
+        }
+        self.xregs[SP] = sp;
+        self.xregs[RA] = RETURN_ADDRESS;
+        self.pc = *address;
+        while self.pc != RETURN_ADDRESS {
+            if self.steps >= self.step_limit {
+                return Err(error(self.pc, SimulatorErrorKind::StepLimitExceeded(self.step_limit)));
+            }
+            self.steps += 1;
+            let pc = self.pc;
+            self.step().map_err(|kind| error(pc, kind))?;
+        }
+        let return_value = signature.return_type.as_ref().map(|ty| {
+            let bits = if is_float(ty) { self.fregs[10] } else { self.xregs[10] };
+            RuntimeValue::from_bytes(ty, &normalize_bits(bits, ty).to_le_bytes())
+        });
+        Ok(return_value)
+    }
+}
+
+/// Memory access.
+impl Simulator<'_> {
+    fn check_range(&self, address: u64, size: u64) -> Result<std::ops::Range<usize>, SimulatorErrorKind> {
+        match address.checked_add(size) {
+            Some(end) if end <= self.memory.len() as u64 => Ok(address as usize..end as usize),
+            _ => Err(SimulatorErrorKind::OutOfBounds { address, size }),
+        }
+    }
+    fn load(&self, address: u64, size: u64) -> Result<u64, SimulatorErrorKind> {
+        let range = self.check_range(address, size)?;
+        let mut buffer = [0_u8; 8];
+        buffer[..size as usize].copy_from_slice(&self.memory[range]);
+        Ok(u64::from_le_bytes(buffer))
+    }
+    fn store(&mut self, address: u64, bytes: &[u8]) -> StepResult {
+        let range = self.check_range(address, bytes.len() as u64)?;
+        self.memory[range].copy_from_slice(bytes);
+        Ok(())
+    }
+    fn fetch(&self) -> Result<u32, SimulatorErrorKind> {
+        let text_end = self.program.text_base + 4 * self.program.text.len() as u64;
+        if self.pc < self.program.text_base || self.pc >= text_end || !self.pc.is_multiple_of(4) {
+            return Err(SimulatorErrorKind::InvalidPc(self.pc));
+        }
+        Ok(self.program.text[((self.pc - self.program.text_base) / 4) as usize])
+    }
+}
+
+/// Register access.
+impl Simulator<'_> {
+    fn x(&self, reg: u32) -> u64 {
+        self.xregs[reg as usize]
+    }
+    fn set_x(&mut self, reg: u32, value: u64) {
+        if reg != 0 {
+            self.xregs[reg as usize] = value;
+        }
+    }
+    /// Read single float from register, value which is not NaN boxed is canonical NaN.
+    fn f32(&self, reg: u32) -> f32 {
+        let bits = self.fregs[reg as usize];
+        if bits & NAN_BOX == NAN_BOX {
+            f32::from_bits(bits as u32)
+        } else {
+            f32::from_bits(CANONICAL_NAN_F32)
+        }
+    }
+    fn f64(&self, reg: u32) -> f64 {
+        f64::from_bits(self.fregs[reg as usize])
+    }
+    fn set_f32_bits(&mut self, reg: u32, bits: u32) {
+        self.fregs[reg as usize] = NAN_BOX | bits as u64;
+    }
+    fn set_f32(&mut self, reg: u32, num: f32) {
+        let bits = if num.is_nan() { CANONICAL_NAN_F32 } else { num.to_bits() };
+        self.set_f32_bits(reg, bits);
+    }
+    fn set_f64(&mut self, reg: u32, num: f64) {
+        self.fregs[reg as usize] = if num.is_nan() { CANONICAL_NAN_F64 } else { num.to_bits() };
+    }
+}
+
+/// Decode and execute.
+impl Simulator<'_> {
+    fn step(&mut self) -> StepResult {
+        if let Some(name) = self.program.externals.get(&self.pc) {
+            return self.call_external(&name.clone());
+        }
+        let word = self.fetch()?;
+        let opcode = word & 0x7f;
+        let rd = word >> 7 & 0x1f;
+        let funct3 = word >> 12 & 0x7;
+        let rs1 = word >> 15 & 0x1f;
+        let rs2 = word >> 20 & 0x1f;
+        let funct7 = word >> 25;
+        let imm_i = ((word as i32) >> 20) as i64 as u64;
+        let imm_s = ((((word as i32) >> 25) << 5) as u32 | (word >> 7 & 0x1f)) as i32 as i64 as u64;
+        let illegal = SimulatorErrorKind::IllegalInstruction(word);
+        let mut next_pc = self.pc.wrapping_add(4);
+        match opcode {
+            // LUI
+            0x37 => self.set_x(rd, (word & 0xffff_f000) as i32 as i64 as u64),
+            // AUIPC
+            0x17 => self.set_x(rd, self.pc.wrapping_add((word & 0xffff_f000) as i32 as i64 as u64)),
+            // JAL
+            0x6f => {
+                let imm = (word >> 31 & 1) << 20
+                    | (word >> 12 & 0xff) << 12
+                    | (word >> 20 & 1) << 11
+                    | (word >> 21 & 0x3ff) << 1;
+                self.set_x(rd, next_pc);
+                next_pc = self.pc.wrapping_add(sign_extend(imm as u64, 21));
+            }
+            // JALR
+            0x67 if funct3 == 0 => {
+                let target = self.x(rs1).wrapping_add(imm_i) & !1;
+                self.set_x(rd, next_pc);
+                next_pc = target;
+            }
+            // BRANCH
+            0x63 => {
+                let imm =
+                    (word >> 31 & 1) << 12 | (word >> 7 & 1) << 11 | (word >> 25 & 0x3f) << 5 | (word >> 8 & 0xf) << 1;
+                let (lhs, rhs) = (self.x(rs1), self.x(rs2));
+                let taken = match funct3 {
+                    0 => lhs == rhs,
+                    1 => lhs != rhs,
+                    4 => (lhs as i64) < (rhs as i64),
+                    5 => (lhs as i64) >= (rhs as i64),
+                    6 => lhs < rhs,
+                    7 => lhs >= rhs,
+                    _ => return Err(illegal),
+                };
+                if taken {
+                    next_pc = self.pc.wrapping_add(sign_extend(imm as u64, 13));
+                }
+            }
+            // LOAD
+            0x03 => {
+                let address = self.x(rs1).wrapping_add(imm_i);
+                let value = match funct3 {
+                    0 => sign_extend(self.load(address, 1)?, 8),
+                    1 => sign_extend(self.load(address, 2)?, 16),
+                    2 => sign_extend(self.load(address, 4)?, 32),
+                    3 => self.load(address, 8)?,
+                    4 => self.load(address, 1)?,
+                    5 => self.load(address, 2)?,
+                    6 => self.load(address, 4)?,
+                    _ => return Err(illegal),
+                };
+                self.set_x(rd, value);
+            }
+            // STORE
+            0x23 => {
+                let address = self.x(rs1).wrapping_add(imm_s);
+                let size = match funct3 {
+                    0..=3 => 1 << funct3,
+                    _ => return Err(illegal),
+                };
+                let bytes = self.x(rs2).to_le_bytes();
+                self.store(address, &bytes[..size])?;
+            }
+            // OP-IMM
+            0x13 => {
+                let lhs = self.x(rs1);
+                let shamt = (imm_i & 0x3f) as u32;
+                let value = match funct3 {
+                    0 => lhs.wrapping_add(imm_i),
+                    1 if funct7 >> 1 == 0 => lhs << shamt,
+                    2 => ((lhs as i64) < (imm_i as i64)) as u64,
+                    3 => (lhs < imm_i) as u64,
+                    4 => lhs ^ imm_i,
+                    5 if funct7 >> 1 == 0 => lhs >> shamt,
+                    5 if funct7 >> 1 == 0x10 => ((lhs as i64) >> shamt) as u64,
+                    6 => lhs | imm_i,
+                    7 => lhs & imm_i,
+                    _ => return Err(illegal),
+                };
+                self.set_x(rd, value);
+            }
+            // OP-IMM-32
+            0x1b => {
+                let lhs = self.x(rs1) as u32;
+                let value = match (funct3, funct7) {
+                    (0, _) => lhs.wrapping_add(imm_i as u32),
+                    (1, 0x00) => lhs << rs2,
+                    (5, 0x00) => lhs >> rs2,
+                    (5, 0x20) => ((lhs as i32) >> rs2) as u32,
+                    _ => return Err(illegal),
+                };
+                self.set_x(rd, sign_extend_word(value as u64));
+            }
+            // OP
+            0x33 => {
+                let value = self
+                    .execute_op(funct7, funct3, self.x(rs1), self.x(rs2))
+                    .ok_or(illegal)?;
+                self.set_x(rd, value);
+            }
+            // OP-32
+            0x3b => {
+                let value = self
+                    .execute_op_32(funct7, funct3, self.x(rs1) as u32, self.x(rs2) as u32)
+                    .ok_or(illegal)?;
+                self.set_x(rd, sign_extend_word(value as u64));
+            }
+            // LOAD-FP
+            0x07 => {
+                let address = self.x(rs1).wrapping_add(imm_i);
+                match funct3 {
+                    2 => {
+                        let bits = self.load(address, 4)? as u32;
+                        self.set_f32_bits(rd, bits);
+                    }
+                    3 => self.fregs[rd as usize] = self.load(address, 8)?,
+                    _ => return Err(illegal),
+                }
+            }
+            // STORE-FP
+            0x27 => {
+                let address = self.x(rs1).wrapping_add(imm_s);
+                let bytes = self.fregs[rs2 as usize].to_le_bytes();
+                match funct3 {
+                    2 => self.store(address, &bytes[..4])?,
+                    3 => self.store(address, &bytes)?,
+                    _ => return Err(illegal),
+                }
+            }
+            // OP-FP
+            0x53 => self.execute_op_fp(word, funct7, funct3, rd, rs1, rs2)?,
+            // FENCE
+            0x0f => {}
+            _ => return Err(illegal),
+        }
+        self.pc = next_pc;
+        Ok(())
+    }
+    fn execute_op(&self, funct7: u32, funct3: u32, lhs: u64, rhs: u64) -> Option<u64> {
+        let shamt = (rhs & 0x3f) as u32;
+        let (signed_lhs, signed_rhs) = (lhs as i64, rhs as i64);
+        let value = match (funct7, funct3) {
+            (0x00, 0) => lhs.wrapping_add(rhs),
+            (0x20, 0) => lhs.wrapping_sub(rhs),
+            (0x00, 1) => lhs << shamt,
+            (0x00, 2) => (signed_lhs < signed_rhs) as u64,
+            (0x00, 3) => (lhs < rhs) as u64,
+            (0x00, 4) => lhs ^ rhs,
+            (0x00, 5) => lhs >> shamt,
+            (0x20, 5) => (signed_lhs >> shamt) as u64,
+            (0x00, 6) => lhs | rhs,
+            (0x00, 7) => lhs & rhs,
+            (0x01, 0) => lhs.wrapping_mul(rhs),
+            (0x01, 1) => ((signed_lhs as i128 * signed_rhs as i128) >> 64) as u64,
+            (0x01, 2) => ((signed_lhs as i128 * rhs as i128) >> 64) as u64,
+            (0x01, 3) => ((lhs as u128 * rhs as u128) >> 64) as u64,
+            // division by zero does not trap, overflow is wrapping.
+            (0x01, 4) if rhs == 0 => u64::MAX,
+            (0x01, 4) => signed_lhs.wrapping_div(signed_rhs) as u64,
+            (0x01, 5) => lhs.checked_div(rhs).unwrap_or(u64::MAX),
+            (0x01, 6) if rhs == 0 => lhs,
+            (0x01, 6) => signed_lhs.wrapping_rem(signed_rhs) as u64,
+            (0x01, 7) => lhs.checked_rem(rhs).unwrap_or(lhs),
+            _ => return None,
+        };
+        Some(value)
+    }
+    fn execute_op_32(&self, funct7: u32, funct3: u32, lhs: u32, rhs: u32) -> Option<u32> {
+        let shamt = rhs & 0x1f;
+        let (signed_lhs, signed_rhs) = (lhs as i32, rhs as i32);
+        let value = match (funct7, funct3) {
+            (0x00, 0) => lhs.wrapping_add(rhs),
+            (0x20, 0) => lhs.wrapping_sub(rhs),
+            (0x00, 1) => lhs << shamt,
+            (0x00, 5) => lhs >> shamt,
+            (0x20, 5) => (signed_lhs >> shamt) as u32,
+            (0x01, 0) => lhs.wrapping_mul(rhs),
+            (0x01, 4) if rhs == 0 => u32::MAX,
+            (0x01, 4) => signed_lhs.wrapping_div(signed_rhs) as u32,
+            (0x01, 5) => lhs.checked_div(rhs).unwrap_or(u32::MAX),
+            (0x01, 6) if rhs == 0 => lhs,
+            (0x01, 6) => signed_lhs.wrapping_rem(signed_rhs) as u32,
+            (0x01, 7) => lhs.checked_rem(rhs).unwrap_or(lhs),
+            _ => return None,
+        };
+        Some(value)
+    }
+    fn execute_op_fp(&mut self, word: u32, funct7: u32, rm: u32, rd: u32, rs1: u32, rs2: u32) -> StepResult {
+        let illegal = SimulatorErrorKind::IllegalInstruction(word);
+        let is_double = match funct7 & 0x3 {
+            0 => false,
+            1 => true,
+            _ => return Err(illegal),
+        };
+        let (lhs, rhs) = if is_double {
+            (self.f64(rs1), self.f64(rs2))
+        } else {
+            (self.f32(rs1) as f64, self.f32(rs2) as f64)
+        };
+        match funct7 >> 2 {
+            // FADD, FSUB, FMUL, FDIV, FSQRT
+            funct5 @ (0x00..=0x03 | 0x0b) => {
+                if is_double {
+                    let value = match funct5 {
+                        0x00 => lhs + rhs,
+                        0x01 => lhs - rhs,
+                        0x02 => lhs * rhs,
+                        0x03 => lhs / rhs,
+                        _ => lhs.sqrt(),
+                    };
+                    self.set_f64(rd, value);
+                } else {
+                    let (lhs, rhs) = (self.f32(rs1), self.f32(rs2));
+                    let value = match funct5 {
+                        0x00 => lhs + rhs,
+                        0x01 => lhs - rhs,
+                        0x02 => lhs * rhs,
+                        0x03 => lhs / rhs,
+                        _ => lhs.sqrt(),
+                    };
+                    self.set_f32(rd, value);
+                }
+            }
+            // FSGNJ, FSGNJN, FSGNJX
+            0x04 => {
+                let (bits, sign_bits, sign) = if is_double {
+                    (self.fregs[rs1 as usize], self.fregs[rs2 as usize], 1_u64 << 63)
+                } else {
+                    (
+                        self.f32(rs1).to_bits() as u64,
+                        self.f32(rs2).to_bits() as u64,
+                        1_u64 << 31,
+                    )
+                };
+                let sign = match rm {
+                    0 => sign_bits & sign,
+                    1 => !sign_bits & sign,
+                    2 => (bits ^ sign_bits) & sign,
+                    _ => return Err(illegal),
+                };
+                let bits = bits & !(if is_double { 1_u64 << 63 } else { 1_u64 << 31 }) | sign;
+                if is_double {
+                    self.fregs[rd as usize] = bits;
+                } else {
+                    self.set_f32_bits(rd, bits as u32);
+                }
+            }
+            // FMIN, FMAX
+            0x05 => {
+                let value = match (lhs.is_nan(), rhs.is_nan(), rm) {
+                    (true, true, _) => f64::NAN,
+                    (true, false, _) => rhs,
+                    (false, true, _) => lhs,
+                    // negative zero is less than positive zero.
+                    (false, false, 0) if lhs == rhs => f64::from_bits(lhs.to_bits() | rhs.to_bits()),
+                    (false, false, 1) if lhs == rhs => f64::from_bits(lhs.to_bits() & rhs.to_bits()),
+                    (false, false, 0) => lhs.min(rhs),
+                    (false, false, 1) => lhs.max(rhs),
+                    _ => return Err(illegal),
+                };
+                if is_double {
+                    self.set_f64(rd, value);
+                } else {
+                    self.set_f32(rd, value as f32);
+                }
+            }
+            // FCVT.S.D, FCVT.D.S
+            0x08 => match (is_double, rs2) {
+                (false, 1) => {
+                    let num = self.f64(rs1) as f32;
+                    self.set_f32(rd, num);
+                }
+                (true, 0) => self.set_f64(rd, self.f32(rs1) as f64),
+                _ => return Err(illegal),
+            },
+            // FEQ, FLT, FLE
+            0x14 => {
+                let value = match rm {
+                    0 => lhs <= rhs,
+                    1 => lhs < rhs,
+                    2 => lhs == rhs,
+                    _ => return Err(illegal),
+                };
+                self.set_x(rd, value as u64);
+            }
+            // FCVT.W.S, FCVT.WU.S, FCVT.L.S, FCVT.LU.S and double version
+            0x18 => {
+                let rounded = round_by_mode(lhs, rm).ok_or(illegal.clone())?;
+                let value = match rs2 {
+                    0 if lhs.is_nan() => i32::MAX as u64,
+                    0 => (rounded as i64).clamp(i32::MIN as i64, i32::MAX as i64) as u64,
+                    1 if lhs.is_nan() => sign_extend_word(u32::MAX as u64),
+                    1 => sign_extend_word((rounded as u64).min(u32::MAX as u64)),
+                    2 if lhs.is_nan() => i64::MAX as u64,
+                    2 => rounded as i64 as u64,
+                    3 if lhs.is_nan() => u64::MAX,
+                    3 => rounded as u64,
+                    _ => return Err(illegal),
+                };
+                self.set_x(rd, value);
+            }
+            // FCVT.S.W, FCVT.S.WU, FCVT.S.L, FCVT.S.LU and double version
+            0x1a => {
+                let src = self.x(rs1);
+                if is_double {
+                    let value = match rs2 {
+                        0 => src as i32 as f64,
+                        1 => src as u32 as f64,
+                        2 => src as i64 as f64,
+                        3 => src as f64,
+                        _ => return Err(illegal),
+                    };
+                    self.set_f64(rd, value);
+                } else {
+                    let value = match rs2 {
+                        0 => src as i32 as f32,
+                        1 => src as u32 as f32,
+                        2 => src as i64 as f32,
+                        3 => src as f32,
+                        _ => return Err(illegal),
+                    };
+                    self.set_f32(rd, value);
+                }
+            }
+            // FMV.X.W, FMV.X.D
+            0x1c if rm == 0 && rs2 == 0 => {
+                let bits = self.fregs[rs1 as usize];
+                let value = if is_double { bits } else { sign_extend_word(bits) };
+                self.set_x(rd, value);
+            }
+            // FMV.W.X, FMV.D.X
+            0x1e if rm == 0 && rs2 == 0 => {
+                let bits = self.x(rs1);
+                if is_double {
+                    self.fregs[rd as usize] = bits;
+                } else {
+                    self.set_f32_bits(rd, bits as u32);
+                }
+            }
+            _ => return Err(illegal),
+        }
+        Ok(())
+    }
+    /// Run external function by host and return to caller.
+    fn call_external(&mut self, name: &str) -> StepResult {
+        match name {
+            "fmod" => self.fregs[10] = (self.f64(10) % self.f64(11)).to_bits(),
+            "fmodf" => {
+                let value = self.f32(10) % self.f32(11);
+                self.set_f32_bits(10, value.to_bits());
+            }
+            _ => return Err(SimulatorErrorKind::UnknownExternal(name.to_owned())),
+        }
+        self.pc = self.xregs[RA];
+        Ok(())
+    }
+}
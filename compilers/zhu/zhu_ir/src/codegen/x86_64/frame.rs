@@ -0,0 +1,107 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::codegen::regalloc::{allocate_registers, Assignment};
+use crate::codegen::x86_64::register::{Gpr, X86_64Registers, ALLOCATABLE_REGS};
+use crate::codegen::{get_frame_values, get_stack_allocs, CodegenErrorKind};
+use crate::entities::function::Function;
+use crate::entities::instruction::Instruction;
+use crate::entities::value::Value;
+
+/// Alignment of stack frame, `rbp` is always 16 bytes aligned after prologue.
+pub const FRAME_ALIGN: u64 = 16;
+
+/// Home of a value, value is kept as 64-bit bits in its home for whole function.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Location {
+    Reg(Gpr),
+    /// Offset to `rbp`.
+    Stack(i32),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Reg(reg) => write!(f, "{}", reg),
+            Location::Stack(offset) => write!(f, "{}(%rbp)", offset),
+        }
+    }
+}
+
+/// ## Frame
+/// Stack frame of function, from `rbp` to lower address:
+/// - callee saved registers which are used as home of values.
+/// - spill slots of values which do not get a register.
+/// - slots of `stackalloc` instructions.
+#[derive(Debug)]
+pub struct Frame {
+    pub locations: HashMap<Value, Location>,
+    pub stack_slots: HashMap<Instruction, i32>,
+    pub saved_regs: Vec<Gpr>,
+    /// Bytes subtract from `rsp` after saving registers, make `rsp` 16 bytes aligned.
+    pub frame_size: i32,
+}
+
+fn align_to(size: u64, align: u64) -> u64 {
+    size.div_ceil(align) * align
+}
+
+/// Get offset to `rbp` of slot which end at `cursor` bytes below `rbp`.
+fn to_offset(cursor: u64) -> Result<i32, CodegenErrorKind> {
+    i32::try_from(cursor)
+        .map(|cursor| -cursor)
+        .map_err(|_| CodegenErrorKind::FrameTooLarge)
+}
+
+/// Allocate home of values and slot of `stackalloc`. Home of values is given by linear
+/// scan, so a callee saved register or a spill slot is shared by values whose live
+/// intervals do not overlap.
+pub fn allocate_frame(function: &Function) -> Result<Frame, CodegenErrorKind> {
+    let allocation = allocate_registers(function, &X86_64Registers);
+    let mut locations = HashMap::new();
+    let mut used_regs = HashSet::new();
+    for (value, assignment) in allocation.get_assignments() {
+        if let Assignment::Register(name) = assignment {
+            let reg = ALLOCATABLE_REGS.into_iter().find(|reg| reg.name64() == *name).unwrap();
+            locations.insert(*value, Location::Reg(reg));
+            used_regs.insert(reg);
+        }
+    }
+    let saved_regs = ALLOCATABLE_REGS
+        .into_iter()
+        .filter(|reg| used_regs.contains(reg))
+        .collect::<Vec<_>>();
+    let spill_base = 8 * saved_regs.len() as u64;
+    for (value, assignment) in allocation.get_assignments() {
+        if let Assignment::Spill(slot) = assignment {
+            locations.insert(*value, Location::Stack(to_offset(spill_base + 8 * (*slot as u64 + 1))?));
+        }
+    }
+    let mut cursor = spill_base + 8 * allocation.get_spill_slot_count() as u64;
+    // value in unreachable block has no live interval, it still needs a home.
+    for value in get_frame_values(function) {
+        if let Entry::Vacant(entry) = locations.entry(value) {
+            cursor += 8;
+            entry.insert(Location::Stack(to_offset(cursor)?));
+        }
+    }
+    let mut stack_slots = HashMap::new();
+    for (inst, size, align) in get_stack_allocs(function) {
+        if align > FRAME_ALIGN {
+            return Err(CodegenErrorKind::UnsupportedAlignment(align));
+        }
+        cursor = cursor
+            .checked_add(size)
+            .and_then(|end| end.checked_next_multiple_of(align))
+            .ok_or(CodegenErrorKind::FrameTooLarge)?;
+        stack_slots.insert(inst, to_offset(cursor)?);
+    }
+    let frame_size = align_to(cursor, FRAME_ALIGN) - 8 * saved_regs.len() as u64;
+    Ok(Frame {
+        locations,
+        stack_slots,
+        saved_regs,
+        frame_size: i32::try_from(frame_size).map_err(|_| CodegenErrorKind::FrameTooLarge)?,
+    })
+}
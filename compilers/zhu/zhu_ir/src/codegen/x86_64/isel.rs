@@ -0,0 +1,719 @@
+use std::fmt::Write;
+
+use crate::codegen::x86_64::frame::{allocate_frame, Frame, Location};
+use crate::codegen::x86_64::register::{Gpr, FLOAT_ARG_REGS_COUNT, INT_ARG_REGS};
+use crate::codegen::{
+    constant_bits, get_symbol_of_external_name, int_range, is_float, is_signed, normalize_bits, CodegenError,
+    CodegenErrorKind,
+};
+use crate::entities::block::Block;
+use crate::entities::external_name::ExternalName;
+use crate::entities::function::{Function, FunctionRef};
+use crate::entities::global_value::{GlobalValue, GlobalValueData};
+use crate::entities::instruction::opcode::{CmpFlag, OpCode};
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::module::Module;
+use crate::entities::r#type::ValueType;
+use crate::entities::value::Value;
+
+type EmitResult = Result<(), CodegenErrorKind>;
+
+/// Suffix of scalar sse instruction for float type.
+fn sse_suffix(ty: &ValueType) -> &'static str {
+    if *ty == ValueType::F32 {
+        "ss"
+    } else {
+        "sd"
+    }
+}
+
+/// Bits of integer type, address is 64-bit.
+fn int_bits(ty: &ValueType) -> u32 {
+    ty.get_size() as u32 * 8
+}
+
+/// Float bits of given number in float type.
+fn float_bits(num: f64, ty: &ValueType) -> u64 {
+    if *ty == ValueType::F32 {
+        (num as f32).to_bits() as u64
+    } else {
+        num.to_bits()
+    }
+}
+
+/// ## Function Emitter
+/// Select x86-64 instructions for each IR instruction of function and print them
+/// in GNU assembler (AT&T) syntax. Every value is kept as 64-bit bits in its home
+/// (see `Frame`), narrow integers are zero or sign extended and floats are raw bits,
+/// instructions are computed in scratch registers `rax`, `rcx` and `rdx`.
+pub struct FunctionEmitter<'a> {
+    module: &'a Module,
+    function: &'a Function,
+    name: &'a str,
+    frame: Frame,
+    output: String,
+    label_count: usize,
+}
+
+impl<'a> FunctionEmitter<'a> {
+    pub fn new(module: &'a Module, name: &'a str, function: &'a Function) -> Result<Self, CodegenError> {
+        let frame = allocate_frame(function).map_err(|kind| CodegenError {
+            func: name.to_owned(),
+            inst: None,
+            kind,
+        })?;
+        Ok(Self {
+            module,
+            function,
+            name,
+            frame,
+            output: String::new(),
+            label_count: 0,
+        })
+    }
+    /// Emit assembly text of function.
+    pub fn emit(mut self) -> Result<String, CodegenError> {
+        let error = |inst, kind| CodegenError {
+            func: self.name.to_owned(),
+            inst,
+            kind,
+        };
+        self.emit_prologue();
+        let mut cur_block = self.function.layout.first_block;
+        while let Some(block) = cur_block {
+            let label = self.block_label(block);
+            self.label(&label);
+            for inst in self.function.get_insts_of_block(block) {
+                self.emit_inst(block, inst).map_err(|kind| error(Some(inst), kind))?;
+            }
+            cur_block = self.function.layout.blocks[&block].next;
+        }
+        self.emit_epilogue();
+        Ok(self.output)
+    }
+}
+
+/// Helpers to print assembly.
+impl FunctionEmitter<'_> {
+    fn inst(&mut self, text: &str) {
+        writeln!(self.output, "    {}", text).unwrap();
+    }
+    fn label(&mut self, label: &str) {
+        writeln!(self.output, "{}:", label).unwrap();
+    }
+    fn block_label(&self, block: Block) -> String {
+        format!(".L{}_block{}", self.name, block.0)
+    }
+    fn ret_label(&self) -> String {
+        format!(".L{}_ret", self.name)
+    }
+    fn new_label(&mut self) -> String {
+        self.label_count += 1;
+        format!(".L{}_{}", self.name, self.label_count)
+    }
+    fn value_type(&self, value: Value) -> ValueType {
+        self.function.value_type(value).clone()
+    }
+    fn result_of(&self, inst: Instruction) -> Result<Value, CodegenErrorKind> {
+        self.function
+            .get_inst_result(inst)
+            .ok_or(CodegenErrorKind::MissingResult)
+    }
+    /// Move value from its home to register.
+    fn load(&mut self, value: Value, reg: Gpr) {
+        let location = self.frame.locations[&value];
+        if location != Location::Reg(reg) {
+            self.inst(&format!("movq {}, {}", location, reg));
+        }
+    }
+    /// Move register to the home of value.
+    fn store(&mut self, reg: Gpr, value: Value) {
+        let location = self.frame.locations[&value];
+        if location != Location::Reg(reg) {
+            self.inst(&format!("movq {}, {}", reg, location));
+        }
+    }
+    fn mov_imm(&mut self, bits: u64, reg: Gpr) {
+        let imm = bits as i64;
+        if imm == 0 {
+            self.inst(&format!("xorl {}, {}", reg.name32(), reg.name32()));
+        } else if i32::try_from(imm).is_ok() {
+            self.inst(&format!("movq ${}, {}", imm, reg));
+        } else {
+            self.inst(&format!("movabsq ${}, {}", imm, reg));
+        }
+    }
+    /// Zero or sign extend register to 64-bit by value type.
+    fn normalize(&mut self, reg: Gpr, ty: &ValueType) {
+        match ty {
+            ValueType::U8 => self.inst(&format!("movzbq {}, {}", reg.name8(), reg)),
+            ValueType::U16 => self.inst(&format!("movzwq {}, {}", reg.name16(), reg)),
+            ValueType::U32 | ValueType::F32 => self.inst(&format!("movl {}, {}", reg.name32(), reg.name32())),
+            ValueType::I16 => self.inst(&format!("movswq {}, {}", reg.name16(), reg)),
+            ValueType::I32 => self.inst(&format!("movslq {}, {}", reg.name32(), reg)),
+            ValueType::U64 | ValueType::I64 | ValueType::F64 | ValueType::Mem(_) => {}
+        }
+    }
+    /// Load value type from memory to register, extended to 64-bit.
+    fn load_memory(&mut self, ty: &ValueType, address: &str, reg: Gpr) {
+        let text = match ty {
+            ValueType::U8 => format!("movzbq {}, {}", address, reg),
+            ValueType::U16 => format!("movzwq {}, {}", address, reg),
+            ValueType::U32 | ValueType::F32 => format!("movl {}, {}", address, reg.name32()),
+            ValueType::I16 => format!("movswq {}, {}", address, reg),
+            ValueType::I32 => format!("movslq {}, {}", address, reg),
+            ValueType::U64 | ValueType::I64 | ValueType::F64 | ValueType::Mem(_) => {
+                format!("movq {}, {}", address, reg)
+            }
+        };
+        self.inst(&text);
+    }
+    /// Store low bytes of register to memory by size of value type.
+    fn store_memory(&mut self, ty: &ValueType, reg: Gpr, address: &str) {
+        let suffix = match ty.get_size() {
+            1 => "b",
+            2 => "w",
+            4 => "l",
+            _ => "q",
+        };
+        self.inst(&format!(
+            "mov{} {}, {}",
+            suffix,
+            reg.name_of_size(ty.get_size()),
+            address
+        ));
+    }
+}
+
+/// Prologue and epilogue.
+impl FunctionEmitter<'_> {
+    fn emit_prologue(&mut self) {
+        let name = self.name;
+        self.inst(&format!(".globl {}", name));
+        self.inst(&format!(".type {}, @function", name));
+        self.label(name);
+        self.inst("pushq %rbp");
+        self.inst("movq %rsp, %rbp");
+        for reg in self.frame.saved_regs.clone() {
+            self.inst(&format!("pushq {}", reg));
+        }
+        if self.frame.frame_size > 0 {
+            self.inst(&format!("subq ${}, %rsp", self.frame.frame_size));
+        }
+        // move arguments to home of params.
+        let (mut int_index, mut float_index, mut stack_index) = (0, 0, 0);
+        for param in self.function.entities.params.clone() {
+            let ty = self.value_type(param);
+            if is_float(&ty) && float_index < FLOAT_ARG_REGS_COUNT {
+                self.inst(&format!("movq %xmm{}, %rax", float_index));
+                float_index += 1;
+            } else if !is_float(&ty) && int_index < INT_ARG_REGS.len() {
+                self.inst(&format!("movq {}, %rax", INT_ARG_REGS[int_index]));
+                int_index += 1;
+            } else {
+                self.inst(&format!("movq {}(%rbp), %rax", 16 + 8 * stack_index));
+                stack_index += 1;
+            }
+            self.normalize(Gpr::Rax, &ty);
+            self.store(Gpr::Rax, param);
+        }
+    }
+    fn emit_epilogue(&mut self) {
+        let ret_label = self.ret_label();
+        self.label(&ret_label);
+        if !self.frame.saved_regs.is_empty() {
+            self.inst(&format!("leaq -{}(%rbp), %rsp", 8 * self.frame.saved_regs.len()));
+        }
+        for reg in self.frame.saved_regs.clone().into_iter().rev() {
+            self.inst(&format!("popq {}", reg));
+        }
+        self.inst("popq %rbp");
+        self.inst("ret");
+        let name = self.name;
+        self.inst(&format!(".size {}, .-{}", name, name));
+    }
+}
+
+/// Instruction selection.
+impl FunctionEmitter<'_> {
+    fn emit_inst(&mut self, block: Block, inst: Instruction) -> EmitResult {
+        let inst_data = self.function.get_inst_data(inst);
+        match inst_data {
+            InstructionData::UnaryConst { constant, .. } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(result);
+                let constant_data = self
+                    .function
+                    .constants
+                    .get(constant)
+                    .ok_or(CodegenErrorKind::UnknownConstant)?;
+                self.mov_imm(constant_bits(&constant_data.bytes, &ty), Gpr::Rax);
+                self.store(Gpr::Rax, result);
+            }
+            InstructionData::Unary { opcode, value } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(result);
+                self.load(*value, Gpr::Rax);
+                match (opcode, is_float(&ty)) {
+                    (OpCode::Mov, _) => {}
+                    (OpCode::Neg, true) => {
+                        self.inst(&format!("btcq ${}, %rax", int_bits(&ty) - 1));
+                    }
+                    (OpCode::Neg, false) => self.inst("negq %rax"),
+                    (OpCode::BitwiseNot, false) => self.inst("notq %rax"),
+                    _ => return Err(CodegenErrorKind::UnsupportedOperation(*opcode, ty)),
+                }
+                self.normalize(Gpr::Rax, &ty);
+                self.store(Gpr::Rax, result);
+            }
+            InstructionData::Move { src, .. } => {
+                let result = self.result_of(inst)?;
+                self.load(*src, Gpr::Rax);
+                self.store(Gpr::Rax, result);
+            }
+            InstructionData::Binary { opcode, args } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(result);
+                self.load(args[0], Gpr::Rax);
+                self.load(args[1], Gpr::Rcx);
+                self.emit_binary(*opcode, &ty)?;
+                self.store(Gpr::Rax, result);
+            }
+            InstructionData::BinaryI { opcode, value, imm } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(result);
+                if is_float(&ty) {
+                    return Err(CodegenErrorKind::UnsupportedOperation(*opcode, ty));
+                }
+                self.load(*value, Gpr::Rax);
+                self.mov_imm(normalize_bits(u64::from_le_bytes(imm.get_bytes()), &ty), Gpr::Rcx);
+                self.emit_binary(*opcode, &ty)?;
+                self.store(Gpr::Rax, result);
+            }
+            InstructionData::Icmp { flag, args, .. } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(args[0]);
+                self.load(args[0], Gpr::Rax);
+                self.load(args[1], Gpr::Rcx);
+                self.inst("cmpq %rcx, %rax");
+                let condition = match (flag, is_signed(&ty)) {
+                    (CmpFlag::Eq, _) => "e",
+                    (CmpFlag::NotEq, _) => "ne",
+                    (CmpFlag::Gt, true) => "g",
+                    (CmpFlag::Gteq, true) => "ge",
+                    (CmpFlag::Lt, true) => "l",
+                    (CmpFlag::LtEq, true) => "le",
+                    (CmpFlag::Gt, false) => "a",
+                    (CmpFlag::Gteq, false) => "ae",
+                    (CmpFlag::Lt, false) => "b",
+                    (CmpFlag::LtEq, false) => "be",
+                };
+                self.inst(&format!("set{} %al", condition));
+                self.inst("movzbq %al, %rax");
+                self.store(Gpr::Rax, result);
+            }
+            InstructionData::Fcmp { opcode, flag, args } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(args[0]);
+                if !is_float(&ty) {
+                    return Err(CodegenErrorKind::UnsupportedOperation(*opcode, ty));
+                }
+                self.emit_fcmp(*flag, args, &ty);
+                self.store(Gpr::Rax, result);
+            }
+            InstructionData::Call { name, params, .. } => {
+                let result = self.function.get_inst_result(inst);
+                self.emit_call(*name, params, result)?;
+            }
+            InstructionData::Ret { value, .. } => {
+                if let Some(value) = value {
+                    self.load(*value, Gpr::Rax);
+                    if is_float(&self.value_type(*value)) {
+                        self.inst("movq %rax, %xmm0");
+                    }
+                }
+                let ret_label = self.ret_label();
+                self.inst(&format!("jmp {}", ret_label));
+            }
+            InstructionData::Convert { opcode, src } => {
+                let result = self.result_of(inst)?;
+                let src_ty = self.value_type(*src);
+                let dst_ty = self.value_type(result);
+                self.load(*src, Gpr::Rax);
+                self.emit_convert(*opcode, &src_ty, &dst_ty)?;
+                self.store(Gpr::Rax, result);
+            }
+            InstructionData::StackAlloc { .. } => {
+                let result = self.result_of(inst)?;
+                let offset = self.frame.stack_slots[&inst];
+                self.inst(&format!("leaq {}(%rbp), %rax", offset));
+                self.store(Gpr::Rax, result);
+            }
+            InstructionData::LoadRegister { base, offset, .. } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(result);
+                self.load(*base, Gpr::Rax);
+                self.load_memory(&ty, &format!("{}(%rax)", offset.0), Gpr::Rax);
+                self.store(Gpr::Rax, result);
+            }
+            InstructionData::StoreRegister { base, offset, src, .. } => {
+                let ty = self.value_type(*src);
+                self.load(*base, Gpr::Rax);
+                self.load(*src, Gpr::Rcx);
+                self.store_memory(&ty, Gpr::Rcx, &format!("{}(%rax)", offset.0));
+            }
+            InstructionData::GlobalLoad { base, offset, .. } => {
+                let result = self.result_of(inst)?;
+                let ty = self.value_type(result);
+                self.emit_global_address(*base)?;
+                self.load_memory(&ty, &format!("{}(%rax)", offset.0), Gpr::Rax);
+                self.store(Gpr::Rax, result);
+            }
+            InstructionData::GlobalStore { base, offset, src, .. } => {
+                let ty = self.value_type(*src);
+                self.emit_global_address(*base)?;
+                self.load(*src, Gpr::Rcx);
+                self.store_memory(&ty, Gpr::Rcx, &format!("{}(%rax)", offset.0));
+            }
+            InstructionData::BrIf {
+                test, conseq, alter, ..
+            } => {
+                self.load(*test, Gpr::Rax);
+                self.inst("testq %rax, %rax");
+                let conseq_copies = self.get_phi_copies(block, *conseq)?;
+                let conseq_label = self.block_label(*conseq);
+                let edge_label = if conseq_copies.is_empty() {
+                    self.inst(&format!("jne {}", conseq_label));
+                    None
+                } else {
+                    let edge_label = self.new_label();
+                    self.inst(&format!("jne {}", edge_label));
+                    Some(edge_label)
+                };
+                self.emit_jump(block, *alter)?;
+                if let Some(edge_label) = edge_label {
+                    self.label(&edge_label);
+                    self.emit_parallel_copies(conseq_copies);
+                    self.inst(&format!("jmp {}", conseq_label));
+                }
+            }
+            InstructionData::Jump { dst, .. } => self.emit_jump(block, *dst)?,
+            // phi is resolved by copies at the end of predecessors.
+            InstructionData::Phi { .. } => {}
+            InstructionData::Comment(comment) => {
+                let comment = comment.clone();
+                self.inst(&format!("#{}", comment));
+            }
+        }
+        Ok(())
+    }
+    /// Apply binary opcode to `rax` and `rcx`, result is in `rax`.
+    fn emit_binary(&mut self, opcode: OpCode, ty: &ValueType) -> EmitResult {
+        if is_float(ty) {
+            let suffix = sse_suffix(ty);
+            self.inst("movq %rax, %xmm0");
+            self.inst("movq %rcx, %xmm1");
+            match opcode {
+                OpCode::FAdd => self.inst(&format!("add{} %xmm1, %xmm0", suffix)),
+                OpCode::FSub => self.inst(&format!("sub{} %xmm1, %xmm0", suffix)),
+                OpCode::FMul => self.inst(&format!("mul{} %xmm1, %xmm0", suffix)),
+                OpCode::FDivide => self.inst(&format!("div{} %xmm1, %xmm0", suffix)),
+                // there is no sse instruction for reminder, call `fmod` of libm.
+                OpCode::FReminder => {
+                    let callee = if *ty == ValueType::F32 { "fmodf" } else { "fmod" };
+                    self.inst(&format!("call {}@PLT", callee));
+                }
+                _ => return Err(CodegenErrorKind::UnsupportedOperation(opcode, ty.clone())),
+            }
+            self.inst("movq %xmm0, %rax");
+            self.normalize(Gpr::Rax, ty);
+            return Ok(());
+        }
+        match opcode {
+            OpCode::Add | OpCode::Addi => self.inst("addq %rcx, %rax"),
+            OpCode::Sub | OpCode::Subi => self.inst("subq %rcx, %rax"),
+            OpCode::Mul | OpCode::Muli => self.inst("imulq %rcx, %rax"),
+            OpCode::BitwiseOR => self.inst("orq %rcx, %rax"),
+            OpCode::BitwiseAnd => self.inst("andq %rcx, %rax"),
+            OpCode::ShiftLeft | OpCode::ShiftRight => {
+                // shift amount is masked by width of type.
+                self.inst(&format!("andl ${}, %ecx", int_bits(ty) - 1));
+                let shift = match (opcode, is_signed(ty)) {
+                    (OpCode::ShiftLeft, _) => "shlq",
+                    (_, true) => "sarq",
+                    (_, false) => "shrq",
+                };
+                self.inst(&format!("{} %cl, %rax", shift));
+            }
+            OpCode::Divide | OpCode::Dividei | OpCode::Reminder | OpCode::Reminderi => {
+                let is_divide = matches!(opcode, OpCode::Divide | OpCode::Dividei);
+                if is_signed(ty) {
+                    // `MIN / -1` overflow trap, result is wrapping as negation.
+                    let divide_label = self.new_label();
+                    let done_label = self.new_label();
+                    self.inst("cmpq $-1, %rcx");
+                    self.inst(&format!("jne {}", divide_label));
+                    if is_divide {
+                        self.inst("negq %rax");
+                    } else {
+                        self.inst("xorl %eax, %eax");
+                    }
+                    self.inst(&format!("jmp {}", done_label));
+                    self.label(&divide_label);
+                    self.inst("cqto");
+                    self.inst("idivq %rcx");
+                    if !is_divide {
+                        self.inst("movq %rdx, %rax");
+                    }
+                    self.label(&done_label);
+                } else {
+                    self.inst("xorl %edx, %edx");
+                    self.inst("divq %rcx");
+                    if !is_divide {
+                        self.inst("movq %rdx, %rax");
+                    }
+                }
+            }
+            _ => return Err(CodegenErrorKind::UnsupportedOperation(opcode, ty.clone())),
+        }
+        self.normalize(Gpr::Rax, ty);
+        Ok(())
+    }
+    /// Compare two floats, result is in `rax`. comparison with NaN is only true for
+    /// `noteq`, `lt` and `lteq` swap operands so unordered result is false.
+    fn emit_fcmp(&mut self, flag: CmpFlag, args: &[Value; 2], ty: &ValueType) {
+        let suffix = sse_suffix(ty);
+        self.load(args[0], Gpr::Rax);
+        self.load(args[1], Gpr::Rcx);
+        self.inst("movq %rax, %xmm0");
+        self.inst("movq %rcx, %xmm1");
+        match flag {
+            CmpFlag::Lt | CmpFlag::LtEq => self.inst(&format!("ucomi{} %xmm0, %xmm1", suffix)),
+            _ => self.inst(&format!("ucomi{} %xmm1, %xmm0", suffix)),
+        }
+        match flag {
+            CmpFlag::Eq => {
+                self.inst("sete %al");
+                self.inst("setnp %cl");
+                self.inst("andb %cl, %al");
+            }
+            CmpFlag::NotEq => {
+                self.inst("setne %al");
+                self.inst("setp %cl");
+                self.inst("orb %cl, %al");
+            }
+            CmpFlag::Gt | CmpFlag::Lt => self.inst("seta %al"),
+            CmpFlag::Gteq | CmpFlag::LtEq => self.inst("setae %al"),
+        }
+        self.inst("movzbq %al, %rax");
+    }
+    /// Convert `rax` from source type to destination type with `as` semantic, float to
+    /// integer is saturated and NaN is converted to zero.
+    fn emit_convert(&mut self, opcode: OpCode, src_ty: &ValueType, dst_ty: &ValueType) -> EmitResult {
+        match (is_float(src_ty), is_float(dst_ty)) {
+            (false, false) => {}
+            (false, true) => {
+                let suffix = sse_suffix(dst_ty);
+                if matches!(src_ty, ValueType::U64 | ValueType::Mem(_)) {
+                    // integer larger than `i64::MAX` is halved (keeping lowest bit for
+                    // rounding) and then doubled after conversion.
+                    let large_label = self.new_label();
+                    let done_label = self.new_label();
+                    self.inst("testq %rax, %rax");
+                    self.inst(&format!("js {}", large_label));
+                    self.inst(&format!("cvtsi2{}q %rax, %xmm0", suffix));
+                    self.inst(&format!("jmp {}", done_label));
+                    self.label(&large_label);
+                    self.inst("movq %rax, %rcx");
+                    self.inst("shrq %rcx");
+                    self.inst("andl $1, %eax");
+                    self.inst("orq %rax, %rcx");
+                    self.inst(&format!("cvtsi2{}q %rcx, %xmm0", suffix));
+                    self.inst(&format!("add{} %xmm0, %xmm0", suffix));
+                    self.label(&done_label);
+                } else {
+                    self.inst(&format!("cvtsi2{}q %rax, %xmm0", suffix));
+                }
+                self.inst("movq %xmm0, %rax");
+            }
+            (true, true) => {
+                if src_ty != dst_ty {
+                    self.inst("movq %rax, %xmm0");
+                    self.inst(&format!(
+                        "cvt{}2{} %xmm0, %xmm0",
+                        sse_suffix(src_ty),
+                        sse_suffix(dst_ty)
+                    ));
+                    self.inst("movq %xmm0, %rax");
+                }
+            }
+            (true, false) => self.emit_float_to_int(src_ty, dst_ty),
+        }
+        if opcode == OpCode::ToAddress && is_float(dst_ty) {
+            return Err(CodegenErrorKind::UnsupportedOperation(opcode, dst_ty.clone()));
+        }
+        self.normalize(Gpr::Rax, dst_ty);
+        Ok(())
+    }
+    fn emit_float_to_int(&mut self, src_ty: &ValueType, dst_ty: &ValueType) {
+        let suffix = sse_suffix(src_ty);
+        let (min, max) = int_range(dst_ty);
+        let [zero_label, low_label, high_label, done_label] = [(); 4].map(|_| self.new_label());
+        self.inst("movq %rax, %xmm0");
+        self.inst(&format!("ucomi{} %xmm0, %xmm0", suffix));
+        self.inst(&format!("jp {}", zero_label));
+        self.mov_imm(float_bits(min as f64, src_ty), Gpr::Rcx);
+        self.inst("movq %rcx, %xmm1");
+        self.inst(&format!("ucomi{} %xmm1, %xmm0", suffix));
+        self.inst(&format!("jbe {}", low_label));
+        self.mov_imm(float_bits(max as f64, src_ty), Gpr::Rcx);
+        self.inst("movq %rcx, %xmm1");
+        self.inst(&format!("ucomi{} %xmm1, %xmm0", suffix));
+        self.inst(&format!("jae {}", high_label));
+        if max == u64::MAX {
+            // float not less than `2^63` is out of range of signed conversion, subtract
+            // `2^63` before conversion and add it back by flipping sign bit.
+            let large_label = self.new_label();
+            self.mov_imm(float_bits(2_f64.powi(63), src_ty), Gpr::Rcx);
+            self.inst("movq %rcx, %xmm1");
+            self.inst(&format!("ucomi{} %xmm1, %xmm0", suffix));
+            self.inst(&format!("jae {}", large_label));
+            self.inst(&format!("cvtt{}2si %xmm0, %rax", suffix));
+            self.inst(&format!("jmp {}", done_label));
+            self.label(&large_label);
+            self.inst(&format!("sub{} %xmm1, %xmm0", suffix));
+            self.inst(&format!("cvtt{}2si %xmm0, %rax", suffix));
+            self.inst("btcq $63, %rax");
+        } else {
+            self.inst(&format!("cvtt{}2si %xmm0, %rax", suffix));
+        }
+        self.inst(&format!("jmp {}", done_label));
+        self.label(&zero_label);
+        self.inst("xorl %eax, %eax");
+        self.inst(&format!("jmp {}", done_label));
+        self.label(&low_label);
+        self.mov_imm(min as u64, Gpr::Rax);
+        self.inst(&format!("jmp {}", done_label));
+        self.label(&high_label);
+        self.mov_imm(max, Gpr::Rax);
+        self.label(&done_label);
+    }
+    /// Call function with System V calling convention, integer and address arguments
+    /// are passed by `rdi`, `rsi`, `rdx`, `rcx`, `r8`, `r9`, float arguments are passed
+    /// by `xmm0` - `xmm7`, others are pushed to stack from right to left.
+    fn emit_call(&mut self, func_ref: FunctionRef, params: &[Value], result: Option<Value>) -> EmitResult {
+        let external_func = &self.function.external_funcs[&func_ref];
+        let callee = self.resolve_symbol(&external_func.name)?;
+        let mut int_args = Vec::new();
+        let mut float_args = Vec::new();
+        let mut stack_args = Vec::new();
+        for param in params {
+            if is_float(&self.value_type(*param)) {
+                if float_args.len() < FLOAT_ARG_REGS_COUNT {
+                    float_args.push(*param);
+                    continue;
+                }
+            } else if int_args.len() < INT_ARG_REGS.len() {
+                int_args.push(*param);
+                continue;
+            }
+            stack_args.push(*param);
+        }
+        // keep `rsp` 16 bytes aligned at call.
+        let mut stack_size = 8 * stack_args.len();
+        if stack_args.len() % 2 == 1 {
+            self.inst("subq $8, %rsp");
+            stack_size += 8;
+        }
+        for param in stack_args.into_iter().rev() {
+            self.load(param, Gpr::Rax);
+            self.inst("pushq %rax");
+        }
+        for (index, param) in float_args.into_iter().enumerate() {
+            self.load(param, Gpr::Rax);
+            self.inst(&format!("movq %rax, %xmm{}", index));
+        }
+        for (param, reg) in int_args.into_iter().zip(INT_ARG_REGS) {
+            self.load(param, reg);
+        }
+        self.inst(&format!("call {}@PLT", callee));
+        if stack_size > 0 {
+            self.inst(&format!("addq ${}, %rsp", stack_size));
+        }
+        if let Some(result) = result {
+            let ty = self.value_type(result);
+            if is_float(&ty) {
+                self.inst("movq %xmm0, %rax");
+            }
+            // callee may leave garbage in high bits of narrow integer.
+            self.normalize(Gpr::Rax, &ty);
+            self.store(Gpr::Rax, result);
+        }
+        Ok(())
+    }
+    /// Compute address of global value into `rax`.
+    fn emit_global_address(&mut self, global: GlobalValue) -> EmitResult {
+        let Some(global_data) = self.function.global_values.get(&global) else {
+            return Err(CodegenErrorKind::UnknownGlobalValue(global));
+        };
+        match global_data.clone() {
+            GlobalValueData::Symbol { name } => {
+                let symbol = self.resolve_symbol(&name)?;
+                self.inst(&format!("leaq {}(%rip), %rax", symbol));
+            }
+            GlobalValueData::AddI { base, offset, .. } => {
+                self.emit_global_address(base)?;
+                self.inst(&format!("leaq {}(%rax), %rax", offset.0));
+            }
+            GlobalValueData::Load { base, offset, ty } => {
+                self.emit_global_address(base)?;
+                self.load_memory(&ty, &format!("{}(%rax)", offset.0), Gpr::Rax);
+            }
+        }
+        Ok(())
+    }
+    fn resolve_symbol(&self, name: &ExternalName) -> Result<String, CodegenErrorKind> {
+        get_symbol_of_external_name(self.module, name)
+            .map(|symbol| symbol.to_owned())
+            .ok_or(CodegenErrorKind::UnresolvedSymbol(name.clone()))
+    }
+    /// Jump to block, copy arguments of phis in block before jump.
+    fn emit_jump(&mut self, from: Block, to: Block) -> EmitResult {
+        let copies = self.get_phi_copies(from, to)?;
+        self.emit_parallel_copies(copies);
+        let label = self.block_label(to);
+        self.inst(&format!("jmp {}", label));
+        Ok(())
+    }
+    /// Get (source, destination) of phis in block for edge from predecessor.
+    fn get_phi_copies(&self, from: Block, to: Block) -> Result<Vec<(Value, Value)>, CodegenErrorKind> {
+        let mut copies = Vec::new();
+        for inst in self.function.get_insts_of_block(to) {
+            let InstructionData::Phi { from: sources, .. } = self.function.get_inst_data(inst) else {
+                continue;
+            };
+            let Some((_, source)) = sources.iter().find(|(pred, _)| *pred == from) else {
+                return Err(CodegenErrorKind::MissingPhiArgument { block: to, from });
+            };
+            copies.push((*source, self.result_of(inst)?));
+        }
+        Ok(copies)
+    }
+    /// Phis of a block are assigned at the same time, so sources are pushed to stack
+    /// first and then popped to destinations.
+    fn emit_parallel_copies(&mut self, copies: Vec<(Value, Value)>) {
+        if let [(source, destination)] = copies[..] {
+            self.load(source, Gpr::Rax);
+            self.store(Gpr::Rax, destination);
+            return;
+        }
+        for (source, _) in &copies {
+            self.load(*source, Gpr::Rax);
+            self.inst("pushq %rax");
+        }
+        for (_, destination) in copies.into_iter().rev() {
+            self.inst("popq %rax");
+            self.store(Gpr::Rax, destination);
+        }
+    }
+}
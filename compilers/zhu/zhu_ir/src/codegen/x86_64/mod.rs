@@ -0,0 +1,21 @@
+use crate::codegen::x86_64::isel::FunctionEmitter;
+use crate::codegen::{emit_data_objects, get_defined_functions, CodegenError};
+use crate::entities::module::Module;
+
+pub mod frame;
+pub mod isel;
+pub mod register;
+
+/// ## x86-64 Backend
+/// Emit GNU assembly text (AT&T syntax) of module for x86-64 System V ABI. Every
+/// function with body and every data object is exported as global symbol, the
+/// output can be assembled and linked by host toolchain directly.
+pub fn emit_module(module: &Module) -> Result<String, CodegenError> {
+    let mut output = String::from("    .text\n");
+    for (_, name, function) in get_defined_functions(module) {
+        output.push_str(&FunctionEmitter::new(module, name, function)?.emit()?);
+    }
+    emit_data_objects(module, &mut output)?;
+    output.push_str("    .section .note.GNU-stack,\"\",@progbits\n");
+    Ok(output)
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+use crate::codegen::regalloc::{RegisterClass, RegisterTarget};
+use crate::entities::r#type::ValueType;
+
+/// ## General Purpose Register
+/// 64-bit general purpose registers of x86-64, printed in AT&T syntax.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub enum Gpr {
+    Rax,
+    Rcx,
+    Rdx,
+    Rbx,
+    Rsp,
+    Rbp,
+    Rsi,
+    Rdi,
+    R8,
+    R9,
+    R10,
+    R11,
+    R12,
+    R13,
+    R14,
+    R15,
+}
+
+/// Registers of integer arguments in System V calling convention, in order.
+pub const INT_ARG_REGS: [Gpr; 6] = [Gpr::Rdi, Gpr::Rsi, Gpr::Rdx, Gpr::Rcx, Gpr::R8, Gpr::R9];
+/// Count of xmm registers used to pass float arguments.
+pub const FLOAT_ARG_REGS_COUNT: usize = 8;
+/// Callee saved registers which can hold value across call, used as home of values.
+pub const ALLOCATABLE_REGS: [Gpr; 5] = [Gpr::Rbx, Gpr::R12, Gpr::R13, Gpr::R14, Gpr::R15];
+
+impl Gpr {
+    /// Name of the low 8 bits of register.
+    pub fn name8(&self) -> &'static str {
+        match self {
+            Gpr::Rax => "%al",
+            Gpr::Rcx => "%cl",
+            Gpr::Rdx => "%dl",
+            Gpr::Rbx => "%bl",
+            Gpr::Rsp => "%spl",
+            Gpr::Rbp => "%bpl",
+            Gpr::Rsi => "%sil",
+            Gpr::Rdi => "%dil",
+            Gpr::R8 => "%r8b",
+            Gpr::R9 => "%r9b",
+            Gpr::R10 => "%r10b",
+            Gpr::R11 => "%r11b",
+            Gpr::R12 => "%r12b",
+            Gpr::R13 => "%r13b",
+            Gpr::R14 => "%r14b",
+            Gpr::R15 => "%r15b",
+        }
+    }
+    /// Name of the low 16 bits of register.
+    pub fn name16(&self) -> &'static str {
+        match self {
+            Gpr::Rax => "%ax",
+            Gpr::Rcx => "%cx",
+            Gpr::Rdx => "%dx",
+            Gpr::Rbx => "%bx",
+            Gpr::Rsp => "%sp",
+            Gpr::Rbp => "%bp",
+            Gpr::Rsi => "%si",
+            Gpr::Rdi => "%di",
+            Gpr::R8 => "%r8w",
+            Gpr::R9 => "%r9w",
+            Gpr::R10 => "%r10w",
+            Gpr::R11 => "%r11w",
+            Gpr::R12 => "%r12w",
+            Gpr::R13 => "%r13w",
+            Gpr::R14 => "%r14w",
+            Gpr::R15 => "%r15w",
+        }
+    }
+    /// Name of the low 32 bits of register.
+    pub fn name32(&self) -> &'static str {
+        match self {
+            Gpr::Rax => "%eax",
+            Gpr::Rcx => "%ecx",
+            Gpr::Rdx => "%edx",
+            Gpr::Rbx => "%ebx",
+            Gpr::Rsp => "%esp",
+            Gpr::Rbp => "%ebp",
+            Gpr::Rsi => "%esi",
+            Gpr::Rdi => "%edi",
+            Gpr::R8 => "%r8d",
+            Gpr::R9 => "%r9d",
+            Gpr::R10 => "%r10d",
+            Gpr::R11 => "%r11d",
+            Gpr::R12 => "%r12d",
+            Gpr::R13 => "%r13d",
+            Gpr::R14 => "%r14d",
+            Gpr::R15 => "%r15d",
+        }
+    }
+    /// Name of the whole 64-bit register.
+    pub fn name64(&self) -> &'static str {
+        match self {
+            Gpr::Rax => "%rax",
+            Gpr::Rcx => "%rcx",
+            Gpr::Rdx => "%rdx",
+            Gpr::Rbx => "%rbx",
+            Gpr::Rsp => "%rsp",
+            Gpr::Rbp => "%rbp",
+            Gpr::Rsi => "%rsi",
+            Gpr::Rdi => "%rdi",
+            Gpr::R8 => "%r8",
+            Gpr::R9 => "%r9",
+            Gpr::R10 => "%r10",
+            Gpr::R11 => "%r11",
+            Gpr::R12 => "%r12",
+            Gpr::R13 => "%r13",
+            Gpr::R14 => "%r14",
+            Gpr::R15 => "%r15",
+        }
+    }
+    /// Name of register with given width in bytes.
+    pub fn name_of_size(&self, size: usize) -> &'static str {
+        match size {
+            1 => self.name8(),
+            2 => self.name16(),
+            4 => self.name32(),
+            _ => self.name64(),
+        }
+    }
+}
+
+impl fmt::Display for Gpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name64())
+    }
+}
+
+/// Register target of x86-64 for register allocator, float value is kept as raw bits
+/// in general purpose register as backend does, since no xmm register is callee saved.
+pub struct X86_64Registers;
+
+impl RegisterTarget for X86_64Registers {
+    fn register_class(&self, _ty: &ValueType) -> RegisterClass {
+        RegisterClass::Int
+    }
+    fn allocatable_registers(&self, class: RegisterClass) -> Vec<&'static str> {
+        match class {
+            RegisterClass::Int => ALLOCATABLE_REGS.iter().map(|reg| reg.name64()).collect(),
+            RegisterClass::Float => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::fmt;
+use std::fs::read_to_string;
+use std::io::Read;
+
+use crate::codegen::regalloc::allocate_registers;
+use crate::codegen::riscv64::register::Riscv64Registers;
+use crate::codegen::x86_64::register::X86_64Registers;
+use crate::entities::function::Function;
+use crate::entities::module::{FuncId, Module, ModuleLevelId};
+use crate::formatter::dot::format_dot;
+use crate::formatter::Formatter;
+use crate::frontend::error::{format_parse_errors, ParseError};
+use crate::frontend::parse;
+use crate::pass::analysis::alias::alias_analysis;
+use crate::pass::analysis::cfg::{cfg_anylysis, ControlFlowGraph};
+use crate::pass::analysis::domtree::domtree_analysis;
+use crate::pass::analysis::induction_variable::induction_variable_analysis;
+use crate::pass::analysis::liveness::liveness_analysis;
+use crate::pass::analysis::loop_forest::loop_forest_analysis;
+use crate::pass::analysis::rpo::{revrese_post_order_analysis, RevresePostOrder};
+use crate::pass::manager::{PassManager, PassManagerError};
+use crate::pass::opt::lcm::anticipate_expr::anticipate_expression_anaylsis;
+use crate::pass::opt::lcm::earliest::earliest_expression_anaylsis;
+use crate::pass::opt::lcm::later::later_expression_anaylsis;
+use crate::pass::opt::lcm::postponable_expr::postponable_expression_anaylsis;
+use crate::pass::opt::lcm::used_expr::used_expression_anaylsis;
+use crate::pass::opt::lcm::will_be_available_expr::will_be_available_expression_anaylsis;
+use crate::pass::opt::licm::natural_loop::{natural_loop_analysis, NaturalLoop};
+use crate::pass::FormatTable;
+
+pub const USAGE: &str = "Usage: zhu-opt [OPTIONS] [FILE...]
+
+Parse Zhu IR files (or stdin when no file or `-` is given), run passes and
+print the formatted module, analysis tables or control flow graphs.
+
+Options:
+  -p, --passes <PIPELINE>   comma separated passes, for example `mem2reg,sccp,dce`
+  -a, --analysis <NAMES>    print analysis tables instead of module, comma separated
+                            or repeated, one of anticipate, will-be-available,
+                            earliest, postponable, later, used, liveness, alias,
+                            loop-forest, induction-variables, regalloc-x86-64,
+                            regalloc-riscv64
+  -f, --function <NAME>     only run and print given function, can be repeated
+      --dot                 print control flow graph in graphviz dot instead of module
+      --verify-each         run verifier after each pass
+  -o, --output <FILE>       write output to file instead of stdout
+  -h, --help                print this message
+";
+
+/// Analyses which table can be printed by driver, tables of lazy code motion come first
+/// in the order of lazy code motion.
+const ANALYSIS_NAMES: [&str; 12] = [
+    "anticipate",
+    "will-be-available",
+    "earliest",
+    "postponable",
+    "later",
+    "used",
+    "liveness",
+    "alias",
+    "loop-forest",
+    "induction-variables",
+    "regalloc-x86-64",
+    "regalloc-riscv64",
+];
+
+/// Options of `zhu-opt` command.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DriverOptions {
+    pub inputs: Vec<String>,
+    pub pipeline: String,
+    pub analyses: Vec<String>,
+    pub functions: Vec<String>,
+    pub dot: bool,
+    pub verify_each: bool,
+    pub output: Option<String>,
+    pub help: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DriverError {
+    Usage(String),
+    Io { path: String, message: String },
+    Parse { path: String, errors: Vec<ParseError> },
+    PassManager(PassManagerError),
+    UnknownFunction(String),
+}
+
+impl fmt::Display for DriverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DriverError::Usage(message) => write!(f, "[Error]: {}.\n\n{}", message, USAGE),
+            DriverError::Io { path, message } => write!(f, "[Error]: can not access `{}`: {}", path, message),
+            DriverError::Parse { path, errors } => {
+                write!(
+                    f,
+                    "[Error]: failed to parse `{}`.\n{}",
+                    path,
+                    format_parse_errors(errors)
+                )
+            }
+            DriverError::PassManager(error) => write!(f, "[Error]: {}", error),
+            DriverError::UnknownFunction(name) => write!(f, "[Error]: function `{}` is not in module", name),
+        }
+    }
+}
+
+impl From<PassManagerError> for DriverError {
+    fn from(error: PassManagerError) -> Self {
+        DriverError::PassManager(error)
+    }
+}
+
+/// Parse command line arguments (without program name).
+pub fn parse_args<I>(args: I) -> Result<DriverOptions, DriverError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut options = DriverOptions::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value_of = |flag: &str| {
+            args.next()
+                .ok_or_else(|| DriverError::Usage(format!("option `{}` require a value", flag)))
+        };
+        match arg.as_str() {
+            "-p" | "--passes" => {
+                let pipeline = value_of(&arg)?;
+                if options.pipeline.is_empty() {
+                    options.pipeline = pipeline;
+                } else {
+                    options.pipeline = format!("{},{}", options.pipeline, pipeline);
+                }
+            }
+            "-a" | "--analysis" => {
+                for name in value_of(&arg)?.split(',').map(str::trim) {
+                    if !ANALYSIS_NAMES.contains(&name) {
+                        return Err(DriverError::Usage(format!("unknown analysis `{}`", name)));
+                    }
+                    options.analyses.push(name.to_owned());
+                }
+            }
+            "-f" | "--function" => options.functions.push(value_of(&arg)?),
+            "-o" | "--output" => options.output = Some(value_of(&arg)?),
+            "--dot" => options.dot = true,
+            "--verify-each" => options.verify_each = true,
+            "-h" | "--help" => options.help = true,
+            "-" => options.inputs.push(arg),
+            _ if arg.starts_with('-') => return Err(DriverError::Usage(format!("unknown option `{}`", arg))),
+            _ => options.inputs.push(arg),
+        }
+    }
+    Ok(options)
+}
+
+/// Read all inputs of options and run them, output of each input is concatenated.
+pub fn run(options: &DriverOptions) -> Result<String, DriverError> {
+    let inputs = if options.inputs.is_empty() {
+        vec!["-".to_owned()]
+    } else {
+        options.inputs.clone()
+    };
+    let mut output = String::new();
+    for path in &inputs {
+        let source = read_input(path)?;
+        output.push_str(&run_source(options, path, &source)?);
+    }
+    Ok(output)
+}
+
+fn read_input(path: &str) -> Result<String, DriverError> {
+    let to_error = |error: std::io::Error| DriverError::Io {
+        path: path.to_owned(),
+        message: error.to_string(),
+    };
+    if path == "-" {
+        let mut source = String::new();
+        std::io::stdin().read_to_string(&mut source).map_err(to_error)?;
+        Ok(source)
+    } else {
+        read_to_string(path).map_err(to_error)
+    }
+}
+
+/// Parse source, run passes on selected functions and format the result, `path` is
+/// only used to report error.
+pub fn run_source(options: &DriverOptions, path: &str, source: &str) -> Result<String, DriverError> {
+    let mut module = parse(source).map_err(|errors| DriverError::Parse {
+        path: path.to_owned(),
+        errors,
+    })?;
+    let func_ids = select_functions(&module, &options.functions)?;
+    let mut manager = PassManager::from_pipeline(&options.pipeline)?;
+    manager.set_verify_each(options.verify_each);
+    manager.run_on_module_functions(&mut module, &func_ids)?;
+    if options.analyses.is_empty() && !options.dot {
+        return Ok(Formatter::new().fmt_module_with_functions(&module, &func_ids));
+    }
+    let mut output = String::new();
+    for func_id in &func_ids {
+        let func = module.get_function(*func_id).unwrap();
+        let sym_name = module.get_symbol_by_module_id(ModuleLevelId::Func(*func_id)).unwrap();
+        if !options.analyses.is_empty() {
+            output.push_str(&format!("func {}:\n", sym_name));
+            output.push_str(&format_analyses(func, &module, &options.analyses));
+        }
+        if options.dot {
+            output.push_str(&format_dot(sym_name, func, &module, &cfg_anylysis(func)));
+        }
+    }
+    Ok(output)
+}
+
+/// Get function ids by names, all functions sorted by id when names is empty.
+fn select_functions(module: &Module, names: &[String]) -> Result<Vec<FuncId>, DriverError> {
+    if names.is_empty() {
+        let mut func_ids = module.functions.keys().copied().collect::<Vec<_>>();
+        func_ids.sort_by_key(|func_id| func_id.0);
+        return Ok(func_ids);
+    }
+    names
+        .iter()
+        .map(|name| match module.get_module_id_by_symbol(name) {
+            Some(ModuleLevelId::Func(func_id)) => Ok(*func_id),
+            _ => Err(DriverError::UnknownFunction(name.clone())),
+        })
+        .collect()
+}
+
+/// Format analysis tables of given names, tables are printed in the order of names.
+/// Control flow graph and reverse post order are shared, other analyses are only
+/// computed for the table requesting them.
+fn format_analyses(func: &Function, module: &Module, names: &[String]) -> String {
+    let cfg = cfg_anylysis(func);
+    let rpo = revrese_post_order_analysis(&cfg);
+    let mut output = String::new();
+    for name in names {
+        let table = match name.as_str() {
+            "liveness" => liveness_analysis(func, &cfg, &rpo).format_table(func, module),
+            "alias" => alias_analysis(func).format_table(func, module),
+            "loop-forest" => loop_forest_analysis(&get_sorted_natural_loops(func, &cfg)).format_table(func, module),
+            // one table for each natural loop, sorted by header.
+            "induction-variables" => get_sorted_natural_loops(func, &cfg)
+                .iter()
+                .map(|natural_loop| induction_variable_analysis(func, &cfg, natural_loop).format_table(func, module))
+                .collect(),
+            // same allocation as home of values in backends.
+            "regalloc-x86-64" => allocate_registers(func, &X86_64Registers).format_table(func, module),
+            "regalloc-riscv64" => allocate_registers(func, &Riscv64Registers).format_table(func, module),
+            _ => format_lcm_table(func, module, &cfg, &rpo, name),
+        };
+        output.push_str(&table);
+    }
+    output
+}
+
+/// Format table of lazy code motion analysis, analyses are computed in the order of
+/// lazy code motion until the requested one.
+fn format_lcm_table(
+    func: &Function,
+    module: &Module,
+    cfg: &ControlFlowGraph,
+    rpo: &RevresePostOrder,
+    name: &str,
+) -> String {
+    let anticipate = anticipate_expression_anaylsis(func, cfg, rpo);
+    if name == "anticipate" {
+        return anticipate.format_table(func, module);
+    }
+    let will_be_available = will_be_available_expression_anaylsis(func, cfg, rpo, &anticipate);
+    if name == "will-be-available" {
+        return will_be_available.format_table(func, module);
+    }
+    let earliest = earliest_expression_anaylsis(func, &anticipate, &will_be_available);
+    if name == "earliest" {
+        return earliest.format_table(func, module);
+    }
+    let postponable = postponable_expression_anaylsis(func, &earliest, cfg, rpo);
+    if name == "postponable" {
+        return postponable.format_table(func, module);
+    }
+    let later = later_expression_anaylsis(func, cfg, &earliest, &postponable);
+    if name == "later" {
+        return later.format_table(func, module);
+    }
+    used_expression_anaylsis(func, &anticipate, cfg, rpo, &later).format_table(func, module)
+}
+
+/// Get natural loops of function sorted by header and tail, make tables stable.
+fn get_sorted_natural_loops(func: &Function, cfg: &ControlFlowGraph) -> Vec<NaturalLoop> {
+    let dom = domtree_analysis(func, cfg);
+    let mut natural_loops = natural_loop_analysis(&dom, cfg);
+    natural_loops.sort_by_key(|natural_loop| (natural_loop.header.0, natural_loop.tail.0));
+    natural_loops
+}
@@ -1,10 +1,14 @@
 use super::instruction::Instruction;
+use crate::entities::util::sorted_serde::serialize_sorted_set;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Block(pub u32);
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub struct BlockData {
+    #[serde(serialize_with = "serialize_sorted_set")]
     pub phis: HashSet<Instruction>,
+    #[serde(serialize_with = "serialize_sorted_set")]
     pub insts: HashSet<Instruction>,
 }
 
@@ -1,8 +1,9 @@
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Constant(pub u32);
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub struct ConstantData {
     pub bytes: Vec<u8>,
 }
@@ -1,8 +1,9 @@
-use crate::entities::module::ModuleLevelId;
+use crate::entities::module::{DataId, FuncId, ModuleLevelId};
+use serde::{Deserialize, Serialize};
 
 /// ## Data Entuty: External Name
 /// The term `External` is a view from function aspect.
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub enum ExternalName {
     UserDefName { namespace: UserDefNamespace, value: u32 },
 }
@@ -20,8 +21,23 @@ impl ExternalName {
             },
         }
     }
+    /// Get module level id which external name refer to, return none if name is
+    /// not a data or function of module.
+    pub fn to_module_level_id(&self) -> Option<ModuleLevelId> {
+        match self {
+            Self::UserDefName {
+                namespace: UserDefNamespace::Data,
+                value,
+            } => Some(ModuleLevelId::Data(DataId(*value))),
+            Self::UserDefName {
+                namespace: UserDefNamespace::Function,
+                value,
+            } => Some(ModuleLevelId::Func(FuncId(*value))),
+            _ => None,
+        }
+    }
 }
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub enum UserDefNamespace {
     Data,
     Function,
@@ -1,9 +1,11 @@
 use crate::entities::block::{Block, BlockData};
 use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::util::sorted_serde::serialize_sorted_map;
 use crate::entities::value::{Value, ValueData};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 struct FunctionEntitiesNextContext {
     next_block_index: u32,
     next_inst_index: u32,
@@ -18,13 +20,17 @@ impl FunctionEntitiesNextContext {
         }
     }
 }
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct FunctionEntities {
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub blocks: HashMap<Block, BlockData>,
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub insts: HashMap<Instruction, InstructionData>,
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub values: HashMap<Value, ValueData>,
 
     pub params: Vec<Value>,
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub insts_result: HashMap<Instruction, Value>,
     next_context: FunctionEntitiesNextContext,
 }
@@ -120,6 +126,19 @@ impl FunctionEntities {
     pub(crate) fn set_value_next_index(&mut self, next_index: u32) {
         self.next_context.next_value_index = next_index;
     }
+    /// Should be only used by bytecode reader, reset the next index context of
+    /// instruction
+    pub(crate) fn set_inst_next_index(&mut self, next_index: u32) {
+        self.next_context.next_inst_index = next_index;
+    }
+    /// Get next index of block, instruction and value, used by bytecode writer.
+    pub(crate) fn get_next_indexes(&self) -> (u32, u32, u32) {
+        (
+            self.next_context.next_block_index,
+            self.next_context.next_inst_index,
+            self.next_context.next_value_index,
+        )
+    }
     /// Create a Value
     pub fn create_value(&mut self, value_data: ValueData) -> Value {
         let value = Value(self.value_index());
@@ -1,23 +1,27 @@
 use crate::entities::block::Block;
 use crate::entities::instruction::Instruction;
+use crate::entities::util::sorted_serde::serialize_sorted_map;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub struct BlockNode {
     pub prev: Option<Block>,
     pub next: Option<Block>,
     pub first_inst: Option<Instruction>,
     pub last_inst: Option<Instruction>,
 }
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub struct InstNode {
     pub block: Option<Block>,
     pub prev: Option<Instruction>,
     pub next: Option<Instruction>,
 }
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub struct FunctionLayout {
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub blocks: HashMap<Block, BlockNode>,
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub insts: HashMap<Instruction, InstNode>,
     pub first_block: Option<Block>,
     pub last_block: Option<Block>,
@@ -151,6 +155,7 @@ impl FunctionLayout {
         }
         self.blocks.insert(block, block_node);
     }
+    /// Remove a block and all instructions in it.
     pub fn remove_block(&mut self, block: Block) {
         let block_data = self.blocks.remove(&block).unwrap();
         let before = block_data.prev;
@@ -158,20 +163,20 @@ impl FunctionLayout {
 
         if let Some(before_block) = before {
             let before_block_data = self.blocks.get_mut(&before_block).unwrap();
-            before_block_data.next = after.clone();
+            before_block_data.next = after;
+        } else {
+            self.first_block = after;
         }
         if let Some(after_block) = after {
             let after_block_data = self.blocks.get_mut(&after_block).unwrap();
-            after_block_data.prev = before.clone()
+            after_block_data.prev = before;
+        } else {
+            self.last_block = before;
         }
         let mut cur_inst = block_data.first_inst;
-        loop {
-            if let Some(inst) = cur_inst {
-                let inst_data = self.insts.remove(&inst).unwrap();
-                cur_inst = inst_data.prev.clone();
-            } else {
-                break;
-            }
+        while let Some(inst) = cur_inst {
+            let inst_data = self.insts.remove(&inst).unwrap();
+            cur_inst = inst_data.next;
         }
     }
 }
@@ -3,18 +3,23 @@ use crate::entities::constant::{Constant, ConstantData};
 use crate::entities::external_name::ExternalName;
 use crate::entities::function::entites::FunctionEntities;
 use crate::entities::function::layout::FunctionLayout;
+use crate::entities::function::use_list::UseList;
 use crate::entities::global_value::{GlobalValue, GlobalValueData};
 use crate::entities::instruction::{Instruction, InstructionData};
 use crate::entities::r#type::{MemType, MemTypeData, ValueType};
+use crate::entities::runtime_value::RuntimeValue;
+use crate::entities::util::sorted_serde::serialize_sorted_map;
 use crate::entities::value::{Value, ValueData};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 pub mod entites;
 pub mod layout;
+pub mod use_list;
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct FunctionRef(pub u32);
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct FunctionSignature {
     pub params: Vec<ValueType>,
     pub return_type: Option<ValueType>,
@@ -29,13 +34,13 @@ impl FunctionSignature {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ExternalFunctionData {
     pub sig: FunctionSignature,
     pub name: ExternalName,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Function {
     // signature
     pub signature: FunctionSignature,
@@ -43,10 +48,14 @@ pub struct Function {
     pub entities: FunctionEntities,
     pub layout: FunctionLayout,
     // function info
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub mem_type: HashMap<MemType, MemTypeData>,
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub constants: HashMap<Constant, ConstantData>,
     // (might be) external info
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub external_funcs: HashMap<FunctionRef, ExternalFunctionData>,
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub global_values: HashMap<GlobalValue, GlobalValueData>,
 }
 
@@ -89,9 +98,30 @@ impl Function {
             ValueData::Param { index, .. } => &self.signature.params[*index],
         }
     }
+    /// Get constant value of value if it is defined by const instruction in layout.
+    pub fn get_constant_value(&self, value: Value) -> Option<RuntimeValue> {
+        let ValueData::Inst { inst, ty } = self.entities.values.get(&value)? else {
+            return None;
+        };
+        if !self.layout.insts.contains_key(inst) {
+            return None;
+        }
+        let InstructionData::UnaryConst { constant, .. } = self.entities.insts.get(inst)? else {
+            return None;
+        };
+        let constant_data = self.constants.get(constant)?;
+        Some(RuntimeValue::from_bytes(ty, &constant_data.bytes))
+    }
 }
 /// Data mutation for other entities.
 impl Function {
+    /// Add constant to function, key is one greater than the largest key, so it does
+    /// not overwrite existing constant when keys are sparse.
+    pub fn create_constant(&mut self, constant_data: ConstantData) -> Constant {
+        let constant = Constant(self.constants.keys().map(|constant| constant.0 + 1).max().unwrap_or(0));
+        self.constants.insert(constant, constant_data);
+        constant
+    }
     pub fn declar_external_function(&mut self, exfun_data: ExternalFunctionData) -> FunctionRef {
         let func_ref = FunctionRef(self.external_funcs.len() as u32);
         self.external_funcs.insert(func_ref, exfun_data);
@@ -138,6 +168,45 @@ impl Function {
     }
 }
 
+/// Def-use queries of function, each query scan instructions in layout once, pass which
+/// query or rewrite many values should use `compute_use_list` instead.
+impl Function {
+    /// Compute use list of every value in function.
+    pub fn compute_use_list(&self) -> UseList {
+        UseList::new(self)
+    }
+    /// Get instructions which use value, in layout order.
+    pub fn users_of(&self, value: Value) -> Vec<Instruction> {
+        let mut users = Vec::new();
+        let mut cur_block = self.layout.first_block;
+        while let Some(block) = cur_block {
+            for inst in self.get_insts_of_block(block) {
+                if self.get_inst_data(inst).contain_operand(value) {
+                    users.push(inst);
+                }
+            }
+            cur_block = self.layout.blocks.get(&block).unwrap().next;
+        }
+        users
+    }
+    /// Is value not used by any instruction in layout.
+    pub fn has_no_uses(&self, value: Value) -> bool {
+        self.insts()
+            .into_iter()
+            .all(|inst| !self.get_inst_data(inst).contain_operand(value))
+    }
+    /// Replace every use of `old` with `new` in instructions of layout.
+    pub fn replace_all_uses_with(&mut self, old: Value, new: Value) {
+        for inst in self.insts() {
+            for operand in self.entities.get_inst_data_mut(inst).get_operands_mut() {
+                if *operand == old {
+                    *operand = new;
+                }
+            }
+        }
+    }
+}
+
 /// Expose API from FunctionEntites.
 impl Function {
     /// Inherit from `FunctionEntities`.
@@ -197,6 +266,10 @@ impl Function {
         self.layout.get_insts_of_block(block)
     }
     /// Inherit from `FunctionLayout`.
+    pub fn remove_block(&mut self, block: Block) {
+        self.layout.remove_block(block);
+    }
+    /// Inherit from `FunctionLayout`.
     pub fn append_inst(&mut self, inst: Instruction, block: Block) {
         self.layout.append_inst(inst, block);
     }
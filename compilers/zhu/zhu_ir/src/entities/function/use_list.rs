@@ -0,0 +1,69 @@
+use crate::entities::function::Function;
+use crate::entities::instruction::Instruction;
+use crate::entities::value::Value;
+use std::collections::{HashMap, HashSet};
+
+/// ## Use List
+/// Def-use information of function, map every value to instructions in layout which use
+/// it as operand. use list is computed on demand by `Function::compute_use_list` in one
+/// scan of function, it is only kept up to date by mutations through its own methods, so
+/// a pass should drop it after changing operands in other way.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct UseList {
+    users: HashMap<Value, HashSet<Instruction>>,
+}
+
+impl UseList {
+    pub fn new(function: &Function) -> Self {
+        let mut use_list = Self::default();
+        for inst in function.insts() {
+            use_list.add_inst(function, inst);
+        }
+        use_list
+    }
+    /// Get instructions which use value, sorted by index of instruction.
+    pub fn users_of(&self, value: Value) -> Vec<Instruction> {
+        let mut users = self
+            .users
+            .get(&value)
+            .map(|users| users.iter().copied().collect::<Vec<_>>())
+            .unwrap_or_default();
+        users.sort_by_key(|inst| inst.0);
+        users
+    }
+    /// Is value not used by any instruction.
+    pub fn has_no_uses(&self, value: Value) -> bool {
+        self.users.get(&value).is_none_or(|users| users.is_empty())
+    }
+    /// Record operands of instruction, call it after instruction is inserted into layout.
+    pub fn add_inst(&mut self, function: &Function, inst: Instruction) {
+        for operand in function.get_inst_data(inst).get_operands() {
+            self.users.entry(operand).or_default().insert(inst);
+        }
+    }
+    /// Forget operands of instruction, call it when instruction is removed from layout.
+    pub fn remove_inst(&mut self, function: &Function, inst: Instruction) {
+        for operand in function.get_inst_data(inst).get_operands() {
+            if let Some(users) = self.users.get_mut(&operand) {
+                users.remove(&inst);
+            }
+        }
+    }
+    /// Replace every use of `old` with `new`, cost is linear to number of uses of `old`.
+    pub fn replace_all_uses_with(&mut self, function: &mut Function, old: Value, new: Value) {
+        if old == new {
+            return;
+        }
+        let Some(users) = self.users.remove(&old) else {
+            return;
+        };
+        for inst in &users {
+            for operand in function.get_inst_data_mut(*inst).get_operands_mut() {
+                if *operand == old {
+                    *operand = new;
+                }
+            }
+        }
+        self.users.entry(new).or_default().extend(users);
+    }
+}
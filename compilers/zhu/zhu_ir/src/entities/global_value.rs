@@ -1,9 +1,10 @@
 use crate::entities::external_name::ExternalName;
 use crate::entities::immediate::Offset;
 use crate::entities::r#type::ValueType;
-#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
+use serde::{Deserialize, Serialize};
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct GlobalValue(pub u32);
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub enum GlobalValueData {
     // external symbol, need to resolve by linker (rellocate)
     Symbol {
@@ -1,7 +1,8 @@
 use crate::entities::r#type::ValueType;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Immediate {
     U8(u8),
     U16(u16),
@@ -60,5 +61,5 @@ impl fmt::Display for Immediate {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub struct Offset(pub i32);
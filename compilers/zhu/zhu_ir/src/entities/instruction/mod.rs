@@ -7,13 +7,14 @@ use crate::entities::value::Value;
 
 use super::constant::Constant;
 use super::global_value::GlobalValue;
+use serde::{Deserialize, Serialize};
 
 pub mod opcode;
 /// ## Instruction
 /// A reference to instruction in a function.
-#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Instruction(pub u32);
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum InstructionData {
     // Const instruction
     UnaryConst {
@@ -124,16 +125,39 @@ impl InstructionData {
             InstructionData::Ret { value, .. } => value.iter().cloned().collect(),
             InstructionData::Convert { src, .. } => vec![src.clone()],
             InstructionData::StackAlloc { .. } => vec![],
-            InstructionData::LoadRegister { base, .. } | InstructionData::StoreRegister { base, .. } => {
-                vec![base.clone()]
-            }
-            InstructionData::GlobalLoad { .. } | InstructionData::GlobalStore { .. } => vec![],
+            InstructionData::LoadRegister { base, .. } => vec![*base],
+            InstructionData::StoreRegister { base, src, .. } => vec![*base, *src],
+            InstructionData::GlobalLoad { .. } => vec![],
+            InstructionData::GlobalStore { src, .. } => vec![*src],
             InstructionData::BrIf { test, .. } => vec![test.clone()],
             InstructionData::Jump { .. } => vec![],
             InstructionData::Phi { from, .. } => from.iter().map(|(_, v)| v.clone()).collect(),
             InstructionData::Comment(_) => vec![],
         }
     }
+    /// Get mutable reference of operands, order is same as `get_operands`.
+    pub fn get_operands_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            InstructionData::UnaryConst { .. } => vec![],
+            InstructionData::Unary { value, .. } => vec![value],
+            InstructionData::Binary { args, .. } => args.iter_mut().collect(),
+            InstructionData::BinaryI { value, .. } => vec![value],
+            InstructionData::Move { src, .. } => vec![src],
+            InstructionData::Icmp { args, .. } | InstructionData::Fcmp { args, .. } => args.iter_mut().collect(),
+            InstructionData::Call { params, .. } => params.iter_mut().collect(),
+            InstructionData::Ret { value, .. } => value.iter_mut().collect(),
+            InstructionData::Convert { src, .. } => vec![src],
+            InstructionData::StackAlloc { .. } => vec![],
+            InstructionData::LoadRegister { base, .. } => vec![base],
+            InstructionData::StoreRegister { base, src, .. } => vec![base, src],
+            InstructionData::GlobalLoad { .. } => vec![],
+            InstructionData::GlobalStore { src, .. } => vec![src],
+            InstructionData::BrIf { test, .. } => vec![test],
+            InstructionData::Jump { .. } => vec![],
+            InstructionData::Phi { from, .. } => from.iter_mut().map(|(_, v)| v).collect(),
+            InstructionData::Comment(_) => vec![],
+        }
+    }
     pub fn contain_operand(&self, operand: Value) -> bool {
         match self {
             InstructionData::UnaryConst { .. } => false,
@@ -148,10 +172,10 @@ impl InstructionData {
             InstructionData::Ret { value, .. } => value.iter().any(|value| *value == operand),
             InstructionData::Convert { src, .. } => *src == operand,
             InstructionData::StackAlloc { .. } => false,
-            InstructionData::LoadRegister { base, .. } | InstructionData::StoreRegister { base, .. } => {
-                *base == operand
-            }
-            InstructionData::GlobalLoad { .. } | InstructionData::GlobalStore { .. } => false,
+            InstructionData::LoadRegister { base, .. } => *base == operand,
+            InstructionData::StoreRegister { base, src, .. } => *base == operand || *src == operand,
+            InstructionData::GlobalLoad { .. } => false,
+            InstructionData::GlobalStore { src, .. } => *src == operand,
             InstructionData::BrIf { test, .. } => *test == operand,
             InstructionData::Jump { .. } => false,
             InstructionData::Phi { from, .. } => from.iter().any(|(_, value)| *value == operand),
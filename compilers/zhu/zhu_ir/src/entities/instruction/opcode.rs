@@ -1,5 +1,6 @@
+use serde::{Deserialize, Serialize};
 use std::fmt;
-#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, Serialize, Deserialize)]
 pub enum OpCode {
     // const instruction
     Uconst, // uconst
@@ -62,7 +63,7 @@ pub enum OpCode {
     // Phi Node
     Phi,
 }
-#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, Serialize, Deserialize)]
 pub enum CmpFlag {
     Eq,
     NotEq,
@@ -72,6 +73,19 @@ pub enum CmpFlag {
     LtEq,
 }
 
+impl CmpFlag {
+    /// Get flag which give same result when operands of comparison are swapped.
+    pub fn swapped(self) -> CmpFlag {
+        match self {
+            CmpFlag::Gt => CmpFlag::Lt,
+            CmpFlag::Gteq => CmpFlag::LtEq,
+            CmpFlag::Lt => CmpFlag::Gt,
+            CmpFlag::LtEq => CmpFlag::Gteq,
+            flag => flag,
+        }
+    }
+}
+
 impl fmt::Display for OpCode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let display_text = match *self {
@@ -6,6 +6,7 @@ pub mod global_value;
 pub mod immediate;
 pub mod instruction;
 pub mod module;
+pub mod runtime_value;
 pub mod r#type;
 pub mod util;
 pub mod value;
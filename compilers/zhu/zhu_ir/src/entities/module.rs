@@ -3,33 +3,98 @@ use std::collections::HashMap;
 use crate::entities::external_name::ExternalName;
 use crate::entities::function::{ExternalFunctionData, Function, FunctionRef};
 use crate::entities::global_value::{GlobalValue, GlobalValueData};
+use crate::entities::util::sorted_serde::serialize_sorted_map;
+use serde::{Deserialize, Serialize};
 
 /// Module level entity for compiler
 ///
 /// A module should contain function and data and a symbol table to map the
 /// symbol name to data or function.
+#[derive(Serialize, Deserialize)]
 pub struct Module {
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub functions: HashMap<FuncId, Function>,
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub data_objects: HashMap<DataId, DataDescription>,
+    #[serde(serialize_with = "serialize_sorted_map")]
     pub symbol_table: HashMap<String, ModuleLevelId>,
 }
 
 /// Reference to data in module
-#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct DataId(pub u32);
-#[derive(Debug, PartialEq, Clone)]
-pub struct DataDescription {}
+/// ## Data Description
+/// Describe the memory of a data object in module, data object take `size` bytes
+/// aligned to `align`, its content is initialized by `init`, then every relocation
+/// write address of other symbol into it.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DataDescription {
+    pub size: u64,
+    pub align: u64,
+    pub mutable: bool,
+    pub init: DataInit,
+    pub relocations: Vec<DataRelocation>,
+}
+/// Initial content of data object, byte initializer can be shorter than size of data,
+/// rest bytes are zero.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum DataInit {
+    Zeros,
+    Bytes(Vec<u8>),
+}
+/// Relocation entry of data object, write address of `name` plus `addend` as a
+/// pointer at `offset` of data object.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DataRelocation {
+    pub offset: u64,
+    pub name: ExternalName,
+    pub addend: i64,
+}
+/// Size of pointer written by relocation.
+pub const DATA_POINTER_SIZE: u64 = 8;
 
 impl DataDescription {
+    /// Create a empty, immutable zero-initialized data.
     pub fn new() -> Self {
-        Self {}
+        Self {
+            size: 0,
+            align: 1,
+            mutable: false,
+            init: DataInit::Zeros,
+            relocations: Vec::new(),
+        }
+    }
+    /// Create a zero-initialized data with given size and alignment.
+    pub fn zeroed(size: u64, align: u64) -> Self {
+        Self {
+            size,
+            align,
+            ..Self::new()
+        }
+    }
+    /// Create a data initialized by given bytes, size of data is length of bytes.
+    pub fn from_bytes(bytes: Vec<u8>, align: u64) -> Self {
+        Self {
+            size: bytes.len() as u64,
+            align,
+            init: DataInit::Bytes(bytes),
+            ..Self::new()
+        }
+    }
+    /// Mark data as mutable or not.
+    pub fn set_mutable(&mut self, mutable: bool) {
+        self.mutable = mutable;
+    }
+    /// Add a relocation writing address of `name` plus `addend` at `offset`.
+    pub fn add_relocation(&mut self, offset: u64, name: ExternalName, addend: i64) {
+        self.relocations.push(DataRelocation { offset, name, addend });
     }
 }
 /// Reference to function in module
-#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct FuncId(pub u32);
 /// Reference to function in module
-#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, Serialize, Deserialize)]
 pub enum ModuleLevelId {
     Func(FuncId),
     Data(DataId),
@@ -53,13 +118,15 @@ impl ModuleLevelId {
     }
 }
 impl Module {
-    /// Private method to get the next index of data objects
+    /// Private method to get the next index of data objects, index of removed
+    /// data is not reused.
     fn get_data_len(&self) -> u32 {
-        self.data_objects.len() as u32
+        self.data_objects.keys().map(|data_id| data_id.0 + 1).max().unwrap_or(0)
     }
-    /// Private method to get the next index of functions
+    /// Private method to get the next index of functions, index of removed
+    /// function is not reused.
     fn get_function_len(&self) -> u32 {
-        self.functions.len() as u32
+        self.functions.keys().map(|func_id| func_id.0 + 1).max().unwrap_or(0)
     }
     // fn get_mem_type_len(&self) -> u32 {
     //     self.mem_types.len() as u32
@@ -133,6 +200,18 @@ impl Module {
     pub fn get_mut_function(&mut self, func_id: FuncId) -> Option<&mut Function> {
         self.functions.get_mut(&func_id)
     }
+    /// Remove function and its symbol from module, caller must ensure function is
+    /// not referenced by other functions or data.
+    pub fn remove_function(&mut self, func_id: FuncId) -> Option<Function> {
+        self.symbol_table.retain(|_, id| *id != ModuleLevelId::Func(func_id));
+        self.functions.remove(&func_id)
+    }
+    /// Remove data and its symbol from module, caller must ensure data is not
+    /// referenced by other functions or data.
+    pub fn remove_data(&mut self, data_id: DataId) -> Option<DataDescription> {
+        self.symbol_table.retain(|_, id| *id != ModuleLevelId::Data(data_id));
+        self.data_objects.remove(&data_id)
+    }
     /// Get module level id from symbol name
     pub fn get_module_id_by_symbol(&self, symbol: &str) -> Option<&ModuleLevelId> {
         self.symbol_table.get(symbol)
@@ -0,0 +1,298 @@
+use crate::entities::immediate::Immediate;
+use crate::entities::instruction::opcode::{CmpFlag, OpCode};
+use crate::entities::r#type::ValueType;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// ## Runtime Value
+/// Value of a register when interpreting function or folding constant, every variant
+/// is map to a `ValueType`, `Address` is the value of a register with `Mem` type.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RuntimeValue {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    Address(u64),
+}
+
+impl fmt::Display for RuntimeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RuntimeValue::U8(num) => write!(f, "{} u8", num),
+            RuntimeValue::U16(num) => write!(f, "{} u16", num),
+            RuntimeValue::U32(num) => write!(f, "{} u32", num),
+            RuntimeValue::U64(num) => write!(f, "{} u64", num),
+            RuntimeValue::I16(num) => write!(f, "{} i16", num),
+            RuntimeValue::I32(num) => write!(f, "{} i32", num),
+            RuntimeValue::I64(num) => write!(f, "{} i64", num),
+            RuntimeValue::F32(num) => write!(f, "{} f32", num),
+            RuntimeValue::F64(num) => write!(f, "{} f64", num),
+            RuntimeValue::Address(num) => write!(f, "0x{:X} addr", num),
+        }
+    }
+}
+
+impl From<&Immediate> for RuntimeValue {
+    fn from(imm: &Immediate) -> Self {
+        match *imm {
+            Immediate::U8(num) => RuntimeValue::U8(num),
+            Immediate::U16(num) => RuntimeValue::U16(num),
+            Immediate::U32(num) => RuntimeValue::U32(num),
+            Immediate::U64(num) => RuntimeValue::U64(num),
+            Immediate::I16(num) => RuntimeValue::I16(num),
+            Immediate::I32(num) => RuntimeValue::I32(num),
+            Immediate::I64(num) => RuntimeValue::I64(num),
+            Immediate::F32(num) => RuntimeValue::F32(num),
+            Immediate::F64(num) => RuntimeValue::F64(num),
+        }
+    }
+}
+
+/// Error when evaluating instruction on runtime values.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    UnsupportedOperation(OpCode),
+    TypeMismatch(RuntimeValue, RuntimeValue),
+    DivisionByZero,
+}
+
+/// Apply integer opcode to two operand with same rust integer type, arithmetic
+/// is wrapping in the width of type.
+macro_rules! apply_int_op {
+    ($opcode: expr, $lhs: expr, $rhs: expr) => {
+        match $opcode {
+            OpCode::Add | OpCode::Addi => Ok($lhs.wrapping_add($rhs)),
+            OpCode::Sub | OpCode::Subi => Ok($lhs.wrapping_sub($rhs)),
+            OpCode::Mul | OpCode::Muli => Ok($lhs.wrapping_mul($rhs)),
+            OpCode::Divide | OpCode::Dividei => {
+                if $rhs == 0 {
+                    Err(EvalError::DivisionByZero)
+                } else {
+                    Ok($lhs.wrapping_div($rhs))
+                }
+            }
+            OpCode::Reminder | OpCode::Reminderi => {
+                if $rhs == 0 {
+                    Err(EvalError::DivisionByZero)
+                } else {
+                    Ok($lhs.wrapping_rem($rhs))
+                }
+            }
+            OpCode::BitwiseOR => Ok($lhs | $rhs),
+            OpCode::BitwiseAnd => Ok($lhs & $rhs),
+            OpCode::ShiftLeft => Ok($lhs.wrapping_shl($rhs as u32)),
+            OpCode::ShiftRight => Ok($lhs.wrapping_shr($rhs as u32)),
+            _ => Err(EvalError::UnsupportedOperation($opcode)),
+        }
+    };
+}
+/// Apply float opcode to two operand with same rust float type.
+macro_rules! apply_float_op {
+    ($opcode: expr, $lhs: expr, $rhs: expr) => {
+        match $opcode {
+            OpCode::FAdd => Ok($lhs + $rhs),
+            OpCode::FSub => Ok($lhs - $rhs),
+            OpCode::FMul => Ok($lhs * $rhs),
+            OpCode::FDivide => Ok($lhs / $rhs),
+            OpCode::FReminder => Ok($lhs % $rhs),
+            _ => Err(EvalError::UnsupportedOperation($opcode)),
+        }
+    };
+}
+/// Cast a rust primitive to the runtime value of given value type, using `as`
+/// semantic: truncate or extend integer, saturate float to integer.
+macro_rules! cast_to {
+    ($value: expr, $ty: expr) => {
+        match $ty {
+            ValueType::U8 => RuntimeValue::U8($value as u8),
+            ValueType::U16 => RuntimeValue::U16($value as u16),
+            ValueType::U32 => RuntimeValue::U32($value as u32),
+            ValueType::U64 => RuntimeValue::U64($value as u64),
+            ValueType::I16 => RuntimeValue::I16($value as i16),
+            ValueType::I32 => RuntimeValue::I32($value as i32),
+            ValueType::I64 => RuntimeValue::I64($value as i64),
+            ValueType::F32 => RuntimeValue::F32($value as f32),
+            ValueType::F64 => RuntimeValue::F64($value as f64),
+            ValueType::Mem(_) => RuntimeValue::Address($value as u64),
+        }
+    };
+}
+
+impl RuntimeValue {
+    /// Create value of given type from little-endian bytes, missing bytes are
+    /// filled with zero and extra bytes are ignored.
+    pub fn from_bytes(ty: &ValueType, bytes: &[u8]) -> Self {
+        let mut buffer = [0_u8; 8];
+        for (index, byte) in bytes.iter().take(8).enumerate() {
+            buffer[index] = *byte;
+        }
+        match ty {
+            ValueType::U8 => RuntimeValue::U8(buffer[0]),
+            ValueType::U16 => RuntimeValue::U16(u16::from_le_bytes([buffer[0], buffer[1]])),
+            ValueType::U32 => RuntimeValue::U32(u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]])),
+            ValueType::U64 => RuntimeValue::U64(u64::from_le_bytes(buffer)),
+            ValueType::I16 => RuntimeValue::I16(i16::from_le_bytes([buffer[0], buffer[1]])),
+            ValueType::I32 => RuntimeValue::I32(i32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]])),
+            ValueType::I64 => RuntimeValue::I64(i64::from_le_bytes(buffer)),
+            ValueType::F32 => RuntimeValue::F32(f32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]])),
+            ValueType::F64 => RuntimeValue::F64(f64::from_le_bytes(buffer)),
+            ValueType::Mem(_) => RuntimeValue::Address(u64::from_le_bytes(buffer)),
+        }
+    }
+    /// Get little-endian bytes of value, length is the size of value type.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match *self {
+            RuntimeValue::U8(num) => num.to_le_bytes().to_vec(),
+            RuntimeValue::U16(num) => num.to_le_bytes().to_vec(),
+            RuntimeValue::U32(num) => num.to_le_bytes().to_vec(),
+            RuntimeValue::U64(num) => num.to_le_bytes().to_vec(),
+            RuntimeValue::I16(num) => num.to_le_bytes().to_vec(),
+            RuntimeValue::I32(num) => num.to_le_bytes().to_vec(),
+            RuntimeValue::I64(num) => num.to_le_bytes().to_vec(),
+            RuntimeValue::F32(num) => num.to_le_bytes().to_vec(),
+            RuntimeValue::F64(num) => num.to_le_bytes().to_vec(),
+            RuntimeValue::Address(num) => num.to_le_bytes().to_vec(),
+        }
+    }
+    /// Is value can be hold by register of given type.
+    pub fn is_type_of(&self, ty: &ValueType) -> bool {
+        matches!(
+            (self, ty),
+            (RuntimeValue::U8(_), ValueType::U8)
+                | (RuntimeValue::U16(_), ValueType::U16)
+                | (RuntimeValue::U32(_), ValueType::U32)
+                | (RuntimeValue::U64(_), ValueType::U64)
+                | (RuntimeValue::I16(_), ValueType::I16)
+                | (RuntimeValue::I32(_), ValueType::I32)
+                | (RuntimeValue::I64(_), ValueType::I64)
+                | (RuntimeValue::F32(_), ValueType::F32)
+                | (RuntimeValue::F64(_), ValueType::F64)
+                | (RuntimeValue::Address(_), ValueType::Mem(_))
+        )
+    }
+    /// Integer view of value, used as address or branch condition. return none
+    /// for float value.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            RuntimeValue::U8(num) => Some(num as u64),
+            RuntimeValue::U16(num) => Some(num as u64),
+            RuntimeValue::U32(num) => Some(num as u64),
+            RuntimeValue::U64(num) => Some(num),
+            RuntimeValue::I16(num) => Some(num as u64),
+            RuntimeValue::I32(num) => Some(num as u64),
+            RuntimeValue::I64(num) => Some(num as u64),
+            RuntimeValue::Address(num) => Some(num),
+            RuntimeValue::F32(_) | RuntimeValue::F64(_) => None,
+        }
+    }
+    /// Convert value to given type, used by convert instructions.
+    pub fn cast(&self, ty: &ValueType) -> Self {
+        match *self {
+            RuntimeValue::U8(num) => cast_to!(num, ty),
+            RuntimeValue::U16(num) => cast_to!(num, ty),
+            RuntimeValue::U32(num) => cast_to!(num, ty),
+            RuntimeValue::U64(num) => cast_to!(num, ty),
+            RuntimeValue::I16(num) => cast_to!(num, ty),
+            RuntimeValue::I32(num) => cast_to!(num, ty),
+            RuntimeValue::I64(num) => cast_to!(num, ty),
+            RuntimeValue::F32(num) => cast_to!(num, ty),
+            RuntimeValue::F64(num) => cast_to!(num, ty),
+            RuntimeValue::Address(num) => cast_to!(num, ty),
+        }
+    }
+    /// Apply binary opcode, both side should have same type, address can operate
+    /// with any integer as unsigned 64-bit integer.
+    pub fn binary(opcode: OpCode, lhs: RuntimeValue, rhs: RuntimeValue) -> Result<RuntimeValue, EvalError> {
+        match (lhs, rhs) {
+            (RuntimeValue::U8(a), RuntimeValue::U8(b)) => apply_int_op!(opcode, a, b).map(RuntimeValue::U8),
+            (RuntimeValue::U16(a), RuntimeValue::U16(b)) => apply_int_op!(opcode, a, b).map(RuntimeValue::U16),
+            (RuntimeValue::U32(a), RuntimeValue::U32(b)) => apply_int_op!(opcode, a, b).map(RuntimeValue::U32),
+            (RuntimeValue::U64(a), RuntimeValue::U64(b)) => apply_int_op!(opcode, a, b).map(RuntimeValue::U64),
+            (RuntimeValue::I16(a), RuntimeValue::I16(b)) => apply_int_op!(opcode, a, b).map(RuntimeValue::I16),
+            (RuntimeValue::I32(a), RuntimeValue::I32(b)) => apply_int_op!(opcode, a, b).map(RuntimeValue::I32),
+            (RuntimeValue::I64(a), RuntimeValue::I64(b)) => apply_int_op!(opcode, a, b).map(RuntimeValue::I64),
+            (RuntimeValue::F32(a), RuntimeValue::F32(b)) => apply_float_op!(opcode, a, b).map(RuntimeValue::F32),
+            (RuntimeValue::F64(a), RuntimeValue::F64(b)) => apply_float_op!(opcode, a, b).map(RuntimeValue::F64),
+            (RuntimeValue::Address(a), other) | (other, RuntimeValue::Address(a)) => match other.as_u64() {
+                Some(b) => {
+                    // keep the order of operand for non-commutative opcode.
+                    let (a, b) = if matches!(lhs, RuntimeValue::Address(_)) {
+                        (a, b)
+                    } else {
+                        (b, a)
+                    };
+                    apply_int_op!(opcode, a, b).map(RuntimeValue::Address)
+                }
+                None => Err(EvalError::TypeMismatch(lhs, rhs)),
+            },
+            _ => Err(EvalError::TypeMismatch(lhs, rhs)),
+        }
+    }
+    /// Apply unary opcode (`neg`, `bnot`, `mov`).
+    pub fn unary(opcode: OpCode, value: RuntimeValue) -> Result<RuntimeValue, EvalError> {
+        match opcode {
+            OpCode::Mov => Ok(value),
+            OpCode::Neg => Ok(match value {
+                RuntimeValue::U8(num) => RuntimeValue::U8(num.wrapping_neg()),
+                RuntimeValue::U16(num) => RuntimeValue::U16(num.wrapping_neg()),
+                RuntimeValue::U32(num) => RuntimeValue::U32(num.wrapping_neg()),
+                RuntimeValue::U64(num) => RuntimeValue::U64(num.wrapping_neg()),
+                RuntimeValue::I16(num) => RuntimeValue::I16(num.wrapping_neg()),
+                RuntimeValue::I32(num) => RuntimeValue::I32(num.wrapping_neg()),
+                RuntimeValue::I64(num) => RuntimeValue::I64(num.wrapping_neg()),
+                RuntimeValue::F32(num) => RuntimeValue::F32(-num),
+                RuntimeValue::F64(num) => RuntimeValue::F64(-num),
+                RuntimeValue::Address(num) => RuntimeValue::Address(num.wrapping_neg()),
+            }),
+            OpCode::BitwiseNot => match value {
+                RuntimeValue::U8(num) => Ok(RuntimeValue::U8(!num)),
+                RuntimeValue::U16(num) => Ok(RuntimeValue::U16(!num)),
+                RuntimeValue::U32(num) => Ok(RuntimeValue::U32(!num)),
+                RuntimeValue::U64(num) => Ok(RuntimeValue::U64(!num)),
+                RuntimeValue::I16(num) => Ok(RuntimeValue::I16(!num)),
+                RuntimeValue::I32(num) => Ok(RuntimeValue::I32(!num)),
+                RuntimeValue::I64(num) => Ok(RuntimeValue::I64(!num)),
+                RuntimeValue::Address(num) => Ok(RuntimeValue::Address(!num)),
+                RuntimeValue::F32(_) | RuntimeValue::F64(_) => Err(EvalError::TypeMismatch(value, value)),
+            },
+            _ => Err(EvalError::UnsupportedOperation(opcode)),
+        }
+    }
+    /// Compare two value with same type, signed integer is compared as signed.
+    /// comparison with NaN is only true for `noteq`.
+    pub fn compare(flag: CmpFlag, lhs: RuntimeValue, rhs: RuntimeValue) -> Result<bool, EvalError> {
+        let ordering = match (lhs, rhs) {
+            (RuntimeValue::U8(a), RuntimeValue::U8(b)) => a.partial_cmp(&b),
+            (RuntimeValue::U16(a), RuntimeValue::U16(b)) => a.partial_cmp(&b),
+            (RuntimeValue::U32(a), RuntimeValue::U32(b)) => a.partial_cmp(&b),
+            (RuntimeValue::U64(a), RuntimeValue::U64(b)) => a.partial_cmp(&b),
+            (RuntimeValue::I16(a), RuntimeValue::I16(b)) => a.partial_cmp(&b),
+            (RuntimeValue::I32(a), RuntimeValue::I32(b)) => a.partial_cmp(&b),
+            (RuntimeValue::I64(a), RuntimeValue::I64(b)) => a.partial_cmp(&b),
+            (RuntimeValue::F32(a), RuntimeValue::F32(b)) => a.partial_cmp(&b),
+            (RuntimeValue::F64(a), RuntimeValue::F64(b)) => a.partial_cmp(&b),
+            (RuntimeValue::Address(_), _) | (_, RuntimeValue::Address(_)) => match (lhs.as_u64(), rhs.as_u64()) {
+                (Some(a), Some(b)) => a.partial_cmp(&b),
+                _ => return Err(EvalError::TypeMismatch(lhs, rhs)),
+            },
+            _ => return Err(EvalError::TypeMismatch(lhs, rhs)),
+        };
+        Ok(match ordering {
+            Some(ordering) => match flag {
+                CmpFlag::Eq => ordering == Ordering::Equal,
+                CmpFlag::NotEq => ordering != Ordering::Equal,
+                CmpFlag::Gt => ordering == Ordering::Greater,
+                CmpFlag::Gteq => ordering != Ordering::Less,
+                CmpFlag::Lt => ordering == Ordering::Less,
+                CmpFlag::LtEq => ordering != Ordering::Greater,
+            },
+            None => flag == CmpFlag::NotEq,
+        })
+    }
+}
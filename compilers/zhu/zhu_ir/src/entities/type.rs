@@ -1,4 +1,5 @@
-#[derive(Debug, PartialEq, Clone, Eq, Hash)]
+use serde::{Deserialize, Serialize};
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
 pub enum ValueType {
     U8,
     U16,
@@ -12,25 +13,41 @@ pub enum ValueType {
     Mem(MemType), // register store a address to memory
 }
 
-#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
+impl ValueType {
+    /// Get the size in bytes of value type, address of `Mem` type is 64-bit.
+    pub fn get_size(&self) -> usize {
+        match self {
+            ValueType::U8 => 1,
+            ValueType::U16 | ValueType::I16 => 2,
+            ValueType::U32 | ValueType::I32 | ValueType::F32 => 4,
+            ValueType::U64 | ValueType::I64 | ValueType::F64 | ValueType::Mem(_) => 8,
+        }
+    }
+    /// Is value type a integer, address of `Mem` type is not counted as integer.
+    pub fn is_int(&self) -> bool {
+        !matches!(self, ValueType::F32 | ValueType::F64 | ValueType::Mem(_))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct MemType(pub u32);
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub enum MemTypeData {
     Struct(StructTypeData),
     Array(ArrayTypeData),
     Unknow,
 }
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub struct StructTypeData {
     pub size: u32,
     pub fields: Vec<StructTypeDataField>,
 }
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub struct StructTypeDataField {
     pub offset: u32,
     pub ty: ValueType,
 }
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub struct ArrayTypeData {
     pub size: u32,
     // ty of array type can not be array
@@ -1,2 +1,3 @@
 pub mod inst_operand_key;
 pub mod set_operation;
+pub mod sorted_serde;
@@ -0,0 +1,24 @@
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Serialize hash map with entries sorted by key, so serialized text of same
+/// entities is always the same.
+pub fn serialize_sorted_map<S, K, V>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Serialize + Ord,
+    V: Serialize,
+{
+    serializer.collect_map(map.iter().collect::<BTreeMap<_, _>>())
+}
+
+/// Serialize hash set with elements sorted.
+pub fn serialize_sorted_set<S, T>(set: &HashSet<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize + Ord,
+{
+    let mut elements = set.iter().collect::<Vec<_>>();
+    elements.sort();
+    serializer.collect_seq(elements)
+}
@@ -1,8 +1,9 @@
 use crate::entities::instruction::Instruction;
 use crate::entities::r#type::ValueType;
-#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy)]
+use serde::{Deserialize, Serialize};
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Value(pub u32);
-#[derive(Debug, PartialEq, Clone, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub enum ValueData {
     Inst { inst: Instruction, ty: ValueType },
     Param { ty: ValueType, index: usize },
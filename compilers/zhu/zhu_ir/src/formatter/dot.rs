@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use crate::entities::block::Block;
+use crate::entities::function::Function;
+use crate::entities::instruction::InstructionData;
+use crate::entities::module::Module;
+use crate::formatter::Formatter;
+use crate::pass::analysis::cfg::ControlFlowGraph;
+use crate::pass::analysis::domtree::DomTree;
+use crate::pass::opt::dce::post_domtree::PostDomTree;
+use crate::pass::opt::licm::natural_loop::NaturalLoop;
+
+/// Format control flow graph of function as graphviz dot text without any overlay.
+pub fn format_dot(symbol_name: &str, function: &Function, module: &Module, cfg: &ControlFlowGraph) -> String {
+    DotGraph::new(symbol_name, function, module, cfg).format()
+}
+
+/// Escape text for record label of dot, line is left-justified by `\l`.
+fn escape_label(text: &str) -> String {
+    let mut escaped = String::new();
+    for ch in text.chars() {
+        match ch {
+            '{' | '}' | '|' | '<' | '>' | '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(ch);
+            }
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// ## Dot Graph
+/// Render control flow graph of function as graphviz dot text, every block is a record
+/// node which label contain its formatted instructions, edge of `brif` is labeled by
+/// `T` (conseq) and `F` (alter).
+///
+/// Overlays can be added to graph before format:
+/// - dominator tree: dashed blue edge from immediate dominator to block.
+/// - post dominator tree: dotted red edge from block to immediate post dominator.
+/// - natural loops: header and loop blocks are labeled in node, back edge is bold.
+/// - block set: a named set of text per block (for example, anticipated expression
+///   of lcm), printed as a extra field of node.
+pub struct DotGraph<'a> {
+    symbol_name: &'a str,
+    function: &'a Function,
+    module: &'a Module,
+    cfg: &'a ControlFlowGraph,
+    dom: Option<&'a DomTree>,
+    post_dom: Option<&'a PostDomTree>,
+    loops: Option<&'a [NaturalLoop]>,
+    block_sets: Vec<(String, HashMap<Block, Vec<String>>)>,
+}
+
+impl<'a> DotGraph<'a> {
+    pub fn new(symbol_name: &'a str, function: &'a Function, module: &'a Module, cfg: &'a ControlFlowGraph) -> Self {
+        Self {
+            symbol_name,
+            function,
+            module,
+            cfg,
+            dom: None,
+            post_dom: None,
+            loops: None,
+            block_sets: Vec::new(),
+        }
+    }
+    /// Overlay dominator tree.
+    pub fn with_domtree(mut self, dom: &'a DomTree) -> Self {
+        self.dom = Some(dom);
+        self
+    }
+    /// Overlay post dominator tree.
+    pub fn with_post_domtree(mut self, post_dom: &'a PostDomTree) -> Self {
+        self.post_dom = Some(post_dom);
+        self
+    }
+    /// Overlay natural loops membership.
+    pub fn with_loops(mut self, loops: &'a [NaturalLoop]) -> Self {
+        self.loops = Some(loops);
+        self
+    }
+    /// Overlay a named set of each block, `set_of_block` map block to text of elements in
+    /// the set, elements are sorted so output is stable.
+    pub fn with_block_set<F>(mut self, name: &str, set_of_block: F) -> Self
+    where
+        F: Fn(Block) -> Vec<String>,
+    {
+        let sets = self
+            .layout_blocks()
+            .into_iter()
+            .map(|block| {
+                let mut elements = set_of_block(block);
+                elements.sort();
+                (block, elements)
+            })
+            .collect();
+        self.block_sets.push((name.to_owned(), sets));
+        self
+    }
+    /// Get blocks in layout order.
+    fn layout_blocks(&self) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut cur_block = self.function.layout.first_block;
+        while let Some(block) = cur_block {
+            blocks.push(block);
+            cur_block = self.function.layout.blocks.get(&block).unwrap().next;
+        }
+        blocks
+    }
+    /// Format graph to dot text.
+    pub fn format(&self) -> String {
+        let blocks = self.layout_blocks();
+        let mut dot = format!("digraph \"{}\" {{\n", self.symbol_name);
+        dot.push_str("  node [shape=record, fontname=\"monospace\"];\n");
+        for block in &blocks {
+            dot.push_str(&format!(
+                "  block{} [label=\"{}\"];\n",
+                block.0,
+                self.fmt_node_label(*block)
+            ));
+        }
+        for block in &blocks {
+            dot.push_str(&self.fmt_cfg_edges(*block));
+        }
+        if let Some(dom) = self.dom {
+            for block in &blocks {
+                if let Some(idom) = dom.contains(*block).then(|| dom.idom(*block)).flatten() {
+                    dot.push_str(&format!(
+                        "  block{} -> block{} [style=dashed, color=blue, constraint=false];\n",
+                        idom.0, block.0
+                    ));
+                }
+            }
+        }
+        if let Some(post_dom) = self.post_dom {
+            for block in &blocks {
+                let post_idom = post_dom
+                    .dom_tree
+                    .contains(*block)
+                    .then(|| post_dom.post_idom(*block))
+                    .flatten();
+                // virtual exit block added by post dominator tree is not in layout.
+                if let Some(post_idom) = post_idom.filter(|post_idom| blocks.contains(post_idom)) {
+                    dot.push_str(&format!(
+                        "  block{} -> block{} [style=dotted, color=red, constraint=false];\n",
+                        block.0, post_idom.0
+                    ));
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+    /// Format record label of block: block name, instructions and overlays.
+    fn fmt_node_label(&self, block: Block) -> String {
+        let formatter = Formatter::new();
+        let mut fields = vec![format!("block{}:", block.0)];
+        if let Some(loops) = self.loops {
+            let mut loop_fields = Vec::new();
+            for (index, natural_loop) in loops.iter().enumerate() {
+                if natural_loop.header == block {
+                    loop_fields.push(format!("header of loop{}", index));
+                } else if natural_loop.blocks.contains(&block) {
+                    loop_fields.push(format!("in loop{}", index));
+                }
+            }
+            if !loop_fields.is_empty() {
+                fields.push(loop_fields.join(", "));
+            }
+        }
+        let insts = self
+            .function
+            .get_insts_of_block(block)
+            .into_iter()
+            .map(|inst| {
+                format!(
+                    "{}\\l",
+                    escape_label(&formatter.fmt_inst(inst, self.function, self.module))
+                )
+            })
+            .collect::<String>();
+        fields.push(insts);
+        for (name, sets) in &self.block_sets {
+            let elements = sets.get(&block).map(|elements| elements.join(", ")).unwrap_or_default();
+            fields.push(format!("{}: {}\\l", escape_label(name), escape_label(&elements)));
+        }
+        format!("{{{}}}", fields.join("|"))
+    }
+    /// Format control flow edges out of block, sorted by successor.
+    fn fmt_cfg_edges(&self, block: Block) -> String {
+        let last_inst = self.function.get_insts_of_block(block).last().copied();
+        let (conseq, alter) = match last_inst.map(|inst| self.function.get_inst_data(inst)) {
+            Some(InstructionData::BrIf { conseq, alter, .. }) if conseq != alter => (Some(*conseq), Some(*alter)),
+            _ => (None, None),
+        };
+        let mut successors = self.cfg.get_successors(&block).iter().copied().collect::<Vec<_>>();
+        successors.sort_by_key(|successor| successor.0);
+        let mut edges = String::new();
+        for successor in successors {
+            let mut attrs = Vec::new();
+            if Some(successor) == conseq {
+                attrs.push("label=\"T\"".to_owned());
+            } else if Some(successor) == alter {
+                attrs.push("label=\"F\"".to_owned());
+            }
+            let is_back_edge = self.loops.is_some_and(|loops| {
+                loops
+                    .iter()
+                    .any(|natural_loop| natural_loop.tail == block && natural_loop.header == successor)
+            });
+            if is_back_edge {
+                attrs.push("style=bold".to_owned());
+                attrs.push("color=darkgreen".to_owned());
+            }
+            if attrs.is_empty() {
+                edges.push_str(&format!("  block{} -> block{};\n", block.0, successor.0));
+            } else {
+                edges.push_str(&format!(
+                    "  block{} -> block{} [{}];\n",
+                    block.0,
+                    successor.0,
+                    attrs.join(", ")
+                ));
+            }
+        }
+        edges
+    }
+}
@@ -51,7 +51,9 @@ impl Formatter {
             }
         }
         // write global
-        for (global, _) in &function.global_values {
+        let mut globals = function.global_values.keys().copied().collect::<Vec<_>>();
+        globals.sort_by_key(|global| global.0);
+        for global in &globals {
             string.push_str(format!("  {}\n", self.fmt_global(global, function, module)).as_str());
         }
         // write block and instruction
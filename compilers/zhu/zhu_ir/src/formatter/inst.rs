@@ -20,7 +20,7 @@ impl Formatter {
                     inst_result.unwrap().0,
                     opcode,
                     self.fmt_value_type(&value_type, function),
-                    constant_data
+                    self.fmt_constant_data(&value_type, constant_data)
                 )
             }
             InstructionData::Unary { opcode, value } => {
@@ -1,10 +1,12 @@
+use crate::entities::constant::ConstantData;
 use crate::entities::external_name::{ExternalName, UserDefNamespace};
 use crate::entities::function::Function;
 use crate::entities::global_value::{GlobalValue, GlobalValueData};
-use crate::entities::module::{DataDescription, DataId, FuncId, Module, ModuleLevelId};
+use crate::entities::module::{DataDescription, DataId, DataInit, FuncId, Module, ModuleLevelId};
 use crate::entities::r#type::{MemType, MemTypeData, ValueType};
 use std::cmp::Ordering;
 
+pub mod dot;
 pub mod func;
 pub mod inst;
 
@@ -28,26 +30,49 @@ impl Formatter {
     pub fn new() -> Self {
         Self {}
     }
-    /// Private method to formate data description
-    fn fmt_data_description(&self, _data_description: &DataDescription, _module: &Module) -> String {
-        format!("{{}}")
+    /// Private method to formate data description, size and alignment are always printed,
+    /// other attributes are printed only when they are not default.
+    fn fmt_data_description(&self, data_description: &DataDescription, module: &Module) -> String {
+        let mut attrs = vec![
+            format!("size {}", data_description.size),
+            format!("align {}", data_description.align),
+        ];
+        if data_description.mutable {
+            attrs.push("mut".to_owned());
+        }
+        if let DataInit::Bytes(bytes) = &data_description.init {
+            let bytes = bytes.iter().map(|byte| format!("0x{:02X}", byte)).collect::<Vec<_>>();
+            attrs.push(format!("init [{}]", bytes.join(" ")));
+        }
+        for relocation in &data_description.relocations {
+            attrs.push(format!(
+                "reloc [{}, {}, {}]",
+                relocation.offset,
+                self.fmt_external_name(&relocation.name, module),
+                relocation.addend
+            ));
+        }
+        format!("{{ {} }}", attrs.join(", "))
     }
     /// Format a module
     pub fn fmt_module(&self, module: &Module) -> String {
+        let func_ids = sort_func_ids(module.functions.keys().map(|k| k.clone()).collect());
+        self.fmt_module_with_functions(module, &func_ids)
+    }
+    /// Format data of module and only given functions, functions are printed in given order.
+    pub fn fmt_module_with_functions(&self, module: &Module, func_ids: &[FuncId]) -> String {
         let mut module_in_string = String::new();
-        for (data_id, data_obj) in &module.data_objects {
-            let sym_name = module
-                .get_symbol_by_module_id(ModuleLevelId::Data(data_id.clone()))
-                .unwrap();
+        let mut data_ids = module.data_objects.keys().cloned().collect::<Vec<_>>();
+        data_ids.sort_by_key(|data_id| data_id.0);
+        for data_id in data_ids {
+            let data_obj = module.data_objects.get(&data_id).unwrap();
+            let sym_name = module.get_symbol_by_module_id(ModuleLevelId::Data(data_id)).unwrap();
             module_in_string
                 .push_str(format!("{} = @data {}\n", sym_name, self.fmt_data_description(data_obj, module)).as_str())
         }
-        let func_ids = sort_func_ids(module.functions.keys().map(|k| k.clone()).collect());
         for func_id in func_ids {
-            let func = module.functions.get(&func_id).unwrap();
-            let sym_name = module
-                .get_symbol_by_module_id(ModuleLevelId::Func(func_id.clone()))
-                .unwrap();
+            let func = module.functions.get(func_id).unwrap();
+            let sym_name = module.get_symbol_by_module_id(ModuleLevelId::Func(*func_id)).unwrap();
             module_in_string.push_str(self.fmt_function(sym_name, func, module).as_str());
             module_in_string.push('\n');
         }
@@ -61,14 +86,44 @@ impl Formatter {
                 format!("symbol {}", self.fmt_external_name(name, module))
             }
             GlobalValueData::Load { base, offset, ty } => {
-                format!("{}, load [{}, {}]", self.fmt_value_type(ty, function), base.0, offset.0)
+                format!(
+                    "{}, load [greg{}, {}]",
+                    self.fmt_value_type(ty, function),
+                    base.0,
+                    offset.0
+                )
             }
             GlobalValueData::AddI { base, offset, ty } => {
-                format!("{}, addi [{}, {}]", self.fmt_value_type(ty, function), base.0, offset.0)
+                format!(
+                    "{}, addi [greg{}, {}]",
+                    self.fmt_value_type(ty, function),
+                    base.0,
+                    offset.0
+                )
             }
         };
         format!("greg{} = @global {}", global.0, rhs_text)
     }
+    /// Private method to format constant data by value type, integer is format as decimal
+    /// string, float is format as hex string of its bits.
+    fn fmt_constant_data(&self, ty: &ValueType, constant_data: &ConstantData) -> String {
+        let mut buffer = [0_u8; 8];
+        for (index, byte) in constant_data.bytes.iter().take(8).enumerate() {
+            buffer[index] = *byte;
+        }
+        let bits = u64::from_le_bytes(buffer);
+        match ty {
+            ValueType::U8 => format!("{}", bits as u8),
+            ValueType::U16 => format!("{}", bits as u16),
+            ValueType::U32 => format!("{}", bits as u32),
+            ValueType::U64 => format!("{}", bits),
+            ValueType::I16 => format!("{}", bits as i16),
+            ValueType::I32 => format!("{}", bits as i32),
+            ValueType::I64 => format!("{}", bits as i64),
+            ValueType::F32 => format!("0x{:X}", bits as u32),
+            ValueType::F64 | ValueType::Mem(_) => format!("0x{:X}", bits),
+        }
+    }
     /// Private method to format value type
     fn fmt_value_type(&self, ty: &ValueType, function: &Function) -> String {
         match ty {
@@ -0,0 +1,87 @@
+use crate::frontend::lexer::Lexer;
+use crate::frontend::token::{Token, TokenKind};
+use std::fmt;
+
+/// ## Parse Error
+/// Error reported by parser, position is 1-based line and column (counted in chars)
+/// of the token which cause the error, snippet is the source line of token with a
+/// caret under it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub found: TokenKind,
+    pub expected: Vec<TokenKind>,
+    pub snippet: String,
+}
+
+impl ParseError {
+    /// Create error at given token of source.
+    pub fn new(source: &str, token: &Token, message: String, expected: Vec<TokenKind>) -> Self {
+        let line_start = source[..token.start].rfind('\n').map_or(0, |pos| pos + 1);
+        let line_end = source[token.start..]
+            .find('\n')
+            .map_or(source.len(), |pos| token.start + pos);
+        let line = source[..token.start].matches('\n').count() + 1;
+        let column = source[line_start..token.start].chars().count() + 1;
+        let line_text = &source[line_start..line_end];
+        let gutter = line.to_string();
+        let caret_len = source[token.start..token.end.min(line_end)].chars().count().max(1);
+        let snippet = format!(
+            "{} | {}\n{} | {}{}",
+            gutter,
+            line_text,
+            " ".repeat(gutter.len()),
+            " ".repeat(column - 1),
+            "^".repeat(caret_len)
+        );
+        Self {
+            message,
+            line,
+            column,
+            found: token.kind.clone(),
+            expected,
+            snippet,
+        }
+    }
+    /// Create error when current token of lexer is not one of expected token kinds.
+    pub(crate) fn unexpected_token(lexer: &Lexer, expected: Vec<TokenKind>) -> Self {
+        let token = lexer.get_token();
+        let message = if token.kind == TokenKind::EOF {
+            "unexpected end of file".to_owned()
+        } else {
+            format!("unexpected token `{}`", lexer.get_source_string())
+        };
+        Self::new(lexer.get_source(), &token, message, expected)
+    }
+    /// Create error with message at current token of lexer.
+    pub(crate) fn at_current_token(lexer: &Lexer, message: String) -> Self {
+        Self::new(lexer.get_source(), &lexer.get_token(), message, Vec::new())
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[Error]: {} at {}:{}", self.message, self.line, self.column)?;
+        if !self.expected.is_empty() {
+            let expected = self
+                .expected
+                .iter()
+                .map(|kind| format!("{:?}", kind))
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, ", expect one of {}", expected)?;
+        }
+        write!(f, ".\n{}", self.snippet)
+    }
+}
+
+/// Format list of parse errors, separated by empty line.
+pub fn format_parse_errors(errors: &[ParseError]) -> String {
+    errors
+        .iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
@@ -10,6 +10,8 @@ pub struct Lexer<'a> {
     cur_pos: usize,
     cur_char: Option<char>,
     cur_token: Token,
+    /// Line comments skipped before current token.
+    comments: Vec<Token>,
 }
 /// ### Marco to match single char token
 /// eat char and finish it.
@@ -37,6 +39,7 @@ impl<'a> Lexer<'a> {
                 start: 0,
                 end: 0,
             },
+            comments: Vec::new(),
         }
     }
     /// Create a new lexer, accpet source is a empty string
@@ -69,6 +72,20 @@ impl<'a> Lexer<'a> {
     pub fn get_source_string(&self) -> &'a str {
         &self.source[self.cur_token.start..self.cur_token.end]
     }
+    /// Get line comments skipped before current token, span of comment token
+    /// include the comment marker (`;` or `//`).
+    pub fn get_comments(&self) -> &[Token] {
+        &self.comments
+    }
+    /// Get whole source string of lexer.
+    pub fn get_source(&self) -> &'a str {
+        self.source
+    }
+    /// Return true when current token is the first token of its line.
+    pub fn is_line_start(&self) -> bool {
+        let line_start = self.source[..self.cur_token.start].rfind('\n').map_or(0, |pos| pos + 1);
+        self.source[line_start..self.cur_token.start].trim().is_empty()
+    }
     /// Get current token start pos.
     pub fn get_start_pos(&self) -> usize {
         self.start_pos
@@ -89,6 +106,8 @@ impl<'a> Lexer<'a> {
             self.cur_pos = pos;
             self.cur_char = Some(ch)
         } else {
+            // position of EOF is the end of source.
+            self.cur_pos = self.source.len();
             self.cur_char = None;
         }
     }
@@ -123,8 +142,14 @@ impl<'a> Lexer<'a> {
                 '@' => finish_token_and_eat!(self, TokenKind::At),
                 ':' => finish_token_and_eat!(self, TokenKind::Colon),
                 '%' => finish_token_and_eat!(self, TokenKind::Percent),
+                '-' if self.source[self.cur_pos + 1..].starts_with(|ch: char| ch.is_ascii_digit()) => {
+                    // eat "-"
+                    self.eat_char();
+                    self.read_decimal();
+                    self.finish_token(TokenKind::DecimalString);
+                }
                 '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
-                    if self.source.starts_with("0x") {
+                    if self.source[self.cur_pos..].starts_with("0x") {
                         // eat "0x"
                         self.eat_char();
                         self.eat_char();
@@ -143,18 +168,55 @@ impl<'a> Lexer<'a> {
         }
     }
     /// ### Internal method to skip ignoreable char
-    /// eat '\n', '\t', ' ' util reach other char.
+    /// eat '\n', '\r', '\t', ' ' and comments util reach other char, line comments
+    /// is recorded so that parser can preserve them.
     fn skip_space_and_change_line(&mut self) {
+        self.comments.clear();
         loop {
             match self.get_char() {
                 None => break,
                 Some(ch) => match ch {
-                    '\n' | '\t' | ' ' => self.eat_char(),
+                    '\n' | '\r' | '\t' | ' ' => self.eat_char(),
+                    ';' => self.skip_line_comment(),
+                    '/' if self.source[self.cur_pos..].starts_with("//") => self.skip_line_comment(),
+                    '/' if self.source[self.cur_pos..].starts_with("/*") => self.skip_block_comment(),
                     _ => break,
                 },
             }
         }
     }
+    /// ### Internal method to skip line comment
+    /// eat char util reach end of line, line break is not eaten.
+    fn skip_line_comment(&mut self) {
+        let start = self.cur_pos;
+        while let Some(ch) = self.get_char() {
+            if ch == '\n' {
+                break;
+            }
+            self.eat_char();
+        }
+        let end = self.source[start..self.cur_pos].trim_end().len() + start;
+        self.comments.push(Token {
+            kind: TokenKind::Comment,
+            start,
+            end,
+        });
+    }
+    /// ### Internal method to skip block comment
+    /// eat char util reach `*/`, unterminated comment is skipped to end of file.
+    fn skip_block_comment(&mut self) {
+        // eat "/*"
+        self.eat_char();
+        self.eat_char();
+        while self.get_char().is_some() {
+            if self.source[self.cur_pos..].starts_with("*/") {
+                self.eat_char();
+                self.eat_char();
+                break;
+            }
+            self.eat_char();
+        }
+    }
     /// ### Internal method to skip ignoreable char
     /// eat '\n', '\t', ' ' util reach other char.
     fn read_word(&mut self) -> &'a str {
@@ -163,8 +225,8 @@ impl<'a> Lexer<'a> {
             match self.get_char() {
                 None => break,
                 Some(ch) => match ch {
-                    '\n' | '\t' | ' ' => break,
-                    '{' | '}' | '[' | ']' | '(' | ')' | '=' | ',' | '@' | ':' | '%' => break,
+                    '\n' | '\r' | '\t' | ' ' => break,
+                    '{' | '}' | '[' | ']' | '(' | ')' | '=' | ',' | '@' | ':' | '%' | ';' => break,
                     _ => self.eat_char(),
                 },
             }
@@ -180,7 +242,7 @@ impl<'a> Lexer<'a> {
             match self.get_char() {
                 None => break,
                 Some(ch) => match ch {
-                    '0'..'9' => self.eat_char(),
+                    '0'..='9' => self.eat_char(),
                     _ => break,
                 },
             }
@@ -197,7 +259,7 @@ impl<'a> Lexer<'a> {
             match self.get_char() {
                 None => break,
                 Some(ch) => match ch {
-                    '0'..'9' | 'A'..'F' => self.eat_char(),
+                    '0'..='9' | 'A'..='F' | 'a'..='f' => self.eat_char(),
                     _ => break,
                 },
             }
@@ -272,6 +334,8 @@ impl<'a> Lexer<'a> {
             "align" => TokenKind::AlignKeyword,
             "u8" => TokenKind::U8Keyword,
             "u16" => TokenKind::U16Keyword,
+            "u32" => TokenKind::U32Keyword,
+            "u64" => TokenKind::U64Keyword,
             "i16" => TokenKind::I16Keyword,
             "i32" => TokenKind::I32Keyword,
             "i64" => TokenKind::I64Keyword,
@@ -1,8 +1,10 @@
 use crate::entities::module::Module;
+use error::ParseError;
 use lexer::Lexer;
 use parser::Parser;
 use token::{Token, TokenKind};
 
+pub mod error;
 pub mod lexer;
 pub mod parser;
 pub mod token;
@@ -23,8 +25,9 @@ pub fn to_tokens(code: &str) -> Vec<Token> {
     }
     tokens
 }
-/// Parse given program to module
-pub fn parse(source: &str) -> Module {
+/// Parse given program to module, return all errors reported by parser when
+/// program is invalid.
+pub fn parse(source: &str) -> Result<Module, Vec<ParseError>> {
     let mut parser = Parser::new(source);
     parser.parse()
 }
@@ -7,19 +7,106 @@ use crate::entities::function::Function;
 use crate::entities::global_value::{GlobalValue, GlobalValueData};
 use crate::entities::immediate::Immediate;
 use crate::entities::immediate::Offset;
-use crate::entities::module::DataDescription;
+use crate::entities::instruction::opcode::CmpFlag;
 use crate::entities::module::Module;
 use crate::entities::module::ModuleLevelId;
+use crate::entities::module::{DataDescription, DataId, DataInit, DATA_POINTER_SIZE};
 use crate::entities::r#type::MemType;
 use crate::entities::r#type::StructTypeData;
 use crate::entities::r#type::StructTypeDataField;
 use crate::entities::r#type::{MemTypeData, ValueType};
 use crate::entities::value::Value;
 use crate::entities::value::ValueData;
+use crate::frontend::error::ParseError;
+use crate::frontend::token::Token;
 use crate::frontend::utils::{map_token_to_cmp, map_token_to_opcode};
 use crate::frontend::Lexer;
 use crate::frontend::TokenKind;
+use std::collections::HashSet;
 use std::str::FromStr;
+
+type ParseResult<T> = Result<T, ParseError>;
+
+/// Token kinds which can start a line in function body, parser resynchronize
+/// at those tokens after error in instruction or block label.
+const INSTRUCTION_SYNC_KINDS: &[TokenKind] = &[
+    TokenKind::Reg,
+    TokenKind::Ret,
+    TokenKind::Call,
+    TokenKind::GlobalStore,
+    TokenKind::StoreRegister,
+    TokenKind::Jump,
+    TokenKind::BrIf,
+    TokenKind::BlockLabel,
+    TokenKind::BraceRight,
+    TokenKind::FuncKeyword,
+];
+/// Token kinds which can start a line in the header of function body.
+const HEADER_SYNC_KINDS: &[TokenKind] = &[
+    TokenKind::StructKeyword,
+    TokenKind::GReg,
+    TokenKind::BlockLabel,
+    TokenKind::BraceRight,
+    TokenKind::FuncKeyword,
+];
+/// Token kinds of instruction which define a register.
+const VALUE_INST_KINDS: &[TokenKind] = &[
+    TokenKind::Iconst,
+    TokenKind::Uconst,
+    TokenKind::Fconst,
+    TokenKind::Mov,
+    TokenKind::Neg,
+    TokenKind::Add,
+    TokenKind::Sub,
+    TokenKind::Mul,
+    TokenKind::Divide,
+    TokenKind::Reminder,
+    TokenKind::FAdd,
+    TokenKind::FSub,
+    TokenKind::FMul,
+    TokenKind::FDivide,
+    TokenKind::FReminder,
+    TokenKind::BitwiseOR,
+    TokenKind::BitwiseAnd,
+    TokenKind::ShiftRight,
+    TokenKind::ShiftLeft,
+    TokenKind::AddI,
+    TokenKind::SubI,
+    TokenKind::DivideI,
+    TokenKind::MulI,
+    TokenKind::ReminderI,
+    TokenKind::ToU8,
+    TokenKind::ToU16,
+    TokenKind::ToU32,
+    TokenKind::ToU64,
+    TokenKind::ToI16,
+    TokenKind::ToI32,
+    TokenKind::ToI64,
+    TokenKind::ToF32,
+    TokenKind::ToF64,
+    TokenKind::ToAddress,
+    TokenKind::Call,
+    TokenKind::Icmp,
+    TokenKind::Fcmp,
+    TokenKind::LoadRegister,
+    TokenKind::GlobalLoad,
+    TokenKind::StackAlloc,
+    TokenKind::Phi,
+];
+/// Token kinds of value type.
+const VALUE_TYPE_KINDS: &[TokenKind] = &[
+    TokenKind::U8Keyword,
+    TokenKind::U16Keyword,
+    TokenKind::U32Keyword,
+    TokenKind::U64Keyword,
+    TokenKind::I16Keyword,
+    TokenKind::I32Keyword,
+    TokenKind::I64Keyword,
+    TokenKind::F32Keyword,
+    TokenKind::F64Keyword,
+    TokenKind::MemKeyword,
+    TokenKind::StructKeyword,
+];
 /// Parser for Zhu IR, parse text format and create
 /// a in memory module
 pub struct Parser<'a> {
@@ -27,26 +114,40 @@ pub struct Parser<'a> {
     module: Module,
     function: Function,
     block: Block,
+    errors: Vec<ParseError>,
+    /// Relocations of data waiting for symbol resolution, symbol of relocation can
+    /// be data or function defined after the data statement.
+    relocations: Vec<PendingRelocation<'a>>,
+    /// Registers used as operand in current function with token of the use, register
+    /// can be used before it is defined (e.g. phi in loop header), so they are checked
+    /// after whole function body is parsed.
+    used_regs: Vec<(Value, Token)>,
+    /// Registers whose defining instruction fails to parse in current function, use
+    /// of them is not reported again.
+    failed_regs: HashSet<Value>,
 }
-/// Panic when current token is unexpected.
+/// Relocation parsed in data statement, symbol is resolved after whole module is parsed.
+struct PendingRelocation<'a> {
+    data_id: DataId,
+    offset: u64,
+    symbol: &'a str,
+    symbol_token: Token,
+    addend: i64,
+}
+/// Return error when current token is unexpected, with expected token kinds.
 macro_rules! unexpect_token {
-    ($lexer: expr) => {
-        panic!(
-            "[Error]: Unexpect token kind {:?} ({}, {}).",
-            $lexer.get_token_kind(),
-            $lexer.get_start_pos(),
-            $lexer.get_end_pos()
-        )
+    ($lexer: expr, [$($kind: expr),*]) => {
+        return Err(ParseError::unexpected_token(&$lexer, vec![$($kind),*]))
     };
 }
 /// Expect a token, call next token if match,
-/// otherwise will panic.
+/// otherwise will return error.
 macro_rules! expect_token {
     ($lexer: expr, $kind: expr) => {
         if $lexer.get_token_kind() == $kind {
             $lexer.next_token();
         } else {
-            unexpect_token!($lexer)
+            unexpect_token!($lexer, [$kind])
         }
     };
 }
@@ -79,9 +180,9 @@ macro_rules! parse_identifier {
 macro_rules! parse_immediate_helper_marco {
     ($parser: expr, $ty: ty) => {
         match $parser.lexer.get_token_kind() {
-            TokenKind::DecimalString => $parser.parse_decimal_string::<$ty>(),
-            TokenKind::HexString => $parser.parse_hex_string::<$ty>(),
-            _ => unexpect_token!($parser.lexer),
+            TokenKind::DecimalString => $parser.parse_decimal_string::<$ty>()?,
+            TokenKind::HexString => $parser.parse_hex_string::<$ty>()?,
+            _ => unexpect_token!($parser.lexer, [TokenKind::DecimalString, TokenKind::HexString]),
         }
     };
 }
@@ -96,19 +197,71 @@ impl<'a> Parser<'a> {
             module: Module::new(),
             function: Function::new(),
             block: Block(0),
+            errors: Vec::new(),
+            relocations: Vec::new(),
+            used_regs: Vec::new(),
+            failed_regs: HashSet::new(),
         }
     }
-    /// parse given source string.
-    pub fn parse(&mut self) -> Module {
-        self.parse_module()
+    /// parse given source string, return all errors when source is invalid.
+    pub fn parse(&mut self) -> Result<Module, Vec<ParseError>> {
+        let module = self.parse_module();
+        if self.errors.is_empty() {
+            Ok(module)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
     }
     /// Helper function to create function builder and
     /// switch to current block.
-    fn create_builder(&mut self) -> FunctionBuilder {
+    fn create_builder(&mut self) -> FunctionBuilder<'_> {
         let mut builder = FunctionBuilder::new(&mut self.function);
         builder.switch_to_block(self.block);
         builder
     }
+    /// Helper function to record error and resynchronize, skip tokens until a token
+    /// of `sync_kinds` which start a line, token which start at `start` (where failed
+    /// syntax begin) is always skipped, so parser always make progress.
+    fn recover(&mut self, start: usize, error: ParseError, sync_kinds: &[TokenKind]) {
+        self.errors.push(error);
+        loop {
+            let token = self.lexer.get_token();
+            if token.kind == TokenKind::EOF
+                || (token.start != start && self.lexer.is_line_start() && sync_kinds.contains(&token.kind))
+            {
+                break;
+            }
+            self.lexer.next_token();
+        }
+    }
+    /// Helper function to get text of line comments skipped before current token,
+    /// comment marker (`;;`, `;` or `//`) is removed.
+    fn get_comments(&self) -> Vec<String> {
+        let source = self.lexer.get_source();
+        self.lexer
+            .get_comments()
+            .iter()
+            .map(|token| {
+                let text = &source[token.start..token.end];
+                let text = text
+                    .strip_prefix(";;")
+                    .or_else(|| text.strip_prefix(';'))
+                    .or_else(|| text.strip_prefix("//"))
+                    .unwrap_or(text);
+                text.to_owned()
+            })
+            .collect()
+    }
+    /// Helper function to preserve comments as comment instructions in current block.
+    fn build_comments(&mut self, comments: Vec<String>) {
+        for comment in comments {
+            self.create_builder().comment_inst(comment);
+        }
+    }
+    /// Helper function to create error at given token with message.
+    fn error_at(&self, token: &Token, message: String) -> ParseError {
+        ParseError::new(self.lexer.get_source(), token, message, Vec::new())
+    }
     /// Helper function to overwrite the value in function entity
     /// and function layout.
     /// - Usage: all `dst` value will overwrited by `src` value
@@ -185,7 +338,16 @@ impl<'a> Parser<'a> {
     /// ```
     fn parse_module(&mut self) -> Module {
         self.parse_data_statements();
-        self.parse_functions();
+        loop {
+            self.parse_functions();
+            if TokenKind::EOF == self.lexer.get_token_kind() {
+                break;
+            }
+            let start = self.lexer.get_token().start;
+            let error = ParseError::unexpected_token(&self.lexer, vec![TokenKind::FuncKeyword]);
+            self.recover(start, error, &[TokenKind::FuncKeyword]);
+        }
+        self.resolve_relocations();
         std::mem::replace(&mut self.module, Module::new())
     }
     /// Parse data statements
@@ -195,22 +357,147 @@ impl<'a> Parser<'a> {
     /// ```
     fn parse_data_statements(&mut self) {
         while TokenKind::Identifier == self.lexer.get_token_kind() {
-            self.parse_data_statement();
+            let start = self.lexer.get_token().start;
+            if let Err(error) = self.parse_data_statement() {
+                self.recover(start, error, &[TokenKind::Identifier, TokenKind::FuncKeyword]);
+            }
         }
     }
-    /// Parse data statement
+    /// Parse data statement, attribute not given is default to empty immutable zero-initialized
+    /// data, size of data is length of initializer when only `init` is given.
     /// ```markdown
-    /// <Identifier> "=" "@" "data" "{""}"
+    /// <DataStmt>  := <Identifier> "=" "@" "data" "{" <DataAttrs>? "}"
+    /// <DataAttrs> := <DataAttrs> "," <DataAttr>
+    ///             := <DataAttr>
+    /// <DataAttr>  := "size" <DecimalString>
+    ///             := "align" <DecimalString>
+    ///             := "mut"
+    ///             := "init" "[" <HexString>* "]"
+    ///             := "reloc" "[" <DecimalString> "," <Identifier> "," <DecimalString> "]"
     /// ```
-    fn parse_data_statement(&mut self) {
+    fn parse_data_statement(&mut self) -> ParseResult<()> {
         let id_str = parse_identifier!(self.lexer);
         expect_token!(self.lexer, TokenKind::Assign);
         expect_token!(self.lexer, TokenKind::At);
         expect_token!(self.lexer, TokenKind::DataKeyword);
+        let brace_token = self.lexer.get_token();
         expect_token!(self.lexer, TokenKind::BracesLeft);
+        let mut data_description = DataDescription::new();
+        let mut size = None;
+        let mut relocations = Vec::new();
+        let mut is_first_attr = true;
+        while !match_tokens!(self.lexer, TokenKind::BraceRight | TokenKind::EOF) {
+            if !is_first_attr {
+                expect_token!(self.lexer, TokenKind::Comma);
+            }
+            is_first_attr = false;
+            match (self.lexer.get_token_kind(), self.lexer.get_source_string()) {
+                (TokenKind::SizeKeyword, _) => {
+                    self.lexer.next_token();
+                    size = Some(self.parse_decimal_string::<u64>()?);
+                }
+                (TokenKind::AlignKeyword, _) => {
+                    self.lexer.next_token();
+                    let align_token = self.lexer.get_token();
+                    let align = self.parse_decimal_string::<u64>()?;
+                    if !align.is_power_of_two() {
+                        return Err(self.error_at(&align_token, format!("alignment {} is not power of two", align)));
+                    }
+                    data_description.align = align;
+                }
+                (TokenKind::Identifier, "mut") => {
+                    self.lexer.next_token();
+                    data_description.set_mutable(true);
+                }
+                (TokenKind::Identifier, "init") => {
+                    self.lexer.next_token();
+                    expect_token!(self.lexer, TokenKind::BracketLeft);
+                    let mut bytes = Vec::<u8>::new();
+                    while !match_tokens!(self.lexer, TokenKind::BracketRight | TokenKind::EOF) {
+                        bytes.push(self.parse_hex_string::<u8>()?);
+                    }
+                    expect_token!(self.lexer, TokenKind::BracketRight);
+                    data_description.init = DataInit::Bytes(bytes);
+                }
+                (TokenKind::Identifier, "reloc") => {
+                    self.lexer.next_token();
+                    expect_token!(self.lexer, TokenKind::BracketLeft);
+                    let offset = self.parse_decimal_string::<u64>()?;
+                    expect_token!(self.lexer, TokenKind::Comma);
+                    let symbol_token = self.lexer.get_token();
+                    let symbol = parse_identifier!(self.lexer);
+                    expect_token!(self.lexer, TokenKind::Comma);
+                    let addend = self.parse_decimal_string::<i64>()?;
+                    expect_token!(self.lexer, TokenKind::BracketRight);
+                    relocations.push((offset, symbol, symbol_token, addend));
+                }
+                _ => {
+                    return Err(ParseError::at_current_token(
+                        &self.lexer,
+                        "expect data attribute `size`, `align`, `mut`, `init` or `reloc`".to_owned(),
+                    ))
+                }
+            }
+        }
         expect_token!(self.lexer, TokenKind::BraceRight);
-        let data_description = DataDescription::new();
-        self.module.define_data(id_str, data_description);
+        let init_size = match &data_description.init {
+            DataInit::Zeros => 0,
+            DataInit::Bytes(bytes) => bytes.len() as u64,
+        };
+        data_description.size = size.unwrap_or(init_size);
+        if init_size > data_description.size {
+            let message = format!(
+                "initializer of `{}` has {} bytes, larger than size {}",
+                id_str, init_size, data_description.size
+            );
+            return Err(self.error_at(&brace_token, message));
+        }
+        for (index, (offset, _, symbol_token, _)) in relocations.iter().enumerate() {
+            let end = offset.checked_add(DATA_POINTER_SIZE);
+            if end.is_none_or(|end| end > data_description.size) {
+                let message = format!("relocation at offset {} is out of bound of `{}`", offset, id_str);
+                return Err(self.error_at(symbol_token, message));
+            }
+            // pointer written by relocations can not overlap each other.
+            if let Some((other, ..)) = relocations[..index]
+                .iter()
+                .find(|(other, ..)| *other < offset + DATA_POINTER_SIZE && *offset < *other + DATA_POINTER_SIZE)
+            {
+                let message = format!(
+                    "relocation at offset {} overlaps relocation at offset {} of `{}`",
+                    offset, other, id_str
+                );
+                return Err(self.error_at(symbol_token, message));
+            }
+        }
+        let data_id = self.module.define_data(id_str, data_description);
+        for (offset, symbol, symbol_token, addend) in relocations {
+            self.relocations.push(PendingRelocation {
+                data_id,
+                offset,
+                symbol,
+                symbol_token,
+                addend,
+            });
+        }
+        Ok(())
+    }
+    /// Resolve symbol of relocations after all data and functions are defined.
+    fn resolve_relocations(&mut self) {
+        for relocation in std::mem::take(&mut self.relocations) {
+            let Some(module_id) = self.module.get_module_id_by_symbol(relocation.symbol).cloned() else {
+                let message = format!("unknown symbol `{}`", relocation.symbol);
+                let error = self.error_at(&relocation.symbol_token, message);
+                self.errors.push(error);
+                continue;
+            };
+            let data_description = self.module.get_mut_data(relocation.data_id).unwrap();
+            data_description.add_relocation(
+                relocation.offset,
+                ExternalName::from_module_level_id(module_id),
+                relocation.addend,
+            );
+        }
     }
     /// Parse functions
     /// ```markdown
@@ -219,29 +506,36 @@ impl<'a> Parser<'a> {
     /// ```
     fn parse_functions(&mut self) {
         while match_tokens!(self.lexer, TokenKind::FuncKeyword) {
-            self.parse_function();
+            let start = self.lexer.get_token().start;
+            if let Err(error) = self.parse_function() {
+                self.function = Function::new();
+                self.recover(start, error, &[TokenKind::FuncKeyword]);
+            }
         }
     }
     /// Parse function
     /// ```markdown
     /// <Function> := "func" <FunctionName> "(" <FunctionParams> ")" <FunctionBody>
     /// ```
-    fn parse_function(&mut self) {
+    fn parse_function(&mut self) -> ParseResult<()> {
+        self.used_regs.clear();
+        self.failed_regs.clear();
         expect_token!(self.lexer, TokenKind::FuncKeyword);
         let func_name = parse_identifier!(self.lexer);
-        self.parse_function_params();
-        self.parse_function_return_type();
-        self.parse_function_body();
+        self.parse_function_params()?;
+        self.parse_function_return_type()?;
+        self.parse_function_body()?;
         self.reset_next_context_in_function_entities();
         self.module
             .define_function(func_name, std::mem::replace(&mut self.function, Function::new()));
+        Ok(())
     }
     /// Parse function param
     /// ```markdown
     /// <FunctionParams>    := <FunctionParams> "," <FunctionParam>
     ///                     := FunctionParam
     /// ```
-    fn parse_function_params(&mut self) {
+    fn parse_function_params(&mut self) -> ParseResult<()> {
         expect_token!(self.lexer, TokenKind::ParanLeft);
         let mut is_start = true;
         while !match_tokens!(self.lexer, TokenKind::ParanRight, TokenKind::EOF) {
@@ -250,44 +544,66 @@ impl<'a> Parser<'a> {
             } else {
                 expect_token!(self.lexer, TokenKind::Comma);
             }
-            self.parse_reg();
+            self.parse_reg()?;
             expect_token!(self.lexer, TokenKind::Colon);
-            let ty = self.parse_value_type();
+            let ty = self.parse_value_type()?;
             self.function.def_func_param(ty);
         }
         expect_token!(self.lexer, TokenKind::ParanRight);
+        Ok(())
     }
     /// Parse function return type
     /// ```markdown
     /// <FunctionReturnType> := ":" <ValueType>
     /// ```
-    fn parse_function_return_type(&mut self) {
+    fn parse_function_return_type(&mut self) -> ParseResult<()> {
         if match_tokens!(self.lexer, TokenKind::Colon) {
             self.lexer.next_token();
-            let ty = self.parse_value_type();
+            let ty = self.parse_value_type()?;
             self.function.set_return_type(ty)
         }
+        Ok(())
     }
     /// Parse function body
     /// ```markdown
     /// <FunctionBody>  := "{" <GlobalStmts> <Blocks> "}"
     /// ```
-    fn parse_function_body(&mut self) {
+    fn parse_function_body(&mut self) -> ParseResult<()> {
         expect_token!(self.lexer, TokenKind::BracesLeft);
         self.parse_structs();
         self.parse_global_statements();
         self.parse_blocks();
         expect_token!(self.lexer, TokenKind::BraceRight);
+        self.check_used_regs_defined();
+        Ok(())
+    }
+    /// Report every register which is used as operand but never defined in function,
+    /// error is reported at first use of the register.
+    fn check_used_regs_defined(&mut self) {
+        let mut reported = HashSet::new();
+        for (value, token) in std::mem::take(&mut self.used_regs) {
+            if self.function.entities.values.contains_key(&value)
+                || self.failed_regs.contains(&value)
+                || !reported.insert(value)
+            {
+                continue;
+            }
+            let error = self.error_at(&token, format!("use of undefined register `reg{}`", value.0));
+            self.errors.push(error);
+        }
     }
     /// Parse struct defs
     fn parse_structs(&mut self) {
         while match_tokens!(self.lexer, TokenKind::StructKeyword) {
-            self.parse_struct();
+            let start = self.lexer.get_token().start;
+            if let Err(error) = self.parse_struct() {
+                self.recover(start, error, HEADER_SYNC_KINDS);
+            }
         }
     }
     /// Parse <StrcuDef>
-    fn parse_struct(&mut self) {
-        self.parse_struct_name();
+    fn parse_struct(&mut self) -> ParseResult<()> {
+        self.parse_struct_name()?;
         expect_token!(self.lexer, TokenKind::Assign);
         expect_token!(self.lexer, TokenKind::BracesLeft);
         let mut is_start = true;
@@ -300,7 +616,7 @@ impl<'a> Parser<'a> {
             } else {
                 expect_token!(self.lexer, TokenKind::Comma);
             }
-            let ty = self.parse_value_type();
+            let ty = self.parse_value_type()?;
             size += self.get_value_type_size(&ty);
             fields.push(StructTypeDataField { offset, ty });
             offset += size
@@ -308,9 +624,10 @@ impl<'a> Parser<'a> {
         self.function
             .declar_mem_type(MemTypeData::Struct(StructTypeData { size: 0, fields }));
         expect_token!(self.lexer, TokenKind::BraceRight);
+        Ok(())
     }
     // Parse <StructName>
-    fn parse_struct_name(&mut self) -> u8 {
+    fn parse_struct_name(&mut self) -> ParseResult<u8> {
         expect_token!(self.lexer, TokenKind::StructKeyword);
         expect_token!(self.lexer, TokenKind::Percent);
         self.parse_decimal_string::<u8>()
@@ -322,20 +639,26 @@ impl<'a> Parser<'a> {
     /// ```
     fn parse_global_statements(&mut self) {
         while match_tokens!(self.lexer, TokenKind::GReg) {
-            self.parse_global_statement();
+            let start = self.lexer.get_token().start;
+            if let Err(error) = self.parse_global_statement() {
+                self.recover(start, error, HEADER_SYNC_KINDS);
+            }
         }
     }
     /// Parse global statement
     /// - please reference to readme.
-    fn parse_global_statement(&mut self) {
-        self.parse_greg();
+    fn parse_global_statement(&mut self) -> ParseResult<()> {
+        self.parse_greg()?;
         expect_token!(self.lexer, TokenKind::Assign);
         expect_token!(self.lexer, TokenKind::At);
         expect_token!(self.lexer, TokenKind::GlobalKeyword);
         if TokenKind::SymbolKeyword == self.lexer.get_token_kind() {
             self.lexer.next_token();
+            let sym_token = self.lexer.get_token();
             let sym_name = parse_identifier!(self.lexer);
-            let module_id = self.module.get_module_id_by_symbol(sym_name).unwrap().clone();
+            let Some(module_id) = self.module.get_module_id_by_symbol(sym_name).cloned() else {
+                return Err(self.error_at(&sym_token, format!("unknown symbol `{}`", sym_name)));
+            };
             let external_name = match module_id {
                 ModuleLevelId::Data(data_id) => ExternalName::UserDefName {
                     namespace: UserDefNamespace::Data,
@@ -348,23 +671,25 @@ impl<'a> Parser<'a> {
             };
             self.function
                 .declar_global_value(GlobalValueData::Symbol { name: external_name });
-            return;
+            return Ok(());
         }
-        let ty = self.parse_value_type();
+        let ty = self.parse_value_type()?;
+        expect_token!(self.lexer, TokenKind::Comma);
         let token_kind = self.lexer.get_token_kind();
+        if !matches!(token_kind, TokenKind::LoadRegister | TokenKind::AddI) {
+            unexpect_token!(self.lexer, [TokenKind::LoadRegister, TokenKind::AddI]);
+        }
         self.lexer.next_token();
         expect_token!(self.lexer, TokenKind::BracketLeft);
-        let base = self.parse_greg();
+        let base = self.parse_greg()?;
         expect_token!(self.lexer, TokenKind::Comma);
-        let offset = self.parse_offset();
+        let offset = self.parse_offset()?;
         expect_token!(self.lexer, TokenKind::BracketRight);
         self.function.declar_global_value(match token_kind {
             TokenKind::LoadRegister => GlobalValueData::Load { base, offset, ty },
-            TokenKind::AddI => GlobalValueData::AddI { base, offset, ty },
-            _ => {
-                panic!();
-            }
+            _ => GlobalValueData::AddI { base, offset, ty },
         });
+        Ok(())
     }
     /// Parse blocks
     /// ```markdown
@@ -380,31 +705,53 @@ impl<'a> Parser<'a> {
     /// <Blocks> := (BlockLabel ":" "\n" <Instructions>)*
     /// ```
     fn parse_block(&mut self) {
-        let rewrite_src = self.parse_block_label();
+        let start = self.lexer.get_token().start;
+        // comments before block label belong to the block.
+        let comments = self.get_comments();
+        match self.parse_block_header() {
+            Ok(_) => self.build_comments(comments),
+            Err(error) => {
+                self.recover(start, error, INSTRUCTION_SYNC_KINDS);
+                // keep parsing instructions into a placeholder block, so error of them can be reported.
+                self.reset_next_context_in_function_entities();
+                self.block = self.function.create_block();
+            }
+        }
+        self.parse_instructions();
+    }
+    /// Parse block header, create block of label
+    /// ```markdown
+    /// <BlockHeader> := BlockLabel ":"
+    /// ```
+    fn parse_block_header(&mut self) -> ParseResult<()> {
+        let rewrite_src = self.parse_block_label()?;
+        // block label may not be continuous, make sure created block not collide with parsed block.
+        self.reset_next_context_in_function_entities();
         let rewrite_dst = self.function.create_block();
         self.rewrite_block_when_def(rewrite_dst, rewrite_src);
         self.block = rewrite_src;
         expect_token!(self.lexer, TokenKind::Colon);
-        self.parse_instructions();
+        Ok(())
     }
     /// Parse blocks
     /// ```markdown
     /// <BlockLabel> := "block" <DecimalString>
     /// ```
-    fn parse_block_label(&mut self) -> Block {
-        let bb_number = self.lexer.get_source_string()[5..]
-            .parse::<u32>()
-            .unwrap_or_else(|error| {
-                panic!(
-                    "[Error]: block label `{}` parse error. {:?}",
-                    &self.lexer.get_source_string(),
-                    error
-                )
-            });
+    fn parse_block_label(&mut self) -> ParseResult<Block> {
+        if TokenKind::BlockLabel != self.lexer.get_token_kind() {
+            unexpect_token!(self.lexer, [TokenKind::BlockLabel]);
+        }
+        let label = self.lexer.get_source_string();
+        let Ok(bb_number) = label[5..].parse::<u32>() else {
+            return Err(ParseError::at_current_token(
+                &self.lexer,
+                format!("invalid block label `{}`", label),
+            ));
+        };
         self.lexer.next_token();
-        Block(bb_number)
+        Ok(Block(bb_number))
     }
-    /// Parse Instructions
+    /// Parse Instructions, line comments before instruction are preserved as comment instruction.
     /// ```markdown
     /// <Instructions>  := <Instructions> "\n" <Instructions>
     ///                 := <Instruction>
@@ -420,17 +767,22 @@ impl<'a> Parser<'a> {
             TokenKind::Jump,
             TokenKind::BrIf
         ) {
-            self.parse_instruction();
+            let start = self.lexer.get_token().start;
+            let comments = self.get_comments();
+            self.build_comments(comments);
+            if let Err(error) = self.parse_instruction() {
+                self.recover(start, error, INSTRUCTION_SYNC_KINDS);
+            }
         }
     }
     /// Parse instruction
     /// - please reference to instruction in readme.
-    fn parse_instruction(&mut self) {
+    fn parse_instruction(&mut self) -> ParseResult<()> {
         match self.lexer.get_token_kind() {
             TokenKind::Ret => {
                 self.lexer.next_token();
                 let reg = if TokenKind::Reg == self.lexer.get_token_kind() {
-                    Some(self.parse_reg())
+                    Some(self.parse_used_reg()?)
                 } else {
                     None
                 };
@@ -438,177 +790,213 @@ impl<'a> Parser<'a> {
             }
             TokenKind::Jump => {
                 self.lexer.next_token();
-                let bb = self.parse_block_label();
+                let bb = self.parse_block_label()?;
                 self.create_builder().jump_inst(bb);
             }
             TokenKind::BrIf => {
                 self.lexer.next_token();
-                let test = self.parse_reg();
-                let conseq = self.parse_block_label();
-                let alter = self.parse_block_label();
+                let test = self.parse_used_reg()?;
+                let conseq = self.parse_block_label()?;
+                let alter = self.parse_block_label()?;
                 self.create_builder().brif_inst(test, conseq, alter);
             }
             TokenKind::GlobalStore => {
                 self.lexer.next_token();
-                let src = self.parse_reg();
-                let (base, offset) = self.parse_global_address();
+                let src = self.parse_used_reg()?;
+                let (base, offset) = self.parse_global_address()?;
                 self.create_builder().global_store_inst(base, offset, src);
             }
             TokenKind::StoreRegister => {
                 self.lexer.next_token();
-                let src = self.parse_reg();
-                let (base, offset) = self.parse_address();
+                let src = self.parse_used_reg()?;
+                let (base, offset) = self.parse_address()?;
                 self.create_builder().store_inst(base, offset, src);
             }
             TokenKind::Call => {
-                self.parse_right_hand_side_of_call_inst();
+                self.parse_right_hand_side_of_call_inst()?;
             }
             TokenKind::Reg => {
-                let rewrite_src = self.parse_reg();
+                let rewrite_src = self.parse_reg()?;
                 expect_token!(self.lexer, TokenKind::Assign);
-                let rewrite_dst = match self.lexer.get_token_kind() {
-                    // Const
-                    TokenKind::Iconst | TokenKind::Uconst | TokenKind::Fconst => {
-                        self.lexer.next_token();
-                        let value_type = self.parse_value_type();
-                        let bytes = self.parse_const_data();
-                        self.create_builder().iconst_inst(bytes, value_type)
-                    }
-                    // Unary
-                    TokenKind::Mov | TokenKind::Neg => {
-                        let opcode = map_token_to_opcode(self.lexer.get_token_kind());
-                        self.lexer.next_token();
-                        let arg = self.parse_reg();
-                        self.create_builder().build_unary_inst(opcode, arg)
-                    }
-                    // Binary
-                    TokenKind::Add
-                    | TokenKind::Sub
-                    | TokenKind::Mul
-                    | TokenKind::Divide
-                    | TokenKind::Reminder
-                    | TokenKind::FAdd
-                    | TokenKind::FSub
-                    | TokenKind::FMul
-                    | TokenKind::FDivide
-                    | TokenKind::FReminder
-                    | TokenKind::BitwiseOR
-                    | TokenKind::BitwiseAnd
-                    | TokenKind::ShiftRight
-                    | TokenKind::ShiftLeft => {
-                        let opcode = map_token_to_opcode(self.lexer.get_token_kind());
-                        self.lexer.next_token();
-                        let args = [self.parse_reg(), self.parse_reg()];
-                        self.create_builder().build_binary_inst(opcode, args)
-                    }
-                    // Binary Immi
-                    TokenKind::AddI | TokenKind::SubI | TokenKind::DivideI | TokenKind::MulI | TokenKind::ReminderI => {
-                        let opcode = map_token_to_opcode(self.lexer.get_token_kind());
-                        self.lexer.next_token();
-                        let arg = self.parse_reg();
-                        let value_type = self.function.value_type(arg).clone();
-                        let immediate = self.parse_immediate_by_value_type(value_type);
-                        self.create_builder().build_binary_imm_inst(opcode, arg, immediate)
-                    }
-                    // Convert
-                    TokenKind::ToU8
-                    | TokenKind::ToU16
-                    | TokenKind::ToU32
-                    | TokenKind::ToU64
-                    | TokenKind::ToI16
-                    | TokenKind::ToI32
-                    | TokenKind::ToI64
-                    | TokenKind::ToF32
-                    | TokenKind::ToF64
-                    | TokenKind::ToAddress => {
-                        let opcode = map_token_to_opcode(self.lexer.get_token_kind());
-                        self.lexer.next_token();
-                        let arg = self.parse_reg();
-                        self.create_builder().build_convert_inst(opcode, arg)
-                    }
-                    // call with reg
-                    TokenKind::Call => self.parse_right_hand_side_of_call_inst().unwrap(),
-                    // Cmp
-                    TokenKind::Icmp => {
-                        self.lexer.next_token();
-                        let cmp = map_token_to_cmp(self.lexer.get_token_kind());
-                        self.lexer.next_token();
-                        let args = [self.parse_reg(), self.parse_reg()];
-                        self.create_builder().icmp_inst(cmp, args)
-                    }
-                    TokenKind::Fcmp => {
-                        self.lexer.next_token();
-                        let cmp = map_token_to_cmp(self.lexer.get_token_kind());
-                        self.lexer.next_token();
-                        let args = [self.parse_reg(), self.parse_reg()];
-                        self.create_builder().fcmp_inst(cmp, args)
-                    }
-                    // Memory relate
-                    TokenKind::LoadRegister => {
-                        self.lexer.next_token();
-                        let ty = self.parse_value_type();
-                        let (base, offset) = self.parse_address();
-                        self.create_builder().load_inst(base, offset, ty)
+                // make sure created value not collide with parsed value.
+                self.reset_next_context_in_function_entities();
+                let rewrite_dst = match self.parse_right_hand_side_of_value_inst() {
+                    Ok(value) => value,
+                    Err(error) => {
+                        self.failed_regs.insert(rewrite_src);
+                        return Err(error);
                     }
-                    TokenKind::GlobalLoad => {
-                        self.lexer.next_token();
-                        let ty = self.parse_value_type();
-                        let (base, offset) = self.parse_global_address();
-                        self.create_builder().global_load_inst(base, offset, ty)
-                    }
-                    TokenKind::StackAlloc => {
-                        self.lexer.next_token();
-                        let ty = self.parse_value_type();
-                        expect_token!(self.lexer, TokenKind::Comma);
-                        expect_token!(self.lexer, TokenKind::SizeKeyword);
-                        let size = self.parse_immediate_by_value_type(ValueType::U32);
-                        expect_token!(self.lexer, TokenKind::Comma);
-                        expect_token!(self.lexer, TokenKind::AlignKeyword);
-                        let align = self.parse_immediate_by_value_type(ValueType::U8);
-                        self.create_builder().stack_alloc_inst(size, align, ty)
-                    }
-                    // Phi
-                    TokenKind::Phi => {
-                        self.lexer.next_token();
-                        let args = self.parse_phi_arguments();
-                        self.create_builder().phi_inst(args)
-                    }
-                    _ => unexpect_token!(self.lexer),
                 };
                 self.overwrite_value_when_def(rewrite_dst, rewrite_src);
             }
             _ => unreachable!(),
         }
+        Ok(())
+    }
+    /// Parse right hand side of instruction which define a register.
+    /// - please reference to instruction in readme.
+    fn parse_right_hand_side_of_value_inst(&mut self) -> ParseResult<Value> {
+        let opcode_token = self.lexer.get_token();
+        let value = match self.lexer.get_token_kind() {
+            // Const
+            TokenKind::Iconst | TokenKind::Uconst | TokenKind::Fconst => {
+                let opcode = map_token_to_opcode(self.lexer.get_token_kind());
+                self.lexer.next_token();
+                let value_type = self.parse_value_type()?;
+                let bytes = self.parse_const_data(&value_type)?;
+                self.create_builder().build_const_inst(opcode, bytes, value_type)
+            }
+            // Unary
+            TokenKind::Mov | TokenKind::Neg => {
+                let opcode = map_token_to_opcode(self.lexer.get_token_kind());
+                self.lexer.next_token();
+                let arg = self.parse_defined_reg()?;
+                self.create_builder().build_unary_inst(opcode, arg)
+            }
+            // Binary
+            TokenKind::Add
+            | TokenKind::Sub
+            | TokenKind::Mul
+            | TokenKind::Divide
+            | TokenKind::Reminder
+            | TokenKind::FAdd
+            | TokenKind::FSub
+            | TokenKind::FMul
+            | TokenKind::FDivide
+            | TokenKind::FReminder
+            | TokenKind::BitwiseOR
+            | TokenKind::BitwiseAnd
+            | TokenKind::ShiftRight
+            | TokenKind::ShiftLeft => {
+                let opcode = map_token_to_opcode(self.lexer.get_token_kind());
+                self.lexer.next_token();
+                let args = [self.parse_defined_reg()?, self.parse_used_reg()?];
+                self.create_builder().build_binary_inst(opcode, args)
+            }
+            // Binary Immi
+            TokenKind::AddI | TokenKind::SubI | TokenKind::DivideI | TokenKind::MulI | TokenKind::ReminderI => {
+                let opcode = map_token_to_opcode(self.lexer.get_token_kind());
+                self.lexer.next_token();
+                let arg = self.parse_defined_reg()?;
+                let value_type = self.function.value_type(arg).clone();
+                let immediate = self.parse_immediate_by_value_type(value_type)?;
+                self.create_builder().build_binary_imm_inst(opcode, arg, immediate)
+            }
+            // Convert
+            TokenKind::ToU8
+            | TokenKind::ToU16
+            | TokenKind::ToU32
+            | TokenKind::ToU64
+            | TokenKind::ToI16
+            | TokenKind::ToI32
+            | TokenKind::ToI64
+            | TokenKind::ToF32
+            | TokenKind::ToF64
+            | TokenKind::ToAddress => {
+                let opcode = map_token_to_opcode(self.lexer.get_token_kind());
+                self.lexer.next_token();
+                let arg = self.parse_used_reg()?;
+                self.create_builder().build_convert_inst(opcode, arg)
+            }
+            // call with reg
+            TokenKind::Call => match self.parse_right_hand_side_of_call_inst()? {
+                Some(value) => value,
+                None => {
+                    return Err(self.error_at(
+                        &opcode_token,
+                        "call of function without return value can not define register".to_owned(),
+                    ))
+                }
+            },
+            // Cmp
+            TokenKind::Icmp => {
+                self.lexer.next_token();
+                let cmp = self.parse_cmp_flag()?;
+                let args = [self.parse_used_reg()?, self.parse_used_reg()?];
+                self.create_builder().icmp_inst(cmp, args)
+            }
+            TokenKind::Fcmp => {
+                self.lexer.next_token();
+                let cmp = self.parse_cmp_flag()?;
+                let args = [self.parse_used_reg()?, self.parse_used_reg()?];
+                self.create_builder().fcmp_inst(cmp, args)
+            }
+            // Memory relate
+            TokenKind::LoadRegister => {
+                self.lexer.next_token();
+                let ty = self.parse_value_type()?;
+                let (base, offset) = self.parse_address()?;
+                self.create_builder().load_inst(base, offset, ty)
+            }
+            TokenKind::GlobalLoad => {
+                self.lexer.next_token();
+                let ty = self.parse_value_type()?;
+                let (base, offset) = self.parse_global_address()?;
+                self.create_builder().global_load_inst(base, offset, ty)
+            }
+            TokenKind::StackAlloc => {
+                self.lexer.next_token();
+                let ty = self.parse_value_type()?;
+                expect_token!(self.lexer, TokenKind::Comma);
+                expect_token!(self.lexer, TokenKind::SizeKeyword);
+                let size = self.parse_immediate_by_value_type(ValueType::U32)?;
+                expect_token!(self.lexer, TokenKind::Comma);
+                expect_token!(self.lexer, TokenKind::AlignKeyword);
+                let align = self.parse_immediate_by_value_type(ValueType::U8)?;
+                self.create_builder().stack_alloc_inst(size, align, ty)
+            }
+            // Phi
+            TokenKind::Phi => {
+                self.lexer.next_token();
+                let args = self.parse_phi_arguments()?;
+                // type of phi is resolved by first argument.
+                match args.first() {
+                    None => return Err(self.error_at(&opcode_token, "phi require at least one argument".to_owned())),
+                    Some((_, value)) if !self.function.entities.values.contains_key(value) => {
+                        let message = format!("type of phi can not be resolved, `reg{}` is not defined", value.0);
+                        return Err(self.error_at(&opcode_token, message));
+                    }
+                    _ => {}
+                }
+                self.create_builder().phi_inst(args)
+            }
+            _ => return Err(ParseError::unexpected_token(&self.lexer, VALUE_INST_KINDS.to_vec())),
+        };
+        Ok(value)
     }
     /// Parse right hand side of call instruction
     /// ```markdown
     /// "call" "func" <Identifier> "(" <FunctionArguments> ")"
     /// ```
-    fn parse_right_hand_side_of_call_inst(&mut self) -> Option<Value> {
+    fn parse_right_hand_side_of_call_inst(&mut self) -> ParseResult<Option<Value>> {
         self.lexer.next_token();
         expect_token!(self.lexer, TokenKind::FuncKeyword);
+        let func_token = self.lexer.get_token();
         let func_name = parse_identifier!(self.lexer);
-        let func_ref = match self.module.get_module_id_by_symbol(func_name).unwrap() {
-            ModuleLevelId::Func(func_id) => {
-                let name = ExternalName::UserDefName {
-                    namespace: UserDefNamespace::Function,
-                    value: func_id.0,
-                };
-                let sig = self.module.functions.get(func_id).unwrap().signature.clone();
-                self.function
-                    .declar_external_function(ExternalFunctionData { name, sig })
+        let func_id = match self.module.get_module_id_by_symbol(func_name) {
+            Some(ModuleLevelId::Func(func_id)) => func_id.clone(),
+            Some(ModuleLevelId::Data(_)) => {
+                return Err(self.error_at(&func_token, format!("symbol `{}` is not a function", func_name)))
             }
-            _ => unreachable!(),
+            None => return Err(self.error_at(&func_token, format!("unknown function `{}`", func_name))),
         };
-        let params = self.parse_call_arguments();
-        self.create_builder().call_inst(params, func_ref)
+        let params = self.parse_call_arguments()?;
+        let name = ExternalName::UserDefName {
+            namespace: UserDefNamespace::Function,
+            value: func_id.0,
+        };
+        let sig = self.module.functions.get(&func_id).unwrap().signature.clone();
+        let func_ref = self
+            .function
+            .declar_external_function(ExternalFunctionData { name, sig });
+        Ok(self.create_builder().call_inst(params, func_ref))
     }
     /// Parse function arguments
     /// ```markdown
     /// <FunctionArguments> := <FunctionAreguments> "," <FunctionArgument>
     ///                     := <FunctionArgument>
     /// ```
-    fn parse_call_arguments(&mut self) -> Vec<Value> {
+    fn parse_call_arguments(&mut self) -> ParseResult<Vec<Value>> {
         expect_token!(self.lexer, TokenKind::ParanLeft);
         let mut is_start = true;
         let mut params = Vec::new();
@@ -618,17 +1006,17 @@ impl<'a> Parser<'a> {
             } else {
                 expect_token!(self.lexer, TokenKind::Comma);
             }
-            params.push(self.parse_reg());
+            params.push(self.parse_used_reg()?);
         }
         expect_token!(self.lexer, TokenKind::ParanRight);
-        params
+        Ok(params)
     }
     /// Parse phi arguments
     /// ```markdown
     /// <PhiArguments>  := <PhiArguments> "," <PhiArgument>
     ///                 := <PhiArgument>
     /// ```
-    fn parse_phi_arguments(&mut self) -> Vec<(Block, Value)> {
+    fn parse_phi_arguments(&mut self) -> ParseResult<Vec<(Block, Value)>> {
         expect_token!(self.lexer, TokenKind::BracketLeft);
         let mut is_start = true;
         let mut params = Vec::new();
@@ -638,106 +1026,148 @@ impl<'a> Parser<'a> {
             } else {
                 expect_token!(self.lexer, TokenKind::Comma);
             }
-            params.push((self.parse_block_label(), self.parse_reg()));
+            params.push((self.parse_block_label()?, self.parse_used_reg()?));
         }
         expect_token!(self.lexer, TokenKind::BracketRight);
-        params
+        Ok(params)
     }
     /// Parse Address
     /// ```markdown
     /// <Address> := "[" <VReg> "," <Offset> "]"
     /// ```
-    fn parse_address(&mut self) -> (Value, Offset) {
+    fn parse_address(&mut self) -> ParseResult<(Value, Offset)> {
         expect_token!(self.lexer, TokenKind::BracketLeft);
-        let base = self.parse_reg();
+        let base = self.parse_used_reg()?;
         expect_token!(self.lexer, TokenKind::Comma);
-        let offset = self.parse_offset();
+        let offset = self.parse_offset()?;
         expect_token!(self.lexer, TokenKind::BracketRight);
-        (base, offset)
+        Ok((base, offset))
     }
     /// Parse Address
     /// ```markdown
     /// <GlobalAddress> := "[" <GReg> "," <Offset> "]"
     /// ```
-    fn parse_global_address(&mut self) -> (GlobalValue, Offset) {
+    fn parse_global_address(&mut self) -> ParseResult<(GlobalValue, Offset)> {
         expect_token!(self.lexer, TokenKind::BracketLeft);
-        let base = self.parse_greg();
+        let base = self.parse_greg()?;
         expect_token!(self.lexer, TokenKind::Comma);
-        let offset = self.parse_offset();
+        let offset = self.parse_offset()?;
         expect_token!(self.lexer, TokenKind::BracketRight);
-        (base, offset)
+        Ok((base, offset))
     }
     /// Parse Value type
     /// - please reference to readme
-    fn parse_value_type(&mut self) -> ValueType {
-        match self.lexer.get_token_kind() {
-            TokenKind::U8Keyword => {
-                self.lexer.next_token();
-                ValueType::U8
-            }
-            TokenKind::U16Keyword => {
-                self.lexer.next_token();
-                ValueType::U16
-            }
-            TokenKind::I16Keyword => {
-                self.lexer.next_token();
-                ValueType::I16
-            }
-            TokenKind::I32Keyword => {
-                self.lexer.next_token();
-                ValueType::I32
-            }
-            TokenKind::I64Keyword => {
-                self.lexer.next_token();
-                ValueType::I64
-            }
-            TokenKind::F32Keyword => {
-                self.lexer.next_token();
-                ValueType::F32
-            }
-            TokenKind::F64Keyword => {
-                self.lexer.next_token();
-                ValueType::F64
-            }
+    fn parse_value_type(&mut self) -> ParseResult<ValueType> {
+        let value_type = match self.lexer.get_token_kind() {
+            TokenKind::U8Keyword => ValueType::U8,
+            TokenKind::U16Keyword => ValueType::U16,
+            TokenKind::U32Keyword => ValueType::U32,
+            TokenKind::U64Keyword => ValueType::U64,
+            TokenKind::I16Keyword => ValueType::I16,
+            TokenKind::I32Keyword => ValueType::I32,
+            TokenKind::I64Keyword => ValueType::I64,
+            TokenKind::F32Keyword => ValueType::F32,
+            TokenKind::F64Keyword => ValueType::F64,
             TokenKind::MemKeyword => {
-                self.lexer.next_token();
                 let mem_type = self.function.declar_mem_type(MemTypeData::Unknow);
                 ValueType::Mem(mem_type)
             }
             TokenKind::StructKeyword => {
-                let mem_type_num = self.parse_struct_name();
-                let mem_type = MemType(mem_type_num as u32);
-                ValueType::Mem(mem_type)
+                let struct_token = self.lexer.get_token();
+                let mem_type = MemType(self.parse_struct_name()? as u32);
+                if !self.function.mem_type.contains_key(&mem_type) {
+                    return Err(self.error_at(&struct_token, format!("undefined struct `%{}`", mem_type.0)));
+                }
+                return Ok(ValueType::Mem(mem_type));
             }
-            _ => {
-                unexpect_token!(self.lexer)
+            _ => return Err(ParseError::unexpected_token(&self.lexer, VALUE_TYPE_KINDS.to_vec())),
+        };
+        self.lexer.next_token();
+        Ok(value_type)
+    }
+    /// Parse cmp flag
+    /// ```markdown
+    /// <CmpFlag> := "eq" | "noteq" | "gt" | "gteq" | "lt" | "lteq"
+    /// ```
+    fn parse_cmp_flag(&mut self) -> ParseResult<CmpFlag> {
+        match self.lexer.get_token_kind() {
+            kind @ (TokenKind::Eq
+            | TokenKind::NotEq
+            | TokenKind::Gt
+            | TokenKind::Gteq
+            | TokenKind::Lt
+            | TokenKind::LtEq) => {
+                self.lexer.next_token();
+                Ok(map_token_to_cmp(kind))
             }
+            _ => unexpect_token!(
+                self.lexer,
+                [
+                    TokenKind::Eq,
+                    TokenKind::NotEq,
+                    TokenKind::Gt,
+                    TokenKind::Gteq,
+                    TokenKind::Lt,
+                    TokenKind::LtEq
+                ]
+            ),
         }
     }
     /// Parse reg
     /// ```markdown
     /// <VReg>  := "reg"(no skipable char)<DecimalString>
     /// ```
-    fn parse_reg(&mut self) -> Value {
+    fn parse_reg(&mut self) -> ParseResult<Value> {
         if let TokenKind::Reg = self.lexer.get_token_kind() {
-            let reg_number = self.lexer.get_source_string()[3..].parse::<u32>().unwrap();
+            let reg = self.lexer.get_source_string();
+            let Ok(reg_number) = reg[3..].parse::<u32>() else {
+                return Err(ParseError::at_current_token(
+                    &self.lexer,
+                    format!("invalid register `{}`", reg),
+                ));
+            };
             self.lexer.next_token();
-            return Value(reg_number as u32);
+            Ok(Value(reg_number))
         } else {
-            unexpect_token!(self.lexer)
+            unexpect_token!(self.lexer, [TokenKind::Reg])
+        }
+    }
+    /// Parse reg used as operand, reg may be defined after use, so it is only
+    /// recorded and checked when whole function is parsed.
+    fn parse_used_reg(&mut self) -> ParseResult<Value> {
+        let reg_token = self.lexer.get_token();
+        let value = self.parse_reg()?;
+        self.used_regs.push((value, reg_token));
+        Ok(value)
+    }
+    /// Parse reg which must be defined before, used when type of
+    /// instruction is resolved by this reg.
+    fn parse_defined_reg(&mut self) -> ParseResult<Value> {
+        let reg_token = self.lexer.get_token();
+        let value = self.parse_reg()?;
+        if self.function.entities.values.contains_key(&value) {
+            Ok(value)
+        } else {
+            Err(self.error_at(&reg_token, format!("use of undefined register `reg{}`", value.0)))
         }
     }
     /// Parse greg
     /// ```markdown
     /// <GReg>  := "greg"(no skipable char)<DecimalString>
     /// ```
-    fn parse_greg(&mut self) -> GlobalValue {
+    fn parse_greg(&mut self) -> ParseResult<GlobalValue> {
         if let TokenKind::GReg = self.lexer.get_token_kind() {
-            let reg_number = self.lexer.get_source_string()[4..].parse::<u32>().unwrap();
+            let greg = self.lexer.get_source_string();
+            let Ok(reg_number) = greg[4..].parse::<u32>() else {
+                return Err(ParseError::at_current_token(
+                    &self.lexer,
+                    format!("invalid global register `{}`", greg),
+                ));
+            };
             self.lexer.next_token();
-            return GlobalValue(reg_number as u32);
+            Ok(GlobalValue(reg_number))
         } else {
-            unexpect_token!(self.lexer)
+            unexpect_token!(self.lexer, [TokenKind::GReg])
         }
     }
     /// Parse offset
@@ -745,32 +1175,68 @@ impl<'a> Parser<'a> {
     /// ```markdown
     /// <Offset> := <DecimalString> | <HexString>
     /// ```
-    fn parse_offset(&mut self) -> Offset {
+    fn parse_offset(&mut self) -> ParseResult<Offset> {
         match self.lexer.get_token_kind() {
-            TokenKind::DecimalString => Offset(self.parse_decimal_string::<i32>()),
-            TokenKind::HexString => Offset(self.parse_hex_string::<i32>()),
-            _ => unexpect_token!(self.lexer),
+            TokenKind::DecimalString => Ok(Offset(self.parse_decimal_string::<i32>()?)),
+            TokenKind::HexString => Ok(Offset(self.parse_hex_string::<i32>()?)),
+            _ => unexpect_token!(self.lexer, [TokenKind::DecimalString, TokenKind::HexString]),
         }
     }
-    /// Parse Const data
+    /// Parse Const data, number literal is stored in little-endian bytes with
+    /// the size of value type.
     /// ```markdown
-    ///
+    /// <ConstData> := <DecimalString> | <HexString> | "[" <HexString>* "]"
     /// ```
-    fn parse_const_data(&mut self) -> Vec<u8> {
-        expect_token!(self.lexer, TokenKind::BracketLeft);
-        let mut bytes = Vec::<u8>::new();
-        while !match_tokens!(self.lexer, TokenKind::BracketRight | TokenKind::EOF) {
-            bytes.push(self.parse_hex_string::<u8>());
+    fn parse_const_data(&mut self, value_type: &ValueType) -> ParseResult<Vec<u8>> {
+        let size = value_type.get_size();
+        match self.lexer.get_token_kind() {
+            // decimal literal is range checked by its value type, float is parsed as float.
+            TokenKind::DecimalString => Ok(match value_type {
+                ValueType::U8 => self.parse_decimal_string::<u8>()?.to_le_bytes().to_vec(),
+                ValueType::U16 => self.parse_decimal_string::<u16>()?.to_le_bytes().to_vec(),
+                ValueType::U32 => self.parse_decimal_string::<u32>()?.to_le_bytes().to_vec(),
+                ValueType::U64 | ValueType::Mem(_) => self.parse_decimal_string::<u64>()?.to_le_bytes().to_vec(),
+                ValueType::I16 => self.parse_decimal_string::<i16>()?.to_le_bytes().to_vec(),
+                ValueType::I32 => self.parse_decimal_string::<i32>()?.to_le_bytes().to_vec(),
+                ValueType::I64 => self.parse_decimal_string::<i64>()?.to_le_bytes().to_vec(),
+                ValueType::F32 => self.parse_decimal_string::<f32>()?.to_bits().to_le_bytes().to_vec(),
+                ValueType::F64 => self.parse_decimal_string::<f64>()?.to_bits().to_le_bytes().to_vec(),
+            }),
+            // hex literal is raw bits, it can not have more bits than value type.
+            TokenKind::HexString => {
+                let token = self.lexer.get_token();
+                let literal = self.lexer.get_source_string().to_string();
+                let bits = self.parse_hex_string::<u64>()?;
+                if size < 8 && bits >> (size * 8) != 0 {
+                    return Err(self.error_at(
+                        &token,
+                        format!("hex literal `{}` is out of range of {:?}", literal, value_type),
+                    ));
+                }
+                Ok(bits.to_le_bytes()[..size].to_vec())
+            }
+            TokenKind::BracketLeft => {
+                self.lexer.next_token();
+                let mut bytes = Vec::<u8>::new();
+                while !match_tokens!(self.lexer, TokenKind::BracketRight | TokenKind::EOF) {
+                    bytes.push(self.parse_hex_string::<u8>()?);
+                }
+                expect_token!(self.lexer, TokenKind::BracketRight);
+                Ok(bytes)
+            }
+            _ => unexpect_token!(
+                self.lexer,
+                [TokenKind::DecimalString, TokenKind::HexString, TokenKind::BracketLeft]
+            ),
         }
-        bytes
     }
     /// Parse immediate
     /// - parse immediate only used when parse binary immediate, so we can resolve type by value type.
     /// ```markdown
     /// <Immediate> := <DecimalString> | <HexString>
     /// ```
-    fn parse_immediate_by_value_type(&mut self, value_type: ValueType) -> Immediate {
-        match value_type {
+    fn parse_immediate_by_value_type(&mut self, value_type: ValueType) -> ParseResult<Immediate> {
+        Ok(match value_type {
             ValueType::U8 => Immediate::U8(parse_immediate_helper_marco!(self, u8)),
             ValueType::U16 => Immediate::U16(parse_immediate_helper_marco!(self, u16)),
             ValueType::U32 => Immediate::U32(parse_immediate_helper_marco!(self, u32)),
@@ -780,35 +1246,55 @@ impl<'a> Parser<'a> {
             ValueType::I64 => Immediate::I64(parse_immediate_helper_marco!(self, i64)),
             ValueType::F32 => Immediate::F32(parse_immediate_helper_marco!(self, f32)),
             ValueType::F64 => Immediate::F64(parse_immediate_helper_marco!(self, f64)),
-            _ => panic!("[Error]: value type {:?} can not use as immediate.", value_type),
-        }
+            _ => {
+                return Err(ParseError::at_current_token(
+                    &self.lexer,
+                    format!("value type {:?} can not use as immediate", value_type),
+                ))
+            }
+        })
     }
     /// Parse decimal string with given type
     /// - usually used for other function to parse decimal string with given type
-    fn parse_decimal_string<T>(&mut self) -> T
+    fn parse_decimal_string<T>(&mut self) -> ParseResult<T>
     where
         T: FromStr,
     {
         if let TokenKind::DecimalString = self.lexer.get_token_kind() {
-            let value = self.lexer.get_source_string().parse::<T>();
+            let literal = self.lexer.get_source_string();
+            let Ok(value) = literal.parse::<T>() else {
+                return Err(ParseError::at_current_token(
+                    &self.lexer,
+                    format!("invalid {} literal `{}`", std::any::type_name::<T>(), literal),
+                ));
+            };
             self.lexer.next_token();
-            return value.unwrap_or_else(|_| panic!("[Error]: parse decimal string error"));
+            Ok(value)
         } else {
-            unexpect_token!(self.lexer)
+            unexpect_token!(self.lexer, [TokenKind::DecimalString])
         }
     }
     /// Parse hex string with given type
     /// - usually used for other function to parse hex string with given type
-    fn parse_hex_string<T>(&mut self) -> T
+    fn parse_hex_string<T>(&mut self) -> ParseResult<T>
     where
         T: FromStr,
     {
         if let TokenKind::HexString = self.lexer.get_token_kind() {
-            let value = self.lexer.get_source_string().parse::<T>();
+            let literal = self.lexer.get_source_string();
+            let Some(value) = u64::from_str_radix(&literal[2..], 16)
+                .ok()
+                .and_then(|num| num.to_string().parse::<T>().ok())
+            else {
+                return Err(ParseError::at_current_token(
+                    &self.lexer,
+                    format!("invalid {} literal `{}`", std::any::type_name::<T>(), literal),
+                ));
+            };
             self.lexer.next_token();
-            return value.unwrap_or_else(|_| panic!("[Error]: parse decimal string error."));
+            Ok(value)
         } else {
-            unexpect_token!(self.lexer)
+            unexpect_token!(self.lexer, [TokenKind::HexString])
         }
     }
 }
@@ -6,6 +6,8 @@ pub enum TokenKind {
     // <TyTk>
     U8Keyword,
     U16Keyword,
+    U32Keyword,
+    U64Keyword,
     I8Keyword,
     I16Keyword,
     I32Keyword,
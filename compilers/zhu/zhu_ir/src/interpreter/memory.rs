@@ -0,0 +1,126 @@
+use crate::interpreter::RuntimeErrorKind;
+
+/// First address of memory, address below it is never allocated, so that
+/// zero (null) address is always out of bounds.
+const BASE_ADDRESS: u64 = 0x1000;
+/// Max number of bytes can be allocated, allocation exceed it is out of memory error
+/// rather than abort of process.
+const MAX_MEMORY_SIZE: u64 = 1 << 30;
+
+#[derive(Debug, PartialEq, Clone)]
+struct Allocation {
+    start: u64,
+    size: u64,
+    read_only: bool,
+}
+
+/// ## Memory
+/// Byte-addressed memory of interpreter, memory is allocated like a stack, data objects
+/// are allocated first and live until interpreter is dropped, stack slots are allocated
+/// when executing `stackalloc` and released when function return.
+///
+/// Every access must lie in a single allocation, otherwise it is out of bounds.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Memory {
+    bytes: Vec<u8>,
+    allocations: Vec<Allocation>,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Memory {
+    pub fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            allocations: Vec::new(),
+        }
+    }
+    /// Allocate zero-initialized bytes with given alignment, return start address, error
+    /// when total allocated bytes exceed `MAX_MEMORY_SIZE`.
+    pub fn allocate(&mut self, size: u64, align: u64) -> Result<u64, RuntimeErrorKind> {
+        let align = align.max(1);
+        let end = BASE_ADDRESS + self.bytes.len() as u64;
+        let new_end = end
+            .div_ceil(align)
+            .checked_mul(align)
+            .and_then(|start| Some((start, start.checked_add(size)?)));
+        let (start, new_end) = match new_end {
+            Some((start, new_end)) if new_end - BASE_ADDRESS <= MAX_MEMORY_SIZE => (start, new_end),
+            _ => return Err(RuntimeErrorKind::OutOfMemory { size }),
+        };
+        self.bytes.resize((new_end - BASE_ADDRESS) as usize, 0);
+        self.allocations.push(Allocation {
+            start,
+            size,
+            read_only: false,
+        });
+        Ok(start)
+    }
+    /// Mark latest allocation start at given address as read-only, `write` to it is error.
+    pub fn protect(&mut self, address: u64) {
+        if let Some(allocation) = self
+            .allocations
+            .iter_mut()
+            .rev()
+            .find(|allocation| allocation.start == address)
+        {
+            allocation.read_only = true;
+        }
+    }
+    /// Write bytes ignoring read-only, used to setup content of data objects, bytes must
+    /// be in one allocation.
+    pub fn initialize(&mut self, address: u64, bytes: &[u8]) -> Result<(), RuntimeErrorKind> {
+        self.check(address, bytes.len() as u64)?;
+        self.copy(address, bytes);
+        Ok(())
+    }
+    /// Get a mark of current allocations, pass to `release` to free all allocations
+    /// after the mark.
+    pub fn mark(&self) -> usize {
+        self.allocations.len()
+    }
+    /// Free all allocations allocated after the mark.
+    pub fn release(&mut self, mark: usize) {
+        if let Some(allocation) = self.allocations.get(mark) {
+            self.bytes.truncate((allocation.start - BASE_ADDRESS) as usize);
+        }
+        self.allocations.truncate(mark);
+    }
+    /// Check `[address, address + size)` is in one allocation, return the allocation.
+    fn check(&self, address: u64, size: u64) -> Result<&Allocation, RuntimeErrorKind> {
+        let out_of_bounds = RuntimeErrorKind::OutOfBounds { address, size };
+        // last allocation start before or at address.
+        let index = self
+            .allocations
+            .partition_point(|allocation| allocation.start <= address);
+        if index == 0 {
+            return Err(out_of_bounds);
+        }
+        let allocation = &self.allocations[index - 1];
+        match address.checked_add(size) {
+            Some(end) if end <= allocation.start + allocation.size => Ok(allocation),
+            _ => Err(out_of_bounds),
+        }
+    }
+    pub fn read(&self, address: u64, size: u64) -> Result<&[u8], RuntimeErrorKind> {
+        self.check(address, size)?;
+        let index = (address - BASE_ADDRESS) as usize;
+        Ok(&self.bytes[index..index + size as usize])
+    }
+    pub fn write(&mut self, address: u64, bytes: &[u8]) -> Result<(), RuntimeErrorKind> {
+        if self.check(address, bytes.len() as u64)?.read_only {
+            return Err(RuntimeErrorKind::WriteReadOnly { address });
+        }
+        self.copy(address, bytes);
+        Ok(())
+    }
+    /// Copy bytes to checked address.
+    fn copy(&mut self, address: u64, bytes: &[u8]) {
+        let index = (address - BASE_ADDRESS) as usize;
+        self.bytes[index..index + bytes.len()].copy_from_slice(bytes);
+    }
+}
@@ -0,0 +1,539 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::entities::block::Block;
+use crate::entities::external_name::{ExternalName, UserDefNamespace};
+use crate::entities::function::{Function, FunctionRef};
+use crate::entities::global_value::{GlobalValue, GlobalValueData};
+use crate::entities::immediate::Offset;
+use crate::entities::instruction::opcode::OpCode;
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::module::{DataId, DataInit, FuncId, Module, ModuleLevelId};
+use crate::entities::runtime_value::{EvalError, RuntimeValue};
+use crate::entities::value::Value;
+
+pub mod memory;
+
+use memory::Memory;
+
+const DEFAULT_STEP_LIMIT: u64 = 1_000_000;
+const DEFAULT_CALL_DEPTH_LIMIT: usize = 256;
+
+/// Run function with given name in module, using default step and call depth limit.
+pub fn interpret(
+    module: &Module,
+    func_name: &str,
+    args: &[RuntimeValue],
+) -> Result<Option<RuntimeValue>, RuntimeError> {
+    Interpreter::new(module).run(func_name, args)
+}
+
+/// Runtime error of interpreter, point to the function and instruction
+/// where error happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub func: String,
+    pub inst: Option<Instruction>,
+    pub kind: RuntimeErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+    UnknownFunction(String),
+    UnknownData(String),
+    ArgumentCountMismatch { expect: usize, actual: usize },
+    ArgumentTypeMismatch(usize),
+    EmptyFunction,
+    MissingTerminator(Block),
+    MissingPhiArgument { block: Block, from: Option<Block> },
+    UndefinedValue(Value),
+    UnknownConstant,
+    UnknownGlobalValue(GlobalValue),
+    UnresolvedSymbol(ExternalName),
+    UnsupportedOperation(OpCode),
+    TypeMismatch(RuntimeValue, RuntimeValue),
+    DivisionByZero,
+    OutOfBounds { address: u64, size: u64 },
+    WriteReadOnly { address: u64 },
+    OutOfMemory { size: u64 },
+    StepLimitExceeded(u64),
+    CallDepthExceeded(usize),
+}
+
+impl From<EvalError> for RuntimeErrorKind {
+    fn from(error: EvalError) -> Self {
+        match error {
+            EvalError::UnsupportedOperation(opcode) => RuntimeErrorKind::UnsupportedOperation(opcode),
+            EvalError::TypeMismatch(lhs, rhs) => RuntimeErrorKind::TypeMismatch(lhs, rhs),
+            EvalError::DivisionByZero => RuntimeErrorKind::DivisionByZero,
+        }
+    }
+}
+
+impl fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeErrorKind::UnknownFunction(name) => write!(f, "function `{}` is not in module", name),
+            RuntimeErrorKind::UnknownData(name) => write!(f, "data `{}` is not in module", name),
+            RuntimeErrorKind::ArgumentCountMismatch { expect, actual } => {
+                write!(f, "expect {} arguments but got {}", expect, actual)
+            }
+            RuntimeErrorKind::ArgumentTypeMismatch(index) => write!(f, "type of argument {} mismatch", index),
+            RuntimeErrorKind::EmptyFunction => write!(f, "function has no block"),
+            RuntimeErrorKind::MissingTerminator(block) => write!(f, "block{} has no terminator", block.0),
+            RuntimeErrorKind::MissingPhiArgument { block, from } => match from {
+                Some(from) => write!(f, "phi in block{} has no argument for block{}", block.0, from.0),
+                None => write!(f, "phi in entry block{}", block.0),
+            },
+            RuntimeErrorKind::UndefinedValue(value) => write!(f, "reg{} is used before defined", value.0),
+            RuntimeErrorKind::UnknownConstant => write!(f, "constant is not in function"),
+            RuntimeErrorKind::UnknownGlobalValue(global) => write!(f, "greg{} is not in function", global.0),
+            RuntimeErrorKind::UnresolvedSymbol(name) => write!(f, "can not resolve external name {:?}", name),
+            RuntimeErrorKind::UnsupportedOperation(opcode) => write!(f, "unsupported operation `{}`", opcode),
+            RuntimeErrorKind::TypeMismatch(lhs, rhs) => write!(f, "type mismatch between `{}` and `{}`", lhs, rhs),
+            RuntimeErrorKind::DivisionByZero => write!(f, "division by zero"),
+            RuntimeErrorKind::OutOfBounds { address, size } => {
+                write!(f, "access {} bytes at address 0x{:X} out of bounds", size, address)
+            }
+            RuntimeErrorKind::WriteReadOnly { address } => {
+                write!(f, "write to immutable data at address 0x{:X}", address)
+            }
+            RuntimeErrorKind::OutOfMemory { size } => write!(f, "can not allocate {} bytes", size),
+            RuntimeErrorKind::StepLimitExceeded(limit) => write!(f, "exceed step limit {}", limit),
+            RuntimeErrorKind::CallDepthExceeded(limit) => write!(f, "exceed call depth limit {}", limit),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.inst {
+            Some(inst) => write!(f, "[Error]: func {}, inst{}: {}", self.func, inst.0, self.kind),
+            None => write!(f, "[Error]: func {}: {}", self.func, self.kind),
+        }
+    }
+}
+
+/// What to do after executing a instruction.
+enum Control {
+    Next,
+    Jump(Block),
+    Return(Option<RuntimeValue>),
+}
+
+/// ## Interpreter
+/// Reference interpreter of module, execute function instruction by instruction, used to
+/// check optimization preserve the semantic of function.
+///
+/// - integer arithmetic is wrapping in the width of value type, division by zero is error.
+/// - `stackalloc` allocate a slot which is released when function return, load and store
+///   access memory in bytes and must stay in bound of a slot or data object.
+/// - data objects are allocated when interpreter is created and keep value between runs,
+///   store to immutable data is error. relocation to function is left zero since function
+///   has no address in interpreter. error of setting up data objects is reported when
+///   run function.
+pub struct Interpreter<'a> {
+    module: &'a Module,
+    memory: Memory,
+    data_address: HashMap<DataId, u64>,
+    setup_error: Option<RuntimeErrorKind>,
+    step_limit: u64,
+    call_depth_limit: usize,
+    steps: u64,
+}
+
+impl<'a> Interpreter<'a> {
+    pub fn new(module: &'a Module) -> Self {
+        let mut memory = Memory::new();
+        let mut data_address = HashMap::new();
+        let setup_error = Self::setup_data(module, &mut memory, &mut data_address).err();
+        Self {
+            module,
+            memory,
+            data_address,
+            setup_error,
+            step_limit: DEFAULT_STEP_LIMIT,
+            call_depth_limit: DEFAULT_CALL_DEPTH_LIMIT,
+            steps: 0,
+        }
+    }
+    /// Allocate and initialize data objects, return error when data can not be allocated
+    /// or initializer and relocation is out of data.
+    fn setup_data(
+        module: &Module,
+        memory: &mut Memory,
+        data_address: &mut HashMap<DataId, u64>,
+    ) -> Result<(), RuntimeErrorKind> {
+        let mut data_ids: Vec<DataId> = module.data_objects.keys().copied().collect();
+        data_ids.sort_by_key(|data_id| data_id.0);
+        for data_id in &data_ids {
+            let data_description = &module.data_objects[data_id];
+            let address = memory.allocate(data_description.size, data_description.align)?;
+            if let DataInit::Bytes(bytes) = &data_description.init {
+                memory.initialize(address, bytes)?;
+            }
+            if !data_description.mutable {
+                memory.protect(address);
+            }
+            data_address.insert(*data_id, address);
+        }
+        // relocation is applied after all data is allocated, since it may point to data after it.
+        for data_id in &data_ids {
+            let data_description = &module.data_objects[data_id];
+            for relocation in &data_description.relocations {
+                if let ExternalName::UserDefName {
+                    namespace: UserDefNamespace::Data,
+                    value,
+                } = &relocation.name
+                {
+                    let Some(target) = data_address.get(&DataId(*value)) else {
+                        return Err(RuntimeErrorKind::UnresolvedSymbol(relocation.name.clone()));
+                    };
+                    let target = target.wrapping_add(relocation.addend as u64);
+                    let address = data_address[data_id].wrapping_add(relocation.offset);
+                    memory.initialize(address, &target.to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Set max number of instructions can be executed in a run.
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.step_limit = limit;
+    }
+    /// Set max depth of nested call.
+    pub fn set_call_depth_limit(&mut self, limit: usize) {
+        self.call_depth_limit = limit;
+    }
+    /// Number of instructions executed in last run.
+    pub fn get_steps(&self) -> u64 {
+        self.steps
+    }
+    /// Run function with given name and arguments, return value of `ret`.
+    pub fn run(&mut self, func_name: &str, args: &[RuntimeValue]) -> Result<Option<RuntimeValue>, RuntimeError> {
+        self.steps = 0;
+        if let Some(kind) = &self.setup_error {
+            return Err(RuntimeError {
+                func: func_name.to_owned(),
+                inst: None,
+                kind: kind.clone(),
+            });
+        }
+        let func_id = match self.module.get_module_id_by_symbol(func_name) {
+            Some(ModuleLevelId::Func(func_id)) => *func_id,
+            _ => {
+                return Err(RuntimeError {
+                    func: func_name.to_owned(),
+                    inst: None,
+                    kind: RuntimeErrorKind::UnknownFunction(func_name.to_owned()),
+                })
+            }
+        };
+        self.call(func_id, args.to_vec(), 0)
+    }
+    /// Read bytes of data object with given name.
+    pub fn read_data(&self, data_name: &str) -> Result<&[u8], RuntimeErrorKind> {
+        let (data_id, address) = self.get_data_address_by_symbol(data_name)?;
+        self.memory.read(address, self.module.data_objects[&data_id].size)
+    }
+    /// Write bytes to the start of data object with given name, immutable data can also
+    /// be written, since it is setup of caller rather than store of function.
+    pub fn write_data(&mut self, data_name: &str, bytes: &[u8]) -> Result<(), RuntimeErrorKind> {
+        let (data_id, address) = self.get_data_address_by_symbol(data_name)?;
+        if bytes.len() as u64 > self.module.data_objects[&data_id].size {
+            return Err(RuntimeErrorKind::OutOfBounds {
+                address,
+                size: bytes.len() as u64,
+            });
+        }
+        self.memory.initialize(address, bytes)
+    }
+    fn get_data_address_by_symbol(&self, data_name: &str) -> Result<(DataId, u64), RuntimeErrorKind> {
+        if let Some(kind) = &self.setup_error {
+            return Err(kind.clone());
+        }
+        match self.module.get_module_id_by_symbol(data_name) {
+            Some(ModuleLevelId::Data(data_id)) => Ok((*data_id, self.data_address[data_id])),
+            _ => Err(RuntimeErrorKind::UnknownData(data_name.to_owned())),
+        }
+    }
+}
+
+/// Execute function.
+impl<'a> Interpreter<'a> {
+    fn call(
+        &mut self,
+        func_id: FuncId,
+        args: Vec<RuntimeValue>,
+        depth: usize,
+    ) -> Result<Option<RuntimeValue>, RuntimeError> {
+        let func_name = self
+            .module
+            .get_symbol_by_module_id(ModuleLevelId::Func(func_id))
+            .unwrap_or("<unknown>")
+            .to_owned();
+        let error = |kind| RuntimeError {
+            func: func_name.clone(),
+            inst: None,
+            kind,
+        };
+        if depth >= self.call_depth_limit {
+            return Err(error(RuntimeErrorKind::CallDepthExceeded(self.call_depth_limit)));
+        }
+        let Some(func) = self.module.get_function(func_id) else {
+            return Err(error(RuntimeErrorKind::UnknownFunction(func_name.clone())));
+        };
+        if func.signature.params.len() != args.len() {
+            return Err(error(RuntimeErrorKind::ArgumentCountMismatch {
+                expect: func.signature.params.len(),
+                actual: args.len(),
+            }));
+        }
+        for (index, (arg, ty)) in args.iter().zip(func.signature.params.iter()).enumerate() {
+            if !arg.is_type_of(ty) {
+                return Err(error(RuntimeErrorKind::ArgumentTypeMismatch(index)));
+            }
+        }
+        let mark = self.memory.mark();
+        let result = self.execute(func, &func_name, args, depth);
+        self.memory.release(mark);
+        result
+    }
+    fn execute(
+        &mut self,
+        func: &Function,
+        func_name: &str,
+        args: Vec<RuntimeValue>,
+        depth: usize,
+    ) -> Result<Option<RuntimeValue>, RuntimeError> {
+        let error = |inst: Option<Instruction>, kind| RuntimeError {
+            func: func_name.to_owned(),
+            inst,
+            kind,
+        };
+        let mut values: HashMap<Value, RuntimeValue> = func.entities.params.iter().copied().zip(args).collect();
+        let Some(mut block) = func.first_block() else {
+            return Err(error(None, RuntimeErrorKind::EmptyFunction));
+        };
+        let mut from_block: Option<Block> = None;
+        loop {
+            let insts = func.get_insts_of_block(block);
+            // phis of block are executed at the same time.
+            let mut phi_values = Vec::new();
+            for inst in &insts {
+                let InstructionData::Phi { from, .. } = func.get_inst_data(*inst) else {
+                    continue;
+                };
+                self.step().map_err(|kind| error(Some(*inst), kind))?;
+                let value = from
+                    .iter()
+                    .find(|(pred, _)| Some(*pred) == from_block)
+                    .map(|(_, value)| *value)
+                    .ok_or(RuntimeErrorKind::MissingPhiArgument {
+                        block,
+                        from: from_block,
+                    })
+                    .and_then(|value| Self::get_value(&values, value))
+                    .map_err(|kind| error(Some(*inst), kind))?;
+                if let Some(result) = func.get_inst_result(*inst) {
+                    phi_values.push((result, value));
+                }
+            }
+            values.extend(phi_values);
+            let mut next_block = None;
+            for inst in insts {
+                let inst_data = func.get_inst_data(inst);
+                if matches!(inst_data, InstructionData::Phi { .. } | InstructionData::Comment(_)) {
+                    continue;
+                }
+                self.step().map_err(|kind| error(Some(inst), kind))?;
+                let control = match inst_data {
+                    InstructionData::Call { name, params, .. } => {
+                        let args = params
+                            .iter()
+                            .map(|param| Self::get_value(&values, *param))
+                            .collect::<Result<Vec<_>, _>>()
+                            .map_err(|kind| error(Some(inst), kind))?;
+                        let callee = Self::resolve_callee(func, name).map_err(|kind| error(Some(inst), kind))?;
+                        let result = self.call(callee, args, depth + 1)?;
+                        if let (Some(result_value), Some(result)) = (func.get_inst_result(inst), result) {
+                            values.insert(result_value, result);
+                        }
+                        Control::Next
+                    }
+                    _ => self
+                        .execute_inst(func, inst, &mut values)
+                        .map_err(|kind| error(Some(inst), kind))?,
+                };
+                match control {
+                    Control::Next => {}
+                    Control::Jump(target) => {
+                        next_block = Some(target);
+                        break;
+                    }
+                    Control::Return(value) => return Ok(value),
+                }
+            }
+            let Some(target) = next_block else {
+                return Err(error(None, RuntimeErrorKind::MissingTerminator(block)));
+            };
+            from_block = Some(block);
+            block = target;
+        }
+    }
+    fn step(&mut self) -> Result<(), RuntimeErrorKind> {
+        if self.steps >= self.step_limit {
+            return Err(RuntimeErrorKind::StepLimitExceeded(self.step_limit));
+        }
+        self.steps += 1;
+        Ok(())
+    }
+    fn get_value(values: &HashMap<Value, RuntimeValue>, value: Value) -> Result<RuntimeValue, RuntimeErrorKind> {
+        values
+            .get(&value)
+            .copied()
+            .ok_or(RuntimeErrorKind::UndefinedValue(value))
+    }
+    fn get_address(
+        values: &HashMap<Value, RuntimeValue>,
+        base: Value,
+        offset: &Offset,
+    ) -> Result<u64, RuntimeErrorKind> {
+        let base_value = Self::get_value(values, base)?;
+        let Some(address) = base_value.as_u64() else {
+            return Err(RuntimeErrorKind::TypeMismatch(base_value, base_value));
+        };
+        Ok(address.wrapping_add(offset.0 as i64 as u64))
+    }
+    fn resolve_callee(func: &Function, name: &FunctionRef) -> Result<FuncId, RuntimeErrorKind> {
+        let Some(ex_func) = func.external_funcs.get(name) else {
+            return Err(RuntimeErrorKind::UnknownFunction(format!("func_ref{}", name.0)));
+        };
+        match &ex_func.name {
+            ExternalName::UserDefName {
+                namespace: UserDefNamespace::Function,
+                value,
+            } => Ok(FuncId(*value)),
+            other => Err(RuntimeErrorKind::UnresolvedSymbol(other.clone())),
+        }
+    }
+    /// Compute address of global value.
+    fn global_value_address(&self, func: &Function, global: GlobalValue) -> Result<u64, RuntimeErrorKind> {
+        let Some(global_data) = func.global_values.get(&global) else {
+            return Err(RuntimeErrorKind::UnknownGlobalValue(global));
+        };
+        match global_data {
+            GlobalValueData::Symbol { name } => match name {
+                ExternalName::UserDefName {
+                    namespace: UserDefNamespace::Data,
+                    value,
+                } => self
+                    .data_address
+                    .get(&DataId(*value))
+                    .copied()
+                    .ok_or(RuntimeErrorKind::UnresolvedSymbol(name.clone())),
+                _ => Err(RuntimeErrorKind::UnresolvedSymbol(name.clone())),
+            },
+            GlobalValueData::AddI { base, offset, .. } => {
+                let base_address = self.global_value_address(func, *base)?;
+                Ok(base_address.wrapping_add(offset.0 as i64 as u64))
+            }
+            GlobalValueData::Load { base, offset, ty } => {
+                let base_address = self.global_value_address(func, *base)?;
+                let address = base_address.wrapping_add(offset.0 as i64 as u64);
+                let bytes = self.memory.read(address, ty.get_size() as u64)?;
+                let loaded = RuntimeValue::from_bytes(ty, bytes);
+                loaded.as_u64().ok_or(RuntimeErrorKind::TypeMismatch(loaded, loaded))
+            }
+        }
+    }
+    fn execute_inst(
+        &mut self,
+        func: &Function,
+        inst: Instruction,
+        values: &mut HashMap<Value, RuntimeValue>,
+    ) -> Result<Control, RuntimeErrorKind> {
+        let result = match func.get_inst_data(inst) {
+            InstructionData::UnaryConst { constant, .. } => {
+                let constant_data = func.constants.get(constant).ok_or(RuntimeErrorKind::UnknownConstant)?;
+                let ty = func.value_type(func.get_inst_result(inst).unwrap());
+                RuntimeValue::from_bytes(ty, &constant_data.bytes)
+            }
+            InstructionData::Unary { opcode, value } => RuntimeValue::unary(*opcode, Self::get_value(values, *value)?)?,
+            InstructionData::Move { src, .. } => Self::get_value(values, *src)?,
+            InstructionData::Binary { opcode, args } => {
+                let lhs = Self::get_value(values, args[0])?;
+                let rhs = Self::get_value(values, args[1])?;
+                RuntimeValue::binary(*opcode, lhs, rhs)?
+            }
+            InstructionData::BinaryI { opcode, value, imm } => {
+                RuntimeValue::binary(*opcode, Self::get_value(values, *value)?, RuntimeValue::from(imm))?
+            }
+            InstructionData::Icmp { flag, args, .. } | InstructionData::Fcmp { flag, args, .. } => {
+                let lhs = Self::get_value(values, args[0])?;
+                let rhs = Self::get_value(values, args[1])?;
+                RuntimeValue::U8(RuntimeValue::compare(*flag, lhs, rhs)? as u8)
+            }
+            InstructionData::Convert { src, .. } => {
+                let ty = func.value_type(func.get_inst_result(inst).unwrap());
+                Self::get_value(values, *src)?.cast(ty)
+            }
+            InstructionData::StackAlloc { size, align, .. } => {
+                let size = RuntimeValue::from(size).as_u64().unwrap_or(0);
+                let align = RuntimeValue::from(align).as_u64().unwrap_or(1);
+                RuntimeValue::Address(self.memory.allocate(size, align)?)
+            }
+            InstructionData::LoadRegister { base, offset, .. } => {
+                let address = Self::get_address(values, *base, offset)?;
+                let ty = func.value_type(func.get_inst_result(inst).unwrap());
+                RuntimeValue::from_bytes(ty, self.memory.read(address, ty.get_size() as u64)?)
+            }
+            InstructionData::StoreRegister { base, offset, src, .. } => {
+                let address = Self::get_address(values, *base, offset)?;
+                let src_value = Self::get_value(values, *src)?;
+                self.memory.write(address, &src_value.to_bytes())?;
+                return Ok(Control::Next);
+            }
+            InstructionData::GlobalLoad { base, offset, .. } => {
+                let address = self
+                    .global_value_address(func, *base)?
+                    .wrapping_add(offset.0 as i64 as u64);
+                let ty = func.value_type(func.get_inst_result(inst).unwrap());
+                RuntimeValue::from_bytes(ty, self.memory.read(address, ty.get_size() as u64)?)
+            }
+            InstructionData::GlobalStore { base, offset, src, .. } => {
+                let address = self
+                    .global_value_address(func, *base)?
+                    .wrapping_add(offset.0 as i64 as u64);
+                let src_value = Self::get_value(values, *src)?;
+                self.memory.write(address, &src_value.to_bytes())?;
+                return Ok(Control::Next);
+            }
+            InstructionData::BrIf {
+                test, conseq, alter, ..
+            } => {
+                let test_value = Self::get_value(values, *test)?;
+                let Some(test) = test_value.as_u64() else {
+                    return Err(RuntimeErrorKind::TypeMismatch(test_value, test_value));
+                };
+                return Ok(Control::Jump(if test != 0 { *conseq } else { *alter }));
+            }
+            InstructionData::Jump { dst, .. } => return Ok(Control::Jump(*dst)),
+            InstructionData::Ret { value, .. } => {
+                let ret_value = match value {
+                    Some(value) => Some(Self::get_value(values, *value)?),
+                    None => None,
+                };
+                return Ok(Control::Return(ret_value));
+            }
+            InstructionData::Call { opcode, .. } | InstructionData::Phi { opcode, .. } => {
+                return Err(RuntimeErrorKind::UnsupportedOperation(*opcode))
+            }
+            InstructionData::Comment(_) => return Ok(Control::Next),
+        };
+        if let Some(result_value) = func.get_inst_result(inst) {
+            values.insert(result_value, result);
+        }
+        Ok(Control::Next)
+    }
+}
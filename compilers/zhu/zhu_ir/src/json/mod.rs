@@ -0,0 +1,79 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::entities::module::Module;
+
+/// Version of JSON representation, bump it when layout of entities is changed.
+pub const JSON_FORMAT_VERSION: u32 = 1;
+
+/// Top level object of JSON representation, module is wrapped with format version so
+/// consumer can reject text produced by other version.
+#[derive(Serialize)]
+struct JsonModuleRef<'a> {
+    version: u32,
+    module: &'a Module,
+}
+#[derive(Deserialize)]
+struct JsonVersion {
+    version: u32,
+}
+#[derive(Deserialize)]
+struct JsonModule {
+    module: Module,
+}
+
+#[derive(Debug)]
+pub enum JsonError {
+    Serde(serde_json::Error),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonError::Serde(error) => write!(f, "[Error]: invalid json of module: {}", error),
+            JsonError::UnsupportedVersion(version) => write!(
+                f,
+                "[Error]: json format version {} is not supported, expect version {}",
+                version, JSON_FORMAT_VERSION
+            ),
+        }
+    }
+}
+
+impl From<serde_json::Error> for JsonError {
+    fn from(error: serde_json::Error) -> Self {
+        JsonError::Serde(error)
+    }
+}
+
+/// Serialize module to compact JSON text, map entries are sorted by key so same module
+/// always produce same text.
+pub fn to_json(module: &Module) -> String {
+    serde_json::to_string(&JsonModuleRef {
+        version: JSON_FORMAT_VERSION,
+        module,
+    })
+    .unwrap()
+}
+
+/// Serialize module to indented JSON text.
+pub fn to_json_pretty(module: &Module) -> String {
+    serde_json::to_string_pretty(&JsonModuleRef {
+        version: JSON_FORMAT_VERSION,
+        module,
+    })
+    .unwrap()
+}
+
+/// Deserialize module from JSON text produced by `to_json` or `to_json_pretty`.
+pub fn from_json(text: &str) -> Result<Module, JsonError> {
+    // check version before module, since module of other version may not be parsed.
+    let JsonVersion { version } = serde_json::from_str(text)?;
+    if version != JSON_FORMAT_VERSION {
+        return Err(JsonError::UnsupportedVersion(version));
+    }
+    let json_module: JsonModule = serde_json::from_str(text)?;
+    Ok(json_module.module)
+}
@@ -1,5 +1,10 @@
 pub mod builder;
+pub mod bytecode;
+pub mod codegen;
+pub mod driver;
 pub mod entities;
 pub mod formatter;
 pub mod frontend;
+pub mod interpreter;
+pub mod json;
 pub mod pass;
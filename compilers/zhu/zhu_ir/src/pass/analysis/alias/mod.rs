@@ -0,0 +1,450 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::entities::function::Function;
+use crate::entities::global_value::{GlobalValue, GlobalValueData};
+use crate::entities::immediate::Immediate;
+use crate::entities::instruction::opcode::OpCode;
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::module::Module;
+use crate::entities::runtime_value::RuntimeValue;
+use crate::entities::value::Value;
+use crate::pass::{get_sorted_blocks, get_table_header, AnalysisPass, FormatTable};
+
+/// Create alias analysis result.
+pub fn alias_analysis(func: &Function) -> AliasAnalysis {
+    let mut pass = AliasAnalysisPass::new();
+    pass.process(func)
+}
+
+/// Result of alias query of two memory locations.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AliasResult {
+    NoAlias,
+    MayAlias,
+    /// Two locations have same address and same size.
+    MustAlias,
+}
+
+/// Base address of memory access, value of `load` and `store` or global value of
+/// `gload` and `gstore`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MemoryBase {
+    Value(Value),
+    Global(GlobalValue),
+}
+
+/// ## Memory Location
+/// `size` bytes start at `offset` from base address.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct MemoryLocation {
+    pub base: MemoryBase,
+    pub offset: i64,
+    pub size: u64,
+}
+
+impl MemoryLocation {
+    /// Get location accessed by memory instruction, size is the size of loaded or stored
+    /// value. return none if instruction does not access memory.
+    pub fn of_inst(func: &Function, inst: Instruction) -> Option<Self> {
+        let (base, offset, value) = match func.get_inst_data(inst) {
+            InstructionData::LoadRegister { base, offset, .. } => {
+                (MemoryBase::Value(*base), offset, func.get_inst_result(inst)?)
+            }
+            InstructionData::StoreRegister { base, offset, src, .. } => (MemoryBase::Value(*base), offset, *src),
+            InstructionData::GlobalLoad { base, offset, .. } => {
+                (MemoryBase::Global(*base), offset, func.get_inst_result(inst)?)
+            }
+            InstructionData::GlobalStore { base, offset, src, .. } => (MemoryBase::Global(*base), offset, *src),
+            _ => return None,
+        };
+        Some(Self {
+            base,
+            offset: offset.0 as i64,
+            size: func.value_type(value).get_size() as u64,
+        })
+    }
+    /// Get end offset of location when it start at `offset`, return none if it overflow.
+    fn end_from(&self, offset: i64) -> Option<i64> {
+        i64::try_from(self.size).ok().and_then(|size| offset.checked_add(size))
+    }
+}
+
+/// Where an address come from, addresses from different origins are different object
+/// unless one of them is unknown to analysis.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum AddressOrigin {
+    /// Stack slot allocated by `stackalloc`.
+    StackSlot(Value),
+    /// Pointer passed by caller, it can not point to stack slot of this function.
+    Param(Value),
+    /// Global symbol, symbols declared more than once share the first global value.
+    Global(GlobalValue),
+    /// Pointer loaded from memory by global value `Load`, it is loaded again at every
+    /// access, so two accesses of it may not have same address.
+    GlobalLoad(GlobalValue),
+    /// Integer converted to address by `to.address`.
+    ToAddress(Value),
+    /// Any other value, such as result of `load`, `call` or phi.
+    Opaque(Value),
+}
+
+/// Address as origin plus offset, offset is none if it is not a constant.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Pointer {
+    pub origin: AddressOrigin,
+    pub offset: Option<i64>,
+}
+
+impl Pointer {
+    fn add_offset(self, offset: Option<i64>) -> Self {
+        Self {
+            origin: self.origin,
+            offset: self.offset.zip(offset).map(|(base, offset)| base.wrapping_add(offset)),
+        }
+    }
+}
+
+/// ## Alias Analysis
+/// Answer whether two memory locations may overlap. every address is resolved to origin
+/// and constant offset, by walking `mov`, `addi` and `subi` of value and `AddI` and `Load`
+/// of global value. Locations from same origin are compared by offset and size, and
+/// locations from different origins do not alias when both origins are known objects:
+/// - stack slots differ from each other, from globals and from pointers of caller.
+/// - global symbols differ from each other.
+/// - stack slot whose address never escapes, which means it only used as address of
+///   `load` and `store`, can only be accessed through itself, so it does not alias to
+///   any other origin and is never accessed by callee.
+#[derive(Default)]
+pub struct AliasAnalysis {
+    /// Size of every stack slot, keyed by result of `stackalloc`.
+    stack_slots: HashMap<Value, u64>,
+    escaped_slots: HashSet<Value>,
+    pointers: HashMap<MemoryBase, Pointer>,
+}
+
+impl AliasAnalysis {
+    pub fn new() -> Self {
+        Self {
+            stack_slots: Default::default(),
+            escaped_slots: Default::default(),
+            pointers: Default::default(),
+        }
+    }
+    pub fn is_stack_slot(&self, value: Value) -> bool {
+        self.stack_slots.contains_key(&value)
+    }
+    /// Is address of stack slot used other than address of `load` and `store`.
+    pub fn is_escaped(&self, slot: Value) -> bool {
+        self.escaped_slots.contains(&slot)
+    }
+    /// Get stack slots whose address never escape.
+    pub fn get_local_slots(&self) -> Vec<Value> {
+        let mut slots = self
+            .stack_slots
+            .keys()
+            .filter(|slot| !self.escaped_slots.contains(slot))
+            .copied()
+            .collect::<Vec<_>>();
+        slots.sort_by_key(|slot| slot.0);
+        slots
+    }
+    /// Get location of the whole stack slot.
+    pub fn get_slot_location(&self, slot: Value) -> Option<MemoryLocation> {
+        self.stack_slots.get(&slot).map(|size| MemoryLocation {
+            base: MemoryBase::Value(slot),
+            offset: 0,
+            size: *size,
+        })
+    }
+    /// Get origin and offset of base, base not in function is treated as opaque.
+    pub fn get_pointer(&self, base: MemoryBase) -> Pointer {
+        if let Some(pointer) = self.pointers.get(&base) {
+            return *pointer;
+        }
+        let origin = match base {
+            MemoryBase::Value(value) => AddressOrigin::Opaque(value),
+            MemoryBase::Global(global) => AddressOrigin::GlobalLoad(global),
+        };
+        Pointer {
+            origin,
+            offset: Some(0),
+        }
+    }
+    /// Get pointer of the first byte of location.
+    fn get_location_pointer(&self, location: &MemoryLocation) -> Pointer {
+        self.get_pointer(location.base).add_offset(Some(location.offset))
+    }
+    /// Is location in stack slot whose address never escape.
+    pub fn is_local(&self, location: &MemoryLocation) -> bool {
+        match self.get_pointer(location.base).origin {
+            AddressOrigin::StackSlot(slot) => !self.is_escaped(slot),
+            _ => false,
+        }
+    }
+    /// Query alias of two locations.
+    pub fn alias(&self, lhs: &MemoryLocation, rhs: &MemoryLocation) -> AliasResult {
+        let lhs_pointer = self.get_location_pointer(lhs);
+        let rhs_pointer = self.get_location_pointer(rhs);
+        if lhs_pointer.origin == rhs_pointer.origin {
+            if matches!(lhs_pointer.origin, AddressOrigin::GlobalLoad(_)) {
+                return AliasResult::MayAlias;
+            }
+            let (Some(lhs_offset), Some(rhs_offset)) = (lhs_pointer.offset, rhs_pointer.offset) else {
+                return AliasResult::MayAlias;
+            };
+            if lhs_offset == rhs_offset && lhs.size == rhs.size {
+                return AliasResult::MustAlias;
+            }
+            // end of location which overflow can not be compared.
+            let (Some(lhs_end), Some(rhs_end)) = (lhs.end_from(lhs_offset), rhs.end_from(rhs_offset)) else {
+                return AliasResult::MayAlias;
+            };
+            let is_overlap = lhs_offset < rhs_end && rhs_offset < lhs_end;
+            return if is_overlap {
+                AliasResult::MayAlias
+            } else {
+                AliasResult::NoAlias
+            };
+        }
+        if self.is_distinct_origin(&lhs_pointer.origin, &rhs_pointer.origin) {
+            AliasResult::NoAlias
+        } else {
+            AliasResult::MayAlias
+        }
+    }
+    /// Can two different origins point to same object.
+    fn is_distinct_origin(&self, lhs: &AddressOrigin, rhs: &AddressOrigin) -> bool {
+        use AddressOrigin::*;
+        match (lhs, rhs) {
+            (StackSlot(slot), _) | (_, StackSlot(slot)) if !self.is_escaped(*slot) => true,
+            (StackSlot(_), StackSlot(_) | Param(_) | Global(_)) | (Param(_) | Global(_), StackSlot(_)) => true,
+            (Global(_), Global(_)) => true,
+            _ => false,
+        }
+    }
+    /// Query alias of locations accessed by two memory instructions, return none if any
+    /// of instructions does not access memory.
+    pub fn alias_insts(&self, func: &Function, lhs: Instruction, rhs: Instruction) -> Option<AliasResult> {
+        let lhs = MemoryLocation::of_inst(func, lhs)?;
+        let rhs = MemoryLocation::of_inst(func, rhs)?;
+        Some(self.alias(&lhs, &rhs))
+    }
+    /// Is every byte of `rhs` in `lhs`, store to `lhs` will overwrite `rhs` completely.
+    pub fn covers(&self, lhs: &MemoryLocation, rhs: &MemoryLocation) -> bool {
+        let lhs_pointer = self.get_location_pointer(lhs);
+        let rhs_pointer = self.get_location_pointer(rhs);
+        if lhs_pointer.origin != rhs_pointer.origin || matches!(lhs_pointer.origin, AddressOrigin::GlobalLoad(_)) {
+            return false;
+        }
+        match (lhs_pointer.offset, rhs_pointer.offset) {
+            (Some(lhs_offset), Some(rhs_offset)) => match (lhs.end_from(lhs_offset), rhs.end_from(rhs_offset)) {
+                (Some(lhs_end), Some(rhs_end)) => lhs_offset <= rhs_offset && rhs_end <= lhs_end,
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+    /// Can callee read or write location.
+    pub fn call_may_access(&self, location: &MemoryLocation) -> bool {
+        !self.is_local(location)
+    }
+    /// Can call instruction write location, return false if instruction is not a call.
+    pub fn call_clobbers(&self, func: &Function, call: Instruction, location: &MemoryLocation) -> bool {
+        matches!(func.get_inst_data(call), InstructionData::Call { .. }) && self.call_may_access(location)
+    }
+}
+
+impl FormatTable for AliasAnalysis {
+    fn format_table(&self, func: &Function, _module: &Module) -> String {
+        let mut format_string = get_table_header("Alias Analysis");
+        for inst in get_sorted_blocks(func)
+            .into_iter()
+            .flat_map(|block| func.get_insts_of_block(block))
+        {
+            let Some(location) = MemoryLocation::of_inst(func, inst) else {
+                continue;
+            };
+            let pointer = self.get_location_pointer(&location);
+            let origin = match pointer.origin {
+                AddressOrigin::StackSlot(slot) if self.is_escaped(slot) => format!("escaped slot reg{}", slot.0),
+                AddressOrigin::StackSlot(slot) => format!("slot reg{}", slot.0),
+                AddressOrigin::Param(value) => format!("param reg{}", value.0),
+                AddressOrigin::Global(global) => format!("global greg{}", global.0),
+                AddressOrigin::GlobalLoad(global) => format!("loaded greg{}", global.0),
+                AddressOrigin::ToAddress(value) => format!("to.address reg{}", value.0),
+                AddressOrigin::Opaque(value) => format!("opaque reg{}", value.0),
+            };
+            let offset = pointer.offset.map_or("?".to_owned(), |offset| offset.to_string());
+            format_string.push_str(&format!(
+                "inst{}: {} + {}, size {}\n",
+                inst.0, origin, offset, location.size
+            ));
+        }
+        format_string
+    }
+}
+
+#[derive(Default)]
+pub struct AliasAnalysisPass;
+
+impl AnalysisPass<AliasAnalysis> for AliasAnalysisPass {
+    fn process(&mut self, func: &Function) -> AliasAnalysis {
+        let mut alias = AliasAnalysis::new();
+        self.resolve_values(func, &mut alias);
+        self.resolve_globals(func, &mut alias);
+        self.collect_escaped_slots(func, &mut alias);
+        alias
+    }
+}
+
+impl AliasAnalysisPass {
+    pub fn new() -> Self {
+        Self
+    }
+    /// Resolve pointer of every value, instructions are visited repeatedly until no pointer
+    /// changes, since operand may be visited after its user.
+    fn resolve_values(&self, func: &Function, alias: &mut AliasAnalysis) {
+        for param in &func.entities.params {
+            alias.pointers.insert(
+                MemoryBase::Value(*param),
+                Pointer {
+                    origin: AddressOrigin::Param(*param),
+                    offset: Some(0),
+                },
+            );
+        }
+        let insts = func.insts();
+        let mut is_changed = true;
+        while is_changed {
+            is_changed = false;
+            for inst in &insts {
+                let Some(result) = func.get_inst_result(*inst) else {
+                    continue;
+                };
+                let pointer = self.resolve_inst(func, *inst, result, alias);
+                if alias.pointers.insert(MemoryBase::Value(result), pointer) != Some(pointer) {
+                    is_changed = true;
+                }
+            }
+        }
+    }
+    fn resolve_inst(&self, func: &Function, inst: Instruction, result: Value, alias: &mut AliasAnalysis) -> Pointer {
+        match func.get_inst_data(inst) {
+            InstructionData::StackAlloc { size, .. } => {
+                let size = RuntimeValue::from(size).as_u64().unwrap_or(0);
+                alias.stack_slots.insert(result, size);
+                Pointer {
+                    origin: AddressOrigin::StackSlot(result),
+                    offset: Some(0),
+                }
+            }
+            InstructionData::Move { src, .. }
+            | InstructionData::Unary {
+                opcode: OpCode::Mov,
+                value: src,
+            } => alias.get_pointer(MemoryBase::Value(*src)),
+            InstructionData::BinaryI {
+                opcode: OpCode::Addi,
+                value,
+                imm,
+            } => alias
+                .get_pointer(MemoryBase::Value(*value))
+                .add_offset(get_immediate_offset(imm)),
+            InstructionData::BinaryI {
+                opcode: OpCode::Subi,
+                value,
+                imm,
+            } => alias
+                .get_pointer(MemoryBase::Value(*value))
+                .add_offset(get_immediate_offset(imm).map(i64::wrapping_neg)),
+            InstructionData::Convert {
+                opcode: OpCode::ToAddress,
+                src,
+            } => Pointer {
+                origin: AddressOrigin::ToAddress(*src),
+                offset: Some(0),
+            },
+            _ => Pointer {
+                origin: AddressOrigin::Opaque(result),
+                offset: Some(0),
+            },
+        }
+    }
+    /// Resolve pointer of every global value through chain of `AddI` and `Load`.
+    fn resolve_globals(&self, func: &Function, alias: &mut AliasAnalysis) {
+        let mut globals = func.global_values.keys().copied().collect::<Vec<_>>();
+        globals.sort();
+        for global in globals {
+            let mut offset = 0i64;
+            let mut runner = global;
+            let origin = loop {
+                match func.global_values.get(&runner) {
+                    Some(GlobalValueData::AddI { base, offset: add, .. }) => {
+                        offset = offset.wrapping_add(add.0 as i64);
+                        runner = *base;
+                    }
+                    Some(GlobalValueData::Symbol { name }) => {
+                        // symbol may be declared by more than one global value.
+                        let first = func
+                            .global_values
+                            .iter()
+                            .filter(
+                                |(_, data)| matches!(data, GlobalValueData::Symbol { name: other } if other == name),
+                            )
+                            .map(|(global, _)| *global)
+                            .min()
+                            .unwrap();
+                        break AddressOrigin::Global(first);
+                    }
+                    Some(GlobalValueData::Load { .. }) | None => break AddressOrigin::GlobalLoad(runner),
+                }
+            };
+            alias.pointers.insert(
+                MemoryBase::Global(global),
+                Pointer {
+                    origin,
+                    offset: Some(offset),
+                },
+            );
+        }
+    }
+    /// Stack slot escapes when it or address derived from it is used other than address
+    /// of `load` and `store`, address derived by `mov`, `addi` and `subi` is tracked by
+    /// its own uses.
+    fn collect_escaped_slots(&self, func: &Function, alias: &mut AliasAnalysis) {
+        for inst in func.insts() {
+            let escaped = match func.get_inst_data(inst) {
+                InstructionData::LoadRegister { .. } => vec![],
+                InstructionData::StoreRegister { src, .. } => vec![*src],
+                InstructionData::Move { .. }
+                | InstructionData::Unary {
+                    opcode: OpCode::Mov, ..
+                } => vec![],
+                InstructionData::BinaryI {
+                    opcode: OpCode::Addi | OpCode::Subi,
+                    ..
+                } => vec![],
+                inst_data => inst_data.get_operands(),
+            };
+            for value in escaped {
+                if let AddressOrigin::StackSlot(slot) = alias.get_pointer(MemoryBase::Value(value)).origin {
+                    alias.escaped_slots.insert(slot);
+                }
+            }
+        }
+    }
+}
+
+/// Get offset added by immediate, float immediate is not an offset.
+fn get_immediate_offset(imm: &Immediate) -> Option<i64> {
+    match *imm {
+        Immediate::U8(value) => Some(value as i64),
+        Immediate::U16(value) => Some(value as i64),
+        Immediate::U32(value) => Some(value as i64),
+        Immediate::U64(value) => Some(value as i64),
+        Immediate::I16(value) => Some(value as i64),
+        Immediate::I32(value) => Some(value as i64),
+        Immediate::I64(value) => Some(value),
+        Immediate::F32(_) | Immediate::F64(_) => None,
+    }
+}
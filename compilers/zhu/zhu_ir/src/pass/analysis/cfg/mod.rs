@@ -33,8 +33,11 @@ impl ControlFlowGraph {
         // init
         self.init(function);
         // compute loop
-        for block in function.entities.blocks.keys() {
-            let last_inst = function.layout.blocks.get(block).unwrap().last_inst.unwrap();
+        for block in &function.blocks() {
+            // empty block has no terminator, it can only be report by verifier.
+            let Some(last_inst) = function.layout.blocks.get(block).unwrap().last_inst else {
+                continue;
+            };
             let last_inst_data = function.entities.insts.get(&last_inst).unwrap();
             match last_inst_data {
                 InstructionData::Jump { dst, .. } => {
@@ -45,7 +48,7 @@ impl ControlFlowGraph {
                     self.connect(block, alter);
                 }
                 InstructionData::Ret { .. } => {
-                    self.exists.insert(block.clone());
+                    self.exists.insert(*block);
                 }
                 _ => { /* Should be unreach */ }
             }
@@ -66,6 +69,22 @@ impl ControlFlowGraph {
     pub fn get_successors(&self, block: &Block) -> &HashSet<Block> {
         &self.get_block_cfg_node(block).successors
     }
+    /// Get blocks reachable from entry, empty when function has no block.
+    pub fn get_reachable_blocks(&self) -> HashSet<Block> {
+        let Some(entry) = self.entry else {
+            return HashSet::new();
+        };
+        let mut reachable = HashSet::from([entry]);
+        let mut worklist = vec![entry];
+        while let Some(block) = worklist.pop() {
+            for successor in self.get_successors(&block) {
+                if reachable.insert(*successor) {
+                    worklist.push(*successor);
+                }
+            }
+        }
+        reachable
+    }
 }
 
 impl ControlFlowGraph {
@@ -27,6 +27,10 @@ impl DomTree {
             table: Default::default(),
         }
     }
+    /// Is block in dominator tree, unreachable block is not in tree.
+    pub fn contains(&self, block: Block) -> bool {
+        self.table.contains_key(&block)
+    }
     /// Is bloock a dominate block b
     /// -> Dom(b) contain a
     pub fn dominate(&self, a: Block, b: Block) -> bool {
@@ -72,13 +76,20 @@ impl DomTree {
     }
 }
 
+/// Dominator tree is only computed over blocks reachable from entry, unreachable block
+/// has no dominator and is never a predecessor of reachable block in the tree.
 pub struct DomTreePass<'a> {
     cfg: &'a ControlFlowGraph,
+    reachable: HashSet<Block>,
 }
 
 impl<'a> AnalysisPass<DomTree> for DomTreePass<'a> {
     fn process(&mut self, _func: &Function) -> DomTree {
         let mut dom_tree = DomTree::new();
+        if self.cfg.entry.is_none() {
+            return dom_tree;
+        }
+        self.reachable = self.cfg.get_reachable_blocks();
         self.compute_dom(&mut dom_tree);
         self.compute_idom(&mut dom_tree);
         self.compute_df(&mut dom_tree);
@@ -88,15 +99,27 @@ impl<'a> AnalysisPass<DomTree> for DomTreePass<'a> {
 
 impl<'a> DomTreePass<'a> {
     pub fn new(cfg: &'a ControlFlowGraph) -> Self {
-        Self { cfg }
+        Self {
+            cfg,
+            reachable: Default::default(),
+        }
+    }
+    /// Get predecessors of block which are reachable from entry.
+    fn get_reachable_predecessors(&self, block: &Block) -> Vec<Block> {
+        self.cfg
+            .get_predecessors(block)
+            .iter()
+            .filter(|predecessor| self.reachable.contains(predecessor))
+            .copied()
+            .collect()
     }
     /// Compute dom by flow equation.
     ///
     /// Dominator is a forward flow anaylsis
     fn compute_dom(&mut self, dom_tree: &mut DomTree) {
         // init
-        for block in self.cfg.blocks.keys() {
-            let all_block: HashSet<Block> = self.cfg.blocks.keys().map(|b| b.clone()).collect();
+        for block in &self.reachable {
+            let all_block = self.reachable.clone();
             dom_tree.table.insert(
                 block.clone(),
                 DomTableEntry {
@@ -112,9 +135,9 @@ impl<'a> DomTreePass<'a> {
         let mut is_change = true;
         while is_change {
             is_change = false;
-            for block in self.cfg.blocks.keys() {
+            for block in &self.reachable {
                 let mut next_dom_of_bb = dom_tree.table.get_mut(block).unwrap().dominators.clone();
-                for predecessor in self.cfg.get_predecessors(block) {
+                for predecessor in self.get_reachable_predecessors(block) {
                     let dom_of_predecessor = &dom_tree.table.get_mut(&predecessor).unwrap().dominators;
                     next_dom_of_bb = next_dom_of_bb
                         .intersection(dom_of_predecessor)
@@ -133,8 +156,8 @@ impl<'a> DomTreePass<'a> {
     ///
     /// Using BFS on predecessor edge to get the closest dominator
     fn compute_idom(&mut self, dom_tree: &mut DomTree) {
-        for block in self.cfg.blocks.keys() {
-            let mut worklist: Vec<Block> = self.cfg.get_predecessors(block).iter().map(|b| b.clone()).collect();
+        for block in &self.reachable {
+            let mut worklist = self.get_reachable_predecessors(block);
             let mut marks: HashSet<Block> = HashSet::from([block.clone()]);
             'backward_traversal: while worklist.len() != 0 {
                 let mut next_worklist = Vec::new();
@@ -153,7 +176,7 @@ impl<'a> DomTreePass<'a> {
                                 .insert(block.clone());
                             break 'backward_traversal;
                         }
-                        next_worklist.extend(self.cfg.get_predecessors(b).iter().map(|b| b.clone()));
+                        next_worklist.extend(self.get_reachable_predecessors(b));
                     }
                 }
                 worklist = next_worklist;
@@ -163,8 +186,8 @@ impl<'a> DomTreePass<'a> {
     fn compute_df(&mut self, dom_tree: &mut DomTree) {
         // collect join nodes
         let mut join_nodes = Vec::new();
-        for (block, block_node) in &self.cfg.blocks {
-            if block_node.predecessors.len() > 1 {
+        for block in &self.reachable {
+            if self.get_reachable_predecessors(block).len() > 1 {
                 join_nodes.push(block.clone());
             }
         }
@@ -177,7 +200,7 @@ impl<'a> DomTreePass<'a> {
             } else {
                 continue;
             };
-            for predeceesor_block in self.cfg.get_predecessors(&join_node) {
+            for predeceesor_block in self.get_reachable_predecessors(&join_node) {
                 let mut runner_id = predeceesor_block.clone();
                 while runner_id != idom {
                     dom_tree
@@ -0,0 +1,464 @@
+use std::collections::HashMap;
+
+use crate::entities::block::Block;
+use crate::entities::function::Function;
+use crate::entities::instruction::opcode::{CmpFlag, OpCode};
+use crate::entities::instruction::InstructionData;
+use crate::entities::module::Module;
+use crate::entities::runtime_value::RuntimeValue;
+use crate::entities::value::{Value, ValueData};
+use crate::pass::analysis::cfg::ControlFlowGraph;
+use crate::pass::opt::licm::natural_loop::NaturalLoop;
+use crate::pass::{get_sorted_blocks, get_table_header, FormatTable};
+
+/// Loop whose trip count is greater than this is treated as unknown trip count.
+pub const MAX_TRIP_COUNT: u64 = 1 << 16;
+
+/// Create induction variable analysis result of a natural loop.
+pub fn induction_variable_analysis(
+    func: &Function,
+    cfg: &ControlFlowGraph,
+    natural_loop: &NaturalLoop,
+) -> LoopInductionVariables {
+    let mut pass = InductionVariablePass::new(cfg, natural_loop);
+    pass.process(func)
+}
+
+/// ## Basic Induction Variable
+/// Phi in loop header which is `init` when enter loop and is increased by constant
+/// `step` every iteration:
+/// ```text
+/// header:
+///   phi = phi [outside, init], [tail, update]
+/// ...
+///   update = addi phi step
+/// ```
+/// `update` can also be `subi` or `add`, `sub` with constant, `step` of `subi` is
+/// stored as negative step.
+#[derive(Debug, PartialEq, Clone)]
+pub struct InductionVariable {
+    pub phi: Value,
+    pub init: Value,
+    pub update: Value,
+    pub step: RuntimeValue,
+}
+
+/// ## Derived Induction Variable
+/// Value in loop which is a linear function of basic induction variable `basic` in
+/// every iteration: `value = scale * basic + base + offset`, `base` is a value defined
+/// outside the loop. derived from `add`, `sub`, `mul` and their immediate versions
+/// with constant or loop invariant operand, for example
+/// ```text
+///   offset = muli phi 4
+///   address = add base offset
+/// ```
+/// `offset` is `4 * phi + 0` and `address` is `4 * phi + base + 0`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DerivedInductionVariable {
+    pub value: Value,
+    pub basic: Value,
+    pub scale: RuntimeValue,
+    pub base: Option<Value>,
+    pub offset: RuntimeValue,
+}
+
+/// ## Exit Condition
+/// Loop exits from `exiting_block` when compare of induction variable and `bound` is
+/// equal to `exit_when`. Induction variable is always the left hand side of compare,
+/// it is `update` of induction variable if `use_update` is true, otherwise `phi`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExitCondition {
+    pub exiting_block: Block,
+    pub exit_target: Block,
+    pub induction_variable: Value,
+    pub use_update: bool,
+    pub flag: CmpFlag,
+    pub bound: Value,
+    pub exit_when: bool,
+}
+
+/// Induction variables and trip count of a loop.
+#[derive(Debug, Default)]
+pub struct LoopInductionVariables {
+    induction_variables: Vec<InductionVariable>,
+    derived_induction_variables: Vec<DerivedInductionVariable>,
+    exit_condition: Option<ExitCondition>,
+    trip_count: Option<u64>,
+}
+
+impl LoopInductionVariables {
+    pub fn new() -> Self {
+        Self {
+            induction_variables: Default::default(),
+            derived_induction_variables: Default::default(),
+            exit_condition: None,
+            trip_count: None,
+        }
+    }
+    pub fn get_induction_variables(&self) -> &[InductionVariable] {
+        &self.induction_variables
+    }
+    /// Get induction variable by its phi.
+    pub fn get_induction_variable(&self, phi: Value) -> Option<&InductionVariable> {
+        self.induction_variables
+            .iter()
+            .find(|induction_variable| induction_variable.phi == phi)
+    }
+    /// Get derived induction variables in order of discovery, a derived induction
+    /// variable is always after the ones it is derived from.
+    pub fn get_derived_induction_variables(&self) -> &[DerivedInductionVariable] {
+        &self.derived_induction_variables
+    }
+    pub fn get_derived_induction_variable(&self, value: Value) -> Option<&DerivedInductionVariable> {
+        self.derived_induction_variables
+            .iter()
+            .find(|derived| derived.value == value)
+    }
+    pub fn get_exit_condition(&self) -> Option<&ExitCondition> {
+        self.exit_condition.as_ref()
+    }
+    /// Get number of times loop header is executed when loop is entered once, which is
+    /// number of back edge taken plus one. return none if trip count is not a constant
+    /// or greater than `MAX_TRIP_COUNT`.
+    pub fn get_trip_count(&self) -> Option<u64> {
+        self.trip_count
+    }
+}
+
+impl FormatTable for LoopInductionVariables {
+    fn format_table(&self, _func: &Function, _module: &Module) -> String {
+        let mut format_string = get_table_header("Induction Variables");
+        for induction_variable in &self.induction_variables {
+            format_string.push_str(&format!(
+                "reg{}: init reg{}, update reg{}, step {}\n",
+                induction_variable.phi.0,
+                induction_variable.init.0,
+                induction_variable.update.0,
+                fmt_runtime_value(&induction_variable.step)
+            ));
+        }
+        for derived in &self.derived_induction_variables {
+            format_string.push_str(&format!(
+                "reg{}: {} * reg{}{} + {}\n",
+                derived.value.0,
+                fmt_runtime_value(&derived.scale),
+                derived.basic.0,
+                derived.base.map_or(String::new(), |base| format!(" + reg{}", base.0)),
+                fmt_runtime_value(&derived.offset)
+            ));
+        }
+        if let Some(exit_condition) = &self.exit_condition {
+            let tested = match self.get_induction_variable(exit_condition.induction_variable) {
+                Some(induction_variable) if exit_condition.use_update => induction_variable.update,
+                _ => exit_condition.induction_variable,
+            };
+            format_string.push_str(&format!(
+                "Exit: block{} to block{} when reg{} {:?} reg{} is {}\n",
+                exit_condition.exiting_block.0,
+                exit_condition.exit_target.0,
+                tested.0,
+                exit_condition.flag,
+                exit_condition.bound.0,
+                exit_condition.exit_when
+            ));
+        }
+        match self.trip_count {
+            Some(trip_count) => format_string.push_str(&format!("Trip count: {}\n", trip_count)),
+            None => format_string.push_str("Trip count: unknown\n"),
+        }
+        format_string
+    }
+}
+
+fn fmt_runtime_value(value: &RuntimeValue) -> String {
+    match value {
+        RuntimeValue::I16(num) => num.to_string(),
+        RuntimeValue::I32(num) => num.to_string(),
+        RuntimeValue::I64(num) => num.to_string(),
+        value => value.as_u64().map_or("?".to_owned(), |num| num.to_string()),
+    }
+}
+
+pub struct InductionVariablePass<'a> {
+    cfg: &'a ControlFlowGraph,
+    natural_loop: &'a NaturalLoop,
+}
+
+impl<'a> InductionVariablePass<'a> {
+    pub fn new(cfg: &'a ControlFlowGraph, natural_loop: &'a NaturalLoop) -> Self {
+        Self { cfg, natural_loop }
+    }
+    pub fn process(&mut self, func: &Function) -> LoopInductionVariables {
+        let mut result = LoopInductionVariables::new();
+        result.induction_variables = self.find_induction_variables(func);
+        result.derived_induction_variables = self.find_derived_induction_variables(func, &result.induction_variables);
+        result.exit_condition = self.find_exit_condition(func, &result.induction_variables);
+        result.trip_count = result.exit_condition.as_ref().and_then(|exit_condition| {
+            let induction_variable = result.get_induction_variable(exit_condition.induction_variable)?;
+            compute_trip_count(func, induction_variable, exit_condition)
+        });
+        result
+    }
+    fn is_in_loop(&self, func: &Function, value: Value) -> bool {
+        match func.get_value_data(value) {
+            ValueData::Inst { inst, .. } => self.natural_loop.blocks.contains(&func.get_block_of_inst(*inst)),
+            ValueData::Param { .. } => false,
+        }
+    }
+    /// Find phis in header which has one value from outside and one from tail of loop,
+    /// value from tail is phi plus a constant.
+    fn find_induction_variables(&self, func: &Function) -> Vec<InductionVariable> {
+        let mut induction_variables = Vec::new();
+        let tail = self.natural_loop.tail;
+        for inst in func.get_insts_of_block(self.natural_loop.header) {
+            let InstructionData::Phi { from, .. } = func.get_inst_data(inst) else {
+                continue;
+            };
+            let phi = func.get_inst_result(inst).unwrap();
+            if from.len() != 2 || !func.value_type(phi).is_int() {
+                continue;
+            }
+            let (Some((_, update)), Some((_, init))) = (
+                from.iter().find(|(block, _)| *block == tail),
+                from.iter().find(|(block, _)| !self.natural_loop.blocks.contains(block)),
+            ) else {
+                continue;
+            };
+            if !self.is_in_loop(func, *update) {
+                continue;
+            }
+            if let Some(step) = get_step(func, phi, *update) {
+                induction_variables.push(InductionVariable {
+                    phi,
+                    init: *init,
+                    update: *update,
+                    step,
+                });
+            }
+        }
+        induction_variables
+    }
+    /// Find derived induction variables until no more value can be derived, since a
+    /// value may be derived from value defined in later block.
+    fn find_derived_induction_variables(
+        &self,
+        func: &Function,
+        induction_variables: &[InductionVariable],
+    ) -> Vec<DerivedInductionVariable> {
+        let mut linears = HashMap::new();
+        for induction_variable in induction_variables {
+            let ty = func.value_type(induction_variable.phi);
+            let zero = RuntimeValue::from_bytes(ty, &[]);
+            let one = RuntimeValue::from_bytes(ty, &[1]);
+            let phi = induction_variable.phi;
+            linears.insert(phi, (phi, one, None, zero));
+            linears.insert(induction_variable.update, (phi, one, None, induction_variable.step));
+        }
+        let loop_blocks = get_sorted_blocks(func)
+            .into_iter()
+            .filter(|block| self.natural_loop.blocks.contains(block))
+            .collect::<Vec<_>>();
+        let mut derived_induction_variables = Vec::new();
+        loop {
+            let mut changed = false;
+            for block in &loop_blocks {
+                for inst in func.get_insts_of_block(*block) {
+                    let Some(result) = func.get_inst_result(inst) else {
+                        continue;
+                    };
+                    if linears.contains_key(&result) {
+                        continue;
+                    }
+                    let Some((basic, scale, base, offset)) = self.derive(func, func.get_inst_data(inst), &linears)
+                    else {
+                        continue;
+                    };
+                    linears.insert(result, (basic, scale, base, offset));
+                    derived_induction_variables.push(DerivedInductionVariable {
+                        value: result,
+                        basic,
+                        scale,
+                        base,
+                        offset,
+                    });
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        derived_induction_variables
+    }
+    /// Get linear form of instruction if one operand is linear and another operand is
+    /// constant or loop invariant.
+    fn derive(&self, func: &Function, inst_data: &InstructionData, linears: &HashMap<Value, Linear>) -> Option<Linear> {
+        let (opcode, linear, other) = match inst_data {
+            InstructionData::BinaryI { opcode, value, imm } => {
+                let linear = *linears.get(value)?;
+                let constant = RuntimeValue::from(imm).cast(func.value_type(*value));
+                (*opcode, linear, Operand::Constant(constant))
+            }
+            InstructionData::Binary { opcode, args } => {
+                let (linear, other) = match (linears.get(&args[0]), linears.get(&args[1])) {
+                    (Some(linear), None) => (*linear, args[1]),
+                    (None, Some(linear)) if matches!(opcode, OpCode::Add | OpCode::Mul) => (*linear, args[0]),
+                    _ => return None,
+                };
+                let other = match func.get_constant_value(other) {
+                    Some(constant) => Operand::Constant(constant),
+                    None if !self.is_in_loop(func, other) => Operand::Invariant(other),
+                    None => return None,
+                };
+                (*opcode, linear, other)
+            }
+            _ => return None,
+        };
+        let (basic, scale, base, offset) = linear;
+        match (opcode, other) {
+            (OpCode::Add | OpCode::Addi, Operand::Constant(constant)) => Some((
+                basic,
+                scale,
+                base,
+                RuntimeValue::binary(OpCode::Add, offset, constant).ok()?,
+            )),
+            (OpCode::Sub | OpCode::Subi, Operand::Constant(constant)) => Some((
+                basic,
+                scale,
+                base,
+                RuntimeValue::binary(OpCode::Sub, offset, constant).ok()?,
+            )),
+            (OpCode::Add, Operand::Invariant(value)) if base.is_none() => Some((basic, scale, Some(value), offset)),
+            (OpCode::Mul | OpCode::Muli, Operand::Constant(constant)) if base.is_none() => Some((
+                basic,
+                RuntimeValue::binary(OpCode::Mul, scale, constant).ok()?,
+                None,
+                RuntimeValue::binary(OpCode::Mul, offset, constant).ok()?,
+            )),
+            _ => None,
+        }
+    }
+    /// Get blocks of loop which have successor outside the loop.
+    fn get_exiting_blocks(&self) -> Vec<Block> {
+        self.natural_loop
+            .blocks
+            .iter()
+            .filter(|block| {
+                self.cfg
+                    .get_successors(block)
+                    .iter()
+                    .any(|successor| !self.natural_loop.blocks.contains(successor))
+            })
+            .cloned()
+            .collect()
+    }
+    /// Exit condition is only recognized when loop has single exiting block which is
+    /// header or tail, so it is tested exactly once in every iteration.
+    fn find_exit_condition(&self, func: &Function, induction_variables: &[InductionVariable]) -> Option<ExitCondition> {
+        let exiting_blocks = self.get_exiting_blocks();
+        if exiting_blocks.len() != 1 {
+            return None;
+        }
+        let exiting_block = exiting_blocks[0];
+        if exiting_block != self.natural_loop.header && exiting_block != self.natural_loop.tail {
+            return None;
+        }
+        let InstructionData::BrIf {
+            test, conseq, alter, ..
+        } = func.get_inst_data(func.layout.get_last_inst(exiting_block))
+        else {
+            return None;
+        };
+        let (exit_target, exit_when) = match (
+            self.natural_loop.blocks.contains(conseq),
+            self.natural_loop.blocks.contains(alter),
+        ) {
+            (false, true) => (*conseq, true),
+            (true, false) => (*alter, false),
+            _ => return None,
+        };
+        let ValueData::Inst { inst: test_inst, .. } = func.get_value_data(*test) else {
+            return None;
+        };
+        let InstructionData::Icmp { flag, args, .. } = func.get_inst_data(*test_inst) else {
+            return None;
+        };
+        let find = |value: Value| {
+            induction_variables.iter().find_map(|induction_variable| {
+                if induction_variable.phi == value {
+                    Some((induction_variable.phi, false))
+                } else if induction_variable.update == value {
+                    Some((induction_variable.phi, true))
+                } else {
+                    None
+                }
+            })
+        };
+        let (induction_variable, use_update, flag, bound) = match (find(args[0]), find(args[1])) {
+            (Some((phi, use_update)), _) => (phi, use_update, *flag, args[1]),
+            (None, Some((phi, use_update))) => (phi, use_update, flag.swapped(), args[0]),
+            (None, None) => return None,
+        };
+        if self.is_in_loop(func, bound) && func.get_constant_value(bound).is_none() {
+            return None;
+        }
+        Some(ExitCondition {
+            exiting_block,
+            exit_target,
+            induction_variable,
+            use_update,
+            flag,
+            bound,
+            exit_when,
+        })
+    }
+}
+
+/// Linear form `(basic, scale, base, offset)` of derived induction variable.
+type Linear = (Value, RuntimeValue, Option<Value>, RuntimeValue);
+
+/// Operand of instruction which is not linear.
+enum Operand {
+    Constant(RuntimeValue),
+    Invariant(Value),
+}
+
+/// Get step of update if update is phi plus or minus a constant.
+fn get_step(func: &Function, phi: Value, update: Value) -> Option<RuntimeValue> {
+    let ValueData::Inst { inst, ty } = func.get_value_data(update) else {
+        return None;
+    };
+    let (opcode, constant) = match func.get_inst_data(*inst) {
+        InstructionData::BinaryI { opcode, value, imm } if *value == phi => (*opcode, RuntimeValue::from(imm).cast(ty)),
+        InstructionData::Binary { opcode, args } if args[0] == phi => (*opcode, func.get_constant_value(args[1])?),
+        InstructionData::Binary {
+            opcode: OpCode::Add,
+            args,
+        } if args[1] == phi => (OpCode::Add, func.get_constant_value(args[0])?),
+        _ => return None,
+    };
+    match opcode {
+        OpCode::Add | OpCode::Addi => Some(constant),
+        OpCode::Sub | OpCode::Subi => RuntimeValue::unary(OpCode::Neg, constant).ok(),
+        _ => None,
+    }
+}
+
+/// Compute trip count by running the induction variable until loop exits.
+fn compute_trip_count(
+    func: &Function,
+    induction_variable: &InductionVariable,
+    exit_condition: &ExitCondition,
+) -> Option<u64> {
+    let mut value = func.get_constant_value(induction_variable.init)?;
+    let bound = func.get_constant_value(exit_condition.bound)?;
+    for trip_count in 1..=MAX_TRIP_COUNT {
+        let next = RuntimeValue::binary(OpCode::Add, value, induction_variable.step).ok()?;
+        let tested = if exit_condition.use_update { next } else { value };
+        if RuntimeValue::compare(exit_condition.flag, tested, bound).ok()? == exit_condition.exit_when {
+            return Some(trip_count);
+        }
+        value = next;
+    }
+    None
+}
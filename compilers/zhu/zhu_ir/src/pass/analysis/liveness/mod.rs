@@ -0,0 +1,297 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::entities::block::Block;
+use crate::entities::function::Function;
+use crate::entities::instruction::InstructionData;
+use crate::entities::module::Module;
+use crate::entities::value::Value;
+use crate::pass::analysis::cfg::ControlFlowGraph;
+use crate::pass::analysis::rpo::RevresePostOrder;
+use crate::pass::{get_sorted_blocks, get_table_header, AnalysisPass, FormatTable};
+
+/// Create liveness analysis result.
+pub fn liveness_analysis(func: &Function, cfg: &ControlFlowGraph, rpo: &RevresePostOrder) -> Liveness {
+    let mut pass = LivenessPass::new(cfg, rpo);
+    pass.process(func)
+}
+
+/// ## Live Interval
+/// Positions where a value is live in linear order of instructions, as sorted and
+/// disjoint half open ranges `[start, end)`. A value is live from its definition to
+/// the position of its last use, so a value can share register with a value which
+/// is defined by the instruction of its last use.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct LiveInterval {
+    ranges: Vec<(usize, usize)>,
+}
+
+impl LiveInterval {
+    /// Add a range to interval, overlapping or adjacent ranges are merged.
+    pub fn add_range(&mut self, start: usize, end: usize) {
+        let index = self.ranges.partition_point(|range| range.0 < start);
+        self.ranges.insert(index, (start, end));
+        let mut merged: Vec<(usize, usize)> = Vec::with_capacity(self.ranges.len());
+        for (start, end) in std::mem::take(&mut self.ranges) {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        self.ranges = merged;
+    }
+    pub fn get_ranges(&self) -> &[(usize, usize)] {
+        &self.ranges
+    }
+    /// First position of interval.
+    pub fn start(&self) -> usize {
+        self.ranges.first().map_or(0, |range| range.0)
+    }
+    /// Position after last position of interval.
+    pub fn end(&self) -> usize {
+        self.ranges.last().map_or(0, |range| range.1)
+    }
+    /// Is value live at position.
+    pub fn covers(&self, position: usize) -> bool {
+        self.ranges
+            .iter()
+            .any(|(start, end)| *start <= position && position < *end)
+    }
+    /// Do two intervals live at same position.
+    pub fn overlaps(&self, other: &LiveInterval) -> bool {
+        self.ranges.iter().any(|(start, end)| {
+            other
+                .ranges
+                .iter()
+                .any(|(other_start, other_end)| start < other_end && other_start < end)
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct Liveness {
+    live_in: HashMap<Block, HashSet<Value>>,
+    live_out: HashMap<Block, HashSet<Value>>,
+    /// Blocks in linear order, which is reverse post order.
+    linear_order: Vec<Block>,
+    /// Positions of block in linear order as `[start, end)`.
+    block_ranges: HashMap<Block, (usize, usize)>,
+    intervals: HashMap<Value, LiveInterval>,
+}
+
+impl FormatTable for Liveness {
+    fn format_table(&self, func: &Function, _module: &Module) -> String {
+        let mut format_string = get_table_header("Liveness");
+        for block in get_sorted_blocks(func) {
+            let Some((start, end)) = self.block_ranges.get(&block) else {
+                continue;
+            };
+            format_string.push_str(&format!("Block{}: [{}, {})\n", block.0, start, end));
+            format_string.push_str("\tLive In:\n");
+            for value in sorted_values(&self.live_in[&block]) {
+                format_string.push_str(&format!("\t\treg{}\n", value.0));
+            }
+            format_string.push_str("\tLive Out:\n");
+            for value in sorted_values(&self.live_out[&block]) {
+                format_string.push_str(&format!("\t\treg{}\n", value.0));
+            }
+        }
+        format_string.push_str("Intervals:\n");
+        for value in sorted_values(self.intervals.keys()) {
+            format_string.push_str(&format!("\treg{}: {}\n", value.0, fmt_ranges(&self.intervals[&value])));
+        }
+        format_string
+    }
+}
+
+fn sorted_values<'a>(values: impl IntoIterator<Item = &'a Value>) -> Vec<Value> {
+    let mut values = values.into_iter().copied().collect::<Vec<_>>();
+    values.sort_by_key(|value| value.0);
+    values
+}
+
+/// Format ranges of interval as `[start, end)` separated by space.
+pub fn fmt_ranges(interval: &LiveInterval) -> String {
+    interval
+        .get_ranges()
+        .iter()
+        .map(|(start, end)| format!("[{}, {})", start, end))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Liveness {
+    pub fn new() -> Self {
+        Self {
+            live_in: Default::default(),
+            live_out: Default::default(),
+            linear_order: Default::default(),
+            block_ranges: Default::default(),
+            intervals: Default::default(),
+        }
+    }
+    /// Get live in set of block, results of phis in block are not included since they
+    /// are defined at the start of block. Panic if block is unreachable.
+    pub fn get_live_in(&self, block: Block) -> &HashSet<Value> {
+        self.live_in.get(&block).unwrap()
+    }
+    /// Get live out set of block, include operands of phis in successors which come
+    /// from block. Panic if block is unreachable.
+    pub fn get_live_out(&self, block: Block) -> &HashSet<Value> {
+        self.live_out.get(&block).unwrap()
+    }
+    /// Get reachable blocks in linear order.
+    pub fn get_linear_order(&self) -> &[Block] {
+        &self.linear_order
+    }
+    /// Get positions of block as `[start, end)`, `start` is the position of block itself
+    /// and instructions follow it. Panic if block is unreachable.
+    pub fn get_block_range(&self, block: Block) -> (usize, usize) {
+        *self.block_ranges.get(&block).unwrap()
+    }
+    /// Get live interval of value, value which is never defined in reachable blocks
+    /// has no interval.
+    pub fn get_interval(&self, value: Value) -> Option<&LiveInterval> {
+        self.intervals.get(&value)
+    }
+    pub fn get_intervals(&self) -> &HashMap<Value, LiveInterval> {
+        &self.intervals
+    }
+}
+
+pub struct LivenessPass<'a> {
+    cfg: &'a ControlFlowGraph,
+    rpo: &'a RevresePostOrder,
+}
+
+impl<'a> AnalysisPass<Liveness> for LivenessPass<'a> {
+    fn process(&mut self, func: &Function) -> Liveness {
+        let mut liveness = Liveness::new();
+        self.run(func, &mut liveness);
+        liveness
+    }
+}
+
+impl<'a> LivenessPass<'a> {
+    pub fn new(cfg: &'a ControlFlowGraph, rpo: &'a RevresePostOrder) -> Self {
+        Self { cfg, rpo }
+    }
+    /// Run backward data flow of liveness on SSA form, and then build intervals.
+    /// ```text
+    /// live_out = union of (successor live_in + operands of successor phis from block)
+    /// live_in = upward_exposed + (live_out - defs)
+    /// ```
+    fn run(&mut self, function: &Function, liveness: &mut Liveness) {
+        if function.first_block().is_none() {
+            return;
+        }
+        liveness.linear_order = self.rpo.get_blocks_in_rpo();
+        let mut upward_exposed = HashMap::new();
+        let mut defs = HashMap::new();
+        let mut phi_uses: HashMap<Block, HashSet<Value>> = HashMap::new();
+        for block in &liveness.linear_order {
+            let (block_upward_exposed, block_defs) = self.compute_single_block_set(function, *block, &mut phi_uses);
+            upward_exposed.insert(*block, block_upward_exposed);
+            defs.insert(*block, block_defs);
+            liveness.live_in.insert(*block, HashSet::new());
+            liveness.live_out.insert(*block, HashSet::new());
+        }
+        let mut is_changed = true;
+        while is_changed {
+            is_changed = false;
+            for block in liveness.linear_order.iter().rev() {
+                let mut out_set = phi_uses.get(block).cloned().unwrap_or_default();
+                for successor in self.cfg.get_successors(block) {
+                    out_set.extend(liveness.live_in[successor].iter().copied());
+                }
+                let mut in_set = upward_exposed[block].clone();
+                in_set.extend(out_set.difference(&defs[block]).copied());
+                liveness.live_out.insert(*block, out_set);
+                if in_set != liveness.live_in[block] {
+                    is_changed = true;
+                    liveness.live_in.insert(*block, in_set);
+                }
+            }
+        }
+        self.build_intervals(function, liveness);
+    }
+    /// Get upward exposed uses and definitions of block, operands of phis are recorded
+    /// as uses at the end of predecessors.
+    fn compute_single_block_set(
+        &self,
+        function: &Function,
+        block: Block,
+        phi_uses: &mut HashMap<Block, HashSet<Value>>,
+    ) -> (HashSet<Value>, HashSet<Value>) {
+        let mut upward_exposed = HashSet::new();
+        let mut defs = HashSet::new();
+        if Some(block) == function.first_block() {
+            defs.extend(function.entities.params.iter().copied());
+        }
+        for inst in function.get_insts_of_block(block) {
+            match function.get_inst_data(inst) {
+                InstructionData::Phi { from, .. } => {
+                    for (predecessor, value) in from {
+                        phi_uses.entry(*predecessor).or_default().insert(*value);
+                    }
+                }
+                inst_data => {
+                    for operand in inst_data.get_operands() {
+                        if !defs.contains(&operand) {
+                            upward_exposed.insert(operand);
+                        }
+                    }
+                }
+            }
+            if let Some(result) = function.get_inst_result(inst) {
+                defs.insert(result);
+            }
+        }
+        (upward_exposed, defs)
+    }
+    /// Number instructions in linear order and build interval of every value block by
+    /// block. First position of block is taken by block itself, parameters and results
+    /// of phis are defined at it. Value which is never used lives at its definition only.
+    fn build_intervals(&self, function: &Function, liveness: &mut Liveness) {
+        let mut position = 0;
+        for block in &liveness.linear_order {
+            let start = position;
+            position += function.get_insts_of_block(*block).len() + 1;
+            liveness.block_ranges.insert(*block, (start, position));
+        }
+        for block in &liveness.linear_order {
+            let (start, end) = liveness.block_ranges[block];
+            // end of live range of values which are live at current position.
+            let mut live_until: HashMap<Value, usize> =
+                liveness.live_out[block].iter().map(|value| (*value, end)).collect();
+            let insts = function.get_insts_of_block(*block);
+            for (index, inst) in insts.iter().enumerate().rev() {
+                let inst_position = start + 1 + index;
+                let inst_data = function.get_inst_data(*inst);
+                let is_phi = matches!(inst_data, InstructionData::Phi { .. });
+                if let Some(result) = function.get_inst_result(*inst) {
+                    let def_position = if is_phi { start } else { inst_position };
+                    let until = live_until.remove(&result).unwrap_or(def_position + 1);
+                    liveness
+                        .intervals
+                        .entry(result)
+                        .or_default()
+                        .add_range(def_position, until);
+                }
+                if !is_phi {
+                    for operand in inst_data.get_operands() {
+                        live_until.entry(operand).or_insert(inst_position);
+                    }
+                }
+            }
+            if Some(*block) == function.first_block() {
+                for param in &function.entities.params {
+                    let until = live_until.remove(param).unwrap_or(start + 1);
+                    liveness.intervals.entry(*param).or_default().add_range(start, until);
+                }
+            }
+            for (value, until) in live_until {
+                liveness.intervals.entry(value).or_default().add_range(start, until);
+            }
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::entities::block::Block;
+use crate::entities::function::Function;
+use crate::entities::module::Module;
+use crate::pass::analysis::cfg::ControlFlowGraph;
+use crate::pass::opt::licm::natural_loop::NaturalLoop;
+use crate::pass::{get_table_header, FormatTable};
+
+/// Create loop forest from natural loops of function.
+pub fn loop_forest_analysis(natural_loops: &[NaturalLoop]) -> LoopForest {
+    let mut pass = LoopForestPass::new(natural_loops);
+    pass.process()
+}
+
+/// Index of loop in loop forest.
+#[derive(Debug, PartialEq, Clone, Eq, Hash, Copy, PartialOrd, Ord)]
+pub struct LoopId(pub usize);
+
+/// ## Loop
+/// Natural loops which have the same header are merged as one loop, tails of those
+/// natural loops are latches of the loop. `depth` of outermost loop is 1.
+#[derive(Debug, Clone)]
+pub struct Loop {
+    pub header: Block,
+    pub latches: Vec<Block>,
+    pub blocks: HashSet<Block>,
+    pub parent: Option<LoopId>,
+    pub children: Vec<LoopId>,
+    pub depth: usize,
+}
+
+impl Loop {
+    pub fn contains(&self, block: Block) -> bool {
+        self.blocks.contains(&block)
+    }
+    /// Get predecessors of header which are not in the loop.
+    pub fn get_entering_blocks(&self, cfg: &ControlFlowGraph) -> Vec<Block> {
+        let mut blocks = cfg
+            .get_predecessors(&self.header)
+            .iter()
+            .filter(|predecessor| !self.contains(**predecessor))
+            .copied()
+            .collect::<Vec<_>>();
+        blocks.sort_by_key(|block| block.0);
+        blocks
+    }
+    /// Get blocks of loop which have successor outside the loop.
+    pub fn get_exiting_blocks(&self, cfg: &ControlFlowGraph) -> Vec<Block> {
+        let mut blocks = self
+            .blocks
+            .iter()
+            .filter(|block| {
+                cfg.get_successors(block)
+                    .iter()
+                    .any(|successor| !self.contains(*successor))
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        blocks.sort_by_key(|block| block.0);
+        blocks
+    }
+    /// Get blocks outside the loop which have predecessor in the loop.
+    pub fn get_exit_blocks(&self, cfg: &ControlFlowGraph) -> Vec<Block> {
+        let mut blocks = self
+            .blocks
+            .iter()
+            .flat_map(|block| cfg.get_successors(block))
+            .filter(|successor| !self.contains(**successor))
+            .copied()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        blocks.sort_by_key(|block| block.0);
+        blocks
+    }
+    /// Get the only entering block if it has no other successor than header.
+    pub fn get_preheader(&self, cfg: &ControlFlowGraph) -> Option<Block> {
+        let entering_blocks = self.get_entering_blocks(cfg);
+        match entering_blocks.as_slice() {
+            [preheader] if cfg.get_successors(preheader).len() == 1 => Some(*preheader),
+            _ => None,
+        }
+    }
+    /// Is every predecessor of exit blocks in the loop.
+    pub fn has_dedicated_exits(&self, cfg: &ControlFlowGraph) -> bool {
+        self.get_exit_blocks(cfg).iter().all(|exit_block| {
+            cfg.get_predecessors(exit_block)
+                .iter()
+                .all(|predecessor| self.contains(*predecessor))
+        })
+    }
+    /// Is loop in canonical form, which has a preheader, a single latch and dedicated
+    /// exit blocks.
+    pub fn is_simplified(&self, cfg: &ControlFlowGraph) -> bool {
+        self.get_preheader(cfg).is_some() && self.latches.len() == 1 && self.has_dedicated_exits(cfg)
+    }
+}
+
+/// ## Loop Forest
+/// Loops of function with nesting relation, outer loop always has smaller id than
+/// inner loops, so iterating loops by id visit parent before children.
+#[derive(Debug, Default)]
+pub struct LoopForest {
+    loops: Vec<Loop>,
+    innermost_loops: HashMap<Block, LoopId>,
+}
+
+impl LoopForest {
+    pub fn new() -> Self {
+        Self {
+            loops: Default::default(),
+            innermost_loops: Default::default(),
+        }
+    }
+    pub fn get_loop(&self, id: LoopId) -> &Loop {
+        &self.loops[id.0]
+    }
+    /// Get id of all loops, parent before children.
+    pub fn get_loop_ids(&self) -> Vec<LoopId> {
+        (0..self.loops.len()).map(LoopId).collect()
+    }
+    pub fn get_top_level_loops(&self) -> Vec<LoopId> {
+        self.get_loop_ids()
+            .into_iter()
+            .filter(|id| self.get_loop(*id).parent.is_none())
+            .collect()
+    }
+    pub fn get_loop_by_header(&self, header: Block) -> Option<LoopId> {
+        self.get_loop_ids()
+            .into_iter()
+            .find(|id| self.get_loop(*id).header == header)
+    }
+    /// Get innermost loop which contains block.
+    pub fn get_loop_of_block(&self, block: Block) -> Option<LoopId> {
+        self.innermost_loops.get(&block).copied()
+    }
+    /// Get number of loops which contain block, 0 if block is not in any loop.
+    pub fn get_loop_depth(&self, block: Block) -> usize {
+        self.get_loop_of_block(block).map_or(0, |id| self.get_loop(id).depth)
+    }
+    /// Get loops which have no child loop.
+    pub fn get_innermost_loops(&self) -> Vec<LoopId> {
+        self.get_loop_ids()
+            .into_iter()
+            .filter(|id| self.get_loop(*id).children.is_empty())
+            .collect()
+    }
+}
+
+impl FormatTable for LoopForest {
+    fn format_table(&self, _func: &Function, _module: &Module) -> String {
+        let fmt_blocks = |blocks: Vec<Block>| {
+            blocks
+                .iter()
+                .map(|block| format!("block{}", block.0))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut format_string = get_table_header("Loop Forest");
+        for id in self.get_loop_ids() {
+            let natural_loop = self.get_loop(id);
+            let mut blocks = natural_loop.blocks.iter().copied().collect::<Vec<_>>();
+            blocks.sort_by_key(|block| block.0);
+            format_string.push_str(&format!(
+                "{}loop{}: header block{}, latches [{}], parent {}, blocks [{}]\n",
+                "  ".repeat(natural_loop.depth - 1),
+                id.0,
+                natural_loop.header.0,
+                fmt_blocks(natural_loop.latches.clone()),
+                natural_loop
+                    .parent
+                    .map_or("none".to_owned(), |parent| format!("loop{}", parent.0)),
+                fmt_blocks(blocks)
+            ));
+        }
+        format_string
+    }
+}
+
+pub struct LoopForestPass<'a> {
+    natural_loops: &'a [NaturalLoop],
+}
+
+impl<'a> LoopForestPass<'a> {
+    pub fn new(natural_loops: &'a [NaturalLoop]) -> Self {
+        Self { natural_loops }
+    }
+    pub fn process(&mut self) -> LoopForest {
+        let mut forest = LoopForest::new();
+        forest.loops = self.merge_loops_by_header();
+        self.link_parents(&mut forest);
+        for id in forest.get_loop_ids() {
+            for block in forest.get_loop(id).blocks.clone() {
+                // inner loop is visited after its parent.
+                forest.innermost_loops.insert(block, id);
+            }
+        }
+        forest
+    }
+    /// Merge natural loops of same header, and sort loops by size so outer loop which
+    /// contains inner loop is before it.
+    fn merge_loops_by_header(&self) -> Vec<Loop> {
+        let mut loops: Vec<Loop> = Vec::new();
+        for natural_loop in self.natural_loops {
+            match loops.iter_mut().find(|merged| merged.header == natural_loop.header) {
+                Some(merged) => {
+                    merged.blocks.extend(natural_loop.blocks.iter().copied());
+                    if !merged.latches.contains(&natural_loop.tail) {
+                        merged.latches.push(natural_loop.tail);
+                    }
+                }
+                None => loops.push(Loop {
+                    header: natural_loop.header,
+                    latches: vec![natural_loop.tail],
+                    blocks: natural_loop.blocks.clone(),
+                    parent: None,
+                    children: Vec::new(),
+                    depth: 1,
+                }),
+            }
+        }
+        for merged in loops.iter_mut() {
+            merged.latches.sort_by_key(|block| block.0);
+        }
+        loops.sort_by_key(|merged| (usize::MAX - merged.blocks.len(), merged.header.0));
+        loops
+    }
+    /// Parent of loop is the smallest loop which contains its header, since loops are
+    /// sorted by size it is the last such loop before it.
+    fn link_parents(&self, forest: &mut LoopForest) {
+        for index in 0..forest.loops.len() {
+            let header = forest.loops[index].header;
+            let parent = (0..index).rev().find(|outer| forest.loops[*outer].contains(header));
+            if let Some(parent) = parent {
+                forest.loops[index].parent = Some(LoopId(parent));
+                forest.loops[index].depth = forest.loops[parent].depth + 1;
+                forest.loops[parent].children.push(LoopId(index));
+            }
+        }
+    }
+}
@@ -1,4 +1,9 @@
+pub mod alias;
 pub mod available_expr;
 pub mod cfg;
 pub mod domtree;
+pub mod induction_variable;
+pub mod liveness;
+pub mod loop_forest;
 pub mod rpo;
+pub mod verifier;
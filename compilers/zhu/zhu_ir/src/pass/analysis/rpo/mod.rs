@@ -38,6 +38,10 @@ impl RevresePostOrder {
     pub fn get_blocks_in_rpo(&self) -> Vec<Block> {
         self.blocks_in_rpo.clone()
     }
+    /// Is block visited in reverse post order, unreachable block is not visited.
+    pub fn contains(&self, block: Block) -> bool {
+        self.block_map_rpo.contains_key(&block)
+    }
     pub fn get_block_rpo(&self, block: Block) -> usize {
         self.block_map_rpo.get(&block).unwrap().clone()
     }
@@ -0,0 +1,772 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::entities::block::Block;
+use crate::entities::external_name::{ExternalName, UserDefNamespace};
+use crate::entities::function::Function;
+use crate::entities::global_value::GlobalValueData;
+use crate::entities::instruction::opcode::OpCode;
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::module::{DataId, FuncId, Module, ModuleLevelId};
+use crate::entities::r#type::ValueType;
+use crate::entities::value::{Value, ValueData};
+use crate::pass::analysis::cfg::{cfg_anylysis, ControlFlowGraph};
+use crate::pass::analysis::domtree::{domtree_analysis, DomTree};
+use crate::pass::analysis::rpo::revrese_post_order_analysis;
+use crate::pass::AnalysisPass;
+
+/// Verify a function in module, return all errors found in function, empty vec means
+/// function is well-formed.
+pub fn verify_function(module: &Module, func_id: FuncId) -> Vec<VerifierError> {
+    let mut pass = VerifierPass::new(module, func_id);
+    let function = module.get_function(func_id).unwrap();
+    pass.process(function)
+}
+/// Verify every function in module, errors are sorted by function id.
+pub fn verify_module(module: &Module) -> Vec<VerifierError> {
+    let mut func_ids: Vec<FuncId> = module.functions.keys().copied().collect();
+    func_ids.sort_by_key(|func_id| func_id.0);
+    func_ids
+        .into_iter()
+        .flat_map(|func_id| verify_function(module, func_id))
+        .collect()
+}
+/// Format errors of verifier, one error per line.
+pub fn format_verifier_errors(errors: &[VerifierError], module: &Module) -> String {
+    let mut format_string = String::new();
+    for error in errors {
+        format_string.push_str(&error.fmt_error(module));
+        format_string.push('\n');
+    }
+    format_string
+}
+
+/// Diagnostic of verifier, point to the function, block and instruction
+/// which is malformed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifierError {
+    pub func: FuncId,
+    pub block: Option<Block>,
+    pub inst: Option<Instruction>,
+    pub kind: VerifierErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerifierErrorKind {
+    // structure of function
+    EmptyFunction,
+    EmptyBlock,
+    MissingTerminator,
+    TerminatorNotAtEnd,
+    PhiNotAtBlockStart,
+    UnknownBlock(Block),
+    // instruction shape
+    UnexpectOpcode(OpCode),
+    MissingResult,
+    UnexpectResult(Value),
+    UndefinedValue(Value),
+    UnknownConstant,
+    UnknownGlobalValue,
+    UnknownFunctionRef,
+    UnresolvedSymbol(ExternalName),
+    // types
+    OperandTypeMismatch {
+        expect: ValueType,
+        actual: ValueType,
+    },
+    ResultTypeMismatch {
+        expect: ValueType,
+        actual: ValueType,
+    },
+    ExpectIntegerOperand(ValueType),
+    ExpectFloatOperand(ValueType),
+    ImmediateTypeMismatch {
+        expect: ValueType,
+        actual: ValueType,
+    },
+    ArgumentCountMismatch {
+        expect: usize,
+        actual: usize,
+    },
+    SignatureMismatch,
+    ReturnTypeMismatch {
+        expect: Option<ValueType>,
+        actual: Option<ValueType>,
+    },
+    // ssa property
+    PhiArmNotPredecessor(Block),
+    PhiMissingArm(Block),
+    PhiDuplicateArm(Block),
+    UseNotDominated(Value),
+}
+
+impl fmt::Display for VerifierErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifierErrorKind::EmptyFunction => write!(f, "function has no block"),
+            VerifierErrorKind::EmptyBlock => write!(f, "block has no instruction"),
+            VerifierErrorKind::MissingTerminator => write!(f, "block is not end with `jump`, `brif` or `ret`"),
+            VerifierErrorKind::TerminatorNotAtEnd => write!(f, "terminator is not the last instruction of block"),
+            VerifierErrorKind::PhiNotAtBlockStart => write!(f, "phi is not at the start of block"),
+            VerifierErrorKind::UnknownBlock(block) => write!(f, "block{} is not in function", block.0),
+            VerifierErrorKind::UnexpectOpcode(opcode) => write!(f, "unexpect opcode `{}`", opcode),
+            VerifierErrorKind::MissingResult => write!(f, "instruction should have a result"),
+            VerifierErrorKind::UnexpectResult(value) => write!(f, "instruction should not have result reg{}", value.0),
+            VerifierErrorKind::UndefinedValue(value) => write!(f, "reg{} is not defined", value.0),
+            VerifierErrorKind::UnknownConstant => write!(f, "constant is not in function"),
+            VerifierErrorKind::UnknownGlobalValue => write!(f, "global value is not in function"),
+            VerifierErrorKind::UnknownFunctionRef => write!(f, "function reference is not in function"),
+            VerifierErrorKind::UnresolvedSymbol(name) => write!(f, "external name {:?} is not in module", name),
+            VerifierErrorKind::OperandTypeMismatch { expect, actual } => {
+                write!(f, "operand type mismatch, expect {:?} but got {:?}", expect, actual)
+            }
+            VerifierErrorKind::ResultTypeMismatch { expect, actual } => {
+                write!(f, "result type mismatch, expect {:?} but got {:?}", expect, actual)
+            }
+            VerifierErrorKind::ExpectIntegerOperand(ty) => write!(f, "expect integer operand but got {:?}", ty),
+            VerifierErrorKind::ExpectFloatOperand(ty) => write!(f, "expect float operand but got {:?}", ty),
+            VerifierErrorKind::ImmediateTypeMismatch { expect, actual } => {
+                write!(f, "immediate type mismatch, expect {:?} but got {:?}", expect, actual)
+            }
+            VerifierErrorKind::ArgumentCountMismatch { expect, actual } => {
+                write!(f, "expect {} arguments but got {}", expect, actual)
+            }
+            VerifierErrorKind::SignatureMismatch => write!(f, "signature is not match the callee"),
+            VerifierErrorKind::ReturnTypeMismatch { expect, actual } => {
+                write!(f, "return type mismatch, expect {:?} but got {:?}", expect, actual)
+            }
+            VerifierErrorKind::PhiArmNotPredecessor(block) => {
+                write!(f, "phi argument block{} is not a predecessor", block.0)
+            }
+            VerifierErrorKind::PhiMissingArm(block) => write!(f, "phi is missing argument for block{}", block.0),
+            VerifierErrorKind::PhiDuplicateArm(block) => write!(f, "phi has multiple argument for block{}", block.0),
+            VerifierErrorKind::UseNotDominated(value) => write!(f, "use of reg{} is not dominated by def", value.0),
+        }
+    }
+}
+
+impl VerifierError {
+    /// Format error with symbol name of function.
+    pub fn fmt_error(&self, module: &Module) -> String {
+        let func_name = module
+            .get_symbol_by_module_id(ModuleLevelId::Func(self.func))
+            .unwrap_or("<unknown>");
+        let mut location = format!("func {}", func_name);
+        if let Some(block) = self.block {
+            location.push_str(&format!(", block{}", block.0));
+        }
+        if let Some(inst) = self.inst {
+            let function = module.get_function(self.func).unwrap();
+            match function.get_inst_result(inst) {
+                Some(result) => location.push_str(&format!(", inst{} (reg{})", inst.0, result.0)),
+                None => location.push_str(&format!(", inst{}", inst.0)),
+            }
+        }
+        format!("[Error]: {}: {}", location, self.kind)
+    }
+}
+
+fn is_int_type(ty: &ValueType) -> bool {
+    matches!(
+        ty,
+        ValueType::U8
+            | ValueType::U16
+            | ValueType::U32
+            | ValueType::U64
+            | ValueType::I16
+            | ValueType::I32
+            | ValueType::I64
+    )
+}
+fn is_float_type(ty: &ValueType) -> bool {
+    matches!(ty, ValueType::F32 | ValueType::F64)
+}
+/// Address is compute as integer, so mem type can be used as integer.
+fn is_int_or_mem_type(ty: &ValueType) -> bool {
+    is_int_type(ty) || matches!(ty, ValueType::Mem(_))
+}
+
+/// Verifier for function, checking
+/// - structure: every block end with one terminator, phi at start of block, branch to known block.
+/// - shape: opcode match instruction data, result exist or not, entities referenced exist.
+/// - type: operand, result, immediate, call and return type.
+/// - ssa: phi arguments match predecessors, every use dominated by it's def.
+///
+/// CFG and domtree are only computed when structure is well-formed, since both analysis
+/// assume every block has a terminator.
+pub struct VerifierPass<'a> {
+    module: &'a Module,
+    func_id: FuncId,
+    errors: Vec<VerifierError>,
+}
+
+impl<'a> AnalysisPass<Vec<VerifierError>> for VerifierPass<'a> {
+    fn process(&mut self, func: &Function) -> Vec<VerifierError> {
+        self.errors.clear();
+        self.run(func);
+        std::mem::take(&mut self.errors)
+    }
+}
+
+impl<'a> VerifierPass<'a> {
+    pub fn new(module: &'a Module, func_id: FuncId) -> Self {
+        Self {
+            module,
+            func_id,
+            errors: Vec::new(),
+        }
+    }
+    fn report(&mut self, block: Option<Block>, inst: Option<Instruction>, kind: VerifierErrorKind) {
+        self.errors.push(VerifierError {
+            func: self.func_id,
+            block,
+            inst,
+            kind,
+        });
+    }
+    fn report_inst(&mut self, block: Block, inst: Instruction, kind: VerifierErrorKind) {
+        self.report(Some(block), Some(inst), kind);
+    }
+    fn run(&mut self, func: &Function) {
+        let blocks = Self::blocks_in_layout_order(func);
+        if blocks.is_empty() {
+            self.report(None, None, VerifierErrorKind::EmptyFunction);
+            return;
+        }
+        self.verify_global_values(func);
+        if !self.verify_structure(func, &blocks) {
+            return;
+        }
+        for block in &blocks {
+            for inst in func.get_insts_of_block(*block) {
+                self.verify_inst(func, *block, inst);
+            }
+        }
+        let cfg = cfg_anylysis(func);
+        let reachable_cfg = Self::reachable_cfg(&cfg);
+        let dom = domtree_analysis(func, &reachable_cfg);
+        self.verify_phis(func, &blocks, &cfg);
+        self.verify_dominance(func, &blocks, &reachable_cfg, &dom);
+    }
+    fn blocks_in_layout_order(func: &Function) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut cur_block = func.layout.first_block();
+        while let Some(block) = cur_block {
+            blocks.push(block);
+            cur_block = func.layout.blocks.get(&block).unwrap().next;
+        }
+        blocks
+    }
+    /// Remove unreachable blocks from CFG, domtree of unreachable blocks is meaningless.
+    fn reachable_cfg(cfg: &ControlFlowGraph) -> ControlFlowGraph {
+        let rpo = revrese_post_order_analysis(cfg);
+        let reachable: HashSet<Block> = rpo.get_blocks_in_rpo().into_iter().collect();
+        let mut reachable_cfg = cfg.clone();
+        reachable_cfg.blocks.retain(|block, _| reachable.contains(block));
+        reachable_cfg.exists.retain(|block| reachable.contains(block));
+        for node in reachable_cfg.blocks.values_mut() {
+            node.predecessors.retain(|block| reachable.contains(block));
+        }
+        reachable_cfg
+    }
+}
+
+/// Verify structure of function.
+impl<'a> VerifierPass<'a> {
+    /// Return false when structure is too broken to build CFG.
+    fn verify_structure(&mut self, func: &Function, blocks: &[Block]) -> bool {
+        let mut is_well_formed = true;
+        for block in blocks {
+            let insts = func.get_insts_of_block(*block);
+            let Some(last_inst) = insts.last() else {
+                self.report(Some(*block), None, VerifierErrorKind::EmptyBlock);
+                is_well_formed = false;
+                continue;
+            };
+            let mut is_after_non_phi = false;
+            for inst in &insts {
+                let inst_data = func.get_inst_data(*inst);
+                match inst_data {
+                    InstructionData::Phi { .. } => {
+                        if is_after_non_phi {
+                            self.report_inst(*block, *inst, VerifierErrorKind::PhiNotAtBlockStart);
+                        }
+                    }
+                    InstructionData::Comment(_) => {}
+                    _ => is_after_non_phi = true,
+                }
+                let is_terminator = matches!(
+                    inst_data,
+                    InstructionData::Jump { .. } | InstructionData::BrIf { .. } | InstructionData::Ret { .. }
+                );
+                if is_terminator && inst != last_inst {
+                    self.report_inst(*block, *inst, VerifierErrorKind::TerminatorNotAtEnd);
+                }
+                // branch target must exist, otherwise CFG can not connect blocks.
+                let targets = match inst_data {
+                    InstructionData::Jump { dst, .. } => vec![*dst],
+                    InstructionData::BrIf { conseq, alter, .. } => vec![*conseq, *alter],
+                    _ => vec![],
+                };
+                for target in targets {
+                    if !func.layout.blocks.contains_key(&target) {
+                        self.report_inst(*block, *inst, VerifierErrorKind::UnknownBlock(target));
+                        is_well_formed = false;
+                    }
+                }
+            }
+            match func.get_inst_data(*last_inst) {
+                InstructionData::Jump { .. } | InstructionData::BrIf { .. } | InstructionData::Ret { .. } => {}
+                _ => {
+                    self.report_inst(*block, *last_inst, VerifierErrorKind::MissingTerminator);
+                    is_well_formed = false;
+                }
+            }
+        }
+        is_well_formed
+    }
+    /// Verify global value chain and symbol resolve to module.
+    fn verify_global_values(&mut self, func: &Function) {
+        let mut globals: Vec<_> = func.global_values.iter().collect();
+        globals.sort_by_key(|(global, _)| global.0);
+        for (_, global_data) in globals {
+            match global_data {
+                GlobalValueData::Symbol { name } => {
+                    if !self.is_external_name_resolved(name) {
+                        self.report(None, None, VerifierErrorKind::UnresolvedSymbol(name.clone()));
+                    }
+                }
+                GlobalValueData::Load { base, .. } | GlobalValueData::AddI { base, .. } => {
+                    if !func.global_values.contains_key(base) {
+                        self.report(None, None, VerifierErrorKind::UnknownGlobalValue);
+                    }
+                }
+            }
+        }
+    }
+    fn is_external_name_resolved(&self, name: &ExternalName) -> bool {
+        match name {
+            ExternalName::UserDefName { namespace, value } => match namespace {
+                UserDefNamespace::Data => self.module.data_objects.contains_key(&DataId(*value)),
+                UserDefNamespace::Function => self.module.functions.contains_key(&FuncId(*value)),
+                UserDefNamespace::Other(_) => true,
+            },
+        }
+    }
+}
+
+/// Verify shape and type of single instruction.
+impl<'a> VerifierPass<'a> {
+    /// Get type of value if value is defined, report error otherwise.
+    fn operand_type(&mut self, func: &Function, block: Block, inst: Instruction, value: Value) -> Option<ValueType> {
+        let is_defined = match func.entities.values.get(&value) {
+            Some(ValueData::Inst { inst: def_inst, .. }) => func.layout.insts.contains_key(def_inst),
+            Some(ValueData::Param { index, .. }) => *index < func.signature.params.len(),
+            None => false,
+        };
+        if is_defined {
+            Some(func.value_type(value).clone())
+        } else {
+            self.report_inst(block, inst, VerifierErrorKind::UndefinedValue(value));
+            None
+        }
+    }
+    fn expect_int(&mut self, block: Block, inst: Instruction, ty: &ValueType) {
+        if !is_int_or_mem_type(ty) {
+            self.report_inst(block, inst, VerifierErrorKind::ExpectIntegerOperand(ty.clone()));
+        }
+    }
+    fn expect_float(&mut self, block: Block, inst: Instruction, ty: &ValueType) {
+        if !is_float_type(ty) {
+            self.report_inst(block, inst, VerifierErrorKind::ExpectFloatOperand(ty.clone()));
+        }
+    }
+    fn expect_same_type(&mut self, block: Block, inst: Instruction, expect: &ValueType, actual: &ValueType) {
+        if expect != actual {
+            self.report_inst(
+                block,
+                inst,
+                VerifierErrorKind::OperandTypeMismatch {
+                    expect: expect.clone(),
+                    actual: actual.clone(),
+                },
+            );
+        }
+    }
+    fn expect_result_type(&mut self, block: Block, inst: Instruction, result: Option<&ValueType>, expect: &ValueType) {
+        if let Some(actual) = result {
+            if actual != expect {
+                self.report_inst(
+                    block,
+                    inst,
+                    VerifierErrorKind::ResultTypeMismatch {
+                        expect: expect.clone(),
+                        actual: actual.clone(),
+                    },
+                );
+            }
+        }
+    }
+    fn expect_opcode(&mut self, block: Block, inst: Instruction, opcode: &OpCode, expects: &[OpCode]) {
+        if !expects.contains(opcode) {
+            self.report_inst(block, inst, VerifierErrorKind::UnexpectOpcode(*opcode));
+        }
+    }
+    /// Verify result of instruction exist if and only if instruction produce a value,
+    /// return type of result.
+    fn verify_inst_result(&mut self, func: &Function, block: Block, inst: Instruction) -> Option<ValueType> {
+        let inst_data = func.get_inst_data(inst);
+        let should_have_result = match inst_data {
+            InstructionData::Ret { .. }
+            | InstructionData::StoreRegister { .. }
+            | InstructionData::GlobalStore { .. }
+            | InstructionData::BrIf { .. }
+            | InstructionData::Jump { .. }
+            | InstructionData::Comment(_) => false,
+            InstructionData::Call { name, .. } => match func.external_funcs.get(name) {
+                Some(ex_func) => ex_func.sig.return_type.is_some(),
+                // unknown function ref will be report by call check.
+                None => return None,
+            },
+            _ => true,
+        };
+        match (func.get_inst_result(inst), should_have_result) {
+            (Some(result), true) => Some(func.value_type(result).clone()),
+            (None, true) => {
+                self.report_inst(block, inst, VerifierErrorKind::MissingResult);
+                None
+            }
+            (Some(result), false) => {
+                self.report_inst(block, inst, VerifierErrorKind::UnexpectResult(result));
+                None
+            }
+            (None, false) => None,
+        }
+    }
+    fn verify_inst(&mut self, func: &Function, block: Block, inst: Instruction) {
+        let result_ty = self.verify_inst_result(func, block, inst);
+        match func.get_inst_data(inst) {
+            InstructionData::UnaryConst { opcode, constant } => {
+                self.expect_opcode(block, inst, opcode, &[OpCode::Iconst, OpCode::Uconst, OpCode::Fconst]);
+                if !func.constants.contains_key(constant) {
+                    self.report_inst(block, inst, VerifierErrorKind::UnknownConstant);
+                }
+            }
+            InstructionData::Unary { opcode, value } => {
+                self.expect_opcode(block, inst, opcode, &[OpCode::Mov, OpCode::Neg, OpCode::BitwiseNot]);
+                if let Some(ty) = self.operand_type(func, block, inst, *value) {
+                    if *opcode == OpCode::BitwiseNot {
+                        self.expect_int(block, inst, &ty);
+                    }
+                    self.expect_result_type(block, inst, result_ty.as_ref(), &ty);
+                }
+            }
+            InstructionData::Move { opcode, src } => {
+                self.expect_opcode(block, inst, opcode, &[OpCode::Mov]);
+                if let Some(ty) = self.operand_type(func, block, inst, *src) {
+                    self.expect_result_type(block, inst, result_ty.as_ref(), &ty);
+                }
+            }
+            InstructionData::Binary { opcode, args } => {
+                let is_float_op = matches!(
+                    opcode,
+                    OpCode::FAdd | OpCode::FSub | OpCode::FMul | OpCode::FDivide | OpCode::FReminder
+                );
+                self.expect_opcode(
+                    block,
+                    inst,
+                    opcode,
+                    &[
+                        OpCode::Add,
+                        OpCode::Sub,
+                        OpCode::Mul,
+                        OpCode::Divide,
+                        OpCode::Reminder,
+                        OpCode::FAdd,
+                        OpCode::FSub,
+                        OpCode::FMul,
+                        OpCode::FDivide,
+                        OpCode::FReminder,
+                        OpCode::BitwiseOR,
+                        OpCode::BitwiseAnd,
+                        OpCode::ShiftLeft,
+                        OpCode::ShiftRight,
+                    ],
+                );
+                let lhs = self.operand_type(func, block, inst, args[0]);
+                let rhs = self.operand_type(func, block, inst, args[1]);
+                if let (Some(lhs), Some(rhs)) = (lhs, rhs) {
+                    self.expect_same_type(block, inst, &lhs, &rhs);
+                    if is_float_op {
+                        self.expect_float(block, inst, &lhs);
+                    } else {
+                        self.expect_int(block, inst, &lhs);
+                    }
+                    self.expect_result_type(block, inst, result_ty.as_ref(), &lhs);
+                }
+            }
+            InstructionData::BinaryI { opcode, value, imm } => {
+                self.expect_opcode(
+                    block,
+                    inst,
+                    opcode,
+                    &[
+                        OpCode::Addi,
+                        OpCode::Subi,
+                        OpCode::Muli,
+                        OpCode::Dividei,
+                        OpCode::Reminderi,
+                    ],
+                );
+                if let Some(ty) = self.operand_type(func, block, inst, *value) {
+                    if imm.get_value_type() != ty {
+                        self.report_inst(
+                            block,
+                            inst,
+                            VerifierErrorKind::ImmediateTypeMismatch {
+                                expect: ty.clone(),
+                                actual: imm.get_value_type(),
+                            },
+                        );
+                    }
+                    self.expect_result_type(block, inst, result_ty.as_ref(), &ty);
+                }
+            }
+            InstructionData::Icmp { opcode, args, .. } | InstructionData::Fcmp { opcode, args, .. } => {
+                let is_float_cmp = matches!(func.get_inst_data(inst), InstructionData::Fcmp { .. });
+                let expect_opcode = if is_float_cmp { OpCode::Fcmp } else { OpCode::Icmp };
+                self.expect_opcode(block, inst, opcode, &[expect_opcode]);
+                let lhs = self.operand_type(func, block, inst, args[0]);
+                let rhs = self.operand_type(func, block, inst, args[1]);
+                if let (Some(lhs), Some(rhs)) = (lhs, rhs) {
+                    self.expect_same_type(block, inst, &lhs, &rhs);
+                    if is_float_cmp {
+                        self.expect_float(block, inst, &lhs);
+                    } else {
+                        self.expect_int(block, inst, &lhs);
+                    }
+                }
+                self.expect_result_type(block, inst, result_ty.as_ref(), &ValueType::U8);
+            }
+            InstructionData::Call { opcode, name, params } => {
+                self.expect_opcode(block, inst, opcode, &[OpCode::Call]);
+                let param_tys: Vec<Option<ValueType>> = params
+                    .iter()
+                    .map(|param| self.operand_type(func, block, inst, *param))
+                    .collect();
+                let Some(ex_func) = func.external_funcs.get(name) else {
+                    self.report_inst(block, inst, VerifierErrorKind::UnknownFunctionRef);
+                    return;
+                };
+                if !self.is_external_name_resolved(&ex_func.name) {
+                    self.report_inst(block, inst, VerifierErrorKind::UnresolvedSymbol(ex_func.name.clone()));
+                } else if let ExternalName::UserDefName {
+                    namespace: UserDefNamespace::Function,
+                    value,
+                } = &ex_func.name
+                {
+                    let callee = self.module.get_function(FuncId(*value)).unwrap();
+                    if callee.signature != ex_func.sig {
+                        self.report_inst(block, inst, VerifierErrorKind::SignatureMismatch);
+                    }
+                }
+                if ex_func.sig.params.len() != params.len() {
+                    self.report_inst(
+                        block,
+                        inst,
+                        VerifierErrorKind::ArgumentCountMismatch {
+                            expect: ex_func.sig.params.len(),
+                            actual: params.len(),
+                        },
+                    );
+                }
+                for (expect, actual) in ex_func.sig.params.iter().zip(param_tys.iter()) {
+                    if let Some(actual) = actual {
+                        self.expect_same_type(block, inst, expect, actual);
+                    }
+                }
+                if let Some(return_type) = &ex_func.sig.return_type {
+                    self.expect_result_type(block, inst, result_ty.as_ref(), return_type);
+                }
+            }
+            InstructionData::Ret { opcode, value } => {
+                self.expect_opcode(block, inst, opcode, &[OpCode::Ret]);
+                let actual = match value {
+                    Some(value) => match self.operand_type(func, block, inst, *value) {
+                        Some(ty) => Some(ty),
+                        None => return,
+                    },
+                    None => None,
+                };
+                if actual != func.signature.return_type {
+                    self.report_inst(
+                        block,
+                        inst,
+                        VerifierErrorKind::ReturnTypeMismatch {
+                            expect: func.signature.return_type.clone(),
+                            actual,
+                        },
+                    );
+                }
+            }
+            InstructionData::Convert { opcode, src } => {
+                self.expect_opcode(
+                    block,
+                    inst,
+                    opcode,
+                    &[
+                        OpCode::ToU8,
+                        OpCode::ToU16,
+                        OpCode::ToU32,
+                        OpCode::ToU64,
+                        OpCode::ToI16,
+                        OpCode::ToI32,
+                        OpCode::ToI64,
+                        OpCode::ToF32,
+                        OpCode::ToF64,
+                        OpCode::ToAddress,
+                    ],
+                );
+                self.operand_type(func, block, inst, *src);
+                let expect = match opcode {
+                    OpCode::ToU8 => Some(ValueType::U8),
+                    OpCode::ToU16 => Some(ValueType::U16),
+                    OpCode::ToU32 => Some(ValueType::U32),
+                    OpCode::ToU64 => Some(ValueType::U64),
+                    OpCode::ToI16 => Some(ValueType::I16),
+                    OpCode::ToI32 => Some(ValueType::I32),
+                    OpCode::ToI64 => Some(ValueType::I64),
+                    OpCode::ToF32 => Some(ValueType::F32),
+                    OpCode::ToF64 => Some(ValueType::F64),
+                    _ => None,
+                };
+                if let Some(expect) = expect {
+                    self.expect_result_type(block, inst, result_ty.as_ref(), &expect);
+                }
+            }
+            InstructionData::StackAlloc { opcode, .. } => {
+                self.expect_opcode(block, inst, opcode, &[OpCode::StackAlloc]);
+            }
+            InstructionData::LoadRegister { opcode, base, .. } => {
+                self.expect_opcode(block, inst, opcode, &[OpCode::LoadRegister]);
+                self.operand_type(func, block, inst, *base);
+            }
+            InstructionData::StoreRegister { opcode, base, src, .. } => {
+                self.expect_opcode(block, inst, opcode, &[OpCode::StoreRegister]);
+                self.operand_type(func, block, inst, *base);
+                self.operand_type(func, block, inst, *src);
+            }
+            InstructionData::GlobalLoad { opcode, base, .. } => {
+                self.expect_opcode(block, inst, opcode, &[OpCode::GlobalLoad]);
+                if !func.global_values.contains_key(base) {
+                    self.report_inst(block, inst, VerifierErrorKind::UnknownGlobalValue);
+                }
+            }
+            InstructionData::GlobalStore { opcode, base, src, .. } => {
+                self.expect_opcode(block, inst, opcode, &[OpCode::GlobalStore]);
+                if !func.global_values.contains_key(base) {
+                    self.report_inst(block, inst, VerifierErrorKind::UnknownGlobalValue);
+                }
+                self.operand_type(func, block, inst, *src);
+            }
+            InstructionData::BrIf { opcode, test, .. } => {
+                self.expect_opcode(block, inst, opcode, &[OpCode::BrIf]);
+                if let Some(ty) = self.operand_type(func, block, inst, *test) {
+                    self.expect_int(block, inst, &ty);
+                }
+            }
+            InstructionData::Jump { opcode, .. } => {
+                self.expect_opcode(block, inst, opcode, &[OpCode::Jump]);
+            }
+            InstructionData::Phi { opcode, from } => {
+                self.expect_opcode(block, inst, opcode, &[OpCode::Phi]);
+                for (_, value) in from {
+                    if let Some(ty) = self.operand_type(func, block, inst, *value) {
+                        if let Some(result_ty) = &result_ty {
+                            self.expect_same_type(block, inst, result_ty, &ty);
+                        }
+                    }
+                }
+            }
+            InstructionData::Comment(_) => {}
+        }
+    }
+}
+
+/// Verify SSA property.
+impl<'a> VerifierPass<'a> {
+    /// Every predecessor should have exactly one phi argument.
+    fn verify_phis(&mut self, func: &Function, blocks: &[Block], cfg: &ControlFlowGraph) {
+        for block in blocks {
+            let predecessors = cfg.get_predecessors(block);
+            for inst in func.get_insts_of_block(*block) {
+                let InstructionData::Phi { from, .. } = func.get_inst_data(inst) else {
+                    continue;
+                };
+                let mut seen = HashSet::new();
+                for (from_block, _) in from {
+                    if !predecessors.contains(from_block) {
+                        self.report_inst(*block, inst, VerifierErrorKind::PhiArmNotPredecessor(*from_block));
+                    }
+                    if !seen.insert(*from_block) {
+                        self.report_inst(*block, inst, VerifierErrorKind::PhiDuplicateArm(*from_block));
+                    }
+                }
+                let mut missing: Vec<&Block> = predecessors.iter().filter(|pred| !seen.contains(pred)).collect();
+                missing.sort_by_key(|pred| pred.0);
+                for pred in missing {
+                    self.report_inst(*block, inst, VerifierErrorKind::PhiMissingArm(*pred));
+                }
+            }
+        }
+    }
+    /// Def of value should dominate every use, for phi, def should dominate
+    /// the end of incoming block. use in unreachable block is skipped.
+    fn verify_dominance(&mut self, func: &Function, blocks: &[Block], cfg: &ControlFlowGraph, dom: &DomTree) {
+        let mut inst_position: HashMap<Instruction, (Block, usize)> = HashMap::new();
+        for block in blocks {
+            for (index, inst) in func.get_insts_of_block(*block).into_iter().enumerate() {
+                inst_position.insert(inst, (*block, index));
+            }
+        }
+        let is_reachable = |block: &Block| cfg.blocks.contains_key(block);
+        for block in blocks {
+            if !is_reachable(block) {
+                continue;
+            }
+            for (use_index, inst) in func.get_insts_of_block(*block).into_iter().enumerate() {
+                let inst_data = func.get_inst_data(inst);
+                let uses: Vec<(Value, Block, usize)> = match inst_data {
+                    // phi operand is used at the end of incoming block.
+                    InstructionData::Phi { from, .. } => from
+                        .iter()
+                        .filter(|(from_block, _)| is_reachable(from_block))
+                        .map(|(from_block, value)| (*value, *from_block, usize::MAX))
+                        .collect(),
+                    _ => inst_data
+                        .get_operands()
+                        .into_iter()
+                        .map(|value| (value, *block, use_index))
+                        .collect(),
+                };
+                for (value, use_block, use_index) in uses {
+                    let Some(ValueData::Inst { inst: def_inst, .. }) = func.entities.values.get(&value) else {
+                        continue;
+                    };
+                    // undefined value already report by instruction check.
+                    let Some((def_block, def_index)) = inst_position.get(def_inst) else {
+                        continue;
+                    };
+                    let is_dominated = if *def_block == use_block {
+                        *def_index < use_index
+                    } else {
+                        is_reachable(def_block) && dom.dominate(*def_block, use_block)
+                    };
+                    if !is_dominated {
+                        self.report_inst(*block, inst, VerifierErrorKind::UseNotDominated(value));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::entities::instruction::InstructionData;
+use crate::entities::module::{FuncId, Module};
+use crate::pass::ipo::inline::get_callee_id;
+
+pub fn call_graph_analysis(module: &Module) -> CallGraph {
+    CallGraph::new(module)
+}
+
+/// ## Call Graph
+/// Edge from caller to callee for every `call` instruction whose callee is a function
+/// of module, function ids are sorted so result is stable. Strongly connected
+/// components are computed by Tarjan's algorithm, which find component of callee
+/// before caller, so order of components is bottom-up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallGraph {
+    callees: HashMap<FuncId, Vec<FuncId>>,
+    callers: HashMap<FuncId, Vec<FuncId>>,
+    sccs: Vec<Vec<FuncId>>,
+    scc_of_func: HashMap<FuncId, usize>,
+}
+
+impl CallGraph {
+    pub fn new(module: &Module) -> Self {
+        let mut func_ids = module.functions.keys().copied().collect::<Vec<_>>();
+        func_ids.sort_by_key(|func_id| func_id.0);
+        let mut callees: HashMap<FuncId, Vec<FuncId>> = func_ids.iter().map(|func_id| (*func_id, Vec::new())).collect();
+        let mut callers = callees.clone();
+        for func_id in &func_ids {
+            let function = module.get_function(*func_id).unwrap();
+            let mut callee_set = HashSet::new();
+            for inst in function.insts() {
+                if let InstructionData::Call { name, .. } = function.get_inst_data(inst) {
+                    if let Some(callee) = get_callee_id(function, *name).filter(|id| callees.contains_key(id)) {
+                        callee_set.insert(callee);
+                    }
+                }
+            }
+            let mut callee_ids = callee_set.into_iter().collect::<Vec<_>>();
+            callee_ids.sort_by_key(|func_id| func_id.0);
+            for callee in &callee_ids {
+                callers.get_mut(callee).unwrap().push(*func_id);
+            }
+            callees.insert(*func_id, callee_ids);
+        }
+        let mut graph = Self {
+            callees,
+            callers,
+            sccs: Vec::new(),
+            scc_of_func: HashMap::new(),
+        };
+        TarjanContext::default().run(&mut graph, &func_ids);
+        graph
+    }
+    /// Get functions called by function, sorted by id.
+    pub fn get_callees(&self, func_id: FuncId) -> &[FuncId] {
+        &self.callees[&func_id]
+    }
+    /// Get functions which call function, sorted by id.
+    pub fn get_callers(&self, func_id: FuncId) -> &[FuncId] {
+        &self.callers[&func_id]
+    }
+    /// Get strongly connected components in bottom-up order, functions in a
+    /// component are sorted by id.
+    pub fn get_sccs(&self) -> &[Vec<FuncId>] {
+        &self.sccs
+    }
+    /// Get strongly connected component which contain function.
+    pub fn get_scc_of(&self, func_id: FuncId) -> &[FuncId] {
+        &self.sccs[self.scc_of_func[&func_id]]
+    }
+    /// Is function call itself directly or through other functions.
+    pub fn is_recursive(&self, func_id: FuncId) -> bool {
+        self.get_scc_of(func_id).len() > 1 || self.get_callees(func_id).contains(&func_id)
+    }
+    /// Get functions in bottom-up order, callee is before caller except the functions
+    /// in the same component.
+    pub fn bottom_up_order(&self) -> Vec<FuncId> {
+        self.sccs.iter().flatten().copied().collect()
+    }
+}
+
+#[derive(Default)]
+struct TarjanContext {
+    index: usize,
+    indexes: HashMap<FuncId, usize>,
+    low_links: HashMap<FuncId, usize>,
+    stack: Vec<FuncId>,
+    on_stack: HashSet<FuncId>,
+}
+
+impl TarjanContext {
+    fn run(&mut self, graph: &mut CallGraph, func_ids: &[FuncId]) {
+        for func_id in func_ids {
+            if !self.indexes.contains_key(func_id) {
+                self.strong_connect(graph, *func_id);
+            }
+        }
+    }
+    fn strong_connect(&mut self, graph: &mut CallGraph, func_id: FuncId) {
+        self.indexes.insert(func_id, self.index);
+        self.low_links.insert(func_id, self.index);
+        self.index += 1;
+        self.stack.push(func_id);
+        self.on_stack.insert(func_id);
+        for callee in graph.callees[&func_id].clone() {
+            if !self.indexes.contains_key(&callee) {
+                self.strong_connect(graph, callee);
+                let low_link = self.low_links[&func_id].min(self.low_links[&callee]);
+                self.low_links.insert(func_id, low_link);
+            } else if self.on_stack.contains(&callee) {
+                let low_link = self.low_links[&func_id].min(self.indexes[&callee]);
+                self.low_links.insert(func_id, low_link);
+            }
+        }
+        // function is root of a component, pop all function of component.
+        if self.low_links[&func_id] == self.indexes[&func_id] {
+            let mut scc = Vec::new();
+            loop {
+                let member = self.stack.pop().unwrap();
+                self.on_stack.remove(&member);
+                graph.scc_of_func.insert(member, graph.sccs.len());
+                scc.push(member);
+                if member == func_id {
+                    break;
+                }
+            }
+            scc.sort_by_key(|func_id| func_id.0);
+            graph.sccs.push(scc);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashSet;
+
+use crate::entities::global_value::GlobalValueData;
+use crate::entities::module::{Module, ModuleLevelId};
+use crate::pass::ModulePass;
+
+/// Remove functions and data which are unreachable from exported roots, return
+/// symbol names of removed functions and data.
+pub fn global_dce_pass(module: &mut Module, roots: &[&str]) -> Vec<String> {
+    let mut pass = GlobalDcePass::new(roots.iter().map(|root| root.to_string()).collect());
+    pass.process(module);
+    pass.removed_symbols
+}
+
+/// Get module level entities referenced by entity, a function reference functions
+/// it declared and symbols of its global values, a data reference symbols of its
+/// relocations.
+pub fn get_references(module: &Module, id: ModuleLevelId) -> Vec<ModuleLevelId> {
+    let names = match id {
+        ModuleLevelId::Func(func_id) => match module.get_function(func_id) {
+            Some(function) => {
+                let mut names = function
+                    .external_funcs
+                    .values()
+                    .map(|external_func| &external_func.name)
+                    .collect::<Vec<_>>();
+                names.extend(
+                    function
+                        .global_values
+                        .values()
+                        .filter_map(|global_data| match global_data {
+                            GlobalValueData::Symbol { name } => Some(name),
+                            _ => None,
+                        }),
+                );
+                names
+            }
+            None => Vec::new(),
+        },
+        ModuleLevelId::Data(data_id) => match module.data_objects.get(&data_id) {
+            Some(data) => data.relocations.iter().map(|relocation| &relocation.name).collect(),
+            None => Vec::new(),
+        },
+    };
+    names.into_iter().filter_map(|name| name.to_module_level_id()).collect()
+}
+
+/// ## Global Dead Code Elimination
+/// Mark functions and data reachable from root symbols through references (see
+/// `get_references`), then remove every function and data which is not marked.
+/// A unknown root symbol is ignored.
+pub struct GlobalDcePass {
+    roots: Vec<String>,
+    removed_symbols: Vec<String>,
+}
+
+impl ModulePass for GlobalDcePass {
+    fn process(&mut self, module: &mut Module) {
+        let live = self.mark_live_entities(module);
+        let mut dead_ids = module
+            .symbol_table
+            .iter()
+            .filter(|(_, id)| !live.contains(id))
+            .map(|(name, id)| (name.clone(), *id))
+            .collect::<Vec<_>>();
+        dead_ids.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (name, id) in dead_ids {
+            match id {
+                ModuleLevelId::Func(func_id) => {
+                    module.remove_function(func_id);
+                }
+                ModuleLevelId::Data(data_id) => {
+                    module.remove_data(data_id);
+                }
+            }
+            self.removed_symbols.push(name);
+        }
+    }
+}
+
+impl GlobalDcePass {
+    pub fn new(roots: Vec<String>) -> Self {
+        Self {
+            roots,
+            removed_symbols: Vec::new(),
+        }
+    }
+    /// Get symbol names of removed functions and data, sorted by name.
+    pub fn get_removed_symbols(&self) -> &[String] {
+        &self.removed_symbols
+    }
+    fn mark_live_entities(&self, module: &Module) -> HashSet<ModuleLevelId> {
+        let mut live = HashSet::new();
+        let mut worklist = self
+            .roots
+            .iter()
+            .filter_map(|root| module.get_module_id_by_symbol(root).copied())
+            .collect::<Vec<_>>();
+        while let Some(id) = worklist.pop() {
+            if !live.insert(id) {
+                continue;
+            }
+            worklist.extend(get_references(module, id));
+        }
+        live
+    }
+}
@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+
+use crate::entities::block::{Block, BlockData};
+use crate::entities::constant::Constant;
+use crate::entities::function::{Function, FunctionRef};
+use crate::entities::global_value::{GlobalValue, GlobalValueData};
+use crate::entities::instruction::opcode::OpCode;
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::module::{FuncId, Module, ModuleLevelId};
+use crate::entities::r#type::{MemType, MemTypeData, ValueType};
+use crate::entities::value::{Value, ValueData};
+use crate::pass::ModulePass;
+
+/// Default max cost of callee which can be inlined.
+pub const DEFAULT_INLINE_THRESHOLD: usize = 32;
+
+pub fn inline_pass(module: &mut Module, config: InlineConfig) -> usize {
+    let mut pass = InlinePass::new(config);
+    pass.process(module);
+    pass.get_inlined_call_count()
+}
+
+/// Config of inliner, callee is inlined only when its cost is not greater than
+/// `threshold`, cost of function is computed by `inline_cost`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineConfig {
+    pub threshold: usize,
+}
+
+impl Default for InlineConfig {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_INLINE_THRESHOLD,
+        }
+    }
+}
+
+impl InlineConfig {
+    pub fn new(threshold: usize) -> Self {
+        Self { threshold }
+    }
+}
+
+/// Cost of inlining function, which is number of instructions except comment.
+pub fn inline_cost(function: &Function) -> usize {
+    function
+        .insts()
+        .into_iter()
+        .filter(|inst| !matches!(function.get_inst_data(*inst), InstructionData::Comment(_)))
+        .count()
+}
+
+/// ## Inline Pass
+/// Inline call sites of every function in module, function is visited in order of
+/// function id, and call sites are collected before any inlining, so calls cloned
+/// from callee are not inlined again in the same run. A call is not inlined when
+/// - callee is the caller itself (recursive call).
+/// - callee is only declared, without any block.
+/// - cost of callee is greater than threshold of config.
+/// - number of arguments is not the same as parameters of callee.
+/// - call has result but some `ret` of callee does not return a value.
+pub struct InlinePass {
+    config: InlineConfig,
+    inlined_call_count: usize,
+}
+
+impl ModulePass for InlinePass {
+    fn process(&mut self, module: &mut Module) {
+        let mut func_ids = module.functions.keys().copied().collect::<Vec<_>>();
+        func_ids.sort_by_key(|func_id| func_id.0);
+        for caller_id in func_ids {
+            for (call, callee_id) in self.collect_inlineable_calls(module, caller_id) {
+                let callee = module.get_function(callee_id).unwrap().clone();
+                inline_call(module.get_mut_function(caller_id).unwrap(), call, &callee);
+                self.inlined_call_count += 1;
+            }
+        }
+    }
+}
+
+impl InlinePass {
+    pub fn new(config: InlineConfig) -> Self {
+        Self {
+            config,
+            inlined_call_count: 0,
+        }
+    }
+    /// Get number of call sites inlined by pass.
+    pub fn get_inlined_call_count(&self) -> usize {
+        self.inlined_call_count
+    }
+    /// Collect call sites of caller which can be inlined in layout order, with the
+    /// function id of callee.
+    fn collect_inlineable_calls(&self, module: &Module, caller_id: FuncId) -> Vec<(Instruction, FuncId)> {
+        let caller = module.get_function(caller_id).unwrap();
+        let mut calls = Vec::new();
+        let mut cur_block = caller.layout.first_block;
+        while let Some(block) = cur_block {
+            for inst in caller.get_insts_of_block(block) {
+                let InstructionData::Call { name, params, .. } = caller.get_inst_data(inst) else {
+                    continue;
+                };
+                let Some(callee_id) = get_callee_id(caller, *name) else {
+                    continue;
+                };
+                if callee_id == caller_id {
+                    continue;
+                }
+                let Some(callee) = module.get_function(callee_id) else {
+                    continue;
+                };
+                if callee.first_block().is_none() || inline_cost(callee) > self.config.threshold {
+                    continue;
+                }
+                if params.len() != callee.entities.params.len() {
+                    continue;
+                }
+                if caller.get_inst_result(inst).is_some() && !is_always_return_value(callee) {
+                    continue;
+                }
+                calls.push((inst, callee_id));
+            }
+            cur_block = caller.layout.blocks.get(&block).unwrap().next;
+        }
+        calls
+    }
+}
+
+/// Is function has any `ret`, and every `ret` of function return a value.
+fn is_always_return_value(function: &Function) -> bool {
+    let rets = function
+        .insts()
+        .into_iter()
+        .filter_map(|inst| match function.get_inst_data(inst) {
+            InstructionData::Ret { value, .. } => Some(value.is_some()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    !rets.is_empty() && rets.into_iter().all(|is_return_value| is_return_value)
+}
+
+/// Get function id of a function declared in function, return none if it is not a
+/// function of module.
+pub fn get_callee_id(function: &Function, func_ref: FunctionRef) -> Option<FuncId> {
+    match function.external_funcs.get(&func_ref)?.name.to_module_level_id()? {
+        ModuleLevelId::Func(func_id) => Some(func_id),
+        ModuleLevelId::Data(_) => None,
+    }
+}
+
+fn get_sorted_keys<K: Copy, V>(map: &HashMap<K, V>, index: impl Fn(&K) -> u32) -> Vec<K> {
+    let mut keys = map.keys().copied().collect::<Vec<_>>();
+    keys.sort_by_key(index);
+    keys
+}
+
+/// Inline a call instruction of caller with the body of callee:
+/// 1. clone mem types, constants, global values and function declarations of
+///    callee into caller.
+/// 2. clone blocks of callee after the block of call, parameters of callee are
+///    replaced by arguments of call.
+/// 3. split block of call, instructions after call are moved to a continuation
+///    block, and block of call jump to the cloned entry block.
+/// 4. every `ret` of cloned blocks become a jump to continuation block, call become
+///    a phi of returned values in continuation block, so result of call is still
+///    defined by the same value.
+pub fn inline_call(caller: &mut Function, call: Instruction, callee: &Function) {
+    let InstructionData::Call { params: args, .. } = caller.get_inst_data(call).clone() else {
+        panic!("instruction {:?} to inline is not a call.", call);
+    };
+    let mut cloner = CalleeCloner::default();
+    cloner.clone_mem_types(caller, callee);
+    cloner.clone_constants(caller, callee);
+    cloner.clone_global_values(caller, callee);
+    cloner.clone_external_funcs(caller, callee);
+    for (param, arg) in callee.entities.params.iter().zip(args) {
+        cloner.values.insert(*param, arg);
+    }
+    let call_block = caller.get_block_of_inst(call);
+    let cont_block = cloner.clone_blocks(caller, callee, call_block);
+    let cloned_insts = cloner.clone_insts(caller, callee);
+    let returns = cloner.remap_insts(caller, &cloned_insts, cont_block);
+    split_call_block(
+        caller,
+        call,
+        call_block,
+        cont_block,
+        cloner.blocks[&callee.first_block().unwrap()],
+    );
+    // call become phi of returned value in continuation block.
+    caller.remove_inst(call);
+    caller.get_block_data_mut(call_block).insts.remove(&call);
+    if caller.get_inst_result(call).is_some() && !returns.is_empty() {
+        caller.replace_inst(
+            call,
+            InstructionData::Phi {
+                opcode: OpCode::Phi,
+                from: returns,
+            },
+        );
+        caller.entities.mark_phi_block(call, cont_block);
+        caller.unshift_inst(call, cont_block);
+    }
+}
+
+/// Move instructions after call to continuation block, and jump from block of call
+/// to entry of inlined blocks, phis of successors are updated to new predecessor.
+fn split_call_block(caller: &mut Function, call: Instruction, call_block: Block, cont_block: Block, entry: Block) {
+    let insts = caller.get_insts_of_block(call_block);
+    let position = insts.iter().position(|inst| *inst == call).unwrap();
+    for inst in insts.into_iter().skip(position + 1) {
+        caller.remove_inst(inst);
+        caller.get_block_data_mut(call_block).insts.remove(&inst);
+        caller.entities.mark_inst_block(inst, cont_block);
+        caller.append_inst(inst, cont_block);
+    }
+    let jump = caller.entities.create_inst(InstructionData::Jump {
+        opcode: OpCode::Jump,
+        dst: entry,
+    });
+    caller.entities.mark_inst_block(jump, call_block);
+    caller.append_inst(jump, call_block);
+    let successors = match caller
+        .get_insts_of_block(cont_block)
+        .last()
+        .map(|inst| caller.get_inst_data(*inst))
+    {
+        Some(InstructionData::Jump { dst, .. }) => vec![*dst],
+        Some(InstructionData::BrIf { conseq, alter, .. }) => vec![*conseq, *alter],
+        _ => vec![],
+    };
+    for successor in successors {
+        for inst in caller.get_insts_of_block(successor) {
+            if let InstructionData::Phi { from, .. } = caller.get_inst_data_mut(inst) {
+                for (block, _) in from.iter_mut() {
+                    if *block == call_block {
+                        *block = cont_block;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Map entities of callee to entities cloned into caller.
+#[derive(Default)]
+struct CalleeCloner {
+    values: HashMap<Value, Value>,
+    blocks: HashMap<Block, Block>,
+    constants: HashMap<Constant, Constant>,
+    global_values: HashMap<GlobalValue, GlobalValue>,
+    mem_types: HashMap<MemType, MemType>,
+    func_refs: HashMap<FunctionRef, FunctionRef>,
+}
+
+impl CalleeCloner {
+    fn remap_type(&self, ty: &ValueType) -> ValueType {
+        match ty {
+            ValueType::Mem(mem_type) => ValueType::Mem(self.mem_types[mem_type]),
+            _ => ty.clone(),
+        }
+    }
+    fn clone_mem_types(&mut self, caller: &mut Function, callee: &Function) {
+        let mem_types = get_sorted_keys(&callee.mem_type, |mem_type| mem_type.0);
+        for mem_type in &mem_types {
+            let cloned = caller.declar_mem_type(callee.mem_type[mem_type].clone());
+            self.mem_types.insert(*mem_type, cloned);
+        }
+        // field of struct and element of array may refer to other mem type.
+        for mem_type in &mem_types {
+            let mut mem_type_data = callee.mem_type[mem_type].clone();
+            match &mut mem_type_data {
+                MemTypeData::Struct(struct_data) => {
+                    for field in struct_data.fields.iter_mut() {
+                        field.ty = self.remap_type(&field.ty);
+                    }
+                }
+                MemTypeData::Array(array_data) => array_data.ty = self.remap_type(&array_data.ty),
+                MemTypeData::Unknow => {}
+            }
+            caller.mem_type.insert(self.mem_types[mem_type], mem_type_data);
+        }
+    }
+    fn clone_constants(&mut self, caller: &mut Function, callee: &Function) {
+        for constant in get_sorted_keys(&callee.constants, |constant| constant.0) {
+            let cloned = caller.create_constant(callee.constants[&constant].clone());
+            self.constants.insert(constant, cloned);
+        }
+    }
+    /// Symbol already declared in caller is reused, other global values are declared.
+    fn clone_global_values(&mut self, caller: &mut Function, callee: &Function) {
+        let global_values = get_sorted_keys(&callee.global_values, |global_value| global_value.0);
+        for global_value in &global_values {
+            let global_data = &callee.global_values[global_value];
+            let declared = match global_data {
+                GlobalValueData::Symbol { .. } => get_sorted_keys(&caller.global_values, |global_value| global_value.0)
+                    .into_iter()
+                    .find(|caller_global_value| caller.global_values[caller_global_value] == *global_data),
+                _ => None,
+            };
+            let cloned = declared.unwrap_or_else(|| caller.declar_global_value(global_data.clone()));
+            self.global_values.insert(*global_value, cloned);
+        }
+        // base of global value may be declared after it.
+        for global_value in &global_values {
+            let global_data = match &callee.global_values[global_value] {
+                GlobalValueData::Symbol { .. } => continue,
+                GlobalValueData::Load { base, offset, ty } => GlobalValueData::Load {
+                    base: self.global_values[base],
+                    offset: offset.clone(),
+                    ty: self.remap_type(ty),
+                },
+                GlobalValueData::AddI { base, offset, ty } => GlobalValueData::AddI {
+                    base: self.global_values[base],
+                    offset: offset.clone(),
+                    ty: self.remap_type(ty),
+                },
+            };
+            caller
+                .global_values
+                .insert(self.global_values[global_value], global_data);
+        }
+    }
+    /// Function already declared in caller is reused, other functions are declared.
+    fn clone_external_funcs(&mut self, caller: &mut Function, callee: &Function) {
+        for func_ref in get_sorted_keys(&callee.external_funcs, |func_ref| func_ref.0) {
+            let external_func = &callee.external_funcs[&func_ref];
+            let declared = get_sorted_keys(&caller.external_funcs, |func_ref| func_ref.0)
+                .into_iter()
+                .find(|caller_func_ref| caller.external_funcs[caller_func_ref].name == external_func.name);
+            let cloned = declared.unwrap_or_else(|| caller.declar_external_function(external_func.clone()));
+            self.func_refs.insert(func_ref, cloned);
+        }
+    }
+    /// Clone blocks of callee in layout order after block of call, return the
+    /// continuation block inserted after cloned blocks.
+    fn clone_blocks(&mut self, caller: &mut Function, callee: &Function, call_block: Block) -> Block {
+        let mut after = call_block;
+        let mut cur_block = callee.layout.first_block;
+        while let Some(block) = cur_block {
+            after = caller.create_and_insert_block_after(BlockData::new(), after);
+            self.blocks.insert(block, after);
+            cur_block = callee.layout.blocks.get(&block).unwrap().next;
+        }
+        caller.create_and_insert_block_after(BlockData::new(), after)
+    }
+    /// Clone instructions and results of callee into cloned blocks, operands are not
+    /// remapped yet since phi may use a value defined after it.
+    fn clone_insts(&mut self, caller: &mut Function, callee: &Function) -> Vec<(Instruction, Block)> {
+        let mut cloned_insts = Vec::new();
+        let mut cur_block = callee.layout.first_block;
+        while let Some(block) = cur_block {
+            let cloned_block = self.blocks[&block];
+            for inst in callee.get_insts_of_block(block) {
+                let inst_data = callee.get_inst_data(inst).clone();
+                let is_phi = matches!(inst_data, InstructionData::Phi { .. });
+                let cloned = caller.entities.create_inst(inst_data);
+                if is_phi {
+                    caller.entities.mark_phi_block(cloned, cloned_block);
+                } else {
+                    caller.entities.mark_inst_block(cloned, cloned_block);
+                }
+                caller.append_inst(cloned, cloned_block);
+                if let Some(result) = callee.get_inst_result(inst) {
+                    let ty = self.remap_type(callee.value_type(result));
+                    let cloned_result = caller.entities.create_value(ValueData::Inst { inst: cloned, ty });
+                    caller.entities.mark_inst_result(cloned_result, cloned);
+                    self.values.insert(result, cloned_result);
+                }
+                cloned_insts.push((cloned, cloned_block));
+            }
+            cur_block = callee.layout.blocks.get(&block).unwrap().next;
+        }
+        cloned_insts
+    }
+    /// Remap operands, blocks and other entities of cloned instructions, `ret` is
+    /// replaced by jump to continuation block, return the returned value of each
+    /// `ret` with its block.
+    fn remap_insts(
+        &self,
+        caller: &mut Function,
+        cloned_insts: &[(Instruction, Block)],
+        cont_block: Block,
+    ) -> Vec<(Block, Value)> {
+        let mut returns = Vec::new();
+        for (inst, block) in cloned_insts {
+            let inst_data = caller.get_inst_data_mut(*inst);
+            for operand in inst_data.get_operands_mut() {
+                *operand = self.values[operand];
+            }
+            match inst_data {
+                InstructionData::UnaryConst { constant, .. } => *constant = self.constants[constant],
+                InstructionData::Call { name, .. } => *name = self.func_refs[name],
+                InstructionData::GlobalLoad { base, .. } | InstructionData::GlobalStore { base, .. } => {
+                    *base = self.global_values[base]
+                }
+                InstructionData::BrIf { conseq, alter, .. } => {
+                    *conseq = self.blocks[conseq];
+                    *alter = self.blocks[alter];
+                }
+                InstructionData::Jump { dst, .. } => *dst = self.blocks[dst],
+                InstructionData::Phi { from, .. } => {
+                    for (from_block, _) in from.iter_mut() {
+                        *from_block = self.blocks[from_block];
+                    }
+                }
+                InstructionData::Ret { value, .. } => {
+                    if let Some(value) = value {
+                        returns.push((*block, *value));
+                    }
+                    *inst_data = InstructionData::Jump {
+                        opcode: OpCode::Jump,
+                        dst: cont_block,
+                    };
+                }
+                _ => {}
+            }
+        }
+        returns
+    }
+}
@@ -0,0 +1,3 @@
+pub mod call_graph;
+pub mod global_dce;
+pub mod inline;
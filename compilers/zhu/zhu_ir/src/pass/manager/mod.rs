@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::entities::function::Function;
+use crate::entities::module::{FuncId, Module};
+use crate::pass::analysis::cfg::{cfg_anylysis, ControlFlowGraph};
+use crate::pass::analysis::domtree::{domtree_analysis, DomTree};
+use crate::pass::analysis::loop_forest::{loop_forest_analysis, LoopForest};
+use crate::pass::analysis::rpo::{revrese_post_order_analysis, RevresePostOrder};
+use crate::pass::analysis::verifier::{verify_function, VerifierError};
+use crate::pass::opt::dce::post_domtree::{post_domtree_analysis, PostDomTree};
+use crate::pass::opt::dce::DeadCodeEliminationPass;
+use crate::pass::opt::dse::DeadStoreEliminationPass;
+use crate::pass::opt::gvn::GvnPass;
+use crate::pass::opt::instcombine::InstCombinePass;
+use crate::pass::opt::lcm::critical_edge::CritialEdgePass;
+use crate::pass::opt::lcm::lazy_code_motion;
+use crate::pass::opt::licm::natural_loop::{natural_loop_analysis, NaturalLoop};
+use crate::pass::opt::licm::LoopInvariantCodeMotion;
+use crate::pass::opt::loop_simplify::LoopSimplifyPass;
+use crate::pass::opt::mem2reg::Mem2RegPass;
+use crate::pass::opt::out_of_ssa::OutOfSsaPass;
+use crate::pass::opt::sccp::SccpPass;
+use crate::pass::opt::strength_reduce::StrengthReducePass;
+use crate::pass::opt::unroll::{LoopUnrollPass, UnrollConfig};
+use crate::pass::OptiPass;
+
+/// Analysis can be cached by pass manager.
+#[derive(Debug, PartialEq, Clone, Copy, Eq, Hash)]
+pub enum AnalysisKind {
+    Cfg,
+    Rpo,
+    DomTree,
+    PostDomTree,
+    NaturalLoops,
+    LoopForest,
+}
+
+impl AnalysisKind {
+    /// All analyses, used by pass which do not change control flow graph.
+    pub const ALL: [AnalysisKind; 6] = [
+        AnalysisKind::Cfg,
+        AnalysisKind::Rpo,
+        AnalysisKind::DomTree,
+        AnalysisKind::PostDomTree,
+        AnalysisKind::NaturalLoops,
+        AnalysisKind::LoopForest,
+    ];
+    /// Analyses need to be computed before given analysis.
+    fn dependencies(&self) -> &'static [AnalysisKind] {
+        match self {
+            AnalysisKind::Cfg => &[],
+            AnalysisKind::Rpo | AnalysisKind::DomTree | AnalysisKind::PostDomTree => &[AnalysisKind::Cfg],
+            AnalysisKind::NaturalLoops => &[AnalysisKind::Cfg, AnalysisKind::DomTree],
+            AnalysisKind::LoopForest => &[AnalysisKind::NaturalLoops],
+        }
+    }
+}
+
+/// ## Analysis Cache
+/// Lazily computed analyses of a function. pass call `require` to make sure analyses
+/// are computed, then get analysis by getters, getters will panic if analysis is
+/// not required before.
+#[derive(Default)]
+pub struct AnalysisCache {
+    cfg: Option<ControlFlowGraph>,
+    rpo: Option<RevresePostOrder>,
+    dom: Option<DomTree>,
+    post_dom: Option<PostDomTree>,
+    natural_loops: Option<Vec<NaturalLoop>>,
+    loop_forest: Option<LoopForest>,
+    compute_count: HashMap<AnalysisKind, usize>,
+}
+
+fn format_analysis_not_computed(kind: AnalysisKind) -> String {
+    format!(
+        "Analysis {:?} is not computed, it should be required before used.",
+        kind
+    )
+}
+
+impl AnalysisCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Compute given analyses and their dependencies if not cached.
+    pub fn require(&mut self, func: &Function, kinds: &[AnalysisKind]) {
+        for kind in kinds {
+            self.compute(func, *kind);
+        }
+    }
+    fn compute(&mut self, func: &Function, kind: AnalysisKind) {
+        if self.is_cached(kind) {
+            return;
+        }
+        for dependency in kind.dependencies() {
+            self.compute(func, *dependency);
+        }
+        match kind {
+            AnalysisKind::Cfg => self.cfg = Some(cfg_anylysis(func)),
+            AnalysisKind::Rpo => self.rpo = Some(revrese_post_order_analysis(self.cfg())),
+            AnalysisKind::DomTree => self.dom = Some(domtree_analysis(func, self.cfg())),
+            AnalysisKind::PostDomTree => self.post_dom = Some(post_domtree_analysis(func, self.cfg())),
+            AnalysisKind::NaturalLoops => self.natural_loops = Some(natural_loop_analysis(self.dom(), self.cfg())),
+            AnalysisKind::LoopForest => self.loop_forest = Some(loop_forest_analysis(self.natural_loops())),
+        }
+        *self.compute_count.entry(kind).or_insert(0) += 1;
+    }
+    /// Drop all analyses not in preserved set.
+    pub fn invalidate(&mut self, preserved: &[AnalysisKind]) {
+        for kind in AnalysisKind::ALL {
+            if preserved.contains(&kind) {
+                continue;
+            }
+            match kind {
+                AnalysisKind::Cfg => self.cfg = None,
+                AnalysisKind::Rpo => self.rpo = None,
+                AnalysisKind::DomTree => self.dom = None,
+                AnalysisKind::PostDomTree => self.post_dom = None,
+                AnalysisKind::NaturalLoops => self.natural_loops = None,
+                AnalysisKind::LoopForest => self.loop_forest = None,
+            }
+        }
+    }
+    pub fn is_cached(&self, kind: AnalysisKind) -> bool {
+        match kind {
+            AnalysisKind::Cfg => self.cfg.is_some(),
+            AnalysisKind::Rpo => self.rpo.is_some(),
+            AnalysisKind::DomTree => self.dom.is_some(),
+            AnalysisKind::PostDomTree => self.post_dom.is_some(),
+            AnalysisKind::NaturalLoops => self.natural_loops.is_some(),
+            AnalysisKind::LoopForest => self.loop_forest.is_some(),
+        }
+    }
+    /// How many times the analysis is computed, used to check cache is hit.
+    pub fn get_compute_count(&self, kind: AnalysisKind) -> usize {
+        self.compute_count.get(&kind).copied().unwrap_or(0)
+    }
+    pub fn cfg(&self) -> &ControlFlowGraph {
+        self.cfg
+            .as_ref()
+            .unwrap_or_else(|| panic!("{}", format_analysis_not_computed(AnalysisKind::Cfg)))
+    }
+    pub fn rpo(&self) -> &RevresePostOrder {
+        self.rpo
+            .as_ref()
+            .unwrap_or_else(|| panic!("{}", format_analysis_not_computed(AnalysisKind::Rpo)))
+    }
+    pub fn dom(&self) -> &DomTree {
+        self.dom
+            .as_ref()
+            .unwrap_or_else(|| panic!("{}", format_analysis_not_computed(AnalysisKind::DomTree)))
+    }
+    pub fn post_dom(&self) -> &PostDomTree {
+        self.post_dom
+            .as_ref()
+            .unwrap_or_else(|| panic!("{}", format_analysis_not_computed(AnalysisKind::PostDomTree)))
+    }
+    pub fn natural_loops(&self) -> &Vec<NaturalLoop> {
+        self.natural_loops
+            .as_ref()
+            .unwrap_or_else(|| panic!("{}", format_analysis_not_computed(AnalysisKind::NaturalLoops)))
+    }
+    pub fn loop_forest(&self) -> &LoopForest {
+        self.loop_forest
+            .as_ref()
+            .unwrap_or_else(|| panic!("{}", format_analysis_not_computed(AnalysisKind::LoopForest)))
+    }
+}
+
+/// Optimization pass can be scheduled by pass manager, pass manager will compute
+/// `required` analyses before `run`, and drop analyses not `preserved` after. `run`
+/// create the `OptiPass` from cached analyses and process the function.
+#[derive(Clone, Copy)]
+pub struct ScheduledPass {
+    pub name: &'static str,
+    pub required: &'static [AnalysisKind],
+    pub preserved: &'static [AnalysisKind],
+    pub run: fn(&mut Function, &AnalysisCache),
+}
+
+/// Passes can be created by name in pipeline.
+const SCHEDULED_PASSES: [ScheduledPass; 13] = [
+    // Dead code elimination, may rewrite branch to jump.
+    ScheduledPass {
+        name: "dce",
+        required: &[AnalysisKind::PostDomTree],
+        preserved: &[],
+        run: |func, analyses| DeadCodeEliminationPass::new(analyses.post_dom()).process(func),
+    },
+    // Dead store elimination and store to load forwarding, only remove instructions.
+    ScheduledPass {
+        name: "dse",
+        required: &[AnalysisKind::Cfg, AnalysisKind::Rpo],
+        preserved: &AnalysisKind::ALL,
+        run: |func, analyses| DeadStoreEliminationPass::new(analyses.cfg(), analyses.rpo()).process(func),
+    },
+    // Global value numbering, only replace instructions.
+    ScheduledPass {
+        name: "gvn",
+        required: &[AnalysisKind::Cfg, AnalysisKind::Rpo, AnalysisKind::DomTree],
+        preserved: &AnalysisKind::ALL,
+        run: |func, analyses| GvnPass::new(analyses.dom(), analyses.cfg(), analyses.rpo()).process(func),
+    },
+    // Combine instructions by algebraic patterns, control flow graph is not changed.
+    ScheduledPass {
+        name: "instcombine",
+        required: &[],
+        preserved: &AnalysisKind::ALL,
+        run: |func, _| InstCombinePass::new().process(func),
+    },
+    // Loop invariant code motion, insert preheader for loops.
+    ScheduledPass {
+        name: "licm",
+        required: &[
+            AnalysisKind::Cfg,
+            AnalysisKind::Rpo,
+            AnalysisKind::DomTree,
+            AnalysisKind::NaturalLoops,
+        ],
+        preserved: &[],
+        run: |func, analyses| {
+            LoopInvariantCodeMotion::new(analyses.cfg(), analyses.dom(), analyses.rpo(), analyses.natural_loops())
+                .process(func)
+        },
+    },
+    // Transform loops into canonical form, insert blocks and compute loops by itself.
+    ScheduledPass {
+        name: "loop-simplify",
+        required: &[],
+        preserved: &[],
+        run: |func, _| LoopSimplifyPass::new().process(func),
+    },
+    // Promote stack slots to SSA values, only insert phis and remove instructions.
+    ScheduledPass {
+        name: "mem2reg",
+        required: &[AnalysisKind::Cfg, AnalysisKind::DomTree],
+        preserved: &AnalysisKind::ALL,
+        run: |func, analyses| Mem2RegPass::new(analyses.cfg(), analyses.dom()).process(func),
+    },
+    // Sparse conditional constant propagation, may rewrite branch to jump and remove blocks.
+    ScheduledPass {
+        name: "sccp",
+        required: &[AnalysisKind::Cfg],
+        preserved: &[],
+        run: |func, analyses| SccpPass::new(analyses.cfg()).process(func),
+    },
+    // Translate out of SSA form, split critical edges and replace phi with copies.
+    ScheduledPass {
+        name: "out-of-ssa",
+        required: &[AnalysisKind::Cfg],
+        preserved: &[],
+        run: |func, analyses| OutOfSsaPass::new(analyses.cfg()).process(func),
+    },
+    // Induction variable strength reduction, only insert and replace instructions in
+    // existing blocks.
+    ScheduledPass {
+        name: "strength-reduce",
+        required: &[AnalysisKind::Cfg, AnalysisKind::NaturalLoops],
+        preserved: &AnalysisKind::ALL,
+        run: |func, analyses| StrengthReducePass::new(analyses.cfg(), analyses.natural_loops()).process(func),
+    },
+    // Unroll loops with default config, compute control flow and loops by itself since
+    // they change after every unrolled loop.
+    ScheduledPass {
+        name: "unroll",
+        required: &[],
+        preserved: &[],
+        run: |func, _| LoopUnrollPass::new(UnrollConfig::default()).process(func),
+    },
+    // Split critical edges by inserting empty blocks.
+    ScheduledPass {
+        name: "critical-edge",
+        required: &[AnalysisKind::Cfg, AnalysisKind::Rpo],
+        preserved: &[],
+        run: |func, analyses| CritialEdgePass::new(analyses.cfg(), analyses.rpo()).process(func),
+    },
+    // Lazy code motion on function without critical edge, `lcm` in pipeline is
+    // expanded to `critical-edge` and this pass.
+    ScheduledPass {
+        name: "lazy-code-motion",
+        required: &[AnalysisKind::Cfg, AnalysisKind::Rpo],
+        preserved: &AnalysisKind::ALL,
+        run: |func, analyses| lazy_code_motion(analyses.cfg(), analyses.rpo(), func),
+    },
+];
+
+/// Get pass by name, return none if name is unknown.
+pub fn get_pass_by_name(name: &str) -> Option<ScheduledPass> {
+    SCHEDULED_PASSES.iter().find(|pass| pass.name == name).copied()
+}
+
+/// Create passes by name in pipeline, return none if name is unknown.
+pub fn create_passes_by_name(name: &str) -> Option<Vec<ScheduledPass>> {
+    match name {
+        "lcm" => Some(vec![
+            get_pass_by_name("critical-edge")?,
+            get_pass_by_name("lazy-code-motion")?,
+        ]),
+        _ => Some(vec![get_pass_by_name(name)?]),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PassManagerError {
+    UnknownPass(String),
+    EmptyPassName,
+    VerifyFailed { pass: String, errors: Vec<VerifierError> },
+}
+
+impl fmt::Display for PassManagerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PassManagerError::UnknownPass(name) => write!(f, "unknown pass `{}`", name),
+            PassManagerError::EmptyPassName => write!(f, "pass name in pipeline is empty"),
+            PassManagerError::VerifyFailed { pass, errors } => {
+                write!(f, "verify failed after pass `{}` with {} errors", pass, errors.len())
+            }
+        }
+    }
+}
+
+/// ## Pass Manager
+/// Run a sequence of passes on function or module, analyses are cached per function
+/// and invalidated by the preserved set of each pass.
+#[derive(Default)]
+pub struct PassManager {
+    passes: Vec<ScheduledPass>,
+    verify_each: bool,
+}
+
+impl PassManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Create pass manager from textual pipeline, pass names are separated by comma,
+    /// for example `"dce,gvn,licm,lcm"`.
+    pub fn from_pipeline(pipeline: &str) -> Result<Self, PassManagerError> {
+        let mut manager = Self::new();
+        if pipeline.trim().is_empty() {
+            return Ok(manager);
+        }
+        for name in pipeline.split(',') {
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(PassManagerError::EmptyPassName);
+            }
+            let passes = create_passes_by_name(name).ok_or_else(|| PassManagerError::UnknownPass(name.to_owned()))?;
+            manager.passes.extend(passes);
+        }
+        Ok(manager)
+    }
+    pub fn add_pass(&mut self, pass: ScheduledPass) {
+        self.passes.push(pass);
+    }
+    /// Run verifier after each pass when running on module.
+    pub fn set_verify_each(&mut self, verify_each: bool) {
+        self.verify_each = verify_each;
+    }
+    pub fn get_pass_names(&self) -> Vec<&'static str> {
+        self.passes.iter().map(|pass| pass.name).collect()
+    }
+    /// Run all passes on function with given analysis cache, cache can be reused
+    /// by caller after passes.
+    pub fn run_on_function_with_cache(&mut self, func: &mut Function, analyses: &mut AnalysisCache) {
+        for pass in &self.passes {
+            Self::run_pass(pass, func, analyses);
+        }
+    }
+    pub fn run_on_function(&mut self, func: &mut Function) {
+        self.run_on_function_with_cache(func, &mut AnalysisCache::new());
+    }
+    /// Run all passes on every function of module, in order of function id.
+    pub fn run_on_module(&mut self, module: &mut Module) -> Result<(), PassManagerError> {
+        let mut func_ids: Vec<FuncId> = module.functions.keys().copied().collect();
+        func_ids.sort_by_key(|func_id| func_id.0);
+        self.run_on_module_functions(module, &func_ids)
+    }
+    /// Run all passes on given functions of module, other functions are untouched.
+    pub fn run_on_module_functions(
+        &mut self,
+        module: &mut Module,
+        func_ids: &[FuncId],
+    ) -> Result<(), PassManagerError> {
+        for &func_id in func_ids {
+            let mut analyses = AnalysisCache::new();
+            for pass in &self.passes {
+                let func = module.get_mut_function(func_id).unwrap();
+                Self::run_pass(pass, func, &mut analyses);
+                if self.verify_each {
+                    let errors = verify_function(module, func_id);
+                    if !errors.is_empty() {
+                        return Err(PassManagerError::VerifyFailed {
+                            pass: pass.name.to_owned(),
+                            errors,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    fn run_pass(pass: &ScheduledPass, func: &mut Function, analyses: &mut AnalysisCache) {
+        analyses.require(func, pass.required);
+        (pass.run)(func, analyses);
+        analyses.invalidate(pass.preserved);
+    }
+}
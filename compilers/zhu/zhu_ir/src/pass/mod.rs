@@ -1,11 +1,19 @@
 pub mod analysis;
+pub mod ipo;
+pub mod manager;
 pub mod opt;
 
-use crate::entities::{function::Function, module::Module};
+use crate::entities::util::inst_operand_key::InstOperandKey;
+use crate::entities::{block::Block, function::Function, module::Module};
 /// Trait for a pass which will mutate a function to get opti function.
 pub trait OptiPass {
     fn process(&mut self, func: &mut Function);
 }
+/// Trait for a pass which will mutate functions of a module as a whole, such as
+/// interprocedural transformation.
+pub trait ModulePass {
+    fn process(&mut self, module: &mut Module);
+}
 /// Trait for a pass which is analysis based on function, maybe return a
 /// object for analysis.
 pub trait AnalysisPass<T> {
@@ -23,3 +31,17 @@ const HEADER_LEN: usize = 10;
 pub fn get_table_header(name: &str) -> String {
     format!("{HEADER_CHAR:=>HEADER_LEN$} {} {HEADER_CHAR:=>HEADER_LEN$}\n", name)
 }
+
+/// Get blocks of function sorted by index, make analysis table stable.
+pub fn get_sorted_blocks(func: &Function) -> Vec<Block> {
+    let mut blocks = func.blocks();
+    blocks.sort_by_key(|block| block.0);
+    blocks
+}
+
+/// Format expression keys sorted by text, make analysis table stable.
+pub fn fmt_sorted_keys<'a>(keys: impl IntoIterator<Item = &'a InstOperandKey>) -> Vec<String> {
+    let mut keys = keys.into_iter().map(|key| key.fmt_key()).collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
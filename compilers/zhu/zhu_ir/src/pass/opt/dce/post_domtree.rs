@@ -100,12 +100,12 @@ impl<'a> PostDomTreePass<'a> {
                 }
                 Block(max_block_index + 1)
             };
-            // Create new exit, connect to original exit as predecessors
+            // Create new exit, connect to original exit as successors
             reverse_cfg.blocks.insert(
                 exit_block,
                 CFGNode {
-                    predecessors: reverse_cfg.exists.clone(),
-                    successors: Default::default(),
+                    predecessors: Default::default(),
+                    successors: reverse_cfg.exists.clone(),
                 },
             );
             for origin_exit in reverse_cfg.exists {
@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::entities::block::Block;
+use crate::entities::function::use_list::UseList;
+use crate::entities::function::Function;
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::value::Value;
+use crate::pass::analysis::alias::{
+    alias_analysis, AddressOrigin, AliasAnalysis, AliasResult, MemoryBase, MemoryLocation,
+};
+use crate::pass::analysis::cfg::ControlFlowGraph;
+use crate::pass::analysis::rpo::RevresePostOrder;
+use crate::pass::OptiPass;
+
+pub fn dse_pass(function: &mut Function, cfg: &ControlFlowGraph, rpo: &RevresePostOrder) {
+    let mut pass = DeadStoreEliminationPass::new(cfg, rpo);
+    pass.process(function);
+}
+
+/// ## Dead Store Elimination
+/// Remove redundant memory traffic by alias analysis, run in three steps:
+/// - store to load forwarding: replace load by value stored to or loaded from the same
+///   location before, when no store or call between them may write the location.
+///   available values flow into block which has single predecessor.
+/// - remove stores to stack slot which is never read and whose address never escapes.
+/// - remove stores overwritten by a later store in same block before location is read,
+///   stores to stack slot whose address never escapes are dead at `ret`.
+///
+/// NOTE: instructions which only compute address of removed load or store are left to DCE.
+pub struct DeadStoreEliminationPass<'a> {
+    cfg: &'a ControlFlowGraph,
+    rpo: &'a RevresePostOrder,
+    alias: AliasAnalysis,
+    forwarded_load_count: usize,
+    removed_store_count: usize,
+}
+
+impl<'a> OptiPass for DeadStoreEliminationPass<'a> {
+    fn process(&mut self, function: &mut Function) {
+        if function.first_block().is_none() {
+            return;
+        }
+        self.alias = alias_analysis(function);
+        self.forward_stores(function);
+        self.remove_unread_slot_stores(function);
+        self.remove_overwritten_stores(function);
+    }
+}
+
+impl<'a> DeadStoreEliminationPass<'a> {
+    pub fn new(cfg: &'a ControlFlowGraph, rpo: &'a RevresePostOrder) -> Self {
+        Self {
+            cfg,
+            rpo,
+            alias: AliasAnalysis::new(),
+            forwarded_load_count: 0,
+            removed_store_count: 0,
+        }
+    }
+    /// Number of loads replaced by value already in register.
+    pub fn get_forwarded_load_count(&self) -> usize {
+        self.forwarded_load_count
+    }
+    pub fn get_removed_store_count(&self) -> usize {
+        self.removed_store_count
+    }
+    /// Walk reachable blocks in reverse post order with values known to be in memory
+    /// locations, block with single predecessor start with values at the end of its
+    /// predecessor, since predecessor dominates it.
+    fn forward_stores(&mut self, function: &mut Function) {
+        let mut use_list = function.compute_use_list();
+        let mut exit_values: HashMap<Block, Vec<(MemoryLocation, Value)>> = HashMap::new();
+        for block in self.rpo.get_blocks_in_rpo() {
+            let predecessors = self.cfg.get_predecessors(&block);
+            let mut available = match predecessors.iter().next() {
+                Some(predecessor) if predecessors.len() == 1 => {
+                    exit_values.get(predecessor).cloned().unwrap_or_default()
+                }
+                _ => Vec::new(),
+            };
+            for inst in function.get_insts_of_block(block) {
+                self.forward_inst(function, &mut use_list, inst, &mut available);
+            }
+            exit_values.insert(block, available);
+        }
+    }
+    fn forward_inst(
+        &mut self,
+        function: &mut Function,
+        use_list: &mut UseList,
+        inst: Instruction,
+        available: &mut Vec<(MemoryLocation, Value)>,
+    ) {
+        match function.get_inst_data(inst) {
+            InstructionData::LoadRegister { .. } | InstructionData::GlobalLoad { .. } => {
+                let location = MemoryLocation::of_inst(function, inst).unwrap();
+                let result = function.get_inst_result(inst).unwrap();
+                let known_value = available.iter().find_map(|(known_location, value)| {
+                    let is_same_type = function.value_type(*value) == function.value_type(result);
+                    (self.alias.alias(known_location, &location) == AliasResult::MustAlias && is_same_type)
+                        .then_some(*value)
+                });
+                match known_value {
+                    Some(value) => {
+                        use_list.replace_all_uses_with(function, result, value);
+                        use_list.remove_inst(function, inst);
+                        function.remove_inst(inst);
+                        self.forwarded_load_count += 1;
+                    }
+                    None => available.push((location, result)),
+                }
+            }
+            InstructionData::StoreRegister { src, .. } | InstructionData::GlobalStore { src, .. } => {
+                let src = *src;
+                let location = MemoryLocation::of_inst(function, inst).unwrap();
+                available
+                    .retain(|(known_location, _)| self.alias.alias(known_location, &location) == AliasResult::NoAlias);
+                available.push((location, src));
+            }
+            InstructionData::Call { .. } => {
+                available.retain(|(known_location, _)| !self.alias.call_may_access(known_location));
+            }
+            _ => {}
+        }
+    }
+    /// Stack slot whose address never escapes can only be read by `load` from slot or
+    /// address derived from slot.
+    fn remove_unread_slot_stores(&mut self, function: &mut Function) {
+        let insts = function.insts();
+        let mut read_slots = HashSet::new();
+        for inst in &insts {
+            if let InstructionData::LoadRegister { base, .. } = function.get_inst_data(*inst) {
+                if let AddressOrigin::StackSlot(slot) = self.alias.get_pointer(MemoryBase::Value(*base)).origin {
+                    read_slots.insert(slot);
+                }
+            }
+        }
+        for inst in insts {
+            let Some(location) = MemoryLocation::of_inst(function, inst) else {
+                continue;
+            };
+            if !matches!(function.get_inst_data(inst), InstructionData::StoreRegister { .. }) {
+                continue;
+            }
+            if let AddressOrigin::StackSlot(slot) = self.alias.get_pointer(location.base).origin {
+                if self.alias.is_local(&location) && !read_slots.contains(&slot) {
+                    function.remove_inst(inst);
+                    self.removed_store_count += 1;
+                }
+            }
+        }
+    }
+    /// Scan every block backward with locations which will be overwritten before read,
+    /// store to such location is dead.
+    fn remove_overwritten_stores(&mut self, function: &mut Function) {
+        for block in function.blocks() {
+            let mut overwritten: Vec<MemoryLocation> = Vec::new();
+            for inst in function.get_insts_of_block(block).into_iter().rev() {
+                match function.get_inst_data(inst) {
+                    InstructionData::StoreRegister { .. } | InstructionData::GlobalStore { .. } => {
+                        let location = MemoryLocation::of_inst(function, inst).unwrap();
+                        if overwritten
+                            .iter()
+                            .any(|later_location| self.alias.covers(later_location, &location))
+                        {
+                            function.remove_inst(inst);
+                            self.removed_store_count += 1;
+                        } else {
+                            overwritten.push(location);
+                        }
+                    }
+                    InstructionData::LoadRegister { .. } | InstructionData::GlobalLoad { .. } => {
+                        let location = MemoryLocation::of_inst(function, inst).unwrap();
+                        overwritten.retain(|later_location| {
+                            self.alias.alias(later_location, &location) == AliasResult::NoAlias
+                        });
+                    }
+                    InstructionData::Call { .. } => {
+                        overwritten.retain(|later_location| !self.alias.call_may_access(later_location));
+                    }
+                    InstructionData::Ret { .. } => {
+                        overwritten = self
+                            .alias
+                            .get_local_slots()
+                            .into_iter()
+                            .filter_map(|slot| self.alias.get_slot_location(slot))
+                            .collect();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
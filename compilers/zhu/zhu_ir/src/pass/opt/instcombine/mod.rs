@@ -0,0 +1,588 @@
+use std::collections::HashMap;
+
+use crate::entities::constant::ConstantData;
+use crate::entities::function::use_list::UseList;
+use crate::entities::function::Function;
+use crate::entities::immediate::Immediate;
+use crate::entities::instruction::opcode::{CmpFlag, OpCode};
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::r#type::ValueType;
+use crate::entities::runtime_value::RuntimeValue;
+use crate::entities::value::{Value, ValueData};
+use crate::pass::{get_sorted_blocks, OptiPass};
+
+pub fn instcombine_pass(function: &mut Function) {
+    let mut pass = InstCombinePass::new();
+    pass.process(function);
+}
+
+/// Rewrite of a instruction produced by pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rewrite {
+    /// Replace uses of result by existing value, and remove instruction.
+    Value(Value),
+    /// Replace instruction by const instruction of result type.
+    Constant(RuntimeValue),
+    /// Replace instruction data, result value is kept.
+    Inst(InstructionData),
+    /// Replace instruction by binary instruction which right operand is a new
+    /// constant, used when target form has no immediate version (such as shift).
+    BinaryWithConstant {
+        opcode: OpCode,
+        value: Value,
+        constant: RuntimeValue,
+    },
+}
+
+/// Rewrite pattern, `apply` return none when instruction does not match.
+pub struct Pattern {
+    pub name: &'static str,
+    pub apply: fn(&Function, Instruction) -> Option<Rewrite>,
+}
+
+/// Patterns in order of priority, first matched pattern is applied.
+pub const PATTERNS: [Pattern; 13] = [
+    Pattern {
+        name: "fold-constant",
+        apply: fold_constant,
+    },
+    Pattern {
+        name: "canonicalize-constant-operand",
+        apply: canonicalize_constant_operand,
+    },
+    Pattern {
+        name: "binary-same-operands",
+        apply: binary_same_operands,
+    },
+    Pattern {
+        name: "binary-identity",
+        apply: binary_identity,
+    },
+    Pattern {
+        name: "immediate-identity",
+        apply: immediate_identity,
+    },
+    Pattern {
+        name: "strength-reduction",
+        apply: strength_reduction,
+    },
+    Pattern {
+        name: "reassociate-immediate",
+        apply: reassociate_immediate,
+    },
+    Pattern {
+        name: "double-unary",
+        apply: double_unary,
+    },
+    Pattern {
+        name: "redundant-convert",
+        apply: redundant_convert,
+    },
+    Pattern {
+        name: "convert-chain",
+        apply: convert_chain,
+    },
+    Pattern {
+        name: "icmp-same-operands",
+        apply: icmp_same_operands,
+    },
+    Pattern {
+        name: "icmp-constant-to-rhs",
+        apply: icmp_constant_to_rhs,
+    },
+    Pattern {
+        name: "icmp-unsigned-bound",
+        apply: icmp_unsigned_bound,
+    },
+];
+
+/// ## Instruction Combine
+/// Peephole pass which rewrites `Binary`, `BinaryI`, `Unary`, `Convert` and `Icmp`
+/// instructions by table of algebraic patterns, until no pattern can be applied.
+/// - constant operand of `add`, `sub`, `mul`, `divide` and `reminder` is moved to
+///   immediate of `BinaryI` form, constant of `icmp` is moved to right side.
+/// - multiply by power of two is reduced to shift, unsigned divide and reminder by
+///   power of two are reduced to shift and bitwise and.
+/// - identities like `addi x 0`, `sub x x`, `neg (neg x)` and `to.i32 (to.i32 x)`
+///   are removed.
+///
+/// Patterns keep semantic of interpreter, so signed divide is not reduced and float
+/// arithmetic is only folded when all operands are constant. Instructions which become
+/// unused are left to dead code elimination.
+pub struct InstCombinePass {
+    applied: HashMap<&'static str, usize>,
+    iterations: usize,
+    /// Use list kept up to date by every rewrite, so replacing uses of a value does not
+    /// scan whole function.
+    use_list: UseList,
+}
+
+impl OptiPass for InstCombinePass {
+    fn process(&mut self, function: &mut Function) {
+        self.use_list = function.compute_use_list();
+        loop {
+            self.iterations += 1;
+            if !self.run_once(function) {
+                break;
+            }
+        }
+    }
+}
+
+impl Default for InstCombinePass {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InstCombinePass {
+    pub fn new() -> Self {
+        Self {
+            applied: Default::default(),
+            iterations: 0,
+            use_list: UseList::default(),
+        }
+    }
+    /// Get how many times pattern of given name is applied.
+    pub fn get_applied_count(&self, name: &str) -> usize {
+        self.applied.get(name).copied().unwrap_or(0)
+    }
+    /// Get how many times instructions are scanned, the last scan change nothing.
+    pub fn get_iterations(&self) -> usize {
+        self.iterations
+    }
+    /// Scan every instruction once, blocks are visited by id and instructions by order
+    /// in block, so counts of applied patterns are stable. return true if any is
+    /// rewritten.
+    fn run_once(&mut self, function: &mut Function) -> bool {
+        let mut is_changed = false;
+        let insts = get_sorted_blocks(function)
+            .into_iter()
+            .flat_map(|block| function.get_insts_of_block(block))
+            .collect::<Vec<_>>();
+        for inst in insts {
+            // instruction may be removed by rewrite of value it depends on.
+            if !function.layout.insts.contains_key(&inst) {
+                continue;
+            }
+            let Some((name, rewrite)) = PATTERNS
+                .iter()
+                .find_map(|pattern| (pattern.apply)(function, inst).map(|rewrite| (pattern.name, rewrite)))
+            else {
+                continue;
+            };
+            *self.applied.entry(name).or_insert(0) += 1;
+            self.apply_rewrite(function, inst, rewrite);
+            is_changed = true;
+        }
+        is_changed
+    }
+    fn apply_rewrite(&mut self, function: &mut Function, inst: Instruction, rewrite: Rewrite) {
+        match rewrite {
+            Rewrite::Value(value) => {
+                let result = function.get_inst_result(inst).unwrap();
+                self.use_list.replace_all_uses_with(function, result, value);
+                self.use_list.remove_inst(function, inst);
+                function.remove_inst(inst);
+            }
+            Rewrite::Constant(constant) => {
+                let inst_data = create_const_inst_data(function, constant);
+                self.replace_inst(function, inst, inst_data);
+            }
+            Rewrite::Inst(inst_data) => self.replace_inst(function, inst, inst_data),
+            Rewrite::BinaryWithConstant {
+                opcode,
+                value,
+                constant,
+            } => {
+                let ty = function.value_type(value).clone();
+                let const_inst_data = create_const_inst_data(function, constant);
+                let const_inst = function.entities.create_inst(const_inst_data);
+                let const_value = function.entities.create_value(ValueData::Inst { inst: const_inst, ty });
+                function.entities.mark_inst_result(const_value, const_inst);
+                function
+                    .entities
+                    .mark_inst_block(const_inst, function.get_block_of_inst(inst));
+                function.insert_inst_before(const_inst, inst);
+                self.replace_inst(
+                    function,
+                    inst,
+                    InstructionData::Binary {
+                        opcode,
+                        args: [value, const_value],
+                    },
+                );
+            }
+        }
+    }
+    fn replace_inst(&mut self, function: &mut Function, inst: Instruction, inst_data: InstructionData) {
+        self.use_list.remove_inst(function, inst);
+        function.replace_inst(inst, inst_data);
+        self.use_list.add_inst(function, inst);
+    }
+}
+
+/// Create const instruction data of value, constant is added to function.
+fn create_const_inst_data(function: &mut Function, value: RuntimeValue) -> InstructionData {
+    let opcode = match value {
+        RuntimeValue::F32(_) | RuntimeValue::F64(_) => OpCode::Fconst,
+        RuntimeValue::I16(_) | RuntimeValue::I32(_) | RuntimeValue::I64(_) => OpCode::Iconst,
+        _ => OpCode::Uconst,
+    };
+    let constant = function.create_constant(ConstantData {
+        bytes: value.to_bytes(),
+    });
+    InstructionData::UnaryConst { opcode, constant }
+}
+
+fn is_signed(ty: &ValueType) -> bool {
+    matches!(ty, ValueType::I16 | ValueType::I32 | ValueType::I64)
+}
+
+fn get_width(ty: &ValueType) -> u32 {
+    ty.get_size() as u32 * 8
+}
+
+fn result_type(function: &Function, inst: Instruction) -> Option<&ValueType> {
+    function.get_inst_result(inst).map(|result| function.value_type(result))
+}
+
+/// Get data of instruction which define value, none for parameter.
+fn get_def_inst_data(function: &Function, value: Value) -> Option<&InstructionData> {
+    match function.get_value_data(value) {
+        ValueData::Inst { inst, .. } if function.layout.insts.contains_key(inst) => Some(function.get_inst_data(*inst)),
+        _ => None,
+    }
+}
+
+/// Bits of integer value in width of its type.
+fn get_bits(value: &RuntimeValue, ty: &ValueType) -> Option<u64> {
+    let bits = value.as_u64()?;
+    let width = get_width(ty);
+    Some(if width == 64 { bits } else { bits & ((1 << width) - 1) })
+}
+
+fn all_ones(ty: &ValueType) -> u64 {
+    u64::MAX >> (64 - get_width(ty))
+}
+
+fn get_immediate_value(imm: &Immediate, ty: &ValueType) -> RuntimeValue {
+    RuntimeValue::from(imm).cast(ty)
+}
+
+fn to_immediate(value: RuntimeValue) -> Option<Immediate> {
+    Some(match value {
+        RuntimeValue::U8(num) => Immediate::U8(num),
+        RuntimeValue::U16(num) => Immediate::U16(num),
+        RuntimeValue::U32(num) => Immediate::U32(num),
+        RuntimeValue::U64(num) => Immediate::U64(num),
+        RuntimeValue::I16(num) => Immediate::I16(num),
+        RuntimeValue::I32(num) => Immediate::I32(num),
+        RuntimeValue::I64(num) => Immediate::I64(num),
+        RuntimeValue::F32(num) => Immediate::F32(num),
+        RuntimeValue::F64(num) => Immediate::F64(num),
+        RuntimeValue::Address(_) => return None,
+    })
+}
+
+/// Get immediate form of binary opcode.
+fn get_immediate_opcode(opcode: OpCode) -> Option<OpCode> {
+    match opcode {
+        OpCode::Add => Some(OpCode::Addi),
+        OpCode::Sub => Some(OpCode::Subi),
+        OpCode::Mul => Some(OpCode::Muli),
+        OpCode::Divide => Some(OpCode::Dividei),
+        OpCode::Reminder => Some(OpCode::Reminderi),
+        _ => None,
+    }
+}
+
+/// Evaluate instruction which operands are all constant by semantic of interpreter,
+/// instruction which cause runtime error (such as division by zero) is kept.
+fn fold_constant(function: &Function, inst: Instruction) -> Option<Rewrite> {
+    let ty = result_type(function, inst)?;
+    if let ValueType::Mem(_) = ty {
+        return None;
+    }
+    let folded = match function.get_inst_data(inst) {
+        InstructionData::Unary { opcode, value } => RuntimeValue::unary(*opcode, function.get_constant_value(*value)?),
+        InstructionData::Binary { opcode, args } => RuntimeValue::binary(
+            *opcode,
+            function.get_constant_value(args[0])?,
+            function.get_constant_value(args[1])?,
+        ),
+        InstructionData::BinaryI { opcode, value, imm } => RuntimeValue::binary(
+            *opcode,
+            function.get_constant_value(*value)?,
+            get_immediate_value(imm, function.value_type(*value)),
+        ),
+        InstructionData::Icmp { flag, args, .. } => RuntimeValue::compare(
+            *flag,
+            function.get_constant_value(args[0])?,
+            function.get_constant_value(args[1])?,
+        )
+        .map(|result| RuntimeValue::U8(result as u8)),
+        InstructionData::Convert { src, .. } => Ok(function.get_constant_value(*src)?.cast(ty)),
+        _ => return None,
+    };
+    match folded {
+        Ok(value) if value.is_type_of(ty) => Some(Rewrite::Constant(value)),
+        _ => None,
+    }
+}
+
+/// `op x c` to `opi x c`, and `c op x` to `opi x c` when op is commutative.
+fn canonicalize_constant_operand(function: &Function, inst: Instruction) -> Option<Rewrite> {
+    let InstructionData::Binary { opcode, args } = function.get_inst_data(inst) else {
+        return None;
+    };
+    let immediate_opcode = get_immediate_opcode(*opcode)?;
+    if !result_type(function, inst)?.is_int() {
+        return None;
+    }
+    let (value, constant) = match (
+        function.get_constant_value(args[0]),
+        function.get_constant_value(args[1]),
+    ) {
+        (_, Some(constant)) => (args[0], constant),
+        (Some(constant), None) if matches!(opcode, OpCode::Add | OpCode::Mul) => (args[1], constant),
+        _ => return None,
+    };
+    Some(Rewrite::Inst(InstructionData::BinaryI {
+        opcode: immediate_opcode,
+        value,
+        imm: to_immediate(constant)?,
+    }))
+}
+
+/// `sub x x` to `0`, `band x x` and `bor x x` to `x`.
+fn binary_same_operands(function: &Function, inst: Instruction) -> Option<Rewrite> {
+    let InstructionData::Binary { opcode, args } = function.get_inst_data(inst) else {
+        return None;
+    };
+    let ty = result_type(function, inst)?;
+    if args[0] != args[1] || !ty.is_int() {
+        return None;
+    }
+    match opcode {
+        OpCode::Sub => Some(Rewrite::Constant(RuntimeValue::U64(0).cast(ty))),
+        OpCode::BitwiseAnd | OpCode::BitwiseOR => Some(Rewrite::Value(args[0])),
+        _ => None,
+    }
+}
+
+/// Identity of bitwise and shift with constant: `bor x 0`, `band x -1`, `shl x 0`
+/// and `shr x 0` to `x`, `band x 0` to `0`.
+fn binary_identity(function: &Function, inst: Instruction) -> Option<Rewrite> {
+    let InstructionData::Binary { opcode, args } = function.get_inst_data(inst) else {
+        return None;
+    };
+    let ty = result_type(function, inst)?;
+    if !ty.is_int() {
+        return None;
+    }
+    let is_commutative = matches!(opcode, OpCode::BitwiseAnd | OpCode::BitwiseOR);
+    let (value, constant) = match (
+        function.get_constant_value(args[0]),
+        function.get_constant_value(args[1]),
+    ) {
+        (_, Some(constant)) => (args[0], constant),
+        (Some(constant), None) if is_commutative => (args[1], constant),
+        _ => return None,
+    };
+    let bits = get_bits(&constant, ty)?;
+    match opcode {
+        OpCode::BitwiseOR if bits == 0 => Some(Rewrite::Value(value)),
+        OpCode::BitwiseAnd if bits == all_ones(ty) => Some(Rewrite::Value(value)),
+        OpCode::BitwiseAnd if bits == 0 => Some(Rewrite::Constant(RuntimeValue::U64(0).cast(ty))),
+        // shift amount is masked by width of type.
+        OpCode::ShiftLeft | OpCode::ShiftRight if bits & (get_width(ty) as u64 - 1) == 0 => Some(Rewrite::Value(value)),
+        _ => None,
+    }
+}
+
+/// Identity of immediate form: `addi x 0`, `subi x 0`, `muli x 1`, `dividei x 1` to
+/// `x`, `muli x 0` and `reminderi x 1` to `0`, `muli x -1` to `neg x`.
+fn immediate_identity(function: &Function, inst: Instruction) -> Option<Rewrite> {
+    let InstructionData::BinaryI { opcode, value, imm } = function.get_inst_data(inst) else {
+        return None;
+    };
+    let ty = result_type(function, inst)?;
+    if !ty.is_int() {
+        return None;
+    }
+    let bits = get_bits(&get_immediate_value(imm, ty), ty)?;
+    let neg = Rewrite::Inst(InstructionData::Unary {
+        opcode: OpCode::Neg,
+        value: *value,
+    });
+    match opcode {
+        OpCode::Addi | OpCode::Subi if bits == 0 => Some(Rewrite::Value(*value)),
+        OpCode::Muli | OpCode::Dividei if bits == 1 => Some(Rewrite::Value(*value)),
+        OpCode::Muli if bits == 0 => Some(Rewrite::Constant(RuntimeValue::U64(0).cast(ty))),
+        OpCode::Muli if bits == all_ones(ty) => Some(neg),
+        OpCode::Dividei if bits == all_ones(ty) && is_signed(ty) => Some(neg),
+        OpCode::Reminderi if bits == 1 => Some(Rewrite::Constant(RuntimeValue::U64(0).cast(ty))),
+        _ => None,
+    }
+}
+
+/// `muli x 2^k` to `shl x k`, for unsigned type `dividei x 2^k` to `shr x k` and
+/// `reminderi x 2^k` to `band x 2^k-1`.
+fn strength_reduction(function: &Function, inst: Instruction) -> Option<Rewrite> {
+    let InstructionData::BinaryI { opcode, value, imm } = function.get_inst_data(inst) else {
+        return None;
+    };
+    let ty = result_type(function, inst)?;
+    if !ty.is_int() {
+        return None;
+    }
+    let bits = get_bits(&get_immediate_value(imm, ty), ty)?;
+    if bits < 2 || !bits.is_power_of_two() {
+        return None;
+    }
+    let shift = RuntimeValue::U64(bits.trailing_zeros() as u64).cast(ty);
+    let (opcode, constant) = match opcode {
+        OpCode::Muli => (OpCode::ShiftLeft, shift),
+        OpCode::Dividei if !is_signed(ty) => (OpCode::ShiftRight, shift),
+        OpCode::Reminderi if !is_signed(ty) => (OpCode::BitwiseAnd, RuntimeValue::U64(bits - 1).cast(ty)),
+        _ => return None,
+    };
+    Some(Rewrite::BinaryWithConstant {
+        opcode,
+        value: *value,
+        constant,
+    })
+}
+
+/// `addi (addi x a) b` to `addi x a+b`, `subi (subi x a) b` to `subi x a+b` and
+/// `muli (muli x a) b` to `muli x a*b`, arithmetic is wrapping.
+fn reassociate_immediate(function: &Function, inst: Instruction) -> Option<Rewrite> {
+    let InstructionData::BinaryI { opcode, value, imm } = function.get_inst_data(inst) else {
+        return None;
+    };
+    let ty = result_type(function, inst)?;
+    let combine_opcode = match opcode {
+        OpCode::Addi | OpCode::Subi => OpCode::Add,
+        OpCode::Muli => OpCode::Mul,
+        _ => return None,
+    };
+    if !ty.is_int() {
+        return None;
+    }
+    let InstructionData::BinaryI {
+        opcode: inner_opcode,
+        value: inner_value,
+        imm: inner_imm,
+    } = get_def_inst_data(function, *value)?
+    else {
+        return None;
+    };
+    if inner_opcode != opcode {
+        return None;
+    }
+    let combined = RuntimeValue::binary(
+        combine_opcode,
+        get_immediate_value(inner_imm, ty),
+        get_immediate_value(imm, ty),
+    )
+    .ok()?;
+    Some(Rewrite::Inst(InstructionData::BinaryI {
+        opcode: *opcode,
+        value: *inner_value,
+        imm: to_immediate(combined)?,
+    }))
+}
+
+/// `neg (neg x)` and `bnot (bnot x)` to `x`.
+fn double_unary(function: &Function, inst: Instruction) -> Option<Rewrite> {
+    let InstructionData::Unary { opcode, value } = function.get_inst_data(inst) else {
+        return None;
+    };
+    if !matches!(opcode, OpCode::Neg | OpCode::BitwiseNot) {
+        return None;
+    }
+    match get_def_inst_data(function, *value)? {
+        InstructionData::Unary {
+            opcode: inner_opcode,
+            value: inner_value,
+        } if inner_opcode == opcode => Some(Rewrite::Value(*inner_value)),
+        _ => None,
+    }
+}
+
+/// Convert to the type of source is removed, such as `to.i32 (to.i32 x)`.
+fn redundant_convert(function: &Function, inst: Instruction) -> Option<Rewrite> {
+    let InstructionData::Convert { src, .. } = function.get_inst_data(inst) else {
+        return None;
+    };
+    (function.value_type(*src) == result_type(function, inst)?).then_some(Rewrite::Value(*src))
+}
+
+/// `to.a (to.b x)` to `to.a x` when all types are integer and `b` is not narrower
+/// than `a`, since low bits of `a` are kept by convert to `b`.
+fn convert_chain(function: &Function, inst: Instruction) -> Option<Rewrite> {
+    let InstructionData::Convert { opcode, src } = function.get_inst_data(inst) else {
+        return None;
+    };
+    let InstructionData::Convert { src: inner_src, .. } = get_def_inst_data(function, *src)? else {
+        return None;
+    };
+    let (ty, middle_ty, src_ty) = (
+        result_type(function, inst)?,
+        function.value_type(*src),
+        function.value_type(*inner_src),
+    );
+    if !(ty.is_int() && middle_ty.is_int() && src_ty.is_int()) || get_width(middle_ty) < get_width(ty) {
+        return None;
+    }
+    Some(Rewrite::Inst(InstructionData::Convert {
+        opcode: *opcode,
+        src: *inner_src,
+    }))
+}
+
+/// `icmp x x` to constant.
+fn icmp_same_operands(function: &Function, inst: Instruction) -> Option<Rewrite> {
+    let InstructionData::Icmp { flag, args, .. } = function.get_inst_data(inst) else {
+        return None;
+    };
+    if args[0] != args[1] {
+        return None;
+    }
+    let result = matches!(flag, CmpFlag::Eq | CmpFlag::Gteq | CmpFlag::LtEq);
+    Some(Rewrite::Constant(RuntimeValue::U8(result as u8)))
+}
+
+/// `icmp flag c x` to `icmp swapped_flag x c`.
+fn icmp_constant_to_rhs(function: &Function, inst: Instruction) -> Option<Rewrite> {
+    let InstructionData::Icmp { opcode, flag, args } = function.get_inst_data(inst) else {
+        return None;
+    };
+    function.get_constant_value(args[0])?;
+    if function.get_constant_value(args[1]).is_some() {
+        return None;
+    }
+    Some(Rewrite::Inst(InstructionData::Icmp {
+        opcode: *opcode,
+        flag: flag.swapped(),
+        args: [args[1], args[0]],
+    }))
+}
+
+/// Unsigned compare with zero which is always known: `icmp gteq x 0` to `1` and
+/// `icmp lt x 0` to `0`.
+fn icmp_unsigned_bound(function: &Function, inst: Instruction) -> Option<Rewrite> {
+    let InstructionData::Icmp { flag, args, .. } = function.get_inst_data(inst) else {
+        return None;
+    };
+    let ty = function.value_type(args[0]);
+    if !ty.is_int() || is_signed(ty) || get_bits(&function.get_constant_value(args[1])?, ty)? != 0 {
+        return None;
+    }
+    match flag {
+        CmpFlag::Gteq => Some(Rewrite::Constant(RuntimeValue::U8(1))),
+        CmpFlag::Lt => Some(Rewrite::Constant(RuntimeValue::U8(0))),
+        _ => None,
+    }
+}
@@ -8,7 +8,7 @@ use crate::entities::util::set_operation::{intersection_sets, union_sets};
 use crate::entities::value::Value;
 use crate::pass::analysis::cfg::ControlFlowGraph;
 use crate::pass::analysis::rpo::RevresePostOrder;
-use crate::pass::{get_table_header, AnalysisPass, FormatTable};
+use crate::pass::{fmt_sorted_keys, get_sorted_blocks, get_table_header, AnalysisPass, FormatTable};
 
 /// Create anticipate expression anaylsis result.
 pub fn anticipate_expression_anaylsis(
@@ -30,22 +30,24 @@ pub struct AnticipateExpression {
 impl FormatTable for AnticipateExpression {
     fn format_table(&self, func: &Function, _module: &Module) -> String {
         let mut format_string = get_table_header("Anticipated Expression");
-        for block in func.blocks() {
+        for block in get_sorted_blocks(func) {
             format_string.push_str(&format!("Block{}:\n", block.0));
             format_string.push_str(&format!("\tAnticipate In:\n"));
-            for key in self.anticipate_expr_in.get(&block).unwrap() {
-                format_string.push_str(&format!("\t\t{}\n", key.fmt_key()));
+            for key in fmt_sorted_keys(self.anticipate_expr_in.get(&block).unwrap()) {
+                format_string.push_str(&format!("\t\t{}\n", key));
             }
             format_string.push_str(&format!("\tAnticipate Out:\n"));
-            for key in self.anticipate_expr_out.get(&block).unwrap() {
-                format_string.push_str(&format!("\t\t{}\n", key.fmt_key()));
+            for key in fmt_sorted_keys(self.anticipate_expr_out.get(&block).unwrap()) {
+                format_string.push_str(&format!("\t\t{}\n", key));
             }
             format_string.push_str(&format!("\tUpward exposed expr:\n"));
-            for key in self.upward_exposed_expr.get(&block).unwrap() {
-                format_string.push_str(&format!("\t\t{}\n", key.fmt_key()));
+            for key in fmt_sorted_keys(self.upward_exposed_expr.get(&block).unwrap()) {
+                format_string.push_str(&format!("\t\t{}\n", key));
             }
             format_string.push_str(&format!("\tKill:\n"));
-            for val in self.kill.get(&block).unwrap() {
+            let mut kill = self.kill.get(&block).unwrap().iter().collect::<Vec<_>>();
+            kill.sort_by_key(|val| val.0);
+            for val in kill {
                 format_string.push_str(&format!("\t\treg{}\n", val.0));
             }
         }
@@ -39,15 +39,20 @@ impl<'a> CritialEdgePass<'a> {
         self.find_edges(&function);
         self.insert_blocks(function);
     }
-    /// find every edge lead to a bb that has multiple predecessor
+    /// find every edge lead to a bb that has multiple predecessor, edge from unreachable
+    /// block is never executed, so it is skipped.
     fn find_edges(&mut self, function: &Function) {
         for block in function.blocks() {
+            if !self.rpo.contains(block) {
+                continue;
+            }
             let successors = self.cfg.get_successors(&block).clone();
             for sucessor in successors {
                 if self
                     .cfg
                     .get_predecessors(&sucessor)
                     .iter()
+                    .filter(|predeceesor| self.rpo.contains(**predeceesor))
                     .filter(|predeceesor| self.rpo.get_block_rpo(**predeceesor) < self.rpo.get_block_rpo(sucessor))
                     .collect::<Vec<_>>()
                     .len()
@@ -79,26 +84,42 @@ impl<'a> CritialEdgePass<'a> {
     /// insert block to the edges
     fn insert_blocks(&self, function: &mut Function) {
         for (src, dst) in &self.edges {
-            let new_block = function.create_block();
-            let mut builder = FunctionBuilder::new(function);
-            builder.switch_to_block(new_block);
-            builder.jump_inst(*dst);
+            split_edge(function, *src, *dst);
+        }
+    }
+}
 
-            let last_inst_of_src = function.layout.get_last_inst(*src);
-            match function.get_inst_data_mut(last_inst_of_src) {
-                InstructionData::Jump { dst, .. } => *dst = new_block,
-                InstructionData::BrIf { conseq, alter, .. } => {
-                    if *conseq == *dst {
-                        *conseq = new_block
-                    }
-                    if *alter == *dst {
-                        *alter = new_block
-                    }
-                }
-                _ => {
-                    unreachable!()
+/// Split edge `src -> dst` by inserting a new block which only jump to `dst`,
+/// terminator of `src` and phi of `dst` is rewritten to use new block.
+pub fn split_edge(function: &mut Function, src: Block, dst: Block) -> Block {
+    let new_block = function.create_block();
+    let mut builder = FunctionBuilder::new(function);
+    builder.switch_to_block(new_block);
+    builder.jump_inst(dst);
+
+    let last_inst_of_src = function.layout.get_last_inst(src);
+    match function.get_inst_data_mut(last_inst_of_src) {
+        InstructionData::Jump { dst: target, .. } => *target = new_block,
+        InstructionData::BrIf { conseq, alter, .. } => {
+            if *conseq == dst {
+                *conseq = new_block
+            }
+            if *alter == dst {
+                *alter = new_block
+            }
+        }
+        _ => {
+            unreachable!()
+        }
+    }
+    for inst in function.get_insts_of_block(dst) {
+        if let InstructionData::Phi { from, .. } = function.get_inst_data_mut(inst) {
+            for (block, _) in from.iter_mut() {
+                if *block == src {
+                    *block = new_block;
                 }
             }
         }
     }
+    new_block
 }
@@ -6,7 +6,7 @@ use crate::entities::module::Module;
 use crate::entities::util::inst_operand_key::InstOperandKey;
 use crate::pass::opt::lcm::anticipate_expr::AnticipateExpression;
 use crate::pass::opt::lcm::will_be_available_expr::WillBeAvailableExpression;
-use crate::pass::{get_table_header, AnalysisPass, FormatTable};
+use crate::pass::{fmt_sorted_keys, get_sorted_blocks, get_table_header, AnalysisPass, FormatTable};
 
 pub fn earliest_expression_anaylsis(
     function: &Function,
@@ -35,10 +35,10 @@ impl EarliestExpression {
 impl FormatTable for EarliestExpression {
     fn format_table(&self, func: &Function, _module: &Module) -> String {
         let mut format_string = get_table_header("Earliest");
-        for block in func.blocks() {
+        for block in get_sorted_blocks(func) {
             format_string.push_str(&format!("Block{}:\n", block.0));
-            for key in self.get_earliest(block) {
-                format_string.push_str(&format!("\t{}\n", key.fmt_key()))
+            for key in fmt_sorted_keys(self.get_earliest(block)) {
+                format_string.push_str(&format!("\t{}\n", key))
             }
         }
         format_string
@@ -8,7 +8,7 @@ use crate::entities::util::set_operation::{intersection_sets, union_sets};
 use crate::pass::analysis::cfg::ControlFlowGraph;
 use crate::pass::opt::lcm::earliest::EarliestExpression;
 use crate::pass::opt::lcm::postponable_expr::PostponableExpression;
-use crate::pass::{get_table_header, AnalysisPass, FormatTable};
+use crate::pass::{fmt_sorted_keys, get_sorted_blocks, get_table_header, AnalysisPass, FormatTable};
 
 pub fn later_expression_anaylsis(
     function: &Function,
@@ -52,10 +52,10 @@ impl LaterExpression {
 impl FormatTable for LaterExpression {
     fn format_table(&self, func: &Function, _module: &Module) -> String {
         let mut format_string = get_table_header("Later Expression");
-        for block in func.blocks() {
+        for block in get_sorted_blocks(func) {
             format_string.push_str(&format!("Block{}:\n", block.0));
-            for key in self.later.get(&block).unwrap() {
-                format_string.push_str(&format!("\t{}\n", key.fmt_key()));
+            for key in fmt_sorted_keys(self.later.get(&block).unwrap()) {
+                format_string.push_str(&format!("\t{}\n", key));
             }
         }
         format_string
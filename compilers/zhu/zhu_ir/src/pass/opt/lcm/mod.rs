@@ -25,6 +25,17 @@ pub fn lcm_opt(cfg: &ControlFlowGraph, rpo: &RevresePostOrder, function: &mut Fu
     let mut pass = LCMPass::new(cfg, rpo);
     pass.process(function);
 }
+/// Perform lazy code motion on function which critical edges are already splitted,
+/// `cfg` and `rpo` must be computed after splitting.
+pub fn lazy_code_motion(cfg: &ControlFlowGraph, rpo: &RevresePostOrder, function: &mut Function) {
+    let anticipate_expr = anticipate_expression_anaylsis(function, cfg, rpo);
+    let will_be_available_expr = will_be_available_expression_anaylsis(function, cfg, rpo, &anticipate_expr);
+    let earliest_expr = earliest_expression_anaylsis(function, &anticipate_expr, &will_be_available_expr);
+    let postponeable_expr = postponable_expression_anaylsis(function, &earliest_expr, cfg, rpo);
+    let later_expr = later_expression_anaylsis(function, cfg, &earliest_expr, &postponeable_expr);
+    let used_expr = used_expression_anaylsis(function, &anticipate_expr, cfg, rpo, &later_expr);
+    replacement_opti_pass(function, cfg, &postponeable_expr, &later_expr, &used_expr);
+}
 
 pub struct LCMPass<'a> {
     cfg: &'a ControlFlowGraph,
@@ -36,13 +47,7 @@ impl<'a> OptiPass for LCMPass<'a> {
         critical_edge_opt(self.cfg, self.rpo, function);
         let cfg = cfg_anylysis(function);
         let rpo = revrese_post_order_analysis(&cfg);
-        let anticipate_expr = anticipate_expression_anaylsis(&function, &cfg, &rpo);
-        let will_be_available_expr = will_be_available_expression_anaylsis(function, &cfg, &rpo, &anticipate_expr);
-        let earliest_expr = earliest_expression_anaylsis(function, &anticipate_expr, &will_be_available_expr);
-        let postponeable_expr = postponable_expression_anaylsis(function, &earliest_expr, &cfg, &rpo);
-        let later_expr = later_expression_anaylsis(function, &cfg, &earliest_expr, &postponeable_expr);
-        let used_expr = used_expression_anaylsis(&function, &anticipate_expr, &cfg, &rpo, &later_expr);
-        replacement_opti_pass(function, &cfg, &postponeable_expr, &later_expr, &used_expr);
+        lazy_code_motion(&cfg, &rpo, function);
     }
 }
 
@@ -8,7 +8,7 @@ use crate::entities::util::set_operation::{intersection_sets, union_sets};
 use crate::pass::analysis::cfg::ControlFlowGraph;
 use crate::pass::analysis::rpo::RevresePostOrder;
 use crate::pass::opt::lcm::earliest::EarliestExpression;
-use crate::pass::{AnalysisPass, FormatTable};
+use crate::pass::{fmt_sorted_keys, get_sorted_blocks, get_table_header, AnalysisPass, FormatTable};
 
 pub fn postponable_expression_anaylsis(
     function: &Function,
@@ -46,20 +46,20 @@ impl PostponableExpression {
 
 impl FormatTable for PostponableExpression {
     fn format_table(&self, func: &Function, _module: &Module) -> String {
-        let mut format_string = String::new();
-        for block in func.blocks() {
+        let mut format_string = get_table_header("Postponable Expression");
+        for block in get_sorted_blocks(func) {
             format_string.push_str(&format!("Block{}\n", block.0));
             format_string.push_str(&format!("\tPostponable In:\n"));
-            for key in self.postponable_expr_in.get(&block).unwrap() {
-                format_string.push_str(&format!("\t\t{}\n", key.fmt_key()));
+            for key in fmt_sorted_keys(self.postponable_expr_in.get(&block).unwrap()) {
+                format_string.push_str(&format!("\t\t{}\n", key));
             }
             format_string.push_str(&format!("\tPostponable Out:\n"));
-            for key in self.postponable_expr_out.get(&block).unwrap() {
-                format_string.push_str(&format!("\t\t{}\n", key.fmt_key()));
+            for key in fmt_sorted_keys(self.postponable_expr_out.get(&block).unwrap()) {
+                format_string.push_str(&format!("\t\t{}\n", key));
             }
             format_string.push_str(&format!("\tDownward exposed expr:\n"));
-            for key in self.downward_expose_expr.get(&block).unwrap() {
-                format_string.push_str(&format!("\t\t{}\n", key.fmt_key()));
+            for key in fmt_sorted_keys(self.downward_expose_expr.get(&block).unwrap()) {
+                format_string.push_str(&format!("\t\t{}\n", key));
             }
         }
         format_string
@@ -3,10 +3,9 @@ use std::collections::{HashMap, HashSet};
 use crate::builder::FunctionBuilder;
 use crate::entities::block::Block;
 use crate::entities::function::Function;
-use crate::entities::instruction::{opcode::OpCode, InstructionData};
 use crate::entities::util::inst_operand_key::{insts_to_keys, InstOperandKey};
 use crate::entities::util::set_operation::{intersection_sets, union_sets};
-use crate::entities::value::Value;
+use crate::entities::value::{Value, ValueData};
 use crate::pass::analysis::cfg::ControlFlowGraph;
 use crate::pass::opt::lcm::postponable_expr::PostponableExpression;
 use crate::pass::OptiPass;
@@ -117,6 +116,7 @@ impl<'a> ReplacementPass<'a> {
         }
     }
     fn insert_phi_and_remove_partial_redundancy(&mut self, function: &mut Function) {
+        let mut use_list = function.compute_use_list();
         for (key, context) in &self.table {
             for block in &context.remove_blocks {
                 let mut phi_source: Vec<(Block, Value)> = Vec::new();
@@ -134,17 +134,18 @@ impl<'a> ReplacementPass<'a> {
                 let mut builder = FunctionBuilder::new(function);
                 builder.switch_to_block(*block);
                 let phi_result = builder.phi_inst(phi_source);
-                // remove inst if match key in block
+                let ValueData::Inst { inst: phi, .. } = function.get_value_data(phi_result).clone() else {
+                    unreachable!()
+                };
+                use_list.add_inst(function, phi);
+                // replace result of inst which match key in block by phi and remove it.
                 for inst in function.get_insts_of_block(*block) {
                     if let Some(inst_key) = function.get_inst_data(inst).to_inst_operand_key() {
                         if *key == inst_key {
-                            function.replace_inst(
-                                inst,
-                                InstructionData::Move {
-                                    opcode: OpCode::Mov,
-                                    src: phi_result,
-                                },
-                            );
+                            let result = function.get_inst_result(inst).unwrap();
+                            use_list.replace_all_uses_with(function, result, phi_result);
+                            use_list.remove_inst(function, inst);
+                            function.remove_inst(inst);
                         }
                     }
                 }
@@ -9,7 +9,7 @@ use crate::pass::analysis::cfg::ControlFlowGraph;
 use crate::pass::analysis::rpo::RevresePostOrder;
 use crate::pass::opt::lcm::anticipate_expr::AnticipateExpression;
 use crate::pass::opt::lcm::later::LaterExpression;
-use crate::pass::{get_table_header, AnalysisPass, FormatTable};
+use crate::pass::{fmt_sorted_keys, get_sorted_blocks, get_table_header, AnalysisPass, FormatTable};
 
 pub fn used_expression_anaylsis(
     function: &Function,
@@ -44,15 +44,15 @@ impl UsedExpression {
 impl FormatTable for UsedExpression {
     fn format_table(&self, func: &Function, _module: &Module) -> String {
         let mut format_string = get_table_header("Used Expression");
-        for block in func.blocks() {
+        for block in get_sorted_blocks(func) {
             format_string.push_str(&format!("Block{}\n", block.0));
             format_string.push_str(&format!("\tUsed Expression In:\n"));
-            for key in self.used_expr_in.get(&block).unwrap() {
-                format_string.push_str(&format!("\t\t{}\n", key.fmt_key()));
+            for key in fmt_sorted_keys(self.used_expr_in.get(&block).unwrap()) {
+                format_string.push_str(&format!("\t\t{}\n", key));
             }
             format_string.push_str(&format!("\tUsed Expressiom Out:\n"));
-            for key in self.used_expr_out.get(&block).unwrap() {
-                format_string.push_str(&format!("\t\t{}\n", key.fmt_key()));
+            for key in fmt_sorted_keys(self.used_expr_out.get(&block).unwrap()) {
+                format_string.push_str(&format!("\t\t{}\n", key));
             }
         }
         format_string
@@ -8,7 +8,7 @@ use crate::entities::util::set_operation::{intersection_sets, union_sets};
 use crate::pass::analysis::cfg::ControlFlowGraph;
 use crate::pass::analysis::rpo::RevresePostOrder;
 use crate::pass::opt::lcm::anticipate_expr::AnticipateExpression;
-use crate::pass::{get_table_header, AnalysisPass, FormatTable};
+use crate::pass::{fmt_sorted_keys, get_sorted_blocks, get_table_header, AnalysisPass, FormatTable};
 
 pub fn will_be_available_expression_anaylsis(
     function: &Function,
@@ -40,15 +40,15 @@ impl WillBeAvailableExpression {
 impl FormatTable for WillBeAvailableExpression {
     fn format_table(&self, func: &Function, _module: &Module) -> String {
         let mut format_string = get_table_header("Will be Available");
-        for block in func.blocks() {
+        for block in get_sorted_blocks(func) {
             format_string.push_str(&format!("Block{}:\n", block.0));
             format_string.push_str(&format!("\tWill be Available In:\n"));
-            for key in self.will_be_avail_expr_in.get(&block).unwrap() {
-                format_string.push_str(&format!("\t\t{}\n", key.fmt_key()));
+            for key in fmt_sorted_keys(self.will_be_avail_expr_in.get(&block).unwrap()) {
+                format_string.push_str(&format!("\t\t{}\n", key));
             }
             format_string.push_str(&format!("\tWill be Available Out:\n"));
-            for key in self.will_be_avail_expr_out.get(&block).unwrap() {
-                format_string.push_str(&format!("\t\t{}\n", key.fmt_key()));
+            for key in fmt_sorted_keys(self.will_be_avail_expr_out.get(&block).unwrap()) {
+                format_string.push_str(&format!("\t\t{}\n", key));
             }
         }
         format_string
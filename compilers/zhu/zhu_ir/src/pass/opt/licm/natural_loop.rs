@@ -43,14 +43,15 @@ impl<'a> NaturalLoopAnalysis<'a> {
         self.find_backward_edges_by_dfs(self.cfg.get_entry(), &mut HashSet::new(), &mut edges);
         edges
     }
-    /// find natural loop blocks by reverse DFS.
+    /// find natural loop blocks by reverse DFS, predecessor unreachable from entry is
+    /// not in dominator tree and never part of loop.
     fn find_natural_loop_blocks(&self, header: Block, vertex: Block, blocks: &mut HashSet<Block>) {
         if vertex == header {
             return;
         }
         blocks.insert(vertex.clone());
         for predecessor in self.cfg.get_predecessors(&vertex) {
-            if !blocks.contains(predecessor) {
+            if !blocks.contains(predecessor) && self.dom.contains(*predecessor) {
                 self.find_natural_loop_blocks(header.clone(), predecessor.clone(), blocks);
             }
         }
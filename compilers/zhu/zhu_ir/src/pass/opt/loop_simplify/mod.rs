@@ -0,0 +1,184 @@
+use crate::builder::FunctionBuilder;
+use crate::entities::block::{Block, BlockData};
+use crate::entities::function::Function;
+use crate::entities::instruction::InstructionData;
+use crate::entities::value::Value;
+use crate::pass::analysis::cfg::{cfg_anylysis, ControlFlowGraph};
+use crate::pass::analysis::domtree::domtree_analysis;
+use crate::pass::analysis::loop_forest::{loop_forest_analysis, Loop, LoopForest};
+use crate::pass::opt::licm::natural_loop::natural_loop_analysis;
+use crate::pass::OptiPass;
+
+pub fn loop_simplify_pass(func: &mut Function) {
+    let mut pass = LoopSimplifyPass::new();
+    pass.process(func);
+}
+
+/// ## Loop Simplify Pass
+/// Transform every loop into canonical form, so later loop passes can assume:
+/// - preheader: header has only one predecessor outside the loop, and the predecessor
+///   has header as its only successor.
+/// - single latch: header has only one predecessor inside the loop.
+/// - dedicated exits: every predecessor of exit block is inside the loop.
+///
+/// Each form is reached by inserting a block which only jumps to header or exit block,
+/// and redirecting edges from a set of predecessors to the new block, incomings of phi
+/// from those predecessors are merged into a phi in the new block. Loop whose header is
+/// entry of function has no preheader since entry block can not have predecessor.
+///
+/// Loop forest is recomputed after every inserted block, outer loops are simplified
+/// before inner loops.
+pub struct LoopSimplifyPass {
+    inserted_preheader_count: usize,
+    merged_latch_count: usize,
+    inserted_exit_count: usize,
+}
+
+impl OptiPass for LoopSimplifyPass {
+    fn process(&mut self, func: &mut Function) {
+        if func.first_block().is_none() {
+            return;
+        }
+        while self.simplify_once(func) {}
+    }
+}
+
+impl Default for LoopSimplifyPass {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LoopSimplifyPass {
+    pub fn new() -> Self {
+        Self {
+            inserted_preheader_count: 0,
+            merged_latch_count: 0,
+            inserted_exit_count: 0,
+        }
+    }
+    pub fn get_inserted_preheader_count(&self) -> usize {
+        self.inserted_preheader_count
+    }
+    pub fn get_merged_latch_count(&self) -> usize {
+        self.merged_latch_count
+    }
+    pub fn get_inserted_exit_count(&self) -> usize {
+        self.inserted_exit_count
+    }
+    /// Insert one block for the first loop not in canonical form, return false when
+    /// every loop is in canonical form.
+    fn simplify_once(&mut self, func: &mut Function) -> bool {
+        let cfg = cfg_anylysis(func);
+        let forest = compute_loop_forest(func, &cfg);
+        for id in forest.get_loop_ids() {
+            let natural_loop = forest.get_loop(id);
+            if self.insert_preheader(func, &cfg, natural_loop)
+                || self.merge_latches(func, natural_loop)
+                || self.insert_dedicated_exit(func, &cfg, natural_loop)
+            {
+                return true;
+            }
+        }
+        false
+    }
+    fn insert_preheader(&mut self, func: &mut Function, cfg: &ControlFlowGraph, natural_loop: &Loop) -> bool {
+        let entering_blocks = natural_loop.get_entering_blocks(cfg);
+        if entering_blocks.is_empty() || natural_loop.get_preheader(cfg).is_some() {
+            return false;
+        }
+        let preheader = func.create_and_insert_block_before(BlockData::new(), natural_loop.header);
+        redirect_edges(func, &entering_blocks, natural_loop.header, preheader);
+        self.inserted_preheader_count += 1;
+        true
+    }
+    fn merge_latches(&mut self, func: &mut Function, natural_loop: &Loop) -> bool {
+        if natural_loop.latches.len() < 2 {
+            return false;
+        }
+        let last_latch = *natural_loop.latches.last().unwrap();
+        let latch = func.create_and_insert_block_after(BlockData::new(), last_latch);
+        redirect_edges(func, &natural_loop.latches, natural_loop.header, latch);
+        self.merged_latch_count += 1;
+        true
+    }
+    fn insert_dedicated_exit(&mut self, func: &mut Function, cfg: &ControlFlowGraph, natural_loop: &Loop) -> bool {
+        for exit_block in natural_loop.get_exit_blocks(cfg) {
+            let predecessors = cfg.get_predecessors(&exit_block);
+            if predecessors
+                .iter()
+                .all(|predecessor| natural_loop.contains(*predecessor))
+            {
+                continue;
+            }
+            let mut exiting_blocks = predecessors
+                .iter()
+                .filter(|predecessor| natural_loop.contains(**predecessor))
+                .copied()
+                .collect::<Vec<_>>();
+            exiting_blocks.sort_by_key(|block| block.0);
+            let dedicated_exit = func.create_and_insert_block_before(BlockData::new(), exit_block);
+            redirect_edges(func, &exiting_blocks, exit_block, dedicated_exit);
+            self.inserted_exit_count += 1;
+            return true;
+        }
+        false
+    }
+}
+
+fn compute_loop_forest(func: &Function, cfg: &ControlFlowGraph) -> LoopForest {
+    let dom = domtree_analysis(func, cfg);
+    let natural_loops = natural_loop_analysis(&dom, cfg);
+    loop_forest_analysis(&natural_loops)
+}
+
+/// Redirect edges from `predecessors` to `dst` into `new_block`, which jumps to `dst`.
+/// Incomings of phi in `dst` from `predecessors` are moved to a phi in `new_block`,
+/// phi is not created if those incomings have the same value.
+fn redirect_edges(func: &mut Function, predecessors: &[Block], dst: Block, new_block: Block) {
+    for predecessor in predecessors {
+        let last_inst = func.layout.get_last_inst(*predecessor);
+        match func.get_inst_data_mut(last_inst) {
+            InstructionData::Jump { dst: target, .. } => *target = new_block,
+            InstructionData::BrIf { conseq, alter, .. } => {
+                if *conseq == dst {
+                    *conseq = new_block;
+                }
+                if *alter == dst {
+                    *alter = new_block;
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    let mut builder = FunctionBuilder::new(func);
+    builder.switch_to_block(new_block);
+    builder.jump_inst(dst);
+    for inst in func.get_insts_of_block(dst) {
+        let InstructionData::Phi { from, .. } = func.get_inst_data(inst) else {
+            continue;
+        };
+        let (moved, mut kept): (Vec<_>, Vec<_>) = from
+            .iter()
+            .cloned()
+            .partition(|(from_block, _)| predecessors.contains(from_block));
+        if moved.is_empty() {
+            continue;
+        }
+        let value = if moved.iter().all(|(_, value)| *value == moved[0].1) {
+            moved[0].1
+        } else {
+            merge_incomings(func, new_block, moved)
+        };
+        kept.push((new_block, value));
+        if let InstructionData::Phi { from, .. } = func.get_inst_data_mut(inst) {
+            *from = kept;
+        }
+    }
+}
+
+fn merge_incomings(func: &mut Function, block: Block, from: Vec<(Block, Value)>) -> Value {
+    let mut builder = FunctionBuilder::new(func);
+    builder.switch_to_block(block);
+    builder.phi_inst(from)
+}
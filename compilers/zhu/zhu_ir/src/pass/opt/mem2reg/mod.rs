@@ -0,0 +1,382 @@
+use crate::builder::FunctionBuilder;
+use crate::entities::block::Block;
+use crate::entities::function::Function;
+use crate::entities::instruction::opcode::OpCode;
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::r#type::ValueType;
+use crate::entities::runtime_value::RuntimeValue;
+use crate::entities::value::{Value, ValueData};
+use crate::pass::analysis::cfg::ControlFlowGraph;
+use crate::pass::analysis::domtree::DomTree;
+use crate::pass::OptiPass;
+use std::collections::{HashMap, HashSet};
+
+pub fn mem2reg_pass(function: &mut Function, cfg: &ControlFlowGraph, dom: &DomTree) {
+    let mut pass = Mem2RegPass::new(cfg, dom);
+    pass.process(function);
+}
+
+/// Stack slot can be promoted to register.
+#[derive(Debug, Clone)]
+struct PromotableSlot {
+    /// Type and size declared by `stackalloc`.
+    alloc_ty: ValueType,
+    size: u64,
+    /// Type of value loaded from or stored to slot, none if slot is never accessed.
+    ty: Option<ValueType>,
+    def_blocks: HashSet<Block>,
+}
+
+/// ## Mem2Reg
+/// Promote `stackalloc` slots to SSA value, slot can be promoted when its address never
+/// escape, which means it only used as base of `load` and `store` at offset 0 with exactly
+/// the type and size declared by `stackalloc`. algorithm is based on the book `engineering a compiler 2e`:
+/// - insert phi at iterated dominance frontier of blocks which store to slot.
+/// - rename loads to reaching value by walking dominator tree.
+///
+/// NOTE: load before any store is replaced by zero constant, because stack slot is
+/// zero-initialized.
+pub struct Mem2RegPass<'a> {
+    cfg: &'a ControlFlowGraph,
+    dom: &'a DomTree,
+    slots: HashMap<Value, PromotableSlot>,
+    /// Phi instruction inserted for slot.
+    phi_slots: HashMap<Instruction, Value>,
+    /// Stack of reaching value of each slot during renaming.
+    stacks: HashMap<Value, Vec<Value>>,
+    /// Replace result of load by reaching value.
+    replace_map: HashMap<Value, Value>,
+    zero_values: HashMap<ValueType, Value>,
+    remove_insts: Vec<Instruction>,
+}
+
+impl<'a> OptiPass for Mem2RegPass<'a> {
+    fn process(&mut self, function: &mut Function) {
+        if function.first_block().is_none() {
+            return;
+        }
+        self.collect_promotable_slots(function);
+        if self.slots.is_empty() {
+            return;
+        }
+        self.insert_phis(function);
+        let entry = self.cfg.get_entry();
+        self.rename_block(entry, function);
+        self.complete_phis_from_unreachable(function);
+        self.remove_promoted_insts(function);
+        self.replace_operands(function);
+        self.remove_dead_phis(function);
+    }
+}
+
+impl<'a> Mem2RegPass<'a> {
+    pub fn new(cfg: &'a ControlFlowGraph, dom: &'a DomTree) -> Self {
+        Self {
+            cfg,
+            dom,
+            slots: Default::default(),
+            phi_slots: Default::default(),
+            stacks: Default::default(),
+            replace_map: Default::default(),
+            zero_values: Default::default(),
+            remove_insts: Default::default(),
+        }
+    }
+    fn collect_promotable_slots(&mut self, function: &Function) {
+        // slot accessed in unreachable block is not promoted.
+        let reachable = self.cfg.get_reachable_blocks();
+        let mut blocks = function.blocks();
+        blocks.sort_by_key(|block| block.0);
+        for block in &blocks {
+            for inst in function.get_insts_of_block(*block) {
+                if let InstructionData::StackAlloc { size, .. } = function.get_inst_data(inst) {
+                    if reachable.contains(block) {
+                        let slot = function.get_inst_result(inst).unwrap();
+                        self.slots.insert(
+                            slot,
+                            PromotableSlot {
+                                alloc_ty: function.value_type(slot).clone(),
+                                size: RuntimeValue::from(size).as_u64().unwrap_or(0),
+                                ty: None,
+                                def_blocks: Default::default(),
+                            },
+                        );
+                    }
+                }
+            }
+        }
+        let mut escaped = HashSet::new();
+        for block in &blocks {
+            for inst in function.get_insts_of_block(*block) {
+                let inst_data = function.get_inst_data(inst);
+                let (slot, ty, is_store) = match inst_data {
+                    InstructionData::LoadRegister { base, offset, .. } if offset.0 == 0 => {
+                        let result = function.get_inst_result(inst).unwrap();
+                        (*base, function.value_type(result).clone(), false)
+                    }
+                    InstructionData::StoreRegister { base, offset, src, .. } if offset.0 == 0 && base != src => {
+                        (*base, function.value_type(*src).clone(), true)
+                    }
+                    _ => {
+                        // any other usage make address escape.
+                        for operand in inst_data.get_operands() {
+                            if self.slots.contains_key(&operand) {
+                                escaped.insert(operand);
+                            }
+                        }
+                        continue;
+                    }
+                };
+                // store a slot address to other slot also make it escape.
+                if let InstructionData::StoreRegister { src, .. } = inst_data {
+                    if self.slots.contains_key(src) {
+                        escaped.insert(*src);
+                    }
+                }
+                let Some(promotable) = self.slots.get_mut(&slot) else {
+                    continue;
+                };
+                if !reachable.contains(block) {
+                    escaped.insert(slot);
+                    continue;
+                }
+                // access wider or narrower than slot can not be replaced by a single value.
+                if ty != promotable.alloc_ty || ty.get_size() as u64 != promotable.size {
+                    escaped.insert(slot);
+                    continue;
+                }
+                promotable.ty = Some(ty);
+                if is_store {
+                    promotable.def_blocks.insert(*block);
+                }
+            }
+        }
+        self.slots.retain(|slot, _| !escaped.contains(slot));
+    }
+    /// Insert empty phi at iterated dominance frontier of def blocks of each slot.
+    fn insert_phis(&mut self, function: &mut Function) {
+        let mut slots: Vec<Value> = self.slots.keys().copied().collect();
+        slots.sort_by_key(|slot| slot.0);
+        for slot in slots {
+            let promotable = self.slots.get(&slot).unwrap();
+            let Some(ty) = promotable.ty.clone() else {
+                continue;
+            };
+            let mut has_phi: HashSet<Block> = HashSet::new();
+            let mut worklist: Vec<Block> = promotable.def_blocks.iter().copied().collect();
+            worklist.sort_by_key(|block| block.0);
+            let mut visited: HashSet<Block> = worklist.iter().copied().collect();
+            while let Some(block) = worklist.pop() {
+                let mut frontier: Vec<Block> = self.dom.df(block).iter().copied().collect();
+                frontier.sort_by_key(|block| block.0);
+                for df_block in frontier {
+                    if !has_phi.insert(df_block) {
+                        continue;
+                    }
+                    let phi = self.create_empty_phi(function, df_block, ty.clone());
+                    self.phi_slots.insert(phi, slot);
+                    if visited.insert(df_block) {
+                        worklist.push(df_block);
+                    }
+                }
+            }
+        }
+    }
+    fn create_empty_phi(&self, function: &mut Function, block: Block, ty: ValueType) -> Instruction {
+        let inst = function.entities.create_inst(InstructionData::Phi {
+            opcode: OpCode::Phi,
+            from: Vec::new(),
+        });
+        let result = function.entities.create_value(ValueData::Inst { inst, ty });
+        function.entities.mark_phi_block(inst, block);
+        function.entities.mark_inst_result(result, inst);
+        match self.get_first_non_phi_inst(function, block) {
+            Some(before) => function.insert_inst_before(inst, before),
+            None => function.append_inst(inst, block),
+        }
+        inst
+    }
+    fn get_first_non_phi_inst(&self, function: &Function, block: Block) -> Option<Instruction> {
+        function
+            .get_insts_of_block(block)
+            .into_iter()
+            .find(|inst| !matches!(function.get_inst_data(*inst), InstructionData::Phi { .. }))
+    }
+    /// Get zero constant of given type, zero constant is created at entry block after phis.
+    fn get_zero_value(&mut self, function: &mut Function, ty: &ValueType) -> Value {
+        if let Some(value) = self.zero_values.get(ty) {
+            return *value;
+        }
+        let entry = self.cfg.get_entry();
+        let size = ty.get_size();
+        let mut builder = FunctionBuilder::new(function);
+        builder.switch_to_block(entry);
+        let value = match ty {
+            ValueType::F32 | ValueType::F64 => builder.fconst_inst(vec![0; size], ty.clone()),
+            ValueType::U8 | ValueType::U16 | ValueType::U32 | ValueType::U64 => {
+                builder.uconst_inst(vec![0; size], ty.clone())
+            }
+            _ => builder.iconst_inst(vec![0; size], ty.clone()),
+        };
+        let ValueData::Inst { inst, .. } = function.get_value_data(value).clone() else {
+            unreachable!()
+        };
+        function.layout.remove_inst(inst);
+        match self.get_first_non_phi_inst(function, entry) {
+            Some(before) => function.insert_inst_before(inst, before),
+            None => function.append_inst(inst, entry),
+        }
+        self.zero_values.insert(ty.clone(), value);
+        value
+    }
+    fn get_reaching_value(&mut self, function: &mut Function, slot: Value) -> Value {
+        if let Some(value) = self.stacks.get(&slot).and_then(|stack| stack.last()) {
+            return *value;
+        }
+        let ty = self.slots.get(&slot).unwrap().ty.clone().unwrap();
+        self.get_zero_value(function, &ty)
+    }
+    fn resolve_value(&self, value: Value) -> Value {
+        self.replace_map.get(&value).copied().unwrap_or(value)
+    }
+    /// Rename loads and stores of promoted slots by dfs on dominator tree.
+    fn rename_block(&mut self, block: Block, function: &mut Function) {
+        let stack_lens: HashMap<Value, usize> = self.stacks.iter().map(|(slot, stack)| (*slot, stack.len())).collect();
+        for inst in function.get_insts_of_block(block) {
+            if let Some(slot) = self.phi_slots.get(&inst) {
+                let result = function.get_inst_result(inst).unwrap();
+                self.stacks.entry(*slot).or_default().push(result);
+                continue;
+            }
+            match function.get_inst_data(inst).clone() {
+                InstructionData::StackAlloc { .. } => {
+                    let slot = function.get_inst_result(inst).unwrap();
+                    if self.slots.contains_key(&slot) {
+                        self.remove_insts.push(inst);
+                    }
+                }
+                InstructionData::LoadRegister { base, .. } if self.slots.contains_key(&base) => {
+                    let result = function.get_inst_result(inst).unwrap();
+                    let value = self.get_reaching_value(function, base);
+                    self.replace_map.insert(result, value);
+                    self.remove_insts.push(inst);
+                }
+                InstructionData::StoreRegister { base, src, .. } if self.slots.contains_key(&base) => {
+                    let value = self.resolve_value(src);
+                    self.stacks.entry(base).or_default().push(value);
+                    self.remove_insts.push(inst);
+                }
+                _ => {}
+            }
+        }
+        // fill phi arguments of successors.
+        let mut successors: Vec<Block> = self.cfg.get_successors(&block).iter().copied().collect();
+        successors.sort_by_key(|block| block.0);
+        for successor in successors {
+            for inst in function.get_insts_of_block(successor) {
+                let Some(slot) = self.phi_slots.get(&inst).copied() else {
+                    continue;
+                };
+                let value = self.get_reaching_value(function, slot);
+                if let InstructionData::Phi { from, .. } = function.get_inst_data_mut(inst) {
+                    from.push((block, value));
+                }
+            }
+        }
+        let mut children: Vec<Block> = self.dom.children(block).iter().copied().collect();
+        children.sort_by_key(|block| block.0);
+        for child in children {
+            self.rename_block(child, function);
+        }
+        for (slot, stack) in self.stacks.iter_mut() {
+            stack.truncate(stack_lens.get(slot).copied().unwrap_or(0));
+        }
+    }
+    /// Predecessor not reachable from entry is not visited in renaming, give it zero value
+    /// to keep phi has argument from every predecessor.
+    fn complete_phis_from_unreachable(&mut self, function: &mut Function) {
+        let mut phis: Vec<(Instruction, Value)> = self.phi_slots.iter().map(|(inst, slot)| (*inst, *slot)).collect();
+        phis.sort_by_key(|(inst, _)| inst.0);
+        for (phi, slot) in phis {
+            let block = function.get_block_of_inst(phi);
+            let mut predecessors: Vec<Block> = self.cfg.get_predecessors(&block).iter().copied().collect();
+            predecessors.sort_by_key(|block| block.0);
+            for predecessor in predecessors {
+                let has_arm = match function.get_inst_data(phi) {
+                    InstructionData::Phi { from, .. } => from.iter().any(|(from_block, _)| *from_block == predecessor),
+                    _ => unreachable!(),
+                };
+                if has_arm {
+                    continue;
+                }
+                let ty = self.slots.get(&slot).unwrap().ty.clone().unwrap();
+                let value = self.get_zero_value(function, &ty);
+                if let InstructionData::Phi { from, .. } = function.get_inst_data_mut(phi) {
+                    from.push((predecessor, value));
+                }
+            }
+        }
+    }
+    fn remove_promoted_insts(&mut self, function: &mut Function) {
+        for inst in std::mem::take(&mut self.remove_insts) {
+            let block = function.get_block_of_inst(inst);
+            function.get_block_data_mut(block).insts.remove(&inst);
+            function.remove_inst(inst);
+        }
+    }
+    fn replace_operands(&mut self, function: &mut Function) {
+        for inst in function.insts() {
+            for operand in function.get_inst_data_mut(inst).get_operands_mut() {
+                if let Some(value) = self.replace_map.get(operand) {
+                    *operand = *value;
+                }
+            }
+        }
+    }
+    /// Remove inserted phis which result is not used by any other instruction. phi is live only
+    /// when it reach a use of non-inserted instruction, so dead phis using each other around loop
+    /// are also removed.
+    fn remove_dead_phis(&mut self, function: &mut Function) {
+        let phi_results: HashMap<Value, Instruction> = self
+            .phi_slots
+            .keys()
+            .map(|phi| (function.get_inst_result(*phi).unwrap(), *phi))
+            .collect();
+        let mut live: HashSet<Instruction> = HashSet::new();
+        let mut worklist: Vec<Instruction> = Vec::new();
+        for inst in function.insts() {
+            if self.phi_slots.contains_key(&inst) {
+                continue;
+            }
+            for operand in function.get_inst_data(inst).get_operands() {
+                if let Some(phi) = phi_results.get(&operand) {
+                    if live.insert(*phi) {
+                        worklist.push(*phi);
+                    }
+                }
+            }
+        }
+        while let Some(phi) = worklist.pop() {
+            for operand in function.get_inst_data(phi).get_operands() {
+                if let Some(phi) = phi_results.get(&operand) {
+                    if live.insert(*phi) {
+                        worklist.push(*phi);
+                    }
+                }
+            }
+        }
+        let mut dead_phis: Vec<Instruction> = self
+            .phi_slots
+            .keys()
+            .filter(|phi| !live.contains(phi))
+            .copied()
+            .collect();
+        dead_phis.sort_by_key(|phi| phi.0);
+        for phi in dead_phis {
+            let block = function.get_block_of_inst(phi);
+            function.get_block_data_mut(block).phis.remove(&phi);
+            function.remove_inst(phi);
+            self.phi_slots.remove(&phi);
+        }
+    }
+}
@@ -1,4 +1,12 @@
 pub mod dce;
+pub mod dse;
 pub mod gvn;
+pub mod instcombine;
 pub mod lcm;
 pub mod licm;
+pub mod loop_simplify;
+pub mod mem2reg;
+pub mod out_of_ssa;
+pub mod sccp;
+pub mod strength_reduce;
+pub mod unroll;
@@ -0,0 +1,178 @@
+use crate::entities::block::Block;
+use crate::entities::function::Function;
+use crate::entities::instruction::opcode::OpCode;
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::value::{Value, ValueData};
+use crate::pass::analysis::cfg::{cfg_anylysis, ControlFlowGraph};
+use crate::pass::opt::lcm::critical_edge::split_edge;
+use crate::pass::OptiPass;
+
+pub fn out_of_ssa_pass(function: &mut Function, cfg: &ControlFlowGraph) {
+    let mut pass = OutOfSsaPass::new(cfg);
+    pass.process(function);
+}
+
+/// Parallel copies `(dst, src)` on a edge, all sources are read before any destination is written.
+type ParallelCopies = Vec<(Value, Value)>;
+
+/// ## Out Of SSA
+/// Translate function out of SSA form by replacing every phi with copies (`mov`)
+/// in its predecessors, result of phi become a register defined by multiple copies.
+/// - critical edges into block with phi are splitted first, so copy of a edge never
+///   execute on other path (lost-copy problem).
+/// - copies of a edge are parallel copies, they are sequentialized so that no source
+///   is overwritten before it is read, cycle is broken by a temporary (swap problem).
+pub struct OutOfSsaPass<'a> {
+    cfg: &'a ControlFlowGraph,
+    /// Parallel copies of edge `(predecessor, block)`.
+    copies: Vec<((Block, Block), ParallelCopies)>,
+}
+
+impl<'a> OptiPass for OutOfSsaPass<'a> {
+    fn process(&mut self, function: &mut Function) {
+        if function.first_block().is_none() {
+            return;
+        }
+        self.split_critical_edges(function);
+        let cfg = cfg_anylysis(function);
+        self.collect_copies(function, &cfg);
+        self.insert_copies(function, &cfg);
+    }
+}
+
+impl<'a> OutOfSsaPass<'a> {
+    pub fn new(cfg: &'a ControlFlowGraph) -> Self {
+        Self {
+            cfg,
+            copies: Default::default(),
+        }
+    }
+    /// Get blocks in function sorted by index, make output text format stable.
+    fn sorted_blocks(function: &Function) -> Vec<Block> {
+        let mut blocks = function.blocks();
+        blocks.sort_by_key(|block| block.0);
+        blocks
+    }
+    fn has_phi(function: &Function, block: Block) -> bool {
+        function
+            .get_insts_of_block(block)
+            .into_iter()
+            .any(|inst| matches!(function.get_inst_data(inst), InstructionData::Phi { .. }))
+    }
+    /// Split edge which source has multiple successors and target has multiple
+    /// predecessors, only target with phi need to be splitted.
+    fn split_critical_edges(&self, function: &mut Function) {
+        let mut edges = Vec::new();
+        for block in Self::sorted_blocks(function) {
+            if self.cfg.get_predecessors(&block).len() < 2 || !Self::has_phi(function, block) {
+                continue;
+            }
+            let mut predecessors = self.cfg.get_predecessors(&block).iter().cloned().collect::<Vec<_>>();
+            predecessors.sort_by_key(|block| block.0);
+            for predecessor in predecessors {
+                if self.cfg.get_successors(&predecessor).len() > 1 {
+                    edges.push((predecessor, block));
+                }
+            }
+        }
+        for (src, dst) in edges {
+            split_edge(function, src, dst);
+        }
+    }
+    /// Collect parallel copies of each edge from phi, and remove phi from function.
+    fn collect_copies(&mut self, function: &mut Function, cfg: &ControlFlowGraph) {
+        for block in Self::sorted_blocks(function) {
+            let mut edge_copies: Vec<(Block, ParallelCopies)> = Vec::new();
+            for inst in function.get_insts_of_block(block) {
+                let InstructionData::Phi { from, .. } = function.get_inst_data(inst).clone() else {
+                    continue;
+                };
+                let dst = function.get_inst_result(inst).unwrap();
+                for (predecessor, src) in from {
+                    // arm from unreachable or removed block never execute.
+                    if !cfg.get_predecessors(&block).contains(&predecessor) {
+                        continue;
+                    }
+                    match edge_copies.iter_mut().find(|(block, _)| *block == predecessor) {
+                        Some((_, copies)) => copies.push((dst, src)),
+                        None => edge_copies.push((predecessor, vec![(dst, src)])),
+                    }
+                }
+                function.remove_inst(inst);
+                function.get_block_data_mut(block).phis.remove(&inst);
+                function.entities.insts_result.remove(&inst);
+            }
+            edge_copies.sort_by_key(|(predecessor, _)| predecessor.0);
+            for (predecessor, copies) in edge_copies {
+                self.copies.push(((predecessor, block), copies));
+            }
+        }
+    }
+    /// Insert sequentialized copies of each edge, copies are placed before terminator
+    /// of predecessor, or at start of block when predecessor has multiple successors
+    /// (block must have only one predecessor since critical edges are splitted).
+    fn insert_copies(&mut self, function: &mut Function, cfg: &ControlFlowGraph) {
+        for ((predecessor, block), copies) in std::mem::take(&mut self.copies) {
+            let (target_block, before) = if cfg.get_successors(&predecessor).len() == 1 {
+                (predecessor, function.layout.get_last_inst(predecessor))
+            } else {
+                (block, function.layout.get_first_inst(block))
+            };
+            for inst in Self::sequentialize(function, target_block, copies) {
+                function.insert_inst_before(inst, before);
+            }
+        }
+    }
+    /// Sequentialize parallel copies, copy is emitted only when its destination is
+    /// not read by other pending copies. when every pending destination is still read,
+    /// pending copies form cycles, save one destination into temporary to break it.
+    fn sequentialize(function: &mut Function, block: Block, copies: ParallelCopies) -> Vec<Instruction> {
+        let mut pending = copies.into_iter().filter(|(dst, src)| dst != src).collect::<Vec<_>>();
+        let mut insts = Vec::new();
+        while !pending.is_empty() {
+            let ready = pending
+                .iter()
+                .position(|(dst, _)| !pending.iter().any(|(_, src)| src == dst));
+            match ready {
+                Some(index) => {
+                    let (dst, src) = pending.remove(index);
+                    insts.push(Self::create_copy(function, block, Some(dst), src).1);
+                }
+                None => {
+                    let saved = pending[0].0;
+                    let (temp, inst) = Self::create_copy(function, block, None, saved);
+                    insts.push(inst);
+                    for (_, src) in pending.iter_mut() {
+                        if *src == saved {
+                            *src = temp;
+                        }
+                    }
+                }
+            }
+        }
+        insts
+    }
+    /// Create a `mov` instruction without layout. copy to a existed destination make
+    /// destination defined by this copy, otherwise a new temporary value is created.
+    fn create_copy(function: &mut Function, block: Block, dst: Option<Value>, src: Value) -> (Value, Instruction) {
+        let inst = function.entities.create_inst(InstructionData::Move {
+            opcode: OpCode::Mov,
+            src,
+        });
+        function.entities.mark_inst_block(inst, block);
+        let dst = match dst {
+            Some(dst) => {
+                if let ValueData::Inst { inst: def, .. } = function.get_value_data_mut(dst) {
+                    *def = inst;
+                }
+                dst
+            }
+            None => {
+                let ty = function.value_type(src).clone();
+                function.entities.create_value(ValueData::Inst { inst, ty })
+            }
+        };
+        function.entities.mark_inst_result(dst, inst);
+        (dst, inst)
+    }
+}
@@ -0,0 +1,346 @@
+use crate::entities::block::Block;
+use crate::entities::constant::ConstantData;
+use crate::entities::function::Function;
+use crate::entities::instruction::opcode::OpCode;
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::r#type::ValueType;
+use crate::entities::runtime_value::RuntimeValue;
+use crate::entities::value::Value;
+use crate::pass::analysis::cfg::ControlFlowGraph;
+use crate::pass::OptiPass;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem::discriminant;
+
+pub fn sccp_pass(function: &mut Function, cfg: &ControlFlowGraph) {
+    let mut pass = SccpPass::new(cfg);
+    pass.process(function);
+}
+
+/// Lattice of value in SCCP, `Top` means value is not yet defined, `Bottom`
+/// means value is not a constant (overdefined).
+#[derive(Debug, Clone, Copy)]
+pub enum LatticeValue {
+    Top,
+    Constant(RuntimeValue),
+    Bottom,
+}
+
+/// Constants are equal only when bytes are same, so that NaN is equal to itself
+/// and `0.0` is not equal to `-0.0`.
+impl PartialEq for LatticeValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (LatticeValue::Top, LatticeValue::Top) | (LatticeValue::Bottom, LatticeValue::Bottom) => true,
+            (LatticeValue::Constant(a), LatticeValue::Constant(b)) => {
+                discriminant(a) == discriminant(b) && a.to_bytes() == b.to_bytes()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl LatticeValue {
+    /// Meet operator of lattice.
+    pub fn meet(self, other: LatticeValue) -> LatticeValue {
+        match (self, other) {
+            (LatticeValue::Top, value) | (value, LatticeValue::Top) => value,
+            (LatticeValue::Constant(_), LatticeValue::Constant(_)) if self == other => self,
+            _ => LatticeValue::Bottom,
+        }
+    }
+}
+
+/// ## Sparse Conditional Constant Propagation
+/// Propagate constant on SSA graph and only through executable edges of CFG, algorithm
+/// is based on Wegman and Zadeck's paper `Constant propagation with conditional branches`.
+/// after propagation:
+/// - instruction evaluated to constant is replaced by const instruction.
+/// - `brif` with constant test is rewritten to `jump`.
+/// - phi arms from non-executable edges are removed.
+/// - blocks never executed are removed.
+///
+/// Constant is folded by the same semantic of interpreter, so that it respects the width
+/// and signedness of value type.
+pub struct SccpPass<'a> {
+    cfg: &'a ControlFlowGraph,
+    lattice: HashMap<Value, LatticeValue>,
+    users: HashMap<Value, Vec<Instruction>>,
+    executable_blocks: HashSet<Block>,
+    executable_edges: HashSet<(Block, Block)>,
+    flow_worklist: VecDeque<(Block, Block)>,
+    ssa_worklist: VecDeque<Value>,
+}
+
+impl<'a> OptiPass for SccpPass<'a> {
+    fn process(&mut self, function: &mut Function) {
+        if function.first_block().is_none() {
+            return;
+        }
+        self.propagate(function);
+        self.rewrite_constant_insts(function);
+        self.rewrite_constant_branches(function);
+        self.remove_dead_phi_arms(function);
+        self.remove_unexecutable_blocks(function);
+    }
+}
+
+impl<'a> SccpPass<'a> {
+    pub fn new(cfg: &'a ControlFlowGraph) -> Self {
+        Self {
+            cfg,
+            lattice: Default::default(),
+            users: Default::default(),
+            executable_blocks: Default::default(),
+            executable_edges: Default::default(),
+            flow_worklist: Default::default(),
+            ssa_worklist: Default::default(),
+        }
+    }
+    /// Get lattice value of given value after pass is processed.
+    pub fn get_lattice(&self, value: Value) -> LatticeValue {
+        self.lattice.get(&value).copied().unwrap_or(LatticeValue::Top)
+    }
+    pub fn is_executable_block(&self, block: Block) -> bool {
+        self.executable_blocks.contains(&block)
+    }
+    fn propagate(&mut self, function: &Function) {
+        for param in &function.entities.params {
+            self.lattice.insert(*param, LatticeValue::Bottom);
+        }
+        for inst in function.insts() {
+            for operand in function.get_inst_data(inst).get_operands() {
+                self.users.entry(operand).or_default().push(inst);
+            }
+        }
+        let entry = self.cfg.get_entry();
+        self.executable_blocks.insert(entry);
+        self.visit_block(entry, function);
+        while !self.flow_worklist.is_empty() || !self.ssa_worklist.is_empty() {
+            while let Some((from, to)) = self.flow_worklist.pop_front() {
+                if !self.executable_edges.insert((from, to)) {
+                    continue;
+                }
+                if self.executable_blocks.insert(to) {
+                    self.visit_block(to, function);
+                } else {
+                    for inst in function.get_insts_of_block(to) {
+                        if let InstructionData::Phi { .. } = function.get_inst_data(inst) {
+                            self.visit_inst(inst, function);
+                        }
+                    }
+                }
+            }
+            while let Some(value) = self.ssa_worklist.pop_front() {
+                for inst in self.users.get(&value).cloned().unwrap_or_default() {
+                    if self.executable_blocks.contains(&function.get_block_of_inst(inst)) {
+                        self.visit_inst(inst, function);
+                    }
+                }
+            }
+        }
+    }
+    fn visit_block(&mut self, block: Block, function: &Function) {
+        for inst in function.get_insts_of_block(block) {
+            self.visit_inst(inst, function);
+        }
+    }
+    fn visit_inst(&mut self, inst: Instruction, function: &Function) {
+        let block = function.get_block_of_inst(inst);
+        match function.get_inst_data(inst) {
+            InstructionData::Jump { dst, .. } => self.flow_worklist.push_back((block, *dst)),
+            InstructionData::BrIf {
+                test, conseq, alter, ..
+            } => match self.get_lattice(*test) {
+                LatticeValue::Top => {}
+                LatticeValue::Constant(value) => match value.as_u64() {
+                    Some(0) => self.flow_worklist.push_back((block, *alter)),
+                    Some(_) => self.flow_worklist.push_back((block, *conseq)),
+                    None => {
+                        self.flow_worklist.push_back((block, *conseq));
+                        self.flow_worklist.push_back((block, *alter));
+                    }
+                },
+                LatticeValue::Bottom => {
+                    self.flow_worklist.push_back((block, *conseq));
+                    self.flow_worklist.push_back((block, *alter));
+                }
+            },
+            _ => {
+                let Some(result) = function.get_inst_result(inst) else {
+                    return;
+                };
+                let old_value = self.get_lattice(result);
+                if old_value == LatticeValue::Bottom {
+                    return;
+                }
+                let new_value = old_value.meet(self.evaluate_inst(inst, block, function));
+                if new_value != old_value {
+                    self.lattice.insert(result, new_value);
+                    self.ssa_worklist.push_back(result);
+                }
+            }
+        }
+    }
+    /// Evaluate lattice value of instruction result, phi only meet arms from
+    /// executable edges.
+    fn evaluate_inst(&self, inst: Instruction, block: Block, function: &Function) -> LatticeValue {
+        let result_ty = function.value_type(function.get_inst_result(inst).unwrap());
+        if let ValueType::Mem(_) = result_ty {
+            return LatticeValue::Bottom;
+        }
+        let inst_data = function.get_inst_data(inst);
+        if let InstructionData::Phi { from, .. } = inst_data {
+            return from
+                .iter()
+                .filter(|(from_block, _)| self.executable_edges.contains(&(*from_block, block)))
+                .fold(LatticeValue::Top, |acc, (_, value)| acc.meet(self.get_lattice(*value)));
+        }
+        if let InstructionData::UnaryConst { constant, .. } = inst_data {
+            return match function.constants.get(constant) {
+                Some(constant_data) => {
+                    LatticeValue::Constant(RuntimeValue::from_bytes(result_ty, &constant_data.bytes))
+                }
+                None => LatticeValue::Bottom,
+            };
+        }
+        let foldable = matches!(
+            inst_data,
+            InstructionData::Unary { .. }
+                | InstructionData::Move { .. }
+                | InstructionData::Binary { .. }
+                | InstructionData::BinaryI { .. }
+                | InstructionData::Icmp { .. }
+                | InstructionData::Fcmp { .. }
+                | InstructionData::Convert { .. }
+        );
+        if !foldable {
+            return LatticeValue::Bottom;
+        }
+        let mut operands = Vec::new();
+        let mut has_top = false;
+        for operand in inst_data.get_operands() {
+            match self.get_lattice(operand) {
+                LatticeValue::Bottom => return LatticeValue::Bottom,
+                LatticeValue::Top => has_top = true,
+                LatticeValue::Constant(value) => operands.push(value),
+            }
+        }
+        if has_top {
+            return LatticeValue::Top;
+        }
+        let folded = match inst_data {
+            InstructionData::Unary { opcode, .. } => RuntimeValue::unary(*opcode, operands[0]),
+            InstructionData::Move { .. } => Ok(operands[0]),
+            InstructionData::Binary { opcode, .. } => RuntimeValue::binary(*opcode, operands[0], operands[1]),
+            InstructionData::BinaryI { opcode, value, imm } => {
+                let imm = RuntimeValue::from(imm).cast(function.value_type(*value));
+                RuntimeValue::binary(*opcode, operands[0], imm)
+            }
+            InstructionData::Icmp { flag, .. } | InstructionData::Fcmp { flag, .. } => {
+                RuntimeValue::compare(*flag, operands[0], operands[1]).map(|result| RuntimeValue::U8(result as u8))
+            }
+            InstructionData::Convert { .. } => Ok(operands[0].cast(result_ty)),
+            _ => unreachable!(),
+        };
+        match folded {
+            Ok(value) if value.is_type_of(result_ty) => LatticeValue::Constant(value),
+            // keep runtime error like division by zero.
+            _ => LatticeValue::Bottom,
+        }
+    }
+    fn get_sorted_executable_blocks(&self, function: &Function) -> Vec<Block> {
+        let mut blocks: Vec<Block> = function
+            .blocks()
+            .into_iter()
+            .filter(|block| self.executable_blocks.contains(block))
+            .collect();
+        blocks.sort_by_key(|block| block.0);
+        blocks
+    }
+    /// Replace instructions evaluated to constant by const instruction, result value is kept
+    /// so that users do not need to be rewritten.
+    fn rewrite_constant_insts(&self, function: &mut Function) {
+        for block in self.get_sorted_executable_blocks(function) {
+            for inst in function.get_insts_of_block(block) {
+                let Some(result) = function.get_inst_result(inst) else {
+                    continue;
+                };
+                let LatticeValue::Constant(value) = self.get_lattice(result) else {
+                    continue;
+                };
+                let is_phi = match function.get_inst_data(inst) {
+                    InstructionData::UnaryConst { .. } => continue,
+                    InstructionData::Phi { .. } => true,
+                    _ => false,
+                };
+                let opcode = match value {
+                    RuntimeValue::F32(_) | RuntimeValue::F64(_) => OpCode::Fconst,
+                    RuntimeValue::I16(_) | RuntimeValue::I32(_) | RuntimeValue::I64(_) => OpCode::Iconst,
+                    _ => OpCode::Uconst,
+                };
+                let constant = function.create_constant(ConstantData {
+                    bytes: value.to_bytes(),
+                });
+                function.replace_inst(inst, InstructionData::UnaryConst { opcode, constant });
+                if is_phi {
+                    // const instruction can not be placed before phis.
+                    function.remove_inst(inst);
+                    let block_data = function.get_block_data_mut(block);
+                    block_data.phis.remove(&inst);
+                    block_data.insts.insert(inst);
+                    let first_non_phi = function
+                        .get_insts_of_block(block)
+                        .into_iter()
+                        .find(|inst| !matches!(function.get_inst_data(*inst), InstructionData::Phi { .. }));
+                    match first_non_phi {
+                        Some(before) => function.insert_inst_before(inst, before),
+                        None => function.append_inst(inst, block),
+                    }
+                }
+            }
+        }
+    }
+    fn rewrite_constant_branches(&self, function: &mut Function) {
+        for block in self.get_sorted_executable_blocks(function) {
+            for inst in function.get_insts_of_block(block) {
+                let InstructionData::BrIf {
+                    test, conseq, alter, ..
+                } = function.get_inst_data(inst)
+                else {
+                    continue;
+                };
+                let LatticeValue::Constant(value) = self.get_lattice(*test) else {
+                    continue;
+                };
+                let dst = match value.as_u64() {
+                    Some(0) => *alter,
+                    Some(_) => *conseq,
+                    None => continue,
+                };
+                function.replace_inst(
+                    inst,
+                    InstructionData::Jump {
+                        opcode: OpCode::Jump,
+                        dst,
+                    },
+                );
+            }
+        }
+    }
+    fn remove_dead_phi_arms(&self, function: &mut Function) {
+        for block in self.get_sorted_executable_blocks(function) {
+            for inst in function.get_insts_of_block(block) {
+                if let InstructionData::Phi { from, .. } = function.get_inst_data_mut(inst) {
+                    from.retain(|(from_block, _)| self.executable_edges.contains(&(*from_block, block)));
+                }
+            }
+        }
+    }
+    fn remove_unexecutable_blocks(&self, function: &mut Function) {
+        for block in function.blocks() {
+            if !self.executable_blocks.contains(&block) {
+                function.remove_block(block);
+            }
+        }
+    }
+}
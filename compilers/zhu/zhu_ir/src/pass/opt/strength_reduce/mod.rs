@@ -0,0 +1,407 @@
+use std::collections::HashSet;
+
+use crate::entities::block::Block;
+use crate::entities::constant::ConstantData;
+use crate::entities::function::Function;
+use crate::entities::instruction::opcode::{CmpFlag, OpCode};
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::r#type::ValueType;
+use crate::entities::runtime_value::RuntimeValue;
+use crate::entities::value::{Value, ValueData};
+use crate::pass::analysis::cfg::ControlFlowGraph;
+use crate::pass::analysis::induction_variable::{
+    induction_variable_analysis, DerivedInductionVariable, ExitCondition, LoopInductionVariables,
+};
+use crate::pass::analysis::loop_forest::loop_forest_analysis;
+use crate::pass::opt::licm::natural_loop::NaturalLoop;
+use crate::pass::OptiPass;
+
+pub fn strength_reduce_pass(func: &mut Function, cfg: &ControlFlowGraph, natural_loops: &[NaturalLoop]) {
+    let mut pass = StrengthReducePass::new(cfg, natural_loops);
+    pass.process(func);
+}
+
+/// ## Induction Variable Strength Reduction Pass
+/// Reduce multiplication of induction variable in loop into addition, for example
+/// ```text
+/// header:                               header:
+///   i = phi [pre 0, latch i.next]         d = phi [pre base, latch d.next]
+///   ...                                   ...
+///   offset = muli i 4              =>     d.next = add d 4
+///   address = add base offset             ...
+///   i.next = addi i 1
+/// ```
+/// every derived induction variable `scale * i + base + offset` which scale is not 1
+/// and is used by other than derived induction variable, is replaced by a new phi in
+/// header, which init is computed in preheader and is increased by `scale * step`
+/// right after update of basic induction variable.
+///
+/// When trip count of loop is known, comparison of exit branch is rewritten to test a
+/// reduced induction variable equal to its value in the last iteration, then basic
+/// induction variable which is only used by itself is removed.
+///
+/// Only loop which has a preheader and a single latch is reduced, so `loop-simplify`
+/// should be run before this pass. Control flow graph is not changed.
+pub struct StrengthReducePass<'a> {
+    cfg: &'a ControlFlowGraph,
+    natural_loops: &'a [NaturalLoop],
+    reduced_count: usize,
+    replaced_exit_count: usize,
+    eliminated_count: usize,
+}
+
+/// Derived induction variable replaced by new phi, `next` is its value in next
+/// iteration.
+struct ReducedVariable {
+    derived: DerivedInductionVariable,
+    phi: Value,
+    next: Value,
+}
+
+impl<'a> OptiPass for StrengthReducePass<'a> {
+    fn process(&mut self, func: &mut Function) {
+        let forest = loop_forest_analysis(self.natural_loops);
+        let mut headers = forest
+            .get_loop_ids()
+            .into_iter()
+            .map(|id| forest.get_loop(id))
+            .filter(|merged| merged.latches.len() == 1)
+            .filter_map(|merged| Some((merged.header, merged.get_preheader(self.cfg)?)))
+            .collect::<Vec<_>>();
+        headers.sort_by_key(|(header, _)| header.0);
+        for (header, preheader) in headers {
+            let natural_loop = self
+                .natural_loops
+                .iter()
+                .find(|natural_loop| natural_loop.header == header)
+                .unwrap();
+            self.reduce_loop(func, natural_loop, preheader);
+        }
+    }
+}
+
+impl<'a> StrengthReducePass<'a> {
+    pub fn new(cfg: &'a ControlFlowGraph, natural_loops: &'a [NaturalLoop]) -> Self {
+        Self {
+            cfg,
+            natural_loops,
+            reduced_count: 0,
+            replaced_exit_count: 0,
+            eliminated_count: 0,
+        }
+    }
+    /// Get number of derived induction variables replaced by phi.
+    pub fn get_reduced_count(&self) -> usize {
+        self.reduced_count
+    }
+    /// Get number of exit comparisons rewritten to test reduced induction variable.
+    pub fn get_replaced_exit_count(&self) -> usize {
+        self.replaced_exit_count
+    }
+    /// Get number of basic induction variables removed.
+    pub fn get_eliminated_count(&self) -> usize {
+        self.eliminated_count
+    }
+    fn reduce_loop(&mut self, func: &mut Function, natural_loop: &NaturalLoop, preheader: Block) {
+        let induction_variables = induction_variable_analysis(func, self.cfg, natural_loop);
+        let candidates = induction_variables
+            .get_derived_induction_variables()
+            .iter()
+            .filter(|derived| !is_one(&derived.scale) && !is_zero(&derived.scale))
+            .cloned()
+            .collect::<Vec<_>>();
+        let candidate_values = candidates.iter().map(|derived| derived.value).collect::<HashSet<_>>();
+        let mut reduced_variables = Vec::new();
+        for derived in candidates {
+            let users = func.users_of(derived.value);
+            // value from previous iteration can not be replaced by phi outside the loop.
+            if users
+                .iter()
+                .any(|user| !natural_loop.blocks.contains(&func.get_block_of_inst(*user)))
+            {
+                continue;
+            }
+            if users.iter().all(|user| {
+                func.get_inst_result(*user)
+                    .is_some_and(|result| candidate_values.contains(&result))
+            }) {
+                continue;
+            }
+            reduced_variables.push(self.reduce(func, natural_loop, preheader, &induction_variables, derived));
+            self.reduced_count += 1;
+        }
+        if reduced_variables.is_empty() {
+            return;
+        }
+        if self.replace_exit_condition(func, preheader, &induction_variables, &reduced_variables) {
+            self.replaced_exit_count += 1;
+        }
+        remove_dead_values(func, candidate_values);
+        for induction_variable in induction_variables.get_induction_variables() {
+            if remove_dead_induction_variable(func, induction_variable.phi, induction_variable.update) {
+                self.eliminated_count += 1;
+            }
+        }
+    }
+    /// Replace derived induction variable by a new phi in header.
+    fn reduce(
+        &self,
+        func: &mut Function,
+        natural_loop: &NaturalLoop,
+        preheader: Block,
+        induction_variables: &LoopInductionVariables,
+        derived: DerivedInductionVariable,
+    ) -> ReducedVariable {
+        let induction_variable = induction_variables.get_induction_variable(derived.basic).unwrap();
+        let ty = func.value_type(derived.basic).clone();
+        let before = func.layout.get_last_inst(preheader);
+        // init is `scale * init + base + offset`, folded when init is constant.
+        let init = match func.get_constant_value(induction_variable.init) {
+            Some(init) => {
+                let init = RuntimeValue::binary(OpCode::Mul, derived.scale, init).unwrap();
+                let init = RuntimeValue::binary(OpCode::Add, init, derived.offset).unwrap();
+                insert_const_before(func, init, &ty, before)
+            }
+            None => {
+                let scale = insert_const_before(func, derived.scale, &ty, before);
+                let mut init = insert_binary_before(func, OpCode::Mul, [induction_variable.init, scale], &ty, before);
+                if !is_zero(&derived.offset) {
+                    let offset = insert_const_before(func, derived.offset, &ty, before);
+                    init = insert_binary_before(func, OpCode::Add, [init, offset], &ty, before);
+                }
+                init
+            }
+        };
+        let init = match derived.base {
+            Some(base) => insert_binary_before(func, OpCode::Add, [init, base], &ty, before),
+            None => init,
+        };
+        let step = RuntimeValue::binary(OpCode::Mul, derived.scale, induction_variable.step).unwrap();
+        let step = insert_const_before(func, step, &ty, before);
+        // phi of reduced value, incoming from latch is filled after next is created.
+        let phi_inst = func.entities.create_inst(InstructionData::Phi {
+            opcode: OpCode::Phi,
+            from: vec![(preheader, init)],
+        });
+        let phi = func.entities.create_value(ValueData::Inst {
+            inst: phi_inst,
+            ty: ty.clone(),
+        });
+        func.entities.mark_inst_result(phi, phi_inst);
+        func.entities.mark_phi_block(phi_inst, natural_loop.header);
+        func.unshift_inst(phi_inst, natural_loop.header);
+        let update_inst = get_inst_of_value(func, induction_variable.update).unwrap();
+        let next = insert_binary_after(func, OpCode::Add, [phi, step], &ty, update_inst);
+        if let InstructionData::Phi { from, .. } = func.get_inst_data_mut(phi_inst) {
+            from.push((natural_loop.tail, next));
+        }
+        func.replace_all_uses_with(derived.value, phi);
+        ReducedVariable { derived, phi, next }
+    }
+    /// Rewrite exit comparison of basic induction variable to `eq` or `noteq` of reduced
+    /// induction variable and its value when loop exit, only when trip count is known
+    /// and the value is different in every previous iteration.
+    fn replace_exit_condition(
+        &self,
+        func: &mut Function,
+        preheader: Block,
+        induction_variables: &LoopInductionVariables,
+        reduced_variables: &[ReducedVariable],
+    ) -> bool {
+        let (Some(exit_condition), Some(trip_count)) = (
+            induction_variables.get_exit_condition(),
+            induction_variables.get_trip_count(),
+        ) else {
+            return false;
+        };
+        let Some(reduced) = reduced_variables
+            .iter()
+            .find(|reduced| reduced.derived.basic == exit_condition.induction_variable)
+        else {
+            return false;
+        };
+        let InstructionData::BrIf { test, .. } =
+            func.get_inst_data(func.layout.get_last_inst(exit_condition.exiting_block))
+        else {
+            return false;
+        };
+        let test = *test;
+        let Some(compare_inst) = get_inst_of_value(func, test) else {
+            return false;
+        };
+        if !matches!(func.get_inst_data(compare_inst), InstructionData::Icmp { .. }) || func.users_of(test).len() != 1 {
+            return false;
+        }
+        let induction_variable = induction_variables
+            .get_induction_variable(exit_condition.induction_variable)
+            .unwrap();
+        let Some(init) = func.get_constant_value(induction_variable.init) else {
+            return false;
+        };
+        let Some(exit_value) = get_exit_value(
+            &reduced.derived,
+            exit_condition,
+            init,
+            induction_variable.step,
+            trip_count,
+        ) else {
+            return false;
+        };
+        let ty = func.value_type(reduced.phi).clone();
+        let before = func.layout.get_last_inst(preheader);
+        let mut bound = insert_const_before(func, exit_value, &ty, before);
+        if let Some(base) = reduced.derived.base {
+            bound = insert_binary_before(func, OpCode::Add, [bound, base], &ty, before);
+        }
+        let tested = if exit_condition.use_update {
+            reduced.next
+        } else {
+            reduced.phi
+        };
+        func.replace_inst(
+            compare_inst,
+            InstructionData::Icmp {
+                opcode: OpCode::Icmp,
+                flag: if exit_condition.exit_when {
+                    CmpFlag::Eq
+                } else {
+                    CmpFlag::NotEq
+                },
+                args: [tested, bound],
+            },
+        );
+        true
+    }
+}
+
+/// Get `scale * tested + offset` of the last iteration, which is none if the same value
+/// is tested in previous iterations.
+fn get_exit_value(
+    derived: &DerivedInductionVariable,
+    exit_condition: &ExitCondition,
+    init: RuntimeValue,
+    step: RuntimeValue,
+    trip_count: u64,
+) -> Option<RuntimeValue> {
+    let mut value = init;
+    let mut tested_values = Vec::new();
+    for _ in 0..trip_count {
+        let next = RuntimeValue::binary(OpCode::Add, value, step).ok()?;
+        let tested = if exit_condition.use_update { next } else { value };
+        let tested = RuntimeValue::binary(OpCode::Mul, derived.scale, tested).ok()?;
+        tested_values.push(RuntimeValue::binary(OpCode::Add, tested, derived.offset).ok()?);
+        value = next;
+    }
+    let exit_value = tested_values.pop()?;
+    if tested_values.contains(&exit_value) {
+        return None;
+    }
+    Some(exit_value)
+}
+
+/// Remove derived induction variables which have no use, until no more can be removed.
+fn remove_dead_values(func: &mut Function, mut values: HashSet<Value>) {
+    loop {
+        let dead_values = values
+            .iter()
+            .filter(|value| func.has_no_uses(**value))
+            .copied()
+            .collect::<Vec<_>>();
+        if dead_values.is_empty() {
+            break;
+        }
+        for value in dead_values {
+            values.remove(&value);
+            if let Some(inst) = get_inst_of_value(func, value) {
+                func.remove_inst(inst);
+            }
+        }
+    }
+}
+
+/// Remove basic induction variable if phi and its update are only used by each other.
+fn remove_dead_induction_variable(func: &mut Function, phi: Value, update: Value) -> bool {
+    let (Some(phi_inst), Some(update_inst)) = (get_inst_of_value(func, phi), get_inst_of_value(func, update)) else {
+        return false;
+    };
+    if func.users_of(phi).iter().any(|user| *user != update_inst)
+        || func.users_of(update).iter().any(|user| *user != phi_inst)
+    {
+        return false;
+    }
+    func.remove_inst(update_inst);
+    func.remove_inst(phi_inst);
+    true
+}
+
+fn get_inst_of_value(func: &Function, value: Value) -> Option<Instruction> {
+    match func.get_value_data(value) {
+        ValueData::Inst { inst, .. } => Some(*inst),
+        ValueData::Param { .. } => None,
+    }
+}
+
+fn insert_const_before(func: &mut Function, value: RuntimeValue, ty: &ValueType, before: Instruction) -> Value {
+    let opcode = match value {
+        RuntimeValue::I16(_) | RuntimeValue::I32(_) | RuntimeValue::I64(_) => OpCode::Iconst,
+        _ => OpCode::Uconst,
+    };
+    let constant = func.create_constant(ConstantData {
+        bytes: value.to_bytes(),
+    });
+    let inst = create_inst_with_result(
+        func,
+        InstructionData::UnaryConst { opcode, constant },
+        ty,
+        func.get_block_of_inst(before),
+    );
+    func.insert_inst_before(inst, before);
+    func.get_inst_result(inst).unwrap()
+}
+
+fn insert_binary_before(
+    func: &mut Function,
+    opcode: OpCode,
+    args: [Value; 2],
+    ty: &ValueType,
+    before: Instruction,
+) -> Value {
+    let block = func.get_block_of_inst(before);
+    let inst = create_inst_with_result(func, InstructionData::Binary { opcode, args }, ty, block);
+    func.insert_inst_before(inst, before);
+    func.get_inst_result(inst).unwrap()
+}
+
+fn insert_binary_after(
+    func: &mut Function,
+    opcode: OpCode,
+    args: [Value; 2],
+    ty: &ValueType,
+    after: Instruction,
+) -> Value {
+    let block = func.get_block_of_inst(after);
+    let inst = create_inst_with_result(func, InstructionData::Binary { opcode, args }, ty, block);
+    func.insert_inst_after(inst, after);
+    func.get_inst_result(inst).unwrap()
+}
+
+fn create_inst_with_result(
+    func: &mut Function,
+    inst_data: InstructionData,
+    ty: &ValueType,
+    block: Block,
+) -> Instruction {
+    let inst = func.entities.create_inst(inst_data);
+    let value = func.entities.create_value(ValueData::Inst { inst, ty: ty.clone() });
+    func.entities.mark_inst_result(value, inst);
+    func.entities.mark_inst_block(inst, block);
+    inst
+}
+
+fn is_zero(value: &RuntimeValue) -> bool {
+    value.as_u64() == Some(0)
+}
+
+fn is_one(value: &RuntimeValue) -> bool {
+    value.as_u64() == Some(1)
+}
@@ -0,0 +1,575 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::entities::block::{Block, BlockData};
+use crate::entities::function::Function;
+use crate::entities::instruction::opcode::OpCode;
+use crate::entities::instruction::{Instruction, InstructionData};
+use crate::entities::value::{Value, ValueData};
+use crate::pass::analysis::cfg::{cfg_anylysis, ControlFlowGraph};
+use crate::pass::analysis::domtree::domtree_analysis;
+use crate::pass::analysis::induction_variable::induction_variable_analysis;
+use crate::pass::get_sorted_blocks;
+use crate::pass::opt::licm::natural_loop::{natural_loop_analysis, NaturalLoop};
+use crate::pass::OptiPass;
+
+/// Default number of iterations in loop body after partial unrolling.
+pub const DEFAULT_UNROLL_FACTOR: usize = 2;
+/// Default max size of fully unrolled loop, which is trip count times loop size.
+pub const DEFAULT_FULL_UNROLL_THRESHOLD: usize = 64;
+
+pub fn loop_unroll_pass(func: &mut Function, config: UnrollConfig) {
+    let mut pass = LoopUnrollPass::new(config);
+    pass.process(func);
+}
+
+/// Config of loop unroller, loop with constant trip count is fully unrolled when trip
+/// count times size of loop is not greater than `full_unroll_threshold`, other loops
+/// are unrolled by `factor`, factor less than 2 disable partial unrolling.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnrollConfig {
+    pub factor: usize,
+    pub full_unroll_threshold: usize,
+}
+
+impl Default for UnrollConfig {
+    fn default() -> Self {
+        Self {
+            factor: DEFAULT_UNROLL_FACTOR,
+            full_unroll_threshold: DEFAULT_FULL_UNROLL_THRESHOLD,
+        }
+    }
+}
+
+impl UnrollConfig {
+    pub fn new(factor: usize, full_unroll_threshold: usize) -> Self {
+        Self {
+            factor,
+            full_unroll_threshold,
+        }
+    }
+}
+
+/// Size of loop, which is number of instructions except phi and comment.
+pub fn loop_size(func: &Function, natural_loop: &NaturalLoop) -> usize {
+    natural_loop
+        .blocks
+        .iter()
+        .flat_map(|block| func.get_insts_of_block(*block))
+        .filter(|inst| {
+            !matches!(
+                func.get_inst_data(*inst),
+                InstructionData::Phi { .. } | InstructionData::Comment(_)
+            )
+        })
+        .count()
+}
+
+/// ## Loop Unroll Pass
+/// Unroll innermost loops which have a single back edge, loops are visited by order of
+/// header, control flow and loops are recomputed after every unrolled loop.
+/// - full unroll: when trip count is known by induction variable analysis, loop body is
+///   copied trip count times, exit branch of every copy is folded to jump, so only the
+///   last copy exits and there is no back edge any more.
+/// - partial unroll: loop body is copied `factor` times, back edge of a copy jumps to
+///   header of next copy and the last copy jumps back to header, every copy keeps its
+///   exit branch. Value of loop used outside the loop is replaced by phi inserted in
+///   exit block, so loop is not partially unrolled when it has such value but more
+///   than one exit block or exit block has predecessor outside the loop.
+///
+/// Phi in header of copy is replaced by value from back edge of previous copy, phi in
+/// exit block get incoming from every copy. Finally blocks become unreachable are
+/// removed and phi which has only one incoming is replaced by its value.
+pub struct LoopUnrollPass {
+    config: UnrollConfig,
+    visited_headers: HashSet<Block>,
+    fully_unrolled_count: usize,
+    partially_unrolled_count: usize,
+}
+
+impl OptiPass for LoopUnrollPass {
+    fn process(&mut self, func: &mut Function) {
+        if func.first_block().is_none() {
+            return;
+        }
+        while let Some((cfg, natural_loop)) = self.find_unrollable_loop(func) {
+            self.visited_headers.insert(natural_loop.header);
+            if self.unroll_loop(func, &cfg, &natural_loop) {
+                remove_unreachable_blocks(func);
+                remove_trivial_phis(func);
+            }
+        }
+    }
+}
+
+/// Blocks and values of a copy of loop body, copy 0 is the original loop.
+struct LoopCopy {
+    blocks: HashMap<Block, Block>,
+    values: HashMap<Value, Value>,
+}
+
+impl LoopCopy {
+    fn get_block(&self, block: Block) -> Block {
+        self.blocks.get(&block).copied().unwrap_or(block)
+    }
+    fn get_value(&self, value: Value) -> Value {
+        self.values.get(&value).copied().unwrap_or(value)
+    }
+}
+
+impl LoopUnrollPass {
+    pub fn new(config: UnrollConfig) -> Self {
+        Self {
+            config,
+            visited_headers: Default::default(),
+            fully_unrolled_count: 0,
+            partially_unrolled_count: 0,
+        }
+    }
+    pub fn get_fully_unrolled_count(&self) -> usize {
+        self.fully_unrolled_count
+    }
+    pub fn get_partially_unrolled_count(&self) -> usize {
+        self.partially_unrolled_count
+    }
+    /// Find first innermost loop which is not visited and has single back edge.
+    fn find_unrollable_loop(&self, func: &Function) -> Option<(ControlFlowGraph, NaturalLoop)> {
+        let cfg = cfg_anylysis(func);
+        let dom = domtree_analysis(func, &cfg);
+        let mut natural_loops = natural_loop_analysis(&dom, &cfg);
+        natural_loops.sort_by_key(|natural_loop| natural_loop.header.0);
+        let natural_loop = natural_loops.iter().find(|natural_loop| {
+            !self.visited_headers.contains(&natural_loop.header)
+                && natural_loops.iter().all(|other| {
+                    if other.header == natural_loop.header {
+                        other.tail == natural_loop.tail
+                    } else {
+                        !natural_loop.blocks.contains(&other.header)
+                    }
+                })
+        })?;
+        Some((cfg.clone(), natural_loop.clone()))
+    }
+    /// Unroll loop fully or partially, return false if loop is not changed.
+    fn unroll_loop(&mut self, func: &mut Function, cfg: &ControlFlowGraph, natural_loop: &NaturalLoop) -> bool {
+        let size = loop_size(func, natural_loop);
+        let outside_values = get_outside_used_values(func, natural_loop);
+        let outside_blocks = get_layout_blocks(func)
+            .into_iter()
+            .filter(|block| !natural_loop.blocks.contains(block))
+            .collect::<Vec<_>>();
+        let induction_variables = induction_variable_analysis(func, cfg, natural_loop);
+        if let (Some(trip_count), Some(exit_condition)) = (
+            induction_variables.get_trip_count(),
+            induction_variables.get_exit_condition(),
+        ) {
+            if (trip_count as usize).saturating_mul(size) <= self.config.full_unroll_threshold {
+                let copies = self.copy_loop(func, cfg, natural_loop, trip_count as usize);
+                for (index, copy) in copies.iter().enumerate() {
+                    fold_exit_branch(
+                        func,
+                        copy.get_block(exit_condition.exiting_block),
+                        exit_condition.exit_target,
+                        index + 1 == copies.len(),
+                    );
+                }
+                let last = copies.last().unwrap();
+                let mapping = outside_values
+                    .iter()
+                    .map(|value| (*value, last.get_value(*value)))
+                    .collect();
+                replace_outside_uses(func, natural_loop, &outside_blocks, &mapping);
+                self.fully_unrolled_count += 1;
+                return true;
+            }
+        }
+        if self.config.factor < 2 {
+            return false;
+        }
+        let exit_block = get_dedicated_exit(cfg, natural_loop);
+        if !outside_values.is_empty() && exit_block.is_none() {
+            return false;
+        }
+        let copies = self.copy_loop(func, cfg, natural_loop, self.config.factor);
+        if let Some(exit_block) = exit_block {
+            let mapping = insert_exit_phis(func, cfg, &copies, exit_block, &outside_values);
+            replace_outside_uses(func, natural_loop, &outside_blocks, &mapping);
+        }
+        self.partially_unrolled_count += 1;
+        true
+    }
+    /// Copy loop body until there are `count` copies including the original loop, and
+    /// chain copies by back edge.
+    fn copy_loop(
+        &self,
+        func: &mut Function,
+        cfg: &ControlFlowGraph,
+        natural_loop: &NaturalLoop,
+        count: usize,
+    ) -> Vec<LoopCopy> {
+        let loop_blocks = get_layout_blocks(func)
+            .into_iter()
+            .filter(|block| natural_loop.blocks.contains(block))
+            .collect::<Vec<_>>();
+        let mut copies = vec![LoopCopy {
+            blocks: HashMap::new(),
+            values: HashMap::new(),
+        }];
+        let mut after = *loop_blocks.last().unwrap();
+        for _ in 1..count {
+            let mut blocks = HashMap::new();
+            for block in &loop_blocks {
+                after = func.create_and_insert_block_after(BlockData::new(), after);
+                blocks.insert(*block, after);
+            }
+            copies.push(LoopCopy {
+                blocks,
+                values: HashMap::new(),
+            });
+        }
+        for index in 1..count {
+            let cloned_insts = self.clone_insts(func, natural_loop, &loop_blocks, &mut copies, index);
+            let next_header = copies
+                .get(index + 1)
+                .map_or(natural_loop.header, |next| next.get_block(natural_loop.header));
+            remap_insts(func, natural_loop, &copies[index], &cloned_insts, next_header);
+        }
+        if count > 1 {
+            let first_copy_header = copies[1].get_block(natural_loop.header);
+            retarget_back_edge(func, natural_loop, first_copy_header);
+            let last = copies.last().unwrap();
+            let last_tail = last.get_block(natural_loop.tail);
+            for inst in func.get_insts_of_block(natural_loop.header) {
+                if let InstructionData::Phi { from, .. } = func.get_inst_data_mut(inst) {
+                    for (from_block, value) in from.iter_mut() {
+                        if *from_block == natural_loop.tail {
+                            *from_block = last_tail;
+                            *value = last.get_value(*value);
+                        }
+                    }
+                }
+            }
+        }
+        add_exit_phi_incomings(func, cfg, natural_loop, &copies);
+        copies
+    }
+    /// Clone instructions of loop into copy, phi in header of copy is not cloned but
+    /// mapped to value from back edge of previous copy.
+    fn clone_insts(
+        &self,
+        func: &mut Function,
+        natural_loop: &NaturalLoop,
+        loop_blocks: &[Block],
+        copies: &mut [LoopCopy],
+        index: usize,
+    ) -> Vec<Instruction> {
+        let mut cloned_insts = Vec::new();
+        let mut values = HashMap::new();
+        for block in loop_blocks {
+            let cloned_block = copies[index].get_block(*block);
+            for inst in func.get_insts_of_block(*block) {
+                let inst_data = func.get_inst_data(inst).clone();
+                let result = func.get_inst_result(inst);
+                if let InstructionData::Phi { from, .. } = &inst_data {
+                    if *block == natural_loop.header {
+                        let (_, value) = from
+                            .iter()
+                            .find(|(from_block, _)| *from_block == natural_loop.tail)
+                            .unwrap();
+                        values.insert(result.unwrap(), copies[index - 1].get_value(*value));
+                        continue;
+                    }
+                }
+                let is_phi = matches!(inst_data, InstructionData::Phi { .. });
+                let cloned = func.entities.create_inst(inst_data);
+                if is_phi {
+                    func.entities.mark_phi_block(cloned, cloned_block);
+                } else {
+                    func.entities.mark_inst_block(cloned, cloned_block);
+                }
+                func.append_inst(cloned, cloned_block);
+                if let Some(result) = result {
+                    let ty = func.value_type(result).clone();
+                    let cloned_result = func.entities.create_value(ValueData::Inst { inst: cloned, ty });
+                    func.entities.mark_inst_result(cloned_result, cloned);
+                    values.insert(result, cloned_result);
+                }
+                cloned_insts.push(cloned);
+            }
+        }
+        copies[index].values = values;
+        cloned_insts
+    }
+}
+
+/// Remap operands and blocks of cloned instructions, back edge jumps to `next_header`.
+fn remap_insts(
+    func: &mut Function,
+    natural_loop: &NaturalLoop,
+    copy: &LoopCopy,
+    cloned_insts: &[Instruction],
+    next_header: Block,
+) {
+    let remap_block = |block: &mut Block| {
+        *block = if *block == natural_loop.header {
+            next_header
+        } else {
+            copy.get_block(*block)
+        };
+    };
+    for inst in cloned_insts {
+        let inst_data = func.get_inst_data_mut(*inst);
+        for operand in inst_data.get_operands_mut() {
+            *operand = copy.get_value(*operand);
+        }
+        match inst_data {
+            InstructionData::BrIf { conseq, alter, .. } => {
+                remap_block(conseq);
+                remap_block(alter);
+            }
+            InstructionData::Jump { dst, .. } => remap_block(dst),
+            InstructionData::Phi { from, .. } => {
+                for (from_block, _) in from.iter_mut() {
+                    *from_block = copy.get_block(*from_block);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Retarget back edge of original loop to header of first copy.
+fn retarget_back_edge(func: &mut Function, natural_loop: &NaturalLoop, first_copy_header: Block) {
+    let last_inst = func.layout.get_last_inst(natural_loop.tail);
+    let retarget = |block: &mut Block| {
+        if *block == natural_loop.header {
+            *block = first_copy_header;
+        }
+    };
+    match func.get_inst_data_mut(last_inst) {
+        InstructionData::BrIf { conseq, alter, .. } => {
+            retarget(conseq);
+            retarget(alter);
+        }
+        InstructionData::Jump { dst, .. } => retarget(dst),
+        _ => {}
+    }
+}
+
+/// Phi in exit block which has incoming from loop get incoming from every copy.
+fn add_exit_phi_incomings(
+    func: &mut Function,
+    cfg: &ControlFlowGraph,
+    natural_loop: &NaturalLoop,
+    copies: &[LoopCopy],
+) {
+    let mut exit_blocks = natural_loop
+        .blocks
+        .iter()
+        .flat_map(|block| cfg.get_successors(block))
+        .filter(|successor| !natural_loop.blocks.contains(successor))
+        .copied()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    exit_blocks.sort_by_key(|block| block.0);
+    for exit_block in exit_blocks {
+        for inst in func.get_insts_of_block(exit_block) {
+            let InstructionData::Phi { from, .. } = func.get_inst_data_mut(inst) else {
+                continue;
+            };
+            let mut incomings = Vec::new();
+            for (from_block, value) in from.iter() {
+                if natural_loop.blocks.contains(from_block) {
+                    for copy in &copies[1..] {
+                        incomings.push((copy.get_block(*from_block), copy.get_value(*value)));
+                    }
+                }
+            }
+            from.extend(incomings);
+        }
+    }
+}
+
+/// Replace exit branch of exiting block by jump, jump to exit target only if `is_last`.
+fn fold_exit_branch(func: &mut Function, exiting_block: Block, exit_target: Block, is_last: bool) {
+    let last_inst = func.layout.get_last_inst(exiting_block);
+    let InstructionData::BrIf { conseq, alter, .. } = func.get_inst_data(last_inst) else {
+        return;
+    };
+    let stay_target = if *conseq == exit_target { *alter } else { *conseq };
+    func.replace_inst(
+        last_inst,
+        InstructionData::Jump {
+            opcode: OpCode::Jump,
+            dst: if is_last { exit_target } else { stay_target },
+        },
+    );
+}
+
+fn is_defined_in_loop(func: &Function, natural_loop: &NaturalLoop, value: Value) -> bool {
+    match func.get_value_data(value) {
+        ValueData::Inst { inst, .. } => {
+            func.layout.insts.contains_key(inst) && natural_loop.blocks.contains(&func.get_block_of_inst(*inst))
+        }
+        ValueData::Param { .. } => false,
+    }
+}
+
+/// Get values defined in loop which are used outside the loop, use by phi of exit block
+/// from loop is not counted since it get incoming from every copy.
+fn get_outside_used_values(func: &Function, natural_loop: &NaturalLoop) -> Vec<Value> {
+    let mut values = Vec::new();
+    for block in get_layout_blocks(func) {
+        if natural_loop.blocks.contains(&block) {
+            continue;
+        }
+        for inst in func.get_insts_of_block(block) {
+            let operands = match func.get_inst_data(inst) {
+                InstructionData::Phi { from, .. } => from
+                    .iter()
+                    .filter(|(from_block, _)| !natural_loop.blocks.contains(from_block))
+                    .map(|(_, value)| *value)
+                    .collect(),
+                inst_data => inst_data.get_operands(),
+            };
+            for operand in operands {
+                if is_defined_in_loop(func, natural_loop, operand) && !values.contains(&operand) {
+                    values.push(operand);
+                }
+            }
+        }
+    }
+    values.sort_by_key(|value| value.0);
+    values
+}
+
+/// Replace use of value defined in loop by instructions in `blocks` with mapped value.
+fn replace_outside_uses(
+    func: &mut Function,
+    natural_loop: &NaturalLoop,
+    blocks: &[Block],
+    mapping: &HashMap<Value, Value>,
+) {
+    for block in blocks {
+        for inst in func.get_insts_of_block(*block) {
+            match func.get_inst_data_mut(inst) {
+                InstructionData::Phi { from, .. } => {
+                    for (from_block, value) in from.iter_mut() {
+                        if !natural_loop.blocks.contains(from_block) {
+                            *value = mapping.get(value).copied().unwrap_or(*value);
+                        }
+                    }
+                }
+                inst_data => {
+                    for operand in inst_data.get_operands_mut() {
+                        *operand = mapping.get(operand).copied().unwrap_or(*operand);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Get exit block of loop if it is the only block outside the loop which is successor
+/// of loop, and all of its predecessors are in the loop.
+fn get_dedicated_exit(cfg: &ControlFlowGraph, natural_loop: &NaturalLoop) -> Option<Block> {
+    let mut exit_blocks = natural_loop
+        .blocks
+        .iter()
+        .flat_map(|block| cfg.get_successors(block))
+        .filter(|successor| !natural_loop.blocks.contains(successor));
+    let exit_block = *exit_blocks.next()?;
+    if exit_blocks.any(|other| *other != exit_block) {
+        return None;
+    }
+    cfg.get_predecessors(&exit_block)
+        .iter()
+        .all(|predecessor| natural_loop.blocks.contains(predecessor))
+        .then_some(exit_block)
+}
+
+/// Insert phi in exit block for every value of loop used outside the loop, which get
+/// value from every exiting block of every copy.
+fn insert_exit_phis(
+    func: &mut Function,
+    cfg: &ControlFlowGraph,
+    copies: &[LoopCopy],
+    exit_block: Block,
+    values: &[Value],
+) -> HashMap<Value, Value> {
+    let mut predecessors = cfg.get_predecessors(&exit_block).iter().copied().collect::<Vec<_>>();
+    predecessors.sort_by_key(|block| block.0);
+    let mut mapping = HashMap::new();
+    for value in values.iter().rev() {
+        let from = copies
+            .iter()
+            .flat_map(|copy| {
+                predecessors
+                    .iter()
+                    .map(|predecessor| (copy.get_block(*predecessor), copy.get_value(*value)))
+            })
+            .collect();
+        let inst = func.entities.create_inst(InstructionData::Phi {
+            opcode: OpCode::Phi,
+            from,
+        });
+        func.entities.mark_phi_block(inst, exit_block);
+        func.unshift_inst(inst, exit_block);
+        let ty = func.value_type(*value).clone();
+        let result = func.entities.create_value(ValueData::Inst { inst, ty });
+        func.entities.mark_inst_result(result, inst);
+        mapping.insert(*value, result);
+    }
+    mapping
+}
+
+/// Get blocks of function in layout order.
+fn get_layout_blocks(func: &Function) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut cur_block = func.layout.first_block;
+    while let Some(block) = cur_block {
+        blocks.push(block);
+        cur_block = func.layout.blocks.get(&block).unwrap().next;
+    }
+    blocks
+}
+
+/// Remove blocks which can not be reached from entry, and incoming of phi from
+/// block which is not predecessor any more.
+fn remove_unreachable_blocks(func: &mut Function) {
+    let reachable = cfg_anylysis(func).get_reachable_blocks();
+    for block in func.blocks() {
+        if !reachable.contains(&block) {
+            func.remove_block(block);
+        }
+    }
+    let cfg = cfg_anylysis(func);
+    for block in get_sorted_blocks(func) {
+        let predecessors = cfg.get_predecessors(&block);
+        for inst in func.get_insts_of_block(block) {
+            if let InstructionData::Phi { from, .. } = func.get_inst_data_mut(inst) {
+                from.retain(|(from_block, _)| predecessors.contains(from_block));
+            }
+        }
+    }
+}
+
+/// Replace phi which has only one incoming value by the value.
+fn remove_trivial_phis(func: &mut Function) {
+    for block in get_sorted_blocks(func) {
+        for inst in func.get_insts_of_block(block) {
+            let InstructionData::Phi { from, .. } = func.get_inst_data(inst) else {
+                continue;
+            };
+            let result = func.get_inst_result(inst).unwrap();
+            let mut values = from.iter().map(|(_, value)| *value).filter(|value| *value != result);
+            let Some(value) = values.next() else {
+                continue;
+            };
+            if values.all(|other| other == value) {
+                func.replace_all_uses_with(result, value);
+                func.remove_inst(inst);
+            }
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use zsh_ir::entities::function::Function;
+use zsh_ir::entities::instruction::{Instruction, InstructionData};
+use zsh_ir::entities::module::Module;
+use zsh_ir::frontend::parse;
+use zsh_ir::pass::analysis::alias::{alias_analysis, AddressOrigin, AliasAnalysis, AliasResult, MemoryLocation};
+use zsh_ir::pass::analysis::cfg::cfg_anylysis;
+use zsh_ir::pass::analysis::rpo::revrese_post_order_analysis;
+use zsh_ir::pass::opt::dse::dse_pass;
+use zsh_ir::pass::FormatTable;
+
+const SOURCE: &str = "data_a = @data { size 16, align 8, mut }
+data_b = @data { size 16, align 8, mut }
+func escape (reg0: u64) {
+block0:
+  ret
+}
+func alias (reg0: u64, reg1: u64, reg2: u64): u64 {
+  greg0 = @global symbol data_a
+  greg1 = @global symbol data_b
+  greg2 = @global u64, addi [greg0, 8]
+  greg3 = @global u64, load [greg1, 0]
+  greg4 = @global symbol data_a
+block0:
+  reg3 = stackalloc u64, size 16, align 8
+  reg4 = stackalloc u64, size 16, align 8
+  reg5 = addi reg3 8
+  reg6 = mov reg4
+  store reg0 [reg3, 0]
+  store reg0 [reg5, 0]
+  reg7 = load u32 [reg3, 12]
+  store reg1 [reg6, 4]
+  call func escape(reg4)
+  reg8 = load u64 [reg0, 0]
+  reg9 = load u64 [reg1, 8]
+  gstore reg2 [greg0, 8]
+  reg10 = gload u64 [greg2, 0]
+  reg11 = gload u64 [greg4, 8]
+  reg12 = gload u64 [greg1, 0]
+  reg13 = gload u64 [greg3, 0]
+  reg14 = gload u64 [greg3, 0]
+  reg15 = to.addr reg2
+  reg16 = load u64 [reg15, 0]
+  reg17 = load u64 [reg15, 0]
+  ret reg16
+}
+";
+
+/// Memory instructions of function in layout order, with alias analysis of function.
+fn analyze<'a>(module: &'a Module, func_name: &str) -> (&'a Function, Vec<Instruction>, AliasAnalysis) {
+    let func_id = module.get_module_id_by_symbol(func_name).unwrap().to_func_id();
+    let func = module.get_function(func_id).unwrap();
+    let memory_insts = func
+        .get_insts_of_block(func.first_block().unwrap())
+        .into_iter()
+        .filter(|inst| MemoryLocation::of_inst(func, *inst).is_some())
+        .collect::<Vec<_>>();
+    (func, memory_insts, alias_analysis(func))
+}
+
+#[test]
+fn alias_of_stack_slots() {
+    let module = parse(SOURCE).unwrap();
+    let (func, insts, alias) = analyze(&module, "alias");
+    let query = |lhs: usize, rhs: usize| alias.alias_insts(func, insts[lhs], insts[rhs]).unwrap();
+    // [reg3, 0] and [reg3 + 8, 0] do not overlap, [reg3 + 12] size 4 is inside of [reg3 + 8].
+    assert_eq!(query(0, 1), AliasResult::NoAlias);
+    assert_eq!(query(1, 2), AliasResult::MayAlias);
+    assert_eq!(query(0, 2), AliasResult::NoAlias);
+    // different slots.
+    assert_eq!(query(0, 3), AliasResult::NoAlias);
+    // pointer of caller can not point to slot.
+    assert_eq!(query(3, 4), AliasResult::NoAlias);
+    assert_eq!(query(0, 4), AliasResult::NoAlias);
+    assert!(!alias.is_escaped(
+        func.get_inst_result(func.get_insts_of_block(func.first_block().unwrap())[0])
+            .unwrap()
+    ));
+    assert!(alias.is_escaped(
+        func.get_inst_result(func.get_insts_of_block(func.first_block().unwrap())[1])
+            .unwrap()
+    ));
+}
+
+#[test]
+fn alias_of_params_and_globals() {
+    let module = parse(SOURCE).unwrap();
+    let (func, insts, alias) = analyze(&module, "alias");
+    let query = |lhs: usize, rhs: usize| alias.alias_insts(func, insts[lhs], insts[rhs]).unwrap();
+    // params may point to same object.
+    assert_eq!(query(4, 5), AliasResult::MayAlias);
+    assert_eq!(query(4, 6), AliasResult::MayAlias);
+    // `greg2 + 0` is `data_a + 8`, `greg4` declare `data_a` again.
+    assert_eq!(query(6, 7), AliasResult::MustAlias);
+    assert_eq!(query(6, 8), AliasResult::MustAlias);
+    assert_eq!(query(6, 9), AliasResult::NoAlias);
+    // pointer loaded from `data_b` may point to anywhere, even itself.
+    assert_eq!(query(9, 10), AliasResult::MayAlias);
+    assert_eq!(query(10, 11), AliasResult::MayAlias);
+    assert_eq!(query(6, 10), AliasResult::MayAlias);
+    // same integer converted to address.
+    assert_eq!(query(12, 13), AliasResult::MustAlias);
+    assert_eq!(query(3, 12), AliasResult::MayAlias);
+    assert_eq!(query(0, 12), AliasResult::NoAlias);
+}
+
+#[test]
+fn call_clobber_escaped_slot_only() {
+    let module = parse(SOURCE).unwrap();
+    let (func, insts, alias) = analyze(&module, "alias");
+    let call = func
+        .get_insts_of_block(func.first_block().unwrap())
+        .into_iter()
+        .find(|inst| matches!(func.get_inst_data(*inst), InstructionData::Call { .. }))
+        .unwrap();
+    let location = |index: usize| MemoryLocation::of_inst(func, insts[index]).unwrap();
+    assert!(!alias.call_clobbers(func, call, &location(0)));
+    assert!(alias.call_clobbers(func, call, &location(3)));
+    assert!(alias.call_clobbers(func, call, &location(4)));
+    assert!(alias.call_clobbers(func, call, &location(6)));
+    assert!(!alias.call_clobbers(func, insts[0], &location(3)));
+    assert_eq!(
+        alias.get_pointer(location(1).base).origin,
+        AddressOrigin::StackSlot(
+            func.get_inst_result(func.get_insts_of_block(func.first_block().unwrap())[0])
+                .unwrap()
+        )
+    );
+}
+
+#[test]
+fn format_alias_table() {
+    let module = parse(SOURCE).unwrap();
+    let (func, _, alias) = analyze(&module, "alias");
+    assert_eq!(
+        alias.format_table(func, &module),
+        "========== Alias Analysis ==========
+inst4: slot reg3 + 0, size 8
+inst5: slot reg3 + 8, size 8
+inst6: slot reg3 + 12, size 4
+inst7: escaped slot reg4 + 4, size 8
+inst9: param reg0 + 0, size 8
+inst10: param reg1 + 8, size 8
+inst11: global greg0 + 8, size 8
+inst12: global greg0 + 8, size 8
+inst13: global greg0 + 8, size 8
+inst14: global greg1 + 0, size 8
+inst15: loaded greg3 + 0, size 8
+inst16: loaded greg3 + 0, size 8
+inst18: to.address reg2 + 0, size 8
+inst19: to.address reg2 + 0, size 8
+"
+    );
+}
+
+#[test]
+fn alias_of_offset_near_overflow() {
+    let source = "func far (reg0: i64, reg1: u64): i64 {
+block0:
+  reg2 = addi reg1 9223372036854775807
+  store reg0 [reg1, 0]
+  reg3 = load i64 [reg2, 0]
+  ret reg3
+}
+";
+    let mut module = parse(source).unwrap();
+    let (func, insts, alias) = analyze(&module, "far");
+    let store = MemoryLocation::of_inst(func, insts[0]).unwrap();
+    let load = MemoryLocation::of_inst(func, insts[1]).unwrap();
+    assert_eq!(alias.alias(&store, &load), AliasResult::MayAlias);
+    assert_eq!(alias.alias(&load, &store), AliasResult::MayAlias);
+    assert!(!alias.covers(&store, &load));
+    assert!(!alias.covers(&load, &store));
+    let func_id = module.get_module_id_by_symbol("far").unwrap().to_func_id();
+    let func = module.get_mut_function(func_id).unwrap();
+    let cfg = cfg_anylysis(func);
+    let rpo = revrese_post_order_analysis(&cfg);
+    dse_pass(func, &cfg, &rpo);
+    assert_eq!(func.insts().len(), 4);
+}
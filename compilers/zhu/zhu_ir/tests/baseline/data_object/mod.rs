@@ -0,0 +1,41 @@
+use zsh_ir::builder::FunctionBuilder;
+use zsh_ir::entities::external_name::ExternalName;
+use zsh_ir::entities::global_value::GlobalValueData;
+use zsh_ir::entities::immediate::Offset;
+use zsh_ir::entities::module::{DataDescription, Module, ModuleLevelId};
+use zsh_ir::entities::r#type::ValueType;
+
+const MESSAGE_NAME: &str = "message";
+const TABLE_NAME: &str = "table";
+const FUNC_NAME: &str = "data_object";
+
+pub fn build_module() -> Module {
+    let mut module = Module::new();
+    let func_id = module.declar_function(FUNC_NAME);
+    let mut message = DataDescription::from_bytes(vec![0x68, 0x69, 0x21], 1);
+    message.size = 4;
+    let message_id = module.define_data(MESSAGE_NAME, message);
+    let mut table = DataDescription::zeroed(16, 8);
+    table.set_mutable(true);
+    table.add_relocation(
+        0,
+        ExternalName::from_module_level_id(ModuleLevelId::Data(message_id)),
+        1,
+    );
+    table.add_relocation(8, ExternalName::from_module_level_id(ModuleLevelId::Func(func_id)), 0);
+    let table_id = module.define_data(TABLE_NAME, table);
+    let table_value = module.declar_data_in_function(table_id, func_id);
+    let func_mut_ref = module.get_mut_function(func_id).unwrap();
+    func_mut_ref.set_return_type(ValueType::U8);
+    let message_value = func_mut_ref.declar_global_value(GlobalValueData::Load {
+        base: table_value,
+        offset: Offset(0),
+        ty: ValueType::U64,
+    });
+    let bb = func_mut_ref.create_block();
+    let mut builder = FunctionBuilder::new(func_mut_ref);
+    builder.switch_to_block(bb);
+    let reg0 = builder.global_load_inst(message_value, Offset(0), ValueType::U8);
+    builder.ret_inst(Some(reg0));
+    module
+}
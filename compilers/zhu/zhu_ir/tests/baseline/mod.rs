@@ -6,6 +6,7 @@ pub mod branch_inst;
 pub mod call_inst;
 pub mod cmp_inst;
 pub mod convert_inst;
+pub mod data_object;
 pub mod global_inst;
 pub mod mem_alloc_inst;
 pub mod mem_inst_base;
@@ -30,12 +30,12 @@ macro_rules! generate_test_cases {
             #[test]
             fn ${concat($test_case, _parseable)}() {
                 let source = read_file_from_case_name(stringify!($test_case));
-                parse(&source);
+                parse(&source).unwrap();
             }
             #[test]
             fn ${concat($test_case, _parse_match_formatter)}() {
                 let source = read_file_from_case_name(stringify!($test_case));
-                let module = parse(&source);
+                let module = parse(&source).unwrap();
                 let result = format(&module);
                 assert_eq!(result, source);
             }
@@ -61,6 +61,7 @@ generate_test_cases! {
     binary_inst_float,
     binary_immi_inst,
     global_inst,
+    data_object,
     mem_inst_base,
     mem_alloc_inst,
     mem_inst_struct,
@@ -72,7 +73,7 @@ generate_test_cases! {
 /// given function, we need to reset the max index of block and value.
 fn when_reg_and_block_index_is_not_continue_module_create_by_parser_can_reset_the_index_correctly() {
     let source = read_file_from_case_name("correct_module_after_parse");
-    let mut module = parse(&source);
+    let mut module = parse(&source).unwrap();
     let func_name = "correct_module_after_parse";
 
     let id = match module.get_module_id_by_symbol(func_name).unwrap() {
@@ -97,3 +98,11 @@ fn when_reg_and_block_index_is_not_continue_module_create_by_parser_can_reset_th
         .unwrap()
     )
 }
+
+#[test]
+/// Data statement without any field is still accepted, it is printed with default size and alignment.
+fn empty_data_statement_is_printed_with_default_size_and_align() {
+    let source = read_file_from_case_name("global_inst");
+    let module = parse(&source.replace("@data { size 0, align 1 }", "@data {}")).unwrap();
+    assert_eq!(format(&module), source);
+}
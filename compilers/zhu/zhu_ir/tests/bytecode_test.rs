@@ -0,0 +1,256 @@
+mod round_trip;
+
+use round_trip::{assert_module_eq, read_baseline_sources};
+use std::collections::BTreeSet;
+use std::env::current_dir;
+use std::fs::read_to_string;
+use zsh_ir::builder::FunctionBuilder;
+use zsh_ir::bytecode::{decode_module, encode_module, BytecodeError, BYTECODE_HEADER_SIZE, BYTECODE_VERSION};
+use zsh_ir::entities::instruction::opcode::OpCode;
+use zsh_ir::entities::instruction::InstructionData;
+use zsh_ir::entities::module::Module;
+use zsh_ir::entities::r#type::ValueType;
+use zsh_ir::formatter::format;
+use zsh_ir::frontend::parse;
+use zsh_ir::pass::analysis::cfg::cfg_anylysis;
+use zsh_ir::pass::opt::out_of_ssa::out_of_ssa_pass;
+use zsh_ir::pass::opt::sccp::sccp_pass;
+
+fn variant_name(inst_data: &InstructionData) -> &'static str {
+    match inst_data {
+        InstructionData::UnaryConst { .. } => "UnaryConst",
+        InstructionData::Unary { .. } => "Unary",
+        InstructionData::Binary { .. } => "Binary",
+        InstructionData::BinaryI { .. } => "BinaryI",
+        InstructionData::Move { .. } => "Move",
+        InstructionData::Icmp { .. } => "Icmp",
+        InstructionData::Fcmp { .. } => "Fcmp",
+        InstructionData::Call { .. } => "Call",
+        InstructionData::Ret { .. } => "Ret",
+        InstructionData::Convert { .. } => "Convert",
+        InstructionData::StackAlloc { .. } => "StackAlloc",
+        InstructionData::LoadRegister { .. } => "LoadRegister",
+        InstructionData::StoreRegister { .. } => "StoreRegister",
+        InstructionData::GlobalLoad { .. } => "GlobalLoad",
+        InstructionData::GlobalStore { .. } => "GlobalStore",
+        InstructionData::BrIf { .. } => "BrIf",
+        InstructionData::Jump { .. } => "Jump",
+        InstructionData::Phi { .. } => "Phi",
+        InstructionData::Comment(_) => "Comment",
+    }
+}
+
+#[test]
+fn round_trip_baseline_modules() {
+    let mut variants = BTreeSet::new();
+    let (mut text_size, mut bytecode_size) = (0, 0);
+    for (name, source) in read_baseline_sources() {
+        let module = parse(&source).unwrap();
+        for function in module.functions.values() {
+            variants.extend(function.entities.insts.values().map(variant_name));
+        }
+        let bytes = encode_module(&module);
+        let decoded = decode_module(&bytes).unwrap_or_else(|error| panic!("[Error]: case {} failed: {}", name, error));
+        assert_module_eq(&module, &decoded);
+        assert_eq!(
+            format(&decoded),
+            format(&module),
+            "[Error]: case {} changed after round trip.",
+            name
+        );
+        assert_eq!(encode_module(&decoded), bytes, "[Error]: case {} is not stable.", name);
+        text_size += format(&module).len();
+        bytecode_size += bytes.len();
+    }
+    assert_eq!(
+        variants.into_iter().collect::<Vec<_>>(),
+        vec![
+            "Binary",
+            "BinaryI",
+            "BrIf",
+            "Call",
+            "Convert",
+            "Fcmp",
+            "GlobalLoad",
+            "GlobalStore",
+            "Icmp",
+            "Jump",
+            "LoadRegister",
+            "Phi",
+            "Ret",
+            "StackAlloc",
+            "StoreRegister",
+            "Unary",
+        ]
+    );
+    // baseline cases are small, so header and symbol names take large part of them.
+    assert!(
+        bytecode_size * 2 < text_size,
+        "[Error]: bytecode has {} bytes, text has {} bytes.",
+        bytecode_size,
+        text_size
+    );
+}
+
+#[test]
+fn round_trip_variants_not_in_baseline() {
+    let mut module = Module::new();
+    let func_id = module.declar_function("build_only");
+    let func = module.get_mut_function(func_id).unwrap();
+    func.set_return_type(ValueType::I32);
+    let block0 = func.create_block();
+    let mut builder = FunctionBuilder::new(func);
+    builder.switch_to_block(block0);
+    builder.comment_inst(" comment made by builder".to_string());
+    let one = builder.iconst_inst(vec![1], ValueType::I32);
+    let copy = builder.mov_inst(one);
+    builder.ret_inst(Some(copy));
+    // move is only created by passes such as out of ssa.
+    let mov = func.get_insts_of_block(block0)[2];
+    func.replace_inst(
+        mov,
+        InstructionData::Move {
+            opcode: OpCode::Mov,
+            src: one,
+        },
+    );
+    let insts = module.get_function(func_id).unwrap().entities.insts.values();
+    assert_eq!(
+        insts.map(variant_name).collect::<BTreeSet<_>>(),
+        BTreeSet::from(["Comment", "Move", "Ret", "UnaryConst"])
+    );
+    let decoded = decode_module(&encode_module(&module)).unwrap();
+    assert_module_eq(&module, &decoded);
+}
+
+#[test]
+fn round_trip_after_optimization() {
+    // passes leave removed instructions and blocks in entities, which can not be
+    // derived from layout.
+    for name in ["out_of_ssa_swap", "sccp_branch_fold"] {
+        let path = current_dir()
+            .unwrap()
+            .join("tests/fixtures")
+            .join(name)
+            .join("original.zhu");
+        let mut module = parse(&read_to_string(path).unwrap()).unwrap();
+        let func_id = module.get_module_id_by_symbol(name).unwrap().to_func_id();
+        let func = module.get_mut_function(func_id).unwrap();
+        let cfg = cfg_anylysis(func);
+        if name == "out_of_ssa_swap" {
+            out_of_ssa_pass(func, &cfg);
+        } else {
+            sccp_pass(func, &cfg);
+        }
+        let decoded = decode_module(&encode_module(&module)).unwrap();
+        assert_module_eq(&module, &decoded);
+        assert_eq!(format(&decoded), format(&module));
+    }
+}
+
+#[test]
+fn reject_corrupted_bytecode() {
+    let module = parse(
+        "func sum (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = add reg0 reg1
+  ret reg2
+}
+",
+    )
+    .unwrap();
+    let bytes = encode_module(&module);
+    assert_eq!(&bytes[..4], b"ZHUB");
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[0] = b'X';
+    assert!(matches!(decode_module(&bad_magic), Err(BytecodeError::BadMagic)));
+    assert!(matches!(decode_module(b""), Err(BytecodeError::BadMagic)));
+
+    let mut new_version = bytes.clone();
+    new_version[4..6].copy_from_slice(&(BYTECODE_VERSION + 1).to_le_bytes());
+    assert_eq!(
+        decode_module(&new_version).err(),
+        Some(BytecodeError::UnsupportedVersion(BYTECODE_VERSION + 1))
+    );
+
+    assert!(matches!(
+        decode_module(&bytes[..BYTECODE_HEADER_SIZE - 1]),
+        Err(BytecodeError::UnexpectedEnd { .. })
+    ));
+    assert!(matches!(
+        decode_module(&bytes[..bytes.len() - 1]),
+        Err(BytecodeError::LengthMismatch { .. })
+    ));
+
+    let mut flipped = bytes.clone();
+    *flipped.last_mut().unwrap() ^= 0xff;
+    assert!(matches!(
+        decode_module(&flipped),
+        Err(BytecodeError::ChecksumMismatch { .. })
+    ));
+    assert!(decode_module(&bytes).is_ok());
+}
+
+/// Replace payload of bytecode and recompute header, so only reference checking can
+/// reject it.
+fn reseal(bytes: &mut [u8]) {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in &bytes[BYTECODE_HEADER_SIZE..] {
+        hash ^= *byte as u32;
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    bytes[10..14].copy_from_slice(&hash.to_le_bytes());
+}
+
+#[test]
+fn reject_reference_to_undefined_entity() {
+    let module = parse(
+        "func sum (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = add reg0 reg1
+  ret reg2
+}
+",
+    )
+    .unwrap();
+    let bytes = encode_module(&module);
+    // payload starts with symbol table, which has one symbol `sum` to function 0.
+    let symbol = BYTECODE_HEADER_SIZE + 6;
+    assert_eq!(&bytes[BYTECODE_HEADER_SIZE..=symbol], &[1, 3, b's', b'u', b'm', 0, 0]);
+    let mut undefined_function = bytes.clone();
+    undefined_function[symbol] = 5;
+    reseal(&mut undefined_function);
+    assert_eq!(
+        decode_module(&undefined_function).err(),
+        Some(BytecodeError::UnknownEntity {
+            kind: "function",
+            index: 5
+        })
+    );
+}
+
+#[test]
+fn decode_corrupted_payload_without_panic() {
+    for (name, source) in read_baseline_sources() {
+        let bytes = encode_module(&parse(&source).unwrap());
+        for position in BYTECODE_HEADER_SIZE..bytes.len() {
+            for mask in [0x01, 0x04, 0x80] {
+                let mut corrupted = bytes.clone();
+                corrupted[position] ^= mask;
+                reseal(&mut corrupted);
+                // decoded module must be formattable, otherwise it is rejected.
+                if let Ok(module) = decode_module(&corrupted) {
+                    let result = std::panic::catch_unwind(|| format(&module));
+                    assert!(
+                        result.is_ok(),
+                        "[Error]: case {} panic when byte {} is flipped by {:#x}.",
+                        name,
+                        position,
+                        mask
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use zsh_ir::builder::FunctionBuilder;
+use zsh_ir::entities::instruction::opcode::CmpFlag;
+use zsh_ir::entities::module::{FuncId, Module};
+use zsh_ir::entities::r#type::ValueType;
+use zsh_ir::frontend::parse;
+use zsh_ir::pass::ipo::call_graph::call_graph_analysis;
+
+const SOURCE: &str = "func leaf (reg0: i32): i32 {
+block0:
+  ret reg0
+}
+func middle (reg0: i32): i32 {
+block0:
+  reg1 = call func leaf(reg0)
+  reg2 = call func leaf(reg1)
+  ret reg2
+}
+func main (reg0: i32): i32 {
+block0:
+  reg1 = call func middle(reg0)
+  reg2 = call func leaf(reg1)
+  ret reg2
+}
+func unused () {
+block0:
+  ret
+}
+";
+
+fn get_func_id(module: &Module, name: &str) -> FuncId {
+    module.get_module_id_by_symbol(name).unwrap().to_func_id()
+}
+
+/// Add `is_even` and `is_odd` which call each other, and `countdown` which call itself.
+fn add_recursive_functions(module: &mut Module) {
+    let is_even = module.declar_function("is_even");
+    let is_odd = module.declar_function("is_odd");
+    let countdown = module.declar_function("countdown");
+    for func_id in [is_even, is_odd, countdown] {
+        let func = module.get_mut_function(func_id).unwrap();
+        func.def_func_param(ValueType::U64);
+        func.set_return_type(ValueType::U64);
+    }
+    for (func_id, callee) in [(is_even, is_odd), (is_odd, is_even), (countdown, countdown)] {
+        let func_ref = module.declar_function_in_function(callee, func_id);
+        let func = module.get_mut_function(func_id).unwrap();
+        let reg0 = func.entities.params[0];
+        let block0 = func.create_block();
+        let block1 = func.create_block();
+        let block2 = func.create_block();
+        let mut builder = FunctionBuilder::new(func);
+        builder.switch_to_block(block0);
+        let zero = builder.iconst_inst(vec![0], ValueType::U64);
+        let is_zero = builder.icmp_inst(CmpFlag::Eq, [reg0, zero]);
+        builder.brif_inst(is_zero, block1, block2);
+        builder.switch_to_block(block1);
+        builder.ret_inst(Some(zero));
+        builder.switch_to_block(block2);
+        let result = builder.call_inst(vec![reg0], func_ref).unwrap();
+        builder.ret_inst(Some(result));
+    }
+}
+
+#[test]
+fn callers_and_callees() {
+    let module = parse(SOURCE).unwrap();
+    let call_graph = call_graph_analysis(&module);
+    let [leaf, middle, main, unused] = ["leaf", "middle", "main", "unused"].map(|name| get_func_id(&module, name));
+    assert_eq!(call_graph.get_callees(main), &[leaf, middle]);
+    assert_eq!(call_graph.get_callees(middle), &[leaf]);
+    assert_eq!(call_graph.get_callees(leaf), &[]);
+    assert_eq!(call_graph.get_callers(leaf), &[middle, main]);
+    assert_eq!(call_graph.get_callers(main), &[]);
+    assert_eq!(call_graph.get_callers(unused), &[]);
+}
+
+#[test]
+fn bottom_up_order_and_recursion() {
+    let mut module = parse(SOURCE).unwrap();
+    add_recursive_functions(&mut module);
+    let call_graph = call_graph_analysis(&module);
+    let [leaf, middle, main, unused, is_even, is_odd, countdown] =
+        ["leaf", "middle", "main", "unused", "is_even", "is_odd", "countdown"].map(|name| get_func_id(&module, name));
+    assert_eq!(
+        call_graph.get_sccs(),
+        &[
+            vec![leaf],
+            vec![middle],
+            vec![main],
+            vec![unused],
+            vec![is_even, is_odd],
+            vec![countdown]
+        ]
+    );
+    assert_eq!(
+        call_graph.bottom_up_order(),
+        vec![leaf, middle, main, unused, is_even, is_odd, countdown]
+    );
+    assert_eq!(call_graph.get_scc_of(is_odd), &[is_even, is_odd]);
+    assert!(call_graph.is_recursive(is_even));
+    assert!(call_graph.is_recursive(is_odd));
+    assert!(call_graph.is_recursive(countdown));
+    assert!(!call_graph.is_recursive(main));
+    assert!(!call_graph.is_recursive(leaf));
+}
@@ -0,0 +1,57 @@
+use zsh_ir::entities::instruction::InstructionData;
+use zsh_ir::formatter::format;
+use zsh_ir::frontend::parse;
+use zsh_ir::frontend::to_tokens;
+use zsh_ir::frontend::token::TokenKind;
+
+#[test]
+fn lexer_skip_comments_and_blank_lines() {
+    let source = "; line comment\r\n\r\n// another line comment\nreg0 /* block\ncomment */ = ;; trailing\n  add";
+    let kinds = to_tokens(source)
+        .into_iter()
+        .map(|token| token.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, vec![TokenKind::Reg, TokenKind::Assign, TokenKind::Add]);
+}
+
+#[test]
+fn parse_preserve_comments_in_block() {
+    let source = "; comment before function is skipped
+func comment (reg0: i32): i32 {
+/* comment in function header is skipped */
+; comment of block0
+block0:
+  // load constant
+  reg1 = iconst i32 1 ; trailing comment
+  reg2 = add reg0 reg1
+  ret reg2
+}
+";
+    let module = parse(source).unwrap();
+    let func_id = module.get_module_id_by_symbol("comment").unwrap().to_func_id();
+    let func = module.get_function(func_id).unwrap();
+    let comments = func
+        .get_insts_of_block(func.first_block().unwrap())
+        .into_iter()
+        .filter_map(|inst| match func.get_inst_data(inst) {
+            InstructionData::Comment(text) => Some(text.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        comments,
+        vec![" comment of block0", " load constant", " trailing comment"]
+    );
+    let expected = "func comment (reg0: i32): i32 {
+block0:
+  ;; comment of block0
+  ;; load constant
+  reg1 = iconst i32 1
+  ;; trailing comment
+  reg2 = add reg0 reg1
+  ret reg2
+}
+";
+    assert_eq!(format(&module), expected);
+    assert_eq!(format(&parse(expected).unwrap()), expected);
+}
@@ -0,0 +1,90 @@
+use zsh_ir::formatter::dot::{format_dot, DotGraph};
+use zsh_ir::frontend::parse;
+use zsh_ir::pass::analysis::cfg::cfg_anylysis;
+use zsh_ir::pass::analysis::domtree::domtree_analysis;
+use zsh_ir::pass::opt::dce::post_domtree::post_domtree_analysis;
+use zsh_ir::pass::opt::licm::natural_loop::natural_loop_analysis;
+
+const LOOP_SOURCE: &str = "func count (reg0: i32): i32 {
+block0:
+  reg1 = iconst i32 0
+  jump block1
+block1:
+  reg2 = phi [block0 reg1, block2 reg3]
+  reg4 = icmp lt reg2 reg0
+  brif reg4 block2 block3
+block2:
+  reg3 = addi reg2 1
+  jump block1
+block3:
+  ret reg2
+}
+";
+
+#[test]
+fn format_cfg_as_dot() {
+    let module = parse(LOOP_SOURCE).unwrap();
+    let function = module
+        .get_function(module.get_module_id_by_symbol("count").unwrap().to_func_id())
+        .unwrap();
+    let cfg = cfg_anylysis(function);
+    assert_eq!(
+        format_dot("count", function, &module, &cfg),
+        r#"digraph "count" {
+  node [shape=record, fontname="monospace"];
+  block0 [label="{block0:|reg1 = iconst i32 0\ljump block1\l}"];
+  block1 [label="{block1:|reg2 = phi [block0 reg1, block2 reg3]\lreg4 = icmp lt reg2 reg0\lbrif reg4 block2 block3\l}"];
+  block2 [label="{block2:|reg3 = addi reg2 1\ljump block1\l}"];
+  block3 [label="{block3:|ret reg2\l}"];
+  block0 -> block1;
+  block1 -> block2 [label="T"];
+  block1 -> block3 [label="F"];
+  block2 -> block1;
+}
+"#
+    );
+}
+
+#[test]
+fn format_dot_with_overlays() {
+    let module = parse(LOOP_SOURCE).unwrap();
+    let function = module
+        .get_function(module.get_module_id_by_symbol("count").unwrap().to_func_id())
+        .unwrap();
+    let cfg = cfg_anylysis(function);
+    let dom = domtree_analysis(function, &cfg);
+    let post_dom = post_domtree_analysis(function, &cfg);
+    let loops = natural_loop_analysis(&dom, &cfg);
+    let dot = DotGraph::new("count", function, &module, &cfg)
+        .with_domtree(&dom)
+        .with_post_domtree(&post_dom)
+        .with_loops(&loops)
+        .with_block_set("dom", |block| {
+            dom.dom(block)
+                .iter()
+                .map(|dom_block| format!("block{}", dom_block.0))
+                .collect()
+        })
+        .format();
+    assert_eq!(
+        dot,
+        r#"digraph "count" {
+  node [shape=record, fontname="monospace"];
+  block0 [label="{block0:|reg1 = iconst i32 0\ljump block1\l|dom: block0\l}"];
+  block1 [label="{block1:|header of loop0|reg2 = phi [block0 reg1, block2 reg3]\lreg4 = icmp lt reg2 reg0\lbrif reg4 block2 block3\l|dom: block0, block1\l}"];
+  block2 [label="{block2:|in loop0|reg3 = addi reg2 1\ljump block1\l|dom: block0, block1, block2\l}"];
+  block3 [label="{block3:|ret reg2\l|dom: block0, block1, block3\l}"];
+  block0 -> block1;
+  block1 -> block2 [label="T"];
+  block1 -> block3 [label="F"];
+  block2 -> block1 [style=bold, color=darkgreen];
+  block0 -> block1 [style=dashed, color=blue, constraint=false];
+  block1 -> block2 [style=dashed, color=blue, constraint=false];
+  block1 -> block3 [style=dashed, color=blue, constraint=false];
+  block0 -> block1 [style=dotted, color=red, constraint=false];
+  block1 -> block3 [style=dotted, color=red, constraint=false];
+  block2 -> block1 [style=dotted, color=red, constraint=false];
+}
+"#
+    );
+}
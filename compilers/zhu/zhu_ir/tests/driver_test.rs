@@ -0,0 +1,240 @@
+use std::env::{current_dir, temp_dir};
+use std::fs::{read_to_string, remove_file};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use zsh_ir::driver::{parse_args, run_source, DriverError, DriverOptions};
+use zsh_ir::pass::manager::PassManagerError;
+
+const SOURCE: &str = "func add_one (reg0: i32): i32 {
+block0:
+  reg1 = addi reg0 1
+  reg2 = addi reg0 1
+  ret reg1
+}
+
+func identity (reg0: i32): i32 {
+block0:
+  reg1 = addi reg0 0
+  ret reg0
+}
+";
+
+fn args(text: &str) -> Vec<String> {
+    text.split_whitespace().map(|arg| arg.to_owned()).collect()
+}
+
+fn read_fixture(case_name: &str) -> String {
+    let path_buf = current_dir()
+        .unwrap()
+        .join("tests/fixtures")
+        .join(case_name)
+        .join("original.zhu");
+    read_to_string(path_buf).unwrap()
+}
+
+#[test]
+fn parse_command_line_options() {
+    let options = parse_args(args(
+        "-p dce -p gvn,licm -a earliest,later -f main --dot -o out.zhu a.zhu -",
+    ))
+    .unwrap();
+    assert_eq!(
+        options,
+        DriverOptions {
+            inputs: vec!["a.zhu".to_owned(), "-".to_owned()],
+            pipeline: "dce,gvn,licm".to_owned(),
+            analyses: vec!["earliest".to_owned(), "later".to_owned()],
+            functions: vec!["main".to_owned()],
+            dot: true,
+            verify_each: false,
+            output: Some("out.zhu".to_owned()),
+            help: false,
+        }
+    );
+    assert_eq!(
+        parse_args(args("-a dominance")),
+        Err(DriverError::Usage("unknown analysis `dominance`".to_owned()))
+    );
+    assert_eq!(
+        parse_args(args("a.zhu -p")),
+        Err(DriverError::Usage("option `-p` require a value".to_owned()))
+    );
+    assert_eq!(
+        parse_args(args("--fast")),
+        Err(DriverError::Usage("unknown option `--fast`".to_owned()))
+    );
+}
+
+#[test]
+fn run_passes_on_selected_functions() {
+    let options = parse_args(args("-p dce -f add_one")).unwrap();
+    assert_eq!(
+        run_source(&options, "input.zhu", SOURCE).unwrap(),
+        "func add_one (reg0: i32): i32 {
+block0:
+  reg1 = addi reg0 1
+  ret reg1
+}
+"
+    );
+    let options = parse_args(args("-p dce")).unwrap();
+    assert_eq!(
+        run_source(&options, "input.zhu", SOURCE).unwrap(),
+        "func add_one (reg0: i32): i32 {
+block0:
+  reg1 = addi reg0 1
+  ret reg1
+}
+func identity (reg0: i32): i32 {
+block0:
+  ret reg0
+}
+"
+    );
+}
+
+#[test]
+fn print_analysis_tables() {
+    let options = parse_args(args("-p critical-edge -a earliest")).unwrap();
+    assert_eq!(
+        run_source(&options, "input.zhu", &read_fixture("lcm_diamond")).unwrap(),
+        "func lcm_diamond:
+========== Earliest ==========
+Block0:
+\taddi reg0 10
+Block1:
+\tadd reg0 reg1
+Block2:
+\tadd reg0 reg1
+Block3:
+Block4:
+Block5:
+"
+    );
+}
+
+#[test]
+fn print_loop_and_register_allocation_tables() {
+    let options = parse_args(args("-a loop-forest,induction-variables")).unwrap();
+    assert_eq!(
+        run_source(&options, "input.zhu", &read_fixture("unroll_full")).unwrap(),
+        "func unroll_full:
+========== Loop Forest ==========
+loop0: header block1, latches [block2], parent none, blocks [block1, block2]
+========== Induction Variables ==========
+reg3: init reg1, update reg7, step 1
+Exit: block1 to block3 when reg3 Lt reg2 is false
+Trip count: 4
+"
+    );
+    let options = parse_args(args("-a regalloc-x86-64,regalloc-riscv64 -f add_one")).unwrap();
+    assert_eq!(
+        run_source(&options, "input.zhu", SOURCE).unwrap(),
+        "func add_one:
+========== Register Allocation ==========
+reg0: %rbx [0, 2)
+reg1: %r12 [1, 3)
+reg2: %rbx [2, 3)
+Spill slots: 0
+========== Register Allocation ==========
+reg0: s1 [0, 2)
+reg1: s2 [1, 3)
+reg2: s1 [2, 3)
+Spill slots: 0
+"
+    );
+    let options = parse_args(args("-a liveness,alias -f identity")).unwrap();
+    let output = run_source(&options, "input.zhu", SOURCE).unwrap();
+    assert!(output.starts_with("func identity:\n========== Liveness ==========\n"));
+    assert!(output.ends_with("========== Alias Analysis ==========\n"));
+}
+
+#[test]
+fn print_analysis_tables_with_unreachable_block() {
+    // block1 is unreachable and jump to join block2.
+    let source = "func unreachable_join (reg0: i32): i32 {
+block0:
+  reg1 = addi reg0 1
+  jump block2
+block1:
+  jump block2
+block2:
+  reg2 = add reg0 reg1
+  ret reg2
+}
+";
+    for name in [
+        "anticipate",
+        "will-be-available",
+        "earliest",
+        "postponable",
+        "later",
+        "used",
+        "liveness",
+        "alias",
+        "loop-forest",
+        "induction-variables",
+        "regalloc-x86-64",
+        "regalloc-riscv64",
+    ] {
+        let options = parse_args(vec!["-a".to_owned(), name.to_owned()]).unwrap();
+        let output = run_source(&options, "input.zhu", source).unwrap();
+        assert!(
+            output.starts_with("func unreachable_join:\n"),
+            "analysis {} failed",
+            name
+        );
+    }
+}
+
+#[test]
+fn report_driver_errors() {
+    let options = parse_args(args("-p dce,unknown")).unwrap();
+    assert_eq!(
+        run_source(&options, "input.zhu", SOURCE),
+        Err(DriverError::PassManager(PassManagerError::UnknownPass(
+            "unknown".to_owned()
+        )))
+    );
+    let options = parse_args(args("-f missing")).unwrap();
+    assert_eq!(
+        run_source(&options, "input.zhu", SOURCE),
+        Err(DriverError::UnknownFunction("missing".to_owned()))
+    );
+    let error = run_source(
+        &DriverOptions::default(),
+        "broken.zhu",
+        "func broken () {\nblock0:\n  ret",
+    )
+    .unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("[Error]: failed to parse `broken.zhu`.\n[Error]: unexpected end of file at 3:6"));
+}
+
+#[test]
+fn binary_read_stdin_and_write_output_file() {
+    let output_path = temp_dir().join(format!("zhu_opt_driver_test_{}.zhu", std::process::id()));
+    let mut child = Command::new(env!("CARGO_BIN_EXE_zhu-opt"))
+        .args(["-p", "dce", "-o", output_path.to_str().unwrap()])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(SOURCE.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let result = read_to_string(&output_path).unwrap();
+    remove_file(&output_path).unwrap();
+    assert!(result.starts_with("func add_one (reg0: i32): i32 {\nblock0:\n  reg1 = addi reg0 1\n  ret reg1\n}"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zhu-opt"))
+        .arg("missing.zhu")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("[Error]: can not access `missing.zhu`"));
+}
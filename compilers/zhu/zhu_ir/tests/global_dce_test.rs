@@ -0,0 +1,69 @@
+use zsh_ir::formatter::format;
+use zsh_ir::frontend::parse;
+use zsh_ir::pass::analysis::verifier::verify_module;
+use zsh_ir::pass::ipo::global_dce::global_dce_pass;
+
+const SOURCE: &str = "counter = @data { size 8, align 8, mut }
+table = @data { size 8, align 8, reloc [0, counter, 0] }
+unused_data = @data { size 4, align 4 }
+func helper (reg0: i32): i32 {
+block0:
+  ret reg0
+}
+func dead_helper (reg0: i32): i32 {
+block0:
+  ret reg0
+}
+func dead (reg0: i32): i32 {
+block0:
+  reg1 = call func dead_helper(reg0)
+  ret reg1
+}
+func main (reg0: i32): i32 {
+  greg0 = @global symbol table
+block0:
+  reg1 = call func helper(reg0)
+  ret reg1
+}
+";
+
+#[test]
+fn remove_unreachable_functions_and_data() {
+    let mut module = parse(SOURCE).unwrap();
+    let removed = global_dce_pass(&mut module, &["main"]);
+    assert_eq!(removed, vec!["dead", "dead_helper", "unused_data"]);
+    assert_eq!(verify_module(&module), vec![]);
+    assert_eq!(
+        format(&module),
+        "counter = @data { size 8, align 8, mut }
+table = @data { size 8, align 8, reloc [0, counter, 0] }
+func helper (reg0: i32): i32 {
+block0:
+  ret reg0
+}
+func main (reg0: i32): i32 {
+  greg0 = @global symbol table
+block0:
+  reg1 = call func helper(reg0)
+  ret reg1
+}
+"
+    );
+    // new function must not take index of remaining function.
+    let first = module.declar_function("first");
+    let second = module.declar_function("second");
+    assert_eq!(module.functions.len(), 4);
+    assert_ne!(first, second);
+    assert!(module.get_module_id_by_symbol("main").is_some());
+}
+
+#[test]
+fn data_and_unknown_symbol_as_root() {
+    let mut module = parse(SOURCE).unwrap();
+    let removed = global_dce_pass(&mut module, &["table", "dead", "not_exist"]);
+    assert_eq!(removed, vec!["helper", "main", "unused_data"]);
+    assert!(module.get_module_id_by_symbol("counter").is_some());
+    assert!(module.get_module_id_by_symbol("dead_helper").is_some());
+    assert_eq!(global_dce_pass(&mut module, &[]).len(), 4);
+    assert!(module.symbol_table.is_empty());
+}
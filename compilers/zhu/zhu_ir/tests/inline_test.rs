@@ -0,0 +1,151 @@
+mod semantic;
+
+use semantic::assert_preserve_semantic;
+use zsh_ir::entities::runtime_value::RuntimeValue;
+use zsh_ir::formatter::format;
+use zsh_ir::frontend::parse;
+use zsh_ir::pass::analysis::verifier::verify_module;
+use zsh_ir::pass::ipo::inline::{inline_pass, InlineConfig};
+
+const MAX_SOURCE: &str = "func max (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = icmp gt reg0 reg1
+  brif reg2 block1 block2
+block1:
+  ret reg0
+block2:
+  ret reg1
+}
+func clamp (reg0: i32): i32 {
+block0:
+  reg1 = iconst i32 0
+  reg2 = call func max(reg0, reg1)
+  reg3 = addi reg2 1
+  ret reg3
+}
+";
+
+const LOOP_SOURCE: &str = "counter = @data { size 4, align 4, mut }
+func bump (reg0: i32): i32 {
+  greg0 = @global symbol counter
+block0:
+  reg1 = gload i32 [greg0, 0]
+  reg2 = add reg1 reg0
+  gstore reg2 [greg0, 0]
+  ret reg2
+}
+func square (reg0: i32): i32 {
+block0:
+  reg1 = mul reg0 reg0
+  ret reg1
+}
+func sum (reg0: i32): i32 {
+block0:
+  reg1 = iconst i32 0
+  jump block1
+block1:
+  reg2 = phi [block0 reg1, block2 reg4]
+  reg3 = icmp lt reg2 reg0
+  brif reg3 block2 block3
+block2:
+  reg5 = call func bump(reg2)
+  reg6 = call func square(reg2)
+  reg4 = addi reg2 1
+  jump block1
+block3:
+  reg7 = call func bump(reg0)
+  ret reg7
+}
+";
+
+#[test]
+fn inline_call_with_multiple_returns() {
+    let original = parse(MAX_SOURCE).unwrap();
+    let mut module = parse(MAX_SOURCE).unwrap();
+    assert_eq!(inline_pass(&mut module, InlineConfig::default()), 1);
+    assert_eq!(verify_module(&module), vec![]);
+    assert_eq!(
+        format(&module),
+        "func max (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = icmp gt reg0 reg1
+  brif reg2 block1 block2
+block1:
+  ret reg0
+block2:
+  ret reg1
+}
+func clamp (reg0: i32): i32 {
+block0:
+  reg1 = iconst i32 0
+  jump block1
+block1:
+  reg4 = icmp gt reg0 reg1
+  brif reg4 block2 block3
+block2:
+  jump block4
+block3:
+  jump block4
+block4:
+  reg2 = phi [block2 reg0, block3 reg1]
+  reg3 = addi reg2 1
+  ret reg3
+}
+"
+    );
+    assert_preserve_semantic(
+        &original,
+        &module,
+        "clamp",
+        &[vec![RuntimeValue::I32(-5)], vec![RuntimeValue::I32(7)]],
+    );
+}
+
+#[test]
+fn inline_call_in_loop_with_global_value() {
+    let original = parse(LOOP_SOURCE).unwrap();
+    let mut module = parse(LOOP_SOURCE).unwrap();
+    assert_eq!(inline_pass(&mut module, InlineConfig::default()), 3);
+    assert_eq!(verify_module(&module), vec![]);
+    let text = format(&module);
+    assert!(!text.contains("call func"));
+    assert_preserve_semantic(
+        &original,
+        &module,
+        "sum",
+        &[vec![RuntimeValue::I32(0)], vec![RuntimeValue::I32(5)]],
+    );
+    // inlined module can be parsed again.
+    assert_eq!(format(&parse(&text).unwrap()), text);
+}
+
+#[test]
+fn callee_over_threshold_is_not_inlined() {
+    let mut module = parse(MAX_SOURCE).unwrap();
+    assert_eq!(inline_pass(&mut module, InlineConfig::new(2)), 0);
+    assert_eq!(format(&module), format(&parse(MAX_SOURCE).unwrap()));
+}
+
+#[test]
+fn call_mismatch_with_callee_is_not_inlined() {
+    let source = "func plus (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = add reg0 reg1
+  ret reg2
+}
+func nothing (reg0: i32): i32 {
+block0:
+  ret
+}
+func caller (reg0: i32): i32 {
+block0:
+  reg1 = call func plus(reg0)
+  reg2 = call func nothing(reg0)
+  reg3 = add reg1 reg2
+  ret reg3
+}
+";
+    let mut module = parse(source).unwrap();
+    assert_eq!(inline_pass(&mut module, InlineConfig::default()), 0);
+    assert_eq!(format(&module), format(&parse(source).unwrap()));
+}
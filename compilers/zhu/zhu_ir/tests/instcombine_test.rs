@@ -0,0 +1,192 @@
+mod semantic;
+
+use semantic::assert_preserve_semantic;
+use zsh_ir::entities::constant::Constant;
+use zsh_ir::entities::instruction::InstructionData;
+use zsh_ir::entities::module::Module;
+use zsh_ir::entities::runtime_value::RuntimeValue;
+use zsh_ir::formatter::format;
+use zsh_ir::frontend::parse;
+use zsh_ir::interpreter::Interpreter;
+use zsh_ir::pass::analysis::verifier::verify_module;
+use zsh_ir::pass::manager::PassManager;
+use zsh_ir::pass::opt::instcombine::InstCombinePass;
+use zsh_ir::pass::OptiPass;
+
+const IDENTITY_SOURCE: &str = "func identity (reg0: i32, reg1: u64): i32 {
+block0:
+  reg2 = addi reg0 0
+  reg3 = muli reg2 1
+  reg4 = neg reg3
+  reg5 = neg reg4
+  reg6 = neg reg5
+  reg7 = neg reg6
+  reg8 = to.i32 reg7
+  reg9 = to.i32 reg8
+  reg10 = sub reg9 reg9
+  reg11 = add reg9 reg10
+  reg12 = band reg1 reg1
+  reg13 = to.i32 reg12
+  reg14 = add reg11 reg13
+  ret reg14
+}
+";
+
+const STRENGTH_SOURCE: &str = "func strength (reg0: u32, reg1: i64): u32 {
+block0:
+  reg2 = uconst u32 8
+  reg3 = mul reg2 reg0
+  reg4 = dividei reg3 4
+  reg5 = reminderi reg4 16
+  reg6 = to.u32 reg1
+  reg7 = iconst i64 32
+  reg8 = mul reg1 reg7
+  reg9 = divide reg8 reg7
+  reg10 = to.u32 reg9
+  reg11 = add reg5 reg6
+  reg12 = add reg11 reg10
+  ret reg12
+}
+";
+
+fn run_instcombine(module: &mut Module, func_name: &str) -> InstCombinePass {
+    let func_id = module.get_module_id_by_symbol(func_name).unwrap().to_func_id();
+    let mut pass = InstCombinePass::new();
+    pass.process(module.get_mut_function(func_id).unwrap());
+    pass
+}
+
+#[test]
+fn remove_algebraic_identities() {
+    let original = parse(IDENTITY_SOURCE).unwrap();
+    let mut module = parse(IDENTITY_SOURCE).unwrap();
+    let pass = run_instcombine(&mut module, "identity");
+    assert_eq!(verify_module(&module), vec![]);
+    assert_eq!(pass.get_applied_count("canonicalize-constant-operand"), 1);
+    assert_eq!(pass.get_applied_count("immediate-identity"), 3);
+    assert_eq!(pass.get_applied_count("double-unary"), 2);
+    assert_eq!(pass.get_applied_count("redundant-convert"), 2);
+    assert_eq!(pass.get_applied_count("binary-same-operands"), 2);
+    assert_eq!(
+        format(&module),
+        "func identity (reg0: i32, reg1: u64): i32 {
+block0:
+  reg4 = neg reg0
+  reg6 = neg reg0
+  reg10 = iconst i32 0
+  reg13 = to.i32 reg1
+  reg14 = add reg0 reg13
+  ret reg14
+}
+"
+    );
+    use RuntimeValue::*;
+    assert_preserve_semantic(
+        &original,
+        &module,
+        "identity",
+        &[vec![I32(7), U64(u64::MAX)], vec![I32(i32::MIN), U64(1 << 40 | 5)]],
+    );
+}
+
+#[test]
+fn reduce_strength_of_power_of_two() {
+    let original = parse(STRENGTH_SOURCE).unwrap();
+    let mut module = parse(STRENGTH_SOURCE).unwrap();
+    let pass = run_instcombine(&mut module, "strength");
+    assert_eq!(verify_module(&module), vec![]);
+    assert_eq!(pass.get_applied_count("canonicalize-constant-operand"), 3);
+    assert_eq!(pass.get_applied_count("strength-reduction"), 4);
+    let output = format(&module);
+    assert!(output.contains("shl"));
+    assert!(output.contains("shr"));
+    assert!(output.contains("band"));
+    // signed divide is not a shift, since it rounds toward zero.
+    assert!(output.contains("dividei reg8 32"));
+    use RuntimeValue::*;
+    assert_preserve_semantic(
+        &original,
+        &module,
+        "strength",
+        &[
+            vec![U32(0), I64(0)],
+            vec![U32(123456), I64(-77)],
+            vec![U32(u32::MAX), I64(i64::MAX / 64)],
+        ],
+    );
+}
+
+const ICMP_SOURCE: &str = "func compare (reg0: i32, reg1: u32): u8 {
+block0:
+  reg2 = iconst i32 5
+  reg3 = icmp lt reg2 reg0
+  reg4 = icmp eq reg0 reg0
+  reg5 = uconst u32 0
+  reg6 = icmp gteq reg1 reg5
+  reg7 = add reg3 reg4
+  reg8 = add reg7 reg6
+  ret reg8
+}
+";
+
+#[test]
+fn canonicalize_compare() {
+    let original = parse(ICMP_SOURCE).unwrap();
+    let mut module = parse(ICMP_SOURCE).unwrap();
+    let pass = run_instcombine(&mut module, "compare");
+    assert_eq!(verify_module(&module), vec![]);
+    assert_eq!(pass.get_applied_count("icmp-constant-to-rhs"), 1);
+    assert_eq!(pass.get_applied_count("icmp-same-operands"), 1);
+    assert_eq!(pass.get_applied_count("icmp-unsigned-bound"), 1);
+    use RuntimeValue::*;
+    assert_preserve_semantic(
+        &original,
+        &module,
+        "compare",
+        &[vec![I32(4), U32(0)], vec![I32(5), U32(9)], vec![I32(6), U32(u32::MAX)]],
+    );
+}
+
+#[test]
+fn run_instcombine_in_pipeline() {
+    let original = parse(IDENTITY_SOURCE).unwrap();
+    let mut module = parse(IDENTITY_SOURCE).unwrap();
+    let mut manager = PassManager::from_pipeline("instcombine,dce").unwrap();
+    manager.run_on_module(&mut module).unwrap();
+    assert_eq!(verify_module(&module), vec![]);
+    assert!(!format(&module).contains("neg"));
+    use RuntimeValue::*;
+    assert_preserve_semantic(&original, &module, "identity", &[vec![I32(-3), U64(42)]]);
+}
+
+#[test]
+fn fold_constant_with_sparse_constant_keys() {
+    let source = "func sparse (): i32 {
+block0:
+  reg0 = iconst i32 2
+  reg1 = iconst i32 3
+  reg2 = add reg0 reg1
+  reg3 = add reg2 reg1
+  ret reg3
+}
+";
+    let mut module = parse(source).unwrap();
+    // keys of constants become `0` and `2`, as a module decoded from json or bytecode may have.
+    let func_id = module.get_module_id_by_symbol("sparse").unwrap().to_func_id();
+    let func = module.get_mut_function(func_id).unwrap();
+    let data = func.constants.remove(&Constant(1)).unwrap();
+    func.constants.insert(Constant(2), data);
+    for inst in func.insts() {
+        if let InstructionData::UnaryConst { constant, .. } = func.get_inst_data_mut(inst) {
+            if *constant == Constant(1) {
+                *constant = Constant(2);
+            }
+        }
+    }
+    run_instcombine(&mut module, "sparse");
+    assert_eq!(verify_module(&module), vec![]);
+    assert_eq!(
+        Interpreter::new(&module).run("sparse", &[]).unwrap(),
+        Some(RuntimeValue::I32(8))
+    );
+}
@@ -0,0 +1,573 @@
+use std::env::current_dir;
+use std::fs::read_to_string;
+use zsh_ir::builder::FunctionBuilder;
+use zsh_ir::entities::external_name::{ExternalName, UserDefNamespace};
+use zsh_ir::entities::function::Function;
+use zsh_ir::entities::immediate::Immediate;
+use zsh_ir::entities::instruction::opcode::CmpFlag;
+use zsh_ir::entities::instruction::InstructionData;
+use zsh_ir::entities::module::{DataDescription, DataInit, DataRelocation, Module};
+use zsh_ir::entities::r#type::ValueType;
+use zsh_ir::entities::runtime_value::RuntimeValue;
+use zsh_ir::frontend::parse;
+use zsh_ir::interpreter::{interpret, Interpreter, RuntimeErrorKind};
+use zsh_ir::pass::analysis::cfg::cfg_anylysis;
+use zsh_ir::pass::analysis::domtree::domtree_analysis;
+use zsh_ir::pass::analysis::rpo::revrese_post_order_analysis;
+use zsh_ir::pass::analysis::verifier::verify_module;
+use zsh_ir::pass::opt::dce::dce_pass;
+use zsh_ir::pass::opt::dce::post_domtree::post_domtree_analysis;
+use zsh_ir::pass::opt::gvn::gvn_pass;
+use zsh_ir::pass::opt::lcm::lcm_opt;
+use zsh_ir::pass::opt::mem2reg::mem2reg_pass;
+use zsh_ir::pass::opt::out_of_ssa::out_of_ssa_pass;
+use zsh_ir::pass::opt::sccp::sccp_pass;
+
+fn read_case(folder: &str, case_name: &str, file_name: &str) -> String {
+    let path_buf = current_dir()
+        .unwrap()
+        .join("tests")
+        .join(folder)
+        .join(case_name)
+        .join(file_name);
+    read_to_string(path_buf)
+        .unwrap_or_else(|_| panic!("[Error]: test file can not read. test case is {:?}.", case_name))
+}
+
+fn run_source(source: &str, func_name: &str, args: &[RuntimeValue]) -> Result<Option<RuntimeValue>, RuntimeErrorKind> {
+    let module = parse(source).unwrap();
+    interpret(&module, func_name, args).map_err(|error| error.kind)
+}
+
+/// Run fixture before and after optimization with same arguments, result should be the same.
+fn assert_opti_preserve_semantic(
+    case_name: &str,
+    func_name: &str,
+    args_list: &[Vec<RuntimeValue>],
+    process: impl FnOnce(&mut Function),
+) {
+    let original = parse(&read_case("fixtures", case_name, "original.zhu")).unwrap();
+    let mut optimized = parse(&read_case("fixtures", case_name, "original.zhu")).unwrap();
+    let func_id = optimized.get_module_id_by_symbol(func_name).unwrap().to_func_id();
+    process(optimized.get_mut_function(func_id).unwrap());
+    for args in args_list {
+        let mut original_interpreter = Interpreter::new(&original);
+        original_interpreter.set_step_limit(10_000);
+        let mut optimized_interpreter = Interpreter::new(&optimized);
+        optimized_interpreter.set_step_limit(10_000);
+        let original_result = original_interpreter.run(func_name, args).map_err(|error| error.kind);
+        let optimized_result = optimized_interpreter.run(func_name, args).map_err(|error| error.kind);
+        assert_eq!(
+            original_result, optimized_result,
+            "Test case {} change semantic with arguments {:?}",
+            case_name, args
+        );
+    }
+}
+
+fn dce(func: &mut Function) {
+    let cfg = cfg_anylysis(func);
+    let post_dom = post_domtree_analysis(func, &cfg);
+    dce_pass(func, &post_dom);
+}
+
+fn gvn(func: &mut Function) {
+    let cfg = cfg_anylysis(func);
+    let dom = domtree_analysis(func, &cfg);
+    let rpo = revrese_post_order_analysis(&cfg);
+    gvn_pass(func, &dom, &cfg, &rpo);
+}
+
+fn lcm(func: &mut Function) {
+    let cfg = cfg_anylysis(func);
+    let rpo = revrese_post_order_analysis(&cfg);
+    lcm_opt(&cfg, &rpo, func);
+}
+
+fn mem2reg(func: &mut Function) {
+    let cfg = cfg_anylysis(func);
+    let dom = domtree_analysis(func, &cfg);
+    mem2reg_pass(func, &cfg, &dom);
+}
+
+fn sccp(func: &mut Function) {
+    let cfg = cfg_anylysis(func);
+    sccp_pass(func, &cfg);
+}
+
+fn out_of_ssa(func: &mut Function) {
+    let cfg = cfg_anylysis(func);
+    out_of_ssa_pass(func, &cfg);
+}
+
+#[test]
+fn integer_arithmetic() {
+    let source = "func arith (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = add reg0 reg1
+  reg3 = muli reg2 3
+  reg4 = sub reg3 reg1
+  reg5 = divide reg4 reg0
+  reg6 = reminder reg4 reg0
+  reg7 = add reg5 reg6
+  ret reg7
+}";
+    // (7 + 3) * 3 - 3 = 27, 27 / 7 + 27 % 7 = 3 + 6
+    let result = run_source(source, "arith", &[RuntimeValue::I32(7), RuntimeValue::I32(3)]);
+    assert_eq!(result, Ok(Some(RuntimeValue::I32(9))));
+}
+
+#[test]
+fn integer_arithmetic_wrap_in_width_of_type() {
+    let source = "func wrap (reg0: u8, reg1: u8): u8 {
+block0:
+  reg2 = add reg0 reg1
+  ret reg2
+}";
+    let result = run_source(source, "wrap", &[RuntimeValue::U8(200), RuntimeValue::U8(100)]);
+    assert_eq!(result, Ok(Some(RuntimeValue::U8(44))));
+}
+
+#[test]
+fn signed_division_and_shift() {
+    let source = "func signed (reg0: i16, reg1: i16): i16 {
+block0:
+  reg2 = divide reg0 reg1
+  reg3 = shr reg0 reg1
+  reg4 = add reg2 reg3
+  ret reg4
+}";
+    // -7 / 2 = -3, -7 >> 2 = -2
+    let result = run_source(source, "signed", &[RuntimeValue::I16(-7), RuntimeValue::I16(2)]);
+    assert_eq!(result, Ok(Some(RuntimeValue::I16(-5))));
+}
+
+#[test]
+fn float_arithmetic_and_compare() {
+    let source = "func float (reg0: f64, reg1: f64): u8 {
+block0:
+  reg2 = fmul reg0 reg1
+  reg3 = fsub reg2 reg0
+  reg4 = fcmp gt reg3 reg1
+  ret reg4
+}";
+    let result = run_source(source, "float", &[RuntimeValue::F64(1.5), RuntimeValue::F64(4.0)]);
+    assert_eq!(result, Ok(Some(RuntimeValue::U8(1))));
+    let result = run_source(source, "float", &[RuntimeValue::F64(0.5), RuntimeValue::F64(4.0)]);
+    assert_eq!(result, Ok(Some(RuntimeValue::U8(0))));
+}
+
+#[test]
+fn convert_between_types() {
+    let source = "func convert (reg0: i32): i16 {
+block0:
+  reg1 = to.u8 reg0
+  reg2 = to.f32 reg1
+  reg3 = fadd reg2 reg2
+  reg4 = to.i16 reg3
+  ret reg4
+}";
+    // 300 truncate to 44 in u8.
+    let result = run_source(source, "convert", &[RuntimeValue::I32(300)]);
+    assert_eq!(result, Ok(Some(RuntimeValue::I16(88))));
+}
+
+#[test]
+fn loop_with_phi() {
+    let source = "func sum (reg0: u32): u32 {
+block0:
+  reg1 = sub reg0 reg0
+  jump block1
+block1:
+  reg2 = phi [block0 reg1, block2 reg6]
+  reg3 = phi [block0 reg1, block2 reg5]
+  reg4 = icmp lt reg3 reg0
+  brif reg4 block2 block3
+block2:
+  reg5 = addi reg3 1
+  reg6 = add reg2 reg5
+  jump block1
+block3:
+  ret reg2
+}";
+    let result = run_source(source, "sum", &[RuntimeValue::U32(100)]);
+    assert_eq!(result, Ok(Some(RuntimeValue::U32(5050))));
+}
+
+#[test]
+fn call_function_in_module() {
+    let source = "func double (reg0: u16): u16 {
+block0:
+  reg1 = add reg0 reg0
+  ret reg1
+}
+func caller (reg0: u16): u16 {
+block0:
+  reg1 = call func double(reg0)
+  reg2 = call func double(reg1)
+  ret reg2
+}";
+    let result = run_source(source, "caller", &[RuntimeValue::U16(5)]);
+    assert_eq!(result, Ok(Some(RuntimeValue::U16(20))));
+}
+
+fn build_factorial_module() -> Module {
+    let mut module = Module::new();
+    let func_id = module.declar_function("factorial");
+    let func = module.get_mut_function(func_id).unwrap();
+    let reg0 = func.def_func_param(ValueType::U64);
+    func.set_return_type(ValueType::U64);
+    let func_ref = module.declar_function_in_function(func_id, func_id);
+    let func = module.get_mut_function(func_id).unwrap();
+    let block0 = func.create_block();
+    let block1 = func.create_block();
+    let block2 = func.create_block();
+    let mut builder = FunctionBuilder::new(func);
+    builder.switch_to_block(block0);
+    let one = builder.iconst_inst(vec![1], ValueType::U64);
+    let is_base = builder.icmp_inst(CmpFlag::LtEq, [reg0, one]);
+    builder.brif_inst(is_base, block1, block2);
+    builder.switch_to_block(block1);
+    builder.ret_inst(Some(one));
+    builder.switch_to_block(block2);
+    let next = builder.sub_imm_inst(reg0, Immediate::U64(1));
+    let sub_result = builder.call_inst(vec![next], func_ref).unwrap();
+    let result = builder.mul_inst([reg0, sub_result]);
+    builder.ret_inst(Some(result));
+    module
+}
+
+#[test]
+fn recursive_call() {
+    let module = build_factorial_module();
+    let result = interpret(&module, "factorial", &[RuntimeValue::U64(10)]);
+    assert_eq!(result, Ok(Some(RuntimeValue::U64(3628800))));
+}
+
+#[test]
+fn recursive_call_exceed_depth_limit() {
+    let module = build_factorial_module();
+    let mut interpreter = Interpreter::new(&module);
+    interpreter.set_call_depth_limit(8);
+    let result = interpreter.run("factorial", &[RuntimeValue::U64(10)]);
+    assert_eq!(result.unwrap_err().kind, RuntimeErrorKind::CallDepthExceeded(8));
+}
+
+#[test]
+fn stack_memory_store_and_load() {
+    let source = "func stack (reg0: i32): u8 {
+block0:
+  reg1 = stackalloc i32, size 8, align 4
+  store reg0 [reg1, 4]
+  reg2 = load u8 [reg1, 5]
+  ret reg2
+}";
+    let result = run_source(source, "stack", &[RuntimeValue::I32(0x1234)]);
+    assert_eq!(result, Ok(Some(RuntimeValue::U8(0x12))));
+}
+
+#[test]
+fn stack_memory_out_of_bounds() {
+    let source = "func stack (reg0: i32): i32 {
+block0:
+  reg1 = stackalloc i32, size 4, align 4
+  store reg0 [reg1, 2]
+  ret reg0
+}";
+    let result = run_source(source, "stack", &[RuntimeValue::I32(1)]);
+    assert!(matches!(result, Err(RuntimeErrorKind::OutOfBounds { size: 4, .. })));
+}
+
+#[test]
+fn stack_alloc_exceed_memory_limit() {
+    let source = "func huge (reg0: i32): i32 {
+block0:
+  reg1 = stackalloc i32, size 4000000000, align 4
+  ret reg0
+}";
+    let result = run_source(source, "huge", &[RuntimeValue::I32(1)]);
+    assert!(matches!(result, Err(RuntimeErrorKind::OutOfMemory { .. })));
+}
+
+#[test]
+fn relocation_out_of_data_is_error() {
+    let mut module = parse("func empty () {\nblock0:\n  ret\n}").unwrap();
+    let target = module.define_data(
+        "target",
+        DataDescription {
+            size: 8,
+            align: 8,
+            mutable: false,
+            init: DataInit::Zeros,
+            relocations: Vec::new(),
+        },
+    );
+    module.define_data(
+        "table",
+        DataDescription {
+            size: 8,
+            align: 8,
+            mutable: false,
+            init: DataInit::Zeros,
+            relocations: vec![DataRelocation {
+                offset: 4,
+                name: ExternalName::UserDefName {
+                    namespace: UserDefNamespace::Data,
+                    value: target.0,
+                },
+                addend: 0,
+            }],
+        },
+    );
+    let mut interpreter = Interpreter::new(&module);
+    assert!(matches!(
+        interpreter.run("empty", &[]).map_err(|error| error.kind),
+        Err(RuntimeErrorKind::OutOfBounds { size: 8, .. })
+    ));
+}
+
+#[test]
+fn load_from_invalid_address() {
+    let source = read_case("baseline", "mem_inst_base", "case.zhu");
+    let result = run_source(&source, "mem_inst_base", &[RuntimeValue::U8(0)]);
+    assert_eq!(result, Err(RuntimeErrorKind::OutOfBounds { address: 0, size: 2 }));
+}
+
+#[test]
+fn global_load_and_store() {
+    // data of baseline case is empty, give it a mutable byte to load and store.
+    let source =
+        read_case("baseline", "global_inst", "case.zhu").replace("{ size 0, align 1 }", "{ size 1, align 1, mut }");
+    let module = parse(&source).unwrap();
+    let mut interpreter = Interpreter::new(&module);
+    interpreter.write_data("global_data", &[5]).unwrap();
+    interpreter.run("global_inst", &[RuntimeValue::U8(3)]).unwrap();
+    assert_eq!(interpreter.read_data("global_data").unwrap()[0], 8);
+    // data keep value between runs.
+    interpreter.run("global_inst", &[RuntimeValue::U8(3)]).unwrap();
+    assert_eq!(interpreter.read_data("global_data").unwrap()[0], 11);
+}
+
+#[test]
+fn data_object_initializer_and_relocation() {
+    let module = parse(&read_case("baseline", "data_object", "case.zhu")).unwrap();
+    let mut interpreter = Interpreter::new(&module);
+    // relocation point to second byte of message.
+    let result = interpreter.run("data_object", &[]).unwrap();
+    assert_eq!(result, Some(RuntimeValue::U8(0x69)));
+    assert_eq!(interpreter.read_data("message").unwrap(), &[0x68, 0x69, 0x21, 0x00]);
+    assert_eq!(interpreter.read_data("table").unwrap().len(), 16);
+    // relocation to function is left zero.
+    assert_eq!(&interpreter.read_data("table").unwrap()[8..], &[0; 8]);
+    assert!(matches!(
+        interpreter.write_data("message", &[0; 5]),
+        Err(RuntimeErrorKind::OutOfBounds { size: 5, .. })
+    ));
+}
+
+#[test]
+fn store_to_immutable_data() {
+    let source = "counter = @data { size 4, align 4 }
+func bump (reg0: u32) {
+  greg0 = @global symbol counter
+block0:
+  gstore reg0 [greg0, 0]
+  ret
+}";
+    let result = run_source(source, "bump", &[RuntimeValue::U32(1)]);
+    assert!(matches!(result, Err(RuntimeErrorKind::WriteReadOnly { .. })));
+    let mutable_source = source.replace("align 4 }", "align 4, mut }");
+    assert_eq!(run_source(&mutable_source, "bump", &[RuntimeValue::U32(1)]), Ok(None));
+}
+
+#[test]
+fn division_by_zero() {
+    let source = "func div (reg0: u32, reg1: u32): u32 {
+block0:
+  reg2 = divide reg0 reg1
+  ret reg2
+}";
+    let module = parse(source).unwrap();
+    let error = interpret(&module, "div", &[RuntimeValue::U32(1), RuntimeValue::U32(0)]).unwrap_err();
+    assert_eq!(error.kind, RuntimeErrorKind::DivisionByZero);
+    assert_eq!(error.to_string(), "[Error]: func div, inst0: division by zero");
+}
+
+#[test]
+fn argument_mismatch() {
+    let source = "func id (reg0: u32): u32 {
+block0:
+  ret reg0
+}";
+    let result = run_source(source, "id", &[]);
+    assert_eq!(
+        result,
+        Err(RuntimeErrorKind::ArgumentCountMismatch { expect: 1, actual: 0 })
+    );
+    let result = run_source(source, "id", &[RuntimeValue::U8(1)]);
+    assert_eq!(result, Err(RuntimeErrorKind::ArgumentTypeMismatch(0)));
+}
+
+#[test]
+fn infinite_loop_exceed_step_limit() {
+    let module = parse(&read_case("fixtures", "lcm_cmu_example", "original.zhu")).unwrap();
+    let mut interpreter = Interpreter::new(&module);
+    interpreter.set_step_limit(100);
+    let result = interpreter.run("lcm_cmu_example", &[RuntimeValue::U8(1)]);
+    assert_eq!(result.unwrap_err().kind, RuntimeErrorKind::StepLimitExceeded(100));
+    let result = interpreter.run("lcm_cmu_example", &[RuntimeValue::U8(0)]);
+    assert_eq!(result, Ok(None));
+}
+
+#[test]
+fn optimizations_preserve_semantic() {
+    let u8_args: Vec<Vec<RuntimeValue>> = [(0, 0), (0, 1), (1, 0), (3, 5), (255, 1)]
+        .into_iter()
+        .map(|(a, b)| vec![RuntimeValue::U8(a), RuntimeValue::U8(b)])
+        .collect();
+    assert_opti_preserve_semantic("dce_mem_oneline", "dce_mem_oneline", &u8_args, dce);
+    assert_opti_preserve_semantic("dce_wihtout_mem_oneline", "dce_wihtout_mem_oneline", &u8_args, dce);
+    assert_opti_preserve_semantic("gvn_diamond", "gvn_func", &u8_args, gvn);
+    assert_opti_preserve_semantic("gvn_do_while_loop", "gvn_do_while_loop", &u8_args, gvn);
+    assert_opti_preserve_semantic(
+        "lcm_cmu_example",
+        "lcm_cmu_example",
+        &[vec![RuntimeValue::U8(0)], vec![RuntimeValue::U8(1)]],
+        lcm,
+    );
+    assert_opti_preserve_semantic(
+        "lcm_diamond",
+        "lcm_diamond",
+        &[vec![RuntimeValue::I16(0)], vec![RuntimeValue::I16(-3)]],
+        lcm,
+    );
+    assert_opti_preserve_semantic("mem2reg_diamond", "mem2reg_diamond", &u8_args, mem2reg);
+    assert_opti_preserve_semantic(
+        "mem2reg_loop",
+        "mem2reg_loop",
+        &[vec![RuntimeValue::U32(0)], vec![RuntimeValue::U32(10)]],
+        mem2reg,
+    );
+    assert_opti_preserve_semantic(
+        "sccp_branch_fold",
+        "sccp_branch_fold",
+        &[vec![RuntimeValue::I32(0)], vec![RuntimeValue::I32(-5)]],
+        sccp,
+    );
+    assert_opti_preserve_semantic(
+        "sccp_loop",
+        "sccp_loop",
+        &[vec![RuntimeValue::U8(0)], vec![RuntimeValue::U8(3)]],
+        sccp,
+    );
+    assert_opti_preserve_semantic(
+        "out_of_ssa_swap",
+        "out_of_ssa_swap",
+        &[
+            vec![RuntimeValue::I32(1), RuntimeValue::I32(2), RuntimeValue::I32(1)],
+            vec![RuntimeValue::I32(1), RuntimeValue::I32(2), RuntimeValue::I32(2)],
+            vec![RuntimeValue::I32(7), RuntimeValue::I32(-4), RuntimeValue::I32(5)],
+        ],
+        out_of_ssa,
+    );
+    assert_opti_preserve_semantic(
+        "out_of_ssa_lost_copy",
+        "out_of_ssa_lost_copy",
+        &[vec![RuntimeValue::I32(0)], vec![RuntimeValue::I32(5)]],
+        out_of_ssa,
+    );
+    assert_opti_preserve_semantic("gvn_do_while_loop", "gvn_do_while_loop", &u8_args, out_of_ssa);
+    assert_opti_preserve_semantic("mem2reg_loop", "mem2reg_loop", &[vec![RuntimeValue::U32(10)]], |func| {
+        mem2reg(func);
+        out_of_ssa(func);
+    });
+}
+
+#[test]
+fn mem2reg_load_before_store_read_zero() {
+    let source = "func uninit (reg0: i32): i32 {
+block0:
+  reg1 = stackalloc i32, size 4, align 4
+  brif reg0 block1 block2
+block1:
+  store reg0 [reg1, 0]
+  jump block2
+block2:
+  reg2 = load i32 [reg1, 0]
+  ret reg2
+}";
+    let mut module = parse(source).unwrap();
+    let func_id = module.get_module_id_by_symbol("uninit").unwrap().to_func_id();
+    mem2reg(module.get_mut_function(func_id).unwrap());
+    let func = module.get_function(func_id).unwrap();
+    assert!(func
+        .insts()
+        .into_iter()
+        .all(|inst| !matches!(func.get_inst_data(inst), InstructionData::StackAlloc { .. })));
+    assert!(verify_module(&module).is_empty());
+    assert_eq!(
+        interpret(&module, "uninit", &[RuntimeValue::I32(0)]),
+        Ok(Some(RuntimeValue::I32(0)))
+    );
+    assert_eq!(
+        interpret(&module, "uninit", &[RuntimeValue::I32(7)]),
+        Ok(Some(RuntimeValue::I32(7)))
+    );
+}
+
+#[test]
+fn mem2reg_remove_dead_phi_cycle() {
+    let source = "func dead_phi_cycle (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = stackalloc i32, size 4, align 4
+  jump block1
+block1:
+  reg3 = phi [block0 reg1, block3 reg5]
+  reg4 = icmp lt reg3 reg0
+  brif reg4 block2 block3
+block2:
+  store reg3 [reg2, 0]
+  jump block3
+block3:
+  reg5 = addi reg3 1
+  reg6 = icmp lt reg5 reg0
+  brif reg6 block1 block4
+block4:
+  ret reg3
+}";
+    let mut module = parse(source).unwrap();
+    let func_id = module.get_module_id_by_symbol("dead_phi_cycle").unwrap().to_func_id();
+    mem2reg(module.get_mut_function(func_id).unwrap());
+    let func = module.get_function(func_id).unwrap();
+    let phi_count = func
+        .insts()
+        .into_iter()
+        .filter(|inst| matches!(func.get_inst_data(*inst), InstructionData::Phi { .. }))
+        .count();
+    assert_eq!(phi_count, 1);
+    assert!(verify_module(&module).is_empty());
+    assert_eq!(
+        interpret(&module, "dead_phi_cycle", &[RuntimeValue::I32(3), RuntimeValue::I32(0)]),
+        Ok(Some(RuntimeValue::I32(2)))
+    );
+}
+
+#[test]
+fn mem2reg_not_promote_access_wider_than_slot() {
+    let source = "func wide_store (reg0: i64): i64 {
+block0:
+  reg1 = stackalloc i32, size 4, align 4
+  store reg0 [reg1, 0]
+  reg2 = load i64 [reg1, 0]
+  ret reg2
+}";
+    let mut module = parse(source).unwrap();
+    let func_id = module.get_module_id_by_symbol("wide_store").unwrap().to_func_id();
+    mem2reg(module.get_mut_function(func_id).unwrap());
+    let func = module.get_function(func_id).unwrap();
+    assert!(func
+        .insts()
+        .into_iter()
+        .any(|inst| matches!(func.get_inst_data(inst), InstructionData::StackAlloc { .. })));
+    assert!(interpret(&module, "wide_store", &[RuntimeValue::I64(1)]).is_err());
+}
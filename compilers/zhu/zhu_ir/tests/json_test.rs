@@ -0,0 +1,59 @@
+mod round_trip;
+
+use round_trip::{assert_module_eq, read_baseline_sources};
+use zsh_ir::formatter::format;
+use zsh_ir::frontend::parse;
+use zsh_ir::json::{from_json, to_json, to_json_pretty, JsonError};
+
+#[test]
+fn round_trip_baseline_modules() {
+    for (name, source) in read_baseline_sources() {
+        let module = parse(&source).unwrap();
+        let json = to_json(&module);
+        let decoded = from_json(&json).unwrap_or_else(|error| panic!("[Error]: case {} failed: {}", name, error));
+        assert_module_eq(&module, &decoded);
+        assert_eq!(
+            format(&decoded),
+            format(&module),
+            "[Error]: case {} changed after round trip.",
+            name
+        );
+        // output is stable, pretty output decode to the same module.
+        assert_eq!(to_json(&decoded), json, "[Error]: case {} json is not stable.", name);
+        assert_module_eq(&module, &from_json(&to_json_pretty(&module)).unwrap());
+    }
+}
+
+#[test]
+fn json_shape_of_module() {
+    let module = parse(
+        "seed = @data { size 4, align 4, init [0x01 0x02] }
+func id (reg0: u8): u8 {
+block0:
+  ret reg0
+}
+",
+    )
+    .unwrap();
+    let json = to_json(&module);
+    assert!(json
+        .starts_with(r#"{"version":1,"module":{"functions":{"0":{"signature":{"params":["U8"],"return_type":"U8"}"#));
+    assert!(json.contains(r#""insts":{"0":{"Ret":{"opcode":"Ret","value":0}}}"#));
+    assert!(json.contains(
+        r#""data_objects":{"0":{"size":4,"align":4,"mutable":false,"init":{"Bytes":[1,2]},"relocations":[]}}"#
+    ));
+    assert!(json.ends_with(r#""symbol_table":{"id":{"Func":0},"seed":{"Data":0}}}}"#));
+}
+
+#[test]
+fn reject_invalid_json() {
+    assert!(matches!(
+        from_json(r#"{"version":2,"module":{}}"#),
+        Err(JsonError::UnsupportedVersion(2))
+    ));
+    assert!(matches!(
+        from_json(r#"{"version":1,"module":{}}"#),
+        Err(JsonError::Serde(_))
+    ));
+    assert!(matches!(from_json("func"), Err(JsonError::Serde(_))));
+}
@@ -0,0 +1,222 @@
+use zsh_ir::codegen::regalloc::{linear_scan, Assignment, RegisterClass, RegisterTarget};
+use zsh_ir::codegen::riscv64::register::Riscv64Registers;
+use zsh_ir::entities::block::Block;
+use zsh_ir::entities::module::Module;
+use zsh_ir::entities::r#type::ValueType;
+use zsh_ir::entities::value::Value;
+use zsh_ir::frontend::parse;
+use zsh_ir::pass::analysis::cfg::cfg_anylysis;
+use zsh_ir::pass::analysis::liveness::{liveness_analysis, Liveness};
+use zsh_ir::pass::analysis::rpo::revrese_post_order_analysis;
+use zsh_ir::pass::FormatTable;
+
+const SUM_TO: &str = "func sum_to (reg0: u32): u32 {
+block0:
+  reg1 = uconst u32 0
+  reg2 = uconst u32 1
+  jump block1
+block1:
+  reg3 = phi [block0 reg1, block2 reg5]
+  reg4 = phi [block0 reg2, block2 reg6]
+  reg7 = icmp gt reg4 reg0
+  brif reg7 block3 block2
+block2:
+  reg5 = add reg3 reg4
+  reg6 = addi reg4 1
+  jump block1
+block3:
+  ret reg3
+}
+";
+
+fn compute_liveness(module: &Module, name: &str) -> Liveness {
+    let func_id = module.get_module_id_by_symbol(name).unwrap().to_func_id();
+    let func = module.get_function(func_id).unwrap();
+    let cfg = cfg_anylysis(func);
+    let rpo = revrese_post_order_analysis(&cfg);
+    liveness_analysis(func, &cfg, &rpo)
+}
+
+fn sorted(values: &std::collections::HashSet<Value>) -> Vec<u32> {
+    let mut values = values.iter().map(|value| value.0).collect::<Vec<_>>();
+    values.sort();
+    values
+}
+
+/// Target with only two registers of each class, used to force spill.
+struct TinyTarget;
+
+impl RegisterTarget for TinyTarget {
+    fn register_class(&self, ty: &ValueType) -> RegisterClass {
+        match ty {
+            ValueType::F32 | ValueType::F64 => RegisterClass::Float,
+            _ => RegisterClass::Int,
+        }
+    }
+    fn allocatable_registers(&self, class: RegisterClass) -> Vec<&'static str> {
+        match class {
+            RegisterClass::Int => vec!["r0", "r1"],
+            RegisterClass::Float => vec!["f0", "f1"],
+        }
+    }
+}
+
+#[test]
+fn live_sets_of_loop() {
+    let module = parse(SUM_TO).unwrap();
+    let liveness = compute_liveness(&module, "sum_to");
+    // parameters are defined in entry block.
+    assert!(liveness.get_live_in(Block(0)).is_empty());
+    assert_eq!(sorted(liveness.get_live_out(Block(0))), vec![0, 1, 2]);
+    assert_eq!(sorted(liveness.get_live_in(Block(1))), vec![0]);
+    assert_eq!(sorted(liveness.get_live_out(Block(1))), vec![0, 3, 4]);
+    assert_eq!(sorted(liveness.get_live_in(Block(2))), vec![0, 3, 4]);
+    assert_eq!(sorted(liveness.get_live_out(Block(2))), vec![0, 5, 6]);
+    assert_eq!(sorted(liveness.get_live_in(Block(3))), vec![3]);
+    assert!(liveness.get_live_out(Block(3)).is_empty());
+}
+
+#[test]
+fn intervals_of_loop() {
+    let module = parse(SUM_TO).unwrap();
+    let liveness = compute_liveness(&module, "sum_to");
+    assert_eq!(liveness.get_linear_order().len(), 4);
+    let interval_of = |value: u32| liveness.get_interval(Value(value)).unwrap();
+    // parameter is live across whole loop.
+    let (loop_start, _) = liveness.get_block_range(Block(1));
+    let (_, body_end) = liveness.get_block_range(Block(2));
+    assert!(interval_of(0).covers(loop_start));
+    assert!(interval_of(0).covers(body_end - 1));
+    // phi operands from back edge do not overlap phi results after their last use.
+    assert!(!interval_of(7).overlaps(interval_of(5)));
+    assert!(interval_of(3).overlaps(interval_of(4)));
+    assert!(interval_of(5).overlaps(interval_of(6)));
+    for block in liveness.get_linear_order() {
+        let (start, end) = liveness.get_block_range(*block);
+        for value in liveness.get_live_in(*block) {
+            assert!(liveness.get_interval(*value).unwrap().covers(start));
+        }
+        for value in liveness.get_live_out(*block) {
+            assert!(liveness.get_interval(*value).unwrap().covers(end - 1));
+        }
+    }
+}
+
+#[test]
+fn dead_value_lives_at_definition() {
+    let module = parse(
+        "func dead (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = add reg0 reg1
+  reg3 = mul reg0 reg0
+  ret reg3
+}
+",
+    )
+    .unwrap();
+    let liveness = compute_liveness(&module, "dead");
+    assert_eq!(liveness.get_block_range(Block(0)), (0, 4));
+    assert_eq!(liveness.get_interval(Value(0)).unwrap().get_ranges(), &[(0, 2)]);
+    assert_eq!(liveness.get_interval(Value(1)).unwrap().get_ranges(), &[(0, 1)]);
+    assert_eq!(liveness.get_interval(Value(2)).unwrap().get_ranges(), &[(1, 2)]);
+    assert_eq!(liveness.get_interval(Value(3)).unwrap().get_ranges(), &[(2, 3)]);
+}
+
+#[test]
+fn format_liveness_table() {
+    let module = parse(
+        "func dead (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = add reg0 reg1
+  reg3 = mul reg2 reg0
+  ret reg3
+}
+",
+    )
+    .unwrap();
+    let liveness = compute_liveness(&module, "dead");
+    let func = module
+        .get_function(module.get_module_id_by_symbol("dead").unwrap().to_func_id())
+        .unwrap();
+    assert_eq!(
+        liveness.format_table(func, &module),
+        "========== Liveness ==========
+Block0: [0, 4)
+\tLive In:
+\tLive Out:
+Intervals:
+\treg0: [0, 2)
+\treg1: [0, 1)
+\treg2: [1, 2)
+\treg3: [2, 3)
+"
+    );
+}
+
+#[test]
+fn linear_scan_reuse_registers() {
+    let module = parse(SUM_TO).unwrap();
+    let liveness = compute_liveness(&module, "sum_to");
+    let func = module
+        .get_function(module.get_module_id_by_symbol("sum_to").unwrap().to_func_id())
+        .unwrap();
+    let allocation = linear_scan(func, &liveness, &Riscv64Registers);
+    assert_eq!(allocation.get_spill_slot_count(), 0);
+    let assignments = allocation.get_assignments();
+    assert_eq!(assignments.len(), 8);
+    for (value, assignment) in assignments {
+        for (other, other_assignment) in assignments {
+            if value != other && assignment == other_assignment {
+                let interval = liveness.get_interval(*value).unwrap();
+                let other_interval = liveness.get_interval(*other).unwrap();
+                assert!(interval.end() <= other_interval.start() || other_interval.end() <= interval.start());
+            }
+        }
+    }
+    assert_eq!(allocation.get_assignment(Value(0)), Some(Assignment::Register("s1")));
+}
+
+#[test]
+fn linear_scan_spill_longest_interval() {
+    let module = parse(
+        "func pressure (reg0: i64, reg1: i64, reg2: i64, reg3: f64, reg4: f64): i64 {
+block0:
+  reg5 = add reg0 reg1
+  reg6 = add reg5 reg2
+  reg7 = fadd reg3 reg4
+  reg8 = to.i64 reg7
+  reg9 = add reg6 reg8
+  reg10 = add reg9 reg0
+  ret reg10
+}
+",
+    )
+    .unwrap();
+    let liveness = compute_liveness(&module, "pressure");
+    let func = module
+        .get_function(module.get_module_id_by_symbol("pressure").unwrap().to_func_id())
+        .unwrap();
+    let allocation = linear_scan(func, &liveness, &TinyTarget);
+    // reg0 lives longest, it is spilled when reg2 need a register.
+    assert_eq!(allocation.get_assignment(Value(0)), Some(Assignment::Spill(0)));
+    assert_eq!(allocation.get_assignment(Value(3)), Some(Assignment::Register("f0")));
+    assert_eq!(allocation.get_assignment(Value(4)), Some(Assignment::Register("f1")));
+    assert_eq!(allocation.get_spill_slot_count(), 1);
+    assert_eq!(
+        allocation.format_table(func, &module),
+        "========== Register Allocation ==========
+reg0: spill0 [0, 6)
+reg1: r1 [0, 1)
+reg2: r0 [0, 2)
+reg3: f0 [0, 3)
+reg4: f1 [0, 3)
+reg5: r1 [1, 2)
+reg6: r1 [2, 5)
+reg7: f1 [3, 4)
+reg8: r0 [4, 5)
+reg9: r0 [5, 6)
+reg10: r0 [6, 7)
+Spill slots: 1
+"
+    );
+}
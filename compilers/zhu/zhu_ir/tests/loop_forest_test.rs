@@ -0,0 +1,136 @@
+use std::fs::read_to_string;
+
+use zsh_ir::entities::block::Block;
+use zsh_ir::entities::function::Function;
+use zsh_ir::entities::module::Module;
+use zsh_ir::entities::runtime_value::RuntimeValue;
+use zsh_ir::frontend::parse;
+use zsh_ir::interpreter::Interpreter;
+use zsh_ir::pass::analysis::cfg::cfg_anylysis;
+use zsh_ir::pass::analysis::domtree::domtree_analysis;
+use zsh_ir::pass::analysis::loop_forest::{loop_forest_analysis, LoopForest, LoopId};
+use zsh_ir::pass::analysis::verifier::verify_module;
+use zsh_ir::pass::manager::{AnalysisCache, AnalysisKind, PassManager};
+use zsh_ir::pass::opt::licm::natural_loop::natural_loop_analysis;
+use zsh_ir::pass::opt::loop_simplify::LoopSimplifyPass;
+use zsh_ir::pass::{FormatTable, OptiPass};
+
+fn read_nest_source() -> String {
+    read_to_string("tests/fixtures/loop_simplify_nest/original.zhu").unwrap()
+}
+
+fn get_function<'a>(module: &'a Module, func_name: &str) -> &'a Function {
+    let func_id = module.get_module_id_by_symbol(func_name).unwrap().to_func_id();
+    module.get_function(func_id).unwrap()
+}
+
+fn analyze(func: &Function) -> LoopForest {
+    let cfg = cfg_anylysis(func);
+    let dom = domtree_analysis(func, &cfg);
+    loop_forest_analysis(&natural_loop_analysis(&dom, &cfg))
+}
+
+#[test]
+fn loop_forest_of_nested_loops() {
+    let module = parse(&read_nest_source()).unwrap();
+    let func = get_function(&module, "loop_simplify_nest");
+    let forest = analyze(func);
+    assert_eq!(forest.get_loop_ids().len(), 2);
+    assert_eq!(forest.get_top_level_loops(), vec![LoopId(0)]);
+    assert_eq!(forest.get_innermost_loops(), vec![LoopId(1)]);
+    let outer = forest.get_loop(LoopId(0));
+    let inner = forest.get_loop(LoopId(1));
+    assert_eq!(outer.header, Block(2));
+    assert_eq!(outer.children, vec![LoopId(1)]);
+    // natural loops of two back edges to block3 are merged.
+    assert_eq!(inner.header, Block(3));
+    assert_eq!(inner.latches, vec![Block(4), Block(5)]);
+    assert_eq!(inner.parent, Some(LoopId(0)));
+    assert_eq!(forest.get_loop_by_header(Block(3)), Some(LoopId(1)));
+    assert_eq!(forest.get_loop_of_block(Block(6)), Some(LoopId(0)));
+    assert_eq!(forest.get_loop_of_block(Block(5)), Some(LoopId(1)));
+    assert_eq!(forest.get_loop_of_block(Block(7)), None);
+    assert_eq!(forest.get_loop_depth(Block(0)), 0);
+    assert_eq!(forest.get_loop_depth(Block(2)), 1);
+    assert_eq!(forest.get_loop_depth(Block(4)), 2);
+    assert_eq!(
+        forest.format_table(func, &module),
+        "========== Loop Forest ==========
+loop0: header block2, latches [block6], parent none, blocks [block2, block3, block4, block5, block6]
+  loop1: header block3, latches [block4, block5], parent loop0, blocks [block3, block4, block5]
+"
+    );
+}
+
+#[test]
+fn loop_forest_not_in_canonical_form() {
+    let module = parse(&read_nest_source()).unwrap();
+    let func = get_function(&module, "loop_simplify_nest");
+    let cfg = cfg_anylysis(func);
+    let forest = analyze(func);
+    let outer = forest.get_loop(LoopId(0));
+    let inner = forest.get_loop(LoopId(1));
+    assert_eq!(outer.get_entering_blocks(&cfg), vec![Block(0), Block(1)]);
+    assert_eq!(outer.get_preheader(&cfg), None);
+    assert!(outer.has_dedicated_exits(&cfg));
+    assert_eq!(inner.get_preheader(&cfg), Some(Block(2)));
+    assert_eq!(inner.get_exiting_blocks(&cfg), vec![Block(3), Block(5)]);
+    assert_eq!(inner.get_exit_blocks(&cfg), vec![Block(6), Block(7)]);
+    assert!(!inner.has_dedicated_exits(&cfg));
+    assert!(!outer.is_simplified(&cfg));
+    assert!(!inner.is_simplified(&cfg));
+}
+
+#[test]
+fn simplify_loops_into_canonical_form() {
+    let original = parse(&read_nest_source()).unwrap();
+    let mut module = parse(&read_nest_source()).unwrap();
+    let func_id = module
+        .get_module_id_by_symbol("loop_simplify_nest")
+        .unwrap()
+        .to_func_id();
+    let mut pass = LoopSimplifyPass::new();
+    pass.process(module.get_mut_function(func_id).unwrap());
+    assert_eq!(verify_module(&module), vec![]);
+    assert_eq!(pass.get_inserted_preheader_count(), 1);
+    assert_eq!(pass.get_merged_latch_count(), 1);
+    assert_eq!(pass.get_inserted_exit_count(), 2);
+    let func = get_function(&module, "loop_simplify_nest");
+    let cfg = cfg_anylysis(func);
+    let forest = analyze(func);
+    assert_eq!(forest.get_loop_ids().len(), 2);
+    for id in forest.get_loop_ids() {
+        assert!(forest.get_loop(id).is_simplified(&cfg));
+    }
+    for args in [[0, 5], [3, 5], [2, 9], [7, 1], [-4, 6]] {
+        let args = args.map(RuntimeValue::I32);
+        assert_eq!(
+            Interpreter::new(&original)
+                .run("loop_simplify_nest", &args)
+                .map_err(|error| error.kind),
+            Interpreter::new(&module)
+                .run("loop_simplify_nest", &args)
+                .map_err(|error| error.kind),
+            "[Error]: result differ with args {:?}.",
+            args
+        );
+    }
+}
+
+#[test]
+fn loop_forest_in_analysis_cache() {
+    let mut module = parse(&read_nest_source()).unwrap();
+    let func_id = module
+        .get_module_id_by_symbol("loop_simplify_nest")
+        .unwrap()
+        .to_func_id();
+    let mut cache = AnalysisCache::new();
+    cache.require(module.get_function(func_id).unwrap(), &[AnalysisKind::LoopForest]);
+    assert!(cache.is_cached(AnalysisKind::NaturalLoops));
+    assert_eq!(cache.loop_forest().get_loop_ids().len(), 2);
+    PassManager::from_pipeline("loop-simplify")
+        .unwrap()
+        .run_on_module(&mut module)
+        .unwrap();
+    assert_eq!(verify_module(&module), vec![]);
+}
@@ -13,9 +13,16 @@ use zsh_ir::pass::opt::dce::post_domtree::post_domtree_analysis;
 use zsh_ir::pass::opt::licm::natural_loop::natural_loop_analysis;
 
 use zsh_ir::pass::opt::dce::dce_pass;
+use zsh_ir::pass::opt::dse::dse_pass;
 use zsh_ir::pass::opt::gvn::gvn_pass;
 use zsh_ir::pass::opt::lcm::lcm_opt;
 use zsh_ir::pass::opt::licm::licm_pass;
+use zsh_ir::pass::opt::loop_simplify::loop_simplify_pass;
+use zsh_ir::pass::opt::mem2reg::mem2reg_pass;
+use zsh_ir::pass::opt::out_of_ssa::out_of_ssa_pass;
+use zsh_ir::pass::opt::sccp::sccp_pass;
+use zsh_ir::pass::opt::strength_reduce::strength_reduce_pass;
+use zsh_ir::pass::opt::unroll::{loop_unroll_pass, UnrollConfig};
 
 fn get_folder_path_by_case_name(name: &str) -> PathBuf {
     current_dir().unwrap().join("tests/fixtures").join(name)
@@ -40,7 +47,7 @@ fn read_expected_file_from_namespec(test_case: &str, namespace: &str) -> String
 fn compare_test_case(test_case: &str, namespace: &str, process: impl FnOnce(Module) -> Module) {
     let original_source = read_original_file_from_case_name(test_case);
     let expected_source = read_expected_file_from_namespec(test_case, namespace);
-    let result = process(parse(&original_source));
+    let result = process(parse(&original_source).unwrap());
     let result_string = format(&result);
     assert_eq!(
         result_string, expected_source,
@@ -176,3 +183,154 @@ generate_test_case!(
         module
     })
 );
+
+fn mem2reg_pass_wrapper(module: &mut Module, func_name: &str) {
+    let module_id = module.get_module_id_by_symbol(func_name).unwrap();
+    let func_id = module_id.to_func_id();
+    let func = module.get_mut_function(func_id).unwrap();
+    let cfg = cfg_anylysis(func);
+    let dom = domtree_analysis(func, &cfg);
+    mem2reg_pass(func, &cfg, &dom);
+}
+
+generate_test_case!(
+    (mem2reg, mem2reg_diamond, |mut module| {
+        mem2reg_pass_wrapper(&mut module, "mem2reg_diamond");
+        module
+    }),
+    (mem2reg, mem2reg_loop, |mut module| {
+        mem2reg_pass_wrapper(&mut module, "mem2reg_loop");
+        module
+    }),
+    (mem2reg, mem2reg_unreachable, |mut module| {
+        mem2reg_pass_wrapper(&mut module, "mem2reg_unreachable");
+        module
+    })
+);
+
+fn sccp_pass_wrapper(module: &mut Module, func_name: &str) {
+    let module_id = module.get_module_id_by_symbol(func_name).unwrap();
+    let func_id = module_id.to_func_id();
+    let func = module.get_mut_function(func_id).unwrap();
+    let cfg = cfg_anylysis(func);
+    sccp_pass(func, &cfg);
+}
+
+generate_test_case!(
+    (sccp, sccp_branch_fold, |mut module| {
+        sccp_pass_wrapper(&mut module, "sccp_branch_fold");
+        module
+    }),
+    (sccp, sccp_loop, |mut module| {
+        sccp_pass_wrapper(&mut module, "sccp_loop");
+        module
+    }),
+    (sccp, sccp_fold_type, |mut module| {
+        sccp_pass_wrapper(&mut module, "sccp_fold_type");
+        module
+    })
+);
+
+fn out_of_ssa_pass_wrapper(module: &mut Module, func_name: &str) {
+    let module_id = module.get_module_id_by_symbol(func_name).unwrap();
+    let func_id = module_id.to_func_id();
+    let func = module.get_mut_function(func_id).unwrap();
+    let cfg = cfg_anylysis(func);
+    out_of_ssa_pass(func, &cfg);
+}
+
+generate_test_case!(
+    (out_of_ssa, out_of_ssa_swap, |mut module| {
+        out_of_ssa_pass_wrapper(&mut module, "out_of_ssa_swap");
+        module
+    }),
+    (out_of_ssa, out_of_ssa_lost_copy, |mut module| {
+        out_of_ssa_pass_wrapper(&mut module, "out_of_ssa_lost_copy");
+        module
+    })
+);
+
+fn dse_pass_wrapper(module: &mut Module, func_name: &str) {
+    let module_id = module.get_module_id_by_symbol(func_name).unwrap();
+    let func_id = module_id.to_func_id();
+    let func = module.get_mut_function(func_id).unwrap();
+    let cfg = cfg_anylysis(func);
+    let rpo = revrese_post_order_analysis(&cfg);
+    dse_pass(func, &cfg, &rpo);
+}
+
+generate_test_case!(
+    (dse, dse_forward, |mut module| {
+        dse_pass_wrapper(&mut module, "dse_forward");
+        module
+    }),
+    (dse, dse_overwritten, |mut module| {
+        dse_pass_wrapper(&mut module, "dse_overwritten");
+        module
+    }),
+    (dse, dse_call, |mut module| {
+        dse_pass_wrapper(&mut module, "dse_call");
+        module
+    })
+);
+
+fn unroll_pass_wrapper(module: &mut Module, func_name: &str) {
+    let module_id = module.get_module_id_by_symbol(func_name).unwrap();
+    let func_id = module_id.to_func_id();
+    let func = module.get_mut_function(func_id).unwrap();
+    loop_unroll_pass(func, UnrollConfig::default());
+}
+
+generate_test_case!(
+    (unroll, unroll_full, |mut module| {
+        unroll_pass_wrapper(&mut module, "unroll_full");
+        module
+    }),
+    (unroll, unroll_partial, |mut module| {
+        unroll_pass_wrapper(&mut module, "unroll_partial");
+        module
+    }),
+    (unroll, unroll_unreachable, |mut module| {
+        unroll_pass_wrapper(&mut module, "unroll_unreachable");
+        module
+    })
+);
+
+fn loop_simplify_pass_wrapper(module: &mut Module, func_name: &str) {
+    let module_id = module.get_module_id_by_symbol(func_name).unwrap();
+    let func_id = module_id.to_func_id();
+    let func = module.get_mut_function(func_id).unwrap();
+    loop_simplify_pass(func);
+}
+
+generate_test_case!(
+    (loop_simplify, loop_simplify_nest, |mut module| {
+        loop_simplify_pass_wrapper(&mut module, "loop_simplify_nest");
+        module
+    }),
+    (loop_simplify, loop_simplify_unreachable, |mut module| {
+        loop_simplify_pass_wrapper(&mut module, "loop_simplify_unreachable");
+        module
+    })
+);
+
+fn strength_reduce_pass_wrapper(module: &mut Module, func_name: &str) {
+    let module_id = module.get_module_id_by_symbol(func_name).unwrap();
+    let func_id = module_id.to_func_id();
+    let func = module.get_mut_function(func_id).unwrap();
+    let cfg = cfg_anylysis(func);
+    let dom = domtree_analysis(func, &cfg);
+    let natural_loops = natural_loop_analysis(&dom, &cfg);
+    strength_reduce_pass(func, &cfg, &natural_loops);
+}
+
+generate_test_case!(
+    (strength_reduce, strength_reduce_array, |mut module| {
+        strength_reduce_pass_wrapper(&mut module, "strength_reduce_array");
+        module
+    }),
+    (strength_reduce, strength_reduce_unreachable, |mut module| {
+        strength_reduce_pass_wrapper(&mut module, "strength_reduce_unreachable");
+        module
+    })
+);
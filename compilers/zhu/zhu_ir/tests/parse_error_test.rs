@@ -0,0 +1,256 @@
+use zsh_ir::frontend::error::{format_parse_errors, ParseError};
+use zsh_ir::frontend::parse;
+use zsh_ir::frontend::token::TokenKind;
+
+fn parse_errors(source: &str) -> Vec<ParseError> {
+    parse(source).err().expect("source should not be parsed")
+}
+
+#[test]
+fn report_line_column_and_snippet() {
+    let errors = parse_errors(
+        "func test (reg0: i32): i32 {
+block0:
+  reg1 = icmp big reg0 reg0
+  ret reg0
+}",
+    );
+    assert_eq!(errors.len(), 1);
+    let error = &errors[0];
+    assert_eq!((error.line, error.column), (3, 15));
+    assert_eq!(error.found, TokenKind::Identifier);
+    assert_eq!(
+        error.expected,
+        vec![
+            TokenKind::Eq,
+            TokenKind::NotEq,
+            TokenKind::Gt,
+            TokenKind::Gteq,
+            TokenKind::Lt,
+            TokenKind::LtEq
+        ]
+    );
+    assert_eq!(
+        error.to_string(),
+        "[Error]: unexpected token `big` at 3:15, expect one of Eq, NotEq, Gt, Gteq, Lt, LtEq.
+3 |   reg1 = icmp big reg0 reg0
+  |               ^^^"
+    );
+}
+
+#[test]
+fn recover_at_next_instruction_and_block() {
+    let errors = parse_errors(
+        "func first (reg0: i32): i32 {
+block0:
+  reg1 = foo reg0
+  reg2 = addi reg0 1
+  reg3 = add reg9 reg2
+  jump block1
+block1 
+  reg4 = phi [block0 reg2]
+  ret reg4
+}
+
+func second (reg0: i32): i32 {
+block0:
+  store reg0 [reg0 0]
+  ret
+}",
+    );
+    let positions = errors
+        .iter()
+        .map(|error| (error.line, error.column))
+        .collect::<Vec<_>>();
+    assert_eq!(positions, vec![(3, 10), (5, 14), (8, 3), (14, 20)]);
+    assert_eq!(errors[0].message, "unexpected token `foo`");
+    assert_eq!(errors[1].message, "use of undefined register `reg9`");
+    assert_eq!(errors[2].expected, vec![TokenKind::Colon]);
+    assert_eq!(errors[3].expected, vec![TokenKind::Comma]);
+}
+
+#[test]
+fn report_undefined_register_used_as_operand() {
+    let errors = parse_errors(
+        "func callee (reg0: i32) {
+block0:
+  ret
+}
+
+func test (reg0: i32): i32 {
+block0:
+  reg1 = add reg0 reg7
+  store reg8 [reg0, 0]
+  store reg0 [reg9, 0]
+  call func callee(reg10)
+  jump block1
+block1:
+  reg2 = phi [block0 reg1, block1 reg3]
+  reg3 = addi reg2 1
+  ret reg11
+}",
+    );
+    let errors = errors
+        .iter()
+        .map(|error| (error.line, error.column, error.message.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        vec![
+            (8, 19, "use of undefined register `reg7`"),
+            (9, 9, "use of undefined register `reg8`"),
+            (10, 15, "use of undefined register `reg9`"),
+            (11, 20, "use of undefined register `reg10`"),
+            (16, 7, "use of undefined register `reg11`"),
+        ]
+    );
+}
+
+#[test]
+fn report_undefined_register_once_and_not_for_failed_definition() {
+    let errors = parse_errors(
+        "func test (reg0: i32): i32 {
+block0:
+  reg1 = foo reg0
+  reg2 = add reg0 reg1
+  reg3 = add reg0 reg5
+  reg4 = add reg3 reg5
+  ret reg4
+}",
+    );
+    let errors = errors
+        .iter()
+        .map(|error| (error.line, error.column, error.message.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        vec![
+            (3, 10, "unexpected token `foo`"),
+            (5, 19, "use of undefined register `reg5`"),
+        ]
+    );
+}
+
+#[test]
+fn report_unknown_symbol() {
+    let errors = parse_errors(
+        "func test (): u8 {
+  greg0 = @global symbol missing
+block0:
+  reg0 = call func nothing ()
+  ret reg0
+}",
+    );
+    let messages = errors.iter().map(|error| error.message.as_str()).collect::<Vec<_>>();
+    assert_eq!(messages, vec!["unknown symbol `missing`", "unknown function `nothing`"]);
+    assert_eq!(
+        format_parse_errors(&errors),
+        "[Error]: unknown symbol `missing` at 2:26.
+2 |   greg0 = @global symbol missing
+  |                          ^^^^^^^
+
+[Error]: unknown function `nothing` at 4:20.
+4 |   reg0 = call func nothing ()
+  |                    ^^^^^^^"
+    );
+}
+
+#[test]
+fn report_unexpected_end_of_file() {
+    let errors = parse_errors("func test (reg0: i32): i32 {\nblock0:\n  ret reg0\n");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "unexpected end of file");
+    assert_eq!(errors[0].found, TokenKind::EOF);
+    assert_eq!(errors[0].expected, vec![TokenKind::BraceRight]);
+    assert_eq!((errors[0].line, errors[0].column), (4, 1));
+}
+
+#[test]
+fn report_invalid_data_statement() {
+    let errors = parse_errors(
+        "small = @data { size 2, init [0x01 0x02 0x03] }
+table = @data { size 16, align 8, reloc [8, missing, 0] }
+ptr = @data { size 4, reloc [0, small, 0] }
+bad = @data { size 8, align 3 }
+huge = @data { size 8, reloc [18446744073709551615, small, 0] }
+overlap = @data { size 24, reloc [0, small, 0], reloc [16, small, 0], reloc [4, small, 0] }
+func main () {
+block0:
+  ret
+}",
+    );
+    let errors = errors
+        .iter()
+        .map(|error| (error.line, error.column, error.message.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        vec![
+            (1, 15, "initializer of `small` has 3 bytes, larger than size 2"),
+            (3, 33, "relocation at offset 0 is out of bound of `ptr`"),
+            (4, 29, "alignment 3 is not power of two"),
+            (
+                5,
+                53,
+                "relocation at offset 18446744073709551615 is out of bound of `huge`"
+            ),
+            (
+                6,
+                81,
+                "relocation at offset 4 overlaps relocation at offset 0 of `overlap`"
+            ),
+            (2, 45, "unknown symbol `missing`"),
+        ]
+    );
+}
+
+#[test]
+fn report_constant_out_of_range_of_type() {
+    let errors = parse_errors(
+        "func test (): i32 {
+block0:
+  reg0 = iconst i32 99999999999999999999999999
+  reg1 = uconst u8 256
+  reg2 = iconst i16 0x10000
+  reg3 = fconst f32 one
+  ret reg0
+}",
+    );
+    let errors = errors
+        .iter()
+        .map(|error| (error.line, error.column, error.message.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        vec![
+            (3, 21, "invalid i32 literal `99999999999999999999999999`"),
+            (4, 20, "invalid u8 literal `256`"),
+            (5, 21, "hex literal `0x10000` is out of range of I16"),
+            (6, 21, "unexpected token `one`"),
+        ]
+    );
+}
+
+#[test]
+fn parse_decimal_float_constant_as_float() {
+    let module = parse(
+        "func test (): f32 {
+block0:
+  reg0 = fconst f32 1
+  reg1 = fconst f64 -2
+  ret reg0
+}",
+    )
+    .unwrap();
+    let func_id = module.get_module_id_by_symbol("test").unwrap().to_func_id();
+    let func = module.get_function(func_id).unwrap();
+    let mut constants = func
+        .constants
+        .values()
+        .map(|data| data.bytes.clone())
+        .collect::<Vec<_>>();
+    constants.sort();
+    let mut expected = vec![1.0_f32.to_le_bytes().to_vec(), (-2.0_f64).to_le_bytes().to_vec()];
+    expected.sort();
+    assert_eq!(constants, expected);
+}
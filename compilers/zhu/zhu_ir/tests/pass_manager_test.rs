@@ -0,0 +1,163 @@
+use std::env::current_dir;
+use std::fs::read_to_string;
+use std::path::PathBuf;
+use zsh_ir::entities::module::Module;
+use zsh_ir::formatter::format;
+use zsh_ir::frontend::parse;
+use zsh_ir::pass::analysis::verifier::verify_module;
+use zsh_ir::pass::manager::{AnalysisCache, AnalysisKind, PassManager, PassManagerError};
+
+fn get_folder_path_by_case_name(name: &str) -> PathBuf {
+    current_dir().unwrap().join("tests/fixtures").join(name)
+}
+
+fn read_fixture_file(case_name: &str, file_name: &str) -> String {
+    let path_buf = get_folder_path_by_case_name(case_name).join(file_name);
+    read_to_string(path_buf).unwrap_or_else(|_| {
+        panic!(
+            "[Error]: test file can not read. test case is {:?}, file is {:?}.",
+            case_name, file_name
+        )
+    })
+}
+
+fn run_pipeline_on_function(module: &mut Module, func_name: &str, pipeline: &str) {
+    let func_id = module.get_module_id_by_symbol(func_name).unwrap().to_func_id();
+    let func = module.get_mut_function(func_id).unwrap();
+    PassManager::from_pipeline(pipeline).unwrap().run_on_function(func);
+}
+
+fn compare_pipeline_with_fixture(case_name: &str, func_name: &str, pipeline: &str) {
+    let mut module = parse(&read_fixture_file(case_name, "original.zhu")).unwrap();
+    run_pipeline_on_function(&mut module, func_name, pipeline);
+    let expected = read_fixture_file(case_name, &format!("expect_{}.zhu", pipeline));
+    assert_eq!(
+        format(&module),
+        expected,
+        "Test case {} failed for pipeline {}",
+        case_name,
+        pipeline
+    );
+}
+
+#[test]
+fn pipeline_parse_pass_names() {
+    let manager = PassManager::from_pipeline("dce, gvn,licm ,lcm").unwrap();
+    assert_eq!(
+        manager.get_pass_names(),
+        vec!["dce", "gvn", "licm", "critical-edge", "lazy-code-motion"]
+    );
+    assert!(PassManager::from_pipeline("").unwrap().get_pass_names().is_empty());
+}
+
+#[test]
+fn pipeline_report_unknown_pass() {
+    assert_eq!(
+        PassManager::from_pipeline("dce,foo").err(),
+        Some(PassManagerError::UnknownPass("foo".to_owned()))
+    );
+    assert_eq!(
+        PassManager::from_pipeline("dce,,gvn").err(),
+        Some(PassManagerError::EmptyPassName)
+    );
+}
+
+#[test]
+fn pipeline_match_single_pass_fixtures() {
+    compare_pipeline_with_fixture("gvn_diamond", "gvn_func", "gvn");
+    compare_pipeline_with_fixture("gvn_do_while_loop", "gvn_do_while_loop", "gvn");
+    compare_pipeline_with_fixture("dce_diamond_return_void", "dce_diamond_return_void", "dce");
+    compare_pipeline_with_fixture("dce_diamond_return_i16", "dce_diamond_return_i16", "dce");
+    compare_pipeline_with_fixture("dce_mem_oneline", "dce_mem_oneline", "dce");
+    compare_pipeline_with_fixture("lcm_diamond", "lcm_diamond", "lcm");
+    compare_pipeline_with_fixture("lcm_cmu_example", "lcm_cmu_example", "lcm");
+    compare_pipeline_with_fixture("do_while_loop", "do_while_loop", "licm");
+    compare_pipeline_with_fixture("licm_topo_order", "licm_topo_order", "licm");
+}
+
+#[test]
+fn analysis_cache_reuse_and_invalidate() {
+    let mut module = parse(&read_fixture_file("lcm_cmu_example", "original.zhu")).unwrap();
+    let func_id = module.get_module_id_by_symbol("lcm_cmu_example").unwrap().to_func_id();
+    let func = module.get_mut_function(func_id).unwrap();
+    let mut cache = AnalysisCache::new();
+    // gvn preserve all analyses, second gvn should not compute any analysis again.
+    PassManager::from_pipeline("gvn,gvn")
+        .unwrap()
+        .run_on_function_with_cache(func, &mut cache);
+    assert_eq!(cache.get_compute_count(AnalysisKind::Cfg), 1);
+    assert_eq!(cache.get_compute_count(AnalysisKind::Rpo), 1);
+    assert_eq!(cache.get_compute_count(AnalysisKind::DomTree), 1);
+    assert!(cache.is_cached(AnalysisKind::DomTree));
+    // lcm split critical edges, so cfg is recomputed once for lazy code motion,
+    // lazy code motion itself preserve the cfg.
+    PassManager::from_pipeline("lcm")
+        .unwrap()
+        .run_on_function_with_cache(func, &mut cache);
+    assert_eq!(cache.get_compute_count(AnalysisKind::Cfg), 2);
+    assert_eq!(cache.get_compute_count(AnalysisKind::Rpo), 2);
+    assert!(cache.is_cached(AnalysisKind::Cfg));
+    assert!(!cache.is_cached(AnalysisKind::DomTree));
+    // dce do not preserve anything.
+    PassManager::from_pipeline("dce")
+        .unwrap()
+        .run_on_function_with_cache(func, &mut cache);
+    assert_eq!(cache.get_compute_count(AnalysisKind::PostDomTree), 1);
+    assert!(!cache.is_cached(AnalysisKind::Cfg));
+}
+
+#[test]
+fn pipeline_run_on_module_with_verify() {
+    let mut module = parse(&read_fixture_file("lcm_cmu_example", "original.zhu")).unwrap();
+    let mut manager = PassManager::from_pipeline("gvn,lcm,dce").unwrap();
+    manager.set_verify_each(true);
+    manager.run_on_module(&mut module).unwrap();
+    assert!(verify_module(&module).is_empty());
+}
+
+#[test]
+fn pipeline_run_after_pass_leave_unreachable_blocks() {
+    // dce rewrite branch of diamond to jump, arms of diamond are unreachable for passes after it.
+    let mut module = parse(&read_fixture_file("gvn_diamond", "original.zhu")).unwrap();
+    let mut manager = PassManager::from_pipeline("dce,gvn,licm,lcm").unwrap();
+    manager.set_verify_each(true);
+    manager.run_on_module(&mut module).unwrap();
+    assert_eq!(
+        format(&module),
+        "func gvn_func (reg0: u8, reg1: u8) {
+block0:
+  jump block3
+block1:
+  jump block3
+block2:
+  jump block3
+block3:
+  ret
+}
+"
+    );
+}
+
+#[test]
+fn pipeline_out_of_ssa_round_trip() {
+    for case_name in [
+        "out_of_ssa_swap",
+        "out_of_ssa_lost_copy",
+        "gvn_do_while_loop",
+        "sccp_loop",
+    ] {
+        let mut module = parse(&read_fixture_file(case_name, "original.zhu")).unwrap();
+        PassManager::from_pipeline("out-of-ssa")
+            .unwrap()
+            .run_on_module(&mut module)
+            .unwrap();
+        let output = format(&module);
+        assert!(!output.contains("phi"), "Test case {} still contain phi", case_name);
+        assert_eq!(
+            format(&parse(&output).unwrap()),
+            output,
+            "Test case {} can not round trip",
+            case_name
+        );
+    }
+}
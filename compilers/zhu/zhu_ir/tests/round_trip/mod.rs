@@ -0,0 +1,25 @@
+use std::env::current_dir;
+use std::fs::{read_dir, read_to_string};
+use zsh_ir::entities::module::Module;
+
+/// Read source of every baseline case, sorted by case name.
+pub fn read_baseline_sources() -> Vec<(String, String)> {
+    let mut cases = read_dir(current_dir().unwrap().join("tests/baseline"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .map(|path| {
+            let name = path.file_name().unwrap().to_str().unwrap().to_owned();
+            (name, read_to_string(path.join("case.zhu")).unwrap())
+        })
+        .collect::<Vec<_>>();
+    cases.sort();
+    cases
+}
+
+/// Assert module decoded from serialized form is the same as original module.
+pub fn assert_module_eq(left: &Module, right: &Module) {
+    assert_eq!(left.symbol_table, right.symbol_table);
+    assert_eq!(left.data_objects, right.data_objects);
+    assert_eq!(left.functions, right.functions);
+}
@@ -0,0 +1,26 @@
+use zsh_ir::entities::module::Module;
+use zsh_ir::entities::runtime_value::RuntimeValue;
+use zsh_ir::interpreter::Interpreter;
+
+/// Run function of original and optimized module with every arguments, assert they
+/// return the same result or fail with the same error.
+pub fn assert_preserve_semantic(
+    original: &Module,
+    optimized: &Module,
+    func_name: &str,
+    args_list: &[Vec<RuntimeValue>],
+) {
+    for args in args_list {
+        let original_result = Interpreter::new(original)
+            .run(func_name, args)
+            .map_err(|error| error.kind);
+        let optimized_result = Interpreter::new(optimized)
+            .run(func_name, args)
+            .map_err(|error| error.kind);
+        assert_eq!(
+            original_result, optimized_result,
+            "[Error]: result differ with args {:?}.",
+            args
+        );
+    }
+}
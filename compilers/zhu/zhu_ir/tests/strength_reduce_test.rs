@@ -0,0 +1,218 @@
+mod semantic;
+
+use std::fs::read_to_string;
+
+use semantic::assert_preserve_semantic;
+use zsh_ir::entities::function::Function;
+use zsh_ir::entities::instruction::InstructionData;
+use zsh_ir::entities::module::Module;
+use zsh_ir::entities::runtime_value::RuntimeValue;
+use zsh_ir::entities::value::Value;
+use zsh_ir::frontend::parse;
+use zsh_ir::pass::analysis::cfg::cfg_anylysis;
+use zsh_ir::pass::analysis::domtree::domtree_analysis;
+use zsh_ir::pass::analysis::induction_variable::induction_variable_analysis;
+use zsh_ir::pass::analysis::verifier::verify_module;
+use zsh_ir::pass::manager::PassManager;
+use zsh_ir::pass::opt::licm::natural_loop::natural_loop_analysis;
+use zsh_ir::pass::opt::strength_reduce::StrengthReducePass;
+use zsh_ir::pass::{FormatTable, OptiPass};
+
+const COUNT_DOWN_SOURCE: &str = "func count_down (reg0: i32): i32 {
+block0:
+  reg1 = iconst i32 10
+  reg2 = iconst i32 0
+  jump block1
+block1:
+  reg3 = phi [block0 reg1, block1 reg5]
+  reg4 = phi [block0 reg0, block1 reg7]
+  reg5 = subi reg3 3
+  reg6 = muli reg5 3
+  reg7 = add reg4 reg6
+  reg8 = icmp gt reg5 reg2
+  brif reg8 block1 block2
+block2:
+  ret reg7
+}
+";
+
+const UNKNOWN_BOUND_SOURCE: &str = "func unknown_bound (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = iconst i32 0
+  jump block1
+block1:
+  reg3 = phi [block0 reg2, block2 reg8]
+  reg4 = phi [block0 reg0, block2 reg7]
+  reg5 = icmp lt reg3 reg1
+  brif reg5 block2 block3
+block2:
+  reg6 = muli reg3 4
+  reg7 = add reg4 reg6
+  reg8 = addi reg3 1
+  jump block1
+block3:
+  ret reg4
+}
+";
+
+const NO_PREHEADER_SOURCE: &str = "func no_preheader (reg0: i32): i32 {
+block0:
+  reg1 = iconst i32 0
+  reg2 = iconst i32 5
+  reg3 = icmp gt reg0 reg1
+  brif reg3 block1 block3
+block1:
+  reg4 = phi [block0 reg1, block2 reg9]
+  reg5 = phi [block0 reg0, block2 reg8]
+  reg6 = icmp lt reg4 reg2
+  brif reg6 block2 block3
+block2:
+  reg7 = muli reg4 6
+  reg8 = add reg5 reg7
+  reg9 = addi reg4 1
+  jump block1
+block3:
+  reg10 = phi [block0 reg1, block1 reg5]
+  ret reg10
+}
+";
+
+const OUTSIDE_USE_SOURCE: &str = "func outside_use (reg0: i32): i32 {
+block0:
+  reg1 = iconst i32 0
+  reg2 = iconst i32 5
+  jump block1
+block1:
+  reg3 = phi [block0 reg1, block1 reg5]
+  reg4 = muli reg3 7
+  reg5 = addi reg3 1
+  reg6 = icmp lt reg5 reg2
+  brif reg6 block1 block2
+block2:
+  reg7 = add reg4 reg0
+  ret reg7
+}
+";
+
+fn read_array_source() -> String {
+    read_to_string("tests/fixtures/strength_reduce_array/original.zhu").unwrap()
+}
+
+fn get_function<'a>(module: &'a Module, func_name: &str) -> &'a Function {
+    let func_id = module.get_module_id_by_symbol(func_name).unwrap().to_func_id();
+    module.get_function(func_id).unwrap()
+}
+
+fn run_strength_reduce(module: &mut Module, func_name: &str) -> (usize, usize, usize) {
+    let func_id = module.get_module_id_by_symbol(func_name).unwrap().to_func_id();
+    let func = module.get_mut_function(func_id).unwrap();
+    let cfg = cfg_anylysis(func);
+    let dom = domtree_analysis(func, &cfg);
+    let natural_loops = natural_loop_analysis(&dom, &cfg);
+    let mut pass = StrengthReducePass::new(&cfg, &natural_loops);
+    pass.process(func);
+    (
+        pass.get_reduced_count(),
+        pass.get_replaced_exit_count(),
+        pass.get_eliminated_count(),
+    )
+}
+
+#[test]
+fn derived_induction_variables_of_array_traversal() {
+    let module = parse(&read_array_source()).unwrap();
+    let func = get_function(&module, "strength_reduce_array");
+    let cfg = cfg_anylysis(func);
+    let dom = domtree_analysis(func, &cfg);
+    let natural_loops = natural_loop_analysis(&dom, &cfg);
+    let induction_variables = induction_variable_analysis(func, &cfg, &natural_loops[0]);
+    let offset = induction_variables.get_derived_induction_variable(Value(6)).unwrap();
+    assert_eq!(offset.basic, Value(3));
+    assert_eq!(offset.scale, RuntimeValue::U64(8));
+    assert_eq!(offset.base, None);
+    let address = induction_variables.get_derived_induction_variable(Value(9)).unwrap();
+    assert_eq!(address.scale, RuntimeValue::U64(8));
+    assert_eq!(address.base, Some(Value(0)));
+    // sum is not linear since it is phi.
+    assert!(induction_variables.get_derived_induction_variable(Value(7)).is_none());
+    assert_eq!(
+        induction_variables.format_table(func, &module),
+        "========== Induction Variables ==========
+reg3: init reg1, update reg8, step 1
+reg6: 8 * reg3 + 0
+reg9: 8 * reg3 + reg0 + 0
+Exit: block1 to block3 when reg3 Lt reg2 is false
+Trip count: 11
+"
+    );
+}
+
+#[test]
+fn reduce_and_eliminate_induction_variable() {
+    let source = read_array_source();
+    let original = parse(&source).unwrap();
+    let mut module = parse(&source).unwrap();
+    let counts = run_strength_reduce(&mut module, "strength_reduce_array");
+    assert_eq!(counts, (1, 1, 1));
+    assert_eq!(verify_module(&module), vec![]);
+    let args_list = [0, 3, u64::MAX - 20]
+        .into_iter()
+        .map(|base| vec![RuntimeValue::U64(base)])
+        .collect::<Vec<_>>();
+    assert_preserve_semantic(&original, &module, "strength_reduce_array", &args_list);
+}
+
+#[test]
+fn reduce_count_down_loop_exit_from_latch() {
+    let original = parse(COUNT_DOWN_SOURCE).unwrap();
+    let mut module = parse(COUNT_DOWN_SOURCE).unwrap();
+    let counts = run_strength_reduce(&mut module, "count_down");
+    assert_eq!(counts, (1, 1, 1));
+    assert_eq!(verify_module(&module), vec![]);
+    assert_preserve_semantic(
+        &original,
+        &module,
+        "count_down",
+        &[vec![RuntimeValue::I32(0)], vec![RuntimeValue::I32(-9)]],
+    );
+}
+
+#[test]
+fn keep_induction_variable_of_unknown_trip_count() {
+    let original = parse(UNKNOWN_BOUND_SOURCE).unwrap();
+    let mut module = parse(UNKNOWN_BOUND_SOURCE).unwrap();
+    let counts = run_strength_reduce(&mut module, "unknown_bound");
+    assert_eq!(counts, (1, 0, 0));
+    assert_eq!(verify_module(&module), vec![]);
+    let args_list = (-1..6)
+        .map(|bound| vec![RuntimeValue::I32(2), RuntimeValue::I32(bound)])
+        .collect::<Vec<_>>();
+    assert_preserve_semantic(&original, &module, "unknown_bound", &args_list);
+}
+
+#[test]
+fn not_reduce_value_used_outside_loop() {
+    let mut module = parse(OUTSIDE_USE_SOURCE).unwrap();
+    let counts = run_strength_reduce(&mut module, "outside_use");
+    assert_eq!(counts, (0, 0, 0));
+}
+
+#[test]
+fn reduce_after_loop_simplify_in_pipeline() {
+    let mut module = parse(NO_PREHEADER_SOURCE).unwrap();
+    assert_eq!(run_strength_reduce(&mut module, "no_preheader"), (0, 0, 0));
+    let original = parse(NO_PREHEADER_SOURCE).unwrap();
+    let mut module = parse(NO_PREHEADER_SOURCE).unwrap();
+    PassManager::from_pipeline("loop-simplify,strength-reduce")
+        .unwrap()
+        .run_on_module(&mut module)
+        .unwrap();
+    assert_eq!(verify_module(&module), vec![]);
+    let func = get_function(&module, "no_preheader");
+    assert!(func
+        .insts()
+        .into_iter()
+        .all(|inst| !matches!(func.get_inst_data(inst), InstructionData::BinaryI { .. })));
+    let args_list = (-2..3).map(|arg| vec![RuntimeValue::I32(arg)]).collect::<Vec<_>>();
+    assert_preserve_semantic(&original, &module, "no_preheader", &args_list);
+}
@@ -0,0 +1,201 @@
+mod semantic;
+
+use semantic::assert_preserve_semantic;
+use zsh_ir::entities::function::Function;
+use zsh_ir::entities::module::Module;
+use zsh_ir::entities::runtime_value::RuntimeValue;
+use zsh_ir::entities::value::Value;
+use zsh_ir::frontend::parse;
+use zsh_ir::pass::analysis::cfg::cfg_anylysis;
+use zsh_ir::pass::analysis::domtree::domtree_analysis;
+use zsh_ir::pass::analysis::induction_variable::{induction_variable_analysis, LoopInductionVariables};
+use zsh_ir::pass::analysis::verifier::verify_module;
+use zsh_ir::pass::manager::PassManager;
+use zsh_ir::pass::opt::licm::natural_loop::natural_loop_analysis;
+use zsh_ir::pass::opt::unroll::{LoopUnrollPass, UnrollConfig};
+use zsh_ir::pass::{FormatTable, OptiPass};
+
+const COUNT_UP_SOURCE: &str = "func count_up (reg0: i32): i32 {
+block0:
+  reg1 = iconst i32 0
+  reg2 = iconst i32 4
+  jump block1
+block1:
+  reg3 = phi [block0 reg1, block2 reg7]
+  reg4 = phi [block0 reg0, block2 reg6]
+  reg5 = icmp lt reg3 reg2
+  brif reg5 block2 block3
+block2:
+  reg6 = add reg4 reg3
+  reg7 = addi reg3 1
+  jump block1
+block3:
+  ret reg4
+}
+";
+
+const COUNT_DOWN_SOURCE: &str = "func count_down (reg0: i32): i32 {
+block0:
+  reg1 = iconst i32 10
+  reg2 = iconst i32 0
+  jump block1
+block1:
+  reg3 = phi [block0 reg1, block1 reg5]
+  reg4 = phi [block0 reg0, block1 reg6]
+  reg5 = subi reg3 3
+  reg6 = mul reg4 reg3
+  reg7 = icmp gt reg5 reg2
+  brif reg7 block1 block2
+block2:
+  ret reg6
+}
+";
+
+const UNKNOWN_BOUND_SOURCE: &str = "func unknown_bound (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = iconst i32 0
+  jump block1
+block1:
+  reg3 = phi [block0 reg2, block2 reg6]
+  reg4 = phi [block0 reg0, block2 reg5]
+  reg5 = add reg4 reg3
+  reg6 = addi reg3 1
+  jump block2
+block2:
+  reg7 = icmp lt reg6 reg1
+  brif reg7 block1 block3
+block3:
+  ret reg5
+}
+";
+
+fn get_function<'a>(module: &'a Module, func_name: &str) -> &'a Function {
+    let func_id = module.get_module_id_by_symbol(func_name).unwrap().to_func_id();
+    module.get_function(func_id).unwrap()
+}
+
+/// Induction variables of the only loop in function.
+fn analyze(module: &Module, func_name: &str) -> LoopInductionVariables {
+    let func = get_function(module, func_name);
+    let cfg = cfg_anylysis(func);
+    let dom = domtree_analysis(func, &cfg);
+    let natural_loops = natural_loop_analysis(&dom, &cfg);
+    assert_eq!(natural_loops.len(), 1);
+    induction_variable_analysis(func, &cfg, &natural_loops[0])
+}
+
+fn count_loops(module: &Module, func_name: &str) -> usize {
+    let func = get_function(module, func_name);
+    let cfg = cfg_anylysis(func);
+    let dom = domtree_analysis(func, &cfg);
+    natural_loop_analysis(&dom, &cfg).len()
+}
+
+fn run_unroll(module: &mut Module, func_name: &str, config: UnrollConfig) -> LoopUnrollPass {
+    let func_id = module.get_module_id_by_symbol(func_name).unwrap().to_func_id();
+    let mut pass = LoopUnrollPass::new(config);
+    pass.process(module.get_mut_function(func_id).unwrap());
+    pass
+}
+
+#[test]
+fn trip_count_of_loop_exit_from_header() {
+    let module = parse(COUNT_UP_SOURCE).unwrap();
+    let induction_variables = analyze(&module, "count_up");
+    let induction_variable = &induction_variables.get_induction_variables()[0];
+    assert_eq!(induction_variables.get_induction_variables().len(), 1);
+    assert_eq!(induction_variable.phi, Value(3));
+    assert_eq!(induction_variable.init, Value(1));
+    assert_eq!(induction_variable.update, Value(7));
+    assert_eq!(induction_variable.step, RuntimeValue::I32(1));
+    let exit_condition = induction_variables.get_exit_condition().unwrap();
+    assert!(!exit_condition.use_update);
+    assert!(!exit_condition.exit_when);
+    // header is executed for 0, 1, 2, 3 and 4.
+    assert_eq!(induction_variables.get_trip_count(), Some(5));
+}
+
+#[test]
+fn trip_count_of_loop_exit_from_latch() {
+    let module = parse(COUNT_DOWN_SOURCE).unwrap();
+    let induction_variables = analyze(&module, "count_down");
+    assert_eq!(
+        induction_variables.get_induction_variables()[0].step,
+        RuntimeValue::I32(-3)
+    );
+    let exit_condition = induction_variables.get_exit_condition().unwrap();
+    assert!(exit_condition.use_update);
+    // update is 7, 4, 1 and -2.
+    assert_eq!(induction_variables.get_trip_count(), Some(4));
+    assert_eq!(
+        induction_variables.format_table(get_function(&module, "count_down"), &module),
+        "========== Induction Variables ==========
+reg3: init reg1, update reg5, step -3
+Exit: block1 to block2 when reg5 Gt reg2 is false
+Trip count: 4
+"
+    );
+}
+
+#[test]
+fn trip_count_of_unknown_bound() {
+    let module = parse(UNKNOWN_BOUND_SOURCE).unwrap();
+    let induction_variables = analyze(&module, "unknown_bound");
+    assert_eq!(induction_variables.get_induction_variables().len(), 1);
+    assert_eq!(induction_variables.get_exit_condition().unwrap().bound, Value(1));
+    assert_eq!(induction_variables.get_trip_count(), None);
+}
+
+#[test]
+fn fully_unroll_constant_trip_count_loop() {
+    for (source, func_name) in [(COUNT_UP_SOURCE, "count_up"), (COUNT_DOWN_SOURCE, "count_down")] {
+        let original = parse(source).unwrap();
+        let mut module = parse(source).unwrap();
+        let pass = run_unroll(&mut module, func_name, UnrollConfig::default());
+        assert_eq!(verify_module(&module), vec![]);
+        assert_eq!(pass.get_fully_unrolled_count(), 1);
+        assert_eq!(count_loops(&module, func_name), 0);
+        assert_preserve_semantic(
+            &original,
+            &module,
+            func_name,
+            &[vec![RuntimeValue::I32(1)], vec![RuntimeValue::I32(-7)]],
+        );
+    }
+}
+
+#[test]
+fn partially_unroll_by_factor() {
+    let original = parse(UNKNOWN_BOUND_SOURCE).unwrap();
+    let mut module = parse(UNKNOWN_BOUND_SOURCE).unwrap();
+    let pass = run_unroll(&mut module, "unknown_bound", UnrollConfig::new(3, 64));
+    assert_eq!(verify_module(&module), vec![]);
+    assert_eq!(pass.get_fully_unrolled_count(), 0);
+    assert_eq!(pass.get_partially_unrolled_count(), 1);
+    assert_eq!(count_loops(&module, "unknown_bound"), 1);
+    let args_list = (-1..8)
+        .map(|bound| vec![RuntimeValue::I32(5), RuntimeValue::I32(bound)])
+        .collect::<Vec<_>>();
+    assert_preserve_semantic(&original, &module, "unknown_bound", &args_list);
+}
+
+#[test]
+fn not_unroll_over_threshold() {
+    let mut module = parse(COUNT_UP_SOURCE).unwrap();
+    let pass = run_unroll(&mut module, "count_up", UnrollConfig::new(1, 8));
+    assert_eq!(pass.get_fully_unrolled_count(), 0);
+    assert_eq!(pass.get_partially_unrolled_count(), 0);
+    assert_eq!(count_loops(&module, "count_up"), 1);
+}
+
+#[test]
+fn run_unroll_in_pipeline() {
+    let original = parse(COUNT_UP_SOURCE).unwrap();
+    let mut module = parse(COUNT_UP_SOURCE).unwrap();
+    PassManager::from_pipeline("unroll,instcombine")
+        .unwrap()
+        .run_on_module(&mut module)
+        .unwrap();
+    assert_eq!(count_loops(&module, "count_up"), 0);
+    assert_preserve_semantic(&original, &module, "count_up", &[vec![RuntimeValue::I32(3)]]);
+}
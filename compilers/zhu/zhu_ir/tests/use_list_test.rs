@@ -0,0 +1,114 @@
+use zsh_ir::entities::function::Function;
+use zsh_ir::entities::instruction::Instruction;
+use zsh_ir::entities::module::Module;
+use zsh_ir::entities::value::Value;
+use zsh_ir::formatter::format;
+use zsh_ir::frontend::parse;
+
+const SOURCE: &str = "func callee (reg0: i32): i32 {
+block0:
+  ret reg0
+}
+func rewrite (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = add reg0 reg0
+  reg3 = call func callee(reg0)
+  brif reg1 block1 block2
+block1:
+  reg4 = add reg2 reg1
+  jump block2
+block2:
+  reg5 = phi [block0 reg0, block1 reg4]
+  ret reg5
+}
+";
+
+fn get_function_mut<'a>(module: &'a mut Module, name: &str) -> &'a mut Function {
+    let func_id = module.get_module_id_by_symbol(name).unwrap().to_func_id();
+    module.get_mut_function(func_id).unwrap()
+}
+
+fn results_of(function: &Function, users: Vec<Instruction>) -> Vec<Option<Value>> {
+    users.into_iter().map(|inst| function.get_inst_result(inst)).collect()
+}
+
+#[test]
+fn query_users_of_value() {
+    let mut module = parse(SOURCE).unwrap();
+    let function = get_function_mut(&mut module, "rewrite");
+    let use_list = function.compute_use_list();
+    // `add reg0 reg0` is recorded once.
+    assert_eq!(
+        results_of(function, function.users_of(Value(0))),
+        vec![Some(Value(2)), Some(Value(3)), Some(Value(5))]
+    );
+    assert_eq!(use_list.users_of(Value(0)), function.users_of(Value(0)));
+    assert_eq!(results_of(function, use_list.users_of(Value(4))), vec![Some(Value(5))]);
+    assert!(!function.has_no_uses(Value(5)));
+    assert!(function.has_no_uses(Value(3)));
+    assert!(use_list.has_no_uses(Value(3)));
+}
+
+#[test]
+fn replace_all_uses_on_function() {
+    let mut module = parse(SOURCE).unwrap();
+    let function = get_function_mut(&mut module, "rewrite");
+    function.replace_all_uses_with(Value(0), Value(1));
+    assert!(function.has_no_uses(Value(0)));
+    assert_eq!(
+        format(&module),
+        "func callee (reg0: i32): i32 {
+block0:
+  ret reg0
+}
+func rewrite (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = add reg1 reg1
+  reg3 = call func callee(reg1)
+  brif reg1 block1 block2
+block1:
+  reg4 = add reg2 reg1
+  jump block2
+block2:
+  reg5 = phi [block0 reg1, block1 reg4]
+  ret reg5
+}
+"
+    );
+}
+
+#[test]
+fn replace_all_uses_keep_use_list_up_to_date() {
+    let mut module = parse(SOURCE).unwrap();
+    let function = get_function_mut(&mut module, "rewrite");
+    let mut use_list = function.compute_use_list();
+    use_list.replace_all_uses_with(function, Value(4), Value(2));
+    use_list.replace_all_uses_with(function, Value(2), Value(0));
+    assert!(use_list.has_no_uses(Value(2)));
+    assert!(use_list.has_no_uses(Value(4)));
+    assert_eq!(use_list, function.compute_use_list());
+    assert_eq!(
+        results_of(function, use_list.users_of(Value(0))),
+        vec![Some(Value(2)), Some(Value(3)), Some(Value(4)), Some(Value(5))]
+    );
+    assert_eq!(
+        format(&module),
+        "func callee (reg0: i32): i32 {
+block0:
+  ret reg0
+}
+func rewrite (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = add reg0 reg0
+  reg3 = call func callee(reg0)
+  brif reg1 block1 block2
+block1:
+  reg4 = add reg0 reg1
+  jump block2
+block2:
+  reg5 = phi [block0 reg0, block1 reg0]
+  ret reg5
+}
+"
+    );
+}
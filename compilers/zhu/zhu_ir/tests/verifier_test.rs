@@ -0,0 +1,220 @@
+use std::env::current_dir;
+use std::fs::read_to_string;
+use zsh_ir::entities::block::Block;
+use zsh_ir::entities::module::{Module, ModuleLevelId};
+use zsh_ir::entities::r#type::ValueType;
+use zsh_ir::entities::value::Value;
+use zsh_ir::frontend::parse;
+use zsh_ir::pass::analysis::cfg::cfg_anylysis;
+use zsh_ir::pass::analysis::domtree::domtree_analysis;
+use zsh_ir::pass::analysis::rpo::revrese_post_order_analysis;
+use zsh_ir::pass::analysis::verifier::{format_verifier_errors, verify_module, VerifierErrorKind};
+use zsh_ir::pass::opt::gvn::gvn_pass;
+use zsh_ir::pass::opt::lcm::lcm_opt;
+
+fn read_case(folder: &str, case_name: &str, file_name: &str) -> String {
+    let path_buf = current_dir()
+        .unwrap()
+        .join("tests")
+        .join(folder)
+        .join(case_name)
+        .join(file_name);
+    read_to_string(path_buf)
+        .unwrap_or_else(|_| panic!("[Error]: test file can not read. test case is {:?}.", case_name))
+}
+
+fn assert_module_is_valid(module: &Module) {
+    let errors = verify_module(module);
+    assert!(errors.is_empty(), "{}", format_verifier_errors(&errors, module));
+}
+
+fn get_error_kinds(source: &str) -> Vec<VerifierErrorKind> {
+    let module = parse(source).unwrap();
+    verify_module(&module).into_iter().map(|error| error.kind).collect()
+}
+
+#[test]
+fn baseline_cases_are_valid() {
+    for case_name in [
+        "convert_inst",
+        "unary_inst",
+        "cmp_inst",
+        "call_inst",
+        "branch_inst",
+        "binary_inst_base",
+        "binary_inst_byte",
+        "binary_inst_float",
+        "binary_immi_inst",
+        "global_inst",
+        "data_object",
+        "mem_inst_base",
+        "mem_alloc_inst",
+        "mem_inst_struct",
+        "phi_inst",
+    ] {
+        let module = parse(&read_case("baseline", case_name, "case.zhu")).unwrap();
+        assert_module_is_valid(&module);
+    }
+}
+
+#[test]
+fn fixture_original_cases_are_valid() {
+    for case_name in [
+        "do_while_loop",
+        "for_loop",
+        "gvn_diamond",
+        "gvn_do_while_loop",
+        "lcm_cmu_example",
+        "lcm_diamond",
+    ] {
+        let module = parse(&read_case("fixtures", case_name, "original.zhu")).unwrap();
+        assert_module_is_valid(&module);
+    }
+}
+
+#[test]
+/// `licm_diamond_like` has two terminators in block3 and redefine reg8, verifier should report it.
+fn malformed_fixture_is_reported() {
+    let module = parse(&read_case("fixtures", "licm_diamond_like", "original.zhu")).unwrap();
+    let kinds: Vec<VerifierErrorKind> = verify_module(&module).into_iter().map(|error| error.kind).collect();
+    assert!(kinds.contains(&VerifierErrorKind::TerminatorNotAtEnd));
+}
+
+#[test]
+fn verify_between_optimizations() {
+    let mut module = parse(&read_case("fixtures", "lcm_cmu_example", "original.zhu")).unwrap();
+    for func in module.functions.values_mut() {
+        let cfg = cfg_anylysis(func);
+        let dom = domtree_analysis(func, &cfg);
+        let rpo = revrese_post_order_analysis(&cfg);
+        gvn_pass(func, &dom, &cfg, &rpo);
+    }
+    assert_module_is_valid(&module);
+    for func in module.functions.values_mut() {
+        let cfg = cfg_anylysis(func);
+        let rpo = revrese_post_order_analysis(&cfg);
+        lcm_opt(&cfg, &rpo, func);
+    }
+    assert_module_is_valid(&module);
+}
+
+#[test]
+fn binary_operand_type_mismatch() {
+    let kinds = get_error_kinds(
+        "func test (reg0: u8, reg1: u16) {
+block0:
+  reg2 = add reg0 reg1
+  ret
+}",
+    );
+    assert_eq!(
+        kinds,
+        vec![VerifierErrorKind::OperandTypeMismatch {
+            expect: ValueType::U8,
+            actual: ValueType::U16,
+        }]
+    );
+}
+
+#[test]
+fn float_operand_for_integer_binary() {
+    let kinds = get_error_kinds(
+        "func test (reg0: f32, reg1: f32) {
+block0:
+  reg2 = add reg0 reg1
+  ret
+}",
+    );
+    assert_eq!(kinds, vec![VerifierErrorKind::ExpectIntegerOperand(ValueType::F32)]);
+}
+
+#[test]
+fn block_without_terminator() {
+    let kinds = get_error_kinds(
+        "func test (reg0: u8, reg1: u8) {
+block0:
+  reg2 = add reg0 reg1
+}",
+    );
+    assert_eq!(kinds, vec![VerifierErrorKind::MissingTerminator]);
+}
+
+#[test]
+fn phi_argument_is_not_predecessor() {
+    let kinds = get_error_kinds(
+        "func test (reg0: u8, reg1: u8) {
+block0:
+  reg2 = icmp eq reg0 reg1
+  brif reg2 block1 block2
+block1:
+  reg3 = add reg0 reg1
+  jump block3
+block2:
+  reg4 = sub reg0 reg1
+  jump block3
+block3:
+  reg5 = phi [block1 reg3, block0 reg4]
+  ret
+}",
+    );
+    assert!(kinds.contains(&VerifierErrorKind::PhiArmNotPredecessor(Block(0))));
+    assert!(kinds.contains(&VerifierErrorKind::PhiMissingArm(Block(2))));
+}
+
+#[test]
+fn use_not_dominated_by_def() {
+    let kinds = get_error_kinds(
+        "func test (reg0: u8, reg1: u8) {
+block0:
+  reg2 = icmp eq reg0 reg1
+  brif reg2 block1 block2
+block1:
+  reg3 = add reg0 reg1
+  jump block3
+block2:
+  jump block3
+block3:
+  reg4 = add reg3 reg0
+  ret
+}",
+    );
+    assert_eq!(kinds, vec![VerifierErrorKind::UseNotDominated(Value(3))]);
+}
+
+#[test]
+fn return_type_mismatch_signature() {
+    let kinds = get_error_kinds(
+        "func test (reg0: u8): u16 {
+block0:
+  ret reg0
+}",
+    );
+    assert_eq!(
+        kinds,
+        vec![VerifierErrorKind::ReturnTypeMismatch {
+            expect: Some(ValueType::U16),
+            actual: Some(ValueType::U8),
+        }]
+    );
+}
+
+#[test]
+fn format_error_with_function_name() {
+    let module = parse(
+        "func test (reg0: u8): u16 {
+block0:
+  ret reg0
+}",
+    )
+    .unwrap();
+    let errors = verify_module(&module);
+    let func_id = match module.get_module_id_by_symbol("test").unwrap() {
+        ModuleLevelId::Func(func_id) => *func_id,
+        _ => panic!(),
+    };
+    assert_eq!(errors[0].func, func_id);
+    assert_eq!(
+        format_verifier_errors(&errors, &module),
+        "[Error]: func test, block0, inst0: return type mismatch, expect Some(U16) but got Some(U8)\n"
+    );
+}
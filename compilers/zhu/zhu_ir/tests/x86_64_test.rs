@@ -0,0 +1,778 @@
+#![cfg(all(target_arch = "x86_64", target_os = "linux"))]
+
+use std::env::temp_dir;
+use std::fs::{create_dir_all, write};
+use std::process::{id, Command};
+use zsh_ir::builder::FunctionBuilder;
+use zsh_ir::codegen::x86_64::emit_module;
+use zsh_ir::codegen::x86_64::frame::{allocate_frame, Location};
+use zsh_ir::codegen::x86_64::register::Gpr;
+use zsh_ir::codegen::CodegenErrorKind;
+use zsh_ir::entities::immediate::Immediate;
+use zsh_ir::entities::instruction::opcode::CmpFlag;
+use zsh_ir::entities::module::Module;
+use zsh_ir::entities::r#type::ValueType;
+use zsh_ir::entities::runtime_value::RuntimeValue;
+use zsh_ir::frontend::parse;
+use zsh_ir::interpreter::Interpreter;
+
+const DRIVER_PRELUDE: &str = "#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+static void put(uint64_t bits) { printf(\"%016llx\\n\", (unsigned long long)bits); }
+static float f32_of(uint32_t bits) { float num; memcpy(&num, &bits, 4); return num; }
+static double f64_of(uint64_t bits) { double num; memcpy(&num, &bits, 8); return num; }
+static uint64_t bits_of_f32(float num) { uint32_t bits; memcpy(&bits, &num, 4); return bits; }
+static uint64_t bits_of_f64(double num) { uint64_t bits; memcpy(&bits, &num, 8); return bits; }
+";
+
+fn c_type(ty: &ValueType) -> &'static str {
+    match ty {
+        ValueType::U8 => "uint8_t",
+        ValueType::U16 => "uint16_t",
+        ValueType::U32 => "uint32_t",
+        ValueType::U64 | ValueType::Mem(_) => "uint64_t",
+        ValueType::I16 => "int16_t",
+        ValueType::I32 => "int32_t",
+        ValueType::I64 => "int64_t",
+        ValueType::F32 => "float",
+        ValueType::F64 => "double",
+    }
+}
+
+/// C expression of runtime value, float is built from bits so NaN and signed zero
+/// are passed exactly.
+fn c_expr(value: &RuntimeValue) -> String {
+    match *value {
+        RuntimeValue::U8(num) => format!("(uint8_t){}u", num),
+        RuntimeValue::U16(num) => format!("(uint16_t){}u", num),
+        RuntimeValue::U32(num) => format!("(uint32_t){}u", num),
+        RuntimeValue::U64(num) | RuntimeValue::Address(num) => format!("(uint64_t){}ull", num),
+        RuntimeValue::I16(num) => format!("(int16_t){}", num),
+        RuntimeValue::I32(num) => format!("(int32_t){}", num),
+        RuntimeValue::I64(num) => format!("(int64_t){}ull", num as u64),
+        RuntimeValue::F32(num) => format!("f32_of({}u)", num.to_bits()),
+        RuntimeValue::F64(num) => format!("f64_of({}ull)", num.to_bits()),
+    }
+}
+
+/// Bits printed by driver for a result, signed integer is sign extended.
+fn result_bits(value: &RuntimeValue) -> u64 {
+    match *value {
+        RuntimeValue::U8(num) => num as u64,
+        RuntimeValue::U16(num) => num as u64,
+        RuntimeValue::U32(num) => num as u64,
+        RuntimeValue::U64(num) | RuntimeValue::Address(num) => num,
+        RuntimeValue::I16(num) => num as i64 as u64,
+        RuntimeValue::I32(num) => num as i64 as u64,
+        RuntimeValue::I64(num) => num as u64,
+        RuntimeValue::F32(num) => num.to_bits() as u64,
+        RuntimeValue::F64(num) => num.to_bits(),
+    }
+}
+
+/// Generate C driver which calls functions in order and prints every result.
+fn generate_driver(module: &Module, calls: &[(&str, Vec<RuntimeValue>)]) -> String {
+    let mut driver = DRIVER_PRELUDE.to_owned();
+    let mut declared = Vec::new();
+    for (name, _) in calls {
+        if declared.contains(name) {
+            continue;
+        }
+        declared.push(*name);
+        let func_id = module.get_module_id_by_symbol(name).unwrap().to_func_id();
+        let signature = &module.get_function(func_id).unwrap().signature;
+        let params = signature.params.iter().map(c_type).collect::<Vec<_>>();
+        let return_type = signature.return_type.as_ref().map_or("void", c_type);
+        let params = if params.is_empty() {
+            "void".to_owned()
+        } else {
+            params.join(", ")
+        };
+        driver.push_str(&format!("{} {}({});\n", return_type, name, params));
+    }
+    driver.push_str("int main(void) {\n");
+    for (name, args) in calls {
+        let func_id = module.get_module_id_by_symbol(name).unwrap().to_func_id();
+        let call = format!("{}({})", name, args.iter().map(c_expr).collect::<Vec<_>>().join(", "));
+        let line = match &module.get_function(func_id).unwrap().signature.return_type {
+            None => format!("  {}; printf(\"void\\n\");\n", call),
+            Some(ValueType::F32) => format!("  put(bits_of_f32({}));\n", call),
+            Some(ValueType::F64) => format!("  put(bits_of_f64({}));\n", call),
+            Some(ValueType::I16 | ValueType::I32 | ValueType::I64) => {
+                format!("  put((uint64_t)(int64_t){});\n", call)
+            }
+            Some(_) => format!("  put((uint64_t){});\n", call),
+        };
+        driver.push_str(&line);
+    }
+    driver.push_str("  return 0;\n}\n");
+    driver
+}
+
+/// Assemble module with host toolchain, run calls natively and compare every result
+/// with interpreter. Calls share one interpreter so global data is shared as native.
+fn assert_native_match_interpreter(case_name: &str, module: &Module, calls: &[(&str, Vec<RuntimeValue>)]) {
+    let asm = emit_module(module).unwrap_or_else(|error| panic!("{}", error));
+    let dir = temp_dir().join(format!("zhu_x86_64_{}_{}", case_name, id()));
+    create_dir_all(&dir).unwrap();
+    write(dir.join("module.s"), &asm).unwrap();
+    write(dir.join("driver.c"), generate_driver(module, calls)).unwrap();
+    let compile = Command::new("cc")
+        .current_dir(&dir)
+        .args(["-o", "driver", "driver.c", "module.s", "-lm"])
+        .output()
+        .expect("[Error]: host c compiler is not found.");
+    assert!(
+        compile.status.success(),
+        "[Error]: case {} can not be assembled:\n{}\n{}",
+        case_name,
+        String::from_utf8_lossy(&compile.stderr),
+        asm
+    );
+    let run = Command::new(dir.join("driver")).output().unwrap();
+    assert!(run.status.success(), "[Error]: case {} crashed:\n{}", case_name, asm);
+    let stdout = String::from_utf8(run.stdout).unwrap();
+    let mut interpreter = Interpreter::new(module);
+    for ((name, args), line) in calls.iter().zip(stdout.lines()) {
+        let expect = match interpreter.run(name, args).unwrap() {
+            Some(value) => format!("{:016x}", result_bits(&value)),
+            None => "void".to_owned(),
+        };
+        assert_eq!(
+            line, expect,
+            "[Error]: case {} call {}{:?} mismatch.",
+            case_name, name, args
+        );
+    }
+    assert_eq!(
+        stdout.lines().count(),
+        calls.len(),
+        "[Error]: case {} missing output.",
+        case_name
+    );
+}
+
+#[test]
+fn integer_arithmetic() {
+    let module = parse(
+        "func arith_i32 (reg0: i32, reg1: i32): i32 {
+block0:
+  reg2 = add reg0 reg1
+  reg3 = mul reg2 reg1
+  reg4 = sub reg3 reg0
+  reg5 = divide reg4 reg1
+  reg6 = reminder reg4 reg1
+  reg7 = add reg5 reg6
+  reg8 = muli reg7 -3
+  ret reg8
+}
+func arith_u8 (reg0: u8, reg1: u8): u8 {
+block0:
+  reg2 = mul reg0 reg1
+  reg3 = addi reg2 200
+  reg4 = divide reg3 reg1
+  reg5 = reminderi reg4 7
+  reg6 = add reg3 reg5
+  ret reg6
+}
+func arith_i16 (reg0: i16, reg1: i16): i16 {
+block0:
+  reg2 = divide reg0 reg1
+  reg3 = reminder reg0 reg1
+  reg4 = subi reg2 30000
+  reg5 = add reg4 reg3
+  reg6 = neg reg5
+  ret reg6
+}
+func arith_u64 (reg0: u64, reg1: u64): u64 {
+block0:
+  reg2 = divide reg0 reg1
+  reg3 = reminder reg0 reg1
+  reg4 = mul reg2 reg3
+  reg5 = dividei reg4 3
+  ret reg5
+}
+func bits_i64 (reg0: i64, reg1: i64): i64 {
+block0:
+  reg2 = shl reg0 reg1
+  reg3 = shr reg0 reg1
+  reg4 = bor reg2 reg3
+  reg5 = band reg4 reg0
+  reg6 = iconst i64 -1
+  reg7 = divide reg0 reg6
+  reg8 = add reg5 reg7
+  ret reg8
+}
+func shift_u16 (reg0: u16, reg1: u16): u16 {
+block0:
+  reg2 = shl reg0 reg1
+  reg3 = shr reg0 reg1
+  reg4 = bor reg2 reg3
+  ret reg4
+}
+func shift_i16 (reg0: i16, reg1: i16): i16 {
+block0:
+  reg2 = shr reg0 reg1
+  ret reg2
+}
+",
+    )
+    .unwrap();
+    use RuntimeValue::*;
+    let calls = vec![
+        ("arith_i32", vec![I32(7), I32(3)]),
+        ("arith_i32", vec![I32(-100), I32(7)]),
+        ("arith_i32", vec![I32(i32::MAX), I32(2)]),
+        ("arith_i32", vec![I32(i32::MIN), I32(-1)]),
+        ("arith_u8", vec![U8(17), U8(9)]),
+        ("arith_u8", vec![U8(255), U8(255)]),
+        ("arith_i16", vec![I16(-32768), I16(-1)]),
+        ("arith_i16", vec![I16(-7), I16(2)]),
+        ("arith_u64", vec![U64(u64::MAX), U64(3)]),
+        ("arith_u64", vec![U64(1 << 63), U64(12345)]),
+        ("bits_i64", vec![I64(-12345), I64(3)]),
+        ("bits_i64", vec![I64(i64::MIN), I64(67)]),
+        ("shift_u16", vec![U16(0xbeef), U16(4)]),
+        ("shift_u16", vec![U16(0xbeef), U16(19)]),
+        ("shift_i16", vec![I16(-32000), I16(5)]),
+    ];
+    assert_native_match_interpreter("integer_arithmetic", &module, &calls);
+}
+
+#[test]
+fn float_arithmetic_and_compare() {
+    let module = parse(
+        "func arith_f32 (reg0: f32, reg1: f32): f32 {
+block0:
+  reg2 = fadd reg1 reg0
+  reg3 = fsub reg2 reg1
+  reg4 = fmul reg3 reg2
+  reg5 = fdivide reg4 reg1
+  reg6 = freminder reg5 reg0
+  reg7 = neg reg6
+  ret reg7
+}
+func arith_f64 (reg0: f64, reg1: f64): f64 {
+block0:
+  reg2 = fmul reg0 reg1
+  reg3 = freminder reg2 reg1
+  reg4 = fconst f64 0x3FF8000000000000
+  reg5 = fadd reg3 reg4
+  reg6 = neg reg5
+  ret reg6
+}
+func cmp_mask (reg0: i32, reg1: i32, reg2: f64, reg3: f64): u32 {
+block0:
+  reg4 = icmp lt reg0 reg1
+  reg5 = icmp gteq reg0 reg1
+  reg6 = fcmp eq reg2 reg3
+  reg7 = fcmp noteq reg2 reg3
+  reg8 = fcmp lt reg2 reg3
+  reg9 = fcmp gteq reg2 reg3
+  reg10 = to.u32 reg4
+  reg11 = to.u32 reg5
+  reg12 = to.u32 reg6
+  reg13 = to.u32 reg7
+  reg14 = to.u32 reg8
+  reg15 = to.u32 reg9
+  reg16 = muli reg11 2
+  reg17 = muli reg12 4
+  reg18 = muli reg13 8
+  reg19 = muli reg14 16
+  reg20 = muli reg15 32
+  reg21 = add reg10 reg16
+  reg22 = add reg21 reg17
+  reg23 = add reg22 reg18
+  reg24 = add reg23 reg19
+  reg25 = add reg24 reg20
+  ret reg25
+}
+func cmp_unsigned (reg0: u8, reg1: u8): u8 {
+block0:
+  reg2 = icmp gt reg0 reg1
+  ret reg2
+}
+func cmp_f32 (reg0: f32, reg1: f32): u8 {
+block0:
+  reg2 = fcmp lteq reg0 reg1
+  ret reg2
+}
+",
+    )
+    .unwrap();
+    use RuntimeValue::*;
+    let calls = vec![
+        ("arith_f32", vec![F32(1.5), F32(-2.25)]),
+        ("arith_f32", vec![F32(3.0), F32(0.0)]),
+        ("arith_f64", vec![F64(10.5), F64(3.0)]),
+        ("arith_f64", vec![F64(-7.25), F64(f64::INFINITY)]),
+        ("cmp_mask", vec![I32(-1), I32(1), F64(1.0), F64(2.0)]),
+        ("cmp_mask", vec![I32(5), I32(5), F64(2.0), F64(2.0)]),
+        ("cmp_mask", vec![I32(9), I32(-9), F64(f64::NAN), F64(2.0)]),
+        ("cmp_mask", vec![I32(0), I32(0), F64(f64::NAN), F64(f64::NAN)]),
+        ("cmp_unsigned", vec![U8(200), U8(100)]),
+        ("cmp_unsigned", vec![U8(1), U8(255)]),
+        ("cmp_f32", vec![F32(-0.0), F32(0.0)]),
+        ("cmp_f32", vec![F32(f32::NAN), F32(0.0)]),
+        ("cmp_f32", vec![F32(3.5), F32(-1.0)]),
+    ];
+    assert_native_match_interpreter("float_arithmetic_and_compare", &module, &calls);
+}
+
+#[test]
+fn convert_between_types() {
+    let module = parse(
+        "func int_to_float (reg0: i64, reg1: u64, reg2: u8): f64 {
+block0:
+  reg3 = to.f64 reg0
+  reg4 = to.f64 reg1
+  reg5 = to.f32 reg1
+  reg6 = to.f64 reg5
+  reg7 = to.f32 reg2
+  reg8 = to.f64 reg7
+  reg9 = fadd reg3 reg4
+  reg10 = fadd reg9 reg6
+  reg11 = fadd reg10 reg8
+  ret reg11
+}
+func f64_to_u64 (reg0: f64): u64 {
+block0:
+  reg1 = to.u64 reg0
+  ret reg1
+}
+func f64_to_i32 (reg0: f64): i32 {
+block0:
+  reg1 = to.i32 reg0
+  ret reg1
+}
+func f32_to_u8 (reg0: f32): u8 {
+block0:
+  reg1 = to.u8 reg0
+  ret reg1
+}
+func f32_to_i64 (reg0: f32): i64 {
+block0:
+  reg1 = to.i64 reg0
+  ret reg1
+}
+func narrow_int (reg0: i64): i16 {
+block0:
+  reg1 = to.u8 reg0
+  reg2 = to.i16 reg0
+  reg3 = to.u32 reg2
+  reg4 = to.i16 reg1
+  reg5 = to.i16 reg3
+  reg6 = add reg4 reg5
+  ret reg6
+}
+func f64_to_f32 (reg0: f64): f32 {
+block0:
+  reg1 = to.f32 reg0
+  ret reg1
+}
+",
+    )
+    .unwrap();
+    use RuntimeValue::*;
+    let calls = vec![
+        ("int_to_float", vec![I64(-3), U64(u64::MAX), U8(255)]),
+        ("int_to_float", vec![I64(i64::MIN), U64(1 << 63 | 1), U8(0)]),
+        ("f64_to_u64", vec![F64(1e19)]),
+        ("f64_to_u64", vec![F64(1e20)]),
+        ("f64_to_u64", vec![F64(-5.5)]),
+        ("f64_to_u64", vec![F64(f64::NAN)]),
+        ("f64_to_u64", vec![F64(123.9)]),
+        ("f64_to_i32", vec![F64(-3e10)]),
+        ("f64_to_i32", vec![F64(3e10)]),
+        ("f64_to_i32", vec![F64(-7.9)]),
+        ("f32_to_u8", vec![F32(300.0)]),
+        ("f32_to_u8", vec![F32(-1.0)]),
+        ("f32_to_u8", vec![F32(77.7)]),
+        ("f32_to_i64", vec![F32(f32::NEG_INFINITY)]),
+        ("f32_to_i64", vec![F32(f32::NAN)]),
+        ("f32_to_i64", vec![F32(-1234.5)]),
+        ("narrow_int", vec![I64(-1)]),
+        ("narrow_int", vec![I64(0x1234_5678_9abc)]),
+        ("f64_to_f32", vec![F64(1e300)]),
+        ("f64_to_f32", vec![F64(0.1)]),
+    ];
+    assert_native_match_interpreter("convert_between_types", &module, &calls);
+}
+
+#[test]
+fn control_flow_with_phi() {
+    let module = parse(
+        "func sum_to (reg0: u32): u32 {
+block0:
+  reg1 = uconst u32 0
+  reg2 = uconst u32 1
+  jump block1
+block1:
+  reg3 = phi [block0 reg1, block2 reg5]
+  reg4 = phi [block0 reg2, block2 reg6]
+  reg7 = icmp gt reg4 reg0
+  brif reg7 block3 block2
+block2:
+  reg5 = add reg3 reg4
+  reg6 = addi reg4 1
+  jump block1
+block3:
+  ret reg3
+}
+func fib_loop (reg0: u64): u64 {
+block0:
+  reg1 = uconst u64 0
+  reg2 = uconst u64 1
+  jump block1
+block1:
+  reg3 = phi [block0 reg1, block2 reg4]
+  reg4 = phi [block0 reg2, block2 reg6]
+  reg5 = phi [block0 reg0, block2 reg7]
+  reg8 = icmp eq reg5 reg1
+  brif reg8 block3 block2
+block2:
+  reg6 = add reg3 reg4
+  reg7 = subi reg5 1
+  jump block1
+block3:
+  ret reg3
+}
+func select_i16 (reg0: i16, reg1: i16): i16 {
+block0:
+  reg2 = icmp lt reg0 reg1
+  brif reg2 block1 block2
+block1:
+  reg3 = sub reg1 reg0
+  jump block3
+block2:
+  reg4 = sub reg0 reg1
+  jump block3
+block3:
+  reg5 = phi [block1 reg3, block2 reg4]
+  ret reg5
+}
+",
+    )
+    .unwrap();
+    use RuntimeValue::*;
+    let calls = vec![
+        ("sum_to", vec![U32(0)]),
+        ("sum_to", vec![U32(100)]),
+        ("fib_loop", vec![U64(1)]),
+        ("fib_loop", vec![U64(90)]),
+        ("select_i16", vec![I16(-5), I16(7)]),
+        ("select_i16", vec![I16(30000), I16(-30000)]),
+    ];
+    assert_native_match_interpreter("control_flow_with_phi", &module, &calls);
+}
+
+#[test]
+fn memory_and_global_data() {
+    let module = parse(
+        "message = @data { size 4, align 1, init [0x68 0x69 0x21] }
+table = @data { size 16, align 8, reloc [0, message, 1], reloc [8, message, 0] }
+counter = @data { size 8, align 8, mut, init [0x05] }
+func read_table (): u8 {
+  greg0 = @global symbol table
+  greg1 = @global u64, load [greg0, 0]
+  greg2 = @global u64, load [greg0, 8]
+block0:
+  reg0 = gload u8 [greg1, 0]
+  reg1 = gload u8 [greg2, 2]
+  reg2 = add reg0 reg1
+  ret reg2
+}
+func bump (reg0: u64): u64 {
+  greg0 = @global symbol counter
+block0:
+  reg1 = gload u64 [greg0, 0]
+  reg2 = add reg1 reg0
+  gstore reg2 [greg0, 0]
+  ret reg2
+}
+func stack_slots (reg0: i16, reg1: f64): f64 {
+block0:
+  reg2 = stackalloc i16, size 32, align 8
+  reg3 = stackalloc f64, size 8, align 8
+  store reg0 [reg2, 0]
+  store reg0 [reg2, 30]
+  store reg1 [reg3, 0]
+  reg4 = load i16 [reg2, 0]
+  reg5 = load i16 [reg2, 30]
+  reg6 = add reg4 reg5
+  reg7 = to.f64 reg6
+  reg8 = load f64 [reg3, 0]
+  reg9 = fadd reg7 reg8
+  ret reg9
+}
+func byte_view (reg0: u32): u8 {
+block0:
+  reg1 = stackalloc u32, size 4, align 4
+  store reg0 [reg1, 0]
+  reg2 = load u8 [reg1, 1]
+  reg3 = load u16 [reg1, 2]
+  reg4 = to.u8 reg3
+  reg5 = add reg2 reg4
+  ret reg5
+}
+",
+    )
+    .unwrap();
+    use RuntimeValue::*;
+    let calls = vec![
+        ("read_table", vec![]),
+        ("bump", vec![U64(10)]),
+        ("bump", vec![U64(u64::MAX)]),
+        ("stack_slots", vec![I16(-300), F64(0.5)]),
+        ("byte_view", vec![U32(0xdead_beef)]),
+    ];
+    assert_native_match_interpreter("memory_and_global_data", &module, &calls);
+}
+
+#[test]
+fn calls_with_many_arguments() {
+    let module = parse(
+        "func mix (reg0: u8, reg1: f64, reg2: i16, reg3: f32, reg4: u64, reg5: i32, reg6: u16, reg7: i64, reg8: u32, reg9: f64): f64 {
+block0:
+  reg10 = to.f64 reg0
+  reg11 = to.f64 reg2
+  reg12 = to.f64 reg3
+  reg13 = to.f64 reg4
+  reg14 = to.f64 reg5
+  reg15 = to.f64 reg6
+  reg16 = to.f64 reg7
+  reg17 = to.f64 reg8
+  reg18 = fadd reg10 reg1
+  reg19 = fmul reg18 reg11
+  reg20 = fadd reg19 reg12
+  reg21 = fsub reg20 reg13
+  reg22 = fadd reg21 reg14
+  reg23 = fmul reg22 reg15
+  reg24 = fadd reg23 reg16
+  reg25 = fsub reg24 reg17
+  reg26 = fdivide reg25 reg9
+  ret reg26
+}
+func floats (reg0: f32, reg1: f32, reg2: f32, reg3: f32, reg4: f32, reg5: f32, reg6: f32, reg7: f32, reg8: f32, reg9: f32): f32 {
+block0:
+  reg10 = fsub reg0 reg1
+  reg11 = fsub reg10 reg2
+  reg12 = fsub reg11 reg3
+  reg13 = fsub reg12 reg4
+  reg14 = fsub reg13 reg5
+  reg15 = fsub reg14 reg6
+  reg16 = fsub reg15 reg7
+  reg17 = fsub reg16 reg8
+  reg18 = fmul reg17 reg9
+  ret reg18
+}
+func ints (reg0: i64, reg1: i64, reg2: i64, reg3: i64, reg4: i64, reg5: i64, reg6: i64, reg7: i64, reg8: i64): i64 {
+block0:
+  reg9 = sub reg0 reg1
+  reg10 = sub reg9 reg2
+  reg11 = sub reg10 reg3
+  reg12 = sub reg11 reg4
+  reg13 = sub reg12 reg5
+  reg14 = sub reg13 reg6
+  reg15 = sub reg14 reg7
+  reg16 = mul reg15 reg8
+  ret reg16
+}
+func caller (reg0: i64, reg1: f32): f64 {
+block0:
+  reg2 = uconst u8 3
+  reg3 = fconst f64 0x4004000000000000
+  reg4 = iconst i16 -4
+  reg5 = iconst i32 -123456
+  reg6 = uconst u16 65535
+  reg7 = uconst u32 4000000000
+  reg8 = to.u64 reg0
+  reg9 = call func mix(reg2, reg3, reg4, reg1, reg8, reg5, reg6, reg0, reg7, reg3)
+  reg10 = call func floats(reg1, reg1, reg1, reg1, reg1, reg1, reg1, reg1, reg1, reg1)
+  reg11 = call func ints(reg0, reg0, reg0, reg0, reg0, reg0, reg0, reg0, reg0)
+  reg12 = to.f64 reg10
+  reg13 = to.f64 reg11
+  reg14 = fadd reg9 reg12
+  reg15 = fadd reg14 reg13
+  ret reg15
+}
+",
+    )
+    .unwrap();
+    use RuntimeValue::*;
+    let calls = vec![
+        (
+            "mix",
+            vec![
+                U8(1),
+                F64(2.5),
+                I16(-3),
+                F32(4.5),
+                U64(5),
+                I32(-6),
+                U16(7),
+                I64(-8),
+                U32(9),
+                F64(-0.5),
+            ],
+        ),
+        ("floats", (1..=10).map(|num| F32(num as f32 * 1.25)).collect()),
+        ("ints", (1..=9).map(|num| I64(num * 1000)).collect()),
+        ("caller", vec![I64(77), F32(-1.75)]),
+        ("caller", vec![I64(-5), F32(1e10)]),
+    ];
+    assert_native_match_interpreter("calls_with_many_arguments", &module, &calls);
+}
+
+/// Build `fib(n) = n < 2 ? n : fib(n - 1) + fib(n - 2)` and a function which calls it,
+/// recursive call can not be parsed from text.
+fn build_recursive_module() -> Module {
+    let mut module = Module::new();
+    let fib = module.declar_function("fib");
+    let func = module.get_mut_function(fib).unwrap();
+    let reg0 = func.def_func_param(ValueType::I32);
+    func.set_return_type(ValueType::I32);
+    let fib_ref = module.declar_function_in_function(fib, fib);
+    let func = module.get_mut_function(fib).unwrap();
+    let [block0, block1, block2] = [func.create_block(), func.create_block(), func.create_block()];
+    let mut builder = FunctionBuilder::new(func);
+    builder.switch_to_block(block0);
+    let two = builder.iconst_inst(vec![2], ValueType::I32);
+    let is_base = builder.icmp_inst(CmpFlag::Lt, [reg0, two]);
+    builder.brif_inst(is_base, block1, block2);
+    builder.switch_to_block(block1);
+    builder.ret_inst(Some(reg0));
+    builder.switch_to_block(block2);
+    let minus_one = builder.sub_imm_inst(reg0, Immediate::I32(1));
+    let minus_two = builder.sub_imm_inst(reg0, Immediate::I32(2));
+    let left = builder.call_inst(vec![minus_one], fib_ref).unwrap();
+    let right = builder.call_inst(vec![minus_two], fib_ref).unwrap();
+    let sum = builder.add_inst([left, right]);
+    builder.ret_inst(Some(sum));
+
+    let fib_pair = module.declar_function("fib_pair");
+    let func = module.get_mut_function(fib_pair).unwrap();
+    let reg0 = func.def_func_param(ValueType::I32);
+    func.set_return_type(ValueType::I64);
+    let fib_ref = module.declar_function_in_function(fib, fib_pair);
+    let func = module.get_mut_function(fib_pair).unwrap();
+    let block0 = func.create_block();
+    let mut builder = FunctionBuilder::new(func);
+    builder.switch_to_block(block0);
+    builder.comment_inst(" fib(n) * 2^32 + fib(n + 1)".to_string());
+    let next = builder.add_imm_inst(reg0, Immediate::I32(1));
+    let high = builder.call_inst(vec![reg0], fib_ref).unwrap();
+    let low = builder.call_inst(vec![next], fib_ref).unwrap();
+    let high = builder.to_i64_inst(high);
+    let low = builder.to_i64_inst(low);
+    let shift = builder.iconst_inst(vec![32], ValueType::I64);
+    let high = builder.shl_inst([high, shift]);
+    let result = builder.bor_inst([high, low]);
+    builder.ret_inst(Some(result));
+    module
+}
+
+#[test]
+fn recursive_calls() {
+    let module = build_recursive_module();
+    use RuntimeValue::*;
+    let calls = vec![
+        ("fib", vec![I32(0)]),
+        ("fib", vec![I32(20)]),
+        ("fib_pair", vec![I32(15)]),
+    ];
+    assert_native_match_interpreter("recursive_calls", &module, &calls);
+}
+
+const CHAIN_SOURCE: &str = "func chain (reg0: i64): i64 {
+block0:
+  reg1 = addi reg0 1
+  reg2 = addi reg1 2
+  reg3 = addi reg2 3
+  reg4 = addi reg3 4
+  reg5 = addi reg4 5
+  reg6 = addi reg5 6
+  reg7 = addi reg6 7
+  reg8 = addi reg7 8
+  ret reg8
+}
+";
+
+#[test]
+fn share_registers_between_values() {
+    let module = parse(CHAIN_SOURCE).unwrap();
+    let func_id = module.get_module_id_by_symbol("chain").unwrap().to_func_id();
+    let frame = allocate_frame(module.get_function(func_id).unwrap()).unwrap();
+    // every value dies at definition of next one, so all values share one register.
+    assert_eq!(frame.saved_regs, vec![Gpr::Rbx]);
+    assert!(frame
+        .locations
+        .values()
+        .all(|location| *location == Location::Reg(Gpr::Rbx)));
+    assert_eq!(frame.locations.len(), 9);
+    assert_native_match_interpreter(
+        "share_registers_between_values",
+        &module,
+        &[("chain", vec![RuntimeValue::I64(-36)])],
+    );
+}
+
+#[test]
+fn emit_module_layout() {
+    let module = parse(
+        "greeting = @data { size 8, align 4, init [0x01 0x02] }
+func answer (): u8 {
+block0:
+  reg0 = uconst u8 42
+  ret reg0
+}
+",
+    )
+    .unwrap();
+    let asm = emit_module(&module).unwrap();
+    assert!(asm.starts_with("    .text\n"));
+    assert!(asm.contains("    .globl answer\n    .type answer, @function\nanswer:\n"));
+    assert!(asm.contains(
+        "    .section .rodata\n    .globl greeting\n    .type greeting, @object\n    .balign 4\ngreeting:\n    .byte 1, 2\n    .zero 6\n    .size greeting, 8\n"
+    ));
+    assert!(asm.ends_with("    .section .note.GNU-stack,\"\",@progbits\n"));
+}
+
+#[test]
+fn reject_unsupported_stack_alignment() {
+    let module = parse(
+        "func aligned (): u8 {
+block0:
+  reg0 = stackalloc u8, size 64, align 32
+  reg1 = load u8 [reg0, 0]
+  ret reg1
+}
+",
+    )
+    .unwrap();
+    let error = emit_module(&module).unwrap_err();
+    assert_eq!(error.func, "aligned");
+    assert_eq!(error.kind, CodegenErrorKind::UnsupportedAlignment(32));
+    assert_eq!(
+        error.to_string(),
+        "[Error]: func aligned: stack alignment 32 is not supported"
+    );
+}
+
+#[test]
+fn reject_too_large_stack_frame() {
+    let module = parse(
+        "func huge (): u8 {
+block0:
+  reg0 = stackalloc u8, size 0xFFFFFFF0, align 1
+  reg1 = load u8 [reg0, 0]
+  ret reg1
+}
+",
+    )
+    .unwrap();
+    let error = emit_module(&module).unwrap_err();
+    assert_eq!(error.kind, CodegenErrorKind::FrameTooLarge);
+    assert_eq!(error.to_string(), "[Error]: func huge: stack frame is too large");
+}